@@ -11,6 +11,7 @@ use opentelemetry_sdk::trace::{BatchConfigBuilder, Config, Tracer};
 use opentelemetry_sdk::{runtime, Resource};
 use std::fmt;
 use std::fmt::Display;
+use std::sync::OnceLock;
 use std::time::{Duration, SystemTime};
 use time::{format_description, OffsetDateTime};
 use tracing::field::{Field, Visit};
@@ -19,9 +20,26 @@ use tracing_core::LevelFilter;
 use tracing_opentelemetry::OpenTelemetryLayer;
 use tracing_subscriber::layer::SubscriberExt as _;
 use tracing_subscriber::util::SubscriberInitExt as _;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{reload, EnvFilter, Registry};
 use url::Url;
 
+/// Handle to the filter of the global tracing subscriber, set once the subscriber is installed.
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+fn parse_log_filter(filter: &str) -> Result<EnvFilter, tracing_subscriber::filter::ParseError> {
+    EnvFilter::builder().with_default_directive(LevelFilter::INFO.into()).parse(filter)
+}
+
+/// Replaces the log filter of the node, using the `RUST_LOG` syntax such as `info,mc_sync::fetch=debug`. Modules which
+/// are not matched by any directive log at the info level. Returns the previous filter.
+pub fn set_log_filter(filter: &str) -> anyhow::Result<String> {
+    let filter = parse_log_filter(filter).map_err(|err| anyhow::anyhow!("Invalid log filter: {err}"))?;
+    let handle = LOG_FILTER.get().ok_or_else(|| anyhow::anyhow!("The logger is not initialized"))?;
+    let mut previous = None;
+    handle.modify(|current| previous = Some(std::mem::replace(current, filter).to_string()))?;
+    Ok(previous.unwrap_or_default())
+}
+
 pub struct Analytics {
     meter_provider: Option<SdkMeterProvider>,
     service_name: String,
@@ -37,9 +55,13 @@ impl Analytics {
         let local_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
         let custom_formatter = CustomFormatter { local_offset };
 
+        // The filter can be replaced at runtime through `set_log_filter`.
+        let (filter, filter_handle) =
+            reload::Layer::new(EnvFilter::builder().with_default_directive(LevelFilter::INFO.into()).from_env()?);
         let tracing_subscriber = tracing_subscriber::registry()
-            .with(tracing_subscriber::fmt::layer().event_format(custom_formatter).with_writer(std::io::stderr))
-            .with(EnvFilter::builder().with_default_directive(LevelFilter::INFO.into()).from_env()?);
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer().event_format(custom_formatter).with_writer(std::io::stderr));
+        let _ = LOG_FILTER.set(filter_handle);
 
         if self.collection_endpoint.is_none() {
             tracing_subscriber.init();
@@ -0,0 +1,166 @@
+//! Cache of the compiled Sierra classes.
+//!
+//! Compiling a Sierra class to CASM is the most expensive part of the pre-validation of a block. The same class is
+//! converted again whenever it is declared again: by the pending block on every poll until it is closed, by the blocks
+//! imported again after a pipeline restart or a reorg, and by the chains where a class is declared in several blocks.
+//! The [`ClassCache`] keeps the latest converted classes so that these declarations skip the compilation.
+
+use mc_analytics::register_counter_metric_instrument;
+use mp_class::SierraConvertedClass;
+use opentelemetry::metrics::Counter;
+use opentelemetry::{global, KeyValue};
+use starknet_types_core::felt::Felt;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Default number of classes kept by the [`ClassCache`].
+pub const DEFAULT_CLASS_CACHE_SIZE: usize = 128;
+
+/// Bounded cache of the converted Sierra classes, by class hash. The least recently used class is evicted first.
+pub struct ClassCache {
+    capacity: usize,
+    lru: Mutex<Lru>,
+    hit_counter: Counter<u64>,
+    miss_counter: Counter<u64>,
+}
+
+#[derive(Default)]
+struct Lru {
+    /// Classes with the time of their last use.
+    classes: HashMap<Felt, (SierraConvertedClass, u64)>,
+    /// Class hashes by time of last use.
+    by_last_use: BTreeMap<u64, Felt>,
+    clock: u64,
+}
+
+impl ClassCache {
+    /// A cache of `capacity` classes. Nothing is cached when `capacity` is 0.
+    pub fn new(capacity: usize) -> Self {
+        let common_scope_attributes = vec![KeyValue::new("crate", "block_import")];
+        let block_import_meter = global::meter_with_version(
+            "crates.block_import.opentelemetry",
+            Some("0.17"),
+            Some("https://opentelemetry.io/schemas/1.2.0"),
+            Some(common_scope_attributes.clone()),
+        );
+        let hit_counter = register_counter_metric_instrument(
+            &block_import_meter,
+            "class_cache_hit_count".to_string(),
+            "A counter of the declared Sierra classes whose compiled class was found in the class cache".to_string(),
+            "class".to_string(),
+        );
+        let miss_counter = register_counter_metric_instrument(
+            &block_import_meter,
+            "class_cache_miss_count".to_string(),
+            "A counter of the declared Sierra classes which had to be compiled".to_string(),
+            "class".to_string(),
+        );
+
+        Self { capacity, lru: Default::default(), hit_counter, miss_counter }
+    }
+
+    /// The class `class_hash`, if it is cached with the compiled class hash `compiled_class_hash`.
+    pub(crate) fn get(&self, class_hash: &Felt, compiled_class_hash: &Felt) -> Option<SierraConvertedClass> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut lru = self.lru.lock().expect("Poisoned lock");
+        let lru = &mut *lru;
+        let Some((class, last_use)) =
+            lru.classes.get_mut(class_hash).filter(|(class, _)| class.info.compiled_class_hash == *compiled_class_hash)
+        else {
+            self.miss_counter.add(1, &[]);
+            return None;
+        };
+        lru.by_last_use.remove(last_use);
+        lru.clock += 1;
+        *last_use = lru.clock;
+        lru.by_last_use.insert(lru.clock, *class_hash);
+        self.hit_counter.add(1, &[]);
+        Some(class.clone())
+    }
+
+    /// Caches a class, evicting the least recently used class when the cache is full.
+    pub(crate) fn insert(&self, class: SierraConvertedClass) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lru = self.lru.lock().expect("Poisoned lock");
+        let lru = &mut *lru;
+        let class_hash = class.class_hash;
+        if let Some((_, last_use)) = lru.classes.remove(&class_hash) {
+            lru.by_last_use.remove(&last_use);
+        } else if lru.classes.len() >= self.capacity {
+            if let Some((_, evicted)) = lru.by_last_use.pop_first() {
+                lru.classes.remove(&evicted);
+            }
+        }
+        lru.clock += 1;
+        lru.by_last_use.insert(lru.clock, class_hash);
+        lru.classes.insert(class_hash, (class, lru.clock));
+    }
+
+    pub fn len(&self) -> usize {
+        self.lru.lock().expect("Poisoned lock").classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_class::{CompiledSierra, EntryPointsByType, FlattenedSierraClass, SierraClassInfo};
+    use std::sync::Arc;
+
+    fn class(class_hash: u64, compiled_class_hash: u64) -> SierraConvertedClass {
+        SierraConvertedClass {
+            class_hash: Felt::from(class_hash),
+            info: SierraClassInfo {
+                contract_class: Arc::new(FlattenedSierraClass {
+                    sierra_program: vec![],
+                    contract_class_version: "0.1.0".into(),
+                    entry_points_by_type: EntryPointsByType {
+                        constructor: vec![],
+                        external: vec![],
+                        l1_handler: vec![],
+                    },
+                    abi: String::new(),
+                }),
+                compiled_class_hash: Felt::from(compiled_class_hash),
+            },
+            compiled: Arc::new(CompiledSierra(String::new())),
+        }
+    }
+
+    #[test]
+    fn test_class_cache() {
+        let cache = ClassCache::new(2);
+        assert!(cache.get(&Felt::ONE, &Felt::from(10)).is_none());
+
+        cache.insert(class(1, 10));
+        cache.insert(class(2, 20));
+        assert_eq!(cache.get(&Felt::ONE, &Felt::from(10)), Some(class(1, 10)));
+        // The class was declared with another compiled class hash: it has to be compiled.
+        assert!(cache.get(&Felt::ONE, &Felt::from(11)).is_none());
+
+        // Class 2 is the least recently used one.
+        cache.insert(class(3, 30));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&Felt::TWO, &Felt::from(20)).is_none());
+        assert!(cache.get(&Felt::ONE, &Felt::from(10)).is_some());
+        assert!(cache.get(&Felt::THREE, &Felt::from(30)).is_some());
+
+        // Inserting a cached class again does not evict another one.
+        cache.insert(class(3, 30));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&Felt::ONE, &Felt::from(10)).is_some());
+
+        let disabled = ClassCache::new(0);
+        disabled.insert(class(1, 10));
+        assert!(disabled.is_empty());
+        assert!(disabled.get(&Felt::ONE, &Felt::from(10)).is_none());
+    }
+}
@@ -0,0 +1,206 @@
+//! Block commitment schemes, by protocol version.
+//!
+//! The hash functions used by the transaction, event and receipt commitments changed over the protocol versions. The
+//! scheme of every version is registered in [`COMMITMENT_SCHEMES`]: supporting the commitments of a new protocol
+//! version only requires adding an entry there, and every component computing commitments goes through
+//! [`CommitmentScheme::for_version`].
+
+use crate::ValidatedCommitments;
+use bitvec::vec::BitVec;
+use mp_chain_config::StarknetVersion;
+use mp_receipt::{Event, TransactionReceipt};
+use mp_state_update::StateDiff;
+use mp_transactions::Transaction;
+use rayon::prelude::*;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+
+/// Hash function of a commitment merkle trie.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrieHash {
+    Pedersen,
+    Poseidon,
+}
+
+impl TrieHash {
+    pub fn merkle_root(&self, values: &[Felt]) -> Felt {
+        match self {
+            Self::Pedersen => compute_merkle_root::<Pedersen>(values),
+            Self::Poseidon => compute_merkle_root::<Poseidon>(values),
+        }
+    }
+}
+
+/// Hash of the events, which are the leaves of the event commitment trie.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventHash {
+    /// Pedersen hash of the event.
+    Pedersen,
+    /// Poseidon hash of the event and of the hash of the transaction which emitted it.
+    PoseidonWithTransactionHash,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CommitmentScheme {
+    pub transaction_trie: TrieHash,
+    pub event_trie: TrieHash,
+    pub event_hash: EventHash,
+    pub receipt_trie: TrieHash,
+    /// Whether the block header has a receipt commitment and a state diff commitment. They are only part of the block
+    /// hash from 0.13.2.
+    pub header_commitments: bool,
+}
+
+/// The commitment schemes, with the first protocol version they apply to. Sorted by version.
+pub const COMMITMENT_SCHEMES: &[(StarknetVersion, CommitmentScheme)] = &[
+    (
+        StarknetVersion::V_0_0_0,
+        CommitmentScheme {
+            transaction_trie: TrieHash::Pedersen,
+            event_trie: TrieHash::Pedersen,
+            event_hash: EventHash::Pedersen,
+            // Receipt commitments were only introduced in 0.13.2.
+            receipt_trie: TrieHash::Poseidon,
+            header_commitments: false,
+        },
+    ),
+    (
+        StarknetVersion::V0_13_2,
+        CommitmentScheme {
+            transaction_trie: TrieHash::Poseidon,
+            event_trie: TrieHash::Poseidon,
+            event_hash: EventHash::PoseidonWithTransactionHash,
+            receipt_trie: TrieHash::Poseidon,
+            header_commitments: true,
+        },
+    ),
+];
+
+impl CommitmentScheme {
+    /// The commitment scheme of a protocol version.
+    pub fn for_version(version: StarknetVersion) -> Self {
+        COMMITMENT_SCHEMES
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= version)
+            .map(|(_, scheme)| *scheme)
+            .unwrap_or(COMMITMENT_SCHEMES[0].1)
+    }
+
+    /// Commitment of the transactions, from their hashes with signature.
+    pub fn transaction_commitment(&self, tx_hashes_with_signature: &[Felt]) -> Felt {
+        self.transaction_trie.merkle_root(tx_hashes_with_signature)
+    }
+
+    /// Commitment of the events, with the hash of the transaction which emitted them.
+    pub fn event_commitment(&self, events: &[(Felt, Event)]) -> Felt {
+        if events.is_empty() {
+            return Felt::ZERO;
+        }
+        let hashes: Vec<_> = match self.event_hash {
+            EventHash::Pedersen => events.par_iter().map(|(_, event)| event.compute_hash_pedersen()).collect(),
+            EventHash::PoseidonWithTransactionHash => {
+                events.par_iter().map(|(tx_hash, event)| event.compute_hash_poseidon(tx_hash)).collect()
+            }
+        };
+        self.event_trie.merkle_root(&hashes)
+    }
+
+    pub fn receipt_commitment(&self, receipts: &[TransactionReceipt]) -> Felt {
+        let hashes = receipts.par_iter().map(TransactionReceipt::compute_hash).collect::<Vec<_>>();
+        self.receipt_trie.merkle_root(&hashes)
+    }
+
+    pub fn state_diff_commitment(&self, state_diff: &StateDiff) -> Felt {
+        state_diff.compute_hash()
+    }
+
+    /// Commitments of a block of the protocol version `version`, whose transaction hashes are the ones of its
+    /// receipts. This is how the block production commits to the blocks it closes.
+    pub fn block_commitments(
+        version: StarknetVersion,
+        transactions: &[Transaction],
+        receipts: &[TransactionReceipt],
+        state_diff: &StateDiff,
+    ) -> ValidatedCommitments {
+        let scheme = Self::for_version(version);
+        let tx_hashes_with_signature: Vec<_> = transactions
+            .par_iter()
+            .zip(receipts)
+            .map(|(tx, receipt)| tx.compute_hash_with_signature(receipt.transaction_hash(), version))
+            .collect();
+        let events_with_tx_hash: Vec<_> = receipts
+            .iter()
+            .flat_map(|receipt| receipt.events().iter().map(move |event| (receipt.transaction_hash(), event.clone())))
+            .collect();
+
+        ValidatedCommitments {
+            transaction_count: transactions.len() as _,
+            transaction_commitment: scheme.transaction_commitment(&tx_hashes_with_signature),
+            event_count: events_with_tx_hash.len() as _,
+            event_commitment: scheme.event_commitment(&events_with_tx_hash),
+            state_diff_length: state_diff.len() as _,
+            state_diff_commitment: scheme.state_diff_commitment(state_diff),
+            receipt_commitment: scheme.receipt_commitment(receipts),
+        }
+    }
+}
+
+/// Compute the root hash of a list of values.
+/// This implements transactions, events, receipts and state-diff [commitments specs] using memory
+/// backed bonsai storage.
+///
+/// [commitments specs]: https://docs.starknet.io/architecture-and-concepts/network-architecture/block-structure/#transactions_events_receipts_commitments
+// The `HashMapDb` can't fail, so we can safely unwrap the results.
+//
+// perf: Note that committing changes still has the greatest performance hit
+// as this is where the root hash is calculated. Due to the Merkle structure
+// of Bonsai Tries, this results in a trie size that grows very rapidly with
+// each new insertion. It seems that the only vector of optimization here
+// would be to parallelize the tree traversal on insertion and optimize hash computation.
+// It seems lambdaclass' crypto lib does not do simd hashing, we may want to look into that.
+fn compute_merkle_root<H: StarkHash + Send + Sync>(values: &[Felt]) -> Felt {
+    //TODO: replace the identifier by an empty slice when bonsai supports it
+    const IDENTIFIER: &[u8] = b"0xinmemory";
+    let config = bonsai_trie::BonsaiStorageConfig::default();
+    let bonsai_db = bonsai_trie::databases::HashMapDb::<bonsai_trie::id::BasicId>::default();
+    let mut bonsai_storage =
+        bonsai_trie::BonsaiStorage::<_, _, H>::new(bonsai_db, config, /* max tree height */ 64);
+
+    values.iter().enumerate().for_each(|(id, value)| {
+        let key = BitVec::from_vec(id.to_be_bytes().to_vec());
+        bonsai_storage.insert(IDENTIFIER, key.as_bitslice(), value).expect("Failed to insert into bonsai storage");
+    });
+
+    let id = bonsai_trie::id::BasicIdBuilder::new().new_id();
+
+    bonsai_storage.commit(id).expect("Failed to commit to bonsai storage");
+    bonsai_storage.root_hash(IDENTIFIER).expect("Failed to get root hash")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_root() {
+        let values = vec![Felt::ONE, Felt::TWO, Felt::THREE];
+        let root = compute_merkle_root::<Poseidon>(&values);
+
+        assert_eq!(root, Felt::from_hex_unchecked("0x3b5cc7f1292eb3847c3f902d048a7e5dc7702d1c191ccd17c2d33f797e6fc32"));
+    }
+
+    #[test]
+    fn test_commitment_scheme_for_version() {
+        assert_eq!(CommitmentScheme::for_version(StarknetVersion::V0_7_0).transaction_trie, TrieHash::Pedersen);
+        assert_eq!(CommitmentScheme::for_version(StarknetVersion::V0_13_1_1).event_hash, EventHash::Pedersen);
+        assert_eq!(CommitmentScheme::for_version(StarknetVersion::V0_13_2).transaction_trie, TrieHash::Poseidon);
+        assert_eq!(
+            CommitmentScheme::for_version(StarknetVersion::LATEST).event_hash,
+            EventHash::PoseidonWithTransactionHash
+        );
+        assert!(!CommitmentScheme::for_version(StarknetVersion::V0_13_1_1).header_commitments);
+        assert!(CommitmentScheme::for_version(StarknetVersion::V0_13_2).header_commitments);
+        assert!(COMMITMENT_SCHEMES.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
@@ -37,24 +37,33 @@
 //! be useful for tendermint validator nodes in the future, and it should also be useful to test-execute a whole blockchain
 //! to check for errors.
 //! A signature verification mode should be added to allow the skipping of block validation entirely if the block is signed.
+//! For now, block signatures are only checked against the sequencer public keys of the chain config, on top of the
+//! usual validation.
 
 use anyhow::Context;
+use class_cache::{ClassCache, DEFAULT_CLASS_CACHE_SIZE};
 use mc_db::{MadaraBackend, MadaraStorageError};
 use metrics::BlockMetrics;
 use mp_class::{class_hash::ComputeClassHashError, compile::ClassCompilationError};
 use starknet_types_core::felt::Felt;
+use state_diff_cache::{StateDiffCommitmentCache, DEFAULT_STATE_DIFF_CACHE_SIZE};
 use std::{borrow::Cow, sync::Arc};
 
+pub mod class_cache;
+pub mod commitments;
 mod metrics;
 mod pre_validate;
 mod rayon;
+pub mod state_diff_cache;
 pub mod tests;
 mod types;
 mod verify_apply;
+mod verify_chain;
 pub use pre_validate::*;
 pub use rayon::*;
 pub use types::*;
 pub use verify_apply::*;
+pub use verify_chain::*;
 
 #[derive(Debug, thiserror::Error)]
 pub enum BlockImportError {
@@ -92,11 +101,33 @@ pub enum BlockImportError {
 
     #[error("Block hash mismatch: expected {expected:#x}, got {got:#x}")]
     BlockHash { got: Felt, expected: Felt },
+    #[error("Invalid signature for block #{block_n} with hash {block_hash:#x}: not signed by a known sequencer key")]
+    InvalidSignature { block_n: u64, block_hash: Felt },
+    #[error("Missing signature for block #{block_n}: the chain has sequencer public keys")]
+    MissingSignature { block_n: u64 },
+
+    #[error("Missing definition for declared class hash {class_hash:#x}")]
+    MissingClassDefinition { class_hash: Felt },
+    #[error("Class hash {class_hash:#x} declared by transaction #{index} is missing from the state diff")]
+    DeclaredClassNotInStateDiff { index: usize, class_hash: Felt },
+    #[error(
+        "Compiled class hash mismatch for class hash {class_hash:#x} declared by transaction #{index}: state diff has \
+         {expected:#x}, transaction has {got:#x}"
+    )]
+    DeclareCompiledClassHash { index: usize, class_hash: Felt, got: Felt, expected: Felt },
+    #[error(
+        "The state diff declares the classes [{state_diff}] but the transactions of the block declare [{transactions}]"
+    )]
+    DeclaredClassesOrder { state_diff: String, transactions: String },
 
     #[error("Block order mismatch: database expects to import block #{expected}, trying to import #{got}. To import a block out of order, use the `ignore_block_order` flag.")]
     LatestBlockN { expected: u64, got: u64 },
     #[error("Parent hash mismatch: expected {expected:#x}, got {got:#x}")]
     ParentHash { got: Felt, expected: Felt },
+    #[error(
+        "The pending block on top of {parent_block_hash:#x} is stale, the next block was imported in the meantime"
+    )]
+    StalePendingBlock { parent_block_hash: Felt },
     #[error("Global state root mismatch: expected {expected:#x}, got {got:#x}")]
     GlobalStateRoot { got: Felt, expected: Felt },
 
@@ -119,6 +150,9 @@ pub struct BlockImporter {
     backend: Arc<MadaraBackend>,
     verify_apply: VerifyApply,
     metrics: BlockMetrics,
+    class_cache: Arc<ClassCache>,
+    state_diff_cache: Arc<StateDiffCommitmentCache>,
+    compilation_pool: Arc<ClassCompilationPool>,
 }
 
 impl BlockImporter {
@@ -140,9 +174,30 @@ impl BlockImporter {
             pool,
             metrics: BlockMetrics::register(starting_block).context("Registering metrics for block import")?,
             backend,
+            class_cache: Arc::new(ClassCache::new(DEFAULT_CLASS_CACHE_SIZE)),
+            state_diff_cache: Arc::new(StateDiffCommitmentCache::new(DEFAULT_STATE_DIFF_CACHE_SIZE)),
+            compilation_pool: Arc::new(
+                ClassCompilationPool::new(ClassCompilationPool::default_n_threads())
+                    .context("Creating the class compilation pool")?,
+            ),
         })
     }
 
+    /// Sets the number of compiled classes kept to skip the compilation of the classes declared again, see
+    /// [`ClassCache`]. Nothing is cached when `size` is 0.
+    pub fn with_class_cache_size(mut self, size: usize) -> Self {
+        self.class_cache = Arc::new(ClassCache::new(size));
+        self
+    }
+
+    /// Sets the number of threads compiling the declared classes, see [`ClassCompilationPool`]. By default, this is
+    /// half of the cores.
+    pub fn with_class_compilation_threads(mut self, n_threads: usize) -> anyhow::Result<Self> {
+        self.compilation_pool =
+            Arc::new(ClassCompilationPool::new(n_threads).context("Creating the class compilation pool")?);
+        Ok(self)
+    }
+
     /// Perform [`BlockImporter::pre_validate`] followed by [`BlockImporter::verify_apply`] to import a block.
     #[tracing::instrument(skip(self, block, validation), fields(module = "BlockImporter"))]
     pub async fn add_block(
@@ -160,7 +215,9 @@ impl BlockImporter {
         block: UnverifiedFullBlock,
         validation: BlockValidationContext,
     ) -> Result<PreValidatedBlock, BlockImportError> {
-        pre_validate(&self.pool, block, validation).await
+        let (class_cache, compilation_pool) = (Arc::clone(&self.class_cache), Arc::clone(&self.compilation_pool));
+        let state_diff_cache = Arc::clone(&self.state_diff_cache);
+        pre_validate(&self.pool, block, validation, class_cache, state_diff_cache, compilation_pool).await
     }
 
     #[tracing::instrument(skip(self, block, validation), fields(module = "BlockImporter"))]
@@ -180,7 +237,8 @@ impl BlockImporter {
         block: UnverifiedPendingFullBlock,
         validation: BlockValidationContext,
     ) -> Result<PreValidatedPendingBlock, BlockImportError> {
-        pre_validate_pending(&self.pool, block, validation).await
+        let (class_cache, compilation_pool) = (Arc::clone(&self.class_cache), Arc::clone(&self.compilation_pool));
+        pre_validate_pending(&self.pool, block, validation, class_cache, compilation_pool).await
     }
 
     #[tracing::instrument(skip(self, block, validation), fields(module = "BlockImporter"))]
@@ -191,4 +249,81 @@ impl BlockImporter {
     ) -> Result<PendingBlockImportResult, BlockImportError> {
         self.verify_apply.verify_apply_pending(block, validation).await
     }
+
+    /// Imports a state snapshot into an empty database. The header of the snapshot must hash to `block_hash`, and the
+    /// state must match the global state root of the header.
+    #[tracing::instrument(skip(self, snapshot, validation), fields(module = "BlockImporter"))]
+    pub async fn import_state_snapshot(
+        &self,
+        snapshot: UnverifiedStateSnapshot,
+        block_hash: Felt,
+        validation: BlockValidationContext,
+    ) -> Result<BlockImportResult, BlockImportError> {
+        let snapshot =
+            pre_validate_state_snapshot(&self.pool, snapshot, validation.clone(), Arc::clone(&self.compilation_pool))
+                .await?;
+        let result = self.verify_apply.verify_apply_state_snapshot(snapshot, block_hash, validation).await?;
+        self.metrics.update(&result.header, &self.backend);
+        Ok(result)
+    }
+
+    /// Stores a block older than the oldest block in the database, see [`verify_apply_backfill_inner`]. The block must
+    /// have been through [`BlockImporter::pre_validate`].
+    #[tracing::instrument(skip(self, block, validation), fields(module = "BlockImporter"))]
+    pub async fn verify_apply_backfill(
+        &self,
+        block: PreValidatedBlock,
+        validation: BlockValidationContext,
+    ) -> Result<BlockImportResult, BlockImportError> {
+        self.verify_apply.verify_apply_backfill(block, validation).await
+    }
+
+    /// Applies at most `max_blocks` blocks imported with `trust_global_tries` to the global tries, so that the node
+    /// can serve storage proofs for them. Returns the latest block applied to the tries.
+    #[tracing::instrument(skip(self), fields(module = "BlockImporter"))]
+    pub async fn catch_up_global_tries(&self, max_blocks: usize) -> Result<Option<u64>, BlockImportError> {
+        self.verify_apply.catch_up_global_tries(max_blocks).await
+    }
+
+    /// Compiles and stores classes declared in block `block_n` which are missing from the database, when the node
+    /// stopped after storing the block but before its classes.
+    #[tracing::instrument(skip(self, classes, validation), fields(module = "BlockImporter"))]
+    pub async fn store_missing_classes(
+        &self,
+        block_n: u64,
+        classes: Vec<DeclaredClass>,
+        validation: BlockValidationContext,
+    ) -> Result<(), BlockImportError> {
+        let backend = Arc::clone(&self.backend);
+        let (class_cache, compilation_pool) = (Arc::clone(&self.class_cache), Arc::clone(&self.compilation_pool));
+        self.pool
+            .spawn_rayon_task(move || {
+                let converted =
+                    pre_validate::convert_classes(classes, &validation, Some(&class_cache), &compilation_pool)?;
+                backend
+                    .class_db_store_block(block_n, &converted)
+                    .map_err(verify_apply::make_db_error("storing the missing classes in db"))?;
+                backend
+                    .flush()
+                    .map_err(|err| BlockImportError::Internal(format!("Flushing the database: {err:#}").into()))
+            })
+            .await
+    }
+
+    /// Verifies a block already stored in the database, without writing anything, see [`verify_stored_block`].
+    #[tracing::instrument(skip(self, validation), fields(module = "BlockImporter"))]
+    pub async fn verify_stored_block(
+        &self,
+        block: mp_block::MadaraBlock,
+        validation: BlockValidationContext,
+    ) -> Result<StoredBlockVerification, BlockImportError> {
+        let backend = Arc::clone(&self.backend);
+        self.pool.spawn_rayon_task(move || verify_stored_block(&backend, block, &validation)).await
+    }
+
+    /// Waits until no block is being applied to the database. A block import keeps running to completion even if the
+    /// future importing it is dropped.
+    pub async fn wait_idle(&self) {
+        self.verify_apply.wait_idle().await
+    }
 }
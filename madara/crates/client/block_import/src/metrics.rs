@@ -1,17 +1,34 @@
-use mc_analytics::register_gauge_metric_instrument;
+use mc_analytics::{register_gauge_metric_instrument, register_histogram_metric_instrument};
 use mc_db::MadaraBackend;
 use mp_block::Header;
 use num_traits::FromPrimitive;
-use opentelemetry::metrics::Gauge;
+use opentelemetry::metrics::{Gauge, Histogram};
 use opentelemetry::{
     global::{self, Error},
     KeyValue,
 };
 use std::{
-    sync::Mutex,
+    sync::{LazyLock, Mutex},
     time::{Duration, Instant},
 };
 
+/// Time spent applying the state diff of a block to each global trie, with a `trie` attribute. This is registered once
+/// as the tries are updated outside of the [`crate::BlockImporter`], by [`crate::verify_apply_inner`].
+pub(crate) static GLOBAL_TRIE_APPLY_DURATION: LazyLock<Histogram<f64>> = LazyLock::new(|| {
+    let block_import_meter = global::meter_with_version(
+        "crates.block_import.opentelemetry",
+        Some("0.17"),
+        Some("https://opentelemetry.io/schemas/1.2.0"),
+        Some(vec![KeyValue::new("crate", "block_import")]),
+    );
+    register_histogram_metric_instrument(
+        &block_import_meter,
+        "global_trie_apply_duration_seconds".to_string(),
+        "Histogram of the time spent applying the state diff of a block to each global trie".to_string(),
+        "s".to_string(),
+    )
+});
+
 #[derive(Debug)]
 pub struct BlockMetrics {
     /// Starting block
@@ -1,18 +1,23 @@
+use crate::class_cache::ClassCache;
+use crate::commitments::CommitmentScheme;
+use crate::state_diff_cache::StateDiffCommitmentCache;
 use crate::{
-    BlockImportError, BlockValidationContext, DeclaredClass, PreValidatedBlock, PreValidatedPendingBlock, RayonPool,
-    UnverifiedFullBlock, UnverifiedPendingFullBlock, ValidatedCommitments,
+    BlockImportError, BlockValidationContext, ClassCompilationPool, DeclaredClass, PreValidatedBlock,
+    PreValidatedPendingBlock, PreValidatedStateSnapshot, RayonPool, UnverifiedCommitments, UnverifiedFullBlock,
+    UnverifiedPendingFullBlock, UnverifiedStateSnapshot, ValidatedCommitments,
 };
-use bitvec::vec::BitVec;
+use itertools::Itertools;
 use mp_chain_config::StarknetVersion;
 use mp_class::{ConvertedClass, LegacyClassInfo, LegacyConvertedClass, SierraClassInfo, SierraConvertedClass};
-use mp_convert::ToFelt;
-use mp_receipt::TransactionReceipt;
-use mp_transactions::Transaction;
+use mp_convert::{FeltHexDisplay, ToFelt};
+use mp_receipt::{ExecutionResult, TransactionReceipt};
+use mp_state_update::StateDiff;
+use mp_transactions::{DeclareTransaction, Transaction};
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator};
 use rayon::prelude::*;
 use starknet_api::core::ChainId;
 use starknet_types_core::felt::Felt;
-use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+use std::collections::HashMap;
 use std::mem;
 use std::sync::Arc;
 
@@ -21,9 +26,16 @@ pub async fn pre_validate(
     pool: &RayonPool,
     block: UnverifiedFullBlock,
     validation: BlockValidationContext,
+    class_cache: Arc<ClassCache>,
+    state_diff_cache: Arc<StateDiffCommitmentCache>,
+    compilation_pool: Arc<ClassCompilationPool>,
 ) -> Result<PreValidatedBlock, BlockImportError> {
     tracing::debug!("spawning pre_validate");
-    let res = pool.spawn_rayon_task(move || pre_validate_inner(block, validation)).await;
+    let res = pool
+        .spawn_rayon_task(move || {
+            pre_validate_inner(block, validation, &class_cache, &state_diff_cache, &compilation_pool)
+        })
+        .await;
     tracing::debug!("finished pre_validate");
     res
 }
@@ -33,31 +45,44 @@ pub async fn pre_validate_pending(
     pool: &RayonPool,
     block: UnverifiedPendingFullBlock,
     validation: BlockValidationContext,
+    class_cache: Arc<ClassCache>,
+    compilation_pool: Arc<ClassCompilationPool>,
 ) -> Result<PreValidatedPendingBlock, BlockImportError> {
     tracing::debug!("spawning pre_validate (pending)");
-    let res = pool.spawn_rayon_task(move || pre_validate_pending_inner(block, validation)).await;
+    let res = pool
+        .spawn_rayon_task(move || pre_validate_pending_inner(block, validation, &class_cache, &compilation_pool))
+        .await;
     tracing::debug!("finished pre_validate (pending)");
     res
 }
 
-/// This runs on the [`rayon`] threadpool.
+/// This runs on the [`rayon`] threadpool, and the classes are converted on `compilation_pool`. The Sierra classes found
+/// in `class_cache` are not compiled again, and the state diffs found in `state_diff_cache` are not hashed again.
 pub fn pre_validate_inner(
     mut block: UnverifiedFullBlock,
     validation: BlockValidationContext,
+    class_cache: &ClassCache,
+    state_diff_cache: &StateDiffCommitmentCache,
+    compilation_pool: &ClassCompilationPool,
 ) -> Result<PreValidatedBlock, BlockImportError> {
     let classes = mem::take(&mut block.declared_classes);
+    check_declared_classes(&block.transactions, &block.receipts, &block.state_diff, &validation)?;
 
     // unfortunately this is ugly but rayon::join does not have the fast error short circuiting behavior that
     // collecting into a Result has.
     // little known fact this uses the impl FromIterator for () from std, nice trick
     let (mut commitments, mut converted_classes) = Default::default();
+    // The closures may run on other threads of the pool, which are not in the span of the block.
+    let span = tracing::Span::current();
     [
         Box::new(|| {
-            commitments = block_commitments(&block, &validation)?;
+            let _span = tracing::info_span!(parent: &span, "block_commitments").entered();
+            commitments = block_commitments(&block, &validation, Some(state_diff_cache))?;
             Ok(())
         }) as Box<dyn FnOnce() -> Result<(), BlockImportError> + Send>,
         Box::new(|| {
-            converted_classes = convert_classes(classes, &validation)?;
+            let _span = tracing::info_span!(parent: &span, "convert_classes", n_classes = classes.len()).entered();
+            converted_classes = convert_classes(classes, &validation, Some(class_cache), compilation_pool)?;
             Ok(())
         }),
     ]
@@ -78,6 +103,7 @@ pub fn pre_validate_inner(
         unverified_block_hash: block.commitments.block_hash,
         unverified_block_number: block.unverified_block_number,
         visited_segments: block.visited_segments,
+        consensus_signature: block.consensus_signature,
     })
 }
 
@@ -85,11 +111,13 @@ pub fn pre_validate_inner(
 pub fn pre_validate_pending_inner(
     mut block: UnverifiedPendingFullBlock,
     validation: BlockValidationContext,
+    class_cache: &ClassCache,
+    compilation_pool: &ClassCompilationPool,
 ) -> Result<PreValidatedPendingBlock, BlockImportError> {
     let starknet_version = block.header.protocol_version;
     let classes = mem::take(&mut block.declared_classes);
 
-    let converted_classes = convert_classes(classes, &validation)?;
+    let converted_classes = convert_classes(classes, &validation, Some(class_cache), compilation_pool)?;
     let _tx_hashes = transaction_hashes(&block.receipts, &block.transactions, starknet_version, &validation)?;
 
     Ok(PreValidatedPendingBlock {
@@ -102,10 +130,71 @@ pub fn pre_validate_pending_inner(
     })
 }
 
-fn block_commitments(
+/// See [`pre_validate_state_snapshot_inner`].
+pub async fn pre_validate_state_snapshot(
+    pool: &RayonPool,
+    snapshot: UnverifiedStateSnapshot,
+    validation: BlockValidationContext,
+    compilation_pool: Arc<ClassCompilationPool>,
+) -> Result<PreValidatedStateSnapshot, BlockImportError> {
+    tracing::debug!("spawning pre_validate (state snapshot)");
+    let res =
+        pool.spawn_rayon_task(move || pre_validate_state_snapshot_inner(snapshot, validation, &compilation_pool)).await;
+    tracing::debug!("finished pre_validate (state snapshot)");
+    res
+}
+
+/// Compiles the classes of a state snapshot, and checks that every class declared in its state diff has a definition.
+/// This runs on the [`rayon`] threadpool.
+pub fn pre_validate_state_snapshot_inner(
+    snapshot: UnverifiedStateSnapshot,
+    validation: BlockValidationContext,
+    compilation_pool: &ClassCompilationPool,
+) -> Result<PreValidatedStateSnapshot, BlockImportError> {
+    let definitions: HashMap<Felt, Option<Felt>> = snapshot
+        .declared_classes
+        .iter()
+        .map(|class| match class {
+            DeclaredClass::Legacy(legacy) => (legacy.class_hash, None),
+            DeclaredClass::Sierra(sierra) => (sierra.class_hash, Some(sierra.compiled_class_hash)),
+        })
+        .collect();
+
+    for item in &snapshot.state_diff.declared_classes {
+        match definitions.get(&item.class_hash) {
+            Some(Some(compiled_class_hash)) if *compiled_class_hash == item.compiled_class_hash => {}
+            Some(Some(compiled_class_hash)) => {
+                return Err(BlockImportError::CompiledClassHash {
+                    class_hash: item.class_hash,
+                    got: *compiled_class_hash,
+                    expected: item.compiled_class_hash,
+                })
+            }
+            _ => return Err(BlockImportError::MissingClassDefinition { class_hash: item.class_hash }),
+        }
+    }
+    for class_hash in &snapshot.state_diff.deprecated_declared_classes {
+        if !matches!(definitions.get(class_hash), Some(None)) {
+            return Err(BlockImportError::MissingClassDefinition { class_hash: *class_hash });
+        }
+    }
+
+    let converted_classes = convert_classes(snapshot.declared_classes, &validation, None, compilation_pool)?;
+
+    Ok(PreValidatedStateSnapshot { header: snapshot.header, state_diff: snapshot.state_diff, converted_classes })
+}
+
+pub(crate) fn block_commitments(
     block: &UnverifiedFullBlock,
     validation: &BlockValidationContext,
+    state_diff_cache: Option<&StateDiffCommitmentCache>,
 ) -> Result<ValidatedCommitments, BlockImportError> {
+    if validation.trust_commitments {
+        if let Some(commitments) = trusted_commitments(&block.commitments) {
+            return Ok(commitments);
+        }
+    }
+
     let (mut receipt_c, mut state_diff_c, mut transaction_c, mut event_c) = Default::default();
     [
         Box::new(|| {
@@ -113,7 +202,7 @@ fn block_commitments(
             Ok(())
         }) as Box<dyn FnOnce() -> Result<(), BlockImportError> + Send>,
         Box::new(|| {
-            state_diff_c = state_diff_commitment(block, validation)?;
+            state_diff_c = state_diff_commitment(block, validation, state_diff_cache)?;
             Ok(())
         }),
         Box::new(|| {
@@ -140,19 +229,103 @@ fn block_commitments(
     })
 }
 
-fn convert_classes(
+/// The commitments given with a block, when none of them is missing.
+fn trusted_commitments(commitments: &UnverifiedCommitments) -> Option<ValidatedCommitments> {
+    Some(ValidatedCommitments {
+        transaction_count: commitments.transaction_count?,
+        transaction_commitment: commitments.transaction_commitment?,
+        event_count: commitments.event_count?,
+        event_commitment: commitments.event_commitment?,
+        state_diff_length: commitments.state_diff_length?,
+        state_diff_commitment: commitments.state_diff_commitment?,
+        receipt_commitment: commitments.receipt_commitment?,
+    })
+}
+
+/// Checks the Sierra classes declared by the transactions of the block against the classes declared in its state
+/// diff. Legacy classes are not checked: the state diffs of the blocks before v0.11 do not list them.
+pub(crate) fn check_declared_classes(
+    transactions: &[Transaction],
+    receipts: &[TransactionReceipt],
+    state_diff: &StateDiff,
+    validation: &BlockValidationContext,
+) -> Result<(), BlockImportError> {
+    let declared_compiled_class_hashes: HashMap<Felt, Felt> = state_diff
+        .declared_classes
+        .iter()
+        .map(|declared_class| (declared_class.class_hash, declared_class.compiled_class_hash))
+        .collect();
+
+    let mut declared_by_transactions = Vec::new();
+    for (index, (tx, receipt)) in transactions.iter().zip(receipts).enumerate() {
+        // Reverted declare transactions do not declare their class.
+        if matches!(receipt.execution_result(), ExecutionResult::Reverted { .. }) {
+            continue;
+        }
+        let (class_hash, compiled_class_hash) = match tx {
+            Transaction::Declare(DeclareTransaction::V2(tx)) => (tx.class_hash, tx.compiled_class_hash),
+            Transaction::Declare(DeclareTransaction::V3(tx)) => (tx.class_hash, tx.compiled_class_hash),
+            _ => continue,
+        };
+        let Some(expected) = declared_compiled_class_hashes.get(&class_hash) else {
+            return Err(BlockImportError::DeclaredClassNotInStateDiff { index, class_hash });
+        };
+        if *expected != compiled_class_hash {
+            return Err(BlockImportError::DeclareCompiledClassHash {
+                index,
+                class_hash,
+                got: compiled_class_hash,
+                expected: *expected,
+            });
+        }
+        declared_by_transactions.push(class_hash);
+    }
+
+    if validation.strict_declared_classes
+        && !state_diff
+            .declared_classes
+            .iter()
+            .map(|class| class.class_hash)
+            .eq(declared_by_transactions.iter().copied())
+    {
+        return Err(BlockImportError::DeclaredClassesOrder {
+            state_diff: state_diff.declared_classes.iter().map(|class| class.class_hash.hex_display()).join(", "),
+            transactions: declared_by_transactions.iter().map(|class_hash| class_hash.hex_display()).join(", "),
+        });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn convert_classes(
     declared_classes: Vec<DeclaredClass>,
     validation: &BlockValidationContext,
+    class_cache: Option<&ClassCache>,
+    compilation_pool: &ClassCompilationPool,
 ) -> Result<Vec<ConvertedClass>, BlockImportError> {
-    declared_classes.into_par_iter().map(|class| class_conversion(class, validation)).collect()
+    compilation_pool.install(|| {
+        let span = tracing::Span::current();
+        declared_classes
+            .into_par_iter()
+            .map(|class| span.in_scope(|| class_conversion(class, validation, class_cache)))
+            .collect()
+    })
 }
 
 fn class_conversion(
     class: DeclaredClass,
     validation: &BlockValidationContext,
+    class_cache: Option<&ClassCache>,
 ) -> Result<ConvertedClass, BlockImportError> {
     match class {
         DeclaredClass::Sierra(sierra) => {
+            // A cached class was checked against its class hash and compiled class hash when it was converted.
+            if let Some(cached) =
+                class_cache.and_then(|cache| cache.get(&sierra.class_hash, &sierra.compiled_class_hash))
+            {
+                tracing::trace!("Class with hash {:#x} found in the class cache", sierra.class_hash);
+                return Ok(ConvertedClass::Sierra(cached));
+            }
             tracing::trace!("Converting class with hash {:#x}", sierra.class_hash);
             if !validation.trust_class_hashes {
                 let class_hash = sierra
@@ -174,11 +347,16 @@ fn class_conversion(
                     expected: compiled_class_hash,
                 });
             }
-            Ok(ConvertedClass::Sierra(SierraConvertedClass {
+            let converted = SierraConvertedClass {
                 class_hash: sierra.class_hash,
                 info: SierraClassInfo { contract_class: Arc::new(sierra.contract_class), compiled_class_hash },
                 compiled: Arc::new(compiled_class),
-            }))
+            };
+            // Only the classes checked against their class hash are cached, so that a cached class can be trusted.
+            if let Some(cache) = class_cache.filter(|_| !validation.trust_class_hashes) {
+                cache.insert(converted.clone());
+            }
+            Ok(ConvertedClass::Sierra(converted))
         }
         DeclaredClass::Legacy(legacy) => {
             tracing::trace!("Converting legacy class with hash {:#x}", legacy.class_hash);
@@ -244,6 +422,7 @@ fn transaction_commitment(
     validation: &BlockValidationContext,
 ) -> Result<Felt, BlockImportError> {
     let starknet_version = block.header.protocol_version;
+    let hash_version = validation.hash_version(starknet_version);
 
     let transaction_hashes = transaction_hashes(&block.receipts, &block.transactions, starknet_version, validation)?;
 
@@ -258,15 +437,11 @@ fn transaction_commitment(
         .transactions
         .par_iter()
         .zip(transaction_hashes)
-        .map(|(tx, tx_hash)| tx.compute_hash_with_signature(tx_hash, starknet_version))
+        .map(|(tx, tx_hash)| tx.compute_hash_with_signature(tx_hash, hash_version))
         .collect();
 
     // Transaction commitment
-    let got = if starknet_version < StarknetVersion::V0_13_2 {
-        compute_merkle_root::<Pedersen>(&tx_hashes_with_signature)
-    } else {
-        compute_merkle_root::<Poseidon>(&tx_hashes_with_signature)
-    };
+    let got = CommitmentScheme::for_version(hash_version).transaction_commitment(&tx_hashes_with_signature);
 
     if let Some(expected) = block.commitments.transaction_commitment.filter(|&expected| expected != got) {
         return Err(BlockImportError::TransactionCommitment { got, expected });
@@ -278,7 +453,7 @@ fn transaction_commitment(
 /// Compute the events commitment for a block.
 fn event_commitment(
     block: &UnverifiedFullBlock,
-    _validation: &BlockValidationContext,
+    validation: &BlockValidationContext,
 ) -> Result<Felt, BlockImportError> {
     let events_with_tx_hash: Vec<_> = block
         .receipts
@@ -292,19 +467,8 @@ fn event_commitment(
         }
     }
 
-    let got = if events_with_tx_hash.is_empty() {
-        Felt::ZERO
-    } else if block.header.protocol_version < StarknetVersion::V0_13_2 {
-        let events_hash =
-            events_with_tx_hash.into_par_iter().map(|(_, event)| event.compute_hash_pedersen()).collect::<Vec<_>>();
-        compute_merkle_root::<Pedersen>(&events_hash)
-    } else {
-        let events_hash = events_with_tx_hash
-            .into_par_iter()
-            .map(|(hash, event)| event.compute_hash_poseidon(&hash))
-            .collect::<Vec<_>>();
-        compute_merkle_root::<Poseidon>(&events_hash)
-    };
+    let scheme = CommitmentScheme::for_version(validation.hash_version(block.header.protocol_version));
+    let got = scheme.event_commitment(&events_with_tx_hash);
 
     if let Some(expected) = block.commitments.event_commitment {
         if expected != got {
@@ -318,10 +482,10 @@ fn event_commitment(
 /// Compute the receipt commitment for a block.
 fn receipt_commitment(
     block: &UnverifiedFullBlock,
-    _validation: &BlockValidationContext,
+    validation: &BlockValidationContext,
 ) -> Result<Felt, BlockImportError> {
-    let hashes = block.receipts.par_iter().map(TransactionReceipt::compute_hash).collect::<Vec<_>>();
-    let got = compute_merkle_root::<Poseidon>(&hashes);
+    let scheme = CommitmentScheme::for_version(validation.hash_version(block.header.protocol_version));
+    let got = scheme.receipt_commitment(&block.receipts);
 
     if let Some(expected) = block.commitments.receipt_commitment {
         if expected != got {
@@ -331,10 +495,11 @@ fn receipt_commitment(
     Ok(got)
 }
 
-/// Compute the state diff commitment for a block.
+/// Compute the state diff commitment for a block, unless its state diff is found in `state_diff_cache`.
 fn state_diff_commitment(
     block: &UnverifiedFullBlock,
-    _validation: &BlockValidationContext,
+    validation: &BlockValidationContext,
+    state_diff_cache: Option<&StateDiffCommitmentCache>,
 ) -> Result<Felt, BlockImportError> {
     let got = block.state_diff.len() as u64;
     if let Some(expected) = block.commitments.state_diff_length {
@@ -343,7 +508,14 @@ fn state_diff_commitment(
         }
     }
 
-    let got = block.state_diff.compute_hash();
+    let scheme = CommitmentScheme::for_version(validation.hash_version(block.header.protocol_version));
+    let compute = || scheme.state_diff_commitment(&block.state_diff);
+    let got = match (block.commitments.state_diff_commitment, state_diff_cache) {
+        (Some(expected), Some(cache)) => {
+            cache.get_or_compute(scheme, block.commitments.block_hash, &block.state_diff, expected, compute)
+        }
+        _ => compute(),
+    };
     if let Some(expected) = block.commitments.state_diff_commitment {
         if expected != got {
             return Err(BlockImportError::StateDiffCommitment { got, expected });
@@ -352,47 +524,67 @@ fn state_diff_commitment(
     Ok(got)
 }
 
-/// Compute the root hash of a list of values.
-/// This implements transactions, events, receipts and state-diff [commitments specs] using memory
-/// backed bonsai storage.
-///
-/// [commitments specs]: https://docs.starknet.io/architecture-and-concepts/network-architecture/block-structure/#transactions_events_receipts_commitments
-// The `HashMapDb` can't fail, so we can safely unwrap the results.
-//
-// perf: Note that committing changes still has the greatest performance hit
-// as this is where the root hash is calculated. Due to the Merkle structure
-// of Bonsai Tries, this results in a trie size that grows very rapidly with
-// each new insertion. It seems that the only vector of optimization here
-// would be to parallelize the tree traversal on insertion and optimize hash computation.
-// It seems lambdaclass' crypto lib does not do simd hashing, we may want to look into that.
-fn compute_merkle_root<H: StarkHash + Send + Sync>(values: &[Felt]) -> Felt {
-    //TODO: replace the identifier by an empty slice when bonsai supports it
-    const IDENTIFIER: &[u8] = b"0xinmemory";
-    let config = bonsai_trie::BonsaiStorageConfig::default();
-    let bonsai_db = bonsai_trie::databases::HashMapDb::<bonsai_trie::id::BasicId>::default();
-    let mut bonsai_storage =
-        bonsai_trie::BonsaiStorage::<_, _, H>::new(bonsai_db, config, /* max tree height */ 64);
-
-    values.iter().enumerate().for_each(|(id, value)| {
-        let key = BitVec::from_vec(id.to_be_bytes().to_vec());
-        bonsai_storage.insert(IDENTIFIER, key.as_bitslice(), value).expect("Failed to insert into bonsai storage");
-    });
-
-    let id = bonsai_trie::id::BasicIdBuilder::new().new_id();
-
-    bonsai_storage.commit(id).expect("Failed to commit to bonsai storage");
-    bonsai_storage.root_hash(IDENTIFIER).expect("Failed to get root hash")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::block_import_utils::create_validation_context;
+    use mp_receipt::DeclareTransactionReceipt;
+    use mp_state_update::DeclaredClassItem;
+    use mp_transactions::{DeclareTransactionV2, InvokeTransaction, InvokeTransactionV1};
+
+    fn declare(class_hash: u64, compiled_class_hash: u64, reverted: bool) -> (Transaction, TransactionReceipt) {
+        let tx = Transaction::Declare(DeclareTransaction::V2(DeclareTransactionV2 {
+            class_hash: Felt::from(class_hash),
+            compiled_class_hash: Felt::from(compiled_class_hash),
+            ..Default::default()
+        }));
+        let execution_result =
+            if reverted { ExecutionResult::Reverted { reason: "reverted".into() } } else { ExecutionResult::Succeeded };
+        (tx, TransactionReceipt::Declare(DeclareTransactionReceipt { execution_result, ..Default::default() }))
+    }
 
-    #[test]
-    fn test_compute_root() {
-        let values = vec![Felt::ONE, Felt::TWO, Felt::THREE];
-        let root = compute_merkle_root::<Poseidon>(&values);
+    fn state_diff(declared_classes: &[(u64, u64)]) -> StateDiff {
+        StateDiff {
+            declared_classes: declared_classes
+                .iter()
+                .map(|&(class_hash, compiled_class_hash)| DeclaredClassItem {
+                    class_hash: Felt::from(class_hash),
+                    compiled_class_hash: Felt::from(compiled_class_hash),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
 
-        assert_eq!(root, Felt::from_hex_unchecked("0x3b5cc7f1292eb3847c3f902d048a7e5dc7702d1c191ccd17c2d33f797e6fc32"));
+    #[test]
+    fn test_check_declared_classes() {
+        let invoke = (
+            Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1::default())),
+            TransactionReceipt::Invoke(Default::default()),
+        );
+        let (transactions, receipts): (Vec<_>, Vec<_>) =
+            [declare(1, 10, false), invoke, declare(2, 20, false), declare(3, 30, true)].into_iter().unzip();
+        let check = |declared_classes: &[(u64, u64)], strict: bool| {
+            let validation = create_validation_context(false).strict_declared_classes(strict);
+            check_declared_classes(&transactions, &receipts, &state_diff(declared_classes), &validation)
+        };
+
+        // The class of the reverted declare transaction is not declared.
+        check(&[(1, 10), (2, 20)], true).unwrap();
+        check(&[(2, 20), (1, 10)], false).unwrap();
+        assert!(matches!(
+            check(&[(1, 10)], false),
+            Err(BlockImportError::DeclaredClassNotInStateDiff { index: 2, class_hash }) if class_hash == Felt::TWO
+        ));
+        assert!(matches!(
+            check(&[(1, 10), (2, 21)], false),
+            Err(BlockImportError::DeclareCompiledClassHash { index: 2, got, expected, .. })
+                if got == Felt::from(20) && expected == Felt::from(21)
+        ));
+        assert!(matches!(check(&[(2, 20), (1, 10)], true), Err(BlockImportError::DeclaredClassesOrder { .. })));
+        assert!(matches!(
+            check(&[(1, 10), (2, 20), (3, 30)], true),
+            Err(BlockImportError::DeclaredClassesOrder { .. })
+        ));
     }
 }
@@ -49,17 +49,57 @@ impl RayonPool {
     }
 }
 
+/// Thread pool dedicated to the conversion of the declared classes, which compiles the Sierra classes to CASM.
+///
+/// Compiling a class takes much longer than the rest of the block pre-validation. On the global rayon pool, a burst of
+/// declared classes could take all of its threads and starve the commitments of the blocks behind them. The
+/// pre-validation of a block waits for its classes on this pool instead, and the [`RayonPool`] bound on the
+/// pre-validation tasks then slows the fetching of the next blocks down.
+pub struct ClassCompilationPool {
+    pool: rayon::ThreadPool,
+}
+
+impl ClassCompilationPool {
+    pub fn new(n_threads: usize) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(n_threads)
+            .thread_name(|i| format!("class-compilation-{i}"))
+            .build()?;
+        Ok(Self { pool })
+    }
+
+    /// Half of the cores, so that the other half stays available for the rest of the block import.
+    pub fn default_n_threads() -> usize {
+        let n_cores = thread::available_parallelism().expect("Getting the number of cores").get();
+        (n_cores / 2).max(1)
+    }
+
+    /// Runs `func` on this pool, the parallel iterators in it included, in the current tracing span. When called from a
+    /// thread of the global rayon pool, that thread keeps running the other tasks of the global pool while waiting.
+    pub fn install<F, R>(&self, func: F) -> R
+    where
+        F: FnOnce() -> R + Send,
+        R: Send,
+    {
+        let span = tracing::Span::current();
+        self.pool.install(move || span.in_scope(func))
+    }
+}
+
+/// Runs `func` on the global rayon pool. The task runs in the current tracing span, so that the spans it opens are
+/// children of the span of the caller, and not new traces.
 pub async fn global_spawn_rayon_task<F, R>(func: F) -> R
 where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
     let (tx, rx) = tokio::sync::oneshot::channel();
+    let span = tracing::Span::current();
 
     // Important: fifo mode.
     rayon::spawn_fifo(move || {
         // We bubble up the panics to the tokio pool.
-        let _result = tx.send(std::panic::catch_unwind(AssertUnwindSafe(func)));
+        let _result = tx.send(std::panic::catch_unwind(AssertUnwindSafe(|| span.in_scope(func))));
     });
 
     rx.await.expect("Tokio channel closed").expect("Rayon task panicked")
@@ -0,0 +1,134 @@
+//! Cache of the state diff commitments.
+//!
+//! The state diff commitment hashes the whole state diff of a block, which is large for the blocks touching a lot of
+//! storage. The same block is pre-validated again when it is imported again, after a pipeline restart or a reorg, or
+//! when a block submitted to the node is also fetched from the gateway. The [`StateDiffCommitmentCache`] keeps the
+//! commitments verified for the latest blocks, by commitment scheme and block hash, so that these imports skip the
+//! hashing.
+//!
+//! The state diffs are not kept: the cache holds a fingerprint of each of them, a SipHash keyed with a random key of
+//! the node, which is much cheaper to compute than the commitment. A state diff is only found in the cache when it has
+//! the same fingerprint, and claims the same commitment: a block with a different state diff is always hashed, whatever
+//! block hash and commitment it claims.
+
+use crate::commitments::CommitmentScheme;
+use mp_state_update::StateDiff;
+use starknet_types_core::felt::Felt;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::Mutex;
+
+/// Default number of state diffs kept by the [`StateDiffCommitmentCache`].
+pub const DEFAULT_STATE_DIFF_CACHE_SIZE: usize = 16;
+
+/// Bounded cache of the verified state diff commitments. The oldest entry is evicted first.
+pub struct StateDiffCommitmentCache {
+    capacity: usize,
+    /// Key of the state diff fingerprints.
+    hasher: RandomState,
+    entries: Mutex<VecDeque<CachedCommitment>>,
+}
+
+struct CachedCommitment {
+    scheme: CommitmentScheme,
+    block_hash: Felt,
+    commitment: Felt,
+    fingerprint: u64,
+}
+
+impl StateDiffCommitmentCache {
+    /// A cache of the commitments of `capacity` blocks. Nothing is cached when `capacity` is 0.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, hasher: RandomState::new(), entries: Default::default() }
+    }
+
+    /// The commitment of `state_diff` in the block `block_hash`, which is computed with `compute` unless the state
+    /// diff was already verified against the `expected` commitment with the same scheme. The commitment is cached when
+    /// it is the expected one. Nothing is cached for the blocks without a block hash.
+    pub(crate) fn get_or_compute(
+        &self,
+        scheme: CommitmentScheme,
+        block_hash: Option<Felt>,
+        state_diff: &StateDiff,
+        expected: Felt,
+        compute: impl FnOnce() -> Felt,
+    ) -> Felt {
+        let Some(block_hash) = block_hash.filter(|_| self.capacity > 0) else { return compute() };
+        let fingerprint = self.hasher.hash_one(state_diff);
+        let cached = self.entries.lock().expect("Poisoned lock").iter().any(|entry| {
+            entry.scheme == scheme
+                && entry.block_hash == block_hash
+                && entry.commitment == expected
+                && entry.fingerprint == fingerprint
+        });
+        if cached {
+            return expected;
+        }
+
+        let got = compute();
+        if got == expected {
+            let mut entries = self.entries.lock().expect("Poisoned lock");
+            entries.retain(|entry| entry.scheme != scheme || entry.block_hash != block_hash);
+            if entries.len() >= self.capacity {
+                entries.pop_front();
+            }
+            entries.push_back(CachedCommitment { scheme, block_hash, commitment: got, fingerprint });
+        }
+        got
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("Poisoned lock").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::StarknetVersion;
+
+    fn state_diff(class_hash: u64) -> StateDiff {
+        StateDiff { deprecated_declared_classes: vec![Felt::from(class_hash)], ..Default::default() }
+    }
+
+    #[test]
+    fn test_state_diff_commitment_cache() {
+        let cache = StateDiffCommitmentCache::new(2);
+        let scheme = CommitmentScheme::for_version(StarknetVersion::LATEST);
+        let (one, two, three) = (state_diff(1), state_diff(2), state_diff(3));
+        let get_or_compute = |block_hash: u64, state_diff: &StateDiff, expected: Felt, compute: fn() -> Felt| {
+            cache.get_or_compute(scheme, Some(Felt::from(block_hash)), state_diff, expected, compute)
+        };
+
+        // A state diff which does not match its expected commitment is not cached.
+        assert_eq!(get_or_compute(1, &one, Felt::TWO, || state_diff(1).compute_hash()), one.compute_hash());
+        assert!(cache.is_empty());
+        // Nor a block without a block hash.
+        cache.get_or_compute(scheme, None, &one, one.compute_hash(), || state_diff(1).compute_hash());
+        assert!(cache.is_empty());
+
+        get_or_compute(1, &one, one.compute_hash(), || state_diff(1).compute_hash());
+        get_or_compute(2, &two, two.compute_hash(), || state_diff(2).compute_hash());
+        assert_eq!(get_or_compute(1, &one, one.compute_hash(), || unreachable!()), one.compute_hash());
+        // Another state diff claiming the block hash and commitment of a cached one is hashed.
+        assert_eq!(
+            get_or_compute(1, &three, one.compute_hash(), || state_diff(3).compute_hash()),
+            three.compute_hash()
+        );
+        // The same block with another commitment scheme is hashed.
+        let other_scheme = CommitmentScheme::for_version(StarknetVersion::V0_13_1);
+        assert_eq!(
+            cache.get_or_compute(other_scheme, Some(Felt::ONE), &one, one.compute_hash(), || Felt::ZERO),
+            Felt::ZERO
+        );
+
+        // The oldest entry is evicted.
+        get_or_compute(3, &three, three.compute_hash(), || state_diff(3).compute_hash());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(get_or_compute(1, &one, one.compute_hash(), || Felt::ZERO), Felt::ZERO);
+    }
+}
@@ -57,6 +57,10 @@ pub fn create_validation_context(ignore_block_order: bool) -> BlockValidationCon
         trust_global_tries: false,
         trust_transaction_hashes: false,
         trust_class_hashes: false,
+        trust_signatures: false,
+        compute_v0_13_2_hashes: false,
+        strict_declared_classes: false,
+        trust_commitments: false,
     }
 }
 
@@ -105,6 +109,7 @@ pub fn create_dummy_block() -> PreValidatedBlock {
         state_diff: StateDiff::default(),
         converted_classes: Default::default(),
         visited_segments: None,
+        consensus_signature: None,
     }
 }
 
@@ -130,6 +135,7 @@ pub fn create_dummy_unverified_full_block() -> UnverifiedFullBlock {
         commitments: UnverifiedCommitments::default(),
         trusted_converted_classes: vec![],
         visited_segments: None,
+        consensus_signature: None,
     }
 }
 
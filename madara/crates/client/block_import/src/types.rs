@@ -3,7 +3,7 @@
 
 use mp_block::{
     header::{BlockTimestamp, GasPrices, L1DataAvailabilityMode},
-    Header, VisitedSegments,
+    ConsensusSignature, Header, VisitedSegments,
 };
 use mp_chain_config::StarknetVersion;
 use mp_class::{
@@ -45,6 +45,19 @@ pub struct BlockValidationContext {
     pub trust_global_tries: bool,
     /// Ignore the order of the blocks to allow starting at some height.
     pub ignore_block_order: bool,
+    /// Do not check the block signatures against the sequencer public keys of the chain config. Otherwise, unsigned
+    /// blocks are rejected when the chain config has sequencer public keys.
+    pub trust_signatures: bool,
+    /// Hash the blocks older than Starknet v0.13.2 with the v0.13.2 block hash algorithm, for chains whose older
+    /// block hashes were recomputed. The expected block hashes must then be the recomputed ones.
+    pub compute_v0_13_2_hashes: bool,
+    /// Require the classes declared in the state diff to be exactly the Sierra classes declared by the transactions of
+    /// the block, in the same order. Blocks which declare classes without a transaction, such as the genesis block of a
+    /// devnet, are rejected.
+    pub strict_declared_classes: bool,
+    /// Use the commitments given with the block instead of computing them. This is only for the blocks closed by the
+    /// block production, which computes them with [`crate::commitments::CommitmentScheme::block_commitments`].
+    pub trust_commitments: bool,
     /// The chain id of the current block.
     pub chain_id: ChainId,
 }
@@ -57,6 +70,10 @@ impl BlockValidationContext {
             trust_global_tries: false,
             chain_id,
             ignore_block_order: false,
+            trust_signatures: false,
+            compute_v0_13_2_hashes: false,
+            strict_declared_classes: false,
+            trust_commitments: false,
         }
     }
     pub fn trust_transaction_hashes(mut self, v: bool) -> Self {
@@ -71,6 +88,32 @@ impl BlockValidationContext {
         self.trust_global_tries = v;
         self
     }
+    pub fn trust_signatures(mut self, v: bool) -> Self {
+        self.trust_signatures = v;
+        self
+    }
+    pub fn compute_v0_13_2_hashes(mut self, v: bool) -> Self {
+        self.compute_v0_13_2_hashes = v;
+        self
+    }
+    pub fn strict_declared_classes(mut self, v: bool) -> Self {
+        self.strict_declared_classes = v;
+        self
+    }
+    pub fn trust_commitments(mut self, v: bool) -> Self {
+        self.trust_commitments = v;
+        self
+    }
+
+    /// The protocol version whose algorithms the block hash and the commitments of a block of version `version` are
+    /// computed with, see [`Self::compute_v0_13_2_hashes`]. The transaction hashes always use `version`.
+    pub fn hash_version(&self, version: StarknetVersion) -> StarknetVersion {
+        if self.compute_v0_13_2_hashes && version < StarknetVersion::V0_13_2 {
+            StarknetVersion::V0_13_2
+        } else {
+            version
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -141,6 +184,22 @@ pub struct UnverifiedCommitments {
     pub block_hash: Option<Felt>,
 }
 
+impl From<ValidatedCommitments> for UnverifiedCommitments {
+    fn from(commitments: ValidatedCommitments) -> Self {
+        Self {
+            transaction_count: Some(commitments.transaction_count),
+            transaction_commitment: Some(commitments.transaction_commitment),
+            event_count: Some(commitments.event_count),
+            event_commitment: Some(commitments.event_commitment),
+            state_diff_length: Some(commitments.state_diff_length),
+            state_diff_commitment: Some(commitments.state_diff_commitment),
+            receipt_commitment: Some(commitments.receipt_commitment),
+            global_state_root: None,
+            block_hash: None,
+        }
+    }
+}
+
 /// An unverified pending full block as input for the block import pipeline.
 #[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct UnverifiedPendingFullBlock {
@@ -167,6 +226,20 @@ pub struct UnverifiedFullBlock {
     pub trusted_converted_classes: Vec<ConvertedClass>,
     pub commitments: UnverifiedCommitments,
     pub visited_segments: Option<VisitedSegments>,
+    /// Signature of the block hash by the sequencer, stored alongside the block.
+    #[serde(default)]
+    pub consensus_signature: Option<ConsensusSignature>,
+}
+
+/// A state snapshot as input for the block import pipeline: the header of a block, and the whole state of the chain at
+/// that block. This is used to bootstrap a node at some height without replaying every block before it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UnverifiedStateSnapshot {
+    pub header: Header,
+    /// The state of the chain at the snapshot block, as a single state diff from the empty state.
+    pub state_diff: StateDiff,
+    /// Definitions of every class declared in the state diff.
+    pub declared_classes: Vec<DeclaredClass>,
 }
 
 // Pre-validate outputs.
@@ -195,6 +268,7 @@ pub struct PreValidatedBlock {
     pub unverified_block_hash: Option<Felt>,
     pub unverified_block_number: Option<u64>,
     pub visited_segments: Option<VisitedSegments>,
+    pub consensus_signature: Option<ConsensusSignature>,
 }
 
 /// Output of the [`crate::pre_validate`] step.
@@ -208,6 +282,14 @@ pub struct PreValidatedPendingBlock {
     pub visited_segments: Option<VisitedSegments>,
 }
 
+/// Output of the [`crate::pre_validate_state_snapshot`] step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PreValidatedStateSnapshot {
+    pub header: Header,
+    pub state_diff: StateDiff,
+    pub converted_classes: Vec<ConvertedClass>,
+}
+
 // Verify-apply output.
 
 #[derive(Clone, Debug, Eq, PartialEq)]
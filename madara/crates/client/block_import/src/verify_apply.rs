@@ -1,28 +1,36 @@
+use crate::metrics::GLOBAL_TRIE_APPLY_DURATION;
 use crate::{
     global_spawn_rayon_task, BlockImportError, BlockImportResult, BlockValidationContext, PendingBlockImportResult,
-    PreValidatedBlock, PreValidatedPendingBlock, UnverifiedHeader, ValidatedCommitments,
+    PreValidatedBlock, PreValidatedPendingBlock, PreValidatedStateSnapshot, UnverifiedHeader, ValidatedCommitments,
 };
 use itertools::Itertools;
+use mc_db::global_trie_progress::calculate_state_root;
 use mc_db::{MadaraBackend, MadaraStorageError};
 use mp_block::BlockTag;
 use mp_block::{
-    header::PendingHeader, BlockId, Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock,
-    MadaraMaybePendingBlockInfo, MadaraPendingBlockInfo,
+    header::PendingHeader, BlockId, Header, MadaraBlock, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock,
+    MadaraMaybePendingBlockInfo, MadaraPendingBlock, MadaraPendingBlockInfo,
 };
 use mp_convert::{FeltHexDisplay, ToFelt};
+use mp_state_update::StateDiff;
+use opentelemetry::KeyValue;
 use starknet_api::core::ChainId;
 use starknet_types_core::felt::Felt;
-use starknet_types_core::hash::{Poseidon, StarkHash};
 use std::{borrow::Cow, sync::Arc};
 
 mod classes;
 mod contracts;
 
+/// Number of lagging blocks applied to the global tries before each imported block. The rest is left to the trie
+/// catch-up, see [`catch_up_global_tries`].
+const GLOBAL_TRIE_CATCH_UP_PER_BLOCK: usize = 16;
+
 pub struct VerifyApply {
     pub(crate) backend: Arc<MadaraBackend>,
     // Only one thread at once can verify_apply. This is the update trie step cannot be parallelized over blocks, and in addition
     // our database does not support concurrent write access.
-    mutex: tokio::sync::Mutex<()>,
+    // The lock is moved to the rayon task, so that it is held until the block is applied even if the future is dropped.
+    mutex: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl VerifyApply {
@@ -41,11 +49,15 @@ impl VerifyApply {
         validation: BlockValidationContext,
     ) -> Result<BlockImportResult, BlockImportError> {
         tracing::debug!("acquiring verify_apply exclusive");
-        let _exclusive = self.mutex.lock().await;
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
         tracing::debug!("acquired verify_apply exclusive");
 
         let backend = Arc::clone(&self.backend);
-        let res = global_spawn_rayon_task(move || verify_apply_inner(&backend, block, validation)).await;
+        let res = global_spawn_rayon_task(move || {
+            let _exclusive = exclusive;
+            verify_apply_inner(&backend, block, validation)
+        })
+        .await;
         tracing::debug!("releasing verify_apply exclusive");
         res
     }
@@ -57,14 +69,81 @@ impl VerifyApply {
         validation: BlockValidationContext,
     ) -> Result<PendingBlockImportResult, BlockImportError> {
         tracing::debug!("acquiring verify_apply exclusive (pending)");
-        let _exclusive = self.mutex.lock().await;
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
         tracing::debug!("acquired verify_apply exclusive (pending)");
 
         let backend = Arc::clone(&self.backend);
-        let res = global_spawn_rayon_task(move || verify_apply_pending_inner(&backend, block, validation)).await;
+        let res = global_spawn_rayon_task(move || {
+            let _exclusive = exclusive;
+            verify_apply_pending_inner(&backend, block, validation)
+        })
+        .await;
         tracing::debug!("releasing verify_apply exclusive (pending)");
         res
     }
+
+    /// See [`Self::verify_apply`].
+    pub async fn verify_apply_state_snapshot(
+        &self,
+        snapshot: PreValidatedStateSnapshot,
+        block_hash: Felt,
+        validation: BlockValidationContext,
+    ) -> Result<BlockImportResult, BlockImportError> {
+        tracing::debug!("acquiring verify_apply exclusive (state snapshot)");
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
+        tracing::debug!("acquired verify_apply exclusive (state snapshot)");
+
+        let backend = Arc::clone(&self.backend);
+        let res = global_spawn_rayon_task(move || {
+            let _exclusive = exclusive;
+            verify_apply_state_snapshot_inner(&backend, snapshot, block_hash, validation)
+        })
+        .await;
+        tracing::debug!("releasing verify_apply exclusive (state snapshot)");
+        res
+    }
+
+    /// See [`Self::verify_apply`].
+    pub async fn verify_apply_backfill(
+        &self,
+        block: PreValidatedBlock,
+        validation: BlockValidationContext,
+    ) -> Result<BlockImportResult, BlockImportError> {
+        tracing::debug!("acquiring verify_apply exclusive (backfill)");
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
+        tracing::debug!("acquired verify_apply exclusive (backfill)");
+
+        let backend = Arc::clone(&self.backend);
+        let res = global_spawn_rayon_task(move || {
+            let _exclusive = exclusive;
+            verify_apply_backfill_inner(&backend, block, validation)
+        })
+        .await;
+        tracing::debug!("releasing verify_apply exclusive (backfill)");
+        res
+    }
+
+    /// Applies the state diffs of at most `max_blocks` stored blocks to the global tries, when they lag behind the
+    /// blocks. See [`catch_up_global_tries`].
+    pub async fn catch_up_global_tries(&self, max_blocks: usize) -> Result<Option<u64>, BlockImportError> {
+        tracing::debug!("acquiring verify_apply exclusive (global tries catch-up)");
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
+        tracing::debug!("acquired verify_apply exclusive (global tries catch-up)");
+
+        let backend = Arc::clone(&self.backend);
+        let res = global_spawn_rayon_task(move || {
+            let _exclusive = exclusive;
+            catch_up_global_tries(&backend, u64::MAX, max_blocks)
+        })
+        .await;
+        tracing::debug!("releasing verify_apply exclusive (global tries catch-up)");
+        res
+    }
+
+    /// Waits until no block is being applied.
+    pub async fn wait_idle(&self) {
+        let _exclusive = self.mutex.lock().await;
+    }
 }
 
 /// This needs to be called sequentially, it will apply the state diff to the db, verify the state root and save the block.
@@ -80,14 +159,17 @@ pub fn verify_apply_inner(
         check_parent_hash_and_num(backend, block.header.parent_block_hash, block.unverified_block_number, &validation)?;
 
     // Update contract and its storage tries
-    let global_state_root = update_tries(backend, &block, &validation, block_number)?;
+    let global_state_root =
+        tracing::info_span!("update_tries").in_scope(|| update_tries(backend, &block, &validation, block_number))?;
 
     // Block hash
     let (block_hash, header) = block_hash(&block, &validation, block_number, parent_block_hash, global_state_root)?;
+    check_signature(&block, &validation, &backend.chain_config().sequencer_public_keys, block_number, block_hash)?;
 
     tracing::debug!("verify_apply_inner store block {}", header.block_number);
 
     // store block, also uses rayon heavily internally
+    let _span = tracing::info_span!("store_block").entered();
     backend
         .store_block(
             MadaraMaybePendingBlock {
@@ -106,6 +188,12 @@ pub fn verify_apply_inner(
         )
         .map_err(make_db_error("storing block in db"))?;
 
+    if let Some(signature) = &block.consensus_signature {
+        backend
+            .store_consensus_signature(header.block_number, signature)
+            .map_err(make_db_error("storing block signature in db"))?;
+    }
+
     Ok(BlockImportResult { header, block_hash })
 }
 
@@ -135,26 +223,148 @@ pub fn verify_apply_pending_inner(
         l1_da_mode,
     };
 
-    backend
-        .store_block(
-            MadaraMaybePendingBlock {
-                info: MadaraMaybePendingBlockInfo::Pending(MadaraPendingBlockInfo {
-                    header: header.clone(),
-                    tx_hashes: block.receipts.iter().map(|tx| tx.transaction_hash()).collect(),
-                }),
-                inner: MadaraBlockInner { transactions: block.transactions, receipts: block.receipts },
-            },
+    let pending_block = MadaraPendingBlock {
+        info: MadaraPendingBlockInfo {
+            header: header.clone(),
+            tx_hashes: block.receipts.iter().map(|tx| tx.transaction_hash()).collect(),
+        },
+        inner: MadaraBlockInner { transactions: block.transactions, receipts: block.receipts },
+    };
+    // The parent was checked above, but the next block may be imported before the pending block is stored.
+    let res = if validation.ignore_block_order {
+        backend.store_block(
+            pending_block.into(),
             block.state_diff,
             block.converted_classes,
             block.visited_segments,
             None,
         )
-        .map_err(make_db_error("storing block in db"))?;
+    } else {
+        backend.store_pending_block(pending_block, block.state_diff, block.converted_classes, block.visited_segments)
+    };
+    res.map_err(|error| match error {
+        MadaraStorageError::StalePendingBlock { parent_block_hash } => {
+            BlockImportError::StalePendingBlock { parent_block_hash }
+        }
+        error => make_db_error("storing block in db")(error),
+    })?;
 
     Ok(PendingBlockImportResult {})
 }
 
-fn make_db_error(context: impl Into<Cow<'static, str>>) -> impl FnOnce(MadaraStorageError) -> BlockImportError {
+/// Applies a state snapshot to an empty database: checks the snapshot header against the trusted block hash, builds the
+/// global tries from the snapshot state and checks them against the header, and stores the snapshot block. The block is
+/// stored without its transactions and receipts, which are not part of a snapshot.
+pub fn verify_apply_state_snapshot_inner(
+    backend: &MadaraBackend,
+    snapshot: PreValidatedStateSnapshot,
+    block_hash: Felt,
+    validation: BlockValidationContext,
+) -> Result<BlockImportResult, BlockImportError> {
+    if let Some(block_n) = backend.get_latest_block_n().map_err(make_db_error("getting latest block number"))? {
+        return Err(BlockImportError::Internal(
+            format!("Cannot import a state snapshot into a database which already has blocks up to #{block_n}").into(),
+        ));
+    }
+
+    let PreValidatedStateSnapshot { header, state_diff, converted_classes } = snapshot;
+    let block_number = header.block_number;
+
+    let got = header.compute_hash(validation.chain_id.to_felt(), validation.compute_v0_13_2_hashes);
+    if got != block_hash {
+        return Err(BlockImportError::BlockHash { got, expected: block_hash });
+    }
+
+    let global_state_root = state_root(backend, &state_diff, block_number, &BlockId::Tag(BlockTag::Latest))?;
+    if global_state_root != header.global_state_root {
+        return Err(BlockImportError::GlobalStateRoot { got: global_state_root, expected: header.global_state_root });
+    }
+    backend.complete_global_trie_update(block_number).map_err(make_db_error("completing global trie update"))?;
+
+    tracing::debug!("verify_apply_state_snapshot_inner store block {block_number}");
+
+    // The blocks before the snapshot can then be backfilled, see [`verify_apply_backfill_inner`].
+    backend
+        .store_state_snapshot(
+            MadaraBlock {
+                info: MadaraBlockInfo { header: header.clone(), block_hash, tx_hashes: vec![] },
+                inner: MadaraBlockInner { transactions: vec![], receipts: vec![] },
+            },
+            state_diff,
+            converted_classes,
+        )
+        .map_err(make_db_error("storing state snapshot in db"))?;
+
+    Ok(BlockImportResult { header, block_hash })
+}
+
+/// Stores a block older than the oldest block in the database, when the node was bootstrapped from a state snapshot.
+/// Blocks are backfilled from the newest to the oldest: the hash of the block must be the parent hash of the oldest
+/// block in the database. The state of the chain and the global tries are left untouched.
+pub fn verify_apply_backfill_inner(
+    backend: &MadaraBackend,
+    mut block: PreValidatedBlock,
+    validation: BlockValidationContext,
+) -> Result<BlockImportResult, BlockImportError> {
+    let Some(oldest_block_n) =
+        backend.get_oldest_backfilled_block_n().map_err(make_db_error("getting oldest backfilled block number"))?
+    else {
+        return Err(BlockImportError::Internal("There are no blocks to backfill".into()));
+    };
+    let expected_block_number = oldest_block_n
+        .checked_sub(1)
+        .ok_or_else(|| BlockImportError::Internal("All the blocks are already backfilled".into()))?;
+    let block_number = block.unverified_block_number.unwrap_or(expected_block_number);
+    if block_number != expected_block_number {
+        return Err(BlockImportError::LatestBlockN { expected: expected_block_number, got: block_number });
+    }
+
+    let oldest_block_info = backend
+        .get_block_info(&BlockId::Number(oldest_block_n))
+        .map_err(make_db_error("getting oldest block info"))?
+        .and_then(|info| info.as_nonpending_owned())
+        .ok_or_else(|| BlockImportError::Internal(format!("Missing oldest block #{oldest_block_n}").into()))?;
+    let (Some(parent_block_hash), Some(global_state_root)) =
+        (block.header.parent_block_hash, block.unverified_global_state_root)
+    else {
+        return Err(BlockImportError::Internal(
+            "Trying to backfill a block without a parent hash or a global state root".into(),
+        ));
+    };
+
+    // The block hash covers the global state root, which cannot be recomputed without the state of the parent block.
+    block.unverified_block_hash = Some(oldest_block_info.header.parent_block_hash);
+    let (block_hash, header) = block_hash(&block, &validation, block_number, parent_block_hash, global_state_root)?;
+    check_signature(&block, &validation, &backend.chain_config().sequencer_public_keys, block_number, block_hash)?;
+
+    tracing::debug!("verify_apply_backfill_inner store block {}", header.block_number);
+
+    backend
+        .store_backfilled_block(
+            &MadaraBlock {
+                info: MadaraBlockInfo {
+                    header: header.clone(),
+                    block_hash,
+                    tx_hashes: block.receipts.iter().map(|tx| tx.transaction_hash()).collect(),
+                },
+                inner: MadaraBlockInner { transactions: block.transactions, receipts: block.receipts },
+            },
+            &block.state_diff,
+        )
+        .map_err(make_db_error("storing backfilled block in db"))?;
+
+    if let Some(signature) = &block.consensus_signature {
+        backend
+            .store_consensus_signature(header.block_number, signature)
+            .map_err(make_db_error("storing block signature in db"))?;
+    }
+
+    Ok(BlockImportResult { header, block_hash })
+}
+
+pub(crate) fn make_db_error(
+    context: impl Into<Cow<'static, str>>,
+) -> impl FnOnce(MadaraStorageError) -> BlockImportError {
     move |error| BlockImportError::InternalDb { context: context.into(), error }
 }
 
@@ -194,17 +404,6 @@ fn check_parent_hash_and_num(
     Ok((block_number, expected_parent_block_hash))
 }
 
-/// "STARKNET_STATE_V0"
-const STARKNET_STATE_PREFIX: Felt = Felt::from_hex_unchecked("0x535441524b4e45545f53544154455f5630");
-
-fn calculate_state_root(contracts_trie_root: Felt, classes_trie_root: Felt) -> Felt {
-    if classes_trie_root == Felt::ZERO {
-        contracts_trie_root
-    } else {
-        Poseidon::hash_array(&[STARKNET_STATE_PREFIX, contracts_trie_root, classes_trie_root])
-    }
-}
-
 /// Returns the new global state root.
 fn update_tries(
     backend: &MadaraBackend,
@@ -218,9 +417,28 @@ fn update_tries(
                 "Trying to import a block without a global state root when using trust_global_tries".into(),
             ));
         };
+        // The tries now lag behind the blocks, they can be caught up later.
+        backend.record_global_trie_lag().map_err(make_db_error("recording global trie lag"))?;
         return Ok(global_state_root);
     }
 
+    // The tries lag behind the blocks when the previous blocks were imported with `trust_global_tries`, or when they
+    // were reset after a crash. Only a few of these blocks are applied here, so that the import is not blocked for the
+    // whole catch-up: until the tries have caught up, the root of the block is trusted, and it is checked later by the
+    // trie catch-up.
+    if let Some(parent_block_n) = block_number.checked_sub(1).filter(|_| !validation.ignore_block_order) {
+        let trie_block_n = catch_up_global_tries(backend, parent_block_n, GLOBAL_TRIE_CATCH_UP_PER_BLOCK)?;
+        if trie_block_n < Some(parent_block_n) {
+            match block.unverified_global_state_root {
+                Some(global_state_root) => return Ok(global_state_root),
+                // The root of a produced block can only be computed from up to date tries.
+                None => {
+                    catch_up_global_tries(backend, parent_block_n, usize::MAX)?;
+                }
+            }
+        }
+    }
+
     tracing::debug!(
         "Deployed contracts: [{:?}]",
         block.state_diff.deployed_contracts.iter().map(|c| c.address.hex_display()).format(", ")
@@ -234,24 +452,7 @@ fn update_tries(
         block.state_diff.deprecated_declared_classes.iter().map(|c| c.hex_display()).format(", ")
     );
 
-    let (contract_trie_root, class_trie_root) = rayon::join(
-        || {
-            contracts::contract_trie_root(
-                backend,
-                &block.state_diff.deployed_contracts,
-                &block.state_diff.replaced_classes,
-                &block.state_diff.nonces,
-                &block.state_diff.storage_diffs,
-                block_number,
-            )
-        },
-        || classes::class_trie_root(backend, &block.state_diff.declared_classes, block_number),
-    );
-
-    let state_root = calculate_state_root(
-        contract_trie_root.map_err(make_db_error("updating contract trie root"))?,
-        class_trie_root.map_err(make_db_error("updating class trie root"))?,
-    );
+    let state_root = state_root(backend, &block.state_diff, block_number, &BlockId::Tag(BlockTag::Latest))?;
 
     if let Some(expected) = block.unverified_global_state_root {
         if expected != state_root {
@@ -259,11 +460,100 @@ fn update_tries(
         }
     }
 
+    backend.complete_global_trie_update(block_number).map_err(make_db_error("completing global trie update"))?;
+
     Ok(state_root)
 }
 
+/// Applies the state diffs of the stored blocks up to `up_to_block_n` to the global tries, when they lag behind the
+/// blocks after an import with `trust_global_tries`. At most `max_blocks` blocks are applied, and the computed state
+/// roots are checked against the stored headers. Returns the latest block applied to the tries.
+pub fn catch_up_global_tries(
+    backend: &MadaraBackend,
+    up_to_block_n: u64,
+    max_blocks: usize,
+) -> Result<Option<u64>, BlockImportError> {
+    let mut trie_block_n =
+        backend.get_global_trie_block_n().map_err(make_db_error("getting global trie block number"))?;
+    let Some(latest_block_n) = backend.get_latest_block_n().map_err(make_db_error("getting latest block number"))?
+    else {
+        return Ok(trie_block_n);
+    };
+    let from_block_n = trie_block_n.map_or(0, |block_n| block_n + 1);
+
+    for block_n in (from_block_n..=up_to_block_n.min(latest_block_n)).take(max_blocks) {
+        let block_id = BlockId::Number(block_n);
+        let header = backend
+            .get_block_info(&block_id)
+            .map_err(make_db_error("getting block info"))?
+            .and_then(|info| info.as_nonpending_owned())
+            .ok_or_else(|| BlockImportError::Internal(format!("Missing block #{block_n}").into()))?
+            .header;
+        let state_diff = backend
+            .get_block_state_diff(&block_id)
+            .map_err(make_db_error("getting block state diff"))?
+            .ok_or_else(|| BlockImportError::Internal(format!("Missing state diff of block #{block_n}").into()))?;
+
+        // The contract fields left unchanged by the state diff are read at this block rather than at the latest one.
+        let global_state_root = state_root(backend, &state_diff, block_n, &block_id)?;
+        if global_state_root != header.global_state_root {
+            return Err(BlockImportError::GlobalStateRoot {
+                got: global_state_root,
+                expected: header.global_state_root,
+            });
+        }
+
+        backend.complete_global_trie_update(block_n).map_err(make_db_error("completing global trie update"))?;
+        trie_block_n = Some(block_n);
+    }
+
+    Ok(trie_block_n)
+}
+
+/// Applies a state diff to the contract and class tries, and returns the new global state root. The update is
+/// completed by [`MadaraBackend::complete_global_trie_update`] once the root is checked.
+fn state_root(
+    backend: &MadaraBackend,
+    state_diff: &StateDiff,
+    block_number: u64,
+    state_at: &BlockId,
+) -> Result<Felt, BlockImportError> {
+    backend.begin_global_trie_update(block_number).map_err(make_db_error("beginning global trie update"))?;
+    // The tries may be updated on another thread of the pool, which is not in the span of the block.
+    let span = tracing::Span::current();
+    let timed = |name: &str, f: &dyn Fn() -> Result<Felt, MadaraStorageError>| {
+        let _span = tracing::info_span!(parent: &span, "trie_apply", trie = %name.to_lowercase()).entered();
+        let started = std::time::Instant::now();
+        let res = f();
+        GLOBAL_TRIE_APPLY_DURATION
+            .record(started.elapsed().as_secs_f64(), &[KeyValue::new("trie", name.to_lowercase())]);
+        res
+    };
+    let (contract_trie_root, class_trie_root) = rayon::join(
+        || {
+            timed("Contract", &|| {
+                contracts::contract_trie_root(
+                    backend,
+                    &state_diff.deployed_contracts,
+                    &state_diff.replaced_classes,
+                    &state_diff.nonces,
+                    &state_diff.storage_diffs,
+                    block_number,
+                    state_at,
+                )
+            })
+        },
+        || timed("Class", &|| classes::class_trie_root(backend, &state_diff.declared_classes, block_number)),
+    );
+
+    Ok(calculate_state_root(
+        contract_trie_root.map_err(make_db_error("updating contract trie root"))?,
+        class_trie_root.map_err(make_db_error("updating class trie root"))?,
+    ))
+}
+
 /// Returns the block hash and header.
-fn block_hash(
+pub(crate) fn block_hash(
     block: &PreValidatedBlock,
     validation: &BlockValidationContext,
     block_number: u64,
@@ -308,11 +598,14 @@ fn block_hash(
         l1_gas_price,
         l1_da_mode,
     };
-    let block_hash = header.compute_hash(validation.chain_id.to_felt());
+    let block_hash = header.compute_hash(validation.chain_id.to_felt(), validation.compute_v0_13_2_hashes);
 
     if let Some(expected) = block.unverified_block_hash {
-        // mismatched block hash is allowed for blocks 1466..=2242 on mainnet
-        let is_special_trusted_case = validation.chain_id == ChainId::Mainnet && (1466..=2242).contains(&block_number);
+        // mismatched block hash is allowed for blocks 1466..=2242 on mainnet, unless they are hashed with the v0.13.2
+        // algorithm which does not have this issue
+        let is_special_trusted_case = validation.chain_id == ChainId::Mainnet
+            && (1466..=2242).contains(&block_number)
+            && !validation.compute_v0_13_2_hashes;
         if is_special_trusted_case {
             return Ok((expected, header));
         }
@@ -325,13 +618,38 @@ fn block_hash(
     Ok((block_hash, header))
 }
 
+/// Checks that the block signature was made by one of the sequencer public keys. All blocks are accepted when no public
+/// key is known for the chain or when the signatures are trusted, unsigned blocks are rejected otherwise.
+fn check_signature(
+    block: &PreValidatedBlock,
+    validation: &BlockValidationContext,
+    public_keys: &[Felt],
+    block_number: u64,
+    block_hash: Felt,
+) -> Result<(), BlockImportError> {
+    if validation.trust_signatures || public_keys.is_empty() {
+        return Ok(());
+    }
+    let Some(signature) = &block.consensus_signature else {
+        return Err(BlockImportError::MissingSignature { block_n: block_number });
+    };
+
+    let signed_by =
+        |public_key| matches!(starknet_crypto::verify(public_key, &block_hash, &signature.r, &signature.s), Ok(true));
+    if !public_keys.iter().any(signed_by) {
+        return Err(BlockImportError::InvalidSignature { block_n: block_number, block_hash });
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod verify_apply_tests {
     use super::*;
     use crate::tests::block_import_utils::*;
     use mc_db::tests::common::{finalized_block_zero, finalized_state_diff_zero};
 
-    use mp_chain_config::ChainConfig;
+    use mp_block::ConsensusSignature;
+    use mp_chain_config::{ChainConfig, StarknetVersion};
 
     use mp_state_update::{ContractStorageDiffItem, DeployedContractItem, StateDiff, StorageEntry};
 
@@ -530,6 +848,10 @@ mod verify_apply_tests {
             trust_global_tries,
             trust_transaction_hashes: false,
             trust_class_hashes: false,
+            trust_signatures: false,
+            compute_v0_13_2_hashes: false,
+            strict_declared_classes: false,
+            trust_commitments: false,
         };
 
         // WHEN: We call update_tries with these parameters
@@ -593,6 +915,10 @@ mod verify_apply_tests {
                 trust_global_tries: false,
                 trust_transaction_hashes: false,
                 trust_class_hashes: false,
+                trust_signatures: false,
+                compute_v0_13_2_hashes: false,
+                strict_declared_classes: false,
+                trust_commitments: false,
             },
             1466,
             felt!("0x1"),
@@ -650,6 +976,63 @@ mod verify_apply_tests {
         }
     }
 
+    #[test]
+    fn test_block_hash_pre_v0_13_2_override() {
+        let chain_id = ChainId::Other("something".to_string()).to_felt();
+        let mut block = create_dummy_block();
+        block.header.protocol_version = StarknetVersion::V0_11_1;
+        let validation = create_validation_context(false).compute_v0_13_2_hashes(true);
+
+        let (hash, header) = block_hash(&block, &validation, 1, felt!("0x1"), felt!("0xa")).unwrap();
+        assert_eq!(hash, header.compute_hash(chain_id, true));
+        let legacy_hash = header.compute_hash(chain_id, false);
+        assert_ne!(hash, legacy_hash);
+
+        // The legacy hash is only accepted without the override.
+        block.unverified_block_hash = Some(legacy_hash);
+        assert!(matches!(
+            block_hash(&block, &validation, 1, felt!("0x1"), felt!("0xa")),
+            Err(BlockImportError::BlockHash { got, expected }) if got == hash && expected == legacy_hash
+        ));
+        let validation = validation.compute_v0_13_2_hashes(false);
+        assert_eq!(block_hash(&block, &validation, 1, felt!("0x1"), felt!("0xa")).unwrap().0, legacy_hash);
+    }
+
+    #[test]
+    fn test_check_signature() {
+        let private_key = felt!("0x1234");
+        let public_key = starknet_crypto::get_public_key(&private_key);
+        let block_hash = felt!("0x271814f105da644661d0ef938cfccfd66d3e3585683fbcbee339db3d29c4574");
+        let k = starknet_crypto::rfc6979_generate_k(&block_hash, &private_key, None);
+        let signature = starknet_crypto::sign(&private_key, &block_hash, &k).unwrap();
+
+        let mut block = create_dummy_block();
+        let validation = create_validation_context(false);
+        let check = |block: &PreValidatedBlock, validation: &BlockValidationContext, public_keys: &[Felt]| {
+            check_signature(block, validation, public_keys, 1, block_hash)
+        };
+
+        // Unsigned blocks are rejected, unless the chain has no key or the signatures are trusted.
+        assert!(matches!(
+            check(&block, &validation, &[public_key]),
+            Err(BlockImportError::MissingSignature { block_n: 1 })
+        ));
+        check(&block, &validation, &[]).unwrap();
+        check(&block, &validation.clone().trust_signatures(true), &[public_key]).unwrap();
+
+        block.consensus_signature = Some(ConsensusSignature { r: signature.r, s: signature.s });
+        check(&block, &validation, &[public_key]).unwrap();
+        check(&block, &validation, &[felt!("0x1"), public_key]).unwrap();
+        assert!(matches!(
+            check(&block, &validation, &[felt!("0x1")]),
+            Err(BlockImportError::InvalidSignature { block_n: 1, block_hash: hash }) if hash == block_hash
+        ));
+
+        // No known key, or signatures are trusted.
+        check(&block, &validation, &[]).unwrap();
+        check(&block, &validation.clone().trust_signatures(true), &[felt!("0x1")]).unwrap();
+    }
+
     mod verify_apply_inner_tests {
         use super::*;
 
@@ -812,4 +1195,154 @@ mod verify_apply_tests {
             );
         }
     }
+
+    mod verify_apply_state_snapshot_tests {
+        use super::*;
+
+        fn snapshot() -> PreValidatedStateSnapshot {
+            let mut header = create_dummy_header();
+            header.block_number = 10;
+            header.global_state_root = felt!("0x738e796f750b21ddb3ce528ca88f7e35fad580768bd58571995b19a6809bb4a");
+            PreValidatedStateSnapshot {
+                header,
+                state_diff: StateDiff {
+                    deployed_contracts: vec![DeployedContractItem { address: felt!("0x1"), class_hash: felt!("0x1") }],
+                    storage_diffs: vec![ContractStorageDiffItem {
+                        address: felt!("0x1"),
+                        storage_entries: vec![StorageEntry { key: felt!("0x1"), value: felt!("0x1") }],
+                    }],
+                    ..Default::default()
+                },
+                converted_classes: vec![],
+            }
+        }
+
+        /// Test that a state snapshot is stored as the latest block, and that its state can be read.
+        #[rstest]
+        #[tokio::test]
+        async fn test_verify_apply_state_snapshot_stores_block(setup_test_backend: Arc<MadaraBackend>) {
+            let backend = setup_test_backend;
+            let validation = create_validation_context(false);
+            let snapshot = snapshot();
+            let block_hash =
+                snapshot.header.compute_hash(validation.chain_id.to_felt(), validation.compute_v0_13_2_hashes);
+
+            let result = verify_apply_state_snapshot_inner(&backend, snapshot, block_hash, validation).unwrap();
+
+            assert_eq!(result.block_hash, block_hash);
+            assert_eq!(backend.get_latest_block_n().unwrap(), Some(10));
+            assert_eq!(backend.get_oldest_backfilled_block_n().unwrap(), Some(10));
+            assert_eq!(backend.get_oldest_state_block_n(), Some(10));
+            assert_eq!(
+                backend.get_contract_storage_at(&BlockId::Number(10), &felt!("0x1"), &felt!("0x1")).unwrap(),
+                Some(felt!("0x1"))
+            );
+            // The snapshot state is not the state diff of the snapshot block.
+            assert_eq!(backend.get_block_state_diff(&BlockId::Number(10)).unwrap(), Some(StateDiff::default()));
+            // The state before the snapshot is not known, even once the blocks before it are backfilled.
+            assert!(matches!(
+                backend.get_contract_storage_at(&BlockId::Number(9), &felt!("0x1"), &felt!("0x1")),
+                Err(MadaraStorageError::StatePruned { block_n: 9, oldest_block_n: 10 })
+            ));
+        }
+
+        /// Test that a state snapshot is rejected when its header does not hash to the trusted block hash, or when its
+        /// state does not match the header.
+        #[rstest]
+        #[case::block_hash_mismatch(snapshot(), Some(felt!("0xdeadbeef")))]
+        #[case::state_root_mismatch(
+            {
+                let mut snapshot = snapshot();
+                snapshot.state_diff.storage_diffs[0].storage_entries[0].value = felt!("0x2");
+                snapshot
+            },
+            None
+        )]
+        #[tokio::test]
+        async fn test_verify_apply_state_snapshot_error_does_not_store_block(
+            #[case] snapshot: PreValidatedStateSnapshot,
+            #[case] block_hash: Option<Felt>,
+            setup_test_backend: Arc<MadaraBackend>,
+        ) {
+            let backend = setup_test_backend;
+            let validation = create_validation_context(false);
+            let block_hash = block_hash.unwrap_or_else(|| {
+                snapshot.header.compute_hash(validation.chain_id.to_felt(), validation.compute_v0_13_2_hashes)
+            });
+
+            let result = verify_apply_state_snapshot_inner(&backend, snapshot, block_hash, validation);
+
+            assert!(matches!(
+                result.unwrap_err(),
+                BlockImportError::BlockHash { .. } | BlockImportError::GlobalStateRoot { .. }
+            ));
+            assert_eq!(backend.get_latest_block_n().unwrap(), None);
+        }
+
+        /// Test that a state snapshot cannot be imported on top of existing blocks.
+        #[rstest]
+        #[tokio::test]
+        async fn test_verify_apply_state_snapshot_non_empty_db(setup_test_backend: Arc<MadaraBackend>) {
+            let backend = setup_test_backend;
+            let mut header = create_dummy_header();
+            header.block_number = 0;
+            backend.store_block(finalized_block_zero(header), finalized_state_diff_zero(), vec![], None, None).unwrap();
+
+            let validation = create_validation_context(false);
+            let snapshot = snapshot();
+            let block_hash =
+                snapshot.header.compute_hash(validation.chain_id.to_felt(), validation.compute_v0_13_2_hashes);
+
+            let result = verify_apply_state_snapshot_inner(&backend, snapshot, block_hash, validation);
+
+            assert!(matches!(result.unwrap_err(), BlockImportError::Internal(_)));
+            assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+        }
+    }
+
+    mod verify_apply_backfill_tests {
+        use super::*;
+
+        /// Hash of [`create_dummy_block`] at block #1, see `test_block_hash`.
+        const DUMMY_BLOCK_HASH: Felt =
+            Felt::from_hex_unchecked("0x271814f105da644661d0ef938cfccfd66d3e3585683fbcbee339db3d29c4574");
+
+        fn setup_oldest_block(backend: &MadaraBackend, parent_block_hash: Felt) {
+            let mut header = create_dummy_header();
+            header.block_number = 2;
+            header.parent_block_hash = parent_block_hash;
+            backend.write_oldest_backfilled_block(2).unwrap();
+            backend.store_block(finalized_block_zero(header), finalized_state_diff_zero(), vec![], None, None).unwrap();
+        }
+
+        /// Test that a block whose hash is the parent hash of the oldest block is stored, without moving the sync tip.
+        #[rstest]
+        #[tokio::test]
+        async fn test_verify_apply_backfill_stores_block(setup_test_backend: Arc<MadaraBackend>) {
+            let backend = setup_test_backend;
+            setup_oldest_block(&backend, DUMMY_BLOCK_HASH);
+
+            let result =
+                verify_apply_backfill_inner(&backend, create_dummy_block(), create_validation_context(false)).unwrap();
+
+            assert_eq!(result.block_hash, DUMMY_BLOCK_HASH);
+            assert_eq!(backend.get_oldest_backfilled_block_n().unwrap(), Some(1));
+            assert_eq!(backend.get_latest_block_n().unwrap(), Some(2));
+            assert_eq!(backend.get_block_hash(&BlockId::Number(1)).unwrap(), Some(DUMMY_BLOCK_HASH));
+        }
+
+        /// Test that a block which does not link to the oldest block is rejected.
+        #[rstest]
+        #[tokio::test]
+        async fn test_verify_apply_backfill_error_does_not_store_block(setup_test_backend: Arc<MadaraBackend>) {
+            let backend = setup_test_backend;
+            setup_oldest_block(&backend, felt!("0xdeadbeef"));
+
+            let result = verify_apply_backfill_inner(&backend, create_dummy_block(), create_validation_context(false));
+
+            assert!(matches!(result.unwrap_err(), BlockImportError::BlockHash { .. }));
+            assert_eq!(backend.get_oldest_backfilled_block_n().unwrap(), Some(2));
+            assert!(backend.get_block_info(&BlockId::Number(1)).unwrap().is_none());
+        }
+    }
 }
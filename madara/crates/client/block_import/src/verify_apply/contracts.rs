@@ -4,7 +4,7 @@ use bitvec::view::AsBits;
 use bonsai_trie::id::BasicId;
 use mc_db::MadaraBackend;
 use mc_db::{bonsai_identifier, MadaraStorageError};
-use mp_block::{BlockId, BlockTag};
+use mp_block::BlockId;
 use mp_state_update::{ContractStorageDiffItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StorageEntry};
 use rayon::prelude::*;
 use starknet_types_core::felt::Felt;
@@ -24,6 +24,7 @@ struct ContractLeaf {
 ///
 /// * `csd`             - Commitment state diff for the current block.
 /// * `block_number`    - The current block number.
+/// * `state_at`        - The block to read the contract fields unchanged by the state diff at.
 ///
 /// # Returns
 ///
@@ -35,6 +36,7 @@ pub fn contract_trie_root(
     nonces: &[NonceUpdate],
     storage_diffs: &[ContractStorageDiffItem],
     block_number: u64,
+    state_at: &BlockId,
 ) -> Result<Felt, MadaraStorageError> {
     let mut contract_leafs: HashMap<Felt, ContractLeaf> = HashMap::new();
 
@@ -77,7 +79,7 @@ pub fn contract_trie_root(
         .map(|(contract_address, mut leaf)| {
             let storage_root = contract_storage_trie.root_hash(&contract_address.to_bytes_be())?;
             leaf.storage_root = Some(storage_root);
-            let leaf_hash = contract_state_leaf_hash(backend, &contract_address, &leaf)?;
+            let leaf_hash = contract_state_leaf_hash(backend, &contract_address, &leaf, state_at)?;
             let bytes = contract_address.to_bytes_be();
             let bv: BitVec<u8, Msb0> = bytes.as_bits()[5..].to_owned();
             Ok((bv, leaf_hash))
@@ -105,6 +107,7 @@ pub fn contract_trie_root(
 /// * `csd`             - Commitment state diff for the current block.
 /// * `contract_address` - The contract address.
 /// * `storage_root`     - The storage root of the contract.
+/// * `state_at`         - The block to read the fields unchanged by the state diff at.
 ///
 /// # Returns
 ///
@@ -113,13 +116,13 @@ fn contract_state_leaf_hash(
     backend: &MadaraBackend,
     contract_address: &Felt,
     contract_leaf: &ContractLeaf,
+    state_at: &BlockId,
 ) -> Result<Felt, MadaraStorageError> {
-    let nonce = contract_leaf.nonce.unwrap_or(
-        backend.get_contract_nonce_at(&BlockId::Tag(BlockTag::Latest), contract_address)?.unwrap_or(Felt::ZERO),
-    );
+    let nonce =
+        contract_leaf.nonce.unwrap_or(backend.get_contract_nonce_at(state_at, contract_address)?.unwrap_or(Felt::ZERO));
 
     let class_hash = contract_leaf.class_hash.unwrap_or(
-        backend.get_contract_class_hash_at(&BlockId::Tag(BlockTag::Latest), contract_address)?.unwrap_or(Felt::ZERO), // .ok_or(MadaraStorageError::InconsistentStorage("Class hash not found".into()))?
+        backend.get_contract_class_hash_at(state_at, contract_address)?.unwrap_or(Felt::ZERO), // .ok_or(MadaraStorageError::InconsistentStorage("Class hash not found".into()))?
     );
 
     let storage_root = contract_leaf
@@ -134,6 +137,7 @@ fn contract_state_leaf_hash(
 mod contract_trie_root_tests {
     use super::*;
     use crate::verify_apply::verify_apply_tests::setup_test_backend;
+    use mp_block::BlockTag;
     use rstest::*;
     use std::sync::Arc;
 
@@ -172,9 +176,16 @@ mod contract_trie_root_tests {
         let block_number = 1;
 
         // Call the function and print the result
-        let result =
-            contract_trie_root(&backend, &deployed_contracts, &replaced_classes, &nonces, &storage_diffs, block_number)
-                .unwrap();
+        let result = contract_trie_root(
+            &backend,
+            &deployed_contracts,
+            &replaced_classes,
+            &nonces,
+            &storage_diffs,
+            block_number,
+            &BlockId::Tag(BlockTag::Latest),
+        )
+        .unwrap();
 
         assert_eq!(
             result,
@@ -200,7 +211,9 @@ mod contract_trie_root_tests {
         };
 
         // Call the function and print the result
-        let result = contract_state_leaf_hash(&backend, &contract_address, &contract_leaf).unwrap();
+        let result =
+            contract_state_leaf_hash(&backend, &contract_address, &contract_leaf, &BlockId::Tag(BlockTag::Latest))
+                .unwrap();
         assert_eq!(
             result,
             Felt::from_hex_unchecked("0x6bbd8d4b5692148f83c38e19091f64381b5239e2a73f53b59be3ec3efb41143")
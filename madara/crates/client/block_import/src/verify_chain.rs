@@ -0,0 +1,147 @@
+//! Verification of the blocks already stored in the database, for example to audit a copied database.
+//!
+//! The stored blocks go through the checks of the [`crate::pre_validate`] and [`crate::verify_apply`] steps, without
+//! writing anything: the transaction hashes, the transaction, event, receipt and state diff commitments, and the block
+//! hash, which commits to the global state root. Computing the global state roots again requires applying the state
+//! diffs to the tries, so the root of the tries is only checked against the block they are at.
+
+use crate::pre_validate::{block_commitments, check_declared_classes};
+use crate::verify_apply::{block_hash, make_db_error};
+use crate::{
+    BlockImportError, BlockValidationContext, PreValidatedBlock, UnverifiedCommitments, UnverifiedFullBlock,
+    UnverifiedHeader,
+};
+use mc_db::MadaraBackend;
+use mp_block::{BlockId, MadaraBlock};
+
+/// Outcome of [`verify_stored_block`] for a block which did not fail verification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoredBlockVerification {
+    /// The commitments and the block hash of the block verify.
+    Verified,
+    /// The transactions of the block are not stored, as it was imported from a state snapshot. Only the parent hash
+    /// was checked.
+    StateSnapshot,
+}
+
+/// Verifies a block stored in the database, read with [`MadaraBackend::iter_blocks`], see the
+/// [module documentation](self). The parent hash is also checked against the stored parent block, when there is one.
+pub fn verify_stored_block(
+    backend: &MadaraBackend,
+    block: MadaraBlock,
+    validation: &BlockValidationContext,
+) -> Result<StoredBlockVerification, BlockImportError> {
+    let MadaraBlock { info, inner } = block;
+    let header = info.header;
+    let block_n = header.block_number;
+    let block_id = BlockId::Number(block_n);
+    let missing = |what: &str| BlockImportError::Internal(format!("Missing {what} of block #{block_n}").into());
+
+    if let Some(parent_block_n) = block_n.checked_sub(1) {
+        // The blocks before a state snapshot may not be backfilled yet.
+        let parent_block_hash = backend
+            .get_block_hash(&BlockId::Number(parent_block_n))
+            .map_err(make_db_error("getting parent block hash"))?;
+        if let Some(expected) = parent_block_hash.filter(|expected| *expected != header.parent_block_hash) {
+            return Err(BlockImportError::ParentHash { got: header.parent_block_hash, expected });
+        }
+    }
+
+    if inner.transactions.is_empty() && header.transaction_count > 0 {
+        return Ok(StoredBlockVerification::StateSnapshot);
+    }
+
+    let state_diff = backend
+        .get_block_state_diff(&block_id)
+        .map_err(make_db_error("getting block state diff"))?
+        .ok_or_else(|| missing("state diff"))?;
+    check_declared_classes(&inner.transactions, &inner.receipts, &state_diff, validation)?;
+
+    let unverified = UnverifiedFullBlock {
+        unverified_block_number: Some(block_n),
+        header: UnverifiedHeader {
+            parent_block_hash: Some(header.parent_block_hash),
+            sequencer_address: header.sequencer_address,
+            block_timestamp: header.block_timestamp,
+            protocol_version: header.protocol_version,
+            l1_gas_price: header.l1_gas_price.clone(),
+            l1_da_mode: header.l1_da_mode,
+        },
+        state_diff,
+        transactions: inner.transactions,
+        receipts: inner.receipts,
+        commitments: UnverifiedCommitments {
+            transaction_count: Some(header.transaction_count),
+            transaction_commitment: Some(header.transaction_commitment),
+            event_count: Some(header.event_count),
+            event_commitment: Some(header.event_commitment),
+            state_diff_length: header.state_diff_length,
+            state_diff_commitment: header.state_diff_commitment,
+            receipt_commitment: header.receipt_commitment,
+            global_state_root: Some(header.global_state_root),
+            block_hash: Some(info.block_hash),
+        },
+        ..Default::default()
+    };
+    let commitments = block_commitments(&unverified, validation, None)?;
+
+    let block = PreValidatedBlock {
+        header: unverified.header,
+        transactions: unverified.transactions,
+        state_diff: unverified.state_diff,
+        receipts: unverified.receipts,
+        commitments,
+        converted_classes: vec![],
+        unverified_global_state_root: Some(header.global_state_root),
+        unverified_block_hash: Some(info.block_hash),
+        unverified_block_number: Some(block_n),
+        visited_segments: None,
+        consensus_signature: None,
+    };
+    block_hash(&block, validation, block_n, header.parent_block_hash, header.global_state_root)?;
+
+    if backend.get_global_trie_block_n().map_err(make_db_error("getting global trie block number"))? == Some(block_n) {
+        let got = backend.get_global_trie_state_root().map_err(make_db_error("getting global state root"))?;
+        if got != header.global_state_root {
+            return Err(BlockImportError::GlobalStateRoot { got, expected: header.global_state_root });
+        }
+    }
+
+    Ok(StoredBlockVerification::Verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::block_import_utils::create_validation_context;
+    use crate::BlockImporter;
+    use mc_db::tests::common::{finalized_block_one, finalized_state_diff_one};
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_verify_stored_block() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let block_importer = BlockImporter::new(Arc::clone(&backend), None).unwrap();
+        let validation = create_validation_context(false);
+
+        let stored_block = |block_n: u64| backend.iter_blocks(block_n..=block_n).next().unwrap().unwrap();
+
+        block_importer.add_block(UnverifiedFullBlock::default(), validation.clone()).await.unwrap();
+        assert_eq!(
+            verify_stored_block(&backend, stored_block(0), &validation).unwrap(),
+            StoredBlockVerification::Verified
+        );
+        assert_eq!(
+            block_importer.verify_stored_block(stored_block(0), validation.clone()).await.unwrap(),
+            StoredBlockVerification::Verified
+        );
+
+        // This block does not point to the stored genesis block.
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        assert!(matches!(
+            verify_stored_block(&backend, stored_block(1), &validation),
+            Err(BlockImportError::ParentHash { .. })
+        ));
+    }
+}
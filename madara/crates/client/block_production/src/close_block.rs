@@ -1,12 +1,14 @@
 use mc_block_import::{
-    BlockImportError, BlockImportResult, BlockImporter, BlockValidationContext, UnverifiedFullBlock, UnverifiedHeader,
+    commitments::CommitmentScheme, global_spawn_rayon_task, BlockImportError, BlockImportResult, BlockImporter,
+    BlockValidationContext, UnverifiedFullBlock, UnverifiedHeader,
 };
 use mp_block::{header::PendingHeader, MadaraPendingBlock, MadaraPendingBlockInfo, VisitedSegments};
 use mp_class::ConvertedClass;
 use mp_state_update::StateDiff;
 use starknet_api::core::ChainId;
 
-/// Close the block (convert from pending to closed), and store to db. This is delegated to the block import module.
+/// Close the block (convert from pending to closed), and store to db. The block hash and the storage are delegated to the
+/// block import module.
 #[tracing::instrument(skip(importer, state_diff, declared_classes), fields(module = "BlockProductionTask"))]
 pub async fn close_block(
     importer: &BlockImporter,
@@ -17,7 +19,11 @@ pub async fn close_block(
     declared_classes: Vec<ConvertedClass>,
     visited_segments: VisitedSegments,
 ) -> Result<BlockImportResult, BlockImportError> {
-    let validation = BlockValidationContext::new(chain_id).trust_transaction_hashes(true);
+    // The blocks produced by the node are not signed, and their commitments are computed below.
+    let validation = BlockValidationContext::new(chain_id)
+        .trust_transaction_hashes(true)
+        .trust_signatures(true)
+        .trust_commitments(true);
 
     let MadaraPendingBlock { info, inner } = block;
     let MadaraPendingBlockInfo { header, tx_hashes: _tx_hashes } = info;
@@ -32,6 +38,17 @@ pub async fn close_block(
         l1_da_mode,
     } = header;
 
+    // Commit to the block with the commitment scheme of its protocol version.
+    let (transactions, receipts, state_diff, commitments) = global_spawn_rayon_task({
+        let (transactions, receipts, state_diff) = (inner.transactions, inner.receipts, state_diff.clone());
+        move || {
+            let commitments =
+                CommitmentScheme::block_commitments(protocol_version, &transactions, &receipts, &state_diff);
+            (transactions, receipts, state_diff, commitments)
+        }
+    })
+    .await;
+
     let block = importer
         .pre_validate(
             UnverifiedFullBlock {
@@ -44,11 +61,11 @@ pub async fn close_block(
                     l1_gas_price,
                     l1_da_mode,
                 },
-                state_diff: state_diff.clone(),
-                transactions: inner.transactions,
-                receipts: inner.receipts,
+                state_diff,
+                transactions,
+                receipts,
                 trusted_converted_classes: declared_classes,
-                commitments: Default::default(), // the block importer will compute the commitments for us
+                commitments: commitments.into(),
                 visited_segments: Some(visited_segments),
                 ..Default::default()
             },
@@ -0,0 +1,103 @@
+//! Dry runs of the block production.
+//!
+//! A dry run executes the transactions the next pending tick would take from the mempool, on top of the current
+//! pending block, without taking them from the mempool and without storing or importing anything. This lets operators
+//! see what the candidate block would look like, and why some transactions are not being included.
+
+use crate::finalize_execution_state::finalize_execution_state;
+use crate::Error;
+use blockifier::blockifier::transaction_executor::BLOCK_STATE_ACCESS_ERR;
+use blockifier::bouncer::BouncerWeights;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mc_exec::ExecutionContext;
+use mc_mempool::MempoolProvider;
+use mp_convert::ToFelt;
+use mp_receipt::{from_blockifier_execution_info, TransactionReceipt};
+use mp_state_update::StateDiff;
+use starknet_types_core::felt::Felt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum DryRunOutcome {
+    /// The transaction would be included in the block. Reverted transactions are included too.
+    Included(TransactionReceipt),
+    /// The transaction failed with an error which cannot be reverted, and would be dropped from the mempool.
+    Rejected(String),
+    /// The block would be full before reaching this transaction, which would stay in the mempool.
+    NotExecuted,
+}
+
+#[derive(Debug, Clone)]
+pub struct DryRunTransaction {
+    pub tx_hash: Felt,
+    pub contract_address: Felt,
+    pub nonce: Felt,
+    pub outcome: DryRunOutcome,
+}
+
+/// The candidate block of a dry run: the transactions the next pending tick would add to the pending block.
+#[derive(Debug, Clone)]
+pub struct BlockDryRun {
+    pub block_n: u64,
+    pub parent_block_hash: Felt,
+    pub transactions: Vec<DryRunTransaction>,
+    /// State diff of the executed transactions, on top of the pending block.
+    pub state_diff: StateDiff,
+    /// Resources used by the executed transactions, on top of the pending block.
+    pub bouncer_weights: BouncerWeights,
+    /// Whether the block would reach its resource limits.
+    pub block_full: bool,
+}
+
+/// Executes the next `max_txs` transactions of the mempool on top of the pending block, without taking them from the
+/// mempool.
+pub fn build_block_dry_run<Mempool: MempoolProvider>(
+    backend: &Arc<MadaraBackend>,
+    mempool: &Mempool,
+    max_txs: usize,
+) -> Result<BlockDryRun, Error> {
+    let pending_info = backend
+        .get_block_info(&DbBlockId::Pending)?
+        .ok_or_else(|| Error::Unexpected("No pending block info".into()))?;
+    let block_n = backend.get_latest_block_n()?.map(|n| n + 1).unwrap_or(0);
+    let parent_block_hash = pending_info.as_pending().map(|info| info.header.parent_block_hash).unwrap_or_default();
+
+    let mut executor = ExecutionContext::new_at_block_end(Arc::clone(backend), &pending_info)?.tx_executor();
+    // The weights of the transactions already in the pending block are not accounted for by this fresh executor.
+    let pending_weights = backend.get_pending_block_bouncer_weights()?.unwrap_or_default();
+    executor.bouncer.bouncer_config.block_max_capacity =
+        backend.chain_config().bouncer_config.block_max_capacity.checked_sub(pending_weights).unwrap_or_default();
+
+    let mempool_txs = mempool.txs_peek_chunk(max_txs);
+    let blockifier_txs: Vec<_> = mempool_txs.iter().map(|tx| tx.clone_tx()).collect();
+    // When the bouncer cap is reached, blockifier returns fewer results than what we asked for.
+    let results = executor.execute_txs(&blockifier_txs);
+    let block_full = results.len() < blockifier_txs.len();
+
+    let mut results = results.into_iter();
+    let transactions = mempool_txs
+        .iter()
+        .map(|mempool_tx| {
+            let outcome = match results.next() {
+                Some(Ok(execution_info)) => {
+                    DryRunOutcome::Included(from_blockifier_execution_info(&execution_info, &mempool_tx.clone_tx()))
+                }
+                Some(Err(err)) => DryRunOutcome::Rejected(format!("{err:#}")),
+                None => DryRunOutcome::NotExecuted,
+            };
+            DryRunTransaction {
+                tx_hash: mempool_tx.tx_hash().to_felt(),
+                contract_address: mempool_tx.contract_address().to_felt(),
+                nonce: mempool_tx.nonce.to_felt(),
+                outcome,
+            }
+        })
+        .collect();
+
+    let on_top_of = executor.block_state.as_ref().expect(BLOCK_STATE_ACCESS_ERR).state.on_top_of_block_id;
+    let (state_diff, _visited_segments, bouncer_weights) =
+        finalize_execution_state(&mut executor, backend, &on_top_of)?;
+
+    Ok(BlockDryRun { block_n, parent_block_hash, transactions, state_diff, bouncer_weights, block_full })
+}
@@ -26,6 +26,7 @@ use mc_db::db_block_id::DbBlockId;
 use mc_db::{MadaraBackend, MadaraStorageError};
 use mc_exec::{BlockifierStateAdapter, ExecutionContext};
 use mc_mempool::header::make_pending_header;
+use mc_mempool::paymaster::PaymasterPolicy;
 use mc_mempool::{L1DataProvider, MempoolProvider};
 use mp_block::{BlockId, BlockTag, MadaraPendingBlock, VisitedSegments};
 use mp_class::compile::ClassCompilationError;
@@ -44,8 +45,10 @@ use std::sync::Arc;
 use std::time::Instant;
 
 mod close_block;
+pub mod dry_run;
 mod finalize_execution_state;
 pub mod metrics;
+pub mod warmup;
 
 #[derive(Default, Clone)]
 struct ContinueBlockStats {
@@ -116,6 +119,7 @@ pub struct BlockProductionTask<Mempool: MempoolProvider> {
     l1_data_provider: Arc<dyn L1DataProvider>,
     current_pending_tick: usize,
     metrics: Arc<BlockProductionMetrics>,
+    paymaster_policy: Arc<dyn PaymasterPolicy>,
 }
 
 impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
@@ -236,19 +240,19 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
             .get_block_hash(&BlockId::Tag(BlockTag::Latest))?
             .unwrap_or(/* genesis block's parent hash */ Felt::ZERO);
 
-        let pending_block = MadaraPendingBlock::new_empty(make_pending_header(
-            parent_block_hash,
-            backend.chain_config(),
-            l1_data_provider.as_ref(),
-        ));
+        let pending_block =
+            MadaraPendingBlock::new_empty(make_pending_header(parent_block_hash, &backend, l1_data_provider.as_ref()));
 
         let executor = ExecutionContext::new_at_block_start(Arc::clone(&backend), &pending_block.info.clone().into())?
             .tx_executor();
 
+        let paymaster_policy = mempool.paymaster_policy();
+
         Ok(Self {
             importer,
             backend,
             mempool,
+            paymaster_policy,
             executor,
             current_pending_tick: 0,
             block: pending_block,
@@ -318,11 +322,13 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
                             self.declared_classes.push(class);
                         }
 
-                        self.block
-                            .inner
-                            .receipts
-                            .push(from_blockifier_execution_info(&execution_info, &mempool_tx.clone_tx()));
+                        let receipt = from_blockifier_execution_info(&execution_info, &mempool_tx.clone_tx());
                         let converted_tx = TransactionWithHash::from(mempool_tx.clone_tx());
+                        if converted_tx.transaction.is_account() {
+                            self.paymaster_policy.on_executed(&converted_tx, &receipt);
+                        }
+
+                        self.block.inner.receipts.push(receipt);
                         self.block.info.tx_hashes.push(converted_tx.hash);
                         self.block.inner.transactions.push(converted_tx.transaction);
                     }
@@ -377,15 +383,10 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         start_time: Instant,
     ) -> Result<(), Error> {
         let block_n = self.block_n();
-        // Convert the pending block to a closed block and save to db
-        let parent_block_hash = Felt::ZERO; // temp parent block hash
-        let new_empty_block = MadaraPendingBlock::new_empty(make_pending_header(
-            parent_block_hash,
-            self.backend.chain_config(),
-            self.l1_data_provider.as_ref(),
-        ));
-
-        let block_to_close = mem::replace(&mut self.block, new_empty_block);
+        // Convert the pending block to a closed block and save to db. The header of the new pending block is made
+        // once this one is imported.
+        let block_to_close = mem::replace(&mut self.block, MadaraPendingBlock::new_empty(Default::default()));
+        let block_timestamp = block_to_close.info.header.block_timestamp;
         let declared_classes = mem::take(&mut self.declared_classes);
 
         let n_txs = block_to_close.inner.transactions.len();
@@ -411,8 +412,10 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         // Flush changes to disk
         self.backend.flush().map_err(|err| BlockImportError::Internal(format!("DB flushing error: {err:#}").into()))?;
 
-        // Update parent hash for new pending block
-        self.block.info.header.parent_block_hash = import_result.block_hash;
+        // Make the header of the new pending block
+        self.backend.on_devnet_block_closed(block_timestamp);
+        self.block.info.header =
+            make_pending_header(import_result.block_hash, &self.backend, self.l1_data_provider.as_ref());
 
         // Prepare executor for next block
         self.executor =
@@ -552,6 +555,31 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         self.close_and_prepare_next_block(new_state_diff, visited_segments, start_time).await
     }
 
+    /// Applies a change of the devnet control: closes the blocks requested by `madara_mineBlocks` right away, or makes
+    /// the pending block use the new timestamp and gas prices when it does not contain any transaction yet. Returns
+    /// whether blocks were closed.
+    #[tracing::instrument(skip(self), fields(module = "BlockProductionTask"))]
+    pub async fn on_devnet_control_changed(&mut self) -> Result<bool, Error> {
+        let n_blocks = self.backend.take_blocks_to_mine();
+        if n_blocks > 0 {
+            tracing::info!("⛏️  Mining {n_blocks} block(s) on request");
+            for _ in 0..n_blocks {
+                self.on_block_time().await?;
+            }
+            return Ok(true);
+        }
+
+        if self.block.inner.transactions.is_empty() && self.declared_classes.is_empty() {
+            let parent_block_hash = self.block.info.header.parent_block_hash;
+            self.block.info.header =
+                make_pending_header(parent_block_hash, &self.backend, self.l1_data_provider.as_ref());
+            self.executor =
+                ExecutionContext::new_at_block_start(Arc::clone(&self.backend), &self.block.info.clone().into())?
+                    .tx_executor();
+        }
+        Ok(false)
+    }
+
     #[tracing::instrument(skip(self, ctx), fields(module = "BlockProductionTask"))]
     pub async fn block_production_task(mut self, mut ctx: ServiceContext) -> Result<(), anyhow::Error> {
         let start = tokio::time::Instant::now();
@@ -562,6 +590,7 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         let mut interval_pending_block_update =
             tokio::time::interval_at(start, self.backend.chain_config().pending_block_update_time);
         interval_pending_block_update.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut devnet_control = self.backend.subscribe_devnet_control();
 
         self.backend.chain_config().precheck_block_production()?; // check chain config for invalid config
 
@@ -611,6 +640,19 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
                         }
                     }
                 },
+                Ok(()) = devnet_control.changed() => {
+                    match self.on_devnet_control_changed().await {
+                        Ok(true) => {
+                            let instant = tokio::time::Instant::now();
+                            interval_pending_block_update.reset_at(instant + interval_pending_block_update.period());
+                            interval_block_time.reset_at(instant + interval_block_time.period());
+                        }
+                        Ok(false) => {}
+                        Err(err) => {
+                            tracing::error!("Devnet control update has errored: {err:#}");
+                        }
+                    }
+                },
                 _ = ctx.cancelled() => break,
             }
         }
@@ -636,13 +678,14 @@ mod tests {
     use mc_block_import::{BlockImporter, BlockValidationContext};
     use mc_db::{db_block_id::DbBlockId, MadaraBackend};
     use mc_devnet::{Call, ChainGenesisDescription, DevnetKeys, DevnetPredeployedContract, Multicall, Selector};
-    use mc_mempool::{Mempool, MempoolLimits, MempoolProvider, MockL1DataProvider};
+    use mc_mempool::{paymaster::PaymasterPolicy, Mempool, MempoolLimits, MempoolProvider, MockL1DataProvider};
     use mp_block::{
         header::{GasPrices, L1DataAvailabilityMode},
         MadaraPendingBlock, VisitedSegments,
     };
     use mp_chain_config::ChainConfig;
     use mp_convert::ToFelt;
+    use mp_receipt::TransactionReceipt;
     use mp_rpc::{
         BroadcastedDeclareTxn, BroadcastedDeclareTxnV3, BroadcastedInvokeTxn, BroadcastedTxn, DaMode, InvokeTxnV3,
         ResourceBounds, ResourceBoundsMapping,
@@ -651,7 +694,7 @@ mod tests {
         ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff,
         StorageEntry,
     };
-    use mp_transactions::{BroadcastedTransactionExt, Transaction};
+    use mp_transactions::{BroadcastedTransactionExt, Transaction, TransactionWithHash};
     use starknet_api::{
         class_hash, contract_address,
         core::{ClassHash, ContractAddress, PatriciaKey},
@@ -695,7 +738,7 @@ mod tests {
         Arc<Mempool>,
         DevnetKeys,
     ) {
-        let mut genesis = ChainGenesisDescription::base_config().unwrap();
+        let mut genesis = ChainGenesisDescription::base_config(mp_chain_config::UDC_CONTRACT_ADDRESS).unwrap();
         let contracts = genesis.add_devnet_contracts(10).unwrap();
 
         let chain_config: Arc<ChainConfig> = if use_bouncer_weights {
@@ -1958,6 +2001,153 @@ mod tests {
         assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 0);
     }
 
+    // This test makes sure that a dry run executes the transactions of the
+    // next pending tick, without taking them from the mempool or storing
+    // anything in the database
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn test_block_prod_dry_run(
+        #[future] devnet_setup: (
+            Arc<MadaraBackend>,
+            Arc<mc_block_import::BlockImporter>,
+            Arc<BlockProductionMetrics>,
+            Arc<MockL1DataProvider>,
+            Arc<Mempool>,
+            DevnetKeys,
+        ),
+    ) {
+        let (backend, importer, metrics, l1_data_provider, mempool, contracts) = devnet_setup.await;
+
+        sign_and_add_declare_tx(&contracts.0[0], &backend, &mempool, Felt::ZERO);
+
+        let mut block_production_task =
+            BlockProductionTask::new(Arc::clone(&backend), importer, Arc::clone(&mempool), metrics, l1_data_provider)
+                .await
+                .unwrap();
+
+        let dry_run = crate::dry_run::build_block_dry_run(&backend, mempool.as_ref(), 10).unwrap();
+
+        assert_eq!(dry_run.block_n, 1);
+        assert!(!dry_run.block_full);
+        assert_eq!(dry_run.transactions.len(), 1);
+        assert_matches::assert_matches!(dry_run.transactions[0].outcome, crate::dry_run::DryRunOutcome::Included(_));
+        assert!(!dry_run.state_diff.declared_classes.is_empty());
+
+        // Nothing was taken from the mempool or stored.
+        assert!(!mempool.is_empty());
+        let pending_block: mp_block::MadaraMaybePendingBlock = backend.get_block(&DbBlockId::Pending).unwrap().unwrap();
+        assert_eq!(pending_block.inner.transactions.len(), 0);
+
+        // The next pending tick includes the transaction of the dry run.
+        block_production_task.set_current_pending_tick(1);
+        block_production_task.on_pending_time_tick().await.unwrap();
+
+        let pending_block: mp_block::MadaraMaybePendingBlock = backend.get_block(&DbBlockId::Pending).unwrap().unwrap();
+        assert!(mempool.is_empty());
+        assert_eq!(pending_block.info.tx_hashes(), [dry_run.transactions[0].tx_hash]);
+    }
+
+    // This test makes sure that the paymaster policy is notified of the
+    // fee charged to an executed transaction, and leaves the state untouched
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn test_block_prod_paymaster_policy_notified(
+        #[future] devnet_setup: (
+            Arc<MadaraBackend>,
+            Arc<mc_block_import::BlockImporter>,
+            Arc<BlockProductionMetrics>,
+            Arc<MockL1DataProvider>,
+            Arc<Mempool>,
+            DevnetKeys,
+        ),
+    ) {
+        #[derive(Default)]
+        struct RecordFees {
+            executed: std::sync::Mutex<Vec<(Felt, Felt)>>,
+        }
+        impl PaymasterPolicy for RecordFees {
+            fn on_executed(&self, tx: &TransactionWithHash, receipt: &TransactionReceipt) {
+                self.executed.lock().unwrap().push((tx.hash, receipt.actual_fee().amount));
+            }
+        }
+
+        let (backend, importer, metrics, l1_data_provider, _, contracts) = devnet_setup.await;
+        let (sender, receiver) = (&contracts.0[0], &contracts.0[1]);
+
+        let policy = Arc::new(RecordFees::default());
+        let mempool = Arc::new(
+            Mempool::new(Arc::clone(&backend), l1_data_provider.clone(), MempoolLimits::for_testing())
+                .with_paymaster_policy(policy.clone()),
+        );
+
+        let sender_before = mc_devnet::get_fee_tokens_balance(&backend, sender.address).unwrap();
+        sign_and_add_invoke_tx(sender, receiver, &backend, &mempool, Felt::ZERO);
+
+        let mut block_production_task =
+            BlockProductionTask::new(Arc::clone(&backend), importer, Arc::clone(&mempool), metrics, l1_data_provider)
+                .await
+                .unwrap();
+        block_production_task.set_current_pending_tick(1);
+        block_production_task.on_pending_time_tick().await.unwrap();
+
+        let pending_block: mp_block::MadaraMaybePendingBlock = backend.get_block(&DbBlockId::Pending).unwrap().unwrap();
+        assert_eq!(pending_block.inner.receipts.len(), 1);
+        let fee = pending_block.inner.receipts[0].actual_fee().amount;
+        assert_ne!(fee, Felt::ZERO);
+        assert_eq!(*policy.executed.lock().unwrap(), [(pending_block.info.tx_hashes()[0], fee)]);
+
+        // The transaction transfers STRK, and the sender pays its fee in STRK.
+        let amount = Felt::from(9_999u128 * 1_000_000_000_000_000_000);
+        let sender_after = mc_devnet::get_fee_tokens_balance(&backend, sender.address).unwrap();
+        assert_eq!(sender_after.fri, sender_before.fri - amount - fee);
+    }
+
+    // This test makes sure that the warm-up preloads the classes and
+    // states of the contracts used in the latest blocks
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn test_block_prod_warm_up_execution_caches(
+        #[future] devnet_setup: (
+            Arc<MadaraBackend>,
+            Arc<mc_block_import::BlockImporter>,
+            Arc<BlockProductionMetrics>,
+            Arc<MockL1DataProvider>,
+            Arc<Mempool>,
+            DevnetKeys,
+        ),
+    ) {
+        let (backend, importer, metrics, l1_data_provider, mempool, contracts) = devnet_setup.await;
+
+        sign_and_add_declare_tx(&contracts.0[0], &backend, &mempool, Felt::ZERO);
+
+        let mut block_production_task =
+            BlockProductionTask::new(Arc::clone(&backend), importer, Arc::clone(&mempool), metrics, l1_data_provider)
+                .await
+                .unwrap();
+        block_production_task.on_block_time().await.unwrap();
+        assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 1);
+
+        let stats = crate::warmup::warm_up_execution_caches(
+            &backend,
+            crate::warmup::WarmupConfig { n_blocks: 10, n_contracts: 64 },
+        )
+        .unwrap();
+
+        // The declaring account and the fee token are used in block 1.
+        assert!(stats.contracts >= 2);
+        assert!(stats.classes >= 2);
+        assert!(stats.storage_entries > 0);
+        assert!(mc_exec::class_cache::cached_classes() >= 2);
+
+        // A disabled warm-up does nothing.
+        let stats = crate::warmup::warm_up_execution_caches(
+            &backend,
+            crate::warmup::WarmupConfig { n_blocks: 10, n_contracts: 0 },
+        )
+        .unwrap();
+        assert_eq!(stats, crate::warmup::WarmupStats::default());
+    }
+
     // This test makes sure that the pending tick updates the correct
     // pending block if a new pending block is added to the database
     #[rstest::rstest]
@@ -2255,6 +2445,49 @@ mod tests {
         assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 1);
     }
 
+    // This test makes sure that the devnet control moves the chain
+    // through time and closes the requested blocks right away
+    #[rstest::rstest]
+    #[tokio::test]
+    async fn test_block_prod_on_devnet_control_changed_mines_blocks(
+        #[future] devnet_setup: (
+            Arc<MadaraBackend>,
+            Arc<mc_block_import::BlockImporter>,
+            Arc<BlockProductionMetrics>,
+            Arc<MockL1DataProvider>,
+            Arc<Mempool>,
+            DevnetKeys,
+        ),
+    ) {
+        let (backend, importer, metrics, l1_data_provider, mempool, _contracts) = devnet_setup.await;
+        backend.enable_devnet_control();
+
+        let mut block_production_task =
+            BlockProductionTask::new(Arc::clone(&backend), importer, Arc::clone(&mempool), metrics, l1_data_provider)
+                .await
+                .unwrap();
+        assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 0);
+
+        // The pending block is still empty, it takes the new timestamp
+        // without closing any block
+        let in_a_year = mp_block::header::BlockTimestamp::now().0 + 365 * 24 * 3600;
+        backend.set_next_block_timestamp(in_a_year).unwrap();
+        assert!(!block_production_task.on_devnet_control_changed().await.unwrap());
+        assert_eq!(block_production_task.block.info.header.block_timestamp.0, in_a_year);
+        assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 0);
+
+        backend.request_mine_blocks(3);
+        assert!(block_production_task.on_devnet_control_changed().await.unwrap());
+        assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 3);
+
+        let block_1 = backend.get_block_info(&DbBlockId::Number(1)).unwrap().unwrap();
+        assert_eq!(block_1.as_nonpending().unwrap().header.block_timestamp.0, in_a_year);
+        // The following blocks continue from the requested timestamp
+        let block_3 = backend.get_block_info(&DbBlockId::Number(3)).unwrap().unwrap();
+        assert!(block_3.as_nonpending().unwrap().header.block_timestamp.0 >= in_a_year);
+        assert_eq!(backend.get_devnet_control().next_block_timestamp, None);
+    }
+
     // This test checks that the task fails to close the block
     // if the block it's working on if forcibly change to one
     // that isn't consistent with the previous state
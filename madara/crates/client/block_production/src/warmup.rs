@@ -0,0 +1,90 @@
+//! Warm-up of the execution caches on sequencer startup.
+//!
+//! Right after a restart, the first transactions executed by the sequencer pay for converting the classes they use and
+//! for reading the contract states from cold database pages, which makes the first blocks much slower than the next
+//! ones. The warm-up runs before the mempool and the gateway accept transactions: the contracts used the most in the
+//! latest blocks have their classes converted into the [`mc_exec::class_cache`], and their nonces and the storage
+//! recently written to are read from the database.
+
+use crate::Error;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mc_exec::class_cache;
+use mp_transactions::Transaction;
+use starknet_types_core::felt::Felt;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WarmupConfig {
+    /// Number of latest blocks the contract usage is counted over.
+    pub n_blocks: u64,
+    /// Number of contracts, by usage, to warm up.
+    pub n_contracts: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WarmupStats {
+    pub classes: usize,
+    pub contracts: usize,
+    pub storage_entries: usize,
+}
+
+/// Preloads the classes and states of the contracts used the most in the latest blocks.
+pub fn warm_up_execution_caches(backend: &MadaraBackend, config: WarmupConfig) -> Result<WarmupStats, Error> {
+    let mut stats = WarmupStats::default();
+    let Some(latest_block_n) = backend.get_latest_block_n()? else {
+        return Ok(stats);
+    };
+    if config.n_blocks == 0 || config.n_contracts == 0 {
+        return Ok(stats);
+    }
+    let from_block_n = latest_block_n.saturating_sub(config.n_blocks - 1);
+
+    let mut usage: HashMap<Felt, usize> = HashMap::new();
+    let mut written_keys: HashMap<Felt, HashSet<Felt>> = HashMap::new();
+    for block_n in from_block_n..=latest_block_n {
+        if let Some(inner) = backend.get_block_inner(&DbBlockId::Number(block_n))? {
+            let senders = inner.transactions.iter().filter_map(|tx| match tx {
+                Transaction::Invoke(tx) => Some(*tx.sender_address()),
+                Transaction::Declare(tx) => Some(*tx.sender_address()),
+                Transaction::L1Handler(tx) => Some(tx.contract_address),
+                Transaction::Deploy(_) | Transaction::DeployAccount(_) => None,
+            });
+            let receipts = inner.receipts.iter().flat_map(|receipt| {
+                receipt.contract_address().into_iter().chain(receipt.events().iter().map(|event| event.from_address))
+            });
+            for address in senders.chain(receipts) {
+                *usage.entry(address).or_default() += 1;
+            }
+        }
+        if let Some(state_diff) = backend.get_block_state_diff(&DbBlockId::Number(block_n))? {
+            for item in state_diff.storage_diffs {
+                written_keys.entry(item.address).or_default().extend(item.storage_entries.into_iter().map(|e| e.key));
+            }
+        }
+    }
+
+    let mut contracts: Vec<_> = usage.into_iter().collect();
+    // Ties are broken by address so that the warm-up is deterministic.
+    contracts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    contracts.truncate(config.n_contracts);
+
+    let block_id = DbBlockId::Number(latest_block_n);
+    let mut classes = HashSet::new();
+    for (address, _) in contracts {
+        let Some(class_hash) = backend.get_contract_class_hash_at(&block_id, &address)? else {
+            continue;
+        };
+        stats.contracts += 1;
+        backend.get_contract_nonce_at(&block_id, &address)?;
+        for key in written_keys.get(&address).into_iter().flatten() {
+            backend.get_contract_storage_at(&block_id, &address, key)?;
+            stats.storage_entries += 1;
+        }
+        if classes.insert(class_hash) && class_cache::preload(backend, &block_id, &class_hash)? {
+            stats.classes += 1;
+        }
+    }
+
+    Ok(stats)
+}
@@ -0,0 +1,254 @@
+//! Index of the activity of each address, for block explorers.
+//!
+//! Every block adds an entry to the index of an address for each transaction sent by it, each event it emitted and
+//! each write to its storage. The entries of an address are sorted by block, so that its activity can be read as a
+//! single timeline without going through every block of the chain. Entries only point into the blocks and state diffs:
+//! the data itself is read from there.
+//!
+//! Only the blocks imported since the index was introduced are indexed.
+
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError, WriteBatchWithTransaction};
+use mp_block::MadaraBlock;
+use mp_state_update::StateDiff;
+use mp_transactions::Transaction;
+use rocksdb::{Direction, IteratorMode};
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+const KEY_LEN: usize = 32 + 8 + 1 + 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AddressActivityKind {
+    /// A transaction sent by the address. Deploy and L1 handler transactions, which have no sender, are indexed under
+    /// the contract they deploy or call.
+    Transaction,
+    /// An event emitted by the address.
+    Event,
+    /// A write to the storage of the address.
+    StorageWrite,
+}
+
+impl AddressActivityKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::Transaction => 0,
+            Self::Event => 1,
+            Self::StorageWrite => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::Transaction),
+            1 => Some(Self::Event),
+            2 => Some(Self::StorageWrite),
+            _ => None,
+        }
+    }
+}
+
+/// An entry of the activity of an address, in timeline order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AddressActivityKey {
+    pub block_n: u64,
+    pub kind: AddressActivityKind,
+    /// Index of the transaction in the block, of the event in the block, or of the storage write in the storage diff
+    /// of the address.
+    pub index: u32,
+}
+
+impl AddressActivityKey {
+    /// The first entry of block `block_n`.
+    pub fn block_start(block_n: u64) -> Self {
+        Self { block_n, kind: AddressActivityKind::Transaction, index: 0 }
+    }
+
+    fn encode(&self, address: &Felt) -> [u8; KEY_LEN] {
+        let mut key = [0u8; KEY_LEN];
+        key[..32].copy_from_slice(&address.to_bytes_be());
+        key[32..40].copy_from_slice(&self.block_n.to_be_bytes());
+        key[40] = self.kind.to_byte();
+        key[41..].copy_from_slice(&self.index.to_be_bytes());
+        key
+    }
+
+    fn decode(key: &[u8]) -> Result<Self> {
+        let malformed = || MadaraStorageError::InconsistentStorage("Malformed address activity key".into());
+        if key.len() != KEY_LEN {
+            return Err(malformed());
+        }
+        Ok(Self {
+            block_n: u64::from_be_bytes(key[32..40].try_into().expect("Checked length")),
+            kind: AddressActivityKind::from_byte(key[40]).ok_or_else(malformed)?,
+            index: u32::from_be_bytes(key[41..].try_into().expect("Checked length")),
+        })
+    }
+}
+
+/// The entries a block adds to the index, by address.
+fn block_activity(block: &MadaraBlock, state_diff: &StateDiff) -> Vec<(Felt, AddressActivityKey)> {
+    let block_n = block.info.header.block_number;
+    let key = |kind, index: usize| AddressActivityKey { block_n, kind, index: index as u32 };
+
+    let transactions =
+        block.inner.transactions.iter().zip(&block.inner.receipts).enumerate().filter_map(|(index, (tx, receipt))| {
+            let address = match tx {
+                Transaction::Invoke(tx) => Some(*tx.sender_address()),
+                Transaction::Declare(tx) => Some(*tx.sender_address()),
+                Transaction::L1Handler(tx) => Some(tx.contract_address),
+                Transaction::Deploy(_) | Transaction::DeployAccount(_) => receipt.contract_address(),
+            }?;
+            Some((address, key(AddressActivityKind::Transaction, index)))
+        });
+    let events = block
+        .inner
+        .receipts
+        .iter()
+        .flat_map(|receipt| receipt.events())
+        .enumerate()
+        .map(|(index, event)| (event.from_address, key(AddressActivityKind::Event, index)));
+    let storage_writes = state_diff.storage_diffs.iter().flat_map(|diff| {
+        (0..diff.storage_entries.len()).map(|index| (diff.address, key(AddressActivityKind::StorageWrite, index)))
+    });
+
+    transactions.chain(events).chain(storage_writes).collect()
+}
+
+impl MadaraBackend {
+    pub(crate) fn address_activity_index_block(
+        &self,
+        tx: &mut WriteBatchWithTransaction,
+        block: &MadaraBlock,
+        state_diff: &StateDiff,
+    ) {
+        let col = self.db.get_column(Column::AddressActivity);
+        for (address, key) in block_activity(block, state_diff) {
+            tx.put_cf(&col, key.encode(&address), []);
+        }
+    }
+
+    pub(crate) fn address_activity_remove_block(
+        &self,
+        tx: &mut WriteBatchWithTransaction,
+        block: &MadaraBlock,
+        state_diff: &StateDiff,
+    ) {
+        let col = self.db.get_column(Column::AddressActivity);
+        for (address, key) in block_activity(block, state_diff) {
+            tx.delete_cf(&col, key.encode(&address));
+        }
+    }
+
+    /// The entries of the activity of `address` from `start` included, oldest first.
+    pub fn iter_address_activity(
+        &self,
+        address: &Felt,
+        start: AddressActivityKey,
+    ) -> impl Iterator<Item = Result<AddressActivityKey>> + '_ {
+        let col = self.db.get_column(Column::AddressActivity);
+        let start = start.encode(address);
+        self.db.iterator_cf_opt(&col, self.read_options(), IteratorMode::From(&start, Direction::Forward)).map_while(
+            move |kv| match kv {
+                Ok((key, _)) if key[..32] == start[..32] => Some(AddressActivityKey::decode(&key)),
+                Ok(_) => None,
+                Err(err) => Some(Err(err.into())),
+            },
+        )
+    }
+
+    /// The entries of the activity of `address` from `start` included, up to the end of block `to_block_n`, oldest
+    /// first. At most `limit` entries are returned.
+    #[tracing::instrument(skip(self), fields(module = "AddressActivityDB"))]
+    pub fn get_address_activity(
+        &self,
+        address: &Felt,
+        start: AddressActivityKey,
+        to_block_n: u64,
+        limit: usize,
+    ) -> Result<Vec<AddressActivityKey>> {
+        self.iter_address_activity(address, start)
+            .take_while(|entry| !entry.as_ref().is_ok_and(|entry| entry.block_n > to_block_n))
+            .take(limit)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_chain_config::ChainConfig;
+    use mp_receipt::{Event, InvokeTransactionReceipt, TransactionReceipt};
+    use mp_state_update::{ContractStorageDiffItem, StorageEntry};
+    use mp_transactions::{InvokeTransaction, InvokeTransactionV1};
+    use std::sync::Arc;
+
+    fn block(block_n: u64, sender: Felt, emitter: Felt) -> (MadaraMaybePendingBlock, StateDiff) {
+        let tx = Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+            sender_address: sender,
+            ..Default::default()
+        }));
+        let event = Event { from_address: emitter, keys: vec![], data: vec![] };
+        let receipt = TransactionReceipt::Invoke(InvokeTransactionReceipt {
+            events: vec![event.clone(), event],
+            ..Default::default()
+        });
+        let block = MadaraMaybePendingBlock {
+            info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                header: Header { block_number: block_n, ..Default::default() },
+                block_hash: Felt::from(block_n),
+                tx_hashes: vec![],
+            }),
+            inner: MadaraBlockInner { transactions: vec![tx], receipts: vec![receipt] },
+        };
+        let state_diff = StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: emitter,
+                storage_entries: vec![StorageEntry { key: Felt::ONE, value: Felt::TWO }],
+            }],
+            ..Default::default()
+        };
+        (block, state_diff)
+    }
+
+    #[test]
+    fn test_address_activity() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let (account, contract) = (Felt::from(0x100), Felt::from(0x200));
+        for block_n in 0..3 {
+            let (block, state_diff) = block(block_n, account, contract);
+            backend.store_block(block, state_diff, vec![], None, None).unwrap();
+        }
+
+        let entry = |block_n, kind, index| AddressActivityKey { block_n, kind, index };
+        assert_eq!(
+            backend.get_address_activity(&account, AddressActivityKey::block_start(0), u64::MAX, 100).unwrap(),
+            (0..3).map(|block_n| entry(block_n, AddressActivityKind::Transaction, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            backend.get_address_activity(&contract, AddressActivityKey::block_start(1), 1, 100).unwrap(),
+            vec![
+                entry(1, AddressActivityKind::Event, 0),
+                entry(1, AddressActivityKind::Event, 1),
+                entry(1, AddressActivityKind::StorageWrite, 0),
+            ]
+        );
+        // Pagination.
+        let start = entry(1, AddressActivityKind::Event, 1);
+        assert_eq!(
+            backend.get_address_activity(&contract, start, u64::MAX, 2).unwrap(),
+            vec![start, entry(1, AddressActivityKind::StorageWrite, 0)]
+        );
+
+        // Reverted blocks are removed from the index.
+        backend.block_db_revert_block(2).unwrap();
+        backend.block_db_revert_block(1).unwrap();
+        assert_eq!(
+            backend.get_address_activity(&account, AddressActivityKey::block_start(0), u64::MAX, 100).unwrap(),
+            vec![entry(0, AddressActivityKind::Transaction, 0)]
+        );
+    }
+}
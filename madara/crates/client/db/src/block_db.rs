@@ -1,18 +1,25 @@
+use crate::chain_head::PipelineStage;
 use crate::db_block_id::{DbBlockId, DbBlockIdResolvable};
+use crate::pruning::ROW_OLDEST_STATE_BLOCK;
+use crate::read_scope::ReadScope;
 use crate::{Column, DatabaseExt, MadaraBackend, WriteBatchWithTransaction};
 use crate::{MadaraStorageError, DB};
 use anyhow::Context;
 use blockifier::bouncer::BouncerWeights;
 use mp_block::header::{GasPrices, PendingHeader};
 use mp_block::{
-    BlockId, BlockTag, MadaraBlock, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock,
+    BlockId, BlockTag, ConsensusSignature, MadaraBlock, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock,
     MadaraMaybePendingBlockInfo, MadaraPendingBlock, MadaraPendingBlockInfo, VisitedSegments,
 };
+use mp_receipt::TransactionReceipt;
 use mp_rpc::EmittedEvent;
 use mp_state_update::StateDiff;
-use rocksdb::WriteOptions;
+use rocksdb::{ReadOptions, WriteOptions};
 use starknet_api::core::ChainId;
 use starknet_types_core::felt::Felt;
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
 
 type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
 
@@ -30,11 +37,19 @@ const ROW_PENDING_BOUNCER_WEIGHTS: &[u8] = b"pending_bouncer_weights";
 const ROW_PENDING_INNER: &[u8] = b"pending";
 const ROW_SYNC_TIP: &[u8] = b"sync_tip";
 const ROW_L1_LAST_CONFIRMED_BLOCK: &[u8] = b"l1_last";
+const ROW_OLDEST_BACKFILLED_BLOCK: &[u8] = b"oldest_backfilled";
+
+/// Number of blocks read at once by [`MadaraBackend::iter_blocks`].
+const ITER_BLOCKS_BATCH_SIZE: u64 = 64;
 
 #[tracing::instrument(skip(db), fields(module = "BlockDB"))]
 pub fn get_latest_block_n(db: &DB) -> Result<Option<u64>> {
+    get_latest_block_n_opt(db, &ReadOptions::default())
+}
+
+fn get_latest_block_n_opt(db: &DB, readopts: &ReadOptions) -> Result<Option<u64>> {
     let col = db.get_column(Column::BlockStorageMeta);
-    let Some(res) = db.get_cf(&col, ROW_SYNC_TIP)? else { return Ok(None) };
+    let Some(res) = db.get_cf_opt(&col, ROW_SYNC_TIP, readopts)? else { return Ok(None) };
     let res = bincode::deserialize(&res)?;
     Ok(Some(res))
 }
@@ -79,7 +94,7 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     fn tx_hash_to_block_n(&self, tx_hash: &Felt) -> Result<Option<u64>> {
         let col = self.db.get_column(Column::TxHashToBlockN);
-        let res = self.db.get_cf(&col, bincode::serialize(tx_hash)?)?;
+        let res = self.db.get_cf_opt(&col, bincode::serialize(tx_hash)?, &self.read_options())?;
         let Some(res) = res else { return Ok(None) };
         let block_n = bincode::deserialize(&res)?;
         Ok(Some(block_n))
@@ -88,7 +103,7 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     fn block_hash_to_block_n(&self, block_hash: &Felt) -> Result<Option<u64>> {
         let col = self.db.get_column(Column::BlockHashToBlockN);
-        let res = self.db.get_cf(&col, bincode::serialize(block_hash)?)?;
+        let res = self.db.get_cf_opt(&col, bincode::serialize(block_hash)?, &self.read_options())?;
         let Some(res) = res else { return Ok(None) };
         let block_n = bincode::deserialize(&res)?;
         Ok(Some(block_n))
@@ -97,16 +112,25 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     fn get_state_update(&self, block_n: u64) -> Result<Option<StateDiff>> {
         let col = self.db.get_column(Column::BlockNToStateDiff);
-        let res = self.db.get_cf(&col, bincode::serialize(&block_n)?)?;
+        let res = self.db.get_cf_opt(&col, bincode::serialize(&block_n)?, &self.read_options())?;
         let Some(res) = res else { return Ok(None) };
         let block = bincode::deserialize(&res)?;
         Ok(Some(block))
     }
 
+    #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
+    fn get_consensus_signature_from_block_n(&self, block_n: u64) -> Result<Option<ConsensusSignature>> {
+        let col = self.db.get_column(Column::ConsensusSignatures);
+        let res = self.db.get_cf_opt(&col, bincode::serialize(&block_n)?, &self.read_options())?;
+        let Some(res) = res else { return Ok(None) };
+        let signature = bincode::deserialize(&res)?;
+        Ok(Some(signature))
+    }
+
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     fn get_block_info_from_block_n(&self, block_n: u64) -> Result<Option<MadaraBlockInfo>> {
         let col = self.db.get_column(Column::BlockNToBlockInfo);
-        let res = self.db.get_cf(&col, bincode::serialize(&block_n)?)?;
+        let res = self.db.get_cf_opt(&col, bincode::serialize(&block_n)?, &self.read_options())?;
         let Some(res) = res else { return Ok(None) };
         let block = bincode::deserialize(&res)?;
         Ok(Some(block))
@@ -115,7 +139,7 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     fn get_block_inner_from_block_n(&self, block_n: u64) -> Result<Option<MadaraBlockInner>> {
         let col = self.db.get_column(Column::BlockNToBlockInner);
-        let res = self.db.get_cf(&col, bincode::serialize(&block_n)?)?;
+        let res = self.db.get_cf_opt(&col, bincode::serialize(&block_n)?, &self.read_options())?;
         let Some(res) = res else { return Ok(None) };
         let block = bincode::deserialize(&res)?;
         Ok(Some(block))
@@ -123,7 +147,7 @@ impl MadaraBackend {
 
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn get_latest_block_n(&self) -> Result<Option<u64>> {
-        get_latest_block_n(&self.db)
+        get_latest_block_n_opt(&self.db, &self.read_options())
     }
 
     // Pending block quirk: We should act as if there is always a pending block in db, to match
@@ -131,71 +155,104 @@ impl MadaraBackend {
 
     fn get_pending_block_info(&self) -> Result<MadaraPendingBlockInfo> {
         let col = self.db.get_column(Column::BlockStorageMeta);
-        let Some(res) = self.db.get_cf(&col, ROW_PENDING_INFO)? else {
+        let Some(res) = self.db.get_cf_opt(&col, ROW_PENDING_INFO, &self.read_options())? else {
+            // See pending block quirk
+            return self.empty_pending_block_info();
+        };
+        let res = bincode::deserialize(&res)?;
+        Ok(res)
+    }
+
+    /// Reads the info and the inner of the pending block from the same database snapshot, the one of the current
+    /// [`ReadScope`] if any.
+    ///
+    /// The pending block is replaced as a whole every time it is updated. Reading its rows separately could return the
+    /// header and transaction hashes of one pending block with the transactions and receipts of the next one.
+    fn get_pending_block_from_db(&self) -> Result<MadaraPendingBlock> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let snapshot = self.db.snapshot();
+        let readopts = if ReadScope::is_active() {
+            self.read_options()
+        } else {
+            let mut readopts = ReadOptions::default();
+            readopts.set_snapshot(&snapshot);
+            readopts
+        };
+        let (Some(info), Some(inner)) = (
+            self.db.get_cf_opt(&col, ROW_PENDING_INFO, &readopts)?,
+            self.db.get_cf_opt(&col, ROW_PENDING_INNER, &readopts)?,
+        ) else {
             // See pending block quirk
+            return Ok(MadaraPendingBlock::new(self.empty_pending_block_info()?, MadaraBlockInner::default()));
+        };
+        Ok(MadaraPendingBlock::new(bincode::deserialize(&info)?, bincode::deserialize(&inner)?))
+    }
 
-            let Some(latest_block_id) = self.get_latest_block_n()? else {
-                // Second quirk: if there is not even a genesis block in db, make up the gas prices and everything else
-                return Ok(MadaraPendingBlockInfo {
-                    header: PendingHeader {
-                        parent_block_hash: Felt::ZERO,
-                        // Sequencer address is ZERO for chains where we don't produce blocks. This means that trying to simulate/trace a transaction on Pending when
-                        // genesis has not been loaded yet will return an error. That probably fine because the ERC20 fee contracts are not even deployed yet - it
-                        // will error somewhere else anyway.
-                        sequencer_address: **self.chain_config().sequencer_address,
-                        block_timestamp: Default::default(), // Junk timestamp: unix epoch
-                        protocol_version: self.chain_config.latest_protocol_version,
-                        l1_gas_price: GasPrices {
-                            eth_l1_gas_price: 1,
-                            strk_l1_gas_price: 1,
-                            eth_l1_data_gas_price: 1,
-                            strk_l1_data_gas_price: 1,
-                        },
-                        l1_da_mode: mp_block::header::L1DataAvailabilityMode::Blob,
-                    },
-                    tx_hashes: vec![],
-                });
-            };
+    /// The pending block, memoized in the current [`ReadScope`] if any.
+    fn get_pending_block(&self) -> Result<Arc<MadaraPendingBlock>> {
+        ReadScope::pending_block(|| Ok(Arc::new(self.get_pending_block_from_db()?)))
+    }
 
-            let latest_block_info =
-                self.get_block_info_from_block_n(latest_block_id)?.ok_or(MadaraStorageError::MissingChainInfo)?;
+    /// Info of the pending block. Inside of a read scope, it is taken from the memoized pending block so that it always
+    /// matches the pending inner.
+    fn pending_block_info(&self) -> Result<MadaraPendingBlockInfo> {
+        if ReadScope::is_active() {
+            return Ok(self.get_pending_block()?.info.clone());
+        }
+        self.get_pending_block_info()
+    }
 
+    /// Info of the empty pending block on top of the latest block, used when there is no pending block in db.
+    fn empty_pending_block_info(&self) -> Result<MadaraPendingBlockInfo> {
+        let Some(latest_block_id) = self.get_latest_block_n()? else {
+            // Second quirk: if there is not even a genesis block in db, make up the gas prices and everything else
             return Ok(MadaraPendingBlockInfo {
                 header: PendingHeader {
-                    parent_block_hash: latest_block_info.block_hash,
-                    sequencer_address: latest_block_info.header.sequencer_address,
-                    block_timestamp: latest_block_info.header.block_timestamp,
-                    protocol_version: latest_block_info.header.protocol_version,
-                    l1_gas_price: latest_block_info.header.l1_gas_price.clone(),
-                    l1_da_mode: latest_block_info.header.l1_da_mode,
+                    parent_block_hash: Felt::ZERO,
+                    // Sequencer address is ZERO for chains where we don't produce blocks. This means that trying to simulate/trace a transaction on Pending when
+                    // genesis has not been loaded yet will return an error. That probably fine because the ERC20 fee contracts are not even deployed yet - it
+                    // will error somewhere else anyway.
+                    sequencer_address: **self.chain_config().sequencer_address,
+                    block_timestamp: Default::default(), // Junk timestamp: unix epoch
+                    protocol_version: self.chain_config.latest_protocol_version,
+                    l1_gas_price: GasPrices {
+                        eth_l1_gas_price: 1,
+                        strk_l1_gas_price: 1,
+                        eth_l1_data_gas_price: 1,
+                        strk_l1_data_gas_price: 1,
+                    },
+                    l1_da_mode: mp_block::header::L1DataAvailabilityMode::Blob,
                 },
                 tx_hashes: vec![],
             });
         };
-        let res = bincode::deserialize(&res)?;
-        Ok(res)
-    }
 
-    fn get_pending_block_inner(&self) -> Result<MadaraBlockInner> {
-        let col = self.db.get_column(Column::BlockStorageMeta);
-        let Some(res) = self.db.get_cf(&col, ROW_PENDING_INNER)? else {
-            // See pending block quirk
-            return Ok(MadaraBlockInner::default());
-        };
-        let res = bincode::deserialize(&res)?;
-        Ok(res)
+        let latest_block_info =
+            self.get_block_info_from_block_n(latest_block_id)?.ok_or(MadaraStorageError::MissingChainInfo)?;
+
+        Ok(MadaraPendingBlockInfo {
+            header: PendingHeader {
+                parent_block_hash: latest_block_info.block_hash,
+                sequencer_address: latest_block_info.header.sequencer_address,
+                block_timestamp: latest_block_info.header.block_timestamp,
+                protocol_version: latest_block_info.header.protocol_version,
+                l1_gas_price: latest_block_info.header.l1_gas_price.clone(),
+                l1_da_mode: latest_block_info.header.l1_da_mode,
+            },
+            tx_hashes: vec![],
+        })
     }
 
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn has_pending_block(&self) -> Result<bool> {
         let col = self.db.get_column(Column::BlockStorageMeta);
-        Ok(self.db.get_cf(&col, ROW_PENDING_STATE_UPDATE)?.is_some())
+        Ok(self.db.get_cf_opt(&col, ROW_PENDING_STATE_UPDATE, &self.read_options())?.is_some())
     }
 
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn get_pending_block_state_update(&self) -> Result<StateDiff> {
         let col = self.db.get_column(Column::BlockStorageMeta);
-        let Some(res) = self.db.get_cf(&col, ROW_PENDING_STATE_UPDATE)? else {
+        let Some(res) = self.db.get_cf_opt(&col, ROW_PENDING_STATE_UPDATE, &self.read_options())? else {
             // See pending block quirk
             return Ok(StateDiff::default());
         };
@@ -206,7 +263,7 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn get_pending_block_segments(&self) -> Result<Option<VisitedSegments>> {
         let col = self.db.get_column(Column::BlockStorageMeta);
-        let Some(res) = self.db.get_cf(&col, ROW_PENDING_SEGMENTS)? else {
+        let Some(res) = self.db.get_cf_opt(&col, ROW_PENDING_SEGMENTS, &self.read_options())? else {
             // See pending block quirk
             return Ok(None);
         };
@@ -217,7 +274,7 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn get_pending_block_bouncer_weights(&self) -> Result<Option<BouncerWeights>> {
         let col = self.db.get_column(Column::BlockStorageMeta);
-        let Some(res) = self.db.get_cf(&col, ROW_PENDING_BOUNCER_WEIGHTS)? else {
+        let Some(res) = self.db.get_cf_opt(&col, ROW_PENDING_BOUNCER_WEIGHTS, &self.read_options())? else {
             // See pending block quirk
             return Ok(None);
         };
@@ -228,13 +285,122 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn get_l1_last_confirmed_block(&self) -> Result<Option<u64>> {
         let col = self.db.get_column(Column::BlockStorageMeta);
-        let Some(res) = self.db.get_cf(&col, ROW_L1_LAST_CONFIRMED_BLOCK)? else { return Ok(None) };
+        let Some(res) = self.db.get_cf_opt(&col, ROW_L1_LAST_CONFIRMED_BLOCK, &self.read_options())? else {
+            return Ok(None);
+        };
+        let res = bincode::deserialize(&res)?;
+        Ok(Some(res))
+    }
+
+    /// Oldest block stored in the database, when the node was bootstrapped from a state snapshot. Returns `None` when
+    /// the history starts at the genesis block.
+    #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
+    pub fn get_oldest_backfilled_block_n(&self) -> Result<Option<u64>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf_opt(&col, ROW_OLDEST_BACKFILLED_BLOCK, &self.read_options())? else {
+            return Ok(None);
+        };
         let res = bincode::deserialize(&res)?;
         Ok(Some(res))
     }
 
     // DB write
 
+    /// Marks the history of the chain as starting at `block_n`, see [`MadaraBackend::store_backfilled_block`].
+    #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
+    pub fn write_oldest_backfilled_block(&self, block_n: u64) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, ROW_OLDEST_BACKFILLED_BLOCK, bincode::serialize(&block_n)?, &writeopts)?;
+        Ok(())
+    }
+
+    /// Stores a block older than the oldest block in the database. Unlike [`MadaraBackend::store_block`], this does not
+    /// move the sync tip, and does not touch the contract state nor the global tries: the state of the chain is
+    /// already known from the state snapshot the node was bootstrapped from.
+    #[tracing::instrument(skip(self, block, state_diff), fields(module = "BlockDB"))]
+    pub fn store_backfilled_block(&self, block: &MadaraBlock, state_diff: &StateDiff) -> Result<()> {
+        let mut tx = WriteBatchWithTransaction::default();
+
+        let tx_hash_to_block_n = self.db.get_column(Column::TxHashToBlockN);
+        let block_hash_to_block_n = self.db.get_column(Column::BlockHashToBlockN);
+        let block_n_to_block = self.db.get_column(Column::BlockNToBlockInfo);
+        let block_n_to_block_inner = self.db.get_column(Column::BlockNToBlockInner);
+        let block_n_to_state_diff = self.db.get_column(Column::BlockNToStateDiff);
+        let meta = self.db.get_column(Column::BlockStorageMeta);
+
+        let block_hash_encoded = bincode::serialize(&block.info.block_hash)?;
+        let block_n_encoded = bincode::serialize(&block.info.header.block_number)?;
+
+        for hash in &block.info.tx_hashes {
+            tx.put_cf(&tx_hash_to_block_n, bincode::serialize(hash)?, &block_n_encoded);
+        }
+
+        tx.put_cf(&block_n_to_block, &block_n_encoded, bincode::serialize(&block.info)?);
+        tx.put_cf(&block_hash_to_block_n, block_hash_encoded, &block_n_encoded);
+        tx.put_cf(&block_n_to_block_inner, &block_n_encoded, bincode::serialize(&block.inner)?);
+        tx.put_cf(&block_n_to_state_diff, &block_n_encoded, bincode::serialize(state_diff)?);
+        tx.put_cf(&meta, ROW_OLDEST_BACKFILLED_BLOCK, &block_n_encoded);
+        self.address_activity_index_block(&mut tx, block, state_diff);
+        self.event_index_block(&mut tx, block);
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
+
+        self.notify_chain_head(PipelineStage::Backfill, block.info.header.block_number);
+        Ok(())
+    }
+
+    /// Removes block `block_n`, which must be the latest block, and moves the sync tip to its parent. Its state is
+    /// reverted separately, see [`MadaraBackend::revert_to`].
+    #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
+    pub(crate) fn block_db_revert_block(&self, block_n: u64) -> Result<()> {
+        let info = self.get_block_info_from_block_n(block_n)?.ok_or_else(|| {
+            MadaraStorageError::InconsistentStorage(format!("Missing block info for block #{block_n}").into())
+        })?;
+        let inner = self.get_block_inner_from_block_n(block_n)?.ok_or_else(|| {
+            MadaraStorageError::InconsistentStorage(format!("Missing block inner for block #{block_n}").into())
+        })?;
+        let state_diff = self.get_state_update(block_n)?.ok_or_else(|| {
+            MadaraStorageError::InconsistentStorage(format!("Missing state diff for block #{block_n}").into())
+        })?;
+        let mut tx = WriteBatchWithTransaction::default();
+
+        let tx_hash_to_block_n = self.db.get_column(Column::TxHashToBlockN);
+        let block_hash_to_block_n = self.db.get_column(Column::BlockHashToBlockN);
+        let block_n_to_block = self.db.get_column(Column::BlockNToBlockInfo);
+        let block_n_to_block_inner = self.db.get_column(Column::BlockNToBlockInner);
+        let block_n_to_state_diff = self.db.get_column(Column::BlockNToStateDiff);
+        let signatures = self.db.get_column(Column::ConsensusSignatures);
+        let meta = self.db.get_column(Column::BlockStorageMeta);
+
+        let block_n_encoded = bincode::serialize(&block_n)?;
+
+        for hash in &info.tx_hashes {
+            tx.delete_cf(&tx_hash_to_block_n, bincode::serialize(hash)?);
+        }
+
+        tx.delete_cf(&block_n_to_block, &block_n_encoded);
+        tx.delete_cf(&block_hash_to_block_n, bincode::serialize(&info.block_hash)?);
+        tx.delete_cf(&block_n_to_block_inner, &block_n_encoded);
+        tx.delete_cf(&block_n_to_state_diff, &block_n_encoded);
+        tx.delete_cf(&signatures, &block_n_encoded);
+        let block = MadaraBlock { info, inner };
+        self.address_activity_remove_block(&mut tx, &block, &state_diff);
+        self.event_index_remove_block(&mut tx, &block);
+        match block_n.checked_sub(1) {
+            Some(parent_block_n) => tx.put_cf(&meta, ROW_SYNC_TIP, bincode::serialize(&parent_block_n)?),
+            None => tx.delete_cf(&meta, ROW_SYNC_TIP),
+        }
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub(crate) fn block_db_store_pending(
         &self,
@@ -277,10 +443,18 @@ impl MadaraBackend {
 
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
     pub fn write_last_confirmed_block(&self, l1_last: u64) -> Result<()> {
+        // Only read the previous value when someone is listening.
+        let advanced = self.has_chain_head_subscribers()
+            && self.get_l1_last_confirmed_block()?.map_or(true, |previous| previous < l1_last);
+
         let col = self.db.get_column(Column::BlockStorageMeta);
         let mut writeopts = WriteOptions::default(); // todo move that in db
         writeopts.disable_wal(true);
         self.db.put_cf_opt(&col, ROW_L1_LAST_CONFIRMED_BLOCK, bincode::serialize(&l1_last)?, &writeopts)?;
+
+        if advanced {
+            self.notify_chain_head(PipelineStage::L1Confirmation, l1_last);
+        }
         Ok(())
     }
 
@@ -289,9 +463,15 @@ impl MadaraBackend {
         self.write_last_confirmed_block(0)
     }
 
-    /// Also clears pending block
+    /// Also clears pending block. When `state_snapshot` is set, the block is the block of the state snapshot the node
+    /// is bootstrapped from: the history of the chain and its state start at this block, in the same write.
     #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
-    pub(crate) fn block_db_store_block(&self, block: &MadaraBlock, state_diff: &StateDiff) -> Result<()> {
+    pub(crate) fn block_db_store_block(
+        &self,
+        block: &MadaraBlock,
+        state_diff: &StateDiff,
+        state_snapshot: bool,
+    ) -> Result<()> {
         let mut tx = WriteBatchWithTransaction::default();
 
         let tx_hash_to_block_n = self.db.get_column(Column::TxHashToBlockN);
@@ -312,9 +492,24 @@ impl MadaraBackend {
         tx.put_cf(&block_hash_to_block_n, block_hash_encoded, &block_n_encoded);
         tx.put_cf(&block_n_to_block_inner, &block_n_encoded, bincode::serialize(&block.inner)?);
         tx.put_cf(&block_n_to_state_diff, &block_n_encoded, bincode::serialize(state_diff)?);
+        if state_snapshot {
+            tx.put_cf(&meta, ROW_OLDEST_BACKFILLED_BLOCK, &block_n_encoded);
+            tx.put_cf(&meta, ROW_OLDEST_STATE_BLOCK, &block_n_encoded);
+        }
         tx.put_cf(&meta, ROW_SYNC_TIP, block_n_encoded);
+        self.address_activity_index_block(&mut tx, block, state_diff);
+        self.event_index_block(&mut tx, block);
+
+        // clear pending
+        tx.delete_cf(&meta, ROW_PENDING_INFO);
+        tx.delete_cf(&meta, ROW_PENDING_INNER);
+        tx.delete_cf(&meta, ROW_PENDING_STATE_UPDATE);
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
 
-        // susbcribers
+        // Subscribers are notified once the block is committed, so that they can read it from the database.
         if self.sender_block_info.receiver_count() > 0 {
             if let Err(e) = self.sender_block_info.send(block.info.clone()) {
                 tracing::debug!("Failed to send block info to subscribers: {e}");
@@ -344,14 +539,7 @@ impl MadaraBackend {
                 });
         }
 
-        // clear pending
-        tx.delete_cf(&meta, ROW_PENDING_INFO);
-        tx.delete_cf(&meta, ROW_PENDING_INNER);
-        tx.delete_cf(&meta, ROW_PENDING_STATE_UPDATE);
-
-        let mut writeopts = WriteOptions::new();
-        writeopts.disable_wal(true);
-        self.db.write_opt(tx, &writeopts)?;
+        self.notify_chain_head(PipelineStage::BlockImport, block.info.header.block_number);
         Ok(())
     }
 
@@ -363,21 +551,22 @@ impl MadaraBackend {
             BlockId::Number(block_n) => Ok(Some(DbBlockId::Number(*block_n))),
             BlockId::Tag(BlockTag::Latest) => Ok(self.get_latest_block_n()?.map(DbBlockId::Number)),
             BlockId::Tag(BlockTag::Pending) => Ok(Some(DbBlockId::Pending)),
+            BlockId::Tag(BlockTag::L1Accepted) => Ok(self.get_l1_last_confirmed_block()?.map(DbBlockId::Number)),
         }
     }
 
     fn storage_to_info(&self, id: &DbBlockId) -> Result<Option<MadaraMaybePendingBlockInfo>> {
         match id {
-            DbBlockId::Pending => Ok(Some(MadaraMaybePendingBlockInfo::Pending(self.get_pending_block_info()?))),
-            DbBlockId::Number(block_n) => {
+            DbBlockId::Pending => Ok(Some(MadaraMaybePendingBlockInfo::Pending(self.pending_block_info()?))),
+            DbBlockId::Number(block_n) => ReadScope::block_info(id, || {
                 Ok(self.get_block_info_from_block_n(*block_n)?.map(MadaraMaybePendingBlockInfo::NotPending))
-            }
+            }),
         }
     }
 
     fn storage_to_inner(&self, id: &DbBlockId) -> Result<Option<MadaraBlockInner>> {
         match id {
-            DbBlockId::Pending => Ok(Some(self.get_pending_block_inner()?)),
+            DbBlockId::Pending => Ok(Some(self.get_pending_block()?.inner.clone())),
             DbBlockId::Number(block_n) => self.get_block_inner_from_block_n(*block_n),
         }
     }
@@ -412,6 +601,45 @@ impl MadaraBackend {
         }
     }
 
+    /// Pending blocks are never signed.
+    #[tracing::instrument(skip(self, id), fields(module = "BlockDB"))]
+    pub fn get_consensus_signature(&self, id: &impl DbBlockIdResolvable) -> Result<Option<ConsensusSignature>> {
+        let Some(ty) = id.resolve_db_block_id(self)? else { return Ok(None) };
+        match ty {
+            DbBlockId::Pending => Ok(None),
+            DbBlockId::Number(block_n) => self.get_consensus_signature_from_block_n(block_n),
+        }
+    }
+
+    /// Replaces the receipts of block `block_n`, which must have as many receipts. This is used to enrich the
+    /// receipts of old blocks with the execution resources the feeder gateway did not serve for them. The write goes
+    /// through the WAL: unlike the sync, the backfill does not flush the database after its writes.
+    #[tracing::instrument(skip(self, receipts), fields(module = "BlockDB"))]
+    pub fn store_block_receipts(&self, block_n: u64, receipts: Vec<TransactionReceipt>) -> Result<()> {
+        let Some(mut inner) = self.get_block_inner_from_block_n(block_n)? else {
+            return Err(MadaraStorageError::InconsistentStorage(format!("Block #{block_n} not found").into()));
+        };
+        if inner.receipts.len() != receipts.len() {
+            return Err(MadaraStorageError::InconsistentStorage(
+                format!("Block #{block_n} has {} receipts, not {}", inner.receipts.len(), receipts.len()).into(),
+            ));
+        }
+        inner.receipts = receipts;
+
+        let col = self.db.get_column(Column::BlockNToBlockInner);
+        self.db.put_cf(&col, bincode::serialize(&block_n)?, bincode::serialize(&inner)?)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "BlockDB"))]
+    pub fn store_consensus_signature(&self, block_n: u64, signature: &ConsensusSignature) -> Result<()> {
+        let col = self.db.get_column(Column::ConsensusSignatures);
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, bincode::serialize(&block_n)?, bincode::serialize(signature)?, &writeopts)?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self, id), fields(module = "BlockDB"))]
     pub fn contains_block(&self, id: &impl DbBlockIdResolvable) -> Result<bool> {
         let Some(ty) = id.resolve_db_block_id(self)? else { return Ok(false) };
@@ -444,11 +672,77 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self, id), fields(module = "BlockDB"))]
     pub fn get_block(&self, id: &impl DbBlockIdResolvable) -> Result<Option<MadaraMaybePendingBlock>> {
         let Some(ty) = id.resolve_db_block_id(self)? else { return Ok(None) };
+        if ty == DbBlockId::Pending {
+            return Ok(Some(Arc::unwrap_or_clone(self.get_pending_block()?).into()));
+        }
         let Some(info) = self.storage_to_info(&ty)? else { return Ok(None) };
         let Some(inner) = self.storage_to_inner(&ty)? else { return Ok(None) };
         Ok(Some(MadaraMaybePendingBlock { info, inner }))
     }
 
+    /// Iterates over the blocks in `range`, in order. The end of the range can be past the latest block, and the blocks
+    /// which are not in the database, such as the blocks before a state snapshot which are not backfilled yet, are
+    /// skipped. The pending block is never returned.
+    ///
+    /// Use this instead of [`MadaraBackend::get_block`] in a loop for sequential scans. Block keys are not laid out in
+    /// block order in the database, which defeats readahead: the blocks are read in batches of multi-gets instead,
+    /// which lets RocksDB fetch all the data blocks of a batch at once.
+    pub fn iter_blocks(&self, range: impl RangeBounds<u64>) -> impl Iterator<Item = Result<MadaraBlock>> + '_ {
+        let mut next = match range.start_bound() {
+            Bound::Included(n) => *n,
+            Bound::Excluded(n) => n.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(n) => n.saturating_add(1),
+            Bound::Excluded(n) => *n,
+            Bound::Unbounded => u64::MAX,
+        };
+        let mut batch = VecDeque::new();
+        let mut end = match self.get_latest_block_n() {
+            Ok(latest_block_n) => end.min(latest_block_n.map_or(0, |block_n| block_n.saturating_add(1))),
+            Err(err) => {
+                batch.push_back(Err(err));
+                next
+            }
+        };
+
+        std::iter::from_fn(move || {
+            // A whole batch may be missing.
+            while batch.is_empty() && next < end {
+                let batch_end = end.min(next.saturating_add(ITER_BLOCKS_BATCH_SIZE));
+                match self.get_blocks_batch(next..batch_end) {
+                    Ok(blocks) => batch.extend(blocks.into_iter().map(Ok)),
+                    Err(err) => {
+                        end = batch_end;
+                        batch.push_back(Err(err));
+                    }
+                }
+                next = batch_end;
+            }
+            batch.pop_front()
+        })
+    }
+
+    /// Reads the blocks in `range`, skipping the missing ones.
+    fn get_blocks_batch(&self, range: std::ops::Range<u64>) -> Result<Vec<MadaraBlock>> {
+        let keys = range.map(|block_n| bincode::serialize(&block_n)).collect::<Result<Vec<_>, _>>()?;
+        let mut readopts = self.read_options();
+        readopts.set_async_io(true);
+
+        let info_col = self.db.get_column(Column::BlockNToBlockInfo);
+        let inner_col = self.db.get_column(Column::BlockNToBlockInner);
+        let infos = self.db.multi_get_cf_opt(keys.iter().map(|key| (&info_col, key)), &readopts);
+        let inners = self.db.multi_get_cf_opt(keys.iter().map(|key| (&inner_col, key)), &readopts);
+
+        let mut blocks = Vec::with_capacity(keys.len());
+        for (info, inner) in infos.into_iter().zip(inners) {
+            let (Some(info), Some(inner)) = (info?, inner?) else { continue };
+            blocks.push(MadaraBlock { info: bincode::deserialize(&info)?, inner: bincode::deserialize(&inner)? });
+        }
+        Ok(blocks)
+    }
+
     // Tx hashes and tx status
 
     /// Returns the index of the tx.
@@ -461,7 +755,7 @@ impl MadaraBackend {
                 Ok(Some((info.into(), TxIndex(tx_index as _))))
             }
             None => {
-                let info = self.get_pending_block_info()?;
+                let info = self.pending_block_info()?;
                 let Some(tx_index) = info.tx_hashes.iter().position(|a| a == tx_hash) else { return Ok(None) };
                 Ok(Some((info.into(), TxIndex(tx_index as _))))
             }
@@ -479,10 +773,9 @@ impl MadaraBackend {
                 Ok(Some((MadaraMaybePendingBlock { info: info.into(), inner }, TxIndex(tx_index as _))))
             }
             None => {
-                let info = self.get_pending_block_info()?;
-                let Some(tx_index) = info.tx_hashes.iter().position(|a| a == tx_hash) else { return Ok(None) };
-                let inner = self.get_pending_block_inner()?;
-                Ok(Some((MadaraMaybePendingBlock { info: info.into(), inner }, TxIndex(tx_index as _))))
+                let block = self.get_pending_block()?;
+                let Some(tx_index) = block.info.tx_hashes.iter().position(|a| a == tx_hash) else { return Ok(None) };
+                Ok(Some((Arc::unwrap_or_clone(block).into(), TxIndex(tx_index as _))))
             }
         }
     }
@@ -0,0 +1,91 @@
+//! Progress of the stages of the block pipeline.
+//!
+//! Every time a stage advances, a [`ChainHeadUpdate`] is sent to the subscribers, so that they can follow the sync
+//! without polling the database.
+
+use crate::{MadaraBackend, MadaraStorageError};
+use serde::{Deserialize, Serialize};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+/// A stage of the block pipeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    /// A block was imported and stored in the database.
+    BlockImport,
+    /// A block was confirmed on L1.
+    L1Confirmation,
+    /// A block older than the state snapshot the node was bootstrapped from was stored in the database.
+    Backfill,
+    /// The blocks after a block were removed from the database, after a reorg.
+    Revert,
+    /// The state diff of a block was applied to the global tries, after they lagged behind the blocks.
+    GlobalTrie,
+}
+
+/// The latest block reached by each stage of the block pipeline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHead {
+    /// Latest block stored in the database.
+    pub latest_block_n: Option<u64>,
+    /// Latest block confirmed on L1.
+    pub l1_confirmed_block_n: Option<u64>,
+    /// Oldest block stored in the database, when the node was bootstrapped from a state snapshot. The history is
+    /// complete once this reaches the genesis block.
+    pub oldest_backfilled_block: Option<u64>,
+    /// Oldest block at which the state can be read, when the state was pruned.
+    pub oldest_state_block_n: Option<u64>,
+    /// Latest block applied to the global tries. This is behind the latest block when blocks were imported with
+    /// `--disable-root`, see [`crate::global_trie_progress`].
+    pub global_trie_block_n: Option<u64>,
+}
+
+/// A stage of the block pipeline advanced to a new block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainHeadUpdate {
+    /// The stage which advanced.
+    pub stage: PipelineStage,
+    /// The block this stage advanced to.
+    pub block_n: u64,
+    /// The chain head after the update.
+    pub chain_head: ChainHead,
+}
+
+impl MadaraBackend {
+    #[tracing::instrument(skip(self), fields(module = "ChainHead"))]
+    pub fn get_chain_head(&self) -> Result<ChainHead> {
+        Ok(ChainHead {
+            latest_block_n: self.get_latest_block_n()?,
+            l1_confirmed_block_n: self.get_l1_last_confirmed_block()?,
+            oldest_backfilled_block: self.get_oldest_backfilled_block_n()?,
+            oldest_state_block_n: self.get_oldest_state_block_n(),
+            global_trie_block_n: self.get_global_trie_block_n()?,
+        })
+    }
+
+    /// Subscribes to the updates of the chain head. Updates are only sent once they have been written to the database.
+    pub fn subscribe_chain_head(&self) -> tokio::sync::broadcast::Receiver<ChainHeadUpdate> {
+        self.sender_chain_head.subscribe()
+    }
+
+    pub(crate) fn has_chain_head_subscribers(&self) -> bool {
+        self.sender_chain_head.receiver_count() > 0
+    }
+
+    pub(crate) fn notify_chain_head(&self, stage: PipelineStage, block_n: u64) {
+        if !self.has_chain_head_subscribers() {
+            return;
+        }
+        let chain_head = match self.get_chain_head() {
+            Ok(chain_head) => chain_head,
+            Err(e) => {
+                tracing::debug!("Failed to get the chain head: {e:#}");
+                return;
+            }
+        };
+        if let Err(e) = self.sender_chain_head.send(ChainHeadUpdate { stage, block_n, chain_head }) {
+            tracing::debug!("Failed to send chain head update to subscribers: {e}");
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use mp_class::{ClassInfo, CompiledSierra, ConvertedClass, LegacyConvertedClass, SierraConvertedClass};
+use mp_state_update::StateDiff;
 use rayon::{iter::ParallelIterator, slice::ParallelSlice};
 use rocksdb::WriteOptions;
 use starknet_types_core::felt::Felt;
@@ -34,14 +35,14 @@ impl MadaraBackend {
         // Get from pending db, then normal db if not found.
         if is_pending {
             let col = self.db.get_column(pending_col);
-            if let Some(res) = self.db.get_pinned_cf(&col, &key_encoded)? {
+            if let Some(res) = self.db.get_pinned_cf_opt(&col, &key_encoded, &self.read_options())? {
                 return Ok(Some(bincode::deserialize(&res)?)); // found in pending
             }
         }
         tracing::debug!("class db get encoded kv, state is not pending");
 
         let col = self.db.get_column(nonpending_col);
-        let Some(val) = self.db.get_pinned_cf(&col, &key_encoded)? else { return Ok(None) };
+        let Some(val) = self.db.get_pinned_cf_opt(&col, &key_encoded, &self.read_options())? else { return Ok(None) };
         let val = bincode::deserialize(&val)?;
 
         Ok(Some(val))
@@ -86,7 +87,7 @@ impl MadaraBackend {
     pub fn contains_class(&self, class_hash: &Felt) -> Result<bool, MadaraStorageError> {
         let col = self.db.get_column(Column::ClassInfo);
         let key_encoded = bincode::serialize(class_hash)?;
-        Ok(self.db.get_pinned_cf(&col, &key_encoded)?.is_some())
+        Ok(self.db.get_pinned_cf_opt(&col, &key_encoded, &self.read_options())?.is_some())
     }
 
     #[tracing::instrument(skip(self, id, compiled_class_hash), fields(module = "ClassDB"))]
@@ -215,7 +216,7 @@ impl MadaraBackend {
 
     /// NB: This functions needs to run on the rayon thread pool
     #[tracing::instrument(skip(self, converted_classes), fields(module = "ClassDB"))]
-    pub(crate) fn class_db_store_block(
+    pub fn class_db_store_block(
         &self,
         block_number: u64,
         converted_classes: &[ConvertedClass],
@@ -237,6 +238,38 @@ impl MadaraBackend {
         )
     }
 
+    /// Removes the classes declared in block `block_number`, given its state diff.
+    #[tracing::instrument(skip(self, state_diff), fields(module = "ClassDB"))]
+    pub(crate) fn class_db_revert_block(
+        &self,
+        block_number: u64,
+        state_diff: &StateDiff,
+    ) -> Result<(), MadaraStorageError> {
+        let col_info = self.db.get_column(Column::ClassInfo);
+        let col_compiled = self.db.get_column(Column::ClassCompiled);
+        let mut batch = WriteBatchWithTransaction::default();
+
+        let declared = state_diff.declared_classes.iter().map(|item| item.class_hash);
+        for class_hash in declared.chain(state_diff.deprecated_declared_classes.iter().copied()) {
+            let key_bin = bincode::serialize(&class_hash)?;
+            let Some(info) = self.db.get_pinned_cf(&col_info, &key_bin)? else { continue };
+            let info: ClassInfoWithBlockNumber = bincode::deserialize(&info)?;
+            // Some legacy classes are declared multiple times, only the first declaration is stored.
+            if info.block_id != DbBlockId::Number(block_number) {
+                continue;
+            }
+            if let ClassInfo::Sierra(sierra) = &info.class_info {
+                batch.delete_cf(&col_compiled, bincode::serialize(&sierra.compiled_class_hash)?);
+            }
+            batch.delete_cf(&col_info, &key_bin);
+        }
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(batch, &writeopts)?;
+        Ok(())
+    }
+
     #[tracing::instrument(fields(module = "ClassDB"))]
     pub(crate) fn class_db_clear_pending(&self) -> Result<(), MadaraStorageError> {
         let mut writeopts = WriteOptions::new();
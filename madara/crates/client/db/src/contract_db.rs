@@ -2,8 +2,9 @@
 
 use std::sync::Arc;
 
+use mp_state_update::StateDiff;
 use rayon::{iter::ParallelIterator, slice::ParallelSlice};
-use rocksdb::{BoundColumnFamily, IteratorMode, ReadOptions, WriteOptions};
+use rocksdb::{BoundColumnFamily, IteratorMode, WriteOptions};
 use serde::Serialize;
 use starknet_types_core::felt::Felt;
 
@@ -19,7 +20,7 @@ pub(crate) const CONTRACT_NONCES_PREFIX_EXTRACTOR: usize = 32;
 
 const LAST_KEY: &[u8] = &[0xFF; 64];
 
-fn make_storage_key_prefix(contract_address: Felt, storage_key: Felt) -> [u8; 64] {
+pub(crate) fn make_storage_key_prefix(contract_address: Felt, storage_key: Felt) -> [u8; 64] {
     let mut key = [0u8; 64];
     key[..32].copy_from_slice(contract_address.to_bytes_be().as_ref());
     key[32..].copy_from_slice(storage_key.to_bytes_be().as_ref());
@@ -45,14 +46,17 @@ impl MadaraBackend {
                 // todo: smallint here to avoid alloc
 
                 // Note: pending has keys in bincode, not bytes
-                if let Some(res) = self.db.get_pinned_cf(&col, bincode::serialize(k)?)? {
+                if let Some(res) = self.db.get_pinned_cf_opt(&col, bincode::serialize(k)?, &self.read_options())? {
                     return Ok(Some(bincode::deserialize(&res)?)); // found in pending
                 }
 
                 let Some(block_n) = self.get_latest_block_n()? else { return Ok(None) };
                 block_n
             }
-            DbBlockId::Number(block_n) => block_n,
+            DbBlockId::Number(block_n) => {
+                self.check_state_not_pruned(block_n)?;
+                block_n
+            }
         };
 
         // We try to find history values.
@@ -61,7 +65,7 @@ impl MadaraBackend {
         let bin_prefix = make_bin_prefix(k);
         let start_at = [bin_prefix.as_ref(), &block_n.to_be_bytes() as &[u8]].concat();
 
-        let mut options = ReadOptions::default();
+        let mut options = self.read_options();
         options.set_prefix_same_as_start(true);
         // We don't need ot set an iteration range as we have set up a prefix extractor for the column.
         // We are doing prefix iteration
@@ -202,6 +206,39 @@ impl MadaraBackend {
         Ok(())
     }
 
+    /// Removes the contract state updates of block `block_number`, given its state diff.
+    #[tracing::instrument(skip(self, state_diff), fields(module = "ContractDB"))]
+    pub(crate) fn contract_db_revert_block(
+        &self,
+        block_number: u64,
+        state_diff: &StateDiff,
+    ) -> Result<(), MadaraStorageError> {
+        let block_number = u32::try_from(block_number).map_err(|_| MadaraStorageError::InvalidBlockNumber)?;
+        let suffix = block_number.to_be_bytes();
+        let mut batch = WriteBatchWithTransaction::default();
+
+        let col = self.db.get_column(Column::ContractToClassHashes);
+        let replaced = state_diff.replaced_classes.iter().map(|item| item.contract_address);
+        for address in state_diff.deployed_contracts.iter().map(|item| item.address).chain(replaced) {
+            batch.delete_cf(&col, [address.to_bytes_be().as_ref(), &suffix].concat());
+        }
+        let col = self.db.get_column(Column::ContractToNonces);
+        for item in &state_diff.nonces {
+            batch.delete_cf(&col, [item.contract_address.to_bytes_be().as_ref(), &suffix].concat());
+        }
+        let col = self.db.get_column(Column::ContractStorage);
+        for item in &state_diff.storage_diffs {
+            for entry in &item.storage_entries {
+                batch.delete_cf(&col, [make_storage_key_prefix(item.address, entry.key).as_ref(), &suffix].concat());
+            }
+        }
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(batch, &writeopts)?;
+        Ok(())
+    }
+
     /// NB: This functions needs to run on the rayon thread pool
     #[tracing::instrument(
         skip(self, contract_class_updates, contract_nonces_updates, contract_kv_updates),
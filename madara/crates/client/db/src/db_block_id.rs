@@ -2,9 +2,9 @@ use core::fmt;
 
 use mp_block::BlockId;
 
-use crate::{MadaraBackend, MadaraStorageError};
+use crate::{read_scope::ReadScope, MadaraBackend, MadaraStorageError};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum DbBlockId {
     Pending,
     Number(u64),
@@ -36,7 +36,7 @@ pub trait DbBlockIdResolvable {
 
 impl DbBlockIdResolvable for BlockId {
     fn resolve_db_block_id(&self, backend: &MadaraBackend) -> Result<Option<DbBlockId>, MadaraStorageError> {
-        backend.id_to_storage_type(self)
+        ReadScope::resolve(self, || backend.id_to_storage_type(self))
     }
 }
 
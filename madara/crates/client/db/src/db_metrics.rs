@@ -1,8 +1,8 @@
 use crate::{Column, DatabaseExt, DB};
 use anyhow::Context as _;
-use mc_analytics::register_gauge_metric_instrument;
+use mc_analytics::{register_gauge_metric_instrument, register_histogram_metric_instrument};
 use opentelemetry::global::Error;
-use opentelemetry::metrics::Gauge;
+use opentelemetry::metrics::{Gauge, Histogram};
 use opentelemetry::{global, KeyValue};
 use rocksdb::perf::MemoryUsageBuilder;
 #[derive(Clone, Debug)]
@@ -13,6 +13,7 @@ pub struct DbMetrics {
     pub mem_table_unflushed: Gauge<u64>,
     pub mem_table_readers_total: Gauge<u64>,
     pub cache_total: Gauge<u64>,
+    pub flush_time: Histogram<f64>,
 }
 
 impl DbMetrics {
@@ -69,7 +70,22 @@ impl DbMetrics {
             "".to_string(),
         );
 
-        Ok(Self { db_size, column_sizes, mem_table_total, mem_table_unflushed, mem_table_readers_total, cache_total })
+        let flush_time = register_histogram_metric_instrument(
+            &rpc_meter,
+            "db_flush_time".to_string(),
+            "Time taken to flush the mem-tables to disk in milliseconds".to_string(),
+            "".to_string(),
+        );
+
+        Ok(Self {
+            db_size,
+            column_sizes,
+            mem_table_total,
+            mem_table_unflushed,
+            mem_table_readers_total,
+            cache_total,
+            flush_time,
+        })
     }
 
     pub fn try_update(&self, db: &DB) -> anyhow::Result<u64> {
@@ -0,0 +1,140 @@
+//! Devnet control over the produced blocks.
+//!
+//! The `madara_setNextBlockTimestamp`, `madara_setNextBlockGasPrices` and `madara_mineBlocks` admin RPC methods update
+//! the [`DevnetControl`] here, so that contract test suites can move the chain through time and produce blocks on
+//! demand. The block production reads it when opening a new pending block. The control is only enabled in devnet mode,
+//! and is not persisted: a restarted devnet produces blocks with the wall clock time again.
+
+use crate::MadaraBackend;
+use mp_block::header::{BlockTimestamp, GasPrices};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevnetControl {
+    /// Whether the node runs as a devnet. The control cannot be changed otherwise.
+    pub enabled: bool,
+    /// Seconds added to the wall clock time to get the timestamp of a new block.
+    pub time_offset_secs: i64,
+    /// Exact timestamp of the next block. The following blocks continue from there.
+    pub next_block_timestamp: Option<u64>,
+    /// Gas prices of the new blocks, instead of the ones of the L1 data provider.
+    pub gas_prices: Option<GasPrices>,
+    /// Number of blocks requested by `madara_mineBlocks` which are not closed yet.
+    pub blocks_to_mine: u64,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Timestamp {0} is out of range, the maximum is {max}", max = i64::MAX)]
+pub struct TimestampOutOfRange(pub u64);
+
+impl DevnetControl {
+    /// Timestamp of a block opened now.
+    pub fn block_timestamp(&self) -> BlockTimestamp {
+        match self.next_block_timestamp {
+            Some(timestamp) => BlockTimestamp(timestamp),
+            None => BlockTimestamp(BlockTimestamp::now().0.saturating_add_signed(self.time_offset_secs)),
+        }
+    }
+}
+
+impl MadaraBackend {
+    pub fn get_devnet_control(&self) -> DevnetControl {
+        self.devnet_control.borrow().clone()
+    }
+
+    pub fn subscribe_devnet_control(&self) -> tokio::sync::watch::Receiver<DevnetControl> {
+        self.devnet_control.subscribe()
+    }
+
+    /// Allows the devnet control to be changed. This is only called when the node runs as a devnet.
+    pub fn enable_devnet_control(&self) {
+        self.devnet_control.send_modify(|control| control.enabled = true);
+    }
+
+    /// Sets the timestamp of the next block, and returns the updated control. The clock keeps running from this
+    /// timestamp for the following blocks, which lets the chain move forward or backward in time. The timestamp must
+    /// fit the signed offset from the wall clock time.
+    pub fn set_next_block_timestamp(&self, timestamp: u64) -> Result<DevnetControl, TimestampOutOfRange> {
+        let offset = i64::try_from(timestamp)
+            .ok()
+            .and_then(|timestamp| timestamp.checked_sub_unsigned(BlockTimestamp::now().0))
+            .ok_or(TimestampOutOfRange(timestamp))?;
+        Ok(self.update_devnet_control(|control| {
+            control.next_block_timestamp = Some(timestamp);
+            control.time_offset_secs = offset;
+        }))
+    }
+
+    /// Sets or clears the gas prices of the next blocks, and returns the updated control.
+    pub fn set_next_block_gas_prices(&self, gas_prices: Option<GasPrices>) -> DevnetControl {
+        self.update_devnet_control(|control| control.gas_prices = gas_prices)
+    }
+
+    /// Requests the block production to close `n_blocks` blocks right away, and returns the updated control.
+    pub fn request_mine_blocks(&self, n_blocks: u64) -> DevnetControl {
+        self.update_devnet_control(|control| control.blocks_to_mine = control.blocks_to_mine.saturating_add(n_blocks))
+    }
+
+    /// Takes the number of blocks the block production should close right away.
+    pub fn take_blocks_to_mine(&self) -> u64 {
+        let mut n_blocks = 0;
+        // This does not notify the block production, which is the one taking them.
+        self.devnet_control.send_if_modified(|control| {
+            n_blocks = std::mem::take(&mut control.blocks_to_mine);
+            false
+        });
+        n_blocks
+    }
+
+    /// Called by the block production when a block is closed. The requested timestamp is cleared once it was used.
+    pub fn on_devnet_block_closed(&self, block_timestamp: BlockTimestamp) {
+        self.devnet_control.send_if_modified(|control| {
+            if control.next_block_timestamp == Some(block_timestamp.0) {
+                control.next_block_timestamp = None;
+            }
+            false
+        });
+    }
+
+    fn update_devnet_control(&self, f: impl FnOnce(&mut DevnetControl)) -> DevnetControl {
+        self.devnet_control.send_modify(f);
+        self.get_devnet_control()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_devnet_control() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let now = BlockTimestamp::now().0;
+        assert!(backend.get_devnet_control().block_timestamp().0 >= now);
+
+        let in_a_day = now + 24 * 3600;
+        let control = backend.set_next_block_timestamp(in_a_day).unwrap();
+        assert_eq!(control.block_timestamp(), BlockTimestamp(in_a_day));
+        // The offset from the wall clock time would wrap.
+        assert_eq!(backend.set_next_block_timestamp(u64::MAX), Err(TimestampOutOfRange(u64::MAX)));
+        assert_eq!(backend.get_devnet_control().next_block_timestamp, Some(in_a_day));
+
+        backend.on_devnet_block_closed(BlockTimestamp(in_a_day));
+        let control = backend.get_devnet_control();
+        assert_eq!(control.next_block_timestamp, None);
+        // The clock continues from the requested timestamp.
+        assert!(control.block_timestamp().0 >= in_a_day);
+        assert!(control.block_timestamp().0 < in_a_day + 3600);
+
+        assert_eq!(backend.request_mine_blocks(3).blocks_to_mine, 3);
+        assert_eq!(backend.request_mine_blocks(2).blocks_to_mine, 5);
+        assert_eq!(backend.take_blocks_to_mine(), 5);
+        assert_eq!(backend.take_blocks_to_mine(), 0);
+
+        let gas_prices = GasPrices { eth_l1_gas_price: 1, strk_l1_gas_price: 2, ..Default::default() };
+        assert_eq!(backend.set_next_block_gas_prices(Some(gas_prices.clone())).gas_prices, Some(gas_prices));
+        assert_eq!(backend.set_next_block_gas_prices(None).gas_prices, None);
+    }
+}
@@ -27,6 +27,10 @@ pub enum MadaraStorageError {
         "Missing compiled class for class with hash {class_hash:#x} (compiled_class_hash={compiled_class_hash:#x}"
     )]
     MissingCompiledClass { class_hash: Felt, compiled_class_hash: Felt },
+    #[error("The state at block #{block_n} is not available, the oldest block with a state is #{oldest_block_n}")]
+    StatePruned { block_n: u64, oldest_block_n: u64 },
+    #[error("The parent {parent_block_hash:#x} of the pending block is not the latest block")]
+    StalePendingBlock { parent_block_hash: Felt },
 }
 
 pub type BonsaiStorageError = bonsai_trie::BonsaiStorageError<DbError>;
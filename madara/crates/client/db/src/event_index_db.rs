@@ -0,0 +1,203 @@
+//! Indexes of the events by emitter and first key, for `starknet_getEvents`.
+//!
+//! Every event with at least one key adds an entry keyed by `(from_address, key0, block_n, event_n)` to
+//! [`Column::EventIndex`], and an entry keyed by `(key0, block_n, event_n)` to [`Column::EventKeyIndex`], where
+//! `event_n` is the index of the event in the block. The events with a given first key, of a given emitter or of any
+//! emitter, can then be found in block order without going through every block of the range. Events without keys are
+//! not indexed: they are found through the [address activity index](crate::address_activity_db) instead.
+//!
+//! The databases written by older nodes have no index for their blocks. The indexes are only read once the database is
+//! marked as indexed, which a new database is when it is opened, and an older one once the schema migrations have
+//! built its indexes, see [`MadaraBackend::events_indexed`].
+
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError, WriteBatchWithTransaction};
+use mp_block::MadaraBlock;
+use rocksdb::{Direction, IteratorMode};
+use starknet_types_core::felt::Felt;
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+const ROW_EVENTS_INDEXED: &[u8] = b"events_indexed";
+
+/// Length of the `(block_n, event_n)` suffix of the keys of both indexes.
+const POSITION_LEN: usize = 8 + 4;
+
+fn encode(prefix: &[&Felt], block_n: u64, event_n: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(prefix.len() * 32 + POSITION_LEN);
+    for felt in prefix {
+        key.extend_from_slice(&felt.to_bytes_be());
+    }
+    key.extend_from_slice(&block_n.to_be_bytes());
+    key.extend_from_slice(&event_n.to_be_bytes());
+    key
+}
+
+/// The `(from_address, key0, block_n, event_n)` positions of the events of a block which have at least one key.
+fn block_keyed_events(block: &MadaraBlock) -> impl Iterator<Item = (&Felt, &Felt, u64, u32)> + '_ {
+    let block_n = block.info.header.block_number;
+    block
+        .inner
+        .receipts
+        .iter()
+        .flat_map(|receipt| receipt.events())
+        .enumerate()
+        .filter_map(move |(event_n, event)| Some((&event.from_address, event.keys.first()?, block_n, event_n as u32)))
+}
+
+impl MadaraBackend {
+    /// Whether every block of the database is in the event and address activity indexes. When it is not,
+    /// `starknet_getEvents` goes through every block of the range instead of reading the indexes.
+    #[tracing::instrument(skip(self), fields(module = "EventIndexDB"))]
+    pub fn events_indexed(&self) -> Result<bool> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        Ok(self.db.get_pinned_cf(&col, ROW_EVENTS_INDEXED)?.is_some())
+    }
+
+    /// Marks every block of the database as indexed, see [`MadaraBackend::events_indexed`].
+    #[tracing::instrument(skip(self), fields(module = "EventIndexDB"))]
+    pub(crate) fn write_events_indexed(&self) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        self.db.put_cf(&col, ROW_EVENTS_INDEXED, [])?;
+        Ok(())
+    }
+
+    pub(crate) fn event_index_block(&self, tx: &mut WriteBatchWithTransaction, block: &MadaraBlock) {
+        self.event_address_index_block(tx, block);
+        self.event_key_index_block(tx, block);
+    }
+
+    pub(crate) fn event_address_index_block(&self, tx: &mut WriteBatchWithTransaction, block: &MadaraBlock) {
+        let col = self.db.get_column(Column::EventIndex);
+        for (from_address, key0, block_n, event_n) in block_keyed_events(block) {
+            tx.put_cf(&col, encode(&[from_address, key0], block_n, event_n), []);
+        }
+    }
+
+    pub(crate) fn event_key_index_block(&self, tx: &mut WriteBatchWithTransaction, block: &MadaraBlock) {
+        let col = self.db.get_column(Column::EventKeyIndex);
+        for (_, key0, block_n, event_n) in block_keyed_events(block) {
+            tx.put_cf(&col, encode(&[key0], block_n, event_n), []);
+        }
+    }
+
+    pub(crate) fn event_index_remove_block(&self, tx: &mut WriteBatchWithTransaction, block: &MadaraBlock) {
+        let address_col = self.db.get_column(Column::EventIndex);
+        let key_col = self.db.get_column(Column::EventKeyIndex);
+        for (from_address, key0, block_n, event_n) in block_keyed_events(block) {
+            tx.delete_cf(&address_col, encode(&[from_address, key0], block_n, event_n));
+            tx.delete_cf(&key_col, encode(&[key0], block_n, event_n));
+        }
+    }
+
+    /// The events emitted by `from_address` with `key0` as their first key, from block `from_block_n` to block
+    /// `to_block_n` included, as `(block_n, event_n)` in block order.
+    #[tracing::instrument(skip(self), fields(module = "EventIndexDB"))]
+    pub fn iter_event_index(
+        &self,
+        from_address: &Felt,
+        key0: &Felt,
+        from_block_n: u64,
+        to_block_n: u64,
+    ) -> impl Iterator<Item = Result<(u64, u32)>> + '_ {
+        self.iter_event_positions(Column::EventIndex, encode(&[from_address, key0], from_block_n, 0), to_block_n)
+    }
+
+    /// The events emitted by any contract with `key0` as their first key, from block `from_block_n` to block
+    /// `to_block_n` included, as `(block_n, event_n)` in block order.
+    #[tracing::instrument(skip(self), fields(module = "EventIndexDB"))]
+    pub fn iter_event_key_index(
+        &self,
+        key0: &Felt,
+        from_block_n: u64,
+        to_block_n: u64,
+    ) -> impl Iterator<Item = Result<(u64, u32)>> + '_ {
+        self.iter_event_positions(Column::EventKeyIndex, encode(&[key0], from_block_n, 0), to_block_n)
+    }
+
+    /// The positions of the entries of `col` which share the prefix of `start`, from `start` up to block `to_block_n`.
+    fn iter_event_positions(
+        &self,
+        col: Column,
+        start: Vec<u8>,
+        to_block_n: u64,
+    ) -> impl Iterator<Item = Result<(u64, u32)>> + '_ {
+        let col = self.db.get_column(col);
+        let prefix_len = start.len() - POSITION_LEN;
+        let iter = self.db.iterator_cf_opt(&col, self.read_options(), IteratorMode::From(&start, Direction::Forward));
+        iter.map_while(move |kv| {
+            let key = match kv {
+                Ok((key, _)) => key,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if key.len() != start.len() || key[..prefix_len] != start[..prefix_len] {
+                return None;
+            }
+            let block_n = u64::from_be_bytes(key[prefix_len..prefix_len + 8].try_into().expect("Checked length"));
+            let event_n = u32::from_be_bytes(key[prefix_len + 8..].try_into().expect("Checked length"));
+            (block_n <= to_block_n).then_some(Ok((block_n, event_n)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_chain_config::ChainConfig;
+    use mp_receipt::{Event, InvokeTransactionReceipt, TransactionReceipt};
+    use mp_state_update::StateDiff;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_event_index() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        // A new database is indexed from its first block.
+        assert!(backend.events_indexed().unwrap());
+        let (contract, other) = (Felt::from(0x100), Felt::from(0x200));
+        let event = |from_address, keys: &[u64]| Event {
+            from_address,
+            keys: keys.iter().copied().map(Felt::from).collect(),
+            data: vec![],
+        };
+        for block_n in 0..3 {
+            let receipt = TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                events: vec![event(contract, &[1, 2]), event(other, &[1]), event(contract, &[]), event(contract, &[1])],
+                ..Default::default()
+            });
+            let block = MadaraMaybePendingBlock {
+                info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                    header: Header { block_number: block_n, ..Default::default() },
+                    block_hash: Felt::from(block_n),
+                    tx_hashes: vec![],
+                }),
+                inner: MadaraBlockInner { transactions: vec![], receipts: vec![receipt] },
+            };
+            backend.store_block(block, StateDiff::default(), vec![], None, None).unwrap();
+        }
+
+        let events = |from_address, key0: u64, from_block_n, to_block_n| {
+            backend
+                .iter_event_index(&from_address, &Felt::from(key0), from_block_n, to_block_n)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+        };
+        assert_eq!(events(contract, 1, 0, u64::MAX), vec![(0, 0), (0, 3), (1, 0), (1, 3), (2, 0), (2, 3)]);
+        assert_eq!(events(contract, 1, 1, 1), vec![(1, 0), (1, 3)]);
+        assert_eq!(events(other, 1, 0, 0), vec![(0, 1)]);
+        assert_eq!(events(contract, 2, 0, u64::MAX), vec![]);
+
+        let key_events = |key0: u64, from_block_n, to_block_n| {
+            backend
+                .iter_event_key_index(&Felt::from(key0), from_block_n, to_block_n)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+        };
+        assert_eq!(key_events(1, 1, 2), vec![(1, 0), (1, 1), (1, 3), (2, 0), (2, 1), (2, 3)]);
+        // Only the first key is indexed.
+        assert_eq!(key_events(2, 0, u64::MAX), vec![]);
+
+        backend.block_db_revert_block(2).unwrap();
+        assert_eq!(events(contract, 1, 0, u64::MAX), vec![(0, 0), (0, 3), (1, 0), (1, 3)]);
+        assert_eq!(key_events(1, 2, u64::MAX), vec![]);
+    }
+}
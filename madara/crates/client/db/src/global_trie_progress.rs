@@ -0,0 +1,305 @@
+//! Progress of the global tries.
+//!
+//! With `--disable-root`, the blocks are imported without updating the contract and class tries, and the node cannot
+//! serve storage proofs for them. The tries can be computed later from the state diffs stored with the blocks, see
+//! `mc_block_import::BlockImporter::catch_up_global_tries`. The latest block applied to the tries is recorded here
+//! while they lag behind the blocks, and cleared once they caught up.
+//!
+//! The tries are committed with the block number as bonsai commit id, and are written without the write-ahead log like
+//! the rest of the blocks. Each update of the tries is recorded in a [`GlobalTrieCheckpoint`], which is written with
+//! the same durability, so that the checkpoint recovered after a crash never claims an update of the tries which was
+//! lost. A crash can still leave the tries partially updated with a block, or out of line with the blocks: they are
+//! reconciled with the blocks when the database is opened, see [`MadaraBackend::reconcile_global_tries`].
+
+use crate::chain_head::PipelineStage;
+use crate::db_block_id::DbBlockId;
+use crate::{bonsai_identifier, Column, DatabaseExt, MadaraBackend, MadaraStorageError, WriteBatchWithTransaction};
+use bonsai_trie::id::BasicId;
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+const ROW_GLOBAL_TRIE_BLOCK: &[u8] = b"global_trie_block";
+const ROW_GLOBAL_TRIE_CHECKPOINT: &[u8] = b"global_trie_checkpoint";
+
+/// Number of blocks searched below the recorded progress of the tries for the block their root matches, when they
+/// were left behind it by a crash.
+const GLOBAL_TRIE_RECONCILE_DEPTH: u64 = 1024;
+
+/// Upper bound of the keys of the trie columns, used to clear them.
+const LAST_TRIE_KEY: &[u8] = &[0xFF; 256];
+
+const GLOBAL_TRIE_COLUMNS: [Column; 9] = [
+    Column::BonsaiContractsTrie,
+    Column::BonsaiContractsFlat,
+    Column::BonsaiContractsLog,
+    Column::BonsaiContractsStorageTrie,
+    Column::BonsaiContractsStorageFlat,
+    Column::BonsaiContractsStorageLog,
+    Column::BonsaiClassesTrie,
+    Column::BonsaiClassesFlat,
+    Column::BonsaiClassesLog,
+];
+
+/// "STARKNET_STATE_V0"
+const STARKNET_STATE_PREFIX: Felt = Felt::from_hex_unchecked("0x535441524b4e45545f53544154455f5630");
+
+/// Global state root committing to the contract and class tries.
+pub fn calculate_state_root(contracts_trie_root: Felt, classes_trie_root: Felt) -> Felt {
+    if classes_trie_root == Felt::ZERO {
+        contracts_trie_root
+    } else {
+        Poseidon::hash_array(&[STARKNET_STATE_PREFIX, contracts_trie_root, classes_trie_root])
+    }
+}
+
+/// Latest update of the global tries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobalTrieCheckpoint {
+    /// Block applied to the tries, which is also the bonsai commit id of the update.
+    pub block_n: u64,
+    /// Whether the contract, contract storage and class tries were all committed.
+    pub committed: bool,
+}
+
+impl MadaraBackend {
+    /// The recorded latest block applied to the global tries: `None` when the tries never lagged behind the blocks,
+    /// `Some(None)` when no block was applied to them yet.
+    fn get_global_trie_row(&self) -> Result<Option<Option<u64>>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_GLOBAL_TRIE_BLOCK)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    /// Latest block applied to the global tries, `None` when no block was applied to them yet. This is the latest
+    /// block unless blocks were imported with `--disable-root`.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn get_global_trie_block_n(&self) -> Result<Option<u64>> {
+        match self.get_global_trie_row()? {
+            Some(block_n) => Ok(block_n),
+            None => self.get_latest_block_n(),
+        }
+    }
+
+    /// Records that the block `block_n` was applied to the global tries. This completes the checkpoint written by
+    /// [`Self::begin_global_trie_update`], in the same write.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn write_global_trie_block_n(&self, block_n: Option<u64>) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let mut batch = WriteBatchWithTransaction::default();
+        batch.put_cf(&col, ROW_GLOBAL_TRIE_BLOCK, bincode::serialize(&block_n)?);
+        match block_n {
+            Some(block_n) => {
+                let checkpoint = GlobalTrieCheckpoint { block_n, committed: true };
+                batch.put_cf(&col, ROW_GLOBAL_TRIE_CHECKPOINT, bincode::serialize(&checkpoint)?);
+            }
+            None => batch.delete_cf(&col, ROW_GLOBAL_TRIE_CHECKPOINT),
+        }
+        self.db.write_opt(batch, &self.write_opt_no_wal)?;
+        if let Some(block_n) = block_n {
+            self.notify_chain_head(PipelineStage::GlobalTrie, block_n);
+        }
+        Ok(())
+    }
+
+    /// Completes the update of the global tries with block `block_n` started by [`Self::begin_global_trie_update`].
+    /// While the tries lag behind the blocks, this also records their progress, which is cleared once they caught up
+    /// with the latest block. Otherwise, only the checkpoint is written.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn complete_global_trie_update(&self, block_n: u64) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let checkpoint = bincode::serialize(&GlobalTrieCheckpoint { block_n, committed: true })?;
+        if self.get_global_trie_row()?.is_none() {
+            self.db.put_cf_opt(&col, ROW_GLOBAL_TRIE_CHECKPOINT, checkpoint, &self.write_opt_no_wal)?;
+            return Ok(());
+        }
+        if self.get_latest_block_n()?.is_some_and(|latest_block_n| block_n < latest_block_n) {
+            return self.write_global_trie_block_n(Some(block_n));
+        }
+
+        let mut batch = WriteBatchWithTransaction::default();
+        batch.delete_cf(&col, ROW_GLOBAL_TRIE_BLOCK);
+        batch.put_cf(&col, ROW_GLOBAL_TRIE_CHECKPOINT, checkpoint);
+        self.db.write_opt(batch, &self.write_opt_no_wal)?;
+        self.notify_chain_head(PipelineStage::GlobalTrie, block_n);
+        Ok(())
+    }
+
+    /// Called when a block is imported without updating the global tries. The first time, this records the latest
+    /// block applied to the tries, so that they can be caught up later. The tries of a database which was always
+    /// synced with `--disable-root` are empty.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn record_global_trie_lag(&self) -> Result<()> {
+        if self.get_global_trie_row()?.is_some() {
+            return Ok(());
+        }
+        let contract_trie_root = self.contract_trie().root_hash(bonsai_identifier::CONTRACT)?;
+        let block_n = if contract_trie_root == Felt::ZERO { None } else { self.get_latest_block_n()? };
+        self.write_global_trie_block_n(block_n)
+    }
+
+    /// Latest update of the global tries, `None` when they were never updated since the checkpoints were introduced.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn get_global_trie_checkpoint(&self) -> Result<Option<GlobalTrieCheckpoint>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_GLOBAL_TRIE_CHECKPOINT)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    /// Called before the block `block_n` is applied to the global tries. The update is completed by
+    /// [`Self::complete_global_trie_update`]; until then, a crash leaves the tries partially updated with the block.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn begin_global_trie_update(&self, block_n: u64) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let checkpoint = GlobalTrieCheckpoint { block_n, committed: false };
+        self.db.put_cf_opt(
+            &col,
+            ROW_GLOBAL_TRIE_CHECKPOINT,
+            bincode::serialize(&checkpoint)?,
+            &self.write_opt_no_wal,
+        )?;
+        Ok(())
+    }
+
+    /// Global state root of the contract and class tries.
+    pub fn get_global_trie_state_root(&self) -> Result<Felt> {
+        Ok(calculate_state_root(
+            self.contract_trie().root_hash(bonsai_identifier::CONTRACT)?,
+            self.class_trie().root_hash(bonsai_identifier::CLASS)?,
+        ))
+    }
+
+    /// Reverts the contract, contract storage and class tries from the commit of block `trie_block_n` to the one of
+    /// block `block_n`.
+    pub(crate) fn revert_global_tries(&self, block_n: u64, trie_block_n: u64) -> Result<()> {
+        let (requested_id, current_id) = (BasicId::new(block_n), BasicId::new(trie_block_n));
+        self.contract_trie().revert_to(requested_id, current_id)?;
+        self.contract_storage_trie().revert_to(requested_id, current_id)?;
+        self.class_trie().revert_to(requested_id, current_id)?;
+        Ok(())
+    }
+
+    /// Reverts the global tries from the commit of block `trie_block_n` to the one of block `block_n`, when the trie
+    /// logs go back far enough. Otherwise, the tries are reset, see [`Self::reset_global_tries`].
+    pub(crate) fn revert_or_reset_global_tries(&self, block_n: u64, trie_block_n: u64) -> Result<()> {
+        if trie_block_n - block_n <= self.max_saved_trie_logs() as u64 {
+            match self.revert_global_tries(block_n, trie_block_n) {
+                Ok(()) => return self.write_global_trie_block_n(Some(block_n)),
+                Err(err) => tracing::warn!("Failed to revert the global tries to block #{block_n}: {err:#}"),
+            }
+        } else {
+            tracing::warn!("The trie logs do not go back from block #{trie_block_n} to block #{block_n}");
+        }
+        self.reset_global_tries()
+    }
+
+    /// Clears the global tries. They are then computed again from the state diffs of the stored blocks by the trie
+    /// catch-up, as when the blocks were imported with `--disable-root`.
+    fn reset_global_tries(&self) -> Result<()> {
+        tracing::warn!("Resetting the global tries, they will be computed again from the state diffs of the blocks");
+        if let Some(oldest_block_n) = self.get_oldest_backfilled_block_n()?.filter(|block_n| *block_n > 0) {
+            tracing::error!(
+                "The blocks before the state snapshot at block #{oldest_block_n} are not stored: the global tries \
+                 cannot be computed again until they are backfilled, and the node has to be synced again otherwise"
+            );
+        }
+        for column in GLOBAL_TRIE_COLUMNS {
+            self.db.delete_range_cf_opt(
+                &self.db.get_column(column),
+                &[] as _,
+                LAST_TRIE_KEY,
+                &self.write_opt_no_wal,
+            )?;
+        }
+        self.write_global_trie_block_n(None)
+    }
+
+    /// Brings the global tries back in line with the blocks after a crash. This is called when the database is opened.
+    ///
+    /// - An update of the tries which did not complete is reverted.
+    /// - Updates with blocks which are not stored anymore are reverted.
+    /// - When the root of the tries does not match the block they are recorded at, the tries were left behind it: their
+    ///   progress is moved back to the block their root matches, and the following blocks are applied to them again
+    ///   by the block import or the trie catch-up.
+    ///
+    /// The tries are reverted with the trie logs. When the logs do not go back far enough, as with the default
+    /// `--db-max-saved-trie-logs` of 0, or when no stored block matches the root of the tries, the tries are reset and
+    /// computed again by the trie catch-up. None of this prevents the database from opening.
+    #[tracing::instrument(skip(self), fields(module = "GlobalTrieProgress"))]
+    pub fn reconcile_global_tries(&self) -> Result<()> {
+        let Some(latest_block_n) = self.get_latest_block_n()? else { return Ok(()) };
+        let mut trie_block_n = self.get_global_trie_block_n()?;
+        let checkpoint = self.get_global_trie_checkpoint()?;
+
+        if let Some(GlobalTrieCheckpoint { block_n, committed: false }) = checkpoint {
+            tracing::warn!("The update of the global tries with block #{block_n} was interrupted, reverting it");
+            match block_n.checked_sub(1) {
+                Some(parent_block_n) => self.revert_or_reset_global_tries(parent_block_n, block_n)?,
+                None => self.reset_global_tries()?,
+            }
+            trie_block_n = self.get_global_trie_block_n()?;
+        }
+
+        // The checkpoint is the latest commit the tries may have kept.
+        let checkpoint = self.get_global_trie_checkpoint()?;
+        let trie_head = trie_block_n.max(checkpoint.filter(|checkpoint| checkpoint.committed).map(|c| c.block_n));
+        if let Some(trie_head) = trie_head.filter(|trie_head| *trie_head > latest_block_n) {
+            tracing::warn!("The global tries are ahead of the blocks, reverting them from block #{trie_head}");
+            self.revert_or_reset_global_tries(latest_block_n, trie_head)?;
+            trie_block_n = self.get_global_trie_block_n()?;
+        }
+
+        let Some(trie_block_n) = trie_block_n else { return Ok(()) };
+        let state_root = self.get_global_trie_state_root()?;
+        let oldest_block_n = trie_block_n.saturating_sub(GLOBAL_TRIE_RECONCILE_DEPTH);
+        for block_n in (oldest_block_n..=trie_block_n).rev() {
+            let Some(info) = self.get_block_info(&DbBlockId::Number(block_n))?.and_then(|i| i.as_nonpending_owned())
+            else {
+                // The blocks before a state snapshot are not stored.
+                break;
+            };
+            if info.header.global_state_root == state_root {
+                if block_n != trie_block_n {
+                    tracing::warn!(
+                        "The global tries are behind the blocks, block #{} onwards will be applied to them again",
+                        block_n + 1
+                    );
+                    self.write_global_trie_block_n(Some(block_n))?;
+                }
+                return Ok(());
+            }
+        }
+        tracing::warn!("The root of the global tries does not match the stored blocks up to #{trie_block_n}");
+        self.reset_global_tries()
+    }
+
+    /// Moves the recorded progress of the global tries back to `block_n` after they were reverted.
+    pub(crate) fn revert_global_trie_block_n(&self, block_n: u64) -> Result<()> {
+        if self.get_global_trie_row()?.is_some() {
+            return self.write_global_trie_block_n(Some(block_n));
+        }
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let checkpoint = GlobalTrieCheckpoint { block_n, committed: true };
+        self.db.put_cf_opt(
+            &col,
+            ROW_GLOBAL_TRIE_CHECKPOINT,
+            bincode::serialize(&checkpoint)?,
+            &self.write_opt_no_wal,
+        )?;
+        Ok(())
+    }
+
+    /// Latest block the global tries may have been committed with. Unlike [`Self::get_global_trie_block_n`], this is
+    /// not the latest block when the tries follow the blocks, but the latest update of the tries.
+    pub(crate) fn get_global_trie_head(&self) -> Result<Option<u64>> {
+        match self.get_global_trie_row()? {
+            Some(block_n) => Ok(block_n),
+            None => match self.get_global_trie_checkpoint()? {
+                Some(checkpoint) => Ok(Some(checkpoint.block_n)),
+                None => self.get_latest_block_n(),
+            },
+        }
+    }
+}
@@ -8,6 +8,7 @@ use db_metrics::DbMetrics;
 use mp_chain_config::ChainConfig;
 use mp_rpc::EmittedEvent;
 use mp_utils::service::{MadaraServiceId, PowerOfTwo, Service, ServiceId};
+use pruning::PruningMode;
 use rocksdb::backup::{BackupEngine, BackupEngineOptions};
 use rocksdb::{
     BoundColumnFamily, ColumnFamilyDescriptor, DBWithThreadMode, Env, FlushOptions, MultiThreaded, WriteOptions,
@@ -17,7 +18,8 @@ use snapshots::Snapshots;
 use starknet_types_core::felt::Felt;
 use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex, RwLock};
 use std::{fmt, fs};
 use tokio::sync::{mpsc, oneshot};
 
@@ -27,16 +29,29 @@ mod rocksdb_options;
 mod rocksdb_snapshot;
 mod snapshots;
 
+pub mod address_activity_db;
 pub mod block_db;
 pub mod bonsai_db;
+pub mod chain_head;
 pub mod class_db;
 pub mod contract_db;
 pub mod db_block_id;
 pub mod db_metrics;
+pub mod devnet_control;
 pub mod devnet_db;
+pub mod event_index_db;
+pub mod global_trie_progress;
 pub mod l1_db;
 pub mod mempool_db;
+pub mod migrations;
+pub mod pipeline_gaps;
+pub mod pruning;
+pub mod read_scope;
 pub mod storage_updates;
+pub mod sync_control;
+pub mod sync_history_db;
+pub mod sync_pressure;
+pub mod sync_status;
 pub mod tests;
 
 pub use bonsai_db::GlobalTrie;
@@ -60,6 +75,27 @@ pub fn open_rocksdb(path: &Path) -> anyhow::Result<Arc<DB>> {
     Ok(Arc::new(db))
 }
 
+/// Opens the database at `path` without the ability to write to it, see [`MadaraBackend::open_read_only`].
+///
+/// The columns cannot be created in read-only mode: the ones missing from a database written by an older node are not
+/// opened.
+pub fn open_rocksdb_read_only(path: &Path) -> anyhow::Result<Arc<DB>> {
+    let opts = rocksdb_global_options()?;
+    tracing::debug!("opening db read-only at {:?}", path.display());
+    let existing_columns = DB::list_cf(&opts, path)?;
+    let db = DB::open_cf_descriptors_read_only(
+        &opts,
+        path,
+        Column::ALL
+            .iter()
+            .filter(|col| existing_columns.iter().any(|name| name == col.rocksdb_name()))
+            .map(|col| ColumnFamilyDescriptor::new(col.rocksdb_name(), col.rocksdb_options())),
+        false,
+    )?;
+
+    Ok(Arc::new(db))
+}
+
 /// This runs in another thread as the backup engine is not thread safe
 fn spawn_backup_db_task(
     backup_dir: &Path,
@@ -87,7 +123,8 @@ fn spawn_backup_db_task(
 
     db_restored_cb.send(()).ok().context("Receiver dropped")?;
 
-    while let Some(BackupRequest { callback, db }) = recv.blocking_recv() {
+    while let Some(BackupRequest { callback, db, import_lock }) = recv.blocking_recv() {
+        let _import = import_lock.lock().expect("Poisoned lock");
         engine.create_new_backup_flush(&db, true).context("Creating rocksdb backup")?;
         let _ = callback.send(());
     }
@@ -108,6 +145,8 @@ pub enum Column {
     BlockHashToBlockN,
     /// One To One
     BlockNToStateDiff,
+    /// block_n => Signature of the block hash by the sequencer
+    ConsensusSignatures,
     /// Meta column for block storage (sync tip, pending block)
     BlockStorageMeta,
 
@@ -154,6 +193,16 @@ pub enum Column {
     Devnet,
 
     MempoolTransactions,
+
+    /// unix timestamp => Sync progress over the period ending at that time
+    SyncHistory,
+
+    /// (address, block_n, kind, index) => (), see [`address_activity_db`]
+    AddressActivity,
+    /// (from_address, key0, block_n, event_n) => (), see [`event_index_db`]
+    EventIndex,
+    /// (key0, block_n, event_n) => (), see [`event_index_db`]
+    EventKeyIndex,
 }
 
 impl fmt::Debug for Column {
@@ -178,6 +227,7 @@ impl Column {
             BlockHashToBlockN,
             BlockStorageMeta,
             BlockNToStateDiff,
+            ConsensusSignatures,
             ClassInfo,
             ClassCompiled,
             PendingClassInfo,
@@ -201,6 +251,10 @@ impl Column {
             PendingContractStorage,
             Devnet,
             MempoolTransactions,
+            SyncHistory,
+            AddressActivity,
+            EventIndex,
+            EventKeyIndex,
         ]
     };
     pub const NUM_COLUMNS: usize = Self::ALL.len();
@@ -214,6 +268,7 @@ impl Column {
             BlockHashToBlockN => "block_hash_to_block_n",
             BlockStorageMeta => "block_storage_meta",
             BlockNToStateDiff => "block_n_to_state_diff",
+            ConsensusSignatures => "consensus_signatures",
             BonsaiContractsTrie => "bonsai_contracts_trie",
             BonsaiContractsFlat => "bonsai_contracts_flat",
             BonsaiContractsLog => "bonsai_contracts_log",
@@ -237,6 +292,10 @@ impl Column {
             PendingContractStorage => "pending_contract_storage",
             Devnet => "devnet",
             MempoolTransactions => "mempool_transactions",
+            SyncHistory => "sync_history",
+            AddressActivity => "address_activity",
+            EventIndex => "event_index",
+            EventKeyIndex => "event_key_index",
         }
     }
 }
@@ -412,14 +471,37 @@ impl EventChannels {
 /// Madara client database backend singleton.
 pub struct MadaraBackend {
     backup_handle: Option<mpsc::Sender<BackupRequest>>,
+    /// Requests to the pruning thread, `None` in archive mode. See [`MadaraBackend::prune_if_due`].
+    pruning_handle: Option<mpsc::Sender<()>>,
     db: Arc<DB>,
     chain_config: Arc<ChainConfig>,
     db_metrics: DbMetrics,
     snapshots: Arc<Snapshots>,
     trie_log_config: TrieLogConfig,
+    pruning_mode: PruningMode,
+    /// See [`MadaraBackend::get_oldest_state_block_n`], 0 when the state was never pruned.
+    oldest_state_block_n: AtomicU64,
     sender_block_info: tokio::sync::broadcast::Sender<mp_block::MadaraBlockInfo>,
     sender_event: EventChannels,
+    sender_chain_head: tokio::sync::broadcast::Sender<chain_head::ChainHeadUpdate>,
     write_opt_no_wal: WriteOptions,
+    /// Whether a pending block may be stored, see [`MadaraBackend::clear_pending_block`]. This starts as `true`, as
+    /// the pending block of the previous run may still be in the database. The pending block is written and cleared
+    /// while holding this lock.
+    pending_may_exist: Mutex<bool>,
+    /// Held while a block is stored, which takes several writes, and while the database is backed up, so that a backup
+    /// does not capture part of a block.
+    import_lock: Arc<Mutex<()>>,
+    /// Number of blocks fetched by the sync pipeline, see [`MadaraBackend::import_backlog`].
+    sync_fetched_blocks: AtomicU64,
+    /// Latest status published by the sync, see [`MadaraBackend::get_sync_status`].
+    sync_status: RwLock<Option<sync_status::SyncStatus>>,
+    /// Operator control over the sync, see [`MadaraBackend::get_sync_control`].
+    sync_control: tokio::sync::watch::Sender<sync_control::SyncControl>,
+    /// Devnet control over the produced blocks, see [`MadaraBackend::get_devnet_control`].
+    devnet_control: tokio::sync::watch::Sender<devnet_control::DevnetControl>,
+    /// Whether the database was opened with [`MadaraBackend::open_read_only`].
+    read_only: bool,
     #[cfg(any(test, feature = "testing"))]
     _temp_dir: Option<tempfile::TempDir>,
 }
@@ -449,6 +531,8 @@ impl DatabaseService {
     /// * `backup_dir` - Optional path to the backup directory.
     /// * `restore_from_latest_backup` - Whether to restore the database from the latest backup.
     /// * `chain_config` - The chain configuration.
+    /// * `trie_log_config` - How far back the global tries can be read.
+    /// * `pruning_mode` - Which part of the historical state is kept.
     ///
     /// # Returns
     ///
@@ -460,6 +544,7 @@ impl DatabaseService {
         restore_from_latest_backup: bool,
         chain_config: Arc<ChainConfig>,
         trie_log_config: TrieLogConfig,
+        pruning_mode: PruningMode,
     ) -> anyhow::Result<Self> {
         tracing::info!("💾 Opening database at: {}", base_path.display());
 
@@ -469,12 +554,19 @@ impl DatabaseService {
             restore_from_latest_backup,
             chain_config,
             trie_log_config,
+            pruning_mode,
         )
         .await?;
 
         Ok(Self { handle })
     }
 
+    /// Opens the database without writing to it, see [`MadaraBackend::open_read_only`].
+    pub fn open_read_only(base_path: &Path, chain_config: Arc<ChainConfig>) -> anyhow::Result<Self> {
+        tracing::info!("💾 Opening database read-only at: {}", base_path.display());
+        Ok(Self { handle: MadaraBackend::open_read_only(base_path, chain_config)? })
+    }
+
     pub fn backend(&self) -> &Arc<MadaraBackend> {
         &self.handle
     }
@@ -494,13 +586,32 @@ impl ServiceId for DatabaseService {
     }
 }
 
+/// Restores the latest backup of the directory `backup_dir` into the database at `db_config_dir`, replacing it. This
+/// must be called before the database is opened.
+pub fn restore_latest_backup(backup_dir: &Path, db_config_dir: &Path) -> anyhow::Result<()> {
+    let backup_opts = BackupEngineOptions::new(backup_dir).context("Creating backup options")?;
+    let mut engine = BackupEngine::open(&backup_opts, &Env::new().context("Creating rocksdb env")?)
+        .context("Opening backup engine")?;
+    let db_path = db_config_dir.join("db");
+    fs::create_dir_all(&db_path).with_context(|| format!("Creating parent directories {:?}", db_path))?;
+
+    tracing::info!("⏳ Restoring latest backup from {}...", backup_dir.display());
+    let opts = rocksdb::backup::RestoreOptions::default();
+    engine.restore_from_latest_backup(&db_path, &db_path, &opts).context("Restoring database")?;
+    Ok(())
+}
+
 struct BackupRequest {
     callback: oneshot::Sender<()>,
     db: Arc<DB>,
+    import_lock: Arc<Mutex<()>>,
 }
 
 impl Drop for MadaraBackend {
     fn drop(&mut self) {
+        if self.read_only {
+            return;
+        }
         tracing::info!("⏳ Gracefully closing the database...");
         self.flush().expect("Error when flushing the database"); // flush :)
     }
@@ -516,18 +627,31 @@ impl MadaraBackend {
         let temp_dir = tempfile::TempDir::with_prefix("madara-test").unwrap();
         let db = open_rocksdb(temp_dir.as_ref()).unwrap();
         let snapshots = Arc::new(Snapshots::new(Arc::clone(&db), None, Some(0), 5));
-        Arc::new(Self {
+        let backend = Arc::new(Self {
             backup_handle: None,
+            pruning_handle: None,
             db,
             chain_config,
             db_metrics: DbMetrics::register().unwrap(),
             snapshots,
             trie_log_config: Default::default(),
+            pruning_mode: Default::default(),
+            oldest_state_block_n: AtomicU64::new(0),
             sender_block_info: tokio::sync::broadcast::channel(100).0,
             sender_event: EventChannels::new(100),
+            sender_chain_head: tokio::sync::broadcast::channel(100).0,
             write_opt_no_wal: make_write_opt_no_wal(),
+            pending_may_exist: Mutex::new(true),
+            import_lock: Default::default(),
+            sync_fetched_blocks: AtomicU64::new(0),
+            sync_status: RwLock::new(None),
+            sync_control: tokio::sync::watch::Sender::new(Default::default()),
+            devnet_control: tokio::sync::watch::Sender::new(Default::default()),
+            read_only: false,
             _temp_dir: Some(temp_dir),
-        })
+        });
+        backend.write_events_indexed().unwrap();
+        backend
     }
 
     /// Open the db.
@@ -537,6 +661,7 @@ impl MadaraBackend {
         restore_from_latest_backup: bool,
         chain_config: Arc<ChainConfig>,
         trie_log_config: TrieLogConfig,
+        pruning_mode: PruningMode,
     ) -> anyhow::Result<Arc<MadaraBackend>> {
         // check if the db version is compatible with the current binary
         tracing::debug!("checking db version");
@@ -569,6 +694,11 @@ impl MadaraBackend {
 
         let db = open_rocksdb(&db_path)?;
         let current_block_n = get_latest_block_n(&db).context("Getting latest block_n from database")?;
+        let oldest_state_block_n =
+            pruning::get_oldest_state_block_n(&db).context("Getting oldest state block_n from database")?;
+        if let (PruningMode::Archive, Some(block_n)) = (pruning_mode, oldest_state_block_n) {
+            tracing::warn!("The state cannot be read before block #{block_n}, it was pruned or comes from a snapshot");
+        }
         let snapshots = Arc::new(Snapshots::new(
             Arc::clone(&db),
             current_block_n,
@@ -576,26 +706,118 @@ impl MadaraBackend {
             trie_log_config.snapshot_interval,
         ));
 
+        let (pruning_handle, pruning_recv) = match pruning_mode {
+            PruningMode::Archive => (None, None),
+            PruningMode::Pruned { .. } => {
+                let (sender, receiver) = mpsc::channel(1);
+                (Some(sender), Some(receiver))
+            }
+        };
+
         let backend = Arc::new(Self {
             db_metrics: DbMetrics::register().context("Registering db metrics")?,
             backup_handle,
+            pruning_handle,
             db,
             chain_config: Arc::clone(&chain_config),
             snapshots,
             trie_log_config,
+            pruning_mode,
+            oldest_state_block_n: AtomicU64::new(oldest_state_block_n.unwrap_or(0)),
             sender_block_info: tokio::sync::broadcast::channel(100).0,
             sender_event: EventChannels::new(100),
+            sender_chain_head: tokio::sync::broadcast::channel(100).0,
             write_opt_no_wal: make_write_opt_no_wal(),
+            pending_may_exist: Mutex::new(true),
+            import_lock: Default::default(),
+            sync_fetched_blocks: AtomicU64::new(0),
+            sync_status: RwLock::new(None),
+            sync_control: tokio::sync::watch::Sender::new(Default::default()),
+            devnet_control: tokio::sync::watch::Sender::new(Default::default()),
+            read_only: false,
             #[cfg(any(test, feature = "testing"))]
             _temp_dir: None,
         });
         backend.check_configuration()?;
+        if current_block_n.is_none() {
+            // Every block of a new database is indexed as it is stored.
+            backend.write_events_indexed().context("Marking the database as indexed")?;
+        }
+        backend.run_migrations()?;
+        backend.resume_revert().context("Completing an interrupted revert")?;
+        backend.reconcile_global_tries().context("Reconciling the global tries with the blocks")?;
+        if let Some(pruning_recv) = pruning_recv {
+            pruning::spawn_pruning_task(Arc::downgrade(&backend), pruning_recv);
+        }
         backend.update_metrics();
         Ok(backend)
     }
 
+    /// Opens the database without writing to it, to inspect it while another node may be using it. Unlike
+    /// [`MadaraBackend::open`], the migrations, the completion of an interrupted revert and the reconciliation of the
+    /// global tries are not run: the database is refused when it needs a migration or a revert, and the global tries
+    /// may be ahead of or behind the blocks.
+    pub fn open_read_only(db_config_dir: &Path, chain_config: Arc<ChainConfig>) -> anyhow::Result<Arc<MadaraBackend>> {
+        let db_path = db_config_dir.join("db");
+        if !db_path.exists() {
+            anyhow::bail!("There is no database at {}", db_config_dir.display());
+        }
+        db_version::check_db_version(db_config_dir).context("Checking database version")?;
+
+        let db = open_rocksdb_read_only(&db_path)?;
+        let migrations = migrations::pending_migrations_of(&db)?;
+        if let Some(migration) = migrations.last() {
+            anyhow::bail!(
+                "The database must be migrated to schema version {}, start the node once to run the migrations",
+                migration.version
+            );
+        }
+        if db.get_cf(&db.get_column(Column::BlockStorageMeta), storage_updates::ROW_REVERT_TO)?.is_some() {
+            anyhow::bail!("A revert of the database was interrupted, start the node once to complete it");
+        }
+
+        let current_block_n = get_latest_block_n(&db).context("Getting latest block_n from database")?;
+        let oldest_state_block_n =
+            pruning::get_oldest_state_block_n(&db).context("Getting oldest state block_n from database")?;
+        let trie_log_config = TrieLogConfig::default();
+        let snapshots = Arc::new(Snapshots::new(
+            Arc::clone(&db),
+            current_block_n,
+            Some(trie_log_config.max_kept_snapshots),
+            trie_log_config.snapshot_interval,
+        ));
+
+        let backend = Arc::new(Self {
+            db_metrics: DbMetrics::register().context("Registering db metrics")?,
+            backup_handle: None,
+            pruning_handle: None,
+            db,
+            chain_config,
+            snapshots,
+            trie_log_config,
+            pruning_mode: PruningMode::Archive,
+            oldest_state_block_n: AtomicU64::new(oldest_state_block_n.unwrap_or(0)),
+            sender_block_info: tokio::sync::broadcast::channel(100).0,
+            sender_event: EventChannels::new(100),
+            sender_chain_head: tokio::sync::broadcast::channel(100).0,
+            write_opt_no_wal: make_write_opt_no_wal(),
+            pending_may_exist: Mutex::new(true),
+            import_lock: Default::default(),
+            sync_fetched_blocks: AtomicU64::new(0),
+            sync_status: RwLock::new(None),
+            sync_control: tokio::sync::watch::Sender::new(Default::default()),
+            devnet_control: tokio::sync::watch::Sender::new(Default::default()),
+            read_only: true,
+            #[cfg(any(test, feature = "testing"))]
+            _temp_dir: None,
+        });
+        backend.check_configuration()?;
+        Ok(backend)
+    }
+
     pub fn flush(&self) -> anyhow::Result<()> {
         tracing::debug!("doing a db flush");
+        let started = std::time::Instant::now();
         let mut opts = FlushOptions::default();
         opts.set_wait(true);
         // we have to collect twice here :/
@@ -603,6 +825,7 @@ impl MadaraBackend {
         let columns = columns.iter().collect::<Vec<_>>();
 
         self.db.flush_cfs_opt(&columns, &opts).context("Flushing database")?;
+        self.db_metrics.flush_time.record(started.elapsed().as_secs_f64() * 1000.0, &[]);
 
         Ok(())
     }
@@ -610,23 +833,58 @@ impl MadaraBackend {
     #[tracing::instrument(skip(self))]
     pub async fn backup(&self) -> anyhow::Result<()> {
         let (callback_sender, callback_recv) = oneshot::channel();
-        let _res = self
-            .backup_handle
-            .as_ref()
-            .context("backups are not enabled")?
-            .try_send(BackupRequest { callback: callback_sender, db: Arc::clone(&self.db) });
+        let _res = self.backup_handle.as_ref().context("backups are not enabled")?.try_send(BackupRequest {
+            callback: callback_sender,
+            db: Arc::clone(&self.db),
+            import_lock: Arc::clone(&self.import_lock),
+        });
         callback_recv.await.context("Backups task died :(")?;
         Ok(())
     }
 
+    /// Creates a backup of the database in the directory `backup_dir`, while the node is running. The column families
+    /// are flushed together first, so the backup holds every block stored so far along with its state and the chain
+    /// head: a node restored from it resumes the sync after its latest block. Backups are incremental, a directory
+    /// can hold several of them.
+    ///
+    /// Storing a block takes several writes: the backup waits for the block being stored, and blocks are not stored
+    /// until it is done.
+    #[tracing::instrument(skip(self))]
+    pub async fn backup_to(&self, backup_dir: PathBuf) -> anyhow::Result<()> {
+        let db = Arc::clone(&self.db);
+        let import_lock = Arc::clone(&self.import_lock);
+        tokio::task::spawn_blocking(move || {
+            let _import = import_lock.lock().expect("Poisoned lock");
+            let mut backup_opts = BackupEngineOptions::new(&backup_dir).context("Creating backup options")?;
+            let cores = std::thread::available_parallelism().map(|e| e.get() as i32).unwrap_or(1);
+            backup_opts.set_max_background_operations(cores);
+            let mut engine = BackupEngine::open(&backup_opts, &Env::new().context("Creating rocksdb env")?)
+                .context("Opening backup engine")?;
+            engine.create_new_backup_flush(&db, true).context("Creating rocksdb backup")
+        })
+        .await
+        .context("Backup task panicked")?
+    }
+
     // tries
 
+    /// Number of blocks the global tries can be reverted by.
+    pub fn max_saved_trie_logs(&self) -> usize {
+        // In pruned mode, the trie logs do not go further back than the state.
+        match self.pruning_mode {
+            PruningMode::Archive => self.trie_log_config.max_saved_trie_logs,
+            PruningMode::Pruned { keep_blocks } => {
+                self.trie_log_config.max_saved_trie_logs.min(usize::try_from(keep_blocks).unwrap_or(usize::MAX))
+            }
+        }
+    }
+
     pub(crate) fn get_bonsai<H: StarkHash + Send + Sync>(
         &self,
         map: DatabaseKeyMapping,
     ) -> BonsaiStorage<BasicId, BonsaiDb, H> {
         let config = BonsaiStorageConfig {
-            max_saved_trie_logs: Some(self.trie_log_config.max_saved_trie_logs),
+            max_saved_trie_logs: Some(self.max_saved_trie_logs()),
             max_saved_snapshots: Some(self.trie_log_config.max_kept_snapshots),
             snapshot_interval: self.trie_log_config.snapshot_interval,
         };
@@ -663,6 +921,13 @@ impl MadaraBackend {
         })
     }
 
+    /// Whether the global tries can be read at block `block_n` from a kept snapshot, without going through their trie
+    /// logs. Snapshots are taken every [`TrieLogConfig::snapshot_interval`] blocks, and only kept in memory: they are
+    /// lost on restart.
+    pub fn has_trie_snapshot(&self, block_n: u64) -> bool {
+        self.snapshots.contains(block_n)
+    }
+
     /// Returns the total storage size
     pub fn update_metrics(&self) -> u64 {
         self.db_metrics.update(&self.db)
@@ -0,0 +1,172 @@
+//! Migrations of the database schema.
+//!
+//! The schema version of a database is stored in a row of the block meta column. On startup, the migrations newer
+//! than this version are run in order, and the version is bumped after each of them, so that a database written by an
+//! older node does not need a resync when a column is added or re-encoded. A database with a schema version newer
+//! than the ones known to this node is refused.
+//!
+//! Migrations must be idempotent: when the node crashes in the middle of one, it is run again from the start. Layout
+//! changes which cannot be migrated bump the `.db-versions.yml` version instead, which requires a resync.
+//!
+//! A new database is created at the latest schema version, without running any migration.
+
+use crate::db_block_id::DbBlockId;
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError, WriteBatchWithTransaction, DB};
+use anyhow::Context;
+use mp_block::MadaraBlock;
+use std::path::Path;
+use std::time::Instant;
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+const ROW_SCHEMA_VERSION: &[u8] = b"schema_version";
+
+/// Number of blocks re-indexed in a single write batch.
+const MIGRATION_BATCH_SIZE: u64 = 1_000;
+
+/// A change of the database schema, applied to the databases written by older nodes.
+pub struct Migration {
+    /// Schema version of the database once this migration has run.
+    pub version: u32,
+    pub name: &'static str,
+    run: fn(&MadaraBackend) -> Result<()>,
+}
+
+/// Every migration, by increasing version.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "Build the address activity index", run: build_address_activity_index },
+    Migration { version: 2, name: "Build the event index", run: build_event_index },
+    Migration { version: 3, name: "Build the event key index", run: build_event_key_index },
+];
+
+/// Schema version of the databases created by this node.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+fn get_schema_version(db: &DB) -> Result<Option<u32>> {
+    let col = db.get_column(Column::BlockStorageMeta);
+    let Some(res) = db.get_cf(&col, ROW_SCHEMA_VERSION)? else { return Ok(None) };
+    Ok(Some(bincode::deserialize(&res)?))
+}
+
+/// The migrations which are not applied to a database at schema version `version` yet.
+fn pending_since(version: u32) -> anyhow::Result<&'static [Migration]> {
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "The database schema version {version} was written by a newer node, this node only knows versions up to \
+             {SCHEMA_VERSION}"
+        );
+    }
+    Ok(&MIGRATIONS[MIGRATIONS.partition_point(|migration| migration.version <= version)..])
+}
+
+/// The migrations which would run when opening the database at `db_config_dir`, without running them. This is used
+/// for dry runs: the database is opened read-only.
+pub fn pending_migrations(db_config_dir: &Path) -> anyhow::Result<&'static [Migration]> {
+    let db_path = db_config_dir.join("db");
+    if !db_path.exists() {
+        return Ok(&[]);
+    }
+    let db = crate::open_rocksdb_read_only(&db_path)?;
+    pending_migrations_of(&db)
+}
+
+/// The migrations which would run on the database `db`.
+pub(crate) fn pending_migrations_of(db: &DB) -> anyhow::Result<&'static [Migration]> {
+    match get_schema_version(db).context("Getting database schema version")? {
+        Some(version) => pending_since(version),
+        None if crate::block_db::get_latest_block_n(db)?.is_none() => Ok(&[]),
+        None => Ok(MIGRATIONS),
+    }
+}
+
+impl MadaraBackend {
+    fn write_schema_version(&self, version: u32) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        // Written with the write-ahead log, once the migration is flushed.
+        self.db.put_cf(&col, ROW_SCHEMA_VERSION, bincode::serialize(&version)?)?;
+        Ok(())
+    }
+
+    /// Runs the migrations newer than the schema version of the database.
+    pub(crate) fn run_migrations(&self) -> anyhow::Result<()> {
+        let migrations = match get_schema_version(&self.db).context("Getting database schema version")? {
+            Some(version) => pending_since(version)?,
+            // Databases written before schema versions were introduced have no version.
+            None if self.get_latest_block_n()?.is_some() => MIGRATIONS,
+            None => {
+                self.write_schema_version(SCHEMA_VERSION)?;
+                return Ok(());
+            }
+        };
+
+        for migration in migrations {
+            tracing::info!("⏳ Migrating the database to schema version {}: {}...", migration.version, migration.name);
+            let start = Instant::now();
+            (migration.run)(self).with_context(|| format!("Running database migration {}", migration.version))?;
+            self.flush()?;
+            self.write_schema_version(migration.version)?;
+            tracing::info!("✅ Migrated the database to schema version {} in {:?}", migration.version, start.elapsed());
+        }
+        // Every block is in the event indexes once their migrations have run.
+        self.write_events_indexed()?;
+        Ok(())
+    }
+
+    /// Calls `f` on every stored block and its state diff, writing the batches it fills.
+    fn reindex_blocks(
+        &self,
+        mut f: impl FnMut(&mut WriteBatchWithTransaction, &MadaraBlock, &mp_state_update::StateDiff),
+    ) -> Result<()> {
+        let Some(latest_block_n) = self.get_latest_block_n()? else { return Ok(()) };
+        let oldest_block_n = self.get_oldest_backfilled_block_n()?.unwrap_or(0);
+
+        let mut batch = WriteBatchWithTransaction::default();
+        let range = oldest_block_n..=latest_block_n;
+        for (block_n, block) in range.clone().zip(self.iter_blocks(range)) {
+            // The blocks iterator skips the missing blocks.
+            let block = block?;
+            if block.info.header.block_number != block_n {
+                return Err(MadaraStorageError::InconsistentStorage(format!("Missing block #{block_n}").into()));
+            }
+            let Some(state_diff) = self.get_block_state_diff(&DbBlockId::Number(block_n))? else {
+                return Err(MadaraStorageError::InconsistentStorage(format!("Missing state diff #{block_n}").into()));
+            };
+            f(&mut batch, &block, &state_diff);
+
+            if (block_n + 1) % MIGRATION_BATCH_SIZE == 0 {
+                self.db.write_opt(std::mem::take(&mut batch), &self.write_opt_no_wal)?;
+                let done = block_n + 1 - oldest_block_n;
+                let total = latest_block_n + 1 - oldest_block_n;
+                tracing::info!("⏳ Migrated {done}/{total} blocks ({}%)", done * 100 / total);
+            }
+        }
+        self.db.write_opt(batch, &self.write_opt_no_wal)?;
+        Ok(())
+    }
+}
+
+fn build_address_activity_index(backend: &MadaraBackend) -> Result<()> {
+    backend.reindex_blocks(|batch, block, state_diff| backend.address_activity_index_block(batch, block, state_diff))
+}
+
+fn build_event_index(backend: &MadaraBackend) -> Result<()> {
+    backend.reindex_blocks(|batch, block, _| backend.event_address_index_block(batch, block))
+}
+
+fn build_event_key_index(backend: &MadaraBackend) -> Result<()> {
+    backend.reindex_blocks(|batch, block, _| backend.event_key_index_block(batch, block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_migrations() {
+        assert_eq!(pending_since(0).unwrap().len(), MIGRATIONS.len());
+        assert_eq!(pending_since(1).unwrap().iter().map(|migration| migration.version).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(pending_since(SCHEMA_VERSION).unwrap().is_empty());
+        assert!(pending_since(SCHEMA_VERSION + 1).is_err());
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+    }
+}
@@ -0,0 +1,194 @@
+//! Gaps left between the stages of the block pipeline by a crash.
+//!
+//! Blocks, their state and their classes are stored in separate column families, written without the write-ahead log.
+//! When the node crashes, each column family only keeps the writes up to its last flush: the classes declared in the
+//! latest blocks may be missing while the blocks themselves are stored. The sync resumes after the latest block and
+//! would never fetch these classes again, so it first looks for the blocks missing classes with
+//! [`MadaraBackend::find_classes_gap`], and fetches their classes again.
+//!
+//! Classes are written in block order, so a gap can only be at the tip of the chain. Most blocks declare no class and
+//! tell nothing about the classes column: they are skipped while looking for the gap.
+
+use crate::chain_head::ChainHead;
+use crate::{MadaraBackend, MadaraStorageError};
+use mp_block::BlockId;
+use serde::{Deserialize, Serialize};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+/// Number of blocks below the latest block which are looked at for missing classes. Column families are flushed long
+/// before this many blocks are imported.
+pub const CLASSES_GAP_MAX_DEPTH: u64 = 10_000;
+
+/// Blocks stored without the classes they declare.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassesGap {
+    /// Latest block below the gap which declares classes, all of them stored. Every older block has its classes. This
+    /// is `None` when no such block was found within [`CLASSES_GAP_MAX_DEPTH`] blocks.
+    pub classes_head: Option<u64>,
+    /// Blocks declaring classes which are not stored, oldest first.
+    pub blocks: Vec<u64>,
+}
+
+impl ClassesGap {
+    /// The block the sync has to re-import from.
+    pub fn first_block(&self) -> u64 {
+        self.blocks[0]
+    }
+}
+
+/// The gaps between the stages of the block pipeline, and what the node does about them on restart.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineGaps {
+    pub chain_head: ChainHead,
+    /// Blocks missing classes, if any.
+    pub classes_gap: Option<ClassesGap>,
+    /// Block the L2 sync resumes from on restart. The missing classes are fetched again before that.
+    pub l2_sync_resume_block_n: u64,
+    /// Number of blocks left to backfill, down to the genesis block, when the node was bootstrapped from a state
+    /// snapshot.
+    pub blocks_to_backfill: u64,
+    /// Number of blocks not yet confirmed on L1.
+    pub blocks_to_confirm_on_l1: u64,
+}
+
+impl MadaraBackend {
+    /// Whether the classes declared in block `block_n` are all stored. `None` when the block declares no class, or is
+    /// not stored.
+    fn has_declared_classes(&self, block_n: u64) -> Result<Option<bool>> {
+        let Some(state_diff) = self.get_block_state_diff(&BlockId::Number(block_n))? else {
+            return Ok(None);
+        };
+        let mut class_hashes = state_diff
+            .declared_classes
+            .iter()
+            .map(|item| &item.class_hash)
+            .chain(&state_diff.deprecated_declared_classes)
+            .peekable();
+        if class_hashes.peek().is_none() {
+            return Ok(None);
+        }
+        for class_hash in class_hashes {
+            if !self.contains_class(class_hash)? {
+                return Ok(Some(false));
+            }
+        }
+        Ok(Some(true))
+    }
+
+    /// Finds the blocks at the tip of the chain which are stored without the classes they declare, looking at most
+    /// [`CLASSES_GAP_MAX_DEPTH`] blocks below the latest block. Returns `None` when there are none.
+    #[tracing::instrument(skip(self), fields(module = "PipelineGaps"))]
+    pub fn find_classes_gap(&self) -> Result<Option<ClassesGap>> {
+        let Some(latest_block_n) = self.get_latest_block_n()? else {
+            return Ok(None);
+        };
+
+        let mut blocks = vec![];
+        let mut classes_head = None;
+        for block_n in (latest_block_n.saturating_sub(CLASSES_GAP_MAX_DEPTH)..=latest_block_n).rev() {
+            match self.has_declared_classes(block_n)? {
+                Some(true) => {
+                    classes_head = Some(block_n);
+                    break;
+                }
+                Some(false) => blocks.push(block_n),
+                None => {}
+            }
+        }
+
+        if blocks.is_empty() {
+            return Ok(None);
+        }
+        blocks.reverse();
+        Ok(Some(ClassesGap { classes_head, blocks }))
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "PipelineGaps"))]
+    pub fn get_pipeline_gaps(&self) -> Result<PipelineGaps> {
+        let chain_head = self.get_chain_head()?;
+        let classes_gap = self.find_classes_gap()?;
+        let l2_sync_resume_block_n = chain_head.latest_block_n.map_or(0, |block_n| block_n + 1);
+        let blocks_to_confirm_on_l1 = match (chain_head.latest_block_n, chain_head.l1_confirmed_block_n) {
+            (Some(latest), Some(confirmed)) => latest.saturating_sub(confirmed),
+            (Some(latest), None) => latest + 1,
+            (None, _) => 0,
+        };
+
+        Ok(PipelineGaps {
+            chain_head,
+            classes_gap,
+            l2_sync_resume_block_n,
+            blocks_to_backfill: chain_head.oldest_backfilled_block.unwrap_or(0),
+            blocks_to_confirm_on_l1,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Column, DatabaseExt};
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_chain_config::ChainConfig;
+    use mp_class::{
+        CompressedLegacyContractClass, ConvertedClass, LegacyClassInfo, LegacyConvertedClass, LegacyEntryPointsByType,
+    };
+    use mp_state_update::StateDiff;
+    use starknet_types_core::felt::Felt;
+    use std::sync::Arc;
+
+    fn store_block(backend: &MadaraBackend, block_n: u64, class_hash: Option<Felt>) {
+        let block = MadaraMaybePendingBlock {
+            info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                header: Header { block_number: block_n, ..Default::default() },
+                block_hash: Felt::from(block_n),
+                tx_hashes: vec![],
+            }),
+            inner: MadaraBlockInner { transactions: vec![], receipts: vec![] },
+        };
+        let state_diff =
+            StateDiff { deprecated_declared_classes: class_hash.into_iter().collect(), ..Default::default() };
+        let classes = class_hash
+            .map(|class_hash| {
+                ConvertedClass::Legacy(LegacyConvertedClass {
+                    class_hash,
+                    info: LegacyClassInfo {
+                        contract_class: Arc::new(CompressedLegacyContractClass {
+                            program: vec![],
+                            entry_points_by_type: LegacyEntryPointsByType {
+                                constructor: vec![],
+                                external: vec![],
+                                l1_handler: vec![],
+                            },
+                            abi: None,
+                        }),
+                    },
+                })
+            })
+            .into_iter()
+            .collect();
+        backend.store_block(block, state_diff, classes, None, None).unwrap();
+    }
+
+    #[test]
+    fn test_classes_gap() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        for block_n in 0..6 {
+            store_block(&backend, block_n, (block_n % 2 == 1).then(|| Felt::from(0x100 + block_n)));
+        }
+        assert_eq!(backend.find_classes_gap().unwrap(), None);
+        assert_eq!(backend.get_pipeline_gaps().unwrap().l2_sync_resume_block_n, 6);
+
+        // The classes of blocks 3 and 5 were lost in a crash.
+        let col = backend.db.get_column(Column::ClassInfo);
+        for class_hash in [Felt::from(0x103), Felt::from(0x105)] {
+            backend.db.delete_cf(&col, bincode::serialize(&class_hash).unwrap()).unwrap();
+        }
+        let gap = ClassesGap { classes_head: Some(1), blocks: vec![3, 5] };
+        assert_eq!(backend.find_classes_gap().unwrap(), Some(gap.clone()));
+        let gaps = backend.get_pipeline_gaps().unwrap();
+        assert_eq!(gaps.classes_gap, Some(gap));
+        assert_eq!(gaps.l2_sync_resume_block_n, 6);
+    }
+}
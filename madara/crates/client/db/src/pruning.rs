@@ -0,0 +1,288 @@
+//! Pruning of the historical state.
+//!
+//! In archive mode, the default, the state of the chain can be read at every block. In pruned mode, only the state at
+//! the latest blocks is kept: the values of the contract storage, nonces and class hashes which were overwritten
+//! before this retention window are deleted, and the trie logs do not go further back than the window. Blocks, their
+//! transactions, receipts and state diffs are kept in both modes.
+//!
+//! Pruning runs every [`PRUNING_INTERVAL`] blocks, on a thread of its own so that the block import does not wait for
+//! it. It goes through the state diffs of the blocks which left the window, and for every key they updated, only keeps
+//! the value it had at the oldest block of the window. Reading the state before this block returns
+//! [`MadaraStorageError::StatePruned`].
+
+use crate::contract_db::make_storage_key_prefix;
+use crate::db_block_id::DbBlockId;
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError, WriteBatchWithTransaction};
+use rocksdb::{BoundColumnFamily, IteratorMode, ReadOptions};
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Weak};
+use tokio::sync::mpsc;
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+pub(crate) const ROW_OLDEST_STATE_BLOCK: &[u8] = b"oldest_state_block";
+
+/// Pruning runs every this many blocks. This is also the number of state diffs which are read at once while pruning.
+pub const PRUNING_INTERVAL: u64 = 100;
+
+/// Which part of the historical state is kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruningMode {
+    /// The state is kept at every block.
+    #[default]
+    Archive,
+    /// The state is only kept at the latest `keep_blocks` blocks.
+    Pruned { keep_blocks: u64 },
+}
+
+impl PruningMode {
+    /// Oldest block whose state is kept when the latest block is `latest_block_n`, `None` in archive mode.
+    pub fn oldest_kept_block_n(&self, latest_block_n: u64) -> Option<u64> {
+        match self {
+            Self::Archive => None,
+            Self::Pruned { keep_blocks } => Some((latest_block_n + 1).saturating_sub(*keep_blocks)),
+        }
+    }
+}
+
+impl FromStr for PruningMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "archive" {
+            return Ok(Self::Archive);
+        }
+        match s.parse() {
+            Ok(keep_blocks) if keep_blocks > 0 => Ok(Self::Pruned { keep_blocks }),
+            _ => Err(format!("Invalid pruning mode `{s}`, expected `archive` or a number of blocks greater than 0")),
+        }
+    }
+}
+
+impl fmt::Display for PruningMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Archive => write!(f, "archive"),
+            Self::Pruned { keep_blocks } => write!(f, "{keep_blocks}"),
+        }
+    }
+}
+
+/// Spawns the thread pruning the state of `backend` when it receives a request, see
+/// [`MadaraBackend::prune_if_due`]. The thread stops with the backend.
+pub(crate) fn spawn_pruning_task(backend: Weak<MadaraBackend>, mut recv: mpsc::Receiver<()>) {
+    std::thread::Builder::new()
+        .name("madara-pruning".into())
+        .spawn(move || {
+            while recv.blocking_recv().is_some() {
+                let Some(backend) = backend.upgrade() else { return };
+                match backend.prune() {
+                    Ok(Some(block_n)) => tracing::debug!("Pruned the state before block #{block_n}"),
+                    Ok(None) => {}
+                    Err(err) => tracing::error!("❗ Failed to prune the state: {err:#}"),
+                }
+            }
+        })
+        .expect("Spawning the pruning thread");
+}
+
+pub(crate) fn get_oldest_state_block_n(db: &crate::DB) -> Result<Option<u64>> {
+    let col = db.get_column(Column::BlockStorageMeta);
+    let Some(res) = db.get_cf(&col, ROW_OLDEST_STATE_BLOCK)? else { return Ok(None) };
+    Ok(Some(bincode::deserialize(&res)?))
+}
+
+impl MadaraBackend {
+    pub fn pruning_mode(&self) -> PruningMode {
+        self.pruning_mode
+    }
+
+    /// Oldest block at which the state can be read. Returns `None` when the state was never pruned and the node was not
+    /// bootstrapped from a state snapshot.
+    pub fn get_oldest_state_block_n(&self) -> Option<u64> {
+        match self.oldest_state_block_n.load(Ordering::Acquire) {
+            0 => None,
+            block_n => Some(block_n),
+        }
+    }
+
+    /// Returns [`MadaraStorageError::StatePruned`] when the state at block `block_n` was pruned, or is older than the
+    /// state snapshot the node was bootstrapped from.
+    pub(crate) fn check_state_not_pruned(&self, block_n: u64) -> Result<()> {
+        match self.get_oldest_state_block_n() {
+            Some(oldest_block_n) if block_n < oldest_block_n => {
+                Err(MadaraStorageError::StatePruned { block_n, oldest_block_n })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Requests the pruning thread to prune the state when block `block_n` was just stored, every
+    /// [`PRUNING_INTERVAL`] blocks. A request is dropped when the previous one is still pending.
+    pub(crate) fn prune_if_due(&self, block_n: u64) {
+        if block_n % PRUNING_INTERVAL != 0 {
+            return;
+        }
+        if let Some(pruning_handle) = &self.pruning_handle {
+            let _ = pruning_handle.try_send(());
+        }
+    }
+
+    /// Prunes the state which is out of the retention window of the [`PruningMode`]. Returns the new oldest block at
+    /// which the state can be read, or `None` when there was nothing to prune.
+    #[tracing::instrument(skip(self), fields(module = "Pruning"))]
+    pub fn prune(&self) -> Result<Option<u64>> {
+        let Some(latest_block_n) = self.get_latest_block_n()? else { return Ok(None) };
+        let Some(block_n) = self.pruning_mode.oldest_kept_block_n(latest_block_n) else { return Ok(None) };
+        if block_n <= self.get_oldest_state_block_n().unwrap_or(0) {
+            return Ok(None);
+        }
+        self.prune_state_below(block_n)?;
+        Ok(Some(block_n))
+    }
+
+    /// Deletes the values of the state which were overwritten at or before block `block_n`, so that the state can only
+    /// be read from this block onwards.
+    pub(crate) fn prune_state_below(&self, block_n: u64) -> Result<()> {
+        let from = self.get_oldest_state_block_n().unwrap_or(0);
+        let block_n_u32 = u32::try_from(block_n).map_err(|_| MadaraStorageError::InvalidBlockNumber)?;
+        tracing::debug!("Pruning the state from block #{from} to block #{block_n}");
+
+        // Written first, and with the write-ahead log, so that the state being deleted is not read in the meantime,
+        // even after a crash which kept some of the deletes but not this marker.
+        self.oldest_state_block_n.store(block_n, Ordering::Release);
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        self.db.put_cf(&col, ROW_OLDEST_STATE_BLOCK, bincode::serialize(&block_n)?)?;
+
+        let class_hashes_col = self.db.get_column(Column::ContractToClassHashes);
+        let nonces_col = self.db.get_column(Column::ContractToNonces);
+        let storage_col = self.db.get_column(Column::ContractStorage);
+        for chunk_start in (from..block_n).step_by(PRUNING_INTERVAL as usize) {
+            let mut batch = WriteBatchWithTransaction::default();
+            let mut class_hashes = HashSet::new();
+            let mut nonces = HashSet::new();
+            let mut storage = HashSet::new();
+            for pruned_block_n in chunk_start..block_n.min(chunk_start + PRUNING_INTERVAL) {
+                // Blocks older than the state snapshot the node was bootstrapped from are not stored.
+                let Some(state_diff) = self.get_block_state_diff(&DbBlockId::Number(pruned_block_n))? else {
+                    continue;
+                };
+                class_hashes.extend(state_diff.deployed_contracts.iter().map(|item| item.address));
+                class_hashes.extend(state_diff.replaced_classes.iter().map(|item| item.contract_address));
+                nonces.extend(state_diff.nonces.iter().map(|item| item.contract_address));
+                for item in &state_diff.storage_diffs {
+                    storage.extend(
+                        item.storage_entries.iter().map(|entry| make_storage_key_prefix(item.address, entry.key)),
+                    );
+                }
+            }
+
+            for address in class_hashes {
+                self.prune_history_key(&class_hashes_col, &address.to_bytes_be(), block_n_u32, &mut batch)?;
+            }
+            for address in nonces {
+                self.prune_history_key(&nonces_col, &address.to_bytes_be(), block_n_u32, &mut batch)?;
+            }
+            for prefix in storage {
+                self.prune_history_key(&storage_col, &prefix, block_n_u32, &mut batch)?;
+            }
+            self.db.write_opt(batch, &self.write_opt_no_wal)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the values of the key with prefix `prefix` which were overwritten at or before block `block_n`.
+    fn prune_history_key(
+        &self,
+        col: &Arc<BoundColumnFamily>,
+        prefix: &[u8],
+        block_n: u32,
+        batch: &mut WriteBatchWithTransaction,
+    ) -> Result<()> {
+        let start_at = [prefix, &block_n.to_be_bytes() as &[u8]].concat();
+        let mut options = ReadOptions::default();
+        options.set_prefix_same_as_start(true);
+        let mode = IteratorMode::From(&start_at, rocksdb::Direction::Reverse);
+
+        // The first value is the one at block `block_n`, which is kept.
+        for res in self.db.iterator_cf_opt(col, options, mode).skip(1) {
+            let (key, _) = res?;
+            batch.delete_cf(col, key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_chain_config::ChainConfig;
+    use mp_state_update::{ContractStorageDiffItem, StateDiff, StorageEntry};
+
+    #[test]
+    fn test_pruning_mode_from_str() {
+        assert_eq!("archive".parse(), Ok(PruningMode::Archive));
+        assert_eq!("1000".parse(), Ok(PruningMode::Pruned { keep_blocks: 1000 }));
+        assert!("0".parse::<PruningMode>().is_err());
+        assert!("full".parse::<PruningMode>().is_err());
+        assert_eq!(PruningMode::Pruned { keep_blocks: 10 }.oldest_kept_block_n(5), Some(0));
+        assert_eq!(PruningMode::Pruned { keep_blocks: 10 }.oldest_kept_block_n(20), Some(11));
+    }
+
+    #[test]
+    fn test_prune_state() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let (address, key) = (Felt::from(0x10), Felt::from(0x20));
+        for block_n in 0..6 {
+            let block = MadaraMaybePendingBlock {
+                info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                    header: Header { block_number: block_n, ..Default::default() },
+                    block_hash: Felt::from(block_n),
+                    tx_hashes: vec![],
+                }),
+                inner: MadaraBlockInner { transactions: vec![], receipts: vec![] },
+            };
+            // The storage value is only updated at even blocks.
+            let storage_entries =
+                if block_n % 2 == 0 { vec![StorageEntry { key, value: Felt::from(block_n + 1) }] } else { vec![] };
+            let state_diff = StateDiff {
+                storage_diffs: vec![ContractStorageDiffItem { address, storage_entries }],
+                ..Default::default()
+            };
+            backend.store_block(block, state_diff, vec![], None, None).unwrap();
+        }
+
+        backend.prune_state_below(3).unwrap();
+        assert_eq!(backend.get_oldest_state_block_n(), Some(3));
+        assert_eq!(
+            backend.get_contract_storage_at(&DbBlockId::Number(3), &address, &key).unwrap(),
+            Some(Felt::from(3))
+        );
+        assert_eq!(
+            backend.get_contract_storage_at(&DbBlockId::Number(5), &address, &key).unwrap(),
+            Some(Felt::from(5))
+        );
+        assert!(matches!(
+            backend.get_contract_storage_at(&DbBlockId::Number(2), &address, &key),
+            Err(MadaraStorageError::StatePruned { block_n: 2, oldest_block_n: 3 })
+        ));
+
+        // Only the value at block 2, which is the value at block 3, is left of the values before block 3.
+        let col = backend.db.get_column(Column::ContractStorage);
+        let prefix = make_storage_key_prefix(address, key);
+        let mut options = ReadOptions::default();
+        options.set_prefix_same_as_start(true);
+        let mode = IteratorMode::From(&prefix, rocksdb::Direction::Forward);
+        let keys = backend.db.iterator_cf_opt(&col, options, mode).map(|res| res.unwrap().0).collect::<Vec<_>>();
+        let block_numbers =
+            keys.iter().map(|key| u32::from_be_bytes(key[64..].try_into().unwrap())).collect::<Vec<_>>();
+        assert_eq!(block_numbers, vec![2, 4]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Consistent reads for a group of related reads.
+//!
+//! Some consumers, such as the JSON-RPC server when it processes a batch, will issue a lot of reads against the
+//! same few blocks, and expect them to agree with each other. Within a [`ReadScope`], the blocks, classes, contract
+//! states and indexes are read from a RocksDB snapshot taken on the first read of the scope: a block imported while
+//! the scope is alive is not seen by any of its reads, which never mix the state of two chain heads. The global tries
+//! are not read through the snapshot.
+//!
+//! Resolving a [`BlockId`] to a [`DbBlockId`] and reading a block info are also only done once: all subsequent reads
+//! are served from memory.
+//!
+//! The pending block is memoized as a whole, info and inner together: every read of the pending block in a scope sees
+//! the same pending block, even if it is replaced while the scope is alive.
+//!
+//! Outside of a scope, reads go straight to the database.
+
+use crate::db_block_id::DbBlockId;
+use crate::rocksdb_snapshot::SnapshotWithDBArc;
+use crate::{MadaraBackend, DB};
+use mp_block::{BlockId, MadaraMaybePendingBlockInfo, MadaraPendingBlock};
+use rocksdb::ReadOptions;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+
+tokio::task_local! {
+    static READ_SCOPE: ReadScope;
+}
+
+/// Block resolution cache shared by every read made inside of [`ReadScope::run`].
+#[derive(Default)]
+pub struct ReadScope {
+    resolved: Mutex<HashMap<BlockId, Option<DbBlockId>>>,
+    block_info: Mutex<HashMap<DbBlockId, Option<MadaraMaybePendingBlockInfo>>>,
+    pending_block: Mutex<Option<Arc<MadaraPendingBlock>>>,
+    /// The snapshot every read of the scope goes through, taken on the first read.
+    snapshot: OnceLock<SnapshotWithDBArc<DB>>,
+}
+
+impl ReadScope {
+    /// Runs `fut` inside of a new read scope. Scopes are task-local and are not inherited by spawned tasks.
+    pub async fn run<F: Future>(fut: F) -> F::Output {
+        READ_SCOPE.scope(Self::default(), fut).await
+    }
+
+    /// Returns true when called from inside of a read scope.
+    pub fn is_active() -> bool {
+        READ_SCOPE.try_with(|_| ()).is_ok()
+    }
+
+    /// Read options for a read of `db`, which read from the snapshot of the current scope if any.
+    ///
+    /// The options must be used inside of the scope, as the snapshot is released when the scope ends.
+    pub(crate) fn read_options(db: &Arc<DB>) -> ReadOptions {
+        READ_SCOPE
+            .try_with(|scope| {
+                let snapshot = scope.snapshot.get_or_init(|| SnapshotWithDBArc::new(Arc::clone(db)));
+                // A scope reads from a single database, the other ones are read directly.
+                if !Arc::ptr_eq(&snapshot.db, db) {
+                    return ReadOptions::default();
+                }
+                // Safety: the snapshot is released when the scope ends, and the options are used inside of it.
+                unsafe { snapshot.read_options() }
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn resolve<E>(
+        id: &BlockId,
+        f: impl FnOnce() -> Result<Option<DbBlockId>, E>,
+    ) -> Result<Option<DbBlockId>, E> {
+        Self::memoize(|scope| &scope.resolved, id, f)
+    }
+
+    pub(crate) fn block_info<E>(
+        id: &DbBlockId,
+        f: impl FnOnce() -> Result<Option<MadaraMaybePendingBlockInfo>, E>,
+    ) -> Result<Option<MadaraMaybePendingBlockInfo>, E> {
+        Self::memoize(|scope| &scope.block_info, id, f)
+    }
+
+    pub(crate) fn pending_block<E>(
+        f: impl FnOnce() -> Result<Arc<MadaraPendingBlock>, E>,
+    ) -> Result<Arc<MadaraPendingBlock>, E> {
+        let cached = READ_SCOPE.try_with(|scope| scope.pending_block.lock().expect("Poisoned lock").clone());
+        match cached {
+            // Not in a read scope.
+            Err(_) => f(),
+            Ok(Some(block)) => Ok(block),
+            Ok(None) => {
+                // See `memoize`, the first block to be cached is kept.
+                let block = f()?;
+                let block = READ_SCOPE
+                    .try_with(|scope| scope.pending_block.lock().expect("Poisoned lock").get_or_insert(block).clone())
+                    .expect("Read scope cannot end during a read");
+                Ok(block)
+            }
+        }
+    }
+
+    fn memoize<K, V, E>(
+        map: impl Fn(&ReadScope) -> &Mutex<HashMap<K, V>>,
+        key: &K,
+        f: impl FnOnce() -> Result<V, E>,
+    ) -> Result<V, E>
+    where
+        K: std::hash::Hash + Eq + Clone,
+        V: Clone,
+    {
+        let cached = READ_SCOPE.try_with(|scope| map(scope).lock().expect("Poisoned lock").get(key).cloned());
+        match cached {
+            // Not in a read scope.
+            Err(_) => f(),
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => {
+                // The lock is not held while reading from the database, concurrent calls in the same scope may
+                // both miss. This is fine since the first value to be inserted is kept.
+                let value = f()?;
+                let value = READ_SCOPE
+                    .try_with(|scope| {
+                        map(scope).lock().expect("Poisoned lock").entry(key.clone()).or_insert(value).clone()
+                    })
+                    .expect("Read scope cannot end during a read");
+                Ok(value)
+            }
+        }
+    }
+}
+
+impl MadaraBackend {
+    /// Read options for the reads which must be consistent inside of a [`ReadScope`].
+    pub(crate) fn read_options(&self) -> ReadOptions {
+        ReadScope::read_options(&self.db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::BlockTag;
+
+    #[tokio::test]
+    async fn test_read_scope_memoizes() {
+        let id = BlockId::Tag(BlockTag::Latest);
+
+        // No scope: always read.
+        assert_eq!(ReadScope::resolve(&id, || Ok::<_, ()>(Some(DbBlockId::Number(1)))), Ok(Some(DbBlockId::Number(1))));
+        assert_eq!(ReadScope::resolve(&id, || Ok::<_, ()>(Some(DbBlockId::Number(2)))), Ok(Some(DbBlockId::Number(2))));
+        assert!(!ReadScope::is_active());
+
+        ReadScope::run(async {
+            assert!(ReadScope::is_active());
+            assert_eq!(
+                ReadScope::resolve(&id, || Ok::<_, ()>(Some(DbBlockId::Number(3)))),
+                Ok(Some(DbBlockId::Number(3)))
+            );
+            // The head moved, but the scope still sees the first resolution.
+            assert_eq!(
+                ReadScope::resolve(&id, || Ok::<_, ()>(Some(DbBlockId::Number(4)))),
+                Ok(Some(DbBlockId::Number(3)))
+            );
+            // Errors are not cached.
+            let other = BlockId::Number(10);
+            assert_eq!(ReadScope::resolve(&other, || Err(())), Err(()));
+            assert_eq!(ReadScope::resolve(&other, || Ok::<_, ()>(None)), Ok(None));
+        })
+        .await;
+    }
+}
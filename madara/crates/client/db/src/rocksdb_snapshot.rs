@@ -28,6 +28,17 @@ impl<D: DBAccess> SnapshotWithDBArc<D> {
         f(&readopts)
     }
 
+    /// Read options which read from this snapshot.
+    ///
+    /// # Safety
+    ///
+    /// The read options, and the iterators created with them, must not be used once the snapshot is dropped.
+    pub(crate) unsafe fn read_options(&self) -> ReadOptions {
+        let mut readopts = ReadOptions::default();
+        readopts.set_raw_snapshot(self.inner);
+        readopts
+    }
+
     /// Creates a new `SnapshotWithDBArc` of the database `db`.
     pub fn new(db: Arc<D>) -> Self {
         let snapshot = unsafe { db.create_snapshot() };
@@ -72,6 +72,25 @@ impl Snapshots {
         }
     }
 
+    /// Called when the database has been reverted to `block_n`. The snapshots of the reverted blocks are dropped.
+    #[tracing::instrument(skip(self), fields(module = "BonsaiDB"))]
+    pub fn revert_head(&self, block_n: Option<u64>) {
+        let snapshot = Arc::new(SnapshotWithDBArc::new(Arc::clone(&self.db)));
+
+        let mut inner = self.inner.write().expect("Poisoned lock");
+        let first_reverted = block_n.map_or(0, |n| n + 1);
+        inner.historical.split_off(&first_reverted);
+        inner.head = snapshot;
+        inner.head_block_n = block_n;
+    }
+
+    /// Whether a snapshot of the database at block `block_n` is kept. The global tries can be read at that block
+    /// without reverting them with their trie logs.
+    pub fn contains(&self, block_n: u64) -> bool {
+        let inner = self.inner.read().expect("Poisoned lock");
+        inner.head_block_n == Some(block_n) || inner.historical.contains_key(&block_n)
+    }
+
     /// Get the closest snapshot that had been made at or after the provided `block_n`.
     /// Also returns the block_n, which can be null if no block is in database in that snapshot.
     #[tracing::instrument(skip(self), fields(module = "BonsaiDB"))]
@@ -89,3 +108,30 @@ impl Snapshots {
             .unwrap_or_else(|| (inner.head_block_n, Arc::clone(&inner.head)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MadaraBackend;
+    use mp_chain_config::ChainConfig;
+
+    #[test]
+    fn test_contains() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let snapshots = Snapshots::new(Arc::clone(&backend.db), None, Some(2), 5);
+        for block_n in 0..=12 {
+            snapshots.set_new_head(DbBlockId::Number(block_n));
+        }
+
+        // The snapshots of blocks 5 and 10 are kept, the one of block 0 was discarded.
+        assert!(snapshots.contains(12));
+        assert!(snapshots.contains(10));
+        assert!(snapshots.contains(5));
+        assert!(!snapshots.contains(0));
+        assert!(!snapshots.contains(7));
+
+        snapshots.revert_head(Some(8));
+        assert!(!snapshots.contains(10));
+        assert!(snapshots.contains(8));
+    }
+}
@@ -1,6 +1,8 @@
+use crate::chain_head::PipelineStage;
 use crate::db_block_id::DbBlockId;
 use crate::MadaraBackend;
 use crate::MadaraStorageError;
+use crate::{Column, DatabaseExt};
 use blockifier::bouncer::BouncerWeights;
 use mp_block::VisitedSegments;
 use mp_block::{MadaraBlock, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo, MadaraPendingBlock};
@@ -10,6 +12,21 @@ use mp_state_update::{
 };
 use starknet_types_core::felt::Felt;
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+/// Block the database is being reverted to, see [`MadaraBackend::revert_to`].
+pub(crate) const ROW_REVERT_TO: &[u8] = b"revert_to";
+
+/// What [`MadaraBackend::store_block_inner`] is storing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StoreKind {
+    /// A block, see [`MadaraBackend::store_block`].
+    Block,
+    /// See [`MadaraBackend::store_state_snapshot`].
+    StateSnapshot,
+    /// See [`MadaraBackend::store_pending_block`].
+    PendingOnLatest,
+}
 
 impl MadaraBackend {
     /// NB: This functions needs to run on the rayon thread pool
@@ -20,12 +37,100 @@ impl MadaraBackend {
         converted_classes: Vec<ConvertedClass>,
         visited_segments: Option<VisitedSegments>,
         bouncer_weights: Option<BouncerWeights>,
+    ) -> Result<(), MadaraStorageError> {
+        self.store_block_inner(
+            block,
+            state_diff,
+            converted_classes,
+            visited_segments,
+            bouncer_weights,
+            StoreKind::Block,
+        )
+    }
+
+    /// Stores a pending block fetched on top of the latest block. The pending block is rejected with
+    /// [`MadaraStorageError::StalePendingBlock`] when its parent is not the latest block anymore, which happens when the
+    /// next block was imported while the pending block was being fetched. This is checked under the import lock, so
+    /// that a stale pending block is never served, even for a moment.
+    ///
+    /// NB: This functions needs to run on the rayon thread pool
+    pub fn store_pending_block(
+        &self,
+        block: MadaraPendingBlock,
+        state_diff: StateDiff,
+        converted_classes: Vec<ConvertedClass>,
+        visited_segments: Option<VisitedSegments>,
+    ) -> Result<(), MadaraStorageError> {
+        let block = MadaraMaybePendingBlock { info: block.info.into(), inner: block.inner };
+        self.store_block_inner(block, state_diff, converted_classes, visited_segments, None, StoreKind::PendingOnLatest)
+    }
+
+    /// Stores the block of the state snapshot the node is bootstrapped from, in an empty database. The history of the
+    /// chain starts at this block, and its state cannot be read at older blocks, see
+    /// [`MadaraBackend::get_oldest_state_block_n`]. This is recorded along with the block itself.
+    ///
+    /// `state_diff` is the whole state of the chain at the block, which is not the state diff of the block: the state
+    /// is stored, but the block is stored with an empty state diff, and is not part of the address activity index.
+    ///
+    /// NB: This functions needs to run on the rayon thread pool
+    pub fn store_state_snapshot(
+        &self,
+        block: MadaraBlock,
+        state_diff: StateDiff,
+        converted_classes: Vec<ConvertedClass>,
+    ) -> Result<(), MadaraStorageError> {
+        let block_n = block.info.header.block_number;
+        let block = MadaraMaybePendingBlock { info: block.info.into(), inner: block.inner };
+        self.store_block_inner(block, state_diff, converted_classes, None, None, StoreKind::StateSnapshot)?;
+        self.oldest_state_block_n.store(block_n, Ordering::Release);
+        Ok(())
+    }
+
+    fn store_block_inner(
+        &self,
+        block: MadaraMaybePendingBlock,
+        state_diff: StateDiff,
+        converted_classes: Vec<ConvertedClass>,
+        visited_segments: Option<VisitedSegments>,
+        bouncer_weights: Option<BouncerWeights>,
+        kind: StoreKind,
     ) -> Result<(), MadaraStorageError> {
         let block_n = block.info.block_n();
-        let state_diff_cpy = state_diff.clone();
+        let state_snapshot = kind == StoreKind::StateSnapshot;
+        let state_diff_cpy = if state_snapshot { StateDiff::default() } else { state_diff.clone() };
 
-        // Clear in every case, even when storing a pending block
-        self.clear_pending_block()?;
+        // Blocks are stored one at a time, so holding this lock while the tasks below run on the rayon thread pool
+        // cannot deadlock: only the backups wait for it, outside of the pool.
+        let _import = self.import_lock.lock().expect("Poisoned lock");
+
+        if let (StoreKind::PendingOnLatest, MadaraMaybePendingBlockInfo::Pending(pending)) = (kind, &block.info) {
+            let latest_block_hash = self
+                .get_latest_block_n()?
+                .map(|block_n| self.get_block_hash(&DbBlockId::Number(block_n)))
+                .transpose()?
+                .flatten()
+                .unwrap_or(/* genesis parent block hash */ Felt::ZERO);
+            if pending.header.parent_block_hash != latest_block_hash {
+                return Err(MadaraStorageError::StalePendingBlock {
+                    parent_block_hash: pending.header.parent_block_hash,
+                });
+            }
+        }
+
+        // Clear in every case, even when storing a pending block. A pending block is written under the same lock, so
+        // that a concurrent clear cannot leave part of it behind.
+        let mut pending_may_exist = self.pending_may_exist.lock().expect("Poisoned lock");
+        self.clear_pending_block_locked(&mut pending_may_exist)?;
+        let pending_lock = match block_n {
+            None => {
+                *pending_may_exist = true;
+                Some(pending_may_exist)
+            }
+            Some(_) => {
+                drop(pending_may_exist);
+                None
+            }
+        };
 
         let task_block_db = || match block.info {
             MadaraMaybePendingBlockInfo::Pending(info) => self.block_db_store_pending(
@@ -35,7 +140,7 @@ impl MadaraBackend {
                 bouncer_weights,
             ),
             MadaraMaybePendingBlockInfo::NotPending(info) => {
-                self.block_db_store_block(&MadaraBlock { info, inner: block.inner }, &state_diff_cpy)
+                self.block_db_store_block(&MadaraBlock { info, inner: block.inner }, &state_diff_cpy, state_snapshot)
             }
         };
 
@@ -80,18 +185,124 @@ impl MadaraBackend {
             Some(block_n) => self.class_db_store_block(block_n, &converted_classes),
         };
 
-        let ((r1, r2), r3) = rayon::join(|| rayon::join(task_block_db, task_contract_db), task_class_db);
+        let ((r1, r2), r3) = match pending_lock {
+            // Not run on the rayon thread pool while holding the lock: this thread could steal a task waiting for it.
+            Some(_) => ((task_block_db(), task_contract_db()), task_class_db()),
+            None => rayon::join(|| rayon::join(task_block_db, task_contract_db), task_class_db),
+        };
+        drop(pending_lock);
 
         r1.and(r2).and(r3)?;
 
         self.snapshots.set_new_head(DbBlockId::from_block_n(block_n));
+        if let Some(block_n) = block_n {
+            self.prune_if_due(block_n);
+        }
+        Ok(())
+    }
+
+    /// Reverts the database to block `block_n`: the blocks after it are removed, along with their state, their
+    /// classes and their updates of the global tries. This is used to recover from a reorg of the chain being synced.
+    ///
+    /// Reverting the global tries requires their trie logs to go back to `block_n`, see
+    /// [`crate::TrieLogConfig::max_saved_trie_logs`]. When they do not, an error is returned and the blocks are kept.
+    ///
+    /// The reverted blocks are removed with several writes, made without the write-ahead log. The target of the revert
+    /// is recorded first with the write-ahead log, and cleared once the writes are flushed: a revert interrupted by a
+    /// crash is completed when the database is opened again, see [`Self::resume_revert`].
+    #[tracing::instrument(skip(self), fields(module = "StorageUpdates"))]
+    pub fn revert_to(&self, block_n: u64) -> Result<(), MadaraStorageError> {
+        let Some(latest_block_n) = self.get_latest_block_n()? else {
+            return Err(MadaraStorageError::InvalidBlockNumber);
+        };
+        if block_n >= latest_block_n {
+            return Ok(());
+        }
+        if self.get_l1_last_confirmed_block()?.is_some_and(|l1_block_n| block_n < l1_block_n) {
+            return Err(MadaraStorageError::InconsistentStorage(
+                format!("Cannot revert to block #{block_n}, the blocks after it are confirmed on L1").into(),
+            ));
+        }
+        if self.get_oldest_backfilled_block_n()?.is_some_and(|oldest_block_n| block_n < oldest_block_n) {
+            return Err(MadaraStorageError::InconsistentStorage(
+                format!("Cannot revert to block #{block_n}, which is older than the state snapshot").into(),
+            ));
+        }
+        self.check_state_not_pruned(block_n)?;
+
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        self.db.put_cf(&col, ROW_REVERT_TO, bincode::serialize(&block_n)?)?;
+
+        // The tries are reverted first, as this is what fails when the trie logs do not go back far enough. When they
+        // lag behind the blocks, they are only reverted if they went past `block_n`.
+        if let Some(trie_block_n) = self.get_global_trie_block_n()?.filter(|trie_block_n| *trie_block_n > block_n) {
+            if let Err(err) = self.revert_global_tries(block_n, trie_block_n) {
+                self.db.delete_cf(&col, ROW_REVERT_TO)?;
+                return Err(err);
+            }
+            self.revert_global_trie_block_n(block_n)?;
+        }
+
+        self.revert_blocks(block_n)
+    }
+
+    /// Completes a revert interrupted by a crash, see [`Self::revert_to`]. This is called when the database is opened.
+    /// When the global tries cannot be reverted anymore, they are reset, see
+    /// [`Self::reconcile_global_tries`](crate::MadaraBackend::reconcile_global_tries).
+    #[tracing::instrument(skip(self), fields(module = "StorageUpdates"))]
+    pub(crate) fn resume_revert(&self) -> Result<(), MadaraStorageError> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(block_n) = self.db.get_cf(&col, ROW_REVERT_TO)? else { return Ok(()) };
+        let block_n: u64 = bincode::deserialize(&block_n)?;
+        tracing::warn!("Reverting the database to block #{block_n} was interrupted, completing the revert");
+
+        if let Some(trie_block_n) = self.get_global_trie_head()?.filter(|trie_block_n| *trie_block_n > block_n) {
+            self.revert_or_reset_global_tries(block_n, trie_block_n)?;
+        }
+        self.revert_blocks(block_n)
+    }
+
+    /// Removes the blocks after `block_n`, once the global tries were reverted.
+    fn revert_blocks(&self, block_n: u64) -> Result<(), MadaraStorageError> {
+        let latest_block_n = self.get_latest_block_n()?.unwrap_or(block_n);
+        self.clear_pending_block()?;
+        for reverted_block_n in (block_n + 1..=latest_block_n).rev() {
+            let state_diff = self.get_block_state_diff(&DbBlockId::Number(reverted_block_n))?.ok_or_else(|| {
+                MadaraStorageError::InconsistentStorage(
+                    format!("Missing state diff for block #{reverted_block_n}").into(),
+                )
+            })?;
+            self.contract_db_revert_block(reverted_block_n, &state_diff)?;
+            self.class_db_revert_block(reverted_block_n, &state_diff)?;
+            self.block_db_revert_block(reverted_block_n)?;
+        }
+
+        self.snapshots.revert_head(Some(block_n));
+        self.flush().map_err(|err| {
+            MadaraStorageError::InconsistentStorage(format!("Flushing the revert to block #{block_n}: {err:#}").into())
+        })?;
+        self.db.delete_cf(&self.db.get_column(Column::BlockStorageMeta), ROW_REVERT_TO)?;
+        self.notify_chain_head(PipelineStage::Revert, block_n);
         Ok(())
     }
 
+    /// Removes the pending block. This is skipped when no pending block was stored since the last time it was cleared:
+    /// clearing deletes rows and ranges in several columns, and doing it for every block of a sync adds a lot of small
+    /// writes and tombstones.
     pub fn clear_pending_block(&self) -> Result<(), MadaraStorageError> {
+        self.clear_pending_block_locked(&mut self.pending_may_exist.lock().expect("Poisoned lock"))
+    }
+
+    /// Clears the pending block while holding the lock of [`MadaraBackend::pending_may_exist`]. On error, it is
+    /// cleared again next time.
+    fn clear_pending_block_locked(&self, pending_may_exist: &mut bool) -> Result<(), MadaraStorageError> {
+        if !*pending_may_exist {
+            return Ok(());
+        }
         self.block_db_clear_pending()?;
         self.contract_db_clear_pending()?;
         self.class_db_clear_pending()?;
+        *pending_may_exist = false;
         Ok(())
     }
 }
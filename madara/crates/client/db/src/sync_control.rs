@@ -0,0 +1,90 @@
+//! Operator control over the sync.
+//!
+//! The `madara_pauseSync`, `madara_resumeSync` and `madara_setSyncTarget` admin RPC methods update the [`SyncControl`]
+//! here, and the sync waits for it to allow a block before fetching it. The blocks already being fetched when the sync
+//! is paused are still imported, so that the database stays consistent. The control is not persisted, a restarted node
+//! syncs normally.
+
+use crate::MadaraBackend;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncControl {
+    /// Whether the sync is paused by the operator.
+    pub paused: bool,
+    /// Last block the sync may fetch, if any. The sync stops fetching after this block until the target is raised or
+    /// cleared.
+    pub target_block_n: Option<u64>,
+}
+
+impl SyncControl {
+    /// Whether the sync may fetch the block `block_n`.
+    pub fn allows(&self, block_n: u64) -> bool {
+        !self.paused && self.target_block_n.map_or(true, |target| block_n <= target)
+    }
+}
+
+impl MadaraBackend {
+    pub fn get_sync_control(&self) -> SyncControl {
+        *self.sync_control.borrow()
+    }
+
+    /// Pauses the sync, and returns the updated control.
+    pub fn pause_sync(&self) -> SyncControl {
+        self.update_sync_control(|control| control.paused = true)
+    }
+
+    /// Resumes the sync, and returns the updated control. The sync target, if any, is kept.
+    pub fn resume_sync(&self) -> SyncControl {
+        self.update_sync_control(|control| control.paused = false)
+    }
+
+    /// Sets or clears the last block the sync may fetch, and returns the updated control.
+    pub fn set_sync_target(&self, target_block_n: Option<u64>) -> SyncControl {
+        self.update_sync_control(|control| control.target_block_n = target_block_n)
+    }
+
+    fn update_sync_control(&self, f: impl FnOnce(&mut SyncControl)) -> SyncControl {
+        self.sync_control.send_modify(f);
+        self.get_sync_control()
+    }
+
+    /// Waits until the sync may fetch the block `block_n`.
+    pub async fn wait_sync_allowed(&self, block_n: u64) {
+        let mut receiver = self.sync_control.subscribe();
+        // The sender lives as long as the backend, so this cannot fail.
+        let _ = receiver.wait_for(|control| control.allows(block_n)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn test_sync_control() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        assert_eq!(backend.get_sync_control(), SyncControl::default());
+        backend.wait_sync_allowed(1000).await;
+
+        assert_eq!(backend.set_sync_target(Some(10)), SyncControl { paused: false, target_block_n: Some(10) });
+        assert!(backend.get_sync_control().allows(10));
+        assert!(!backend.get_sync_control().allows(11));
+
+        assert_eq!(backend.pause_sync(), SyncControl { paused: true, target_block_n: Some(10) });
+        assert!(!backend.get_sync_control().allows(0));
+        assert!(tokio::time::timeout(Duration::from_millis(50), backend.wait_sync_allowed(5)).await.is_err());
+
+        let waiting = tokio::spawn({
+            let backend = Arc::clone(&backend);
+            async move { backend.wait_sync_allowed(5).await }
+        });
+        assert_eq!(backend.resume_sync(), SyncControl { paused: false, target_block_n: Some(10) });
+        tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+
+        assert_eq!(backend.set_sync_target(None), SyncControl::default());
+        assert!(backend.get_sync_control().allows(u64::MAX));
+    }
+}
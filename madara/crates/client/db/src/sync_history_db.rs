@@ -0,0 +1,87 @@
+//! History of the sync progress, kept across restarts.
+//!
+//! The sync periodically stores how many blocks it imported and how long each stage of the block pipeline took, so
+//! that the sync can be analyzed after the fact even when no metrics were collected.
+
+use crate::DatabaseExt;
+use crate::{Column, MadaraBackend, MadaraStorageError};
+use rocksdb::IteratorMode;
+use serde::{Deserialize, Serialize};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+/// Entries older than this, relative to the latest entry, are removed.
+pub const SYNC_HISTORY_RETENTION_SECS: u64 = 30 * 24 * 3600;
+
+/// Time spent in each stage of the block pipeline, in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncStageTimes {
+    /// Fetching the blocks, state updates and classes from the feeder gateway.
+    pub fetch: f64,
+    /// Converting and pre-validating the blocks.
+    pub conversion: f64,
+    /// Verifying the blocks and applying them to the database and the global tries.
+    pub verify_apply: f64,
+}
+
+/// Sync progress over a period of time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncHistoryEntry {
+    /// Unix time, in seconds, of the end of the period.
+    pub timestamp: u64,
+    /// Duration of the period, in seconds.
+    pub period_secs: f64,
+    /// Latest block in the database at the end of the period.
+    pub latest_block_n: Option<u64>,
+    /// Number of blocks imported during the period.
+    pub blocks_imported: u64,
+    pub blocks_per_second: f64,
+    /// Time spent in each stage during the period. Stages work on several blocks at once, so these can add up to more
+    /// than the period.
+    pub stage_secs: SyncStageTimes,
+}
+
+impl MadaraBackend {
+    /// Stores an entry of the sync history, and removes the entries which are past the retention period.
+    #[tracing::instrument(skip(self), fields(module = "SyncHistoryDB"))]
+    pub fn store_sync_history_entry(&self, entry: &SyncHistoryEntry) -> Result<()> {
+        let col = self.db.get_column(Column::SyncHistory);
+        self.db.put_cf(&col, entry.timestamp.to_be_bytes(), bincode::serialize(entry)?)?;
+        let cutoff = entry.timestamp.saturating_sub(SYNC_HISTORY_RETENTION_SECS);
+        self.db.delete_range_cf(&col, 0u64.to_be_bytes(), cutoff.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// The entries of the sync history which ended at or after the unix time `since`, oldest first.
+    #[tracing::instrument(skip(self), fields(module = "SyncHistoryDB"))]
+    pub fn get_sync_history(&self, since: u64) -> Result<Vec<SyncHistoryEntry>> {
+        let col = self.db.get_column(Column::SyncHistory);
+        let since = since.to_be_bytes();
+        self.db
+            .iterator_cf(&col, IteratorMode::From(&since, rocksdb::Direction::Forward))
+            .map(|kv| Ok(bincode::deserialize(&kv?.1)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sync_history() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let entry = |timestamp| SyncHistoryEntry { timestamp, blocks_imported: timestamp, ..Default::default() };
+
+        backend.store_sync_history_entry(&entry(100)).unwrap();
+        backend.store_sync_history_entry(&entry(200)).unwrap();
+        assert_eq!(backend.get_sync_history(0).unwrap(), vec![entry(100), entry(200)]);
+        assert_eq!(backend.get_sync_history(150).unwrap(), vec![entry(200)]);
+
+        // The first entries are now past the retention period.
+        backend.store_sync_history_entry(&entry(150 + SYNC_HISTORY_RETENTION_SECS)).unwrap();
+        assert_eq!(backend.get_sync_history(0).unwrap(), vec![entry(200), entry(150 + SYNC_HISTORY_RETENTION_SECS)]);
+    }
+}
@@ -0,0 +1,45 @@
+//! Signals of the pressure the sync puts on the node.
+//!
+//! These are read by the RPC server to decide when to shed expensive requests, so that they do not compete with the
+//! block import when the node is falling behind.
+
+use crate::{MadaraBackend, MadaraStorageError};
+use std::sync::atomic::Ordering;
+
+impl MadaraBackend {
+    /// Records the latest block fetched by the sync pipeline.
+    pub fn set_sync_fetched_block_n(&self, block_n: u64) {
+        self.sync_fetched_blocks.store(block_n + 1, Ordering::Relaxed);
+    }
+
+    /// Number of blocks fetched by the sync pipeline which have not been imported yet. This stays close to zero when
+    /// the sync is waiting on the feeder gateway, and grows up to the depth of the pipeline when the block import is
+    /// the bottleneck.
+    pub fn import_backlog(&self) -> Result<u64, MadaraStorageError> {
+        let imported = self.get_latest_block_n()?.map(|block_n| block_n + 1).unwrap_or(0);
+        Ok(self.sync_fetched_blocks.load(Ordering::Relaxed).saturating_sub(imported))
+    }
+
+    /// Whether RocksDB is currently delaying or stopping writes because compaction is falling behind.
+    pub fn is_write_stalled(&self) -> bool {
+        let is_set = |property| matches!(self.db.property_int_value(property), Ok(Some(value)) if value > 0);
+        is_set(rocksdb::properties::IS_WRITE_STOPPED) || is_set(rocksdb::properties::ACTUAL_DELAYED_WRITE_RATE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_import_backlog() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        assert_eq!(backend.import_backlog().unwrap(), 0);
+
+        backend.set_sync_fetched_block_n(4);
+        assert_eq!(backend.import_backlog().unwrap(), 5);
+        assert!(!backend.is_write_stalled());
+    }
+}
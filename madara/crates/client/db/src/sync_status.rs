@@ -0,0 +1,58 @@
+//! Progress of the sync towards the head of the chain.
+//!
+//! The sync periodically measures its throughput and publishes it here as a [`SyncStatus`], from where it is served by
+//! the `madara_syncStatus` admin RPC method and the `starknet_syncing` RPC method.
+
+use crate::MadaraBackend;
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+
+/// Throughput of the sync and estimated time until it catches up with the feeder gateway.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// Unix time, in seconds, of the measurement.
+    pub timestamp: u64,
+    /// Latest block in the database when the sync started.
+    pub starting_block_n: Option<u64>,
+    /// Latest block in the database.
+    pub latest_block_n: Option<u64>,
+    /// Latest block on the feeder gateway, when it could be fetched.
+    pub highest_block_n: Option<u64>,
+    /// Hash of the latest block on the feeder gateway.
+    pub highest_block_hash: Option<Felt>,
+    /// Blocks imported per second, averaged over the last few minutes.
+    pub blocks_per_second: f64,
+    /// Classes declared in the imported blocks per second, averaged over the last few minutes.
+    pub classes_per_second: f64,
+    /// Estimated time until the latest block is the latest block on the feeder gateway, in seconds. This is zero once
+    /// caught up, and unknown when no block is being imported.
+    pub eta_secs: Option<f64>,
+}
+
+impl MadaraBackend {
+    pub fn set_sync_status(&self, status: SyncStatus) {
+        *self.sync_status.write().expect("Poisoned lock") = Some(status);
+    }
+
+    /// The latest status published by the sync, or `None` when the sync is not running.
+    pub fn get_sync_status(&self) -> Option<SyncStatus> {
+        *self.sync_status.read().expect("Poisoned lock")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_sync_status() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        assert_eq!(backend.get_sync_status(), None);
+
+        let status = SyncStatus { latest_block_n: Some(10), blocks_per_second: 2.0, ..Default::default() };
+        backend.set_sync_status(status);
+        assert_eq!(backend.get_sync_status(), Some(status));
+    }
+}
@@ -2,11 +2,28 @@
 mod block_tests {
     use super::super::common::temp_db::temp_db;
     use super::super::common::*;
+    use crate::bonsai_identifier;
+    use crate::chain_head::{ChainHead, ChainHeadUpdate, PipelineStage};
     use crate::db_block_id::DbBlockIdResolvable;
+    use crate::global_trie_progress::GlobalTrieCheckpoint;
+    use crate::read_scope::ReadScope;
+    use crate::storage_updates::ROW_REVERT_TO;
     use crate::{block_db::TxIndex, db_block_id::DbBlockId};
-    use mp_block::{BlockId, Header};
+    use crate::{Column, DatabaseExt, MadaraStorageError};
+    use bitvec::order::Msb0;
+    use bitvec::view::AsBits;
+    use bonsai_trie::id::BasicId;
+    use mp_block::header::PendingHeader;
+    use mp_block::{
+        BlockId, BlockTag, ConsensusSignature, Header, MadaraBlock, MadaraBlockInner, MadaraMaybePendingBlock,
+        MadaraPendingBlockInfo,
+    };
     use mp_chain_config::ChainConfig;
+    use mp_receipt::InvokeTransactionReceipt;
+    use mp_state_update::{ContractStorageDiffItem, NonceUpdate, StateDiff, StorageEntry};
+    use mp_transactions::{InvokeTransaction, InvokeTransactionV0, Transaction};
     use starknet_api::felt;
+    use starknet_types_core::felt::Felt;
 
     #[tokio::test]
     async fn test_chain_info() {
@@ -117,6 +134,64 @@ mod block_tests {
         assert_eq!(backend.get_block_state_diff(&BLOCK_ID_PENDING).unwrap().unwrap(), state_diff);
     }
 
+    #[tokio::test]
+    async fn test_store_pending_block_rejects_stale() {
+        const BLOCK_ID_PENDING: DbBlockId = DbBlockId::Pending;
+
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        // The parent of this pending block is not block 0
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        let res = backend.store_pending_block(
+            pending_block_one().try_into().unwrap(),
+            pending_state_diff_one(),
+            vec![],
+            None,
+        );
+        assert!(matches!(
+            res,
+            Err(MadaraStorageError::StalePendingBlock { parent_block_hash }) if parent_block_hash == Felt::ZERO
+        ));
+        assert!(backend.get_block(&BLOCK_ID_PENDING).unwrap().unwrap().inner.transactions.is_empty());
+
+        // The parent of this pending block is block 1
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        let block_pending = pending_block_two();
+        backend
+            .store_pending_block(block_pending.clone().try_into().unwrap(), pending_state_diff_two(), vec![], None)
+            .unwrap();
+        assert_eq!(backend.get_block(&BLOCK_ID_PENDING).unwrap().unwrap(), block_pending);
+    }
+
+    #[tokio::test]
+    async fn test_clear_pending_block_concurrent() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..50 {
+                    backend.store_block(pending_block_one(), pending_state_diff_one(), vec![], None, None).unwrap();
+                }
+            });
+            s.spawn(|| {
+                for _ in 0..50 {
+                    backend.clear_pending_block().unwrap();
+                }
+            });
+        });
+
+        // No part of a pending block is left behind by a clear racing with its write.
+        backend.clear_pending_block().unwrap();
+        assert!(backend.get_block(&DbBlockId::Pending).unwrap().unwrap().inner.transactions.is_empty());
+    }
+
     #[tokio::test]
     async fn test_store_latest_block() {
         let db = temp_db().await;
@@ -132,16 +207,325 @@ mod block_tests {
         assert_eq!(backend.get_latest_block_n().unwrap().unwrap(), 1);
     }
 
+    #[tokio::test]
+    async fn test_iter_blocks() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        let block_zero = finalized_block_zero(Header::default());
+        let block_one = finalized_block_one();
+        backend.store_block(block_zero.clone(), finalized_state_diff_zero(), vec![], None, None).unwrap();
+        backend.store_block(block_one.clone(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        backend.store_block(pending_block_two(), pending_state_diff_two(), vec![], None, None).unwrap();
+
+        let blocks: Vec<MadaraMaybePendingBlock> = backend.iter_blocks(..).map(|block| block.unwrap().into()).collect();
+        assert_eq!(blocks, vec![block_zero, block_one.clone()]);
+
+        let blocks: Vec<MadaraMaybePendingBlock> =
+            backend.iter_blocks(1..=5).map(|block| block.unwrap().into()).collect();
+        assert_eq!(blocks, vec![block_one]);
+
+        assert_eq!(backend.iter_blocks(2..).count(), 0);
+
+        // The missing blocks are skipped, and the following ones returned with their own block number.
+        let db = temp_db().await;
+        let backend = db.backend();
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        let block_ns: Vec<u64> = backend.iter_blocks(..).map(|block| block.unwrap().info.header.block_number).collect();
+        assert_eq!(block_ns, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_consensus_signature() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        let block = finalized_block_zero(Header::default());
+        backend.store_block(block.clone(), finalized_state_diff_zero(), vec![], None, None).unwrap();
+        assert!(backend.get_consensus_signature(&DbBlockId::Number(0)).unwrap().is_none());
+
+        let signature = ConsensusSignature { r: felt!("0x1"), s: felt!("0x2") };
+        backend.store_consensus_signature(0, &signature).unwrap();
+
+        assert_eq!(backend.get_consensus_signature(&DbBlockId::Number(0)).unwrap(), Some(signature));
+        assert_eq!(
+            backend.get_consensus_signature(&BlockId::Hash(block.info.block_hash().unwrap())).unwrap(),
+            Some(signature)
+        );
+        assert!(backend.get_consensus_signature(&DbBlockId::Pending).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_store_block_receipts() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        let block = finalized_block_zero(Header::default());
+        backend.store_block(block.clone(), finalized_state_diff_zero(), vec![], None, None).unwrap();
+
+        let mut receipts = block.inner.receipts.clone();
+        receipts[0].execution_resources_mut().total_gas_consumed.l1_gas = 42;
+        backend.store_block_receipts(0, receipts.clone()).unwrap();
+        assert_eq!(backend.get_block_inner(&DbBlockId::Number(0)).unwrap().unwrap().receipts, receipts);
+
+        assert!(backend.store_block_receipts(0, vec![]).is_err());
+        assert!(backend.store_block_receipts(1, receipts).is_err());
+    }
+
     #[tokio::test]
     async fn test_latest_confirmed_block() {
         let db = temp_db().await;
         let backend = db.backend();
 
         assert!(backend.get_l1_last_confirmed_block().unwrap().is_none());
+        assert!(backend.resolve_block_id(&BlockId::Tag(BlockTag::L1Accepted)).unwrap().is_none());
 
         backend.write_last_confirmed_block(0).unwrap();
 
         assert_eq!(backend.get_l1_last_confirmed_block().unwrap().unwrap(), 0);
+        assert_eq!(backend.resolve_block_id(&BlockId::Tag(BlockTag::L1Accepted)).unwrap(), Some(DbBlockId::Number(0)));
+    }
+
+    #[tokio::test]
+    async fn test_store_backfilled_block() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend.write_oldest_backfilled_block(1).unwrap();
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        assert!(backend.get_block_info(&BlockId::Number(0)).unwrap().is_none());
+
+        let mut rx = backend.subscribe_chain_head();
+        let block = MadaraBlock::try_from(finalized_block_zero(Header::default())).unwrap();
+        backend.store_backfilled_block(&block, &finalized_state_diff_zero()).unwrap();
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ChainHeadUpdate {
+                stage: PipelineStage::Backfill,
+                block_n: 0,
+                chain_head: ChainHead {
+                    latest_block_n: Some(1),
+                    l1_confirmed_block_n: None,
+                    oldest_backfilled_block: Some(0),
+                    oldest_state_block_n: None,
+                    global_trie_block_n: Some(1)
+                }
+            }
+        );
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(1));
+        assert_eq!(backend.get_block(&BlockId::Number(0)).unwrap().unwrap(), block.clone().into());
+        assert_eq!(backend.get_block(&BlockId::Hash(block.info.block_hash)).unwrap().unwrap(), block.into());
+        assert_eq!(backend.get_block_n(&BlockId::Tag(BlockTag::Latest)).unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_revert_block() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        let state_diff_zero = StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::ONE,
+                storage_entries: vec![StorageEntry { key: Felt::TWO, value: Felt::from(10) }],
+            }],
+            ..Default::default()
+        };
+        let state_diff_one = StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::ONE,
+                storage_entries: vec![StorageEntry { key: Felt::TWO, value: Felt::from(11) }],
+            }],
+            nonces: vec![NonceUpdate { contract_address: Felt::ONE, nonce: Felt::ONE }],
+            ..Default::default()
+        };
+        backend.store_block(finalized_block_zero(Header::default()), state_diff_zero, vec![], None, None).unwrap();
+        let block_one = finalized_block_one();
+        backend.store_block(block_one.clone(), state_diff_one.clone(), vec![], None, None).unwrap();
+        assert_eq!(
+            backend.get_contract_storage_at(&BlockId::Number(1), &Felt::ONE, &Felt::TWO).unwrap(),
+            Some(Felt::from(11))
+        );
+
+        backend.contract_db_revert_block(1, &state_diff_one).unwrap();
+        backend.block_db_revert_block(1).unwrap();
+
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+        assert!(backend.get_block_info(&BlockId::Number(1)).unwrap().is_none());
+        assert!(backend.get_block_state_diff(&BlockId::Number(1)).unwrap().is_none());
+        assert!(backend.find_tx_hash_block(&block_one.info.tx_hashes()[0]).unwrap().is_none());
+        assert_eq!(
+            backend.get_contract_storage_at(&BlockId::Number(1), &Felt::ONE, &Felt::TWO).unwrap(),
+            Some(Felt::from(10))
+        );
+        assert_eq!(backend.get_contract_nonce_at(&BlockId::Number(1), &Felt::ONE).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_revert_to_confirmed_block_fails() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        backend.write_last_confirmed_block(1).unwrap();
+
+        assert!(backend.revert_to(0).is_err());
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(1));
+        // Nothing to revert.
+        backend.revert_to(1).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resume_revert() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        backend.resume_revert().unwrap();
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(1));
+
+        // A crash interrupted the revert to block 0 before the block was removed.
+        let col = backend.db.get_column(Column::BlockStorageMeta);
+        backend.db.put_cf(&col, ROW_REVERT_TO, bincode::serialize(&0u64).unwrap()).unwrap();
+        backend.resume_revert().unwrap();
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+        assert!(backend.get_block_info(&BlockId::Number(1)).unwrap().is_none());
+        assert!(backend.db.get_cf(&col, ROW_REVERT_TO).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_chain_head_updates() {
+        let db = temp_db().await;
+        let backend = db.backend();
+        let mut rx = backend.subscribe_chain_head();
+
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ChainHeadUpdate {
+                stage: PipelineStage::BlockImport,
+                block_n: 0,
+                chain_head: ChainHead {
+                    latest_block_n: Some(0),
+                    l1_confirmed_block_n: None,
+                    oldest_backfilled_block: None,
+                    oldest_state_block_n: None,
+                    global_trie_block_n: Some(0)
+                }
+            }
+        );
+
+        backend.write_last_confirmed_block(0).unwrap();
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ChainHeadUpdate {
+                stage: PipelineStage::L1Confirmation,
+                block_n: 0,
+                chain_head: ChainHead {
+                    latest_block_n: Some(0),
+                    l1_confirmed_block_n: Some(0),
+                    oldest_backfilled_block: None,
+                    oldest_state_block_n: None,
+                    global_trie_block_n: Some(0)
+                }
+            }
+        );
+
+        // The L1 head did not move.
+        backend.write_last_confirmed_block(0).unwrap();
+        assert!(rx.try_recv().is_err());
+        assert_eq!(
+            backend.get_chain_head().unwrap(),
+            ChainHead {
+                latest_block_n: Some(0),
+                l1_confirmed_block_n: Some(0),
+                oldest_backfilled_block: None,
+                oldest_state_block_n: None,
+                global_trie_block_n: Some(0)
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_global_trie_progress() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        // The tries follow the blocks until they are imported without updating them.
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), Some(1));
+
+        // The tries of this database were never updated.
+        backend.record_global_trie_lag().unwrap();
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), None);
+        assert_eq!(backend.get_chain_head().unwrap().global_trie_block_n, None);
+
+        let mut rx = backend.subscribe_chain_head();
+        backend.write_global_trie_block_n(Some(0)).unwrap();
+        assert_eq!(rx.try_recv().unwrap().stage, PipelineStage::GlobalTrie);
+        // The lag is only recorded once.
+        backend.record_global_trie_lag().unwrap();
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), Some(0));
+        assert_eq!(backend.get_chain_head().unwrap().global_trie_block_n, Some(0));
+
+        // Once the tries caught up, they follow the blocks again and their progress is not written anymore.
+        backend.complete_global_trie_update(1).unwrap();
+        assert_eq!(rx.try_recv().unwrap().stage, PipelineStage::GlobalTrie);
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), Some(1));
+        backend.complete_global_trie_update(2).unwrap();
+        assert!(rx.try_recv().is_err());
+        assert_eq!(
+            backend.get_global_trie_checkpoint().unwrap(),
+            Some(GlobalTrieCheckpoint { block_n: 2, committed: true })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_global_tries() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+        backend.write_global_trie_block_n(Some(0)).unwrap();
+        backend.reconcile_global_tries().unwrap();
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), Some(0));
+
+        // A crash while applying block 1 to the tries leaves them partially updated.
+        backend.begin_global_trie_update(1).unwrap();
+        let mut contract_trie = backend.contract_trie();
+        let key = Felt::ONE.to_bytes_be().as_bits::<Msb0>()[5..].to_owned();
+        contract_trie.insert(bonsai_identifier::CONTRACT, &key, &Felt::TWO).unwrap();
+        contract_trie.commit(BasicId::new(1)).unwrap();
+        assert_ne!(backend.get_global_trie_state_root().unwrap(), Felt::ZERO);
+
+        // No trie log is saved by default: the tries cannot be reverted, and are reset for the trie catch-up.
+        backend.reconcile_global_tries().unwrap();
+        assert_eq!(backend.get_global_trie_state_root().unwrap(), Felt::ZERO);
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), None);
+        assert_eq!(backend.get_global_trie_checkpoint().unwrap(), None);
+
+        // The tries do not match any stored block.
+        let mut contract_trie = backend.contract_trie();
+        contract_trie.insert(bonsai_identifier::CONTRACT, &key, &Felt::TWO).unwrap();
+        contract_trie.commit(BasicId::new(1)).unwrap();
+        backend.write_global_trie_block_n(Some(1)).unwrap();
+        backend.reconcile_global_tries().unwrap();
+        assert_eq!(backend.get_global_trie_state_root().unwrap(), Felt::ZERO);
+        assert_eq!(backend.get_global_trie_block_n().unwrap(), None);
     }
 
     #[tokio::test]
@@ -178,4 +562,112 @@ mod block_tests {
         );
         assert_eq!(backend.find_tx_hash_block(&tx_hash_1).unwrap().unwrap(), (block_pending, TxIndex(1)));
     }
+
+    /// The `generation`-th version of the pending block. Its transactions and their hashes are tagged with the
+    /// generation, which makes a block mixing two versions of the pending block detectable.
+    fn pending_block_generation(generation: u64) -> MadaraMaybePendingBlock {
+        let transactions = (0..generation)
+            .map(|_| InvokeTransactionV0 { max_fee: generation.into(), ..Default::default() }.into())
+            .collect();
+        let receipts = (0..generation).map(|_| InvokeTransactionReceipt::default().into()).collect();
+        let tx_hashes = (0..generation).map(|i| Felt::from(generation * 1000 + i)).collect();
+        MadaraMaybePendingBlock {
+            info: MadaraPendingBlockInfo::new(PendingHeader::default(), tx_hashes).into(),
+            inner: MadaraBlockInner::new(transactions, receipts),
+        }
+    }
+
+    fn assert_single_generation(block: &MadaraMaybePendingBlock) {
+        let generation = block.info.tx_hashes().len() as u64;
+        assert_eq!(block.inner.transactions.len() as u64, generation);
+        assert_eq!(block.inner.receipts.len() as u64, generation);
+        for (i, (tx, tx_hash)) in block.inner.transactions.iter().zip(block.info.tx_hashes()).enumerate() {
+            let Transaction::Invoke(InvokeTransaction::V0(tx)) = tx else { panic!("Unexpected transaction {tx:?}") };
+            assert_eq!(tx.max_fee, Felt::from(generation));
+            assert_eq!(*tx_hash, Felt::from(generation * 1000 + i as u64));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_block_concurrent_update() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        std::thread::scope(|s| {
+            let writer = s.spawn(|| {
+                for generation in 1..=200 {
+                    backend
+                        .store_block(pending_block_generation(generation), StateDiff::default(), vec![], None, None)
+                        .unwrap();
+                }
+            });
+            while !writer.is_finished() {
+                assert_single_generation(&backend.get_block(&DbBlockId::Pending).unwrap().unwrap());
+            }
+            writer.join().unwrap();
+        });
+
+        assert_eq!(backend.get_block(&DbBlockId::Pending).unwrap().unwrap(), pending_block_generation(200));
+    }
+
+    #[tokio::test]
+    async fn test_pending_block_read_scope() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend.store_block(pending_block_generation(1), StateDiff::default(), vec![], None, None).unwrap();
+
+        ReadScope::run(async {
+            let info = backend.get_block_info(&DbBlockId::Pending).unwrap().unwrap();
+            backend.store_block(pending_block_generation(2), StateDiff::default(), vec![], None, None).unwrap();
+
+            // The pending block was replaced in the middle of the scope: every read still sees the first one.
+            assert_eq!(info, pending_block_generation(1).info);
+            assert_eq!(
+                backend.get_block_inner(&DbBlockId::Pending).unwrap().unwrap(),
+                pending_block_generation(1).inner
+            );
+            assert_eq!(backend.get_block(&DbBlockId::Pending).unwrap().unwrap(), pending_block_generation(1));
+        })
+        .await;
+
+        assert_eq!(backend.get_block(&DbBlockId::Pending).unwrap().unwrap(), pending_block_generation(2));
+    }
+
+    #[tokio::test]
+    async fn test_read_scope_snapshot() {
+        let db = temp_db().await;
+        let backend = db.backend();
+        let (contract, key) = (Felt::ONE, Felt::TWO);
+        let state_diff = |value: u64| StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: contract,
+                storage_entries: vec![StorageEntry { key, value: Felt::from(value) }],
+            }],
+            ..Default::default()
+        };
+
+        backend.store_block(finalized_block_zero(Header::default()), state_diff(3), vec![], None, None).unwrap();
+
+        ReadScope::run(async {
+            assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+            backend.store_block(finalized_block_one(), state_diff(4), vec![], None, None).unwrap();
+
+            // Block #1 was imported in the middle of the scope: no read of the scope sees it.
+            assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+            assert_eq!(backend.get_block_info(&DbBlockId::Number(1)).unwrap(), None);
+            assert_eq!(backend.find_tx_hash_block_info(&Felt::from(10)).unwrap(), None);
+            assert_eq!(
+                backend.get_contract_storage_at(&DbBlockId::Number(1), &contract, &key).unwrap(),
+                Some(Felt::from(3))
+            );
+        })
+        .await;
+
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(1));
+        assert_eq!(
+            backend.get_contract_storage_at(&DbBlockId::Number(1), &contract, &key).unwrap(),
+            Some(Felt::from(4))
+        );
+    }
 }
@@ -0,0 +1,295 @@
+//! Guards against silent changes of the state roots computed by the bonsai tries.
+//!
+//! The roots are checked in two ways:
+//!
+//! - Against the fixtures in `resources/bonsai`, which hold the leaves of a small trie and its root: any change of the
+//!   root, for example after upgrading bonsai or rocksdb, fails the tests, and so does a missing fixture. To record a
+//!   fixture again, delete it and run the tests with `MADARA_RECORD_BONSAI_FIXTURES=1`. A root is only ever recorded
+//!   once it matches the reference implementation below.
+//! - Against [`reference_root`], a from-scratch implementation of the Starknet binary Merkle-Patricia trie, with
+//!   [proptest] [state machine testing] on random sequences of insertions, removals and commits.
+//!
+//! [state machine testing]: https://proptest-rs.github.io/proptest/proptest/state-machine.html
+
+use crate::{GlobalTrie, MadaraBackend};
+use bitvec::order::Msb0;
+use bitvec::vec::BitVec;
+use bitvec::view::AsBits;
+use bonsai_trie::id::BasicId;
+use mp_chain_config::ChainConfig;
+use proptest::prelude::*;
+use proptest_state_machine::{ReferenceStateMachine, StateMachineTest};
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Height of the global tries.
+const TRIE_HEIGHT: usize = 251;
+const IDENTIFIER: &[u8] = b"0xtest";
+/// When set, the missing fixtures are recorded instead of failing the tests.
+const RECORD_FIXTURES_ENV: &str = "MADARA_RECORD_BONSAI_FIXTURES";
+
+fn key_bits(key: &Felt) -> BitVec<u8, Msb0> {
+    key.to_bytes_be().as_bits()[5..].to_owned()
+}
+
+/// Root of the Starknet binary Merkle-Patricia trie holding `leaves`, computed without bonsai. Leaves with a value of
+/// zero are not part of the trie.
+///
+/// A binary node hashes to `H(left, right)`, and an edge node to `H(child, path) + length`.
+pub fn reference_root<H: StarkHash>(leaves: &BTreeMap<Felt, Felt>) -> Felt {
+    let mut leaves: Vec<_> =
+        leaves.iter().filter(|(_, value)| **value != Felt::ZERO).map(|(key, value)| (key_bits(key), *value)).collect();
+    if leaves.is_empty() {
+        return Felt::ZERO;
+    }
+    leaves.sort_by(|(a, _), (b, _)| a.cmp(b));
+    reference_node_hash::<H>(&leaves, 0)
+}
+
+/// Hash of the node at `depth` above `leaves`, which are sorted and share their first `depth` bits.
+fn reference_node_hash<H: StarkHash>(leaves: &[(BitVec<u8, Msb0>, Felt)], depth: usize) -> Felt {
+    if depth == TRIE_HEIGHT {
+        return leaves[0].1;
+    }
+
+    // The leaves are sorted: the prefix shared by all of them is the one shared by the first and the last.
+    let (first, last) = (&leaves[0].0, &leaves[leaves.len() - 1].0);
+    let common = first[depth..].iter().zip(last[depth..].iter()).take_while(|(a, b)| a == b).count();
+    if common > 0 {
+        let child = reference_node_hash::<H>(leaves, depth + common);
+        let path = first[depth..depth + common]
+            .iter()
+            .fold(Felt::ZERO, |path, bit| path * Felt::TWO + if *bit { Felt::ONE } else { Felt::ZERO });
+        return H::hash(&child, &path) + Felt::from(common as u64);
+    }
+
+    let split = leaves.partition_point(|(key, _)| !key[depth]);
+    H::hash(
+        &reference_node_hash::<H>(&leaves[..split], depth + 1),
+        &reference_node_hash::<H>(&leaves[split..], depth + 1),
+    )
+}
+
+/// The leaves of a small trie and its root, as stored in the `resources/bonsai` fixtures.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieFixture {
+    pub leaves: Vec<(Felt, Felt)>,
+    pub root: Felt,
+}
+
+impl TrieFixture {
+    pub fn path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("resources").join("bonsai").join(format!("{name}.json"))
+    }
+
+    /// Returns `None` when the fixture has not been recorded yet.
+    pub fn load(path: &Path) -> Option<Self> {
+        let file = std::fs::read_to_string(path).ok()?;
+        Some(serde_json::from_str(&file).expect("Malformed trie fixture"))
+    }
+
+    pub fn save(&self, path: &Path) {
+        std::fs::create_dir_all(path.parent().expect("Fixture path has a parent")).unwrap();
+        std::fs::write(path, serde_json::to_string_pretty(self).unwrap()).unwrap();
+    }
+
+    /// Inserts the leaves of the fixture into `trie` and commits them, returning the root computed by bonsai.
+    pub fn apply<H: StarkHash + Send + Sync>(&self, trie: &mut GlobalTrie<H>, block_n: u64) -> Felt {
+        for (key, value) in &self.leaves {
+            trie.insert(IDENTIFIER, &key_bits(key), value).unwrap();
+        }
+        trie.commit(BasicId::new(block_n)).unwrap();
+        trie.root_hash(IDENTIFIER).unwrap()
+    }
+
+    /// Random leaves, with keys of 251 bits.
+    fn random_leaves(seed: u64, n_leaves: usize) -> Vec<(Felt, Felt)> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..n_leaves)
+            .map(|_| {
+                let mut key = rng.gen::<[u8; 32]>();
+                key[0] &= 0b111;
+                (Felt::from_bytes_be(&key), Felt::from(rng.gen::<u128>()))
+            })
+            .collect()
+    }
+}
+
+/// Checks the root of `trie` once the leaves of the fixture `name` are inserted. A missing fixture is only recorded
+/// when [`RECORD_FIXTURES_ENV`] is set.
+fn check_fixture<H: StarkHash + Send + Sync>(name: &str, mut trie: GlobalTrie<H>, seed: u64) {
+    let path = TrieFixture::path(name);
+    let (fixture, recorded) = match TrieFixture::load(&path) {
+        Some(fixture) => (fixture, true),
+        None if std::env::var_os(RECORD_FIXTURES_ENV).is_some() => {
+            (TrieFixture { leaves: TrieFixture::random_leaves(seed, 64), root: Felt::ZERO }, false)
+        }
+        None => {
+            panic!("Missing trie fixture {}, run the tests with {RECORD_FIXTURES_ENV}=1 to record it", path.display())
+        }
+    };
+
+    let root = fixture.apply(&mut trie, 0);
+    let leaves = fixture.leaves.iter().copied().collect();
+    assert_eq!(root, reference_root::<H>(&leaves), "Bonsai root of {name} does not match the reference root");
+
+    if recorded {
+        assert_eq!(root, fixture.root, "Root of {name} changed, see {}", path.display());
+    } else {
+        TrieFixture { root, ..fixture }.save(&path);
+    }
+}
+
+#[test]
+fn test_contract_storage_trie_fixture() {
+    let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+    check_fixture::<Pedersen>("contract_storage_trie", backend.contract_storage_trie(), 1);
+}
+
+#[test]
+fn test_class_trie_fixture() {
+    let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+    check_fixture::<Poseidon>("class_trie", backend.class_trie(), 2);
+}
+
+#[test]
+fn test_reference_root() {
+    // A single leaf hangs from an edge covering the whole key.
+    let leaves = BTreeMap::from([(Felt::ONE, Felt::TWO)]);
+    assert_eq!(reference_root::<Pedersen>(&leaves), Pedersen::hash(&Felt::TWO, &Felt::ONE) + Felt::from(251));
+    // Leaves with a value of zero are removed.
+    let leaves = BTreeMap::from([(Felt::ONE, Felt::ZERO)]);
+    assert_eq!(reference_root::<Pedersen>(&leaves), Felt::ZERO);
+
+    // Keys 0b10 and 0b11 share an edge of 250 bits, then split on a binary node right above the leaves.
+    let leaves = BTreeMap::from([(Felt::TWO, Felt::ONE), (Felt::from(3), Felt::TWO)]);
+    let binary = Pedersen::hash(&Felt::ONE, &Felt::TWO);
+    assert_eq!(reference_root::<Pedersen>(&leaves), Pedersen::hash(&binary, &Felt::ONE) + Felt::from(250));
+}
+
+proptest_state_machine::prop_state_machine! {
+    #![proptest_config(proptest::ProptestConfig {
+        // Every case opens a new database.
+        cases: 32,
+        ..Default::default()
+    })]
+
+    /// Simulates insertions, removals and commits on a [`GlobalTrie`], checking its root against [`reference_root`]
+    /// after every commit.
+    #[test]
+    fn bonsai_proptest(sequential 1..128 => TrieUnderTest);
+}
+
+/// Keys are often small, so that they share long prefixes and make for long edges.
+fn trie_key() -> impl Strategy<Value = Felt> {
+    prop_oneof![
+        (0..256_u64).prop_map(Felt::from),
+        any::<[u8; 32]>().prop_map(|mut key| {
+            key[0] &= 0b111;
+            Felt::from_bytes_be(&key)
+        }),
+    ]
+}
+
+fn trie_value() -> impl Strategy<Value = Felt> {
+    (1..u128::MAX).prop_map(Felt::from)
+}
+
+#[derive(Clone, Debug)]
+pub enum TrieTransition {
+    /// Inserts a leaf, or removes it when the value is zero.
+    Insert(Felt, Felt),
+    Remove(Felt),
+    Commit,
+}
+
+pub struct TrieStateMachine;
+
+impl ReferenceStateMachine for TrieStateMachine {
+    type State = BTreeMap<Felt, Felt>;
+    type Transition = TrieTransition;
+
+    fn init_state() -> BoxedStrategy<Self::State> {
+        Just(BTreeMap::new()).boxed()
+    }
+
+    fn transitions(state: &Self::State) -> BoxedStrategy<Self::Transition> {
+        let insert = (trie_key(), trie_value()).prop_map(|(key, value)| TrieTransition::Insert(key, value));
+        if state.is_empty() {
+            return prop_oneof![4 => insert, 1 => Just(TrieTransition::Commit)].boxed();
+        }
+
+        let keys: Vec<_> = state.keys().copied().collect();
+        let update = (proptest::sample::select(keys.clone()), prop_oneof![trie_value(), Just(Felt::ZERO)])
+            .prop_map(|(key, value)| TrieTransition::Insert(key, value));
+        let remove = proptest::sample::select(keys).prop_map(TrieTransition::Remove);
+        prop_oneof![
+            3 => insert,
+            1 => update,
+            1 => remove,
+            1 => Just(TrieTransition::Commit),
+        ]
+        .boxed()
+    }
+
+    fn apply(mut state: Self::State, transition: &Self::Transition) -> Self::State {
+        match transition {
+            TrieTransition::Insert(key, value) if *value == Felt::ZERO => {
+                state.remove(key);
+            }
+            TrieTransition::Insert(key, value) => {
+                state.insert(*key, *value);
+            }
+            TrieTransition::Remove(key) => {
+                state.remove(key);
+            }
+            TrieTransition::Commit => {}
+        }
+        state
+    }
+}
+
+pub struct TrieUnderTest {
+    // Keeps the database open.
+    _backend: Arc<MadaraBackend>,
+    trie: GlobalTrie<Pedersen>,
+    block_n: u64,
+}
+
+impl StateMachineTest for TrieUnderTest {
+    type SystemUnderTest = Self;
+    type Reference = TrieStateMachine;
+
+    fn init_test(_ref_state: &<Self::Reference as ReferenceStateMachine>::State) -> Self::SystemUnderTest {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let trie = backend.contract_storage_trie();
+        Self { _backend: backend, trie, block_n: 0 }
+    }
+
+    fn apply(
+        mut state: Self::SystemUnderTest,
+        ref_state: &<Self::Reference as ReferenceStateMachine>::State,
+        transition: <Self::Reference as ReferenceStateMachine>::Transition,
+    ) -> Self::SystemUnderTest {
+        match transition {
+            TrieTransition::Insert(key, value) => state.trie.insert(IDENTIFIER, &key_bits(&key), &value).unwrap(),
+            TrieTransition::Remove(key) => state.trie.remove(IDENTIFIER, &key_bits(&key)).unwrap(),
+            TrieTransition::Commit => {
+                state.trie.commit(BasicId::new(state.block_n)).unwrap();
+                state.block_n += 1;
+                assert_eq!(
+                    state.trie.root_hash(IDENTIFIER).unwrap(),
+                    reference_root::<Pedersen>(ref_state),
+                    "Bonsai root does not match the reference root after commit #{}",
+                    state.block_n
+                );
+            }
+        }
+        state
+    }
+}
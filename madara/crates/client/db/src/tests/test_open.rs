@@ -1,5 +1,7 @@
 use super::common::*;
+use crate::db_block_id::DbBlockId;
 use crate::DatabaseService;
+use mp_block::Header;
 use mp_chain_config::ChainConfig;
 
 #[tokio::test]
@@ -12,8 +14,65 @@ async fn test_open_different_chain_id() {
     let temp_dir = tempfile::TempDir::new().unwrap();
     {
         let chain_config = std::sync::Arc::new(ChainConfig::starknet_integration());
-        let _db = DatabaseService::new(temp_dir.path(), None, false, chain_config, Default::default()).await.unwrap();
+        let _db =
+            DatabaseService::new(temp_dir.path(), None, false, chain_config, Default::default(), Default::default())
+                .await
+                .unwrap();
     }
     let chain_config = std::sync::Arc::new(ChainConfig::madara_test());
-    assert!(DatabaseService::new(temp_dir.path(), None, false, chain_config, Default::default()).await.is_err());
+    assert!(DatabaseService::new(temp_dir.path(), None, false, chain_config, Default::default(), Default::default())
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_pending_migrations_read_only() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let chain_config = std::sync::Arc::new(ChainConfig::madara_test());
+    let _db = DatabaseService::new(temp_dir.path(), None, false, chain_config, Default::default(), Default::default())
+        .await
+        .unwrap();
+    // The database is read while the node holds its lock.
+    assert!(crate::migrations::pending_migrations(temp_dir.path()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_backup_and_restore() {
+    let (db_dir, backup_dir, restored_dir) =
+        (tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap(), tempfile::TempDir::new().unwrap());
+    let chain_config = std::sync::Arc::new(ChainConfig::madara_test());
+    {
+        let db = DatabaseService::new(
+            db_dir.path(),
+            None,
+            false,
+            chain_config.clone(),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        let backend = db.backend();
+        backend
+            .store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![], None, None)
+            .unwrap();
+        backend.backup_to(backup_dir.path().to_owned()).await.unwrap();
+        // Not part of the backup.
+        backend.store_block(finalized_block_one(), finalized_state_diff_one(), vec![], None, None).unwrap();
+    }
+
+    let db = DatabaseService::new(
+        restored_dir.path(),
+        Some(backup_dir.path().to_owned()),
+        true,
+        chain_config,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let backend = db.backend();
+    assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+    assert_eq!(backend.get_block(&DbBlockId::Number(0)).unwrap().unwrap(), finalized_block_zero(Header::default()));
+    assert_eq!(backend.get_block_state_diff(&DbBlockId::Number(0)).unwrap(), Some(finalized_state_diff_zero()));
 }
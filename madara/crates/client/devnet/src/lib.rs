@@ -53,8 +53,6 @@ impl StorageDiffs {
 
 /// Universal Deployer Contract.
 const UDC_CLASS_DEFINITION: &[u8] = include_bytes!("../../../../../cairo-artifacts/madara_contracts_UDC.json");
-const UDC_CONTRACT_ADDRESS: Felt =
-    Felt::from_hex_unchecked("0x041a78e741e5af2fec34b695679bc6891742439f7afb8484ecd7766661ad02bf");
 
 const ERC20_CLASS_DEFINITION: &[u8] =
     include_bytes!("../../../../../cairo-artifacts/openzeppelin_ERC20Upgradeable.contract_class.json");
@@ -77,15 +75,16 @@ pub struct ChainGenesisDescription {
 }
 
 impl ChainGenesisDescription {
+    /// The UDC and the fee tokens. The UDC is deployed at `udc_address`, see [`ChainConfig::udc_address`].
     #[tracing::instrument(fields(module = "ChainGenesisDescription"))]
-    pub fn base_config() -> anyhow::Result<Self> {
+    pub fn base_config(udc_address: Felt) -> anyhow::Result<Self> {
         let udc_class = InitiallyDeclaredClass::new_legacy(UDC_CLASS_DEFINITION).context("Failed to add UDC class")?;
         let erc20_class =
             InitiallyDeclaredClass::new_sierra(ERC20_CLASS_DEFINITION).context("Failed to add ERC20 class")?;
         Ok(Self {
             initial_balances: InitialBalances::default(),
             deployed_contracts: InitiallyDeployedContracts::default()
-                .with(UDC_CONTRACT_ADDRESS, udc_class.class_hash())
+                .with(udc_address, udc_class.class_hash())
                 .with(ERC20_ETH_CONTRACT_ADDRESS, erc20_class.class_hash())
                 .with(ERC20_STRK_CONTRACT_ADDRESS, erc20_class.class_hash()),
             declared_classes: InitiallyDeclaredClasses::default().with(udc_class).with(erc20_class),
@@ -302,7 +301,7 @@ mod tests {
     fn chain_with_mempool_limits(mempool_limits: MempoolLimits) -> DevnetForTesting {
         let _ = tracing_subscriber::fmt().with_test_writer().try_init();
 
-        let mut g = ChainGenesisDescription::base_config().unwrap();
+        let mut g = ChainGenesisDescription::base_config(mp_chain_config::UDC_CONTRACT_ADDRESS).unwrap();
         let contracts = g.add_devnet_contracts(10).unwrap();
 
         let chain_config = Arc::new(ChainConfig::madara_devnet());
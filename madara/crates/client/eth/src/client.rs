@@ -4,11 +4,13 @@ use alloy::sol_types::SolEvent;
 use alloy::{
     primitives::Address,
     providers::{Provider, ProviderBuilder, ReqwestProvider, RootProvider},
+    rpc::client::RpcClient,
     rpc::types::Filter,
     sol,
     transports::http::{Client, Http},
 };
 use mc_analytics::register_gauge_metric_instrument;
+use mp_utils::http::HttpClientConfig;
 use opentelemetry::{global, KeyValue};
 use opentelemetry::{global::Error, metrics::Gauge};
 
@@ -89,8 +91,14 @@ impl Clone for EthereumClient {
 
 impl EthereumClient {
     /// Create a new EthereumClient instance with the given RPC URL
-    pub async fn new(url: Url, l1_core_address: Address, l1_block_metrics: L1BlockMetrics) -> anyhow::Result<Self> {
-        let provider = ProviderBuilder::new().on_http(url);
+    pub async fn new(
+        url: Url,
+        l1_core_address: Address,
+        l1_block_metrics: L1BlockMetrics,
+        http_config: &HttpClientConfig,
+    ) -> anyhow::Result<Self> {
+        let client = http_config.reqwest_client().context("Creating the L1 RPC http client")?;
+        let provider = ProviderBuilder::new().on_client(RpcClient::new(Http::with_client(client, url), false));
 
         EthereumClient::assert_core_contract_exists(&provider, l1_core_address).await?;
 
@@ -280,7 +288,8 @@ pub mod eth_client_getter_test {
         let core_contract_address = Address::parse_checksummed(INVALID_CORE_CONTRACT_ADDRESS, None).unwrap();
         let l1_block_metrics = L1BlockMetrics::register().unwrap();
 
-        let new_client_result = EthereumClient::new(rpc_url, core_contract_address, l1_block_metrics).await;
+        let new_client_result =
+            EthereumClient::new(rpc_url, core_contract_address, l1_block_metrics, &HttpClientConfig::default()).await;
         assert!(new_client_result.is_err(), "EthereumClient::new should fail with an invalid core contract address");
     }
 
@@ -0,0 +1,633 @@
+//! Audit of the state diffs published on L1.
+//!
+//! For every state update on the core contract, the blobs of the L1 transaction are fetched from a beacon node and
+//! decoded back into a state diff, which is compared with the state diffs of the blocks we synced. A mismatch means
+//! that the data published on L1 does not allow to reconstruct the state of the chain we follow.
+//!
+//! The state updates posted with calldata are audited as well: their state diff is not in the `updateState` calldata,
+//! but in the memory pages registered for the proof of the state update, which are searched in the L1 blocks before
+//! it. The state diffs are decoded in the compressed format of Starknet 0.13.3 and later when they are not in the
+//! uncompressed one.
+
+use crate::client::{EthereumClient, StarknetCoreContract};
+use crate::utils::{convert_log_state_update, u256_to_felt};
+use alloy::consensus::Transaction as _;
+use alloy::eips::BlockNumberOrTag;
+use alloy::primitives::{keccak256, Bytes, FixedBytes, B256, U256};
+use alloy::providers::Provider;
+use alloy::rpc::types::{BlockTransactionsKind, Filter};
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolEvent};
+use anyhow::Context;
+use futures::StreamExt;
+use lazy_static::lazy_static;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_state_update::onchain_data::OnchainDataError;
+use mp_state_update::StateDiff;
+use mp_utils::http::HttpClientConfig;
+use mp_utils::service::ServiceContext;
+use num_bigint::BigUint;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use starknet_types_core::felt::Felt;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Number of field elements in a blob.
+const BLOB_LEN: usize = 4096;
+const SECONDS_PER_SLOT: u64 = 12;
+/// Delay between two checks of the local chain head, when the L1 is ahead of us.
+const LOCAL_SYNC_POLL: Duration = Duration::from_secs(5);
+/// Number of L1 blocks before a state update posted with calldata in which its memory pages are searched.
+const MEMORY_PAGES_LOOKBACK: u64 = 1_000;
+/// Max number of memory pages fetched to find the onchain data of a state update posted with calldata.
+const MAX_MEMORY_PAGES: usize = 512;
+
+sol! {
+    /// Emitted by the memory page fact registry when a page of a program output is registered.
+    event LogMemoryPageFactContinuous(bytes32 factHash, uint256 memoryHash, uint256 prod);
+    /// Emitted by the SHARP verifier with the hashes of the memory pages of the output of a proven program.
+    event LogMemoryPagesHashes(bytes32 factHash, bytes32[] pagesHashes);
+    function registerContinuousMemoryPage(uint256 startAddr, uint256[] values, uint256 z, uint256 alpha, uint256 prime);
+}
+
+lazy_static! {
+    /// EIP-4844 BLS12-381 modulus.
+    static ref BLS_MODULUS: BigUint = BigUint::parse_bytes(
+        b"52435875175126190479447740508185965837690552500527637822603658699938581184513",
+        10
+    )
+    .expect("Valid BLS modulus");
+    /// Primitive root of unity of order 4096 of the BLS12-381 scalar field.
+    static ref GENERATOR: BigUint = BigUint::parse_bytes(
+        b"39033254847818212395286706435128746857159659164139250548781411570340225835782",
+        10
+    )
+    .expect("Valid generator");
+}
+
+#[derive(Clone, Debug)]
+pub struct DaAuditConfig {
+    /// Beacon node API used to fetch the blobs.
+    pub beacon_url: Url,
+    pub http: HttpClientConfig,
+}
+
+/// A difference between the state diff published on L1 and the state diff we synced.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DaMismatch {
+    Storage { address: Felt, key: Felt, onchain: Option<Felt>, local: Option<Felt> },
+    ClassHash { address: Felt, onchain: Option<Felt>, local: Option<Felt> },
+    Nonce { address: Felt, onchain: Felt, local: Felt },
+    DeclaredClass { class_hash: Felt, onchain: Option<Felt>, local: Option<Felt> },
+}
+
+/// The final state of the entries modified by one or more state diffs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct FlatStateDiff {
+    storage: BTreeMap<(Felt, Felt), Felt>,
+    class_hashes: BTreeMap<Felt, Felt>,
+    nonces: BTreeMap<Felt, Felt>,
+    declared_classes: BTreeMap<Felt, Felt>,
+}
+
+impl FlatStateDiff {
+    fn apply(&mut self, state_diff: &StateDiff) {
+        for diff in &state_diff.storage_diffs {
+            for entry in &diff.storage_entries {
+                self.storage.insert((diff.address, entry.key), entry.value);
+            }
+        }
+        for item in &state_diff.deployed_contracts {
+            self.class_hashes.insert(item.address, item.class_hash);
+        }
+        for item in &state_diff.replaced_classes {
+            self.class_hashes.insert(item.contract_address, item.class_hash);
+        }
+        for item in &state_diff.nonces {
+            self.nonces.insert(item.contract_address, item.nonce);
+        }
+        for item in &state_diff.declared_classes {
+            self.declared_classes.insert(item.class_hash, item.compiled_class_hash);
+        }
+    }
+
+    /// Compares the state diff decoded from L1 with the local one.
+    ///
+    /// The onchain data holds the nonce of every updated contract, even when it did not change, so only the nonces
+    /// which changed locally are compared.
+    fn compare(onchain: &Self, local: &Self) -> Vec<DaMismatch> {
+        fn diff_maps<K: Ord + Copy, V: PartialEq + Copy, M>(
+            onchain: &BTreeMap<K, V>,
+            local: &BTreeMap<K, V>,
+            mismatch: impl Fn(K, Option<V>, Option<V>) -> M,
+        ) -> Vec<M> {
+            let mut keys: Vec<K> = onchain.keys().chain(local.keys()).copied().collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .filter_map(|key| {
+                    let (onchain, local) = (onchain.get(&key).copied(), local.get(&key).copied());
+                    (onchain != local).then(|| mismatch(key, onchain, local))
+                })
+                .collect()
+        }
+
+        let mut mismatches = diff_maps(&onchain.storage, &local.storage, |(address, key), onchain, local| {
+            DaMismatch::Storage { address, key, onchain, local }
+        });
+        mismatches.extend(diff_maps(&onchain.class_hashes, &local.class_hashes, |address, onchain, local| {
+            DaMismatch::ClassHash { address, onchain, local }
+        }));
+        mismatches.extend(local.nonces.iter().filter_map(|(address, local)| {
+            let onchain = onchain.nonces.get(address).copied().unwrap_or_default();
+            (onchain != *local).then_some(DaMismatch::Nonce { address: *address, onchain, local: *local })
+        }));
+        mismatches.extend(diff_maps(
+            &onchain.declared_classes,
+            &local.declared_classes,
+            |class_hash, onchain, local| DaMismatch::DeclaredClass { class_hash, onchain, local },
+        ));
+        mismatches
+    }
+}
+
+/// In-place radix-2 FFT over the BLS12-381 scalar field. Takes its input in bit-reversed order.
+fn fft(values: &mut [BigUint], root: &BigUint) {
+    let modulus = &*BLS_MODULUS;
+    let mut len = 2;
+    while len <= values.len() {
+        let step = root.modpow(&BigUint::from(values.len() / len), modulus);
+        for start in (0..values.len()).step_by(len) {
+            let mut w = BigUint::from(1u32);
+            for i in start..start + len / 2 {
+                let u = values[i].clone();
+                let v = (&values[i + len / 2] * &w) % modulus;
+                values[i] = (&u + &v) % modulus;
+                values[i + len / 2] = (u + modulus - v) % modulus;
+                w = (w * &step) % modulus;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Recovers the field elements encoded in a blob.
+///
+/// The blob holds the evaluations of the polynomial whose coefficients are the encoded felts, at the 4096th roots of
+/// unity in bit-reversed order: the coefficients are recovered with an inverse FFT.
+fn recover_blob_data(blob: &[u8]) -> anyhow::Result<Vec<Felt>> {
+    anyhow::ensure!(blob.len() == BLOB_LEN * 32, "Invalid blob length {}", blob.len());
+    let modulus = &*BLS_MODULUS;
+    let mut values: Vec<BigUint> = blob.chunks_exact(32).map(BigUint::from_bytes_be).collect();
+
+    let exponent = modulus - 2u32;
+    fft(&mut values, &GENERATOR.modpow(&exponent, modulus));
+
+    let inverse_len = BigUint::from(BLOB_LEN).modpow(&exponent, modulus);
+    values
+        .into_iter()
+        .map(|value| {
+            let value = (value * &inverse_len) % modulus;
+            let felt = Felt::from_bytes_be_slice(&value.to_bytes_be());
+            anyhow::ensure!(felt.to_biguint() == value, "Blob element {value:#x} is not a felt");
+            Ok(felt)
+        })
+        .collect()
+}
+
+/// Decodes a state diff in the onchain data format, compressed or not. The data can be padded with zeros, as the
+/// last blob is.
+fn decode_onchain_data(data: &[Felt]) -> anyhow::Result<StateDiff> {
+    fn unpadded(
+        data: &[Felt],
+        decode: fn(&[Felt]) -> Result<StateDiff, OnchainDataError>,
+    ) -> Result<StateDiff, OnchainDataError> {
+        match decode(data) {
+            Err(OnchainDataError::TrailingData(n)) if data[data.len() - n..].iter().all(|felt| *felt == Felt::ZERO) => {
+                decode(&data[..data.len() - n])
+            }
+            res => res,
+        }
+    }
+
+    unpadded(data, StateDiff::from_onchain_data).or_else(|err| {
+        unpadded(data, StateDiff::from_compressed_onchain_data)
+            .with_context(|| format!("Decoding the state diff, which is not in the uncompressed format either ({err})"))
+    })
+}
+
+/// Hash of a node of the fact tree of a proven program output, from the hashes of its children and the offsets at
+/// which they end in the output: `keccak256(hash_0, end_0, hash_1, end_1, ...) + 1`.
+fn fact_node_hash(children: impl IntoIterator<Item = (B256, usize)>) -> U256 {
+    let mut encoded = vec![];
+    for (hash, end) in children {
+        encoded.extend_from_slice(hash.as_slice());
+        encoded.extend_from_slice(&U256::from(end).to_be_bytes::<32>());
+    }
+    U256::from_be_bytes(keccak256(encoded).0) + U256::from(1)
+}
+
+/// Finds the memory pages of the onchain data of a state update posted with calldata.
+///
+/// The fact of the state update is a tree: its left child is the program output passed to `updateState`, of length
+/// `output_len`, and its right child is the onchain data, of hash `data_hash` and length `data_size`. The onchain data
+/// is either a single page, whose hash is the keccak of its values, or a node whose children are consecutive pages of
+/// `pages_hashes`.
+fn find_onchain_data_pages(
+    output_len: usize,
+    data_hash: U256,
+    data_size: usize,
+    pages_hashes: &[B256],
+    pages: &HashMap<B256, Vec<U256>>,
+) -> Option<Vec<B256>> {
+    let leaf = B256::from(data_hash);
+    if pages.get(&leaf).is_some_and(|values| values.len() == data_size) {
+        return Some(vec![leaf]);
+    }
+    for start in 0..pages_hashes.len() {
+        let mut children = vec![];
+        let mut end = output_len;
+        for hash in &pages_hashes[start..] {
+            let Some(values) = pages.get(hash) else { break };
+            end += values.len();
+            if end > output_len + data_size {
+                break;
+            }
+            children.push((*hash, end));
+            if end == output_len + data_size && fact_node_hash(children.iter().copied()) == data_hash {
+                return Some(children.into_iter().map(|(hash, _)| hash).collect());
+            }
+        }
+    }
+    None
+}
+
+/// Fetches the onchain data of a state update posted with calldata, from the memory pages registered in the L1 blocks
+/// up to `block_number`.
+async fn fetch_memory_pages(
+    eth_client: &EthereumClient,
+    block_number: u64,
+    update: &StarknetCoreContract::updateStateCall,
+) -> anyhow::Result<Vec<Felt>> {
+    let data_size: usize = update.onchainDataSize.try_into().context("Invalid onchain data size")?;
+    let filter = Filter::new().from_block(block_number.saturating_sub(MEMORY_PAGES_LOOKBACK)).to_block(block_number);
+
+    // Transactions registering each memory page.
+    let registrations: HashMap<B256, B256> = eth_client
+        .provider
+        .get_logs(&filter.clone().event_signature(LogMemoryPageFactContinuous::SIGNATURE_HASH))
+        .await
+        .context("Fetching the memory page registrations")?
+        .into_iter()
+        .filter_map(|log| {
+            let tx_hash = log.transaction_hash?;
+            Some((B256::from(log.log_decode::<LogMemoryPageFactContinuous>().ok()?.inner.data.memoryHash), tx_hash))
+        })
+        .collect();
+    // Most recent first.
+    let pages_hashes: Vec<Vec<B256>> = eth_client
+        .provider
+        .get_logs(&filter.event_signature(LogMemoryPagesHashes::SIGNATURE_HASH))
+        .await
+        .context("Fetching the memory pages of the proven program outputs")?
+        .into_iter()
+        .rev()
+        .filter_map(|log| Some(log.log_decode::<LogMemoryPagesHashes>().ok()?.inner.data.pagesHashes))
+        .collect();
+
+    let mut pages: HashMap<B256, Vec<U256>> = HashMap::new();
+    let leaf = [B256::from(update.onchainDataHash)];
+    for candidates in std::iter::once(&leaf[..]).chain(pages_hashes.iter().map(Vec::as_slice)) {
+        for hash in candidates {
+            let Some(tx_hash) = registrations.get(hash).filter(|_| !pages.contains_key(hash)) else { continue };
+            anyhow::ensure!(pages.len() < MAX_MEMORY_PAGES, "Too many memory pages to search");
+            let tx = eth_client
+                .provider
+                .get_transaction_by_hash(*tx_hash)
+                .await?
+                .with_context(|| format!("L1 transaction {tx_hash} not found"))?;
+            let Ok(registration) = registerContinuousMemoryPageCall::abi_decode(tx.input(), true) else { continue };
+            pages.insert(*hash, registration.values);
+        }
+        let found =
+            find_onchain_data_pages(update.programOutput.len(), update.onchainDataHash, data_size, candidates, &pages);
+        if let Some(found) = found {
+            return found.iter().flat_map(|hash| &pages[hash]).map(|value| u256_to_felt(*value)).collect();
+        }
+    }
+    anyhow::bail!("The memory pages of the onchain data were not found in the last {MEMORY_PAGES_LOOKBACK} L1 blocks")
+}
+
+/// The versioned hash of a blob, as found in the L1 transaction, from its KZG commitment.
+fn versioned_hash(kzg_commitment: &[u8]) -> B256 {
+    let mut hash: [u8; 32] = Sha256::digest(kzg_commitment).into();
+    hash[0] = 0x01;
+    B256::from(hash)
+}
+
+#[derive(Deserialize)]
+struct BeaconResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct BeaconGenesis {
+    genesis_time: String,
+}
+
+#[derive(Deserialize)]
+struct BlobSidecar {
+    blob: Bytes,
+    kzg_commitment: FixedBytes<48>,
+}
+
+struct BeaconClient {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl BeaconClient {
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        let url = self.url.join(path).context("Building the beacon node url")?;
+        let res = self.client.get(url).send().await?.error_for_status()?;
+        Ok(res.json::<BeaconResponse<T>>().await?.data)
+    }
+
+    async fn genesis_time(&self) -> anyhow::Result<u64> {
+        let genesis: BeaconGenesis = self.get("eth/v1/beacon/genesis").await?;
+        genesis.genesis_time.parse().context("Parsing the beacon chain genesis time")
+    }
+
+    async fn blob_sidecars(&self, slot: u64) -> anyhow::Result<Vec<BlobSidecar>> {
+        self.get(&format!("eth/v1/beacon/blob_sidecars/{slot}")).await
+    }
+}
+
+/// Fetches the onchain data of the state update posted in an L1 transaction, from its blobs or from the memory pages
+/// registered before it. Returns `None` when the transaction is neither.
+async fn fetch_onchain_data(
+    eth_client: &EthereumClient,
+    beacon: &BeaconClient,
+    genesis_time: u64,
+    tx_hash: B256,
+) -> anyhow::Result<Option<Vec<Felt>>> {
+    let tx = eth_client
+        .provider
+        .get_transaction_by_hash(tx_hash)
+        .await?
+        .with_context(|| format!("L1 transaction {tx_hash} not found"))?;
+    let block_number = tx.block_number.context("L1 transaction is not included in a block")?;
+    let Some(versioned_hashes) = tx.blob_versioned_hashes().filter(|hashes| !hashes.is_empty()) else {
+        let Ok(update) = StarknetCoreContract::updateStateCall::abi_decode(tx.input(), true) else {
+            return Ok(None);
+        };
+        return fetch_memory_pages(eth_client, block_number, &update).await.map(Some);
+    };
+    let block = eth_client
+        .provider
+        .get_block_by_number(BlockNumberOrTag::Number(block_number), BlockTransactionsKind::Hashes)
+        .await?
+        .with_context(|| format!("L1 block {block_number} not found"))?;
+
+    let slot = block.header.timestamp.saturating_sub(genesis_time) / SECONDS_PER_SLOT;
+    let mut sidecars: BTreeMap<B256, Bytes> = beacon
+        .blob_sidecars(slot)
+        .await
+        .with_context(|| format!("Fetching the blobs of slot {slot}"))?
+        .into_iter()
+        .map(|sidecar| (versioned_hash(sidecar.kzg_commitment.as_slice()), sidecar.blob))
+        .collect();
+
+    let mut data = vec![];
+    for hash in versioned_hashes {
+        let blob = sidecars.remove(hash).with_context(|| format!("Blob {hash} not found in slot {slot}"))?;
+        data.extend(recover_blob_data(&blob)?);
+    }
+    Ok(Some(data))
+}
+
+/// Audits the state update posted in `tx_hash`, which covers the L2 blocks `blocks`. Returns `None` when the state
+/// update was posted neither with blobs nor with calldata.
+async fn audit_state_update(
+    backend: &MadaraBackend,
+    eth_client: &EthereumClient,
+    beacon: &BeaconClient,
+    genesis_time: u64,
+    tx_hash: B256,
+    blocks: RangeInclusive<u64>,
+) -> anyhow::Result<Option<Vec<DaMismatch>>> {
+    let Some(data) = fetch_onchain_data(eth_client, beacon, genesis_time, tx_hash).await? else {
+        return Ok(None);
+    };
+    let mut onchain = FlatStateDiff::default();
+    onchain.apply(&decode_onchain_data(&data)?);
+
+    let mut local = FlatStateDiff::default();
+    for block_n in blocks {
+        let state_diff = backend
+            .get_block_state_diff(&DbBlockId::Number(block_n))?
+            .with_context(|| format!("State diff of block {block_n} not found"))?;
+        local.apply(&state_diff);
+    }
+
+    Ok(Some(FlatStateDiff::compare(&onchain, &local)))
+}
+
+pub async fn da_audit_worker(
+    backend: Arc<MadaraBackend>,
+    eth_client: Arc<EthereumClient>,
+    config: DaAuditConfig,
+    mut ctx: ServiceContext,
+) -> anyhow::Result<()> {
+    let client = config.http.reqwest_client().context("Creating the beacon node http client")?;
+    let beacon = BeaconClient { client, url: config.beacon_url };
+    let genesis_time = beacon.genesis_time().await.context("Getting the beacon chain genesis time")?;
+
+    let mut last_block = Some(eth_client.get_last_verified_block_number().await?);
+    let event_filter = eth_client.l1_core_contract.event_filter::<StarknetCoreContract::LogStateUpdate>();
+    let mut event_stream = match ctx.run_until_cancelled(event_filter.watch()).await {
+        Some(res) => res.context("Watching the state updates")?.into_stream(),
+        None => return anyhow::Ok(()),
+    };
+
+    tracing::info!("🔍 Auditing the state diffs posted on L1");
+    while let Some(Some(event_result)) = ctx.run_until_cancelled(event_stream.next()).await {
+        let (event, log) = event_result.context("listening for events")?;
+        let block_n = convert_log_state_update(event)?.block_number;
+        let first_block = last_block.map_or(block_n, |n| n + 1);
+        last_block = Some(block_n);
+        let Some(tx_hash) = log.transaction_hash else { continue };
+
+        // The L1 can be ahead of our own sync.
+        while backend.get_latest_block_n()?.map_or(true, |latest| latest < block_n) {
+            if ctx.run_until_cancelled(tokio::time::sleep(LOCAL_SYNC_POLL)).await.is_none() {
+                return anyhow::Ok(());
+            }
+        }
+
+        match audit_state_update(&backend, &eth_client, &beacon, genesis_time, tx_hash, first_block..=block_n).await {
+            Ok(None) => {
+                tracing::debug!("DA audit: state update of blocks #{first_block}-#{block_n} was not found on L1")
+            }
+            Ok(Some(mismatches)) if mismatches.is_empty() => {
+                tracing::info!("🔍 DA audit: state diff of blocks #{first_block}-#{block_n} matches L1")
+            }
+            Ok(Some(mismatches)) => {
+                tracing::error!(
+                    "❗ DA audit: state diff of blocks #{first_block}-#{block_n} posted in {tx_hash} does not match \
+                     the local state diff ({} mismatches)",
+                    mismatches.len()
+                );
+                for mismatch in mismatches {
+                    tracing::error!("DA audit mismatch: {mismatch:?}");
+                }
+            }
+            Err(err) => tracing::warn!("DA audit of blocks #{first_block}-#{block_n} failed: {err:#}"),
+        }
+    }
+
+    anyhow::Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_state_update::{ContractStorageDiffItem, DeployedContractItem, NonceUpdate, StorageEntry};
+
+    fn bit_reverse(i: usize) -> usize {
+        i.reverse_bits() >> (usize::BITS - BLOB_LEN.trailing_zeros())
+    }
+
+    /// Encodes the data the same way as the orchestrator: `blob[i] = P(w^bit_reverse(i))`.
+    fn encode_blob(data: &[Felt]) -> Vec<BigUint> {
+        let mut values: Vec<BigUint> =
+            (0..BLOB_LEN).map(|i| data.get(bit_reverse(i)).map(Felt::to_biguint).unwrap_or_default()).collect();
+        fft(&mut values, &GENERATOR);
+        (0..BLOB_LEN).map(|i| values[bit_reverse(i)].clone()).collect()
+    }
+
+    fn evaluate(data: &[Felt], x: &BigUint) -> BigUint {
+        data.iter()
+            .rev()
+            .fold(BigUint::default(), |acc, coefficient| (acc * x + coefficient.to_biguint()) % &*BLS_MODULUS)
+    }
+
+    fn state_diff() -> StateDiff {
+        StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::from(0x10),
+                storage_entries: vec![StorageEntry { key: Felt::ONE, value: Felt::from(0xabc) }],
+            }],
+            deployed_contracts: vec![DeployedContractItem { address: Felt::from(0x10), class_hash: Felt::from(0x55) }],
+            nonces: vec![NonceUpdate { contract_address: Felt::from(0x10), nonce: Felt::ONE }],
+            ..Default::default()
+        }
+    }
+
+    fn blob_bytes(data: &[Felt]) -> Vec<u8> {
+        encode_blob(data)
+            .iter()
+            .flat_map(|value| {
+                let bytes = value.to_bytes_be();
+                [vec![0u8; 32 - bytes.len()], bytes].concat()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_blob() {
+        let data = state_diff().to_onchain_data().unwrap();
+        let blob = encode_blob(&data);
+        for i in [0, 1, 7, BLOB_LEN - 1] {
+            let x = GENERATOR.modpow(&BigUint::from(bit_reverse(i)), &BLS_MODULUS);
+            assert_eq!(blob[i], evaluate(&data, &x));
+        }
+
+        let recovered = recover_blob_data(&blob_bytes(&data)).unwrap();
+        assert_eq!(recovered[data.len()..], vec![Felt::ZERO; BLOB_LEN - data.len()]);
+        assert_eq!(recovered[..data.len()], data);
+
+        let (mut onchain, mut local) = (FlatStateDiff::default(), FlatStateDiff::default());
+        onchain.apply(&decode_onchain_data(&recovered).unwrap());
+        local.apply(&state_diff());
+        assert_eq!(FlatStateDiff::compare(&onchain, &local), vec![]);
+    }
+
+    #[test]
+    fn test_decode_compressed_blob() {
+        let data = state_diff().to_compressed_onchain_data().unwrap();
+        let recovered = recover_blob_data(&blob_bytes(&data)).unwrap();
+
+        let (mut onchain, mut local) = (FlatStateDiff::default(), FlatStateDiff::default());
+        onchain.apply(&decode_onchain_data(&recovered).unwrap());
+        local.apply(&state_diff());
+        assert_eq!(FlatStateDiff::compare(&onchain, &local), vec![]);
+
+        assert!(decode_onchain_data(&[Felt::from(5), Felt::ONE]).is_err());
+    }
+
+    #[test]
+    fn test_find_onchain_data_pages() {
+        let page = |values: &[u64]| {
+            let values: Vec<U256> = values.iter().map(|value| U256::from(*value)).collect();
+            let encoded: Vec<u8> = values.iter().flat_map(|value| value.to_be_bytes::<32>()).collect();
+            (keccak256(encoded), values)
+        };
+        let (a, b, c) = (page(&[1, 2]), page(&[3]), page(&[4, 5, 6]));
+        let pages: HashMap<B256, Vec<U256>> = [a.clone(), b.clone(), c.clone()].into_iter().collect();
+
+        // The onchain data is a single page.
+        assert_eq!(find_onchain_data_pages(10, U256::from_be_bytes(b.0 .0), 1, &[], &pages), Some(vec![b.0]));
+        // The onchain data is a node of consecutive pages, which end after the program output.
+        let hash = fact_node_hash([(b.0, 11), (c.0, 14)]);
+        assert_eq!(find_onchain_data_pages(10, hash, 4, &[a.0, b.0, c.0], &pages), Some(vec![b.0, c.0]));
+        assert_eq!(find_onchain_data_pages(10, hash, 4, &[b.0, a.0, c.0], &pages), None);
+        assert_eq!(find_onchain_data_pages(11, hash, 4, &[a.0, b.0, c.0], &pages), None);
+    }
+
+    #[test]
+    fn test_compare_state_diffs() {
+        let mut local = FlatStateDiff::default();
+        local.apply(&state_diff());
+
+        let mut onchain_diff = state_diff();
+        onchain_diff.storage_diffs[0].storage_entries[0].value = Felt::from(0xdef);
+        onchain_diff.nonces.clear();
+        onchain_diff.declared_classes.push(mp_state_update::DeclaredClassItem {
+            class_hash: Felt::from(0x66),
+            compiled_class_hash: Felt::from(0x67),
+        });
+        let mut onchain = FlatStateDiff::default();
+        onchain.apply(&onchain_diff);
+
+        assert_eq!(
+            FlatStateDiff::compare(&onchain, &local),
+            vec![
+                DaMismatch::Storage {
+                    address: Felt::from(0x10),
+                    key: Felt::ONE,
+                    onchain: Some(Felt::from(0xdef)),
+                    local: Some(Felt::from(0xabc))
+                },
+                DaMismatch::Nonce { address: Felt::from(0x10), onchain: Felt::ZERO, local: Felt::ONE },
+                DaMismatch::DeclaredClass {
+                    class_hash: Felt::from(0x66),
+                    onchain: Some(Felt::from(0x67)),
+                    local: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_versioned_hash() {
+        let hash = versioned_hash(&[0u8; 48]);
+        assert_eq!(hash[0], 0x01);
+        assert_eq!(hash[1..], Sha256::digest([0u8; 48])[1..]);
+    }
+}
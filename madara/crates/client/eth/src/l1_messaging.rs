@@ -217,43 +217,41 @@ mod l1_messaging_tests {
     use std::{sync::Arc, time::Duration};
 
     use crate::l1_messaging::sync;
+    use crate::test_utils::{wait_for, L1TestEnv};
     use crate::{
-        client::{
-            EthereumClient, L1BlockMetrics,
-            StarknetCoreContract::{self, LogMessageToL2},
-        },
-        l1_messaging::get_l1_to_l2_msg_hash,
-        utils::felt_to_u256,
+        client::StarknetCoreContract::LogMessageToL2, l1_messaging::get_l1_to_l2_msg_hash, utils::felt_to_u256,
     };
     use alloy::{
         hex::FromHex,
-        node_bindings::{Anvil, AnvilInstance},
         primitives::{Address, U256},
-        providers::{ProviderBuilder, RootProvider},
+        providers::RootProvider,
         sol,
         transports::http::{Client, Http},
     };
-    use mc_db::DatabaseService;
-    use mc_mempool::{GasPriceProvider, L1DataProvider, Mempool, MempoolLimits};
-    use mp_chain_config::ChainConfig;
+    use mc_mempool::MempoolProvider;
     use mp_utils::service::ServiceContext;
     use rstest::*;
     use starknet_api::core::Nonce;
     use starknet_types_core::felt::Felt;
-    use tempfile::TempDir;
+    use tokio::task::JoinHandle;
     use tracing_test::traced_test;
-    use url::Url;
 
     use self::DummyContract::DummyContractInstance;
 
+    /// Time given to the worker to process an event.
+    const EVENT_PROCESSING_TIMEOUT: Duration = Duration::from_secs(10);
+
     struct TestRunner {
-        #[allow(dead_code)]
-        anvil: AnvilInstance, // Not used but needs to stay in scope otherwise it will be dropped
-        chain_config: Arc<ChainConfig>,
-        db_service: Arc<DatabaseService>,
+        env: L1TestEnv,
         dummy_contract: DummyContractInstance<Http<Client>, RootProvider<Http<Client>>>,
-        eth_client: EthereumClient,
-        mempool: Arc<Mempool>,
+        worker: JoinHandle<anyhow::Result<()>>,
+    }
+
+    impl Drop for TestRunner {
+        fn drop(&mut self) {
+            // The worker would keep running in the background otherwise.
+            self.worker.abort();
+        }
     }
 
     // LogMessageToL2 from https://etherscan.io/tx/0x21980d6674d33e50deee43c6c30ef3b439bd148249b4539ce37b7856ac46b843
@@ -321,110 +319,59 @@ mod l1_messaging_tests {
         }
     );
 
-    /// Common setup for tests
-    ///
-    /// This test performs the following steps:
-    /// 1. Sets up test environemment
-    /// 2. Starts worker
-    /// 3. Fires a Message event from the dummy contract
-    /// 4. Waits for event to be processed
-    /// 5. Assert that the worker handle the event with correct data
-    /// 6. Assert that the hash computed by the worker is correct
-    /// 7. TODO : Assert that the tx is succesfully submited to the mempool
-    /// 8. Assert that the event is successfully pushed to the db
-    /// 9. TODO : Assert that the tx was correctly executed
+    /// Deploys the dummy contract on a local Anvil node, and starts the messaging worker using it as the core
+    /// contract.
     #[fixture]
     async fn setup_test_env() -> TestRunner {
-        // Start Anvil instance
-        let anvil = Anvil::new().block_time(1).chain_id(1337).try_spawn().expect("failed to spawn anvil instance");
-        println!("Anvil started and running at `{}`", anvil.endpoint());
-
-        // Set up chain info
-        let chain_config = Arc::new(ChainConfig::madara_test());
-
-        // Set up database paths
-        let temp_dir = TempDir::new().expect("issue while creating temporary directory");
-        let base_path = temp_dir.path().join("data");
-        let backup_dir = Some(temp_dir.path().join("backups"));
-
-        // Initialize database service
-        let db = Arc::new(
-            DatabaseService::new(&base_path, backup_dir, false, chain_config.clone(), Default::default())
-                .await
-                .expect("Failed to create database service"),
-        );
-
-        let l1_gas_setter = GasPriceProvider::new();
-        let l1_data_provider: Arc<dyn L1DataProvider> = Arc::new(l1_gas_setter.clone());
-
-        let mempool = Arc::new(Mempool::new(
-            Arc::clone(db.backend()),
-            Arc::clone(&l1_data_provider),
-            MempoolLimits::for_testing(),
+        let env = L1TestEnv::spawn().await;
+        let dummy_contract = DummyContract::deploy(env.provider.clone()).await.expect("Deploying dummy contract");
+
+        let worker = tokio::spawn(sync(
+            Arc::clone(env.backend()),
+            Arc::new(env.eth_client(*dummy_contract.address())),
+            env.chain_config.chain_id.clone(),
+            Arc::clone(&env.mempool),
+            ServiceContext::new_for_testing(),
         ));
 
-        // Set up metrics service
-        let l1_block_metrics = L1BlockMetrics::register().unwrap();
-
-        // Set up provider
-        let rpc_url: Url = anvil.endpoint().parse().expect("issue while parsing");
-        let provider = ProviderBuilder::new().on_http(rpc_url);
-
-        // Set up dummy contract
-        let contract = DummyContract::deploy(provider.clone()).await.unwrap();
-
-        let core_contract = StarknetCoreContract::new(*contract.address(), provider.clone());
+        TestRunner { env, dummy_contract, worker }
+    }
 
-        let eth_client = EthereumClient {
-            provider: Arc::new(provider.clone()),
-            l1_core_contract: core_contract.clone(),
-            l1_block_metrics: l1_block_metrics.clone(),
-        };
+    fn message_nonce() -> Nonce {
+        Nonce(Felt::from_dec_str("10000000000000000").expect("failed to parse nonce string"))
+    }
 
-        TestRunner { anvil, chain_config, db_service: db, dummy_contract: contract, eth_client, mempool }
+    fn last_synced_l1_block(env: &L1TestEnv) -> u64 {
+        env.backend()
+            .messaging_last_synced_l1_block_with_event()
+            .expect("failed to retrieve block")
+            .expect("Initialized with the database")
+            .block_number
     }
 
     /// Test the basic workflow of l1 -> l2 messaging
     ///
     /// This test performs the following steps:
-    /// 1. Sets up test environemment
-    /// 2. Starts worker
-    /// 3. Fires a Message event from the dummy contract
-    /// 4. Waits for event to be processed
-    /// 5. Assert that the worker handle the event with correct data
-    /// 6. Assert that the hash computed by the worker is correct
-    /// 7. TODO : Assert that the tx is succesfully submited to the mempool
-    /// 8. Assert that the event is successfully pushed to the db
-    /// 9. TODO : Assert that the tx was correctly executed
+    /// 1. Sets up test environemment and starts the worker
+    /// 2. Fires a Message event from the dummy contract
+    /// 3. Waits for event to be processed
+    /// 4. Assert that the worker handle the event with correct data
+    /// 5. Assert that the hash computed by the worker is correct
+    /// 6. Assert that the L1 handler transaction is submitted to the mempool
+    /// 7. Assert that the event is successfully pushed to the db
+    /// 8. TODO : Assert that the tx was correctly executed
     #[rstest]
     #[traced_test]
     #[tokio::test]
     async fn e2e_test_basic_workflow(#[future] setup_test_env: TestRunner) {
-        let TestRunner { chain_config, db_service: db, dummy_contract: contract, eth_client, anvil: _anvil, mempool } =
-            setup_test_env.await;
-
-        // Start worker
-        let worker_handle = {
-            let db = Arc::clone(&db);
-            tokio::spawn(async move {
-                sync(
-                    Arc::clone(db.backend()),
-                    Arc::new(eth_client),
-                    chain_config.chain_id.clone(),
-                    mempool,
-                    ServiceContext::new_for_testing(),
-                )
-                .await
-            })
-        };
+        let TestRunner { env, dummy_contract: contract, .. } = &setup_test_env.await;
 
         let _ = contract.setIsCanceled(false).send().await;
         // Send a Event and wait for processing, Panic if fail
         let _ = contract.fireEvent().send().await.expect("Failed to fire event");
-        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(wait_for(EVENT_PROCESSING_TIMEOUT, || last_synced_l1_block(env) != 0).await);
 
         // Assert that event was caught by the worker with correct data
-        // TODO: Maybe add some more assert
         assert!(logs_contain("fromAddress: 0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419"));
 
         // Assert the tx hash computed by the worker is correct
@@ -433,121 +380,68 @@ mod l1_messaging_tests {
                 .as_str()
         ));
 
-        // TODO : Assert that the tx has been included in the mempool
+        // Assert that the L1 handler transaction is in the mempool
+        let txs = env.mempool.txs_peek_chunk(usize::MAX);
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].nonce(), message_nonce());
 
         // Assert that the event is well stored in db
-        let last_block =
-            db.backend().messaging_last_synced_l1_block_with_event().expect("failed to retrieve block").unwrap();
-        assert_ne!(last_block.block_number, 0);
-        let nonce = Nonce(Felt::from_dec_str("10000000000000000").expect("failed to parse nonce string"));
-        assert!(db.backend().has_l1_messaging_nonce(nonce).unwrap());
+        assert!(env.backend().has_l1_messaging_nonce(message_nonce()).unwrap());
         // TODO : Assert that the tx was correctly executed
-
-        // Explicitly cancel the listen task, else it would be running in the background
-        worker_handle.abort();
     }
 
     /// Test the workflow of l1 -> l2 messaging with duplicate event
     ///
     /// This test performs the following steps:
-    /// 1. Sets up test environemment
-    /// 2. Starts worker
-    /// 3. Fires a Message event from the dummy contract
-    /// 4. Waits for event to be processed
-    /// 5. Assert that the event is well stored in db
-    /// 6. Fires a Message with the same event from the dummy contract
-    /// 7. Assert that the last event stored is the first one
+    /// 1. Sets up test environemment and starts the worker
+    /// 2. Fires a Message event from the dummy contract
+    /// 3. Waits for event to be processed
+    /// 4. Assert that the event is well stored in db
+    /// 5. Fires a Message with the same event from the dummy contract
+    /// 6. Assert that the last event stored is the first one
     #[rstest]
     #[traced_test]
     #[tokio::test]
     async fn e2e_test_already_processed_event(#[future] setup_test_env: TestRunner) {
-        let TestRunner { chain_config, db_service: db, dummy_contract: contract, eth_client, anvil: _anvil, mempool } =
-            setup_test_env.await;
-
-        // Start worker
-        let worker_handle = {
-            let db = Arc::clone(&db);
-            tokio::spawn(async move {
-                sync(
-                    Arc::clone(db.backend()),
-                    Arc::new(eth_client),
-                    chain_config.chain_id.clone(),
-                    mempool,
-                    ServiceContext::new_for_testing(),
-                )
-                .await
-            })
-        };
+        let TestRunner { env, dummy_contract: contract, .. } = &setup_test_env.await;
 
         let _ = contract.setIsCanceled(false).send().await;
         let _ = contract.fireEvent().send().await.expect("Failed to fire event");
-        tokio::time::sleep(Duration::from_secs(5)).await;
-        let last_block =
-            db.backend().messaging_last_synced_l1_block_with_event().expect("failed to retrieve block").unwrap();
-        assert_ne!(last_block.block_number, 0);
-        let nonce = Nonce(Felt::from_dec_str("10000000000000000").expect("failed to parse nonce string"));
-        assert!(db.backend().has_l1_messaging_nonce(nonce).unwrap());
+        assert!(wait_for(EVENT_PROCESSING_TIMEOUT, || last_synced_l1_block(env) != 0).await);
+        let last_block = last_synced_l1_block(env);
+        assert!(env.backend().has_l1_messaging_nonce(message_nonce()).unwrap());
 
         // Send the event a second time
         let _ = contract.fireEvent().send().await.expect("Failed to fire event");
-        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(wait_for(EVENT_PROCESSING_TIMEOUT, || logs_contain("Event already processed")).await);
         // Assert that the last event in db is still the same as it is already processed (same nonce)
-        assert_eq!(
-            last_block.block_number,
-            db.backend()
-                .messaging_last_synced_l1_block_with_event()
-                .expect("failed to retrieve block")
-                .unwrap()
-                .block_number
-        );
-        assert!(logs_contain("Event already processed"));
-
-        worker_handle.abort();
+        assert_eq!(last_synced_l1_block(env), last_block);
+        assert_eq!(env.mempool.txs_peek_chunk(usize::MAX).len(), 1);
     }
 
     /// Test the workflow of l1 -> l2 messaging with message cancelled
     ///
     /// This test performs the following steps:
-    /// 1. Sets up test environemment
-    /// 2. Starts worker
-    /// 3. Fires a Message event from the dummy contract
-    /// 4. Waits for event to be processed
-    /// 5. Assert that the event is not stored in db
+    /// 1. Sets up test environemment and starts the worker
+    /// 2. Fires a Message event from the dummy contract
+    /// 3. Waits for event to be processed
+    /// 4. Assert that the event is not stored in db
     #[rstest]
     #[traced_test]
     #[tokio::test]
     async fn e2e_test_message_canceled(#[future] setup_test_env: TestRunner) {
-        let TestRunner { chain_config, db_service: db, dummy_contract: contract, eth_client, anvil: _anvil, mempool } =
-            setup_test_env.await;
-
-        // Start worker
-        let worker_handle = {
-            let db = Arc::clone(&db);
-            tokio::spawn(async move {
-                sync(
-                    Arc::clone(db.backend()),
-                    Arc::new(eth_client),
-                    chain_config.chain_id.clone(),
-                    mempool,
-                    ServiceContext::new_for_testing(),
-                )
-                .await
-            })
-        };
+        let TestRunner { env, dummy_contract: contract, .. } = &setup_test_env.await;
 
         // Mock cancelled message
         let _ = contract.setIsCanceled(true).send().await;
         let _ = contract.fireEvent().send().await.expect("Failed to fire event");
-        tokio::time::sleep(Duration::from_secs(5)).await;
-        let last_block =
-            db.backend().messaging_last_synced_l1_block_with_event().expect("failed to retrieve block").unwrap();
-        assert_eq!(last_block.block_number, 0);
-        let nonce = Nonce(Felt::from_dec_str("10000000000000000").expect("failed to parse nonce string"));
         // cancelled message nonce should be inserted to avoid reprocessing
-        assert!(db.backend().has_l1_messaging_nonce(nonce).unwrap());
+        assert!(
+            wait_for(EVENT_PROCESSING_TIMEOUT, || env.backend().has_l1_messaging_nonce(message_nonce()).unwrap()).await
+        );
+        assert_eq!(last_synced_l1_block(env), 0);
+        assert!(env.mempool.txs_peek_chunk(usize::MAX).is_empty());
         assert!(logs_contain("L1 Message was cancelled in block at timestamp : 0x66b4f105"));
-
-        worker_handle.abort();
     }
 
     /// Test taken from starknet.rs to ensure consistency
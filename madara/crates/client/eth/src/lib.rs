@@ -1,7 +1,10 @@
 pub mod client;
+pub mod da_audit;
 pub mod error;
 pub mod l1_gas_price;
 pub mod l1_messaging;
 pub mod state_update;
 pub mod sync;
+#[cfg(test)]
+mod test_utils;
 pub mod utils;
@@ -87,14 +87,11 @@ pub async fn state_update_worker(
 #[cfg(test)]
 mod eth_client_event_subscription_test {
     use super::*;
-    use std::{sync::Arc, time::Duration};
+    use crate::test_utils::{wait_for, L1TestEnv};
+    use std::time::Duration;
 
-    use alloy::{node_bindings::Anvil, providers::ProviderBuilder, sol};
-    use mc_db::DatabaseService;
-    use mp_chain_config::ChainConfig;
+    use alloy::sol;
     use rstest::*;
-    use tempfile::TempDir;
-    use url::Url;
 
     sol!(
         #[sol(rpc, bytecode="6080604052348015600e575f80fd5b506101618061001c5f395ff3fe608060405234801561000f575f80fd5b5060043610610029575f3560e01c80634185df151461002d575b5f80fd5b610035610037565b005b5f7f0639349b21e886487cd6b341de2050db8ab202d9c6b0e7a2666d598e5fcf81a690505f620a1caf90505f7f0279b69383ea92624c1ae4378ac7fae6428f47bbd21047ea0290c3653064188590507fd342ddf7a308dec111745b00315c14b7efb2bdae570a6856e088ed0c65a3576c8383836040516100b9939291906100f6565b60405180910390a1505050565b5f819050919050565b6100d8816100c6565b82525050565b5f819050919050565b6100f0816100de565b82525050565b5f6060820190506101095f8301866100cf565b61011660208301856100e7565b61012360408301846100cf565b94935050505056fea2646970667358221220fbc6fd165c86ed9af0c5fcab2830d4a72894fd6a98e9c16dbf9101c4c22e2f7d64736f6c634300081a0033")]
@@ -112,78 +109,45 @@ mod eth_client_event_subscription_test {
     );
 
     const L2_BLOCK_NUMBER: u64 = 662703;
-    const ANOTHER_ANVIL_PORT: u16 = 8548;
-    const EVENT_PROCESSING_TIME: u64 = 2; // Time to allow for event processing in seconds
+    /// Time given to the worker to process an event.
+    const EVENT_PROCESSING_TIMEOUT: Duration = Duration::from_secs(10);
 
     /// Test the event subscription and state update functionality
     ///
     /// This test performs the following steps:
-    /// 1. Sets up a mock Ethereum environment using Anvil
-    /// 2. Initializes necessary services (Database, Metrics)
-    /// 3. Deploys a dummy contract and sets up an Ethereum client
-    /// 4. Starts listening for state updates
-    /// 5. Fires an event from the dummy contract
-    /// 6. Waits for event processing and verifies the block number
+    /// 1. Sets up a mock Ethereum environment using Anvil, with a database
+    /// 2. Deploys a dummy contract and sets up an Ethereum client
+    /// 3. Starts listening for state updates
+    /// 4. Fires an event from the dummy contract
+    /// 5. Waits for event processing and verifies the block number
     #[rstest]
     #[tokio::test]
     async fn listen_and_update_state_when_event_fired_works() {
-        // Start Anvil instance
-        let anvil = Anvil::new()
-            .block_time(1)
-            .chain_id(1337)
-            .port(ANOTHER_ANVIL_PORT)
-            .try_spawn()
-            .expect("failed to spawn anvil instance");
-        println!("Anvil started and running at `{}`", anvil.endpoint());
-
-        // Set up chain info
-        let chain_info = Arc::new(ChainConfig::madara_test());
-
-        // Set up database paths
-        let temp_dir = TempDir::new().expect("issue while creating temporary directory");
-        let base_path = temp_dir.path().join("data");
-        let backup_dir = Some(temp_dir.path().join("backups"));
-
-        // Initialize database service
-        let db = Arc::new(
-            DatabaseService::new(&base_path, backup_dir, false, chain_info.clone(), Default::default())
-                .await
-                .expect("Failed to create database service"),
-        );
-
-        // Set up metrics service
-        let l1_block_metrics = L1BlockMetrics::register().unwrap();
-
-        let rpc_url: Url = anvil.endpoint().parse().expect("issue while parsing");
-        let provider = ProviderBuilder::new().on_http(rpc_url);
-
-        let contract = DummyContract::deploy(provider.clone()).await.unwrap();
-        let core_contract = StarknetCoreContract::new(*contract.address(), provider.clone());
-
-        let eth_client =
-            EthereumClient { provider: Arc::new(provider), l1_core_contract: core_contract.clone(), l1_block_metrics };
+        let env = L1TestEnv::spawn().await;
+        let contract = DummyContract::deploy(env.provider.clone()).await.unwrap();
 
         // Start listening for state updates
-        let listen_handle = {
-            let db = Arc::clone(&db);
-            tokio::spawn(async move {
-                state_update_worker(Arc::clone(db.backend()), Arc::new(eth_client), ServiceContext::new_for_testing())
-                    .await
-                    .unwrap()
-            })
-        };
+        let listen_handle = tokio::spawn(state_update_worker(
+            Arc::clone(env.backend()),
+            Arc::new(env.eth_client(*contract.address())),
+            ServiceContext::new_for_testing(),
+        ));
 
         let _ = contract.fireEvent().send().await.expect("Failed to fire event");
 
         // Wait for event processing
-        tokio::time::sleep(Duration::from_secs(EVENT_PROCESSING_TIME)).await;
-
-        // Verify the block number
-        let block_in_db =
-            db.backend().get_l1_last_confirmed_block().expect("Failed to get L1 last confirmed block number");
+        let processed = wait_for(EVENT_PROCESSING_TIMEOUT, || {
+            env.backend().get_l1_last_confirmed_block().expect("Failed to get L1 last confirmed block number").is_some()
+        })
+        .await;
 
         // Explicitly cancel the listen task, else it would be running in the background
         listen_handle.abort();
-        assert_eq!(block_in_db, Some(L2_BLOCK_NUMBER), "Block in DB does not match expected L2 block number");
+        assert!(processed, "State update was not processed");
+        assert_eq!(
+            env.backend().get_l1_last_confirmed_block().expect("Failed to get L1 last confirmed block number"),
+            Some(L2_BLOCK_NUMBER),
+            "Block in DB does not match expected L2 block number"
+        );
     }
 }
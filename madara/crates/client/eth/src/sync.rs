@@ -1,4 +1,5 @@
 use crate::client::EthereumClient;
+use crate::da_audit::{da_audit_worker, DaAuditConfig};
 use crate::l1_gas_price::gas_price_worker;
 use crate::l1_messaging::sync;
 use crate::state_update::state_update_worker;
@@ -19,6 +20,7 @@ pub async fn l1_sync_worker(
     gas_price_sync_disabled: bool,
     gas_price_poll_ms: Duration,
     mempool: Arc<Mempool>,
+    da_audit: Option<DaAuditConfig>,
     ctx: ServiceContext,
 ) -> anyhow::Result<()> {
     let mut join_set = tokio::task::JoinSet::new();
@@ -26,6 +28,10 @@ pub async fn l1_sync_worker(
     join_set.spawn(state_update_worker(Arc::clone(&backend), Arc::clone(&eth_client), ctx.clone()));
     join_set.spawn(sync(Arc::clone(&backend), Arc::clone(&eth_client), chain_id, mempool, ctx.clone()));
 
+    if let Some(da_audit) = da_audit {
+        join_set.spawn(da_audit_worker(Arc::clone(&backend), Arc::clone(&eth_client), da_audit, ctx.clone()));
+    }
+
     if !gas_price_sync_disabled {
         join_set.spawn(gas_price_worker(Arc::clone(&eth_client), l1_gas_provider, gas_price_poll_ms, ctx.clone()));
     }
@@ -0,0 +1,82 @@
+//! Harness running the L1 workers against a local Anvil node.
+//!
+//! Each [`L1TestEnv`] has its own Anvil node, database and mempool. Tests deploy the contract standing in for the
+//! Starknet core contract themselves, point an [`EthereumClient`] to it with [`L1TestEnv::eth_client`], and run the
+//! worker under test with it. Workers process events asynchronously: [`wait_for`] polls the database until the
+//! expected change shows up.
+
+use crate::client::{EthereumClient, L1BlockMetrics, StarknetCoreContract};
+use alloy::node_bindings::{Anvil, AnvilInstance};
+use alloy::primitives::Address;
+use alloy::providers::{ProviderBuilder, RootProvider};
+use alloy::transports::http::{Client, Http};
+use mc_db::{DatabaseService, MadaraBackend};
+use mc_mempool::{GasPriceProvider, L1DataProvider, Mempool, MempoolLimits};
+use mp_chain_config::ChainConfig;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+pub struct L1TestEnv {
+    /// Kept alive for the node to keep running.
+    pub anvil: AnvilInstance,
+    pub provider: RootProvider<Http<Client>>,
+    pub chain_config: Arc<ChainConfig>,
+    pub db: Arc<DatabaseService>,
+    pub mempool: Arc<Mempool>,
+    _temp_dir: TempDir,
+}
+
+impl L1TestEnv {
+    pub async fn spawn() -> Self {
+        let anvil = Anvil::new().block_time(1).chain_id(1337).try_spawn().expect("Failed to spawn anvil instance");
+        let provider = ProviderBuilder::new().on_http(anvil.endpoint().parse().expect("Parsing anvil endpoint"));
+
+        let chain_config = Arc::new(ChainConfig::madara_test());
+        let temp_dir = TempDir::new().expect("Creating temporary directory");
+        let db = Arc::new(
+            DatabaseService::new(
+                &temp_dir.path().join("data"),
+                Some(temp_dir.path().join("backups")),
+                false,
+                Arc::clone(&chain_config),
+                Default::default(),
+                Default::default(),
+            )
+            .await
+            .expect("Failed to create database service"),
+        );
+
+        let l1_data_provider: Arc<dyn L1DataProvider> = Arc::new(GasPriceProvider::new());
+        let mempool = Arc::new(Mempool::new(Arc::clone(db.backend()), l1_data_provider, MempoolLimits::for_testing()));
+
+        Self { anvil, provider, chain_config, db, mempool, _temp_dir: temp_dir }
+    }
+
+    pub fn backend(&self) -> &Arc<MadaraBackend> {
+        self.db.backend()
+    }
+
+    /// A client using the contract deployed at `core_contract` as the Starknet core contract.
+    pub fn eth_client(&self, core_contract: Address) -> EthereumClient {
+        EthereumClient {
+            provider: Arc::new(self.provider.clone()),
+            l1_core_contract: StarknetCoreContract::new(core_contract, self.provider.clone()),
+            l1_block_metrics: L1BlockMetrics::register().expect("Registering metrics"),
+        }
+    }
+}
+
+/// Polls `condition` until it holds, for at most `timeout`. Returns whether it held.
+pub async fn wait_for(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if condition() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
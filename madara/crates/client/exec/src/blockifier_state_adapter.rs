@@ -1,3 +1,4 @@
+use crate::class_cache;
 use blockifier::execution::contract_class::ContractClass;
 use blockifier::state::errors::StateError;
 use blockifier::state::state_api::{StateReader, StateResult};
@@ -112,6 +113,18 @@ impl StateReader for BlockifierStateAdapter {
             return Err(StateError::UndeclaredClassHash(class_hash));
         };
 
+        if let Some(class) = class_cache::get(&class_hash.to_felt()) {
+            // The class may not be declared yet at the block we are on top of.
+            let declared = self.backend.get_class_info(&on_top_of_block_id, &class_hash.to_felt()).map_err(|err| {
+                tracing::warn!("Failed to retrieve class {class_hash:#}: {err:#}");
+                StateError::StateReadError(format!("Failed to retrieve class {class_hash:#}"))
+            })?;
+            return match declared {
+                Some(_) => Ok(class),
+                None => Err(StateError::UndeclaredClassHash(class_hash)),
+            };
+        }
+
         let Some(converted_class) =
             self.backend.get_converted_class(&on_top_of_block_id, &class_hash.to_felt()).map_err(|err| {
                 tracing::warn!("Failed to retrieve class {class_hash:#}: {err:#}");
@@ -121,10 +134,12 @@ impl StateReader for BlockifierStateAdapter {
             return Err(StateError::UndeclaredClassHash(class_hash));
         };
 
-        converted_class.to_blockifier_class().map_err(|err| {
+        let class = converted_class.to_blockifier_class().map_err(|err| {
             tracing::warn!("Failed to convert class {class_hash:#} to blockifier format: {err:#}");
             StateError::StateReadError(format!("Failed to convert class {class_hash:#}"))
-        })
+        })?;
+        class_cache::insert(class_hash.to_felt(), class.clone());
+        Ok(class)
     }
 
     fn get_compiled_class_hash(&self, class_hash: ClassHash) -> StateResult<CompiledClassHash> {
@@ -0,0 +1,67 @@
+//! Cache of the classes converted to the blockifier format.
+//!
+//! Converting a class to the blockifier format deserializes its whole program, which is a large part of the execution
+//! time of simple transactions. A class never changes for a given class hash, so the converted classes are kept in a
+//! cache shared by every execution of the process. Whether a class is declared at the block an execution is on top of
+//! is still checked against the database.
+
+use crate::Error;
+use blockifier::execution::contract_class::ContractClass;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use starknet_types_core::felt::Felt;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Maximum number of classes kept in the cache. Classes are not evicted: once the cache is full, other classes are
+/// converted on every use.
+const CLASS_CACHE_CAPACITY: usize = 1024;
+
+static CLASS_CACHE: LazyLock<RwLock<HashMap<Felt, ContractClass>>> = LazyLock::new(Default::default);
+
+pub(crate) fn get(class_hash: &Felt) -> Option<ContractClass> {
+    CLASS_CACHE.read().expect("Poisoned lock").get(class_hash).cloned()
+}
+
+pub(crate) fn insert(class_hash: Felt, class: ContractClass) {
+    let mut cache = CLASS_CACHE.write().expect("Poisoned lock");
+    if cache.len() < CLASS_CACHE_CAPACITY {
+        cache.insert(class_hash, class);
+    }
+}
+
+/// Number of classes in the cache.
+pub fn cached_classes() -> usize {
+    CLASS_CACHE.read().expect("Poisoned lock").len()
+}
+
+/// Converts a class and adds it to the cache ahead of its first execution. Returns whether the class is in the cache,
+/// which is not the case when the class is not declared at `block_id`, when it could not be converted or when the cache
+/// is full.
+pub fn preload(backend: &MadaraBackend, block_id: &DbBlockId, class_hash: &Felt) -> Result<bool, Error> {
+    if get(class_hash).is_some() {
+        return Ok(true);
+    }
+    let Some(converted_class) = backend.get_converted_class(block_id, class_hash)? else {
+        return Ok(false);
+    };
+    match converted_class.to_blockifier_class() {
+        Ok(class) => insert(*class_hash, class),
+        Err(err) => tracing::warn!("Failed to convert class {class_hash:#x} to blockifier format: {err:#}"),
+    }
+    Ok(get(class_hash).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_preload_undeclared_class() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        assert!(!preload(&backend, &DbBlockId::Pending, &Felt::from_hex_unchecked("0xdead")).unwrap());
+        assert!(get(&Felt::from_hex_unchecked("0xdead")).is_none());
+    }
+}
@@ -15,6 +15,7 @@ use starknet_types_core::felt::Felt;
 mod block_context;
 mod blockifier_state_adapter;
 mod call;
+pub mod class_cache;
 pub mod execution;
 mod fee;
 mod trace;
@@ -7,9 +7,12 @@ use hyper_tls::HttpsConnector;
 use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
+use mp_utils::http::HttpClientConfig;
 use std::error::Error;
 use std::future::Future;
+use std::num::NonZeroU32;
 use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
@@ -19,8 +22,12 @@ use tower::Service;
 use tower::{retry::Retry, timeout::Timeout};
 use url::Url;
 
-type HttpsClient = Client<HttpsConnector<HttpConnector>, String>;
-type TimeoutRetryClient = Retry<RetryPolicy, Timeout<HttpsClient>>;
+use crate::failover::GatewayEndpoints;
+use crate::proxy::ProxyConnector;
+use crate::rate_limit::{RateLimitMiddleware, RateLimiter};
+
+type HttpsClient = Client<HttpsConnector<ProxyConnector>, String>;
+type TimeoutRetryClient = Retry<RetryPolicy, RateLimitMiddleware<Timeout<HttpsClient>>>;
 pub type PausedClient = PauseLayerMiddleware<TimeoutRetryClient>;
 #[derive(Debug, Clone)]
 pub struct GatewayProvider {
@@ -28,20 +35,91 @@ pub struct GatewayProvider {
     pub(crate) gateway_url: Url,
     pub(crate) feeder_gateway_url: Url,
     pub(crate) headers: HeaderMap,
+    /// Set once the feeder gateway answered that it does not serve `get_classes_by_hash`.
+    pub(crate) classes_batch_unsupported: Arc<AtomicBool>,
+    /// The gateways requests fail over between, when fallback gateways are set.
+    pub(crate) endpoints: Option<Arc<GatewayEndpoints>>,
+    /// Whether responses with fields unknown to the gateway types are rejected instead of having these fields dropped.
+    pub(crate) strict_schema: bool,
+    http_config: HttpClientConfig,
+    /// Rate limit of the requests, shared with the clones of the provider.
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl GatewayProvider {
     pub fn new(gateway_url: Url, feeder_gateway_url: Url) -> Self {
         let pause_until = Arc::new(RwLock::new(None));
-        let connector = HttpsConnector::new();
-        let base_client = Client::builder(TokioExecutor::new()).build::<_, String>(connector);
-
-        let timeout_layer = Timeout::new(base_client, Duration::from_secs(20)); // Timeout after 20 seconds
         let retry_policy = RetryPolicy::new(5, Duration::from_secs(1), Arc::clone(&pause_until)); // Retry 5 times with 1 second backoff
-        let retry_layer = Retry::new(retry_policy, timeout_layer);
-        let client = PauseLayerMiddleware::new(retry_layer, Arc::clone(&pause_until));
+        let http_config = HttpClientConfig::default();
+
+        Self {
+            client: build_client(retry_policy, pause_until, &http_config, None),
+            gateway_url,
+            feeder_gateway_url,
+            headers: HeaderMap::new(),
+            classes_batch_unsupported: Arc::new(AtomicBool::new(false)),
+            endpoints: None,
+            strict_schema: false,
+            http_config,
+            rate_limiter: None,
+        }
+    }
+
+    /// Builds the client again after a change of its configuration.
+    fn rebuild_client(&mut self) {
+        let pause_until = Arc::new(RwLock::new(None));
+        let retry_policy = if self.endpoints.is_some() {
+            RetryPolicy::new(0, Duration::from_secs(1), Arc::clone(&pause_until)).without_rate_limit_retry()
+        } else {
+            RetryPolicy::new(5, Duration::from_secs(1), Arc::clone(&pause_until))
+        };
+        self.client = build_client(retry_policy, pause_until, &self.http_config, self.rate_limiter.clone());
+    }
+
+    /// Sets the timeouts, keep-alive and proxy of the connections to the gateways.
+    pub fn with_http_config(mut self, http_config: HttpClientConfig) -> Self {
+        self.http_config = http_config;
+        self.rebuild_client();
+        self
+    }
+
+    /// Limits the requests sent to the gateways to `max_requests_per_second`, retries included, so that a node
+    /// without an API key is not banned by the gateways. The limit is shared with the clones of the provider. Short
+    /// bursts of up to a second of requests are sent right away.
+    pub fn with_max_requests_per_second(mut self, max_requests_per_second: Option<NonZeroU32>) -> Self {
+        self.rate_limiter = max_requests_per_second.map(|max| Arc::new(RateLimiter::new(max)));
+        self.rebuild_client();
+        self
+    }
 
-        Self { client, gateway_url, feeder_gateway_url, headers: HeaderMap::new() }
+    /// Time the requests waited for the rate limit set with [`GatewayProvider::with_max_requests_per_second`], since
+    /// the provider was created.
+    pub fn rate_limit_wait(&self) -> Duration {
+        self.rate_limiter.as_ref().map(|rate_limiter| rate_limiter.waited()).unwrap_or_default()
+    }
+
+    /// Sets the gateways, as `(gateway_url, feeder_gateway_url)` pairs, that requests fail over to in order when a
+    /// gateway does not answer or answers with a rate limit or a server error.
+    ///
+    /// Failed requests are then not retried on the same gateway: a failing gateway is instead tried after the healthy
+    /// ones until it answers again.
+    pub fn with_fallbacks(mut self, fallbacks: impl IntoIterator<Item = (Url, Url)>) -> Self {
+        let endpoints: Vec<_> =
+            std::iter::once((self.gateway_url.clone(), self.feeder_gateway_url.clone())).chain(fallbacks).collect();
+        if endpoints.len() == 1 {
+            return self;
+        }
+
+        self.endpoints = Some(Arc::new(GatewayEndpoints::new(endpoints)));
+        self.rebuild_client();
+        self
+    }
+
+    /// Rejects the responses with fields the gateway types do not know, instead of dropping these fields with a
+    /// warning. This is meant for CI, to notice changes of the gateway schema.
+    pub fn with_strict_schema(mut self, strict_schema: bool) -> Self {
+        self.strict_schema = strict_schema;
+        self
     }
 
     pub fn new_with_headers(gateway_url: Url, feeder_gateway_url: Url, headers: &[(HeaderName, HeaderValue)]) -> Self {
@@ -87,16 +165,43 @@ impl GatewayProvider {
     }
 }
 
+fn build_client(
+    retry_policy: RetryPolicy,
+    pause_until: Arc<RwLock<Option<Instant>>>,
+    http_config: &HttpClientConfig,
+    rate_limiter: Option<Arc<RateLimiter>>,
+) -> PausedClient {
+    let mut http = HttpConnector::new();
+    http.set_connect_timeout(Some(http_config.connect_timeout));
+    http.set_keepalive(http_config.tcp_keepalive);
+    let connector = HttpsConnector::new_with_connector(ProxyConnector::new(http, http_config.proxy.clone()));
+    let base_client = Client::builder(TokioExecutor::new())
+        .pool_idle_timeout(http_config.pool_idle_timeout)
+        .build::<_, String>(connector);
+
+    let timeout_layer = Timeout::new(base_client, http_config.timeout);
+    // The wait for the rate limit is not counted in the timeout, while the retries wait for it too.
+    let rate_limit_layer = RateLimitMiddleware::new(timeout_layer, rate_limiter);
+    let retry_layer = Retry::new(retry_policy, rate_limit_layer);
+    PauseLayerMiddleware::new(retry_layer, pause_until)
+}
+
 #[derive(Clone, Debug)]
 pub struct RetryPolicy {
     max_retries: usize,
     backoff: Duration,
     pause_until: Arc<RwLock<Option<Instant>>>,
+    retry_rate_limited: bool,
 }
 
 impl RetryPolicy {
     pub fn new(max_retries: usize, backoff: Duration, pause_until: Arc<RwLock<Option<Instant>>>) -> Self {
-        RetryPolicy { max_retries, backoff, pause_until }
+        RetryPolicy { max_retries, backoff, pause_until, retry_rate_limited: true }
+    }
+
+    /// Returns the rate limited responses instead of waiting for the rate limit to end and retrying.
+    pub fn without_rate_limit_retry(self) -> Self {
+        RetryPolicy { retry_rate_limited: false, ..self }
     }
 }
 
@@ -113,7 +218,7 @@ impl retry::Policy<Request<String>, Response<Incoming>, Box<dyn Error + Send + S
 
         match result {
             Ok(response) => {
-                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                if self.retry_rate_limited && response.status() == StatusCode::TOO_MANY_REQUESTS {
                     let retry_after = get_retry_after(response).unwrap_or(Duration::from_secs(10)); // Default 10 seconds
 
                     let next_policy = self.clone();
@@ -137,11 +242,7 @@ impl retry::Policy<Request<String>, Response<Incoming>, Box<dyn Error + Send + S
             }
             Err(_) if self.max_retries > 0 => {
                 // If the request failed, retry after backoff duration
-                let next_policy = RetryPolicy {
-                    max_retries: self.max_retries - 1,
-                    backoff: self.backoff,
-                    pause_until: self.pause_until.clone(),
-                };
+                let next_policy = RetryPolicy { max_retries: self.max_retries - 1, ..self.clone() };
                 let sleep = tokio::time::sleep(self.backoff);
                 let fut = async move {
                     sleep.await;
@@ -159,7 +260,7 @@ impl retry::Policy<Request<String>, Response<Incoming>, Box<dyn Error + Send + S
     }
 }
 
-fn get_retry_after(response: &Response<Incoming>) -> Option<Duration> {
+pub(crate) fn get_retry_after(response: &Response<Incoming>) -> Option<Duration> {
     if let Some(retry_after_header) = response.headers().get("Retry-After") {
         if let Ok(retry_after_str) = retry_after_header.to_str() {
             if let Ok(retry_seconds) = retry_after_str.parse::<u64>() {
@@ -0,0 +1,205 @@
+//! Failover between several gateways.
+//!
+//! Requests go to the first healthy gateway, in the order the gateways were given. A gateway that does not answer, or
+//! answers with a rate limit or a server error, is marked unhealthy for a backoff that grows with its consecutive
+//! failures, and the request is sent to the next gateway. Unhealthy gateways are still tried, after the healthy ones,
+//! so that a request only fails when every gateway failed it.
+
+use crate::builder::{get_retry_after, PausedClient};
+use crate::metrics::GatewayClientMetrics;
+use hyper::body::Incoming;
+use hyper::{Request, Response, StatusCode};
+use mp_gateway::error::SequencerError;
+use opentelemetry::KeyValue;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tower::Service;
+use url::Url;
+
+/// Backoff of a gateway after its first failure, doubled on each consecutive failure.
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GatewayApi {
+    Gateway,
+    FeederGateway,
+}
+
+#[derive(Debug)]
+struct GatewayEndpoint {
+    gateway_url: Url,
+    feeder_gateway_url: Url,
+    consecutive_failures: AtomicU32,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl GatewayEndpoint {
+    fn url(&self, api: GatewayApi) -> &Url {
+        match api {
+            GatewayApi::Gateway => &self.gateway_url,
+            GatewayApi::FeederGateway => &self.feeder_gateway_url,
+        }
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.lock().expect("Poisoned lock").map_or(true, |until| until <= now)
+    }
+}
+
+pub(crate) struct GatewayEndpoints {
+    endpoints: Vec<GatewayEndpoint>,
+    metrics: GatewayClientMetrics,
+}
+
+impl fmt::Debug for GatewayEndpoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GatewayEndpoints").field("endpoints", &self.endpoints).finish_non_exhaustive()
+    }
+}
+
+impl GatewayEndpoints {
+    pub(crate) fn new(urls: impl IntoIterator<Item = (Url, Url)>) -> Self {
+        let endpoints = urls
+            .into_iter()
+            .map(|(gateway_url, feeder_gateway_url)| GatewayEndpoint {
+                gateway_url,
+                feeder_gateway_url,
+                consecutive_failures: AtomicU32::new(0),
+                unhealthy_until: Mutex::new(None),
+            })
+            .collect();
+        Self { endpoints, metrics: GatewayClientMetrics::register() }
+    }
+
+    /// The healthy gateways in order, followed by the unhealthy ones in order.
+    fn ordered(&self) -> impl Iterator<Item = &GatewayEndpoint> {
+        let now = Instant::now();
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) =
+            self.endpoints.iter().partition(|endpoint| endpoint.is_healthy(now));
+        healthy.into_iter().chain(unhealthy)
+    }
+
+    fn record_success(&self, endpoint: &GatewayEndpoint) {
+        if endpoint.consecutive_failures.swap(0, Ordering::Relaxed) > 0 {
+            tracing::info!("🌐 Gateway {} is healthy again", endpoint.feeder_gateway_url);
+        }
+        *endpoint.unhealthy_until.lock().expect("Poisoned lock") = None;
+        self.metrics.endpoint_healthy.record(1, &[endpoint_attribute(endpoint)]);
+    }
+
+    fn record_failure(&self, endpoint: &GatewayEndpoint, retry_after: Option<Duration>) {
+        let failures = endpoint.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        let backoff = retry_after.unwrap_or_else(|| BASE_BACKOFF.saturating_mul(1 << (failures - 1).min(16)));
+        *endpoint.unhealthy_until.lock().expect("Poisoned lock") = Some(Instant::now() + backoff.min(MAX_BACKOFF));
+        if failures == 1 {
+            tracing::warn!("🌐 Gateway {} is unhealthy, failing over to the next gateway", endpoint.feeder_gateway_url);
+        }
+        self.metrics.endpoint_failure_counter.add(1, &[endpoint_attribute(endpoint)]);
+        self.metrics.endpoint_healthy.record(0, &[endpoint_attribute(endpoint)]);
+    }
+
+    /// Sends the request built by `build_request` for the base URL of each gateway until one of them answers without a
+    /// rate limit or a server error. Returns the answer of the last gateway tried when all of them failed.
+    pub(crate) async fn call(
+        &self,
+        client: &PausedClient,
+        api: GatewayApi,
+        build_request: impl Fn(&Url) -> Result<Request<String>, SequencerError>,
+    ) -> Result<Response<Incoming>, SequencerError> {
+        let mut result = None;
+        for (i, endpoint) in self.ordered().enumerate() {
+            if i > 0 {
+                self.metrics.failover_counter.add(1, &[]);
+            }
+            let request = build_request(endpoint.url(api))?;
+            // The method is the last segment of the path, such as `get_state_update`.
+            let method = request.uri().path().rsplit('/').next().unwrap_or_default().to_owned();
+            let started = Instant::now();
+            let response = client.clone().call(request).await;
+            self.metrics.request_duration_seconds.record(
+                started.elapsed().as_secs_f64(),
+                &[endpoint_attribute(endpoint), KeyValue::new("method", method)],
+            );
+            match &response {
+                Ok(response) if is_failure(response.status()) => {
+                    self.record_failure(endpoint, get_retry_after(response))
+                }
+                Ok(_) => {
+                    self.record_success(endpoint);
+                    return response.map_err(SequencerError::HttpCallError);
+                }
+                Err(_) => self.record_failure(endpoint, None),
+            }
+            result = Some(response.map_err(SequencerError::HttpCallError));
+        }
+        result.expect("There is at least one gateway")
+    }
+}
+
+fn is_failure(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn endpoint_attribute(endpoint: &GatewayEndpoint) -> KeyValue {
+    KeyValue::new("endpoint", endpoint.feeder_gateway_url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints() -> GatewayEndpoints {
+        GatewayEndpoints::new(["http://primary", "http://secondary"].map(|url| {
+            let url = Url::parse(url).unwrap();
+            (url.join("/gateway/").unwrap(), url.join("/feeder_gateway/").unwrap())
+        }))
+    }
+
+    fn ordered_hosts(endpoints: &GatewayEndpoints) -> Vec<String> {
+        endpoints.ordered().map(|endpoint| endpoint.feeder_gateway_url.host_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_unhealthy_gateway_is_tried_last() {
+        let endpoints = endpoints();
+        assert_eq!(ordered_hosts(&endpoints), ["primary", "secondary"]);
+
+        endpoints.record_failure(&endpoints.endpoints[0], None);
+        assert_eq!(ordered_hosts(&endpoints), ["secondary", "primary"]);
+
+        endpoints.record_success(&endpoints.endpoints[0]);
+        assert_eq!(ordered_hosts(&endpoints), ["primary", "secondary"]);
+    }
+
+    #[test]
+    fn test_gateway_backoff_grows_with_failures() {
+        let endpoints = endpoints();
+        let endpoint = &endpoints.endpoints[0];
+        let unhealthy_for = || *endpoint.unhealthy_until.lock().unwrap().as_ref().unwrap() - Instant::now();
+
+        endpoints.record_failure(endpoint, None);
+        let first = unhealthy_for();
+        endpoints.record_failure(endpoint, None);
+        assert!(unhealthy_for() > first);
+
+        for _ in 0..20 {
+            endpoints.record_failure(endpoint, None);
+        }
+        assert!(unhealthy_for() <= MAX_BACKOFF);
+
+        endpoints.record_failure(endpoint, Some(Duration::from_secs(5)));
+        assert!(unhealthy_for() <= Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_is_failure() {
+        assert!(is_failure(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_failure(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_failure(StatusCode::OK));
+        // Starknet errors such as block not found are answered with a client error, which another gateway would repeat.
+        assert!(!is_failure(StatusCode::BAD_REQUEST));
+    }
+}
@@ -1,5 +1,10 @@
 mod builder;
+mod failover;
 mod methods;
+mod metrics;
+mod proxy;
+mod rate_limit;
 mod request_builder;
+mod schema;
 
 pub use builder::GatewayProvider;
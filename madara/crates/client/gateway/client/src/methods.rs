@@ -1,10 +1,15 @@
+use std::sync::atomic::Ordering;
 use std::{borrow::Cow, sync::Arc};
 
+use http::StatusCode;
+
 use mp_block::{BlockId, BlockTag};
 use mp_class::{ContractClass, FlattenedSierraClass};
 use mp_gateway::error::{SequencerError, StarknetError};
 use mp_gateway::{
-    block::{ProviderBlock, ProviderBlockPending, ProviderBlockPendingMaybe, ProviderBlockSignature},
+    block::{
+        ProviderBlock, ProviderBlockHeader, ProviderBlockPending, ProviderBlockPendingMaybe, ProviderBlockSignature,
+    },
     state_update::{
         ProviderStateUpdate, ProviderStateUpdatePending, ProviderStateUpdatePendingMaybe, ProviderStateUpdateWithBlock,
         ProviderStateUpdateWithBlockPending, ProviderStateUpdateWithBlockPendingMaybe,
@@ -12,6 +17,7 @@ use mp_gateway::{
     user_transaction::{
         UserDeclareTransaction, UserDeployAccountTransaction, UserInvokeFunctionTransaction, UserTransaction,
     },
+    MAX_CLASSES_PER_REQUEST,
 };
 use mp_rpc::{AddInvokeTransactionResult, ClassAndTxnHash, ContractAndTxnHash};
 use serde::de::DeserializeOwned;
@@ -19,11 +25,24 @@ use serde_json::Value;
 use starknet_core::types::contract::legacy::LegacyContractClass;
 use starknet_types_core::felt::Felt;
 
-use super::{builder::GatewayProvider, request_builder::RequestBuilder};
+use super::{builder::GatewayProvider, failover::GatewayApi, request_builder::RequestBuilder};
 
 impl GatewayProvider {
+    fn feeder_gateway_request(&self) -> RequestBuilder<'_> {
+        RequestBuilder::new(&self.client, self.feeder_gateway_url.clone(), self.headers.clone())
+            .with_failover(self.endpoints.as_deref(), GatewayApi::FeederGateway)
+            .with_strict_schema(self.strict_schema)
+    }
+
+    fn gateway_request(&self) -> RequestBuilder<'_> {
+        RequestBuilder::new(&self.client, self.gateway_url.clone(), self.headers.clone())
+            .with_failover(self.endpoints.as_deref(), GatewayApi::Gateway)
+            .with_strict_schema(self.strict_schema)
+    }
+
     pub async fn get_block(&self, block_id: BlockId) -> Result<ProviderBlockPendingMaybe, SequencerError> {
-        let request = RequestBuilder::new(&self.client, self.feeder_gateway_url.clone(), self.headers.clone())
+        let request = self
+            .feeder_gateway_request()
             .add_uri_segment("get_block")
             .expect("Failed to add URI segment. This should not fail in prod.")
             .with_block_id(&block_id);
@@ -36,8 +55,24 @@ impl GatewayProvider {
         }
     }
 
+    /// The hash and number of a block, without its transactions. There is no header for the pending block.
+    pub async fn get_block_header(&self, block_id: BlockId) -> Result<ProviderBlockHeader, SequencerError> {
+        if matches!(block_id, BlockId::Tag(BlockTag::Pending)) {
+            return Err(StarknetError::no_block_header_for_pending_block().into());
+        }
+
+        self.feeder_gateway_request()
+            .add_uri_segment("get_block")
+            .expect("Failed to add URI segment. This should not fail in prod.")
+            .with_block_id(&block_id)
+            .add_param(Cow::from("headerOnly"), "true")
+            .send_get::<ProviderBlockHeader>()
+            .await
+    }
+
     pub async fn get_state_update(&self, block_id: BlockId) -> Result<ProviderStateUpdatePendingMaybe, SequencerError> {
-        let request = RequestBuilder::new(&self.client, self.feeder_gateway_url.clone(), self.headers.clone())
+        let request = self
+            .feeder_gateway_request()
             .add_uri_segment("get_state_update")
             .expect("Failed to add URI segment. This should not fail in prod")
             .with_block_id(&block_id);
@@ -54,7 +89,8 @@ impl GatewayProvider {
         &self,
         block_id: BlockId,
     ) -> Result<ProviderStateUpdateWithBlockPendingMaybe, SequencerError> {
-        let request = RequestBuilder::new(&self.client, self.feeder_gateway_url.clone(), self.headers.clone())
+        let request = self
+            .feeder_gateway_request()
             .add_uri_segment("get_state_update")
             .expect("Failed to add URI segment. This should not fail in prod")
             .with_block_id(&block_id)
@@ -70,12 +106,30 @@ impl GatewayProvider {
         }
     }
 
+    /// Same as [`Self::get_state_update_with_block`] for a block which may not be sealed yet. A Madara feeder gateway
+    /// holds the request until the block is sealed, and only answers with a block not found error after a timeout.
+    /// Other feeder gateways answer right away.
+    pub async fn wait_for_state_update_with_block(
+        &self,
+        block_n: u64,
+    ) -> Result<ProviderStateUpdateWithBlock, SequencerError> {
+        self.feeder_gateway_request()
+            .add_uri_segment("get_state_update")
+            .expect("Failed to add URI segment. This should not fail in prod")
+            .with_block_id(&BlockId::Number(block_n))
+            .add_param(Cow::from("includeBlock"), "true")
+            .add_param(Cow::from("waitForBlock"), "true")
+            .send_get::<ProviderStateUpdateWithBlock>()
+            .await
+    }
+
     pub async fn get_signature(&self, block_id: BlockId) -> Result<ProviderBlockSignature, SequencerError> {
         if matches!(block_id, BlockId::Tag(BlockTag::Pending)) {
             return Err(StarknetError::no_signature_for_pending_block().into());
         }
 
-        let request = RequestBuilder::new(&self.client, self.feeder_gateway_url.clone(), self.headers.clone())
+        let request = self
+            .feeder_gateway_request()
             .add_uri_segment("get_signature")
             .expect("Failed to add URI segment. This should not fail in prod")
             .with_block_id(&block_id);
@@ -88,31 +142,83 @@ impl GatewayProvider {
         class_hash: Felt,
         block_id: BlockId,
     ) -> Result<ContractClass, SequencerError> {
-        let request = RequestBuilder::new(&self.client, self.feeder_gateway_url.clone(), self.headers.clone())
+        let request = self
+            .feeder_gateway_request()
             .add_uri_segment("get_class_by_hash")
             .expect("Failed to add URI segment. This should not fail in prod.")
             .with_block_id(&block_id)
             .with_class_hash(class_hash);
 
         let value = request.send_get::<Value>().await?;
+        contract_class_from_value(value)
+    }
+
+    /// Retrieves several classes at once, in the order of `class_hashes`.
+    ///
+    /// This endpoint is only served by Madara feeder gateways: other feeder gateways answer with a
+    /// [`SequencerError::InvalidStarknetError`] with a `404 Not Found` status.
+    pub async fn get_classes_by_hash(
+        &self,
+        class_hashes: &[Felt],
+        block_id: BlockId,
+    ) -> Result<Vec<ContractClass>, SequencerError> {
+        let class_hashes = class_hashes.iter().map(|class_hash| format!("{class_hash:#x}")).collect::<Vec<_>>();
+        let request = self
+            .feeder_gateway_request()
+            .add_uri_segment("get_classes_by_hash")
+            .expect("Failed to add URI segment. This should not fail in prod.")
+            .with_block_id(&block_id)
+            .add_param(Cow::from("classHashes"), &class_hashes.join(","));
 
-        if value.get("sierra_program").is_some() {
-            let sierra: FlattenedSierraClass = serde_json::from_value(value)?;
-            Ok(ContractClass::Sierra(Arc::new(sierra)))
-        } else if value.get("program").is_some() {
-            let legacy: LegacyContractClass = serde_json::from_value(value)?;
-            Ok(ContractClass::Legacy(Arc::new(legacy.compress()?.into())))
-        } else {
-            let err = serde::de::Error::custom("Unknown contract type".to_string());
-            Err(SequencerError::DeserializeBody { serde_error: err })
+        let values = request.send_get::<Vec<Value>>().await?;
+        if values.len() != class_hashes.len() {
+            let err = serde::de::Error::invalid_length(values.len(), &"one class per class hash");
+            return Err(SequencerError::DeserializeBody { serde_error: err });
         }
+        values.into_iter().map(contract_class_from_value).collect()
+    }
+
+    /// Retrieves several classes, in the order of `class_hashes`. This uses [`Self::get_classes_by_hash`] when the
+    /// feeder gateway supports it, and falls back to one request per class otherwise.
+    pub async fn get_classes(
+        &self,
+        class_hashes: &[Felt],
+        block_id: BlockId,
+    ) -> Result<Vec<ContractClass>, SequencerError> {
+        if class_hashes.len() > 1 && !self.classes_batch_unsupported.load(Ordering::Relaxed) {
+            let mut classes = Vec::with_capacity(class_hashes.len());
+            for chunk in class_hashes.chunks(MAX_CLASSES_PER_REQUEST) {
+                match self.get_classes_by_hash(chunk, block_id.clone()).await {
+                    Ok(chunk_classes) => classes.extend(chunk_classes),
+                    Err(SequencerError::InvalidStarknetError { http_status, .. })
+                        if http_status == StatusCode::NOT_FOUND =>
+                    {
+                        tracing::debug!(
+                            "Feeder gateway does not support get_classes_by_hash, fetching classes one by one"
+                        );
+                        self.classes_batch_unsupported.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            if classes.len() == class_hashes.len() {
+                return Ok(classes);
+            }
+        }
+
+        futures::future::try_join_all(
+            class_hashes.iter().map(|class_hash| self.get_class_by_hash(*class_hash, block_id.clone())),
+        )
+        .await
     }
 
     async fn add_transaction<T>(&self, transaction: UserTransaction) -> Result<T, SequencerError>
     where
         T: DeserializeOwned,
     {
-        let request = RequestBuilder::new(&self.client, self.gateway_url.clone(), self.headers.clone())
+        let request = self
+            .gateway_request()
             .add_uri_segment("add_transaction")
             .expect("Failed to add URI segment. This should not fail in prod.");
 
@@ -141,6 +247,19 @@ impl GatewayProvider {
     }
 }
 
+fn contract_class_from_value(value: Value) -> Result<ContractClass, SequencerError> {
+    if value.get("sierra_program").is_some() {
+        let sierra: FlattenedSierraClass = serde_json::from_value(value)?;
+        Ok(ContractClass::Sierra(Arc::new(sierra)))
+    } else if value.get("program").is_some() {
+        let legacy: LegacyContractClass = serde_json::from_value(value)?;
+        Ok(ContractClass::Legacy(Arc::new(legacy.compress()?.into())))
+    } else {
+        let err = serde::de::Error::custom("Unknown contract type".to_string());
+        Err(SequencerError::DeserializeBody { serde_error: err })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Context;
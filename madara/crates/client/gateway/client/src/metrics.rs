@@ -0,0 +1,57 @@
+use mc_analytics::{
+    register_counter_metric_instrument, register_gauge_metric_instrument, register_histogram_metric_instrument,
+};
+use opentelemetry::metrics::{Counter, Gauge, Histogram};
+use opentelemetry::{global, KeyValue};
+
+pub struct GatewayClientMetrics {
+    pub endpoint_failure_counter: Counter<u64>,
+    pub endpoint_healthy: Gauge<u64>,
+    pub failover_counter: Counter<u64>,
+    pub request_duration_seconds: Histogram<f64>,
+}
+
+impl GatewayClientMetrics {
+    pub fn register() -> Self {
+        let common_scope_attributes = vec![KeyValue::new("crate", "gateway_client")];
+        let gateway_client_meter = global::meter_with_version(
+            "crates.gateway_client.opentelemetry",
+            Some("0.17"),
+            Some("https://opentelemetry.io/schemas/1.2.0"),
+            Some(common_scope_attributes.clone()),
+        );
+
+        let endpoint_failure_counter = register_counter_metric_instrument(
+            &gateway_client_meter,
+            "gateway_endpoint_failure_count".to_string(),
+            "A counter of the requests a gateway did not answer or answered with a rate limit or a server error"
+                .to_string(),
+            "request".to_string(),
+        );
+
+        let endpoint_healthy = register_gauge_metric_instrument(
+            &gateway_client_meter,
+            "gateway_endpoint_healthy".to_string(),
+            "Whether a gateway answered the last request sent to it".to_string(),
+            "gateway".to_string(),
+        );
+
+        let failover_counter = register_counter_metric_instrument(
+            &gateway_client_meter,
+            "gateway_failover_count".to_string(),
+            "A counter of the requests sent to the next gateway after a gateway failed them".to_string(),
+            "request".to_string(),
+        );
+
+        let request_duration_seconds = register_histogram_metric_instrument(
+            &gateway_client_meter,
+            "gateway_request_duration_seconds".to_string(),
+            "Histogram of the time a gateway took to answer a request, by gateway and by method, including the wait \
+             for the rate limits"
+                .to_string(),
+            "s".to_string(),
+        );
+
+        Self { endpoint_failure_counter, endpoint_healthy, failover_counter, request_duration_seconds }
+    }
+}
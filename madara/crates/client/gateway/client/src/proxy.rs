@@ -0,0 +1,186 @@
+//! Connections to the gateway through an HTTP proxy.
+//!
+//! The connections are tunneled through the proxy with a `CONNECT` request, after which TLS is negotiated with the
+//! gateway as if it was reached directly.
+
+use base64::Engine;
+use hyper::Uri;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioIo;
+use mp_utils::http::ProxyConfig;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tower::Service;
+use url::Url;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Maximum size of the response of the proxy to a `CONNECT` request.
+const MAX_TUNNEL_RESPONSE_SIZE: usize = 8192;
+
+#[derive(Clone, Debug)]
+pub struct ProxyConnector {
+    http: HttpConnector,
+    proxy: ProxyConfig,
+}
+
+impl ProxyConnector {
+    pub fn new(mut http: HttpConnector, proxy: ProxyConfig) -> Self {
+        // The connector is wrapped in an https connector.
+        http.enforce_http(false);
+        Self { http, proxy }
+    }
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = TokioIo<TcpStream>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let mut http = self.http.clone();
+        let proxy = Url::parse(&dst.to_string()).ok().and_then(|url| self.proxy.proxy_for(&url).cloned());
+
+        Box::pin(async move {
+            let Some(proxy) = proxy else {
+                return Ok(http.call(dst).await?);
+            };
+
+            let host = dst.host().ok_or("Missing host in gateway url")?;
+            let port = dst.port_u16().unwrap_or(if dst.scheme_str() == Some("https") { 443 } else { 80 });
+            let proxy_host = proxy.host_str().ok_or("Missing host in proxy url")?;
+            let proxy_port = proxy.port_or_known_default().unwrap_or(80);
+            let stream = http.call(format!("http://{proxy_host}:{proxy_port}").parse()?).await?.into_inner();
+            // The proxy url is not displayed as it may hold credentials.
+            let stream = tunnel(stream, host, port, &proxy)
+                .await
+                .map_err(|err| format!("Proxy {}://{proxy_host}:{proxy_port}: {err}", proxy.scheme()))?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+/// Asks the proxy to open a tunnel to `host:port` over `stream`.
+async fn tunnel(mut stream: TcpStream, host: &str, port: u16, proxy: &Url) -> Result<TcpStream, BoxError> {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if !proxy.username().is_empty() {
+        // The credentials are percent-encoded in the url.
+        let mut credentials = percent_decode(proxy.username());
+        credentials.push(b':');
+        credentials.extend(percent_decode(proxy.password().unwrap_or_default()));
+        let credentials = base64::engine::general_purpose::STANDARD.encode(credentials);
+        request.push_str(&format!("Proxy-Authorization: Basic {credentials}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // The response has no body, and nothing else is sent by the proxy before we start talking to the gateway.
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    while !response.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err("Connection closed before the tunnel was opened".into());
+        }
+        response.extend_from_slice(&buf[..n]);
+        if response.len() > MAX_TUNNEL_RESPONSE_SIZE {
+            return Err("Response to the CONNECT request is too large".into());
+        }
+    }
+
+    let status_line = String::from_utf8_lossy(response.split(|b| *b == b'\n').next().unwrap_or_default());
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(stream),
+        _ => Err(format!("Tunnel refused: {}", status_line.trim()).into()),
+    }
+}
+
+/// Decodes the percent-encoded parts of a url, such as its username and password.
+fn percent_decode(s: &str) -> Vec<u8> {
+    let hex = |digit: u8| (digit as char).to_digit(16);
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut bytes = s.as_bytes();
+    while let [byte, rest @ ..] = bytes {
+        if let [b'%', high, low, escaped_rest @ ..] = bytes {
+            if let (Some(high), Some(low)) = (hex(*high), hex(*low)) {
+                decoded.push((high * 16 + low) as u8);
+                bytes = escaped_rest;
+                continue;
+            }
+        }
+        decoded.push(*byte);
+        bytes = rest;
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tunnel() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Url::parse(&format!("http://user:pass@{}", listener.local_addr().unwrap())).unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").await.unwrap();
+            // Echoes the tunneled bytes.
+            let n = stream.read(&mut buf).await.unwrap();
+            stream.write_all(&buf[..n]).await.unwrap();
+            request
+        });
+
+        let stream = TcpStream::connect(proxy.socket_addrs(|| None).unwrap()[0]).await.unwrap();
+        let mut stream = tunnel(stream, "alpha-mainnet.starknet.io", 443, &proxy).await.unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("CONNECT alpha-mainnet.starknet.io:443 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("user"), b"user");
+        assert_eq!(percent_decode("p%40ss%3Aw%2fd"), b"p@ss:w/d");
+        // Invalid escapes are kept as is.
+        assert_eq!(percent_decode("100%"), b"100%");
+        assert_eq!(percent_decode("%zz%4"), b"%zz%4");
+    }
+
+    #[tokio::test]
+    async fn test_proxy_error_hides_credentials() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Url::parse(&format!("http://user:secret@{}", listener.local_addr().unwrap())).unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream.write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").await.unwrap();
+        });
+
+        let mut connector = ProxyConnector::new(HttpConnector::new(), ProxyConfig::all(proxy.clone()));
+        let err = connector.call("https://alpha-mainnet.starknet.io".parse().unwrap()).await.unwrap_err().to_string();
+        assert!(err.contains("407"), "{err}");
+        assert!(err.contains(&format!("http://{}", proxy.host_str().unwrap())), "{err}");
+        assert!(!err.contains("secret"), "{err}");
+    }
+}
@@ -0,0 +1,142 @@
+//! Client-side rate limit of the requests to the gateways.
+//!
+//! Gateways ban the nodes which send them too many requests without an API key. With a rate limit, every request of a
+//! [`crate::GatewayProvider`] and of its clones, including the retries of failed requests, first takes a token from a
+//! shared token bucket. The bucket holds up to one second of requests, so that short bursts are sent right away.
+
+use futures::FutureExt;
+use hyper::Request;
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower::Service;
+
+/// Token bucket shared by the requests of a [`crate::GatewayProvider`] and its clones.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// Tokens added to the bucket per second.
+    rate: f64,
+    /// Capacity of the bucket.
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    /// Time the requests waited for a token, in microseconds, since the node started.
+    waited_micros: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens left at `updated_at`. This is negative when requests are waiting for tokens.
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_requests_per_second: NonZeroU32) -> Self {
+        let rate = f64::from(max_requests_per_second.get());
+        Self {
+            rate,
+            burst: rate,
+            bucket: Mutex::new(Bucket { tokens: rate, updated_at: Instant::now() }),
+            waited_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Takes a token, and returns how long the request has to wait before the token is available. Requests take their
+    /// tokens in turn, so that a request waiting for a token is not overtaken by the next ones.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().expect("Poisoned lock");
+        let refill = now.saturating_duration_since(bucket.updated_at).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst) - 1.0;
+        bucket.updated_at = now;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+
+    /// Waits for a token.
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if wait.is_zero() {
+            return;
+        }
+        self.waited_micros.fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+        tokio::time::sleep(wait).await;
+    }
+
+    /// Time the requests waited for a token since the node started.
+    pub(crate) fn waited(&self) -> Duration {
+        Duration::from_micros(self.waited_micros.load(Ordering::Relaxed))
+    }
+}
+
+/// Makes the requests wait for a token of the [`RateLimiter`], when there is one.
+#[derive(Clone, Debug)]
+pub struct RateLimitMiddleware<S> {
+    inner: S,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S> RateLimitMiddleware<S> {
+    pub(crate) fn new(inner: S, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        Self { inner, rate_limiter }
+    }
+}
+
+impl<S> Service<Request<String>> for RateLimitMiddleware<S>
+where
+    S: Service<Request<String>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<String>) -> Self::Future {
+        let rate_limiter = self.rate_limiter.clone();
+        let mut inner = self.inner.clone();
+
+        async move {
+            if let Some(rate_limiter) = rate_limiter {
+                rate_limiter.acquire().await;
+            }
+            inner.call(req).await
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = RateLimiter::new(NonZeroU32::new(4).unwrap());
+        let start = Instant::now();
+
+        // A full bucket lets a second of requests through at once.
+        for _ in 0..4 {
+            assert_eq!(rate_limiter.reserve(start), Duration::ZERO);
+        }
+        // The next requests wait in turn for the tokens added every 250ms.
+        assert_eq!(rate_limiter.reserve(start), Duration::from_millis(250));
+        assert_eq!(rate_limiter.reserve(start), Duration::from_millis(500));
+
+        // Once the waiting requests got their tokens, the bucket fills up again, up to its capacity.
+        let later = start + Duration::from_secs(10);
+        for _ in 0..4 {
+            assert_eq!(rate_limiter.reserve(later), Duration::ZERO);
+        }
+        assert_eq!(rate_limiter.reserve(later), Duration::from_millis(250));
+    }
+}
@@ -6,7 +6,7 @@ use http_body_util::BodyExt;
 use hyper::body::Incoming;
 use hyper::header::{HeaderName, HeaderValue, CONTENT_TYPE};
 use hyper::{HeaderMap, Request, Response, StatusCode, Uri};
-use mp_block::{BlockId, BlockTag};
+use mp_block::BlockId;
 use mp_gateway::error::{SequencerError, StarknetError};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -15,22 +15,47 @@ use tower::Service;
 use url::Url;
 
 use super::builder::PausedClient;
+use super::failover::{GatewayApi, GatewayEndpoints};
 
 #[derive(Debug)]
 pub struct RequestBuilder<'a> {
     client: &'a PausedClient,
     url: Url,
+    segments: Vec<String>,
     params: HashMap<Cow<'static, str>, String>,
     headers: HeaderMap,
+    failover: Option<(&'a GatewayEndpoints, GatewayApi)>,
+    strict_schema: bool,
 }
 
 impl<'a> RequestBuilder<'a> {
     pub fn new(client: &'a PausedClient, base_url: Url, headers: HeaderMap) -> Self {
-        Self { client, url: base_url, params: HashMap::new(), headers }
+        Self {
+            client,
+            url: base_url,
+            segments: Vec::new(),
+            params: HashMap::new(),
+            headers,
+            failover: None,
+            strict_schema: false,
+        }
+    }
+
+    /// Rejects the responses with fields unknown to the gateway types, see [`crate::schema`].
+    pub(crate) fn with_strict_schema(mut self, strict_schema: bool) -> Self {
+        self.strict_schema = strict_schema;
+        self
+    }
+
+    /// Sends the request to the `api` of the first healthy gateway of `endpoints` instead of `base_url`.
+    pub(crate) fn with_failover(mut self, endpoints: Option<&'a GatewayEndpoints>, api: GatewayApi) -> Self {
+        self.failover = endpoints.map(|endpoints| (endpoints, api));
+        self
     }
 
     pub fn add_uri_segment(mut self, segment: &str) -> Result<Self, url::ParseError> {
         self.url = self.url.join(segment)?;
+        self.segments.push(segment.to_string());
         Ok(self)
     }
 
@@ -54,11 +79,7 @@ impl<'a> RequestBuilder<'a> {
                 self = self.add_param(Cow::from("blockNumber"), &number.to_string());
             }
             BlockId::Tag(tag) => {
-                let tag = match tag {
-                    BlockTag::Latest => "latest",
-                    BlockTag::Pending => "pending",
-                };
-                self = self.add_param(Cow::from("blockNumber"), tag);
+                self = self.add_param(Cow::from("blockNumber"), tag.as_str());
             }
         }
         self
@@ -73,21 +94,12 @@ impl<'a> RequestBuilder<'a> {
     where
         T: DeserializeOwned,
     {
-        unpack(self.send_get_raw().await?).await
+        let strict_schema = self.strict_schema;
+        unpack(self.send_get_raw().await?, strict_schema).await
     }
 
     pub async fn send_get_raw(self) -> Result<Response<Incoming>, SequencerError> {
-        let uri = self.build_uri()?;
-
-        let mut req_builder = Request::builder().method(Method::GET).uri(uri);
-
-        req_builder.headers_mut().expect("Failed to get mutable reference to request headers").extend(self.headers);
-
-        let req = req_builder.body(String::new())?;
-
-        let response: Response<Incoming> =
-            self.client.clone().call(req).await.map_err(SequencerError::HttpCallError)?;
-        Ok(response)
+        self.send(Method::GET, String::new()).await
     }
 
     pub async fn send_post<T, D>(self, body: D) -> Result<T, SequencerError>
@@ -95,22 +107,49 @@ impl<'a> RequestBuilder<'a> {
         T: DeserializeOwned,
         D: Serialize,
     {
-        let uri = self.build_uri()?;
+        let body = serde_json::to_string(&body).map_err(SequencerError::SerializeRequest)?;
+        unpack(self.send(Method::POST, body).await?, self.strict_schema).await
+    }
+
+    async fn send(&self, method: Method, body: String) -> Result<Response<Incoming>, SequencerError> {
+        match self.failover {
+            Some((endpoints, api)) => {
+                endpoints
+                    .call(self.client, api, |base_url| {
+                        let url = self
+                            .segments
+                            .iter()
+                            .try_fold(base_url.clone(), |url, segment| url.join(segment))
+                            .map_err(|_| SequencerError::InvalidUrl(base_url.clone()))?;
+                        self.build_request(url, method.clone(), body.clone())
+                    })
+                    .await
+            }
+            None => {
+                let req = self.build_request(self.url.clone(), method, body)?;
+                self.client.clone().call(req).await.map_err(SequencerError::HttpCallError)
+            }
+        }
+    }
 
-        let mut req_builder = Request::builder().method(Method::POST).uri(uri);
+    fn build_request(&self, url: Url, method: Method, body: String) -> Result<Request<String>, SequencerError> {
+        let uri = self.build_uri(url)?;
 
-        req_builder.headers_mut().expect("Failed to get mutable reference to request headers").extend(self.headers);
+        let mut req_builder = Request::builder().method(method.clone()).uri(uri);
 
-        let body = serde_json::to_string(&body).map_err(SequencerError::SerializeRequest)?;
+        req_builder
+            .headers_mut()
+            .expect("Failed to get mutable reference to request headers")
+            .extend(self.headers.clone());
 
-        let req = req_builder.header(CONTENT_TYPE, "application/json").body(body)?;
+        if method == Method::POST {
+            req_builder = req_builder.header(CONTENT_TYPE, "application/json");
+        }
 
-        let response = self.client.clone().call(req).await.map_err(SequencerError::HttpCallError)?;
-        unpack(response).await
+        Ok(req_builder.body(body)?)
     }
 
-    fn build_uri(&self) -> Result<Uri, SequencerError> {
-        let mut url = self.url.clone();
+    fn build_uri(&self, mut url: Url) -> Result<Uri, SequencerError> {
         let query: String =
             self.params.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<String>>().join("&");
 
@@ -123,12 +162,12 @@ impl<'a> RequestBuilder<'a> {
     }
 }
 
-async fn unpack<T>(response: Response<Incoming>) -> Result<T, SequencerError>
+async fn unpack<T>(response: Response<Incoming>, strict_schema: bool) -> Result<T, SequencerError>
 where
     T: ::serde::de::DeserializeOwned,
 {
     let http_status = response.status();
-    let whole_body = response.collect().await?.aggregate();
+    let mut whole_body = response.collect().await?.aggregate();
 
     if http_status == StatusCode::TOO_MANY_REQUESTS {
         return Err(SequencerError::StarknetError(StarknetError::rate_limited()));
@@ -139,7 +178,7 @@ where
         return Err(starknet_error.into());
     }
 
-    let res = serde_json::from_reader(whole_body.reader())
+    let res = crate::schema::from_slice(&whole_body.copy_to_bytes(whole_body.remaining()), strict_schema)
         .map_err(|serde_error| SequencerError::DeserializeBody { serde_error })?;
 
     Ok(res)
@@ -0,0 +1,116 @@
+//! Deserialization of the gateway responses when the gateway adds fields.
+//!
+//! Most gateway types reject the fields they do not know, so that a change of the gateway schema is noticed. A field
+//! added by a new Starknet version would however break the sync until the node is upgraded. In tolerant mode, the
+//! default, the unknown fields are dropped from the response instead, with a warning the first time each of them is
+//! seen. In strict mode, meant for CI, the response is rejected.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+
+/// Maximum number of distinct unknown fields dropped from a single response.
+const MAX_UNKNOWN_FIELDS: usize = 16;
+
+/// The unknown fields already warned about.
+static UNKNOWN_FIELDS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Deserializes a gateway response, dropping the unknown fields unless `strict` is set.
+pub(crate) fn from_slice<T: DeserializeOwned>(body: &[u8], strict: bool) -> serde_json::Result<T> {
+    let mut err = match serde_json::from_slice(body) {
+        Ok(res) => return Ok(res),
+        Err(err) if strict => return Err(err),
+        Err(err) => err,
+    };
+
+    let mut value: Value = serde_json::from_slice(body)?;
+    for _ in 0..MAX_UNKNOWN_FIELDS {
+        let Some((field, expected)) = parse_unknown_field(&err.to_string()) else { return Err(err) };
+        if !remove_field(&mut value, &field, &expected) {
+            return Err(err);
+        }
+        if UNKNOWN_FIELDS.lock().expect("Poisoned lock").insert(field.clone()) {
+            tracing::warn!(
+                "The gateway returned the unknown field `{field}`, which is ignored. The gateway schema may have \
+                 changed: consider upgrading the node."
+            );
+        }
+
+        err = match serde_json::from_value(value.clone()) {
+            Ok(res) => return Ok(res),
+            Err(err) => err,
+        };
+    }
+    Err(err)
+}
+
+/// Parses the serde error `unknown field `{field}`, expected ...` into the field and the fields which were expected.
+fn parse_unknown_field(message: &str) -> Option<(String, Vec<String>)> {
+    let rest = message.strip_prefix("unknown field `")?;
+    let (field, rest) = rest.split_once('`')?;
+    let expected = rest.split('`').skip(1).step_by(2).map(String::from).collect();
+    Some((field.to_string(), expected))
+}
+
+/// Removes `field` from the objects which have it next to at least one of the `expected` fields, that is from the
+/// objects of the type which rejected it. Returns whether any object had it.
+fn remove_field(value: &mut Value, field: &str, expected: &[String]) -> bool {
+    match value {
+        Value::Object(map) => {
+            let mut removed = (expected.is_empty() || expected.iter().any(|key| map.contains_key(key)))
+                && map.remove(field).is_some();
+            for value in map.values_mut() {
+                removed |= remove_field(value, field, expected);
+            }
+            removed
+        }
+        Value::Array(values) => {
+            let mut removed = false;
+            for value in values {
+                removed |= remove_field(value, field, expected);
+            }
+            removed
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Inner {
+        a: u64,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Outer {
+        inner: Vec<Inner>,
+        b: u64,
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let body = br#"{"inner": [{"a": 1, "new": 2}, {"a": 3}], "b": 4, "new": 5, "other": 6}"#;
+        let expected = Outer { inner: vec![Inner { a: 1 }, Inner { a: 3 }], b: 4 };
+        assert_eq!(from_slice::<Outer>(body, false).unwrap(), expected);
+        assert!(from_slice::<Outer>(body, true).is_err());
+
+        // Errors other than unknown fields are kept.
+        assert!(from_slice::<Outer>(br#"{"inner": [], "new": 5}"#, false).is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_field() {
+        assert_eq!(
+            parse_unknown_field("unknown field `new`, expected `a` or `b` at line 1 column 10"),
+            Some(("new".to_string(), vec!["a".to_string(), "b".to_string()]))
+        );
+        assert_eq!(parse_unknown_field("missing field `a`"), None);
+    }
+}
@@ -1,8 +1,10 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::Buf;
 use http_body_util::BodyExt;
 use hyper::{body::Incoming, Request, Response};
+use mc_db::chain_head::ChainHeadUpdate;
 use mc_db::MadaraBackend;
 use mc_rpc::{
     providers::AddTransactionProvider,
@@ -17,8 +19,9 @@ use mp_gateway::user_transaction::{
     UserTransaction,
 };
 use mp_gateway::{
-    block::{BlockStatus, ProviderBlock, ProviderBlockPending, ProviderBlockSignature},
+    block::{BlockStatus, ProviderBlock, ProviderBlockHeader, ProviderBlockPending, ProviderBlockSignature},
     state_update::{ProviderStateUpdate, ProviderStateUpdatePending},
+    MAX_CLASSES_PER_REQUEST,
 };
 use mp_rpc::{BroadcastedDeclareTxn, TraceBlockTransactionsResult};
 use mp_utils::service::ServiceContext;
@@ -30,10 +33,14 @@ use super::{
     error::{GatewayError, OptionExt, ResultExt},
     helpers::{
         block_id_from_params, create_json_response, create_response_with_json_body, create_string_response,
-        get_params_from_request, include_block_params,
+        get_params_from_request, include_block_params, wait_for_block_params,
     },
 };
 
+/// How long a `get_state_update` request with `waitForBlock` is held when its block is not sealed yet. This must stay
+/// below the request timeout of the gateway client.
+const BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub async fn handle_get_block(
     req: Request<Incoming>,
     backend: Arc<MadaraBackend>,
@@ -56,11 +63,11 @@ pub async fn handle_get_block(
                 "Retrieved pending block info from db for non-pending block {block_id:?}"
             ))),
             MadaraMaybePendingBlockInfo::NotPending(block_info) => {
-                let body = json!({
-                    "block_hash": block_info.block_hash,
-                    "block_number": block_info.header.block_number
-                });
-                Ok(create_json_response(hyper::StatusCode::OK, &body))
+                let header = ProviderBlockHeader {
+                    block_hash: block_info.block_hash,
+                    block_number: block_info.header.block_number,
+                };
+                Ok(create_json_response(hyper::StatusCode::OK, &header))
             }
         }
     } else {
@@ -90,6 +97,44 @@ pub async fn handle_get_block(
     }
 }
 
+/// Holds the request until block `block_n` is stored, or until [`BLOCK_WAIT_TIMEOUT`]. Follower nodes keep such a
+/// request open for the next block, so that the sequencer pushes each block to them as soon as it is sealed instead of
+/// being polled.
+async fn wait_for_block(backend: &MadaraBackend, block_n: u64) -> Result<(), GatewayError> {
+    // Subscribing before reading the latest block, so that no block can be missed in between.
+    let chain_head = backend.subscribe_chain_head();
+    let latest_block_n = backend.get_latest_block_n().or_internal_server_error("Retrieving latest block number")?;
+    wait_for_chain_head(chain_head, latest_block_n, block_n, BLOCK_WAIT_TIMEOUT).await;
+    Ok(())
+}
+
+/// Waits for the chain head updates to reach block `block_n`, starting from `latest_block_n`. Returns whether the block
+/// was reached before `timeout`.
+async fn wait_for_chain_head(
+    mut chain_head: tokio::sync::broadcast::Receiver<ChainHeadUpdate>,
+    latest_block_n: Option<u64>,
+    block_n: u64,
+    timeout: Duration,
+) -> bool {
+    let is_stored = |latest_block_n: Option<u64>| latest_block_n.is_some_and(|latest| latest >= block_n);
+    if is_stored(latest_block_n) {
+        return true;
+    }
+
+    tokio::time::timeout(timeout, async {
+        loop {
+            match chain_head.recv().await {
+                Ok(update) if is_stored(update.chain_head.latest_block_n) => return true,
+                Ok(_) => {}
+                // The response is built from the database anyway.
+                Err(_) => return false,
+            }
+        }
+    })
+    .await
+    .unwrap_or(false)
+}
+
 pub async fn handle_get_signature(
     req: Request<Incoming>,
     backend: Arc<MadaraBackend>,
@@ -129,6 +174,10 @@ pub async fn handle_get_state_update(
     let params = get_params_from_request(&req);
     let block_id = block_id_from_params(&params).or_internal_server_error("Retrieving block id")?;
 
+    if let (true, BlockId::Number(block_n)) = (wait_for_block_params(&params), &block_id) {
+        wait_for_block(&backend, *block_n).await?;
+    }
+
     let resolved_block_id = backend
         .resolve_block_id(&block_id)
         .or_internal_server_error("Resolving block id from database")?
@@ -282,6 +331,47 @@ pub async fn handle_get_class_by_hash(
     Ok(json_response)
 }
 
+/// Returns several classes at once, as a JSON array in the order of the `classHashes` parameter, a comma-separated
+/// list of class hashes. This is not part of the Starknet feeder gateway: it lets Madara nodes syncing from another
+/// Madara node avoid making one request per class.
+pub async fn handle_get_classes_by_hash(
+    req: Request<Incoming>,
+    backend: Arc<MadaraBackend>,
+) -> Result<Response<String>, GatewayError> {
+    let params = get_params_from_request(&req);
+    let block_id = block_id_from_params(&params).unwrap_or(BlockId::Tag(BlockTag::Latest));
+
+    let class_hashes = params.get("classHashes").ok_or(StarknetError::missing_class_hash())?;
+    let class_hashes = class_hashes
+        .split(',')
+        .map(|class_hash| Felt::from_hex(class_hash).map_err(StarknetError::invalid_class_hash))
+        .collect::<Result<Vec<_>, _>>()?;
+    if class_hashes.len() > MAX_CLASSES_PER_REQUEST {
+        return Err(GatewayError::StarknetError(StarknetError::too_many_class_hashes(MAX_CLASSES_PER_REQUEST)));
+    }
+
+    let classes = class_hashes
+        .iter()
+        .map(|class_hash| {
+            let class_info = backend
+                .get_class_info(&block_id, class_hash)
+                .or_internal_server_error(format!("Retrieving class info from class hash {class_hash:x}"))?
+                .ok_or(StarknetError::class_not_found(*class_hash))?;
+
+            match class_info.contract_class() {
+                ContractClass::Sierra(flattened_sierra_class) => serde_json::to_string(flattened_sierra_class.as_ref())
+                    .or_internal_server_error("Failed to serialize sierra class"),
+                ContractClass::Legacy(compressed_legacy_contract_class) => compressed_legacy_contract_class
+                    .as_ref()
+                    .serialize_to_json()
+                    .or_internal_server_error("Failed to serialize legacy class"),
+            }
+        })
+        .collect::<Result<Vec<_>, GatewayError>>()?;
+
+    Ok(create_response_with_json_body(hyper::StatusCode::OK, format!("[{}]", classes.join(","))))
+}
+
 pub async fn handle_get_compiled_class_by_class_hash(
     req: Request<Incoming>,
     backend: Arc<MadaraBackend>,
@@ -383,3 +473,47 @@ async fn invoke_transaction(
         Err(e) => create_json_response(hyper::StatusCode::OK, &e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_db::chain_head::{ChainHead, PipelineStage};
+    use tokio::sync::broadcast;
+
+    fn block_stored(block_n: u64) -> ChainHeadUpdate {
+        ChainHeadUpdate {
+            stage: PipelineStage::BlockImport,
+            block_n,
+            chain_head: ChainHead { latest_block_n: Some(block_n), ..Default::default() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_block_already_stored() {
+        let (_sender, chain_head) = broadcast::channel(16);
+        assert!(wait_for_chain_head(chain_head, Some(5), 5, Duration::from_secs(60)).await);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_block_held_until_stored() {
+        let (sender, chain_head) = broadcast::channel(16);
+        let wait = tokio::spawn(wait_for_chain_head(chain_head, Some(3), 5, Duration::from_secs(60)));
+
+        sender.send(block_stored(4)).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!wait.is_finished());
+
+        sender.send(block_stored(5)).unwrap();
+        assert!(tokio::time::timeout(Duration::from_secs(5), wait).await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_block_timeout() {
+        let (sender, chain_head) = broadcast::channel(16);
+        let start = std::time::Instant::now();
+
+        sender.send(block_stored(4)).unwrap();
+        assert!(!wait_for_chain_head(chain_head, None, 5, Duration::from_millis(200)).await);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
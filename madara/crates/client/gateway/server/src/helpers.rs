@@ -94,10 +94,9 @@ pub(crate) fn get_params_from_request(req: &Request<Incoming>) -> HashMap<String
 
 pub(crate) fn block_id_from_params(params: &HashMap<String, String>) -> Result<BlockId, StarknetError> {
     if let Some(block_number) = params.get("blockNumber") {
-        match block_number.as_str() {
-            "latest" => Ok(BlockId::Tag(BlockTag::Latest)),
-            "pending" => Ok(BlockId::Tag(BlockTag::Pending)),
-            _ => {
+        match block_number.parse::<BlockTag>() {
+            Ok(tag) => Ok(BlockId::Tag(tag)),
+            Err(_) => {
                 let block_number = block_number.parse().map_err(|e: std::num::ParseIntError| {
                     StarknetError::new(StarknetErrorCode::MalformedRequest, e.to_string())
                 })?;
@@ -116,3 +115,7 @@ pub(crate) fn block_id_from_params(params: &HashMap<String, String>) -> Result<B
 pub(crate) fn include_block_params(params: &HashMap<String, String>) -> bool {
     params.get("includeBlock").map_or(false, |v| v == "true")
 }
+
+pub(crate) fn wait_for_block_params(params: &HashMap<String, String>) -> bool {
+    params.get("waitForBlock").map_or(false, |v| v == "true")
+}
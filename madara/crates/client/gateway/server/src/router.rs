@@ -7,8 +7,8 @@ use mp_utils::service::ServiceContext;
 
 use super::handler::{
     handle_add_transaction, handle_get_block, handle_get_block_traces, handle_get_class_by_hash,
-    handle_get_compiled_class_by_class_hash, handle_get_contract_addresses, handle_get_public_key,
-    handle_get_signature, handle_get_state_update,
+    handle_get_classes_by_hash, handle_get_compiled_class_by_class_hash, handle_get_contract_addresses,
+    handle_get_public_key, handle_get_signature, handle_get_state_update,
 };
 use super::helpers::{not_found_response, service_unavailable_response};
 
@@ -61,6 +61,9 @@ async fn feeder_gateway_router(
         (&Method::GET, "feeder_gateway/get_class_by_hash") => {
             Ok(handle_get_class_by_hash(req, backend).await.unwrap_or_else(Into::into))
         }
+        (&Method::GET, "feeder_gateway/get_classes_by_hash") => {
+            Ok(handle_get_classes_by_hash(req, backend).await.unwrap_or_else(Into::into))
+        }
         (&Method::GET, "feeder_gateway/get_compiled_class_by_class_hash") => {
             Ok(handle_get_compiled_class_by_class_hash(req, backend).await.unwrap_or_else(Into::into))
         }
@@ -0,0 +1,56 @@
+//! Custom transaction versions for appchains.
+//!
+//! Appchains experimenting with new transaction types can accept invoke transactions with a version which is not a
+//! Starknet one, by listing it in [`custom_transaction_versions`] in their chain config. Such transactions have the
+//! layout of invoke v3 transactions, and are hashed with their own version so that their signature cannot be replayed
+//! as a Starknet transaction, see [`CustomInvokeTransaction::compute_hash`].
+//!
+//! Execution only knows about Starknet transactions: the mempool hands each custom transaction to a
+//! [`CustomTransactionHandler`], which turns it into the Starknet invoke transaction executing it, for example a call
+//! to a contract implementing the new semantics from a relayer account. That transaction is then validated and
+//! added to the mempool like any other, and is the one stored in the blocks. Transactions with a version which is
+//! neither a Starknet one nor enabled in the chain config are rejected.
+//!
+//! [`custom_transaction_versions`]: mp_chain_config::ChainConfig::custom_transaction_versions
+
+use mp_rpc::BroadcastedInvokeTxn;
+use mp_transactions::CustomInvokeTransaction;
+use starknet_types_core::felt::Felt;
+use std::borrow::Cow;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Rejected by the custom transaction handler: {reason}")]
+pub struct CustomTransactionRejection {
+    pub reason: Cow<'static, str>,
+}
+
+impl CustomTransactionRejection {
+    pub fn new(reason: impl Into<Cow<'static, str>>) -> Self {
+        Self { reason: reason.into() }
+    }
+}
+
+pub trait CustomTransactionHandler: Send + Sync {
+    /// Called by the mempool when a transaction with a custom version enabled in the chain config is received.
+    /// `tx_hash` is the hash the sender signed. Returns the Starknet invoke transaction executing it, or an error to
+    /// reject the transaction.
+    fn dispatch(
+        &self,
+        tx: CustomInvokeTransaction,
+        tx_hash: Felt,
+    ) -> Result<BroadcastedInvokeTxn, CustomTransactionRejection>;
+}
+
+/// The default handler: custom transactions cannot be executed, they are all rejected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopCustomTransactionHandler;
+
+impl CustomTransactionHandler for NoopCustomTransactionHandler {
+    fn dispatch(
+        &self,
+        tx: CustomInvokeTransaction,
+        _tx_hash: Felt,
+    ) -> Result<BroadcastedInvokeTxn, CustomTransactionRejection> {
+        Err(CustomTransactionRejection::new(format!("No handler for transaction version {:#x}", tx.version)))
+    }
+}
@@ -1,19 +1,28 @@
 use crate::L1DataProvider;
-use mp_block::header::{BlockTimestamp, PendingHeader};
-use mp_chain_config::ChainConfig;
+use mc_db::MadaraBackend;
+use mp_block::header::{GasPrices, PendingHeader};
 use starknet_types_core::felt::Felt;
 
+/// Header of a new pending block. On a devnet, the timestamp and gas prices may be overridden by the
+/// [`mc_db::devnet_control::DevnetControl`].
 pub fn make_pending_header(
     parent_block_hash: Felt,
-    chain_config: &ChainConfig,
+    backend: &MadaraBackend,
     l1_info: &dyn L1DataProvider,
 ) -> PendingHeader {
+    let chain_config = backend.chain_config();
+    let devnet_control = backend.get_devnet_control();
     PendingHeader {
         parent_block_hash,
         sequencer_address: **chain_config.sequencer_address,
-        block_timestamp: BlockTimestamp::now(),
+        block_timestamp: devnet_control.block_timestamp(),
         protocol_version: chain_config.latest_protocol_version,
-        l1_gas_price: l1_info.get_gas_prices(),
+        l1_gas_price: devnet_control.gas_prices.unwrap_or_else(|| l1_info.get_gas_prices()),
         l1_da_mode: l1_info.get_da_mode(),
     }
 }
+
+/// Gas prices of a new pending block, see [`make_pending_header`].
+pub fn next_block_gas_prices(backend: &MadaraBackend, l1_info: &dyn L1DataProvider) -> GasPrices {
+    backend.get_devnet_control().gas_prices.unwrap_or_else(|| l1_info.get_gas_prices())
+}
@@ -3,6 +3,7 @@ use std::time::{Duration, SystemTime};
 use blockifier::transaction::transaction_types::TransactionType;
 use mc_exec::execution::TxInfo;
 use mp_chain_config::ChainConfig;
+use starknet_types_core::felt::Felt;
 
 use crate::MempoolTransaction;
 
@@ -12,6 +13,9 @@ pub struct MempoolLimits {
     pub max_transactions: usize,
     pub max_declare_transactions: usize,
     pub max_age: Option<Duration>,
+    /// Executor account of the SNIP-9 outside executions sponsored by this node. Its transactions expire at the end of
+    /// the validity window of the outside execution they submit, see [`MempoolTransaction::valid_until`].
+    pub outside_execution_executor: Option<Felt>,
 }
 
 impl MempoolLimits {
@@ -20,11 +24,17 @@ impl MempoolLimits {
             max_transactions: chain_config.mempool_tx_limit,
             max_declare_transactions: chain_config.mempool_declare_tx_limit,
             max_age: chain_config.mempool_tx_max_age,
+            outside_execution_executor: None,
         }
     }
     #[cfg(any(test, feature = "testing"))]
     pub fn for_testing() -> Self {
-        Self { max_age: None, max_declare_transactions: usize::MAX, max_transactions: usize::MAX }
+        Self {
+            max_age: None,
+            max_declare_transactions: usize::MAX,
+            max_transactions: usize::MAX,
+            outside_execution_executor: None,
+        }
     }
 }
 
@@ -47,6 +57,8 @@ pub enum MempoolLimitReached {
     MaxDeclareTransactions { max: usize },
     #[error("The transaction age is greater than the limit of {max:?}")]
     Age { max: Duration },
+    #[error("The transaction validity window ended at {valid_until:?}")]
+    ValidityWindow { valid_until: SystemTime },
 }
 
 #[derive(Debug)]
@@ -55,31 +67,35 @@ pub(crate) struct TransactionCheckedLimits {
     check_declare_limit: bool,
     check_age: bool,
     tx_arrived_at: SystemTime,
+    tx_valid_until: Option<SystemTime>,
 }
 
 impl TransactionCheckedLimits {
     // Returns which limits apply for this transaction.
     // This struct is also used to update the limits after insertion, without having to keep a clone of the transaction around.
     // We can add more limits here as needed :)
-    pub fn limits_for(tx: &MempoolTransaction) -> Self {
+    pub fn limits_for(tx: &MempoolTransaction, outside_execution_executor: Option<Felt>) -> Self {
         match tx.tx.tx_type() {
             TransactionType::Declare => TransactionCheckedLimits {
                 check_tx_limit: true,
                 check_declare_limit: true,
                 check_age: true,
                 tx_arrived_at: tx.arrived_at,
+                tx_valid_until: tx.valid_until(outside_execution_executor),
             },
             TransactionType::DeployAccount => TransactionCheckedLimits {
                 check_tx_limit: true,
                 check_declare_limit: false,
                 check_age: true,
                 tx_arrived_at: tx.arrived_at,
+                tx_valid_until: tx.valid_until(outside_execution_executor),
             },
             TransactionType::InvokeFunction => TransactionCheckedLimits {
                 check_tx_limit: true,
                 check_declare_limit: false,
                 check_age: true,
                 tx_arrived_at: tx.arrived_at,
+                tx_valid_until: tx.valid_until(outside_execution_executor),
             },
             // L1 handler transactions are transactions added into the L1 core contract. We don't want to miss
             // any of those if possible.
@@ -88,6 +104,7 @@ impl TransactionCheckedLimits {
                 check_declare_limit: false,
                 check_age: false,
                 tx_arrived_at: tx.arrived_at,
+                tx_valid_until: tx.valid_until(outside_execution_executor),
             },
         }
     }
@@ -95,6 +112,11 @@ impl TransactionCheckedLimits {
     pub fn checks_age(&self) -> bool {
         self.check_age
     }
+
+    /// End of the validity window of the transaction, see [`MempoolTransaction::valid_until`].
+    pub fn valid_until(&self) -> Option<SystemTime> {
+        self.tx_valid_until.filter(|_| self.check_age)
+    }
 }
 
 impl MempoolLimiter {
@@ -102,6 +124,11 @@ impl MempoolLimiter {
         Self { config: limits, current_transactions: 0, current_declare_transactions: 0 }
     }
 
+    /// The limits which apply to a transaction, see [`TransactionCheckedLimits::limits_for`].
+    pub fn limits_for(&self, tx: &MempoolTransaction) -> TransactionCheckedLimits {
+        TransactionCheckedLimits::limits_for(tx, self.config.outside_execution_executor)
+    }
+
     pub fn check_insert_limits(&self, to_check: &TransactionCheckedLimits) -> Result<(), MempoolLimitReached> {
         // tx limit
         if to_check.check_tx_limit && self.current_transactions >= self.config.max_transactions {
@@ -113,6 +140,11 @@ impl MempoolLimiter {
             return Err(MempoolLimitReached::MaxDeclareTransactions { max: self.config.max_declare_transactions });
         }
 
+        // validity window
+        if let Some(valid_until) = to_check.valid_until().filter(|valid_until| *valid_until < SystemTime::now()) {
+            return Err(MempoolLimitReached::ValidityWindow { valid_until });
+        }
+
         // age
         if let Some(max_age) = self.config.max_age {
             if self.tx_age_exceeded(to_check) {
@@ -123,15 +155,15 @@ impl MempoolLimiter {
         Ok(())
     }
 
+    /// Whether the transaction is older than [`MempoolLimits::max_age`] or past the end of its validity window.
     pub fn tx_age_exceeded(&self, to_check: &TransactionCheckedLimits) -> bool {
-        let Some(max_age) = self.config.max_age else { return false };
-        if to_check.check_age {
-            let current_time = SystemTime::now();
-            if to_check.tx_arrived_at < current_time.checked_sub(max_age).unwrap_or(SystemTime::UNIX_EPOCH) {
-                return true;
-            }
+        let current_time = SystemTime::now();
+        if to_check.valid_until().is_some_and(|valid_until| valid_until < current_time) {
+            return true;
         }
-        false
+        let Some(max_age) = self.config.max_age else { return false };
+        to_check.check_age
+            && to_check.tx_arrived_at < current_time.checked_sub(max_age).unwrap_or(SystemTime::UNIX_EPOCH)
     }
 
     pub fn update_tx_limits(&mut self, limits: &TransactionCheckedLimits) {
@@ -11,7 +11,8 @@ use mc_exec::execution::TxInfo;
 use mp_convert::ToFelt;
 use starknet_api::core::{ContractAddress, Nonce};
 use starknet_types_core::felt::Felt;
-use std::collections::{btree_map, hash_map, BTreeMap, BTreeSet, HashMap};
+use std::collections::{btree_map, hash_map, BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::SystemTime;
 
 mod deployed_contracts;
 mod intent;
@@ -28,7 +29,6 @@ pub use tx::*;
 #[cfg(any(test, feature = "testing"))]
 use crate::CheckInvariants;
 
-#[cfg(any(test, feature = "testing"))]
 use starknet_api::transaction::TransactionHash;
 
 /// A struct responsible for the rapid ordering and disposal of transactions by
@@ -150,9 +150,11 @@ pub struct MempoolInner {
     /// We have one [Nonce] to  [MempoolTransaction] mapping per contract
     /// address.
     ///
+    /// Ordered by contract address, so that accounts can be listed page by
+    /// page.
+    ///
     /// [Nonce]: starknet_api::core::Nonce
-    // TODO: this can be replace with a hasmap with a tupple key
-    pub nonce_mapping: HashMap<Felt, NonceTxMapping>,
+    pub nonce_mapping: BTreeMap<Felt, NonceTxMapping>,
     /// FIFO queue of all [ready] intents.
     ///
     /// [ready]: TransactionIntentReady
@@ -179,6 +181,28 @@ pub struct MempoolInner {
     ///
     /// [Mempool]: super::Mempool
     limiter: MempoolLimiter,
+    /// Hashes of the transactions which were removed because their age exceeded
+    /// the limit, and which have not been collected by
+    /// [take_expired_txs] yet.
+    ///
+    /// [take_expired_txs]: Self::take_expired_txs
+    expired_txs: Vec<TransactionHash>,
+    /// Hashes of all the transactions in [nonce_mapping], to look a
+    /// transaction up by hash without going through every transaction.
+    ///
+    /// [nonce_mapping]: Self::nonce_mapping
+    pub(crate) tx_hashes: HashSet<Felt>,
+    /// End of the validity window, contract address, nonce and hash of the
+    /// transactions which have one, sorted by the end of their window.
+    ///
+    /// These transactions can expire before older ones, so they are not all
+    /// found by going through the intent queues by time of arrival in
+    /// [remove_age_exceeded_txs]. Entries are not removed when their
+    /// transaction leaves the mempool in another way, but only once their
+    /// validity window ends.
+    ///
+    /// [remove_age_exceeded_txs]: Self::remove_age_exceeded_txs
+    tx_validity_windows: BTreeSet<(SystemTime, Felt, Nonce, Felt)>,
 
     /// This is just a helper field to use during tests to get the current nonce
     /// of a contract as known by the [MempoolInner].
@@ -282,7 +306,9 @@ impl CheckInvariants for MempoolInner {
             self.tx_intent_queue_pending_by_timestamp
         );
 
+        let mut tx_hashes = HashSet::new();
         for (contract_address, nonce_mapping) in self.nonce_mapping.iter() {
+            tx_hashes.extend(nonce_mapping.transactions.values().map(|mempool_tx| mempool_tx.tx_hash().to_felt()));
             let count = tx_counts.get(contract_address).unwrap_or_else(|| {
                 panic!(
                     "Extra nonce mapping at contract address {contract_address}, remaining nonces are: {:?}",
@@ -298,6 +324,8 @@ impl CheckInvariants for MempoolInner {
             );
         }
 
+        assert_eq!(tx_hashes, self.tx_hashes, "Transaction hashes do not match the transactions in the nonce mappings");
+
         assert_eq!(
             deployed_count,
             self.deployed_contracts.count(),
@@ -320,6 +348,9 @@ impl MempoolInner {
             tx_intent_queue_pending_by_timestamp: Default::default(),
             deployed_contracts: Default::default(),
             limiter: MempoolLimiter::new(limits_config),
+            expired_txs: Default::default(),
+            tx_hashes: Default::default(),
+            tx_validity_windows: Default::default(),
             #[cfg(any(test, feature = "testing"))]
             nonce_cache_inner: Default::default(),
         }
@@ -338,13 +369,14 @@ impl MempoolInner {
         // delete age-exceeded txs from the mempool
         // todo(perf): this may want to limit this check once every few seconds
         // to avoid it being in the hot path?
-        let limits_for_tx = TransactionCheckedLimits::limits_for(&mempool_tx);
+        let limits_for_tx = self.limiter.limits_for(&mempool_tx);
         if !force {
             self.remove_age_exceeded_txs();
             self.limiter.check_insert_limits(&limits_for_tx)?;
         }
 
         let contract_address = mempool_tx.contract_address().to_felt();
+        let tx_hash = mempool_tx.tx_hash().to_felt();
         let arrived_at = mempool_tx.arrived_at;
         let deployed_contract_address =
             if let Transaction::AccountTransaction(AccountTransaction::DeployAccount(tx)) = &mempool_tx.tx {
@@ -356,7 +388,7 @@ impl MempoolInner {
         // Inserts the transaction into the nonce tx mapping for the current
         // contract
         match self.nonce_mapping.entry(contract_address) {
-            hash_map::Entry::Occupied(mut entry) => {
+            btree_map::Entry::Occupied(mut entry) => {
                 // Handle nonce collision.
                 let nonce_tx_mapping = entry.get_mut();
                 let replaced = match nonce_tx_mapping.insert(mempool_tx, nonce_info.nonce, force) {
@@ -380,7 +412,8 @@ impl MempoolInner {
                                 phantom: std::marker::PhantomData,
                             });
                             debug_assert!(removed);
-                            self.limiter.mark_removed(&TransactionCheckedLimits::limits_for(&previous));
+                            self.limiter.mark_removed(&self.limiter.limits_for(&previous));
+                            self.tx_hashes.remove(&previous.tx_hash().to_felt());
 
                             // So! This is a pretty nasty edge case. If we
                             // replace a transaction, and the previous tx was
@@ -438,7 +471,8 @@ impl MempoolInner {
                             );
                             debug_assert!(removed);
 
-                            self.limiter.mark_removed(&TransactionCheckedLimits::limits_for(&previous));
+                            self.limiter.mark_removed(&self.limiter.limits_for(&previous));
+                            self.tx_hashes.remove(&previous.tx_hash().to_felt());
 
                             if let Some(contract_address) = &deployed_contract_address {
                                 if previous.tx.tx_type() != TransactionType::DeployAccount {
@@ -476,7 +510,7 @@ impl MempoolInner {
                     }
                 };
             }
-            hash_map::Entry::Vacant(entry) => {
+            btree_map::Entry::Vacant(entry) => {
                 // Insert the new nonce tx mapping
                 let nonce_tx_mapping = NonceTxMapping::new_with_first_tx(mempool_tx, nonce_info.nonce);
                 entry.insert(nonce_tx_mapping);
@@ -527,6 +561,11 @@ impl MempoolInner {
             }
         }
 
+        self.tx_hashes.insert(tx_hash);
+        if let Some(valid_until) = limits_for_tx.valid_until() {
+            self.tx_validity_windows.insert((valid_until, contract_address, nonce_info.nonce, tx_hash));
+        }
+
         // Update transaction limits
         if update_limits {
             self.limiter.update_tx_limits(&limits_for_tx);
@@ -540,6 +579,8 @@ impl MempoolInner {
     }
 
     pub fn remove_age_exceeded_txs(&mut self) {
+        self.remove_validity_window_ended_txs();
+
         let mut ready_no_age_check = vec![];
 
         // We take advantage of the fact that TransactionIntentReady is
@@ -547,7 +588,7 @@ impl MempoolInner {
         // not exceeded its max age (and that transaction supports age limits)
         // we know no more transactions can be removed.
         while let Some(intent) = self.tx_intent_queue_ready.first() {
-            let hash_map::Entry::Occupied(mut entry) = self.nonce_mapping.entry(intent.contract_address) else {
+            let btree_map::Entry::Occupied(mut entry) = self.nonce_mapping.entry(intent.contract_address) else {
                 unreachable!("Nonce chain does not match tx queue");
             };
 
@@ -556,9 +597,11 @@ impl MempoolInner {
                 unreachable!("Nonce chain without a tx");
             };
 
-            let limits = TransactionCheckedLimits::limits_for(nonce_mapping_entry.get());
+            let limits = self.limiter.limits_for(nonce_mapping_entry.get());
             if self.limiter.tx_age_exceeded(&limits) {
                 let mempool_tx = nonce_mapping_entry.remove();
+                self.tx_hashes.remove(&mempool_tx.tx_hash().to_felt());
+                self.expired_txs.push(mempool_tx.tx_hash());
 
                 // We must remember to update the deploy contract count on
                 // removal!
@@ -601,7 +644,7 @@ impl MempoolInner {
         // sync. This means removals need to take place across both queues.
         while let Some(intent) = self.tx_intent_queue_pending_by_timestamp.first() {
             // Set 1: look for a pending transaction which is too old
-            let hash_map::Entry::Occupied(mut entry) = self.nonce_mapping.entry(intent.contract_address) else {
+            let btree_map::Entry::Occupied(mut entry) = self.nonce_mapping.entry(intent.contract_address) else {
                 unreachable!("Nonce chain does not match tx queue");
             };
 
@@ -610,12 +653,14 @@ impl MempoolInner {
                 unreachable!("Nonce chain without a tx");
             };
 
-            let limits = TransactionCheckedLimits::limits_for(nonce_mapping_entry.get());
+            let limits = self.limiter.limits_for(nonce_mapping_entry.get());
             if self.limiter.tx_age_exceeded(&limits) {
                 // Step 2: we found it! Now we remove the entry in
                 // tx_intent_queue_pending_by_timestamp
 
                 let mempool_tx = nonce_mapping_entry.remove(); // *- snip -*
+                self.tx_hashes.remove(&mempool_tx.tx_hash().to_felt());
+                self.expired_txs.push(mempool_tx.tx_hash());
                 if let Transaction::AccountTransaction(AccountTransaction::DeployAccount(tx)) = mempool_tx.tx {
                     // Remember to update the deployed contract count along the
                     // way!
@@ -666,6 +711,77 @@ impl MempoolInner {
         }
     }
 
+    /// Removes the transactions whose validity window ended, wherever they are
+    /// in the intent queues.
+    fn remove_validity_window_ended_txs(&mut self) {
+        let now = SystemTime::now();
+        while let Some(&(valid_until, contract_address, nonce, tx_hash)) = self.tx_validity_windows.first() {
+            if valid_until >= now {
+                break;
+            }
+            self.tx_validity_windows.pop_first();
+
+            // The transaction may have already left the mempool, or have been
+            // replaced.
+            let btree_map::Entry::Occupied(mut entry) = self.nonce_mapping.entry(contract_address) else { continue };
+            let nonce_mapping = entry.get_mut();
+            let btree_map::Entry::Occupied(nonce_mapping_entry) = nonce_mapping.transactions.entry(nonce) else {
+                continue;
+            };
+            if nonce_mapping_entry.get().tx_hash().to_felt() != tx_hash {
+                continue;
+            }
+
+            let mempool_tx = nonce_mapping_entry.remove();
+            if nonce_mapping.transactions.is_empty() {
+                entry.remove();
+            }
+
+            let removed = self.tx_intent_queue_ready.remove(&TransactionIntentReady {
+                contract_address,
+                timestamp: mempool_tx.arrived_at,
+                nonce,
+                nonce_next: mempool_tx.nonce_next,
+                phantom: std::marker::PhantomData,
+            });
+            if !removed {
+                let removed = self.tx_intent_queue_pending_by_timestamp.remove(&TransactionIntentPendingByTimestamp {
+                    contract_address,
+                    timestamp: mempool_tx.arrived_at,
+                    nonce,
+                    nonce_next: mempool_tx.nonce_next,
+                    phantom: std::marker::PhantomData,
+                });
+                debug_assert!(removed);
+
+                let hash_map::Entry::Occupied(mut entry) =
+                    self.tx_intent_queue_pending_by_nonce.entry(contract_address)
+                else {
+                    unreachable!("Missing pending intent mapping for {contract_address:?}");
+                };
+                let queue = entry.get_mut();
+                let removed = queue.remove(&TransactionIntentPendingByNonce {
+                    contract_address,
+                    timestamp: mempool_tx.arrived_at,
+                    nonce,
+                    nonce_next: mempool_tx.nonce_next,
+                    phantom: std::marker::PhantomData,
+                });
+                debug_assert!(removed.is_some());
+                if queue.is_empty() {
+                    entry.remove();
+                }
+            }
+
+            self.limiter.mark_removed(&self.limiter.limits_for(&mempool_tx));
+            self.tx_hashes.remove(&tx_hash);
+            self.expired_txs.push(mempool_tx.tx_hash());
+            if let Transaction::AccountTransaction(AccountTransaction::DeployAccount(tx)) = mempool_tx.tx {
+                self.deployed_contracts.decrement(tx.contract_address);
+            }
+        }
+    }
+
     pub fn pop_next(&mut self) -> Option<MempoolTransaction> {
         // Pop tx queue.
         let (tx_mempool, contract_address, nonce_next) = loop {
@@ -673,13 +789,14 @@ impl MempoolInner {
             let tx_intent = self.tx_intent_queue_ready.pop_first()?;
             let tx_mempool = self.pop_tx_from_intent(&tx_intent);
 
-            let limits = TransactionCheckedLimits::limits_for(&tx_mempool);
+            let limits = self.limiter.limits_for(&tx_mempool);
             if !self.limiter.tx_age_exceeded(&limits) {
                 break (tx_mempool, tx_intent.contract_address, tx_intent.nonce_next);
             }
 
             // transaction age exceeded, remove the tx from mempool.
             self.limiter.mark_removed(&limits);
+            self.expired_txs.push(tx_mempool.tx_hash());
         };
 
         // Looks for the next transaction from the same account in the pending
@@ -729,6 +846,7 @@ impl MempoolInner {
 
         // Get the next ready transaction from the nonce chain
         let (mempool_tx, nonce_tx_mapping_new_state) = nonce_tx_mapping.pop();
+        self.tx_hashes.remove(&mempool_tx.tx_hash().to_felt());
         if nonce_tx_mapping_new_state == NonceTxMappingNewState::Empty {
             let removed = self.nonce_mapping.remove(&tx_queue_account.contract_address);
             debug_assert!(removed.is_some());
@@ -742,10 +860,53 @@ impl MempoolInner {
         mempool_tx
     }
 
+    /// Returns the hashes of the transactions which were removed from the
+    /// mempool because their age exceeded the limit since the last call.
+    pub fn take_expired_txs(&mut self) -> Vec<TransactionHash> {
+        std::mem::take(&mut self.expired_txs)
+    }
+
     pub fn pop_next_chunk(&mut self, dest: &mut impl Extend<MempoolTransaction>, n: usize) {
         dest.extend((0..n).map_while(|_| self.pop_next()))
     }
 
+    /// Returns the next `n` transactions, in the order in which [pop_next] would return them, without removing them
+    /// from the mempool.
+    ///
+    /// [pop_next]: Self::pop_next
+    pub fn peek_next_chunk(&self, n: usize) -> Vec<MempoolTransaction> {
+        // Simulates the ready queue: popping a transaction makes the next transaction of the same account ready.
+        let mut ready: BTreeSet<_> = self
+            .tx_intent_queue_ready
+            .iter()
+            .map(|intent| (intent.timestamp, intent.contract_address, intent.nonce))
+            .collect();
+        let mut txs = Vec::with_capacity(n.min(ready.len()));
+
+        while txs.len() < n {
+            let Some((_, contract_address, nonce)) = ready.pop_first() else { break };
+            let Some(mempool_tx) =
+                self.nonce_mapping.get(&contract_address).and_then(|mapping| mapping.transactions.get(&nonce))
+            else {
+                continue;
+            };
+            // Age exceeded transactions are dropped by pop_next, without making the next one ready.
+            if self.limiter.tx_age_exceeded(&self.limiter.limits_for(mempool_tx)) {
+                continue;
+            }
+
+            let next_pending = self
+                .tx_intent_queue_pending_by_nonce
+                .get(&contract_address)
+                .and_then(|queue| queue.keys().find(|intent| intent.nonce == mempool_tx.nonce_next));
+            if let Some(intent) = next_pending {
+                ready.insert((intent.timestamp, intent.contract_address, intent.nonce));
+            }
+            txs.push(mempool_tx.clone());
+        }
+        txs
+    }
+
     /// This is called by the block production after a batch of transaction is executed.
     /// Mark the consumed txs as consumed, and re-add the transactions that are not consumed in the mempool.
     pub fn re_add_txs(
@@ -754,7 +915,7 @@ impl MempoolInner {
         consumed_txs: impl IntoIterator<Item = MempoolTransaction>,
     ) {
         for tx in consumed_txs {
-            self.limiter.mark_removed(&TransactionCheckedLimits::limits_for(&tx))
+            self.limiter.mark_removed(&self.limiter.limits_for(&tx))
         }
         for tx in txs {
             let force = true;
@@ -1,17 +1,28 @@
 use crate::{clone_transaction, contract_addr, nonce, tx_hash};
+use blockifier::transaction::account_transaction::AccountTransaction;
 use blockifier::transaction::transaction_execution::Transaction;
 use mc_exec::execution::TxInfo;
 use mp_class::ConvertedClass;
-use mp_convert::FeltHexDisplay;
+use mp_convert::{felt_to_u64, FeltHexDisplay, ToFelt};
 use starknet_api::{
     core::{ContractAddress, Nonce},
-    transaction::TransactionHash,
+    transaction::{InvokeTransaction, TransactionHash},
     StarknetApiError,
 };
-use std::{fmt, time::SystemTime};
+use starknet_types_core::felt::Felt;
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
 
 pub type ArrivedAtTimestamp = SystemTime;
 
+/// Selectors of the SNIP-9 `execute_from_outside` and `execute_from_outside_v2` entrypoints.
+const EXECUTE_FROM_OUTSIDE_SELECTORS: [Felt; 2] = [
+    Felt::from_hex_unchecked("0x7ec457cd7ed1630225a8328f826a29a327b19486f6b2882b4176545ebdbe3d"),
+    Felt::from_hex_unchecked("0x34cc13b274446654ca3233ed2c1620d4c5d1d32fd20b47146a3371064bdc57d"),
+];
+
 /// Wrapper around a blockifier [Transaction] with some added information needed
 /// by the [Mempool]
 ///
@@ -85,4 +96,42 @@ impl MempoolTransaction {
     pub fn tx_hash(&self) -> TransactionHash {
         tx_hash(&self.tx)
     }
+
+    /// End of the validity window of the transaction, after which it can only
+    /// revert. This is the `execute_before` of the SNIP-9 outside execution
+    /// submitted by a transaction of the outside execution executor, see
+    /// [`MempoolLimits::outside_execution_executor`]. The calldata of the other
+    /// transactions is not inspected: they only expire with the mempool age
+    /// limit.
+    ///
+    /// [`MempoolLimits::outside_execution_executor`]: crate::MempoolLimits::outside_execution_executor
+    pub fn valid_until(&self, outside_execution_executor: Option<Felt>) -> Option<SystemTime> {
+        let Transaction::AccountTransaction(AccountTransaction::Invoke(tx)) = &self.tx else { return None };
+        match &tx.tx {
+            InvokeTransaction::V0(_) => None,
+            InvokeTransaction::V1(_) | InvokeTransaction::V3(_) => {
+                if Some(tx.tx.sender_address().to_felt()) != outside_execution_executor {
+                    return None;
+                }
+                outside_execution_end(&tx.tx.calldata().0)
+            }
+        }
+    }
+}
+
+/// `execute_before` of the outside execution submitted by the executor
+/// account, from the calldata of its `__execute__`. The executor only submits
+/// a single call to `execute_from_outside` on the user account: `[1, account,
+/// selector, calldata_len, caller, nonce, execute_after, execute_before,
+/// calls...]`.
+fn outside_execution_end(calldata: &[Felt]) -> Option<SystemTime> {
+    let [n_calls, _account, selector, calldata_len, rest @ ..] = calldata else { return None };
+    if *n_calls != Felt::ONE
+        || !EXECUTE_FROM_OUTSIDE_SELECTORS.contains(selector)
+        || felt_to_u64(calldata_len).ok()? != rest.len() as u64
+    {
+        return None;
+    }
+    let [_caller, _nonce, _execute_after, execute_before, ..] = rest else { return None };
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(felt_to_u64(execute_before).ok()?))
 }
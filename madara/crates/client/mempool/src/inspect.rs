@@ -0,0 +1,90 @@
+//! Read-only views of the mempool content, used to debug transactions which are not being included, such as
+//! transactions waiting on a nonce which never arrives.
+
+use crate::{Mempool, MempoolInner, MempoolTransaction};
+use blockifier::transaction::transaction_types::TransactionType;
+use mp_convert::ToFelt;
+use starknet_types_core::felt::Felt;
+use std::time::SystemTime;
+
+/// Number of transactions in the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolStatus {
+    /// Transactions which can be included in the next block.
+    pub ready: usize,
+    /// Transactions waiting for a transaction with a lower nonce from the same account.
+    pub pending: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MempoolTxInfo {
+    pub tx_hash: Felt,
+    pub nonce: Felt,
+    pub tx_type: TransactionType,
+    pub ready: bool,
+    pub arrived_at: SystemTime,
+}
+
+/// The transactions of an account in the mempool, by nonce.
+#[derive(Debug, Clone)]
+pub struct MempoolAccountTxs {
+    pub contract_address: Felt,
+    pub transactions: Vec<MempoolTxInfo>,
+}
+
+impl MempoolInner {
+    pub fn status(&self) -> MempoolStatus {
+        MempoolStatus {
+            ready: self.tx_intent_queue_ready.len(),
+            pending: self.tx_intent_queue_pending_by_timestamp.len(),
+        }
+    }
+
+    /// The transactions of at most `max_accounts` accounts, in the order of their address, starting with the first
+    /// account whose address is greater or equal to `start_at`.
+    pub fn accounts(&self, start_at: Felt, max_accounts: usize) -> Vec<MempoolAccountTxs> {
+        self.nonce_mapping
+            .range(start_at..)
+            .take(max_accounts)
+            .map(|(&contract_address, nonce_mapping)| {
+                let transactions = nonce_mapping
+                    .transactions
+                    .values()
+                    .map(|mempool_tx| self.tx_info(contract_address, mempool_tx))
+                    .collect();
+                MempoolAccountTxs { contract_address, transactions }
+            })
+            .collect()
+    }
+
+    /// Whether the transaction with hash `tx_hash` is in the mempool.
+    pub fn contains_tx(&self, tx_hash: &Felt) -> bool {
+        self.tx_hashes.contains(tx_hash)
+    }
+
+    fn tx_info(&self, contract_address: Felt, mempool_tx: &MempoolTransaction) -> MempoolTxInfo {
+        MempoolTxInfo {
+            tx_hash: mempool_tx.tx_hash().to_felt(),
+            nonce: mempool_tx.nonce.to_felt(),
+            tx_type: mempool_tx.tx.tx_type(),
+            ready: self.nonce_is_ready(contract_address, mempool_tx.nonce),
+            arrived_at: mempool_tx.arrived_at,
+        }
+    }
+}
+
+impl Mempool {
+    pub fn status(&self) -> MempoolStatus {
+        self.inner.read().expect("Poisoned lock").status()
+    }
+
+    /// See [`MempoolInner::accounts`].
+    pub fn accounts(&self, start_at: Felt, max_accounts: usize) -> Vec<MempoolAccountTxs> {
+        self.inner.read().expect("Poisoned lock").accounts(start_at, max_accounts)
+    }
+
+    /// See [`MempoolInner::contains_tx`].
+    pub fn contains_tx(&self, tx_hash: &Felt) -> bool {
+        self.inner.read().expect("Poisoned lock").contains_tx(tx_hash)
+    }
+}
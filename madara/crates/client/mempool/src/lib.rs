@@ -5,7 +5,8 @@ use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transactions::{
     DeclareTransaction, DeployAccountTransaction, InvokeTransaction, L1HandlerTransaction as BL1HandlerTransaction,
 };
-use header::make_pending_header;
+use custom_transaction::{CustomTransactionHandler, CustomTransactionRejection, NoopCustomTransactionHandler};
+use header::{make_pending_header, next_block_gas_prices};
 use mc_db::db_block_id::DbBlockId;
 use mc_db::mempool_db::{DbMempoolTxInfoDecoder, NonceInfo};
 use mc_db::{MadaraBackend, MadaraStorageError};
@@ -16,13 +17,16 @@ use mp_class::ConvertedClass;
 use mp_convert::ToFelt;
 use mp_rpc::{
     AddInvokeTransactionResult, BroadcastedDeclareTxn, BroadcastedDeployAccountTxn, BroadcastedInvokeTxn,
-    BroadcastedTxn, ClassAndTxnHash, ContractAndTxnHash,
+    BroadcastedTxn, ClassAndTxnHash, ContractAndTxnHash, CustomInvokeTxn,
 };
 use mp_transactions::BroadcastedDeclareTransactionV0;
 use mp_transactions::BroadcastedTransactionExt;
+use mp_transactions::CustomInvokeTransaction;
 use mp_transactions::L1HandlerTransaction;
 use mp_transactions::L1HandlerTransactionResult;
 use mp_transactions::ToBlockifierError;
+use mp_transactions::TransactionWithHash;
+use paymaster::{NoopPaymasterPolicy, PaymasterPolicy, PaymasterRejection};
 use starknet_api::core::{ContractAddress, Nonce};
 use starknet_api::transaction::TransactionHash;
 use starknet_api::StarknetApiError;
@@ -37,10 +41,13 @@ use tx::saved_to_blockifier_tx;
 pub use l1::MockL1DataProvider;
 pub use l1::{GasPriceProvider, L1DataProvider};
 
+pub mod custom_transaction;
 pub mod header;
 mod inner;
+pub mod inspect;
 mod l1;
 pub mod metrics;
+pub mod paymaster;
 mod tx;
 
 pub use inner::*;
@@ -59,6 +66,12 @@ pub enum MempoolError {
     StarknetApi(#[from] StarknetApiError),
     #[error("Preprocessing transaction: {0:#}")]
     BroadcastedToBlockifier(#[from] ToBlockifierError),
+    #[error(transparent)]
+    Paymaster(#[from] PaymasterRejection),
+    #[error("Unsupported transaction version {0:#x}")]
+    UnsupportedTxnVersion(Felt),
+    #[error(transparent)]
+    CustomTransaction(#[from] CustomTransactionRejection),
 }
 impl MempoolError {
     pub fn is_internal(&self) -> bool {
@@ -87,6 +100,8 @@ pub trait MempoolProvider: Send + Sync {
         paid_fees_on_l1: u128,
     ) -> Result<L1HandlerTransactionResult, MempoolError>;
     fn txs_take_chunk(&self, dest: &mut VecDeque<MempoolTransaction>, n: usize);
+    /// Returns the next `n` transactions [`MempoolProvider::txs_take_chunk`] would take, without taking them.
+    fn txs_peek_chunk(&self, n: usize) -> Vec<MempoolTransaction>;
     fn tx_take(&mut self) -> Option<MempoolTransaction>;
     fn tx_mark_included(&self, contract_address: &Felt);
     fn txs_re_add(
@@ -95,6 +110,7 @@ pub trait MempoolProvider: Send + Sync {
         consumed_txs: Vec<MempoolTransaction>,
     ) -> Result<(), MempoolError>;
     fn chain_id(&self) -> Felt;
+    fn paymaster_policy(&self) -> Arc<dyn PaymasterPolicy>;
 }
 
 pub struct Mempool {
@@ -103,6 +119,10 @@ pub struct Mempool {
     inner: RwLock<MempoolInner>,
     metrics: MempoolMetrics,
     nonce_cache: RwLock<BTreeMap<Felt, Nonce>>,
+    paymaster_policy: Arc<dyn PaymasterPolicy>,
+    custom_transaction_handler: Arc<dyn CustomTransactionHandler>,
+    sender_expired_tx: tokio::sync::broadcast::Sender<Felt>,
+    sender_accepted_tx: tokio::sync::broadcast::Sender<TransactionWithHash>,
 }
 
 impl Mempool {
@@ -113,6 +133,61 @@ impl Mempool {
             inner: RwLock::new(MempoolInner::new(limits)),
             metrics: MempoolMetrics::register(),
             nonce_cache: RwLock::new(BTreeMap::new()),
+            paymaster_policy: Arc::new(NoopPaymasterPolicy),
+            custom_transaction_handler: Arc::new(NoopCustomTransactionHandler),
+            sender_expired_tx: tokio::sync::broadcast::channel(100).0,
+            sender_accepted_tx: tokio::sync::broadcast::channel(100).0,
+        }
+    }
+
+    /// Replaces the default [`NoopPaymasterPolicy`].
+    pub fn with_paymaster_policy(mut self, paymaster_policy: Arc<dyn PaymasterPolicy>) -> Self {
+        self.paymaster_policy = paymaster_policy;
+        self
+    }
+
+    /// Replaces the default [`NoopCustomTransactionHandler`].
+    pub fn with_custom_transaction_handler(
+        mut self,
+        custom_transaction_handler: Arc<dyn CustomTransactionHandler>,
+    ) -> Self {
+        self.custom_transaction_handler = custom_transaction_handler;
+        self
+    }
+
+    /// Subscribes to the hashes of the transactions removed from the mempool because their age exceeded
+    /// [`MempoolLimits::max_age`] or their validity window ended, see [`MempoolTransaction::valid_until`].
+    pub fn subscribe_expired_txs(&self) -> tokio::sync::broadcast::Receiver<Felt> {
+        self.sender_expired_tx.subscribe()
+    }
+
+    /// Subscribes to the transactions accepted into the mempool.
+    pub fn subscribe_accepted_txs(&self) -> tokio::sync::broadcast::Receiver<TransactionWithHash> {
+        self.sender_accepted_tx.subscribe()
+    }
+
+    /// Gas prices the next block will be opened with: the latest prices of the L1 data provider, unless they are
+    /// overridden on a devnet.
+    pub fn next_block_gas_prices(&self) -> mp_block::header::GasPrices {
+        next_block_gas_prices(&self.backend, self.l1_data_provider.as_ref())
+    }
+
+    /// Removes the expired transactions from the database and notifies the subscribers. This must be called without
+    /// holding the inner mempool lock.
+    fn handle_expired_txs(&self, expired: Vec<TransactionHash>) {
+        if expired.is_empty() {
+            return;
+        }
+        self.metrics.expired_transaction_counter.add(expired.len() as u64, &[]);
+        for tx_hash in expired {
+            let tx_hash = tx_hash.to_felt();
+            tracing::debug!("Transaction {tx_hash:#x} expired from the mempool");
+            if let Err(err) = self.backend.remove_mempool_transaction(&tx_hash) {
+                tracing::warn!("Could not remove expired mempool transaction {tx_hash:#x} from db: {err:#}");
+            }
+            if self.sender_expired_tx.receiver_count() > 0 {
+                let _ = self.sender_expired_tx.send(tx_hash);
+            }
         }
     }
 
@@ -125,7 +200,13 @@ impl Mempool {
 
             if let Err(err) = self.accept_tx(tx, converted_class, arrived_at, nonce_readiness) {
                 match err {
-                    MempoolError::InnerMempool(TxInsertionError::Limit(MempoolLimitReached::Age { .. })) => {} // do nothing
+                    MempoolError::InnerMempool(TxInsertionError::Limit(
+                        MempoolLimitReached::Age { .. } | MempoolLimitReached::ValidityWindow { .. },
+                    )) => {
+                        self.backend
+                            .remove_mempool_transaction(&tx_hash)
+                            .context("Removing expired mempool transaction")?;
+                    }
                     err => tracing::warn!("Could not re-add mempool transaction from db: {err:#}"),
                 }
             }
@@ -152,7 +233,7 @@ impl Mempool {
                 .get_block_hash(&BlockId::Tag(BlockTag::Latest))?
                 .unwrap_or(/* genesis block's parent hash */ Felt::ZERO);
             MadaraPendingBlockInfo::new(
-                make_pending_header(parent_block_hash, self.backend.chain_config(), self.l1_data_provider.as_ref()),
+                make_pending_header(parent_block_hash, &self.backend, self.l1_data_provider.as_ref()),
                 vec![],
             )
             .into()
@@ -175,6 +256,10 @@ impl Mempool {
         let tx_hash = tx_hash(&tx).to_felt();
         tracing::debug!("Mempool verify tx_hash={:#x}", tx_hash);
 
+        if matches!(tx, Transaction::AccountTransaction(_)) && !is_only_query(&tx) {
+            self.paymaster_policy.validate(&TransactionWithHash::from(clone_transaction(&tx)))?;
+        }
+
         // Perform validations
         let exec_context = ExecutionContext::new_at_block_end(Arc::clone(&self.backend), &pending_block_info)?;
         let mut validator = exec_context.tx_validator();
@@ -192,23 +277,45 @@ impl Mempool {
             // this transaction?
             self.backend.save_mempool_transaction(&saved_tx, tx_hash, &converted_class, &nonce_info)?;
 
+            let accepted_tx = (self.sender_accepted_tx.receiver_count() > 0)
+                .then(|| TransactionWithHash::from(clone_transaction(&tx)));
+
             // Add it to the inner mempool
             let force = false;
             let nonce = nonce_info.nonce;
             let nonce_next = nonce_info.nonce_next;
-            self.inner.write().expect("Poisoned lock").insert_tx(
+            let mut inner = self.inner.write().expect("Poisoned lock");
+            let res = inner.insert_tx(
                 MempoolTransaction { tx, arrived_at, converted_class, nonce, nonce_next },
                 force,
                 true,
                 nonce_info,
-            )?;
+            );
+            let expired = inner.take_expired_txs();
+            drop(inner);
+            self.handle_expired_txs(expired);
+            res?;
 
             self.metrics.accepted_transaction_counter.add(1, &[]);
+            if let Some(accepted_tx) = accepted_tx {
+                let _ = self.sender_accepted_tx.send(accepted_tx);
+            }
         }
 
         Ok(())
     }
 
+    /// Turns a transaction with a custom version into the Starknet transaction executing it, see
+    /// [`custom_transaction`].
+    fn dispatch_custom_transaction(&self, tx: CustomInvokeTxn) -> Result<BroadcastedInvokeTxn, MempoolError> {
+        if !self.backend.chain_config().custom_transaction_versions.contains(&tx.version) {
+            return Err(MempoolError::UnsupportedTxnVersion(tx.version));
+        }
+        let tx = CustomInvokeTransaction::from(tx);
+        let tx_hash = tx.compute_hash(self.chain_id());
+        Ok(self.custom_transaction_handler.dispatch(tx, tx_hash)?)
+    }
+
     #[cfg(any(test, feature = "testing"))]
     pub fn is_empty(&self) -> bool {
         self.inner.read().expect("Poisoned lock").is_empty()
@@ -304,12 +411,18 @@ fn deployed_contract_address(tx: &Transaction) -> Option<Felt> {
 impl MempoolProvider for Mempool {
     #[tracing::instrument(skip(self), fields(module = "Mempool"))]
     fn tx_accept_invoke(&self, tx: BroadcastedInvokeTxn) -> Result<AddInvokeTransactionResult, MempoolError> {
+        let tx = match tx {
+            BroadcastedInvokeTxn::Custom(tx) => self.dispatch_custom_transaction(tx)?,
+            tx => tx,
+        };
         let nonce_info = match &tx {
             BroadcastedInvokeTxn::V1(ref tx) => self.retrieve_nonce_info(tx.sender_address, tx.nonce)?,
             BroadcastedInvokeTxn::V3(ref tx) => self.retrieve_nonce_info(tx.sender_address, tx.nonce)?,
             BroadcastedInvokeTxn::QueryV1(ref tx) => self.retrieve_nonce_info(tx.sender_address, tx.nonce)?,
             BroadcastedInvokeTxn::QueryV3(ref tx) => self.retrieve_nonce_info(tx.sender_address, tx.nonce)?,
             BroadcastedInvokeTxn::V0(_) | &BroadcastedInvokeTxn::QueryV0(_) => NonceInfo::default(),
+            // The handler did not turn the transaction into a Starknet one.
+            BroadcastedInvokeTxn::Custom(ref tx) => return Err(MempoolError::UnsupportedTxnVersion(tx.version)),
         };
 
         let tx = BroadcastedTxn::Invoke(tx);
@@ -415,19 +528,31 @@ impl MempoolProvider for Mempool {
             let nonce_next = mempool_tx.nonce_next;
             nonce_cache.insert(contract_address, nonce_next);
         }
+
+        let expired = inner.take_expired_txs();
+        drop((inner, nonce_cache));
+        self.handle_expired_txs(expired);
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "Mempool"))]
+    fn txs_peek_chunk(&self, n: usize) -> Vec<MempoolTransaction> {
+        self.inner.read().expect("Poisoned lock").peek_next_chunk(n)
     }
 
     #[tracing::instrument(skip(self), fields(module = "Mempool"))]
     fn tx_take(&mut self) -> Option<MempoolTransaction> {
-        if let Some(mempool_tx) = self.inner.write().expect("Poisoned lock").pop_next() {
-            let contract_address = mempool_tx.contract_address().to_felt();
-            let nonce_next = mempool_tx.nonce_next;
-            self.nonce_cache.write().expect("Poisoned lock").insert(contract_address, nonce_next);
+        let mut inner = self.inner.write().expect("Poisoned lock");
+        let mempool_tx = inner.pop_next();
+        let expired = inner.take_expired_txs();
+        drop(inner);
+        self.handle_expired_txs(expired);
 
-            Some(mempool_tx)
-        } else {
-            None
-        }
+        let mempool_tx = mempool_tx?;
+        let contract_address = mempool_tx.contract_address().to_felt();
+        let nonce_next = mempool_tx.nonce_next;
+        self.nonce_cache.write().expect("Poisoned lock").insert(contract_address, nonce_next);
+
+        Some(mempool_tx)
     }
 
     #[tracing::instrument(skip(self, contract_address), fields(module = "Mempool"))]
@@ -475,6 +600,10 @@ impl MempoolProvider for Mempool {
     fn chain_id(&self) -> Felt {
         Felt::from_bytes_be_slice(format!("{}", self.backend.chain_config().chain_id).as_bytes())
     }
+
+    fn paymaster_policy(&self) -> Arc<dyn PaymasterPolicy> {
+        Arc::clone(&self.paymaster_policy)
+    }
 }
 
 pub(crate) fn is_only_query(tx: &Transaction) -> bool {
@@ -663,6 +792,39 @@ mod test {
         mempool.inner.read().expect("Poisoned lock").check_invariants();
     }
 
+    /// The gas prices of the next block are the ones of the L1 data provider, unless overridden on a devnet.
+    #[rstest::rstest]
+    fn mempool_next_block_gas_prices(backend: Arc<mc_db::MadaraBackend>, l1_data_provider: Arc<MockL1DataProvider>) {
+        let mempool = Mempool::new(Arc::clone(&backend), l1_data_provider, MempoolLimits::for_testing());
+        assert_eq!(mempool.next_block_gas_prices(), mp_block::header::GasPrices::default());
+
+        let gas_prices =
+            mp_block::header::GasPrices { eth_l1_gas_price: 3, strk_l1_gas_price: 5, ..Default::default() };
+        backend.enable_devnet_control();
+        backend.set_next_block_gas_prices(Some(gas_prices.clone()));
+        assert_eq!(mempool.next_block_gas_prices(), gas_prices);
+    }
+
+    /// This test makes sure that transactions accepted into the mempool are
+    /// notified to the subscribers.
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_notify_accepted_tx(
+        backend: Arc<mc_db::MadaraBackend>,
+        l1_data_provider: Arc<MockL1DataProvider>,
+        tx_account_v0_valid: blockifier::transaction::transaction_execution::Transaction,
+    ) {
+        let mempool = Mempool::new(backend, l1_data_provider, MempoolLimits::for_testing());
+        let mut accepted = mempool.subscribe_accepted_txs();
+
+        let tx_hash = tx_hash(&tx_account_v0_valid).to_felt();
+        let result = mempool.accept_tx(tx_account_v0_valid, None, ArrivedAtTimestamp::now(), NonceInfo::default());
+        assert_matches::assert_matches!(result, Ok(()));
+
+        assert_eq!(accepted.try_recv().map(|tx| tx.hash), Ok(tx_hash));
+        assert!(accepted.try_recv().is_err());
+    }
+
     /// This test checks if a ready transaction is indeed inserted into the
     /// ready queue.
     #[rstest::rstest]
@@ -708,6 +870,82 @@ mod test {
         mempool.inner.read().expect("Poisoned lock").check_invariants();
     }
 
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_accept_tx_fail_paymaster(
+        backend: Arc<mc_db::MadaraBackend>,
+        l1_data_provider: Arc<MockL1DataProvider>,
+        tx_account_v0_valid: blockifier::transaction::transaction_execution::Transaction,
+        tx_l1_handler_valid: blockifier::transaction::transaction_execution::Transaction,
+    ) {
+        struct RejectAll;
+        impl PaymasterPolicy for RejectAll {
+            fn validate(&self, tx: &TransactionWithHash) -> Result<(), PaymasterRejection> {
+                Err(PaymasterRejection::new(format!("Transaction {:#x} is not sponsored", tx.hash)))
+            }
+        }
+
+        let mempool = Mempool::new(backend, l1_data_provider, MempoolLimits::for_testing())
+            .with_paymaster_policy(Arc::new(RejectAll));
+        let result = mempool.accept_tx(tx_account_v0_valid, None, ArrivedAtTimestamp::now(), NonceInfo::default());
+        assert_matches::assert_matches!(result, Err(crate::MempoolError::Paymaster(_)));
+
+        // L1 handler transactions are not subject to the paymaster policy.
+        let result = mempool.accept_tx(tx_l1_handler_valid, None, ArrivedAtTimestamp::now(), NonceInfo::default());
+        assert_matches::assert_matches!(result, Ok(()));
+
+        mempool.inner.read().expect("Poisoned lock").check_invariants();
+    }
+
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_accept_custom_version(l1_data_provider: Arc<MockL1DataProvider>) {
+        struct RejectAll;
+        impl CustomTransactionHandler for RejectAll {
+            fn dispatch(
+                &self,
+                _tx: CustomInvokeTransaction,
+                tx_hash: Felt,
+            ) -> Result<BroadcastedInvokeTxn, CustomTransactionRejection> {
+                Err(CustomTransactionRejection::new(format!("Transaction {tx_hash:#x} rejected")))
+            }
+        }
+
+        let chain_config = mp_chain_config::ChainConfig {
+            custom_transaction_versions: vec![Felt::from(0x100)],
+            ..mp_chain_config::ChainConfig::madara_test()
+        };
+        let backend = mc_db::MadaraBackend::open_for_testing(Arc::new(chain_config));
+        let tx = |version| {
+            BroadcastedInvokeTxn::Custom(CustomInvokeTxn {
+                version,
+                transaction: mp_transactions::InvokeTransactionV3::default().into(),
+            })
+        };
+
+        // Custom versions are rejected unless a handler is registered.
+        let mempool = Mempool::new(Arc::clone(&backend), l1_data_provider.clone(), MempoolLimits::for_testing());
+        assert_matches::assert_matches!(
+            mempool.tx_accept_invoke(tx(Felt::from(0x100))),
+            Err(MempoolError::CustomTransaction(_))
+        );
+
+        let mempool = Mempool::new(backend, l1_data_provider, MempoolLimits::for_testing())
+            .with_custom_transaction_handler(Arc::new(RejectAll));
+        let custom = CustomInvokeTransaction { version: Felt::from(0x100), ..Default::default() };
+        assert_matches::assert_matches!(
+            mempool.tx_accept_invoke(tx(Felt::from(0x100))),
+            Err(MempoolError::CustomTransaction(rejection))
+                if rejection.reason == format!("Transaction {:#x} rejected", custom.compute_hash(mempool.chain_id()))
+        );
+        // Versions which are not enabled in the chain config never reach the handler.
+        assert_matches::assert_matches!(
+            mempool.tx_accept_invoke(tx(Felt::from(0x101))),
+            Err(MempoolError::UnsupportedTxnVersion(version)) if version == Felt::from(0x101)
+        );
+        assert!(mempool.is_empty());
+    }
+
     /// This test makes sure that taking a transaction from the mempool works as
     /// intended.
     #[rstest::rstest]
@@ -730,6 +968,102 @@ mod test {
         mempool.inner.read().expect("Poisoned lock").check_invariants();
     }
 
+    /// This test makes sure that peeking at transactions returns them in the
+    /// order in which they are taken, and leaves the mempool untouched.
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_peek_tx_pass(
+        backend: Arc<mc_db::MadaraBackend>,
+        l1_data_provider: Arc<MockL1DataProvider>,
+        #[from(tx_account_v0_valid)] tx_pending: blockifier::transaction::transaction_execution::Transaction,
+        #[from(tx_account_v0_valid)] tx_ready: blockifier::transaction::transaction_execution::Transaction,
+        #[from(tx_account_v0_valid)]
+        #[with(Felt::ONE)]
+        tx_other: blockifier::transaction::transaction_execution::Transaction,
+    ) {
+        let mut mempool = Mempool::new(backend, l1_data_provider, MempoolLimits::for_testing());
+
+        let nonce_info = NonceInfo::pending(Nonce(Felt::ONE), Nonce(Felt::TWO));
+        assert_matches::assert_matches!(
+            mempool.accept_tx(tx_pending, None, ArrivedAtTimestamp::now(), nonce_info),
+            Ok(())
+        );
+        let nonce_info = NonceInfo::ready(Nonce(Felt::ZERO), Nonce(Felt::ONE));
+        assert_matches::assert_matches!(
+            mempool.accept_tx(tx_other, None, ArrivedAtTimestamp::now(), nonce_info),
+            Ok(())
+        );
+        let nonce_info = NonceInfo::ready(Nonce(Felt::ZERO), Nonce(Felt::ONE));
+        assert_matches::assert_matches!(
+            mempool.accept_tx(tx_ready, None, ArrivedAtTimestamp::now(), nonce_info),
+            Ok(())
+        );
+
+        let key = |tx: &MempoolTransaction| (tx.contract_address(), tx.nonce);
+        let peeked: Vec<_> = mempool.txs_peek_chunk(10).iter().map(key).collect();
+        assert_eq!(mempool.txs_peek_chunk(10).iter().map(key).collect::<Vec<_>>(), peeked);
+        assert_eq!(mempool.txs_peek_chunk(2).iter().map(key).collect::<Vec<_>>(), peeked[..2]);
+        mempool.inner.read().expect("Poisoned lock").check_invariants();
+
+        let taken: Vec<_> = std::iter::from_fn(|| mempool.tx_take()).map(|tx| key(&tx)).collect();
+        assert_eq!(peeked.len(), 3);
+        assert_eq!(peeked, taken);
+    }
+
+    /// This test makes sure that the transactions in the mempool are listed by
+    /// account and nonce, along with their readiness.
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_inspect_accounts(
+        backend: Arc<mc_db::MadaraBackend>,
+        l1_data_provider: Arc<MockL1DataProvider>,
+        #[from(tx_account_v0_valid)] tx_pending: blockifier::transaction::transaction_execution::Transaction,
+        #[from(tx_account_v0_valid)]
+        #[with(Felt::ONE)]
+        tx_other: blockifier::transaction::transaction_execution::Transaction,
+    ) {
+        let mut mempool = Mempool::new(backend, l1_data_provider, MempoolLimits::for_testing());
+
+        let nonce_info = NonceInfo::pending(Nonce(Felt::TWO), Nonce(Felt::THREE));
+        assert_matches::assert_matches!(
+            mempool.accept_tx(tx_pending, None, ArrivedAtTimestamp::now(), nonce_info),
+            Ok(())
+        );
+        let nonce_info = NonceInfo::ready(Nonce(Felt::ZERO), Nonce(Felt::ONE));
+        assert_matches::assert_matches!(
+            mempool.accept_tx(tx_other, None, ArrivedAtTimestamp::now(), nonce_info),
+            Ok(())
+        );
+
+        assert_eq!(mempool.status(), inspect::MempoolStatus { ready: 1, pending: 1 });
+
+        let summary = |accounts: Vec<inspect::MempoolAccountTxs>| {
+            accounts
+                .into_iter()
+                .map(|account| {
+                    let txs: Vec<_> = account.transactions.iter().map(|tx| (tx.nonce, tx.ready)).collect();
+                    (account.contract_address, txs)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(mempool.accounts(Felt::ZERO, 10)),
+            [(Felt::ZERO, vec![(Felt::TWO, false)]), (Felt::ONE, vec![(Felt::ZERO, true)])]
+        );
+        assert_eq!(summary(mempool.accounts(Felt::ZERO, 1)), [(Felt::ZERO, vec![(Felt::TWO, false)])]);
+        assert_eq!(summary(mempool.accounts(Felt::ONE, 10)), [(Felt::ONE, vec![(Felt::ZERO, true)])]);
+
+        // The transactions are looked up by hash until they leave the mempool.
+        let tx_hash = mempool.accounts(Felt::ONE, 1)[0].transactions[0].tx_hash;
+        assert!(mempool.contains_tx(&tx_hash));
+        assert!(!mempool.contains_tx(&Felt::from(0x1234)));
+        let mempool_tx = mempool.tx_take().expect("Mempool should contain a ready transaction");
+        assert_eq!(mempool_tx.tx_hash().to_felt(), tx_hash);
+        assert!(!mempool.contains_tx(&tx_hash));
+
+        mempool.inner.read().expect("Poisoned lock").check_invariants();
+    }
+
     /// This test makes sure that all deploy account transactions inserted into
     /// [MempoolInner] are accounted for. Replacements are not taken into
     /// account.
@@ -935,6 +1269,124 @@ mod test {
         inner.check_invariants();
     }
 
+    /// This test makes sure that transactions removed from the mempool because
+    /// of their age are notified to the subscribers.
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_notify_expired_tx(
+        backend: Arc<mc_db::MadaraBackend>,
+        l1_data_provider: Arc<MockL1DataProvider>,
+        tx_account_v0_valid: blockifier::transaction::transaction_execution::Transaction,
+    ) {
+        let mut mempool = Mempool::new(
+            backend,
+            l1_data_provider,
+            MempoolLimits { max_age: Some(Duration::from_secs(3_600)), ..MempoolLimits::for_testing() },
+        );
+        let mut expired = mempool.subscribe_expired_txs();
+
+        let nonce_info = NonceInfo::ready(Nonce(Felt::ZERO), Nonce(Felt::ONE));
+        let mempool_tx = MempoolTransaction {
+            tx: tx_account_v0_valid,
+            arrived_at: ArrivedAtTimestamp::UNIX_EPOCH,
+            converted_class: None,
+            nonce: nonce_info.nonce,
+            nonce_next: nonce_info.nonce_next,
+        };
+        let tx_hash = mempool_tx.tx_hash().to_felt();
+
+        let force = true;
+        let update_limits = true;
+        let result =
+            mempool.inner.write().expect("Poisoned lock").insert_tx(mempool_tx, force, update_limits, nonce_info);
+        assert_matches::assert_matches!(result, Ok(()));
+
+        // The transaction is too old: it is dropped instead of being returned
+        assert!(mempool.tx_take().is_none());
+        assert_eq!(expired.try_recv(), Ok(tx_hash));
+        assert!(expired.try_recv().is_err());
+    }
+
+    /// This test makes sure that transactions submitting an outside execution
+    /// are removed from the mempool once its validity window ends, even when
+    /// older transactions are still valid.
+    #[rstest::rstest]
+    #[timeout(Duration::from_millis(1_000))]
+    fn mempool_remove_validity_window_ended_tx(
+        backend: Arc<mc_db::MadaraBackend>,
+        l1_data_provider: Arc<MockL1DataProvider>,
+        tx_account_v0_valid: blockifier::transaction::transaction_execution::Transaction,
+    ) {
+        let limits = MempoolLimits { outside_execution_executor: Some(Felt::ONE), ..MempoolLimits::for_testing() };
+        let mut mempool = Mempool::new(backend, l1_data_provider, limits);
+        let execute_from_outside_v2 =
+            Felt::from_hex_unchecked("0x34cc13b274446654ca3233ed2c1620d4c5d1d32fd20b47146a3371064bdc57d");
+        let calldata = |execute_before: u64| {
+            let outside_execution = [Felt::ZERO, Felt::ZERO, Felt::ZERO, Felt::from(execute_before), Felt::ZERO];
+            starknet_api::transaction::Calldata(Arc::new(
+                [&[Felt::ONE, Felt::TWO, execute_from_outside_v2, Felt::from(5)][..], &outside_execution].concat(),
+            ))
+        };
+        let tx_outside_execution_from = |sender_address: Felt, execute_before: u64, tx_hash: Felt| {
+            blockifier::transaction::transaction_execution::Transaction::AccountTransaction(
+                blockifier::transaction::account_transaction::AccountTransaction::Invoke(
+                    blockifier::transaction::transactions::InvokeTransaction {
+                        tx: starknet_api::transaction::InvokeTransaction::V1(
+                            starknet_api::transaction::InvokeTransactionV1 {
+                                sender_address: ContractAddress::try_from(sender_address).unwrap(),
+                                calldata: calldata(execute_before),
+                                ..Default::default()
+                            },
+                        ),
+                        tx_hash: TransactionHash(tx_hash),
+                        only_query: false,
+                    },
+                ),
+            )
+        };
+
+        let tx_outside_execution =
+            |execute_before: u64, tx_hash: Felt| tx_outside_execution_from(Felt::ONE, execute_before, tx_hash);
+
+        // Only the transactions of the outside execution executor have a validity window.
+        let not_executor = MempoolTransaction::new_from_blockifier_tx(
+            tx_outside_execution_from(Felt::TWO, 1, Felt::THREE),
+            ArrivedAtTimestamp::now(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(not_executor.valid_until(Some(Felt::ONE)), None);
+
+        let mut inner = mempool.inner.write().expect("Poisoned lock");
+        let force = true;
+        let update_limits = true;
+        for (tx, nonce_info) in [
+            (tx_account_v0_valid, NonceInfo::ready(Nonce(Felt::ZERO), Nonce(Felt::ONE))),
+            (tx_outside_execution(u32::MAX.into(), Felt::ONE), NonceInfo::ready(Nonce(Felt::ZERO), Nonce(Felt::ONE))),
+            (tx_outside_execution(1, Felt::TWO), NonceInfo::pending(Nonce(Felt::ONE), Nonce(Felt::TWO))),
+        ] {
+            let mempool_tx = MempoolTransaction {
+                tx,
+                arrived_at: ArrivedAtTimestamp::now(),
+                converted_class: None,
+                nonce: nonce_info.nonce,
+                nonce_next: nonce_info.nonce_next,
+            };
+            let result = inner.insert_tx(mempool_tx, force, update_limits, nonce_info);
+            assert_matches::assert_matches!(result, Ok(()));
+        }
+
+        inner.remove_age_exceeded_txs();
+        inner.check_invariants();
+        assert_eq!(inner.take_expired_txs(), vec![TransactionHash(Felt::TWO)]);
+        assert!(!inner.tx_hashes.contains(&Felt::TWO));
+        drop(inner);
+
+        assert!(mempool.tx_take().is_some());
+        assert!(mempool.tx_take().is_some());
+        assert!(mempool.tx_take().is_none());
+    }
+
     /// This test makes sure that old transactions are removed from the
     /// [mempool], whether they be represented by ready or pending intents.
     ///
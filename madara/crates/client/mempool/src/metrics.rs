@@ -4,6 +4,7 @@ use opentelemetry::{global, KeyValue};
 
 pub struct MempoolMetrics {
     pub accepted_transaction_counter: Counter<u64>,
+    pub expired_transaction_counter: Counter<u64>,
 }
 
 impl MempoolMetrics {
@@ -24,6 +25,13 @@ impl MempoolMetrics {
             "transaction".to_string(),
         );
 
-        Self { accepted_transaction_counter }
+        let expired_transaction_counter = register_counter_metric_instrument(
+            &mempool_meter,
+            "expired_transaction_count".to_string(),
+            "A counter to show transactions removed from the mempool because their age exceeded the limit".to_string(),
+            "transaction".to_string(),
+        );
+
+        Self { accepted_transaction_counter, expired_transaction_counter }
     }
 }
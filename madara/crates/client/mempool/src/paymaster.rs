@@ -0,0 +1,47 @@
+//! Sponsorship policy hooks for sequencer mode.
+//!
+//! Appchains which sponsor transactions for their users (by having them sent through a sponsor account, or by
+//! refunding them) usually need to restrict what is sponsored: which contracts can be called, by whom, and up to which
+//! amount. A [`PaymasterPolicy`] is consulted by the mempool when a transaction is received, and notified by block
+//! production once the transaction has been executed, with the fee which was actually charged.
+//!
+//! The policy never touches the state: the fee is always charged to the sender of the transaction by the execution,
+//! so that re-executing the block gives the same state diff. Sponsorship itself happens on chain, with a sponsor
+//! account sending the transaction or a fee token transfer executed in the block. The policy decides which
+//! transactions are admitted, and keeps track of what was spent.
+
+use mp_receipt::TransactionReceipt;
+use mp_transactions::TransactionWithHash;
+use std::borrow::Cow;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("Rejected by the paymaster policy: {reason}")]
+pub struct PaymasterRejection {
+    pub reason: Cow<'static, str>,
+}
+
+impl PaymasterRejection {
+    pub fn new(reason: impl Into<Cow<'static, str>>) -> Self {
+        Self { reason: reason.into() }
+    }
+}
+
+pub trait PaymasterPolicy: Send + Sync {
+    /// Called by the mempool when an account transaction is received, before it is validated. Returning an error
+    /// rejects the transaction.
+    ///
+    /// This is not called for the transactions only used for fee estimation and simulation.
+    fn validate(&self, _tx: &TransactionWithHash) -> Result<(), PaymasterRejection> {
+        Ok(())
+    }
+
+    /// Called by block production once an account transaction has been executed and added to the pending block. The
+    /// receipt holds the fee which was actually charged, and whether the transaction was reverted.
+    fn on_executed(&self, _tx: &TransactionWithHash, _receipt: &TransactionReceipt) {}
+}
+
+/// The default policy: nothing is sponsored, all transactions are accepted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopPaymasterPolicy;
+
+impl PaymasterPolicy for NoopPaymasterPolicy {}
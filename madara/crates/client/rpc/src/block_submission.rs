@@ -0,0 +1,144 @@
+//! Submission of blocks built outside of the node.
+//!
+//! Some architectures build blocks in a separate process, or with a separate sequencer implementation, while Madara
+//! remains the canonical storage and RPC node. The block builder submits every complete block (header, transactions,
+//! receipts, state diff and declared classes) with `madara_submitFullBlock`, and the node runs it through the whole
+//! [`BlockImporter`] pipeline before appending it: the commitments, class hashes, global state root and block hash are
+//! verified as for a synced block, and the signature too when sequencer public keys are configured.
+//!
+//! Submissions must carry the key configured on the node. They are rejected while the sync or the block production
+//! are running, as the node would then have two sources of blocks: these services cannot be started from the admin RPC
+//! while a block is being submitted. A block is flushed to disk before its submission is acknowledged.
+
+use crate::errors::StarknetRpcApiError;
+use crate::utils::ResultExt;
+use crate::versions::admin::v0_1_0::SubmittedBlock;
+use crate::Starknet;
+use jsonrpsee::core::RpcResult;
+use mc_block_import::{BlockImporter, BlockValidationContext, UnverifiedFullBlock};
+use mp_utils::service::MadaraServiceId;
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Services which are a source of blocks, and cannot run while blocks are submitted.
+const BLOCK_SOURCES: [MadaraServiceId; 2] = [MadaraServiceId::L2Sync, MadaraServiceId::BlockProduction];
+
+pub struct BlockSubmission {
+    importer: Arc<BlockImporter>,
+    key: String,
+    /// Held while a block is submitted, and while a source of blocks is started.
+    lock: Mutex<()>,
+}
+
+impl BlockSubmission {
+    pub fn new(importer: Arc<BlockImporter>, key: String) -> Self {
+        Self { importer, key, lock: Mutex::new(()) }
+    }
+
+    /// Waits for the submission in progress, if any, when one of `services` is a source of blocks. The returned guard
+    /// must be held until the services are started.
+    pub(crate) async fn lock_for_services(&self, services: &[MadaraServiceId]) -> Option<MutexGuard<'_, ()>> {
+        if services.iter().any(|service| BLOCK_SOURCES.contains(service)) {
+            Some(self.lock.lock().await)
+        } else {
+            None
+        }
+    }
+
+    /// Compares in constant time, so that the key cannot be guessed from the response time.
+    fn is_authorized(&self, key: &str) -> bool {
+        let (expected, key) = (self.key.as_bytes(), key.as_bytes());
+        expected.len() == key.len() && expected.iter().zip(key).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+
+    pub async fn submit(
+        &self,
+        starknet: &Starknet,
+        block: UnverifiedFullBlock,
+        key: &str,
+    ) -> RpcResult<SubmittedBlock> {
+        if !self.is_authorized(key) {
+            return Err(StarknetRpcApiError::Unauthorized.into());
+        }
+        let _lock = self.lock.lock().await;
+        if BLOCK_SOURCES.into_iter().any(|service| starknet.ctx.service_status(service).is_on()) {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Blocks cannot be submitted while the sync or the block production are running".to_string(),
+            }
+            .into());
+        }
+
+        let validation = BlockValidationContext::new(starknet.backend.chain_config().chain_id.clone());
+        let result = match self.importer.add_block(block, validation).await {
+            Err(err) if !err.is_internal() => {
+                return Err(StarknetRpcApiError::ErrUnexpectedError { data: format!("Invalid block: {err}") }.into())
+            }
+            result => result.or_internal_server_error("Importing submitted block")?,
+        };
+        let backend = Arc::clone(&starknet.backend);
+        tokio::task::spawn_blocking(move || backend.flush())
+            .await
+            .or_internal_server_error("Flushing submitted block")?
+            .or_internal_server_error("Flushing submitted block")?;
+
+        tracing::info!(
+            "📥 Imported submitted block #{} with hash {:#x}",
+            result.header.block_number,
+            result.block_hash
+        );
+        Ok(SubmittedBlock { block_number: result.header.block_number, block_hash: result.block_hash })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_block_import::UnverifiedHeader;
+    use mc_db::MadaraBackend;
+    use mp_utils::service::{MadaraServiceMask, ServiceContext};
+    use rstest::rstest;
+    use starknet_types_core::felt::Felt;
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_submit_full_block(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, mut rpc) = rpc_test_setup;
+        let importer = Arc::new(BlockImporter::new(Arc::clone(&backend), None).unwrap());
+        let submission = BlockSubmission::new(importer, "secret".to_string());
+        let block = UnverifiedFullBlock {
+            unverified_block_number: Some(0),
+            header: UnverifiedHeader { parent_block_hash: Some(Felt::ZERO), ..Default::default() },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            submission.submit(&rpc, block.clone(), "wrong").await.unwrap_err(),
+            StarknetRpcApiError::Unauthorized.into()
+        );
+        // All the services are running in the test context.
+        assert!(submission.submit(&rpc, block.clone(), "secret").await.is_err());
+
+        rpc.ctx = ServiceContext::new_with_services(Arc::new(MadaraServiceMask::default()));
+        let submitted = submission.submit(&rpc, block.clone(), "secret").await.unwrap();
+        assert_eq!(submitted.block_number, 0);
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+        assert_eq!(backend.get_block_hash(&mp_block::BlockId::Number(0)).unwrap(), Some(submitted.block_hash));
+
+        // The block is verified: block 0 cannot be imported a second time.
+        assert!(submission.submit(&rpc, block, "secret").await.is_err());
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_submission_blocks_service_start(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, _) = rpc_test_setup;
+        let importer = Arc::new(BlockImporter::new(backend, None).unwrap());
+        let submission = BlockSubmission::new(importer, "secret".to_string());
+
+        assert!(submission.lock_for_services(&[MadaraServiceId::RpcUser]).await.is_none());
+        let _submitting = submission.lock.lock().await;
+        let start = submission.lock_for_services(&[MadaraServiceId::L2Sync]);
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(50), start).await.is_err());
+    }
+}
@@ -2,3 +2,8 @@
 pub const MAX_EVENTS_KEYS: usize = 100;
 /// Maximum number of events that can be fetched in a single chunk for the `get_events` RPC.
 pub const MAX_EVENTS_CHUNK_SIZE: usize = 1000;
+/// Default maximum size in bytes of a page of events returned by the `get_events` RPC, see
+/// [`crate::Starknet::with_max_page_bytes`].
+pub const DEFAULT_MAX_PAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Number of confirmed block state updates kept in memory for the `get_state_update` RPC.
+pub const STATE_UPDATE_CACHE_SIZE: usize = 256;
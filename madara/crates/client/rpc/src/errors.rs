@@ -106,6 +106,14 @@ pub enum StarknetRpcApiError {
     ProofLimitExceeded { kind: StorageProofLimit, limit: usize, got: usize },
     #[error("Cannot create a storage proof for a block that old")]
     CannotMakeProofOnOldBlock,
+    #[error("The node is overloaded by the sync, retry later")]
+    Overloaded { retry_after_secs: u64 },
+    #[error("The state at this block is not available")]
+    StatePruned { block_n: u64, oldest_block_n: u64 },
+    #[error("Invalid authentication key")]
+    Unauthorized,
+    #[error("Invalid params")]
+    InvalidParams { data: String },
 }
 
 impl From<&StarknetRpcApiError> for i32 {
@@ -144,6 +152,10 @@ impl From<&StarknetRpcApiError> for i32 {
             StarknetRpcApiError::UnimplementedMethod => 501,
             StarknetRpcApiError::ProofLimitExceeded { .. } => 10000,
             StarknetRpcApiError::CannotMakeProofOnOldBlock => 10001,
+            StarknetRpcApiError::Overloaded { .. } => 10002,
+            StarknetRpcApiError::StatePruned { .. } => 10003,
+            StarknetRpcApiError::Unauthorized => 10004,
+            StarknetRpcApiError::InvalidParams { .. } => jsonrpsee::types::error::INVALID_PARAMS_CODE,
         }
     }
 }
@@ -151,7 +163,9 @@ impl From<&StarknetRpcApiError> for i32 {
 impl StarknetRpcApiError {
     pub fn data(&self) -> Option<serde_json::Value> {
         match self {
-            StarknetRpcApiError::ErrUnexpectedError { data } => Some(json!(data)),
+            StarknetRpcApiError::ErrUnexpectedError { data } | StarknetRpcApiError::InvalidParams { data } => {
+                Some(json!(data))
+            }
             StarknetRpcApiError::ValidationFailure { error } => Some(json!(error)),
             StarknetRpcApiError::FailedToReceiveTxn { err } => err.as_ref().map(|err| json!(err)),
             StarknetRpcApiError::TxnExecutionError { tx_index, error } => Some(json!({
@@ -161,6 +175,10 @@ impl StarknetRpcApiError {
             StarknetRpcApiError::ProofLimitExceeded { kind, limit, got } => {
                 Some(json!({ "kind": kind, "limit": limit, "got": got }))
             }
+            StarknetRpcApiError::Overloaded { retry_after_secs } => Some(json!({ "retry_after": retry_after_secs })),
+            StarknetRpcApiError::StatePruned { block_n, oldest_block_n } => {
+                Some(json!({ "block_number": block_n, "oldest_block_number": oldest_block_n }))
+            }
             _ => None,
         }
     }
@@ -218,8 +236,13 @@ impl From<StarknetError> for StarknetRpcApiError {
 }
 
 impl From<MadaraStorageError> for StarknetRpcApiError {
-    fn from(_: MadaraStorageError) -> Self {
-        StarknetRpcApiError::ErrUnexpectedError { data: "DB error".to_string() }
+    fn from(err: MadaraStorageError) -> Self {
+        match err {
+            MadaraStorageError::StatePruned { block_n, oldest_block_n } => {
+                StarknetRpcApiError::StatePruned { block_n, oldest_block_n }
+            }
+            _ => StarknetRpcApiError::ErrUnexpectedError { data: "DB error".to_string() },
+        }
     }
 }
 
@@ -233,7 +256,10 @@ impl From<UserTransactionConversionError> for StarknetRpcApiError {
     fn from(err: UserTransactionConversionError) -> Self {
         match err {
             UserTransactionConversionError::ContractClassDecodeError(_) => StarknetRpcApiError::InvalidContractClass,
-            UserTransactionConversionError::UnsupportedQueryTransaction => StarknetRpcApiError::UnsupportedTxnVersion,
+            UserTransactionConversionError::UnsupportedQueryTransaction
+            | UserTransactionConversionError::UnsupportedCustomTransaction(_) => {
+                StarknetRpcApiError::UnsupportedTxnVersion
+            }
         }
     }
 }
@@ -241,10 +267,12 @@ impl From<UserTransactionConversionError> for StarknetRpcApiError {
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[derive(Debug)]
 pub enum StarknetWsApiError {
+    TooManyAddressesInFilter,
     TooManyBlocksBack,
     NoBlocks,
     BlockNotFound,
     Pending,
+    TxnHashNotFound,
     Internal,
 }
 
@@ -252,21 +280,25 @@ impl StarknetWsApiError {
     #[inline]
     fn code(&self) -> i32 {
         match self {
+            Self::TooManyAddressesInFilter => 67,
             Self::TooManyBlocksBack => 68,
             Self::NoBlocks => 32,
             Self::BlockNotFound => 24,
             Self::Pending => 69,
+            Self::TxnHashNotFound => 29,
             Self::Internal => jsonrpsee::types::error::INTERNAL_ERROR_CODE,
         }
     }
     #[inline]
     fn message(&self) -> &str {
         match self {
+            Self::TooManyAddressesInFilter => "Too many addresses in filter sender_address filter",
             Self::TooManyBlocksBack => "Cannot go back more than 1024 blocks",
             Self::NoBlocks => "There are no blocks",
             Self::BlockNotFound => "Block not found",
             // See https://github.com/starkware-libs/starknet-specs/pull/237
             Self::Pending => "The pending block is not supported on this method call",
+            Self::TxnHashNotFound => "Transaction hash not found",
             Self::Internal => jsonrpsee::types::error::INTERNAL_ERROR_MSG,
         }
     }
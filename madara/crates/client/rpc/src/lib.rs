@@ -2,24 +2,32 @@
 //!
 //! It uses the madara client and backend in order to answer queries.
 
+mod block_submission;
 mod constants;
 mod errors;
+pub mod outside_execution;
 pub mod providers;
+mod state_update_cache;
 #[cfg(test)]
 pub mod test_utils;
 mod types;
 pub mod utils;
 pub mod versions;
 
+use block_submission::BlockSubmission;
 use jsonrpsee::RpcModule;
+use mc_block_import::BlockImporter;
 use mc_db::db_block_id::DbBlockIdResolvable;
 use mc_db::MadaraBackend;
+use mc_mempool::Mempool;
 use mp_block::{BlockId, BlockTag, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
 use mp_chain_config::ChainConfig;
 use mp_convert::ToFelt;
 use mp_utils::service::ServiceContext;
+use outside_execution::OutsideExecutor;
 use providers::AddTransactionProvider;
 use starknet_types_core::felt::Felt;
+use state_update_cache::StateUpdateCache;
 use std::sync::Arc;
 use utils::ResultExt;
 
@@ -48,6 +56,11 @@ pub struct Starknet {
     backend: Arc<MadaraBackend>,
     pub(crate) add_transaction_provider: Arc<dyn AddTransactionProvider>,
     storage_proof_config: StorageProofConfig,
+    pub(crate) max_page_bytes: usize,
+    pub(crate) state_update_cache: Arc<StateUpdateCache>,
+    pub(crate) outside_executor: Option<Arc<OutsideExecutor>>,
+    pub(crate) mempool: Option<Arc<Mempool>>,
+    pub(crate) block_submission: Option<Arc<BlockSubmission>>,
     pub ctx: ServiceContext,
 }
 
@@ -58,7 +71,46 @@ impl Starknet {
         storage_proof_config: StorageProofConfig,
         ctx: ServiceContext,
     ) -> Self {
-        Self { backend, add_transaction_provider, storage_proof_config, ctx }
+        Self {
+            backend,
+            add_transaction_provider,
+            storage_proof_config,
+            max_page_bytes: constants::DEFAULT_MAX_PAGE_BYTES,
+            state_update_cache: Arc::new(StateUpdateCache::new(constants::STATE_UPDATE_CACHE_SIZE)),
+            outside_executor: None,
+            mempool: None,
+            block_submission: None,
+            ctx,
+        }
+    }
+
+    /// Sets the maximum size in bytes of a page of a paginated method, such as `starknet_getEvents`. On top of the page
+    /// size requested by the client, pages are cut short with a continuation token once they reach this size. A page
+    /// always has at least one item.
+    pub fn with_max_page_bytes(mut self, max_page_bytes: usize) -> Self {
+        self.max_page_bytes = max_page_bytes;
+        self
+    }
+
+    /// Enables the submission of outside executions through this executor. The RPC servers of a node share the
+    /// executor, which keeps track of the nonce of the executor account.
+    pub fn with_outside_executor(mut self, outside_executor: Arc<OutsideExecutor>) -> Self {
+        self.outside_executor = Some(outside_executor);
+        self
+    }
+
+    /// Enables the block production dry runs against this mempool and its inspection by the txpool methods, and
+    /// notifies the pending transaction subscribers of the transactions it accepts.
+    pub fn with_mempool(mut self, mempool: Arc<Mempool>) -> Self {
+        self.mempool = Some(mempool);
+        self
+    }
+
+    /// Enables the submission of blocks built outside of the node with `madara_submitFullBlock`, authenticated with
+    /// `key`.
+    pub fn with_block_submission(mut self, importer: Arc<BlockImporter>, key: String) -> Self {
+        self.block_submission = Some(Arc::new(BlockSubmission::new(importer, key)));
+        self
     }
 
     pub fn clone_backend(&self) -> Arc<MadaraBackend> {
@@ -119,16 +171,25 @@ pub fn rpc_api_user(starknet: &Starknet) -> anyhow::Result<RpcModule<()>> {
     rpc_api.merge(versions::user::v0_7_1::StarknetWriteRpcApiV0_7_1Server::into_rpc(starknet.clone()))?;
     rpc_api.merge(versions::user::v0_7_1::StarknetTraceRpcApiV0_7_1Server::into_rpc(starknet.clone()))?;
     rpc_api.merge(versions::user::v0_8_0::StarknetWsRpcApiV0_8_0Server::into_rpc(starknet.clone()))?;
+    rpc_api.merge(versions::admin::v0_1_0::MadaraUserRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
 
     Ok(rpc_api)
 }
 
-pub fn rpc_api_admin(starknet: &Starknet) -> anyhow::Result<RpcModule<()>> {
+/// Returns the admin RpcModule. The block production and mempool namespaces are only merged when `block_production`
+/// is set, as they need the node to produce blocks.
+pub fn rpc_api_admin(starknet: &Starknet, block_production: bool) -> anyhow::Result<RpcModule<()>> {
     let mut rpc_api = RpcModule::new(());
 
     rpc_api.merge(versions::admin::v0_1_0::MadaraWriteRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
     rpc_api.merge(versions::admin::v0_1_0::MadaraStatusRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
     rpc_api.merge(versions::admin::v0_1_0::MadaraServicesRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
+    if block_production {
+        rpc_api.merge(versions::admin::v0_1_0::MadaraBlockProductionRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
+        rpc_api.merge(versions::admin::v0_1_0::MadaraMempoolRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
+    }
+    rpc_api.merge(versions::admin::v0_1_0::MadaraExplorerRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
+    rpc_api.merge(versions::admin::v0_1_0::MadaraUserRpcApiV0_1_0Server::into_rpc(starknet.clone()))?;
 
     Ok(rpc_api)
 }
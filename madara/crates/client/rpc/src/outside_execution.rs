@@ -0,0 +1,747 @@
+//! Sequencer-sponsored execution of [SNIP-9](https://github.com/starknet-io/SNIPs/blob/main/SNIPS/snip-9.md) outside
+//! executions.
+//!
+//! An outside execution is a list of calls signed off-chain by a user account. It is submitted on-chain by another
+//! account, the executor, which calls `execute_from_outside` on the user account and pays for the transaction. This
+//! lets appchains offer gasless transactions without running a separate relayer: the node wraps the outside execution
+//! into an invoke transaction from a configured executor account and adds it to its mempool.
+//!
+//! Since the executor pays for the transaction even when it reverts, the node checks the signature with the
+//! `is_valid_signature` entrypoint of the user account and the nonce with its `is_valid_outside_execution_nonce`
+//! entrypoint before submitting the transaction. A user account can be any contract, so these checks alone do not
+//! protect the executor: the fees sponsored for each user account and the fees sponsored in total are also capped by
+//! budgets over a period of time.
+
+use crate::errors::StarknetRpcApiError;
+use crate::utils::ResultExt;
+use crate::versions::user::v0_7_1::methods::read::call::call;
+use crate::Starknet;
+use jsonrpsee::core::RpcResult;
+use mp_block::{BlockId, BlockTag, BlockTimestamp, MadaraMaybePendingBlockInfo};
+use mp_rpc::{
+    AddInvokeTransactionResult, BroadcastedInvokeTxn, DaMode, FunctionCall, InvokeTxnV3, ResourceBounds,
+    ResourceBoundsMapping,
+};
+use mp_utils::crypto::ZeroingPrivateKey;
+use serde::{Deserialize, Serialize};
+use starknet_core::utils::get_selector_from_name;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// `'ANY_CALLER'` as a short string: an outside execution with this caller can be submitted by any account.
+pub const ANY_CALLER: Felt = Felt::from_hex_unchecked("0x414e595f43414c4c4552");
+
+/// `'StarkNet Message'` as a short string, the prefix of the SNIP-12 message hashes.
+const STARKNET_MESSAGE: Felt = Felt::from_hex_unchecked("0x537461726b4e6574204d657373616765");
+/// `'Account.execute_from_outside'` as a short string, the name of the SNIP-12 domain of outside executions.
+const DOMAIN_NAME: Felt = Felt::from_hex_unchecked("0x4163636f756e742e657865637574655f66726f6d5f6f757473696465");
+/// `'VALID'` as a short string, returned by `is_valid_signature`. Older accounts return `1`.
+const VALID: Felt = Felt::from_hex_unchecked("0x56414c4944");
+
+/// SNIP-12 revision 0 type hashes, used by [`OutsideExecutionVersion::V1`].
+mod type_hash_rev_0 {
+    use starknet_types_core::felt::Felt;
+
+    /// `StarkNetDomain(name:felt,version:felt,chainId:felt)`
+    pub const DOMAIN: Felt =
+        Felt::from_hex_unchecked("0x1bfc207425a47a5dfa1a50a4f5241203f50624ca5fdf5e18755765416b8e288");
+    /// `OutsideExecution(caller:felt,nonce:felt,execute_after:felt,execute_before:felt,calls_len:felt,
+    /// calls:OutsideCall*)OutsideCall(to:felt,selector:felt,calldata_len:felt,calldata:felt*)`
+    pub const OUTSIDE_EXECUTION: Felt =
+        Felt::from_hex_unchecked("0x11ff76fe3f640fa6f3d60bbd94a3b9d47141a2c96f87fdcfbeb2af1d03f7050");
+    /// `OutsideCall(to:felt,selector:felt,calldata_len:felt,calldata:felt*)`
+    pub const CALL: Felt = Felt::from_hex_unchecked("0xf00de1fccbb286f9a020ba8821ee936b1deea42a5c485c11ccdc82c8bebb3a");
+}
+
+/// SNIP-12 revision 1 type hashes, used by [`OutsideExecutionVersion::V2`].
+mod type_hash_rev_1 {
+    use starknet_types_core::felt::Felt;
+
+    /// `"StarknetDomain"("name":"shortstring","version":"shortstring","chainId":"shortstring",
+    /// "revision":"shortstring")`
+    pub const DOMAIN: Felt =
+        Felt::from_hex_unchecked("0x1ff2f602e42168014d405a94f75e8a93d640751d71d16311266e140d8b0a210");
+    /// `"OutsideExecution"("Caller":"ContractAddress","Nonce":"felt","Execute After":"u128","Execute Before":"u128",
+    /// "Calls":"Call*")"Call"("To":"ContractAddress","Selector":"selector","Calldata":"felt*")`
+    pub const OUTSIDE_EXECUTION: Felt =
+        Felt::from_hex_unchecked("0x312b56c05a7965066ddbda31c016d8d05afc305071c0ca3cdc2192c3c2f1f0f");
+    /// `"Call"("To":"ContractAddress","Selector":"selector","Calldata":"felt*")`
+    pub const CALL: Felt =
+        Felt::from_hex_unchecked("0x3635c7f2a7ba93844c0d064e18e487f35ab90f7c39d00f186a781fc3f0c2ca9");
+}
+
+/// A call of an outside execution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutsideCall {
+    pub to: Felt,
+    pub selector: Felt,
+    pub calldata: Vec<Felt>,
+}
+
+/// The `OutsideExecution` struct signed by the user account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutsideExecution {
+    /// The only account allowed to submit this outside execution, or [`ANY_CALLER`].
+    pub caller: Felt,
+    pub nonce: Felt,
+    /// The outside execution is only valid strictly after this timestamp.
+    pub execute_after: u64,
+    /// The outside execution is only valid strictly before this timestamp.
+    pub execute_before: u64,
+    pub calls: Vec<OutsideCall>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutsideExecutionVersion {
+    /// `execute_from_outside`
+    V1,
+    /// `execute_from_outside_v2`
+    #[default]
+    V2,
+}
+
+impl OutsideExecutionVersion {
+    fn entrypoint(&self) -> &'static str {
+        match self {
+            Self::V1 => "execute_from_outside",
+            Self::V2 => "execute_from_outside_v2",
+        }
+    }
+}
+
+/// An outside execution, signed by the user account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutsideExecutionRequest {
+    /// The user account which signed the outside execution.
+    pub account_address: Felt,
+    pub outside_execution: OutsideExecution,
+    pub signature: Vec<Felt>,
+    #[serde(default)]
+    pub version: OutsideExecutionVersion,
+}
+
+impl OutsideExecutionRequest {
+    /// Checks that the outside execution can be submitted by `executor_address` at `now`.
+    fn check(&self, executor_address: Felt, now: BlockTimestamp) -> Result<(), String> {
+        let OutsideExecution { caller, execute_after, execute_before, calls, .. } = &self.outside_execution;
+        if *caller != ANY_CALLER && *caller != executor_address {
+            return Err(format!("Outside execution can only be submitted by {caller:#x}"));
+        }
+        if now.0 <= *execute_after {
+            return Err(format!("Outside execution is only valid after timestamp {execute_after}"));
+        }
+        if now.0 >= *execute_before {
+            return Err(format!("Outside execution expired at timestamp {execute_before}"));
+        }
+        if calls.is_empty() {
+            return Err("Outside execution has no calls".into());
+        }
+        Ok(())
+    }
+
+    /// SNIP-12 hash of the outside execution, which the user account signed.
+    fn message_hash(&self, chain_id: Felt) -> Felt {
+        let OutsideExecution { caller, nonce, execute_after, execute_before, calls } = &self.outside_execution;
+        let (execute_after, execute_before) = (Felt::from(*execute_after), Felt::from(*execute_before));
+
+        match self.version {
+            OutsideExecutionVersion::V1 => {
+                use type_hash_rev_0::*;
+                let calls: Vec<Felt> = calls
+                    .iter()
+                    .map(|call| {
+                        Pedersen::hash_array(&[
+                            CALL,
+                            call.to,
+                            call.selector,
+                            call.calldata.len().into(),
+                            Pedersen::hash_array(&call.calldata),
+                        ])
+                    })
+                    .collect();
+                let outside_execution = Pedersen::hash_array(&[
+                    OUTSIDE_EXECUTION,
+                    *caller,
+                    *nonce,
+                    execute_after,
+                    execute_before,
+                    calls.len().into(),
+                    Pedersen::hash_array(&calls),
+                ]);
+                let domain = Pedersen::hash_array(&[DOMAIN, DOMAIN_NAME, Felt::ONE, chain_id]);
+                Pedersen::hash_array(&[STARKNET_MESSAGE, domain, self.account_address, outside_execution])
+            }
+            OutsideExecutionVersion::V2 => {
+                use type_hash_rev_1::*;
+                let calls: Vec<Felt> = calls
+                    .iter()
+                    .map(|call| {
+                        Poseidon::hash_array(&[CALL, call.to, call.selector, Poseidon::hash_array(&call.calldata)])
+                    })
+                    .collect();
+                let outside_execution = Poseidon::hash_array(&[
+                    OUTSIDE_EXECUTION,
+                    *caller,
+                    *nonce,
+                    execute_after,
+                    execute_before,
+                    Poseidon::hash_array(&calls),
+                ]);
+                let domain = Poseidon::hash_array(&[DOMAIN, DOMAIN_NAME, Felt::TWO, chain_id, Felt::ONE]);
+                Poseidon::hash_array(&[STARKNET_MESSAGE, domain, self.account_address, outside_execution])
+            }
+        }
+    }
+
+    /// Calldata of the executor account `__execute__`: a single call to `execute_from_outside` on the user account.
+    fn executor_calldata(&self) -> Vec<Felt> {
+        let OutsideExecution { caller, nonce, execute_after, execute_before, calls } = &self.outside_execution;
+
+        let mut inner = vec![*caller, *nonce, Felt::from(*execute_after), Felt::from(*execute_before)];
+        inner.push(calls.len().into());
+        for call in calls {
+            inner.extend([call.to, call.selector, call.calldata.len().into()]);
+            inner.extend(&call.calldata);
+        }
+        inner.push(self.signature.len().into());
+        inner.extend(&self.signature);
+
+        let selector = get_selector_from_name(self.version.entrypoint()).expect("Valid entrypoint name");
+        let mut calldata = vec![Felt::ONE, self.account_address, selector, inner.len().into()];
+        calldata.extend(inner);
+        calldata
+    }
+}
+
+pub struct OutsideExecutionConfig {
+    /// Address of the executor account, which submits the outside executions and pays for them.
+    pub account_address: Felt,
+    pub private_key: Arc<ZeroingPrivateKey>,
+    /// Max amount of L1 gas of a single outside execution.
+    pub max_l1_gas: u64,
+    /// Max fees, in fri, sponsored for a user account over [`OutsideExecutionConfig::budget_period`]. The max fee of
+    /// each transaction is counted, not the fee it actually pays.
+    pub fee_budget: u128,
+    /// Max fees, in fri, sponsored for all the user accounts together over [`OutsideExecutionConfig::budget_period`].
+    pub total_fee_budget: u128,
+    pub budget_period: Duration,
+}
+
+/// Fees sponsored for a user account, or for all of them, during the current budget period.
+#[derive(Clone, Debug)]
+struct Sponsored {
+    since: Instant,
+    fees: u128,
+}
+
+impl Sponsored {
+    fn new(now: Instant) -> Self {
+        Self { since: now, fees: 0 }
+    }
+}
+
+/// See [`OutsideExecutionConfig::fee_budget`] and [`OutsideExecutionConfig::total_fee_budget`].
+#[derive(Debug)]
+struct Budgets {
+    total: Sponsored,
+    accounts: HashMap<Felt, Sponsored>,
+}
+
+/// Executor transaction of a submitted outside execution.
+#[derive(Clone, Copy, Debug)]
+struct SubmittedTx {
+    tx_hash: Felt,
+    /// See [`OutsideExecution::execute_before`].
+    execute_before: u64,
+}
+
+/// The outside executions submitted through the executor account, which are kept until their transaction is executed
+/// or evicted from the mempool. Until then, `is_valid_outside_execution_nonce` does not see their nonce as used.
+#[derive(Debug, Default)]
+struct Submitted {
+    /// By user account and outside execution nonce.
+    txs: HashMap<(Felt, Felt), SubmittedTx>,
+    /// Hashes of the transactions evicted from the mempool, once subscribed to.
+    expired_txs: Option<tokio::sync::broadcast::Receiver<Felt>>,
+}
+
+impl Submitted {
+    /// Forgets the outside executions whose transaction is `done`, and the ones which are expired at `now`: those
+    /// cannot be submitted anymore, which also covers the evictions missed when the receiver lagged behind.
+    fn prune(&mut self, now: BlockTimestamp, mut done: impl FnMut(&Felt) -> bool) {
+        self.txs.retain(|_, tx| now.0 < tx.execute_before && !done(&tx.tx_hash));
+    }
+
+    /// Hashes of the transactions evicted from the mempool since the last call.
+    fn take_expired_txs(&mut self) -> HashSet<Felt> {
+        use tokio::sync::broadcast::error::TryRecvError;
+
+        let mut expired = HashSet::new();
+        let Some(receiver) = &mut self.expired_txs else { return expired };
+        loop {
+            match receiver.try_recv() {
+                Ok(tx_hash) => {
+                    expired.insert(tx_hash);
+                }
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return expired,
+            }
+        }
+    }
+}
+
+/// Submits outside executions through the executor account.
+pub struct OutsideExecutor {
+    config: OutsideExecutionConfig,
+    /// Held until the transaction is in the mempool, so that concurrent submissions see each other's nonces.
+    submitted: tokio::sync::Mutex<Submitted>,
+    /// Fees sponsored in total and for each user account.
+    sponsored: std::sync::Mutex<Budgets>,
+}
+
+impl OutsideExecutor {
+    pub fn new(config: OutsideExecutionConfig) -> Self {
+        let sponsored = Budgets { total: Sponsored::new(Instant::now()), accounts: HashMap::new() };
+        Self { config, submitted: Default::default(), sponsored: std::sync::Mutex::new(sponsored) }
+    }
+
+    /// Counts `max_fee` in the budget of the user account and in the total budget, unless one of them would be
+    /// exceeded.
+    fn reserve_budget(&self, account_address: Felt, max_fee: u128, now: Instant) -> Result<(), String> {
+        let period = self.config.budget_period;
+        let mut sponsored = self.sponsored.lock().expect("Poisoned lock");
+        let Budgets { total, accounts } = &mut *sponsored;
+        if now.duration_since(total.since) >= period {
+            *total = Sponsored::new(now);
+        }
+        accounts.retain(|_, sponsored| now.duration_since(sponsored.since) < period);
+        let entry = accounts.entry(account_address).or_insert_with(|| Sponsored::new(now));
+
+        let fees = entry.fees.saturating_add(max_fee);
+        if fees > self.config.fee_budget {
+            return Err(format!(
+                "Account {account_address:#x} exceeded its outside execution fee budget of {} fri per {period:?}",
+                self.config.fee_budget
+            ));
+        }
+        let total_fees = total.fees.saturating_add(max_fee);
+        if total_fees > self.config.total_fee_budget {
+            return Err(format!(
+                "The outside execution fee budget of {} fri per {period:?} is exhausted",
+                self.config.total_fee_budget
+            ));
+        }
+        entry.fees = fees;
+        total.fees = total_fees;
+        Ok(())
+    }
+
+    /// Gives back a reservation of [`OutsideExecutor::reserve_budget`] when the transaction was not submitted.
+    fn release_budget(&self, account_address: Felt, max_fee: u128) {
+        let mut sponsored = self.sponsored.lock().expect("Poisoned lock");
+        sponsored.total.fees = sponsored.total.fees.saturating_sub(max_fee);
+        if let Some(entry) = sponsored.accounts.get_mut(&account_address) {
+            entry.fees = entry.fees.saturating_sub(max_fee);
+        }
+    }
+
+    /// Next nonce of the executor account: its nonce in the pending state, followed by its transactions which are
+    /// still in the mempool. A transaction dropped from the mempool leaves a gap at its nonce, which the next
+    /// submission fills instead of waiting behind it.
+    fn next_nonce(&self, starknet: &Starknet) -> RpcResult<Felt> {
+        let executor = self.config.account_address;
+        let mut nonce = starknet
+            .backend
+            .get_contract_nonce_at(&BlockId::Tag(BlockTag::Pending), &executor)
+            .or_internal_server_error("Error getting the executor account nonce")?
+            .unwrap_or_default();
+        let Some(mempool) = &starknet.mempool else { return Ok(nonce) };
+        let txs = mempool.accounts(executor, 1).into_iter().find(|account| account.contract_address == executor);
+        // The transactions are ordered by nonce.
+        for tx in txs.map(|account| account.transactions).unwrap_or_default() {
+            if tx.nonce == nonce {
+                nonce += Felt::ONE;
+            } else if tx.nonce > nonce {
+                break;
+            }
+        }
+        Ok(nonce)
+    }
+
+    /// Checks the signature of the outside execution with the `is_valid_signature` entrypoint of the user account.
+    fn check_signature(&self, starknet: &Starknet, request: &OutsideExecutionRequest) -> Result<(), String> {
+        let message_hash = request.message_hash(starknet.chain_id());
+        let mut calldata = vec![message_hash, request.signature.len().into()];
+        calldata.extend(&request.signature);
+        let function_call = FunctionCall {
+            contract_address: request.account_address,
+            entry_point_selector: get_selector_from_name("is_valid_signature").expect("Valid entrypoint name"),
+            calldata,
+        };
+        match call(starknet, function_call, BlockId::Tag(BlockTag::Pending)) {
+            Ok(result) if is_valid_signature_result(&result) => Ok(()),
+            Ok(_) => Err("Invalid outside execution signature".into()),
+            Err(err) => Err(format!("Invalid outside execution signature: {err}")),
+        }
+    }
+
+    /// Checks that the outside execution nonce was not used yet, with the `is_valid_outside_execution_nonce`
+    /// entrypoint of the user account.
+    fn check_nonce(&self, starknet: &Starknet, request: &OutsideExecutionRequest) -> Result<(), String> {
+        let nonce = request.outside_execution.nonce;
+        let function_call = FunctionCall {
+            contract_address: request.account_address,
+            entry_point_selector: get_selector_from_name("is_valid_outside_execution_nonce")
+                .expect("Valid entrypoint name"),
+            calldata: vec![nonce],
+        };
+        match call(starknet, function_call, BlockId::Tag(BlockTag::Pending)) {
+            Ok(result) if result == [Felt::ONE] => Ok(()),
+            Ok(_) => Err(format!("Outside execution nonce {nonce:#x} was already used")),
+            Err(err) => Err(format!("Invalid outside execution nonce: {err}")),
+        }
+    }
+
+    pub(crate) async fn submit(
+        &self,
+        starknet: &Starknet,
+        request: OutsideExecutionRequest,
+    ) -> RpcResult<AddInvokeTransactionResult> {
+        let block_info = starknet.get_block_info(&BlockId::Tag(BlockTag::Pending))?;
+        request
+            .check(self.config.account_address, block_info.block_timestamp())
+            .map_err(|error| StarknetRpcApiError::ValidationFailure { error: error.into() })?;
+        let l1_gas_price = match &block_info {
+            MadaraMaybePendingBlockInfo::Pending(info) => &info.header.l1_gas_price,
+            MadaraMaybePendingBlockInfo::NotPending(info) => &info.header.l1_gas_price,
+        };
+        // Leave some room for the gas price to move before the transaction is executed.
+        let max_l1_gas_price = l1_gas_price.strk_l1_gas_price.saturating_mul(3) / 2;
+
+        self.check_signature(starknet, &request)
+            .map_err(|error| StarknetRpcApiError::ValidationFailure { error: error.into() })?;
+        self.check_nonce(starknet, &request)
+            .map_err(|error| StarknetRpcApiError::ValidationFailure { error: error.into() })?;
+        let outside_nonce = request.outside_execution.nonce;
+        let max_fee = u128::from(self.config.max_l1_gas).saturating_mul(max_l1_gas_price);
+
+        let mut submitted = self.submitted.lock().await;
+        if submitted.expired_txs.is_none() {
+            submitted.expired_txs = starknet.mempool.as_ref().map(|mempool| mempool.subscribe_expired_txs());
+        }
+        let expired_txs = submitted.take_expired_txs();
+        let mut executed_txs = HashSet::new();
+        for tx in submitted.txs.values() {
+            if starknet
+                .backend
+                .find_tx_hash_block_info(&tx.tx_hash)
+                .or_internal_server_error("Error looking up an outside execution transaction")?
+                .is_some()
+            {
+                executed_txs.insert(tx.tx_hash);
+            }
+        }
+        submitted.prune(block_info.block_timestamp(), |tx_hash| {
+            expired_txs.contains(tx_hash) || executed_txs.contains(tx_hash)
+        });
+        if submitted.txs.contains_key(&(request.account_address, outside_nonce)) {
+            return Err(StarknetRpcApiError::ValidationFailure {
+                error: format!("Outside execution nonce {outside_nonce:#x} was already submitted").into(),
+            }
+            .into());
+        }
+        self.reserve_budget(request.account_address, max_fee, Instant::now())
+            .map_err(|error| StarknetRpcApiError::ValidationFailure { error: error.into() })?;
+
+        let nonce = match self.next_nonce(starknet) {
+            Ok(nonce) => nonce,
+            Err(err) => {
+                self.release_budget(request.account_address, max_fee);
+                return Err(err);
+            }
+        };
+        let res = match self.invoke_transaction(&request, nonce, max_l1_gas_price, starknet.chain_id()) {
+            Ok(transaction) => starknet.add_transaction_provider.add_invoke_transaction(transaction).await,
+            Err(err) => Err(err.into()),
+        };
+        match res {
+            Ok(res) => {
+                let execute_before = request.outside_execution.execute_before;
+                submitted.txs.insert(
+                    (request.account_address, outside_nonce),
+                    SubmittedTx { tx_hash: res.transaction_hash, execute_before },
+                );
+                Ok(res)
+            }
+            Err(err) => {
+                self.release_budget(request.account_address, max_fee);
+                Err(err)
+            }
+        }
+    }
+
+    fn invoke_transaction(
+        &self,
+        request: &OutsideExecutionRequest,
+        nonce: Felt,
+        max_l1_gas_price: u128,
+        chain_id: Felt,
+    ) -> Result<BroadcastedInvokeTxn, StarknetRpcApiError> {
+        let mut transaction = InvokeTxnV3 {
+            account_deployment_data: vec![],
+            calldata: request.executor_calldata(),
+            fee_data_availability_mode: DaMode::L1,
+            nonce,
+            nonce_data_availability_mode: DaMode::L1,
+            paymaster_data: vec![],
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds { max_amount: self.config.max_l1_gas, max_price_per_unit: max_l1_gas_price },
+                l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+            },
+            sender_address: self.config.account_address,
+            signature: vec![],
+            tip: 0,
+        };
+
+        let transaction_hash =
+            mp_transactions::InvokeTransactionV3::from(transaction.clone()).compute_hash(chain_id, false);
+        let signature = self
+            .config
+            .private_key
+            .sign(&transaction_hash)
+            .or_internal_server_error("Error signing the outside execution transaction")?;
+        transaction.signature = vec![signature.r, signature.s];
+
+        Ok(BroadcastedInvokeTxn::V3(transaction))
+    }
+}
+
+/// Whether the result of `is_valid_signature` accepts the signature.
+fn is_valid_signature_result(result: &[Felt]) -> bool {
+    matches!(result, [valid] if *valid == VALID || *valid == Felt::ONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(caller: Felt) -> OutsideExecutionRequest {
+        OutsideExecutionRequest {
+            account_address: Felt::from(0x1234),
+            outside_execution: OutsideExecution {
+                caller,
+                nonce: Felt::from(7),
+                execute_after: 100,
+                execute_before: 200,
+                calls: vec![OutsideCall {
+                    to: Felt::from(0x42),
+                    selector: Felt::from(0x43),
+                    calldata: vec![Felt::TWO],
+                }],
+            },
+            signature: vec![Felt::from(0xa), Felt::from(0xb)],
+            version: OutsideExecutionVersion::V2,
+        }
+    }
+
+    #[test]
+    fn test_check_outside_execution() {
+        let executor = Felt::from(0x99);
+        assert_eq!(request(ANY_CALLER).check(executor, BlockTimestamp(150)), Ok(()));
+        assert_eq!(request(executor).check(executor, BlockTimestamp(150)), Ok(()));
+        assert!(request(Felt::from(0x98)).check(executor, BlockTimestamp(150)).is_err());
+        assert!(request(ANY_CALLER).check(executor, BlockTimestamp(100)).is_err());
+        assert!(request(ANY_CALLER).check(executor, BlockTimestamp(200)).is_err());
+
+        let mut no_calls = request(ANY_CALLER);
+        no_calls.outside_execution.calls.clear();
+        assert!(no_calls.check(executor, BlockTimestamp(150)).is_err());
+    }
+
+    #[test]
+    fn test_executor_calldata() {
+        let selector = get_selector_from_name("execute_from_outside_v2").unwrap();
+        assert_eq!(
+            request(ANY_CALLER).executor_calldata(),
+            vec![
+                // One call to the user account
+                Felt::ONE,
+                Felt::from(0x1234),
+                selector,
+                Felt::from(12),
+                // OutsideExecution
+                ANY_CALLER,
+                Felt::from(7),
+                Felt::from(100),
+                Felt::from(200),
+                Felt::ONE,
+                Felt::from(0x42),
+                Felt::from(0x43),
+                Felt::ONE,
+                Felt::TWO,
+                // Signature
+                Felt::TWO,
+                Felt::from(0xa),
+                Felt::from(0xb),
+            ]
+        );
+    }
+
+    fn executor() -> OutsideExecutor {
+        OutsideExecutor::new(OutsideExecutionConfig {
+            account_address: Felt::from(0x99),
+            private_key: Arc::new(ZeroingPrivateKey::default()),
+            max_l1_gas: 10_000,
+            fee_budget: 1_000,
+            total_fee_budget: 2_000,
+            budget_period: Duration::from_secs(3600),
+        })
+    }
+
+    #[test]
+    fn test_type_hashes() {
+        let type_hash = |ty: &str| starknet_core::utils::starknet_keccak(ty.as_bytes());
+        assert_eq!(type_hash_rev_0::DOMAIN, type_hash("StarkNetDomain(name:felt,version:felt,chainId:felt)"));
+        assert_eq!(
+            type_hash_rev_0::OUTSIDE_EXECUTION,
+            type_hash(
+                "OutsideExecution(caller:felt,nonce:felt,execute_after:felt,execute_before:felt,calls_len:felt,\
+                 calls:OutsideCall*)OutsideCall(to:felt,selector:felt,calldata_len:felt,calldata:felt*)"
+            )
+        );
+        assert_eq!(
+            type_hash_rev_0::CALL,
+            type_hash("OutsideCall(to:felt,selector:felt,calldata_len:felt,calldata:felt*)")
+        );
+        assert_eq!(
+            type_hash_rev_1::DOMAIN,
+            type_hash(concat!(
+                r#""StarknetDomain"("name":"shortstring","version":"shortstring","chainId":"shortstring","#,
+                r#""revision":"shortstring")"#
+            ))
+        );
+        assert_eq!(
+            type_hash_rev_1::OUTSIDE_EXECUTION,
+            type_hash(concat!(
+                r#""OutsideExecution"("Caller":"ContractAddress","Nonce":"felt","Execute After":"u128","#,
+                r#""Execute Before":"u128","Calls":"Call*")"#,
+                r#""Call"("To":"ContractAddress","Selector":"selector","Calldata":"felt*")"#
+            ))
+        );
+        assert_eq!(
+            type_hash_rev_1::CALL,
+            type_hash(r#""Call"("To":"ContractAddress","Selector":"selector","Calldata":"felt*")"#)
+        );
+
+        let short_string = |s: &str| starknet_core::utils::cairo_short_string_to_felt(s).unwrap();
+        assert_eq!(STARKNET_MESSAGE, short_string("StarkNet Message"));
+        assert_eq!(DOMAIN_NAME, short_string("Account.execute_from_outside"));
+        assert_eq!(VALID, short_string("VALID"));
+    }
+
+    #[test]
+    fn test_message_hash() {
+        let chain_id = Felt::from_hex_unchecked("0x534e5f5345504f4c4941");
+        let mut request = request(ANY_CALLER);
+        assert_eq!(
+            request.message_hash(chain_id),
+            Felt::from_hex_unchecked("0x5fa4d7e45597aef0310bf61f4fc2592042eca4b5dc7ec876750a2f759baa963")
+        );
+        request.version = OutsideExecutionVersion::V1;
+        assert_eq!(
+            request.message_hash(chain_id),
+            Felt::from_hex_unchecked("0x14c79339aa762b62db0c9a0639ba81d4f65a6cdacd88af7363724bba53bfb23")
+        );
+    }
+
+    #[test]
+    fn test_is_valid_signature_result() {
+        assert!(is_valid_signature_result(&[VALID]));
+        assert!(is_valid_signature_result(&[Felt::ONE]));
+        assert!(!is_valid_signature_result(&[Felt::ZERO]));
+        assert!(!is_valid_signature_result(&[]));
+        assert!(!is_valid_signature_result(&[VALID, VALID]));
+    }
+
+    #[test]
+    fn test_fee_budget() {
+        let executor = executor();
+        let (alice, bob) = (Felt::from(0xa), Felt::from(0xb));
+        let now = Instant::now();
+
+        assert!(executor.reserve_budget(alice, 600, now).is_ok());
+        assert!(executor.reserve_budget(alice, 600, now).is_err());
+        // The budget is per user account.
+        assert!(executor.reserve_budget(bob, 600, now).is_ok());
+        // A transaction which was not submitted is not counted.
+        executor.release_budget(alice, 600);
+        assert!(executor.reserve_budget(alice, 1_000, now).is_ok());
+        assert!(executor.reserve_budget(alice, 1, now + Duration::from_secs(3599)).is_err());
+        // New period.
+        assert!(executor.reserve_budget(alice, 1_000, now + Duration::from_secs(3600)).is_ok());
+    }
+
+    #[test]
+    fn test_total_fee_budget() {
+        let executor = executor();
+        let now = Instant::now();
+
+        // Each account is within its own budget, but the executor stops sponsoring once the total budget is spent.
+        assert!(executor.reserve_budget(Felt::from(0xa), 1_000, now).is_ok());
+        assert!(executor.reserve_budget(Felt::from(0xb), 1_000, now).is_ok());
+        assert!(executor.reserve_budget(Felt::from(0xc), 1, now).is_err());
+        executor.release_budget(Felt::from(0xb), 1_000);
+        assert!(executor.reserve_budget(Felt::from(0xc), 1_000, now).is_ok());
+        // New period.
+        assert!(executor.reserve_budget(Felt::from(0xd), 1_000, now + Duration::from_secs(3600)).is_ok());
+    }
+
+    #[test]
+    fn test_submitted_outside_executions() {
+        let (alice, bob) = (Felt::from(0xa), Felt::from(0xb));
+        let mut submitted = Submitted::default();
+        submitted.txs.insert((alice, Felt::from(7)), SubmittedTx { tx_hash: Felt::from(0x1), execute_before: 200 });
+        submitted.txs.insert((bob, Felt::from(7)), SubmittedTx { tx_hash: Felt::from(0x2), execute_before: 200 });
+        submitted.txs.insert((bob, Felt::from(8)), SubmittedTx { tx_hash: Felt::from(0x3), execute_before: 300 });
+
+        // The nonces are kept while the transactions are neither executed nor evicted, even after a budget period.
+        submitted.prune(BlockTimestamp(150), |_| false);
+        assert_eq!(submitted.txs.len(), 3);
+        // Executed or evicted.
+        submitted.prune(BlockTimestamp(150), |tx_hash| *tx_hash == Felt::from(0x1));
+        assert!(!submitted.txs.contains_key(&(alice, Felt::from(7))));
+        assert!(submitted.txs.contains_key(&(bob, Felt::from(7))));
+        // The expired outside executions cannot be submitted again anyway.
+        submitted.prune(BlockTimestamp(200), |_| false);
+        assert_eq!(submitted.txs.keys().collect::<Vec<_>>(), [&(bob, Felt::from(8))]);
+    }
+
+    #[test]
+    fn test_outside_execution_transaction_is_signed() {
+        let executor = executor();
+        let chain_id = Felt::from(0x5);
+
+        let BroadcastedInvokeTxn::V3(transaction) =
+            executor.invoke_transaction(&request(ANY_CALLER), Felt::from(3), 150, chain_id).unwrap()
+        else {
+            panic!("Expected an invoke v3 transaction")
+        };
+        assert_eq!(transaction.nonce, Felt::from(3));
+        assert_eq!(transaction.sender_address, Felt::from(0x99));
+        assert_eq!(transaction.resource_bounds.l1_gas, ResourceBounds { max_amount: 10_000, max_price_per_unit: 150 });
+
+        let transaction_hash =
+            mp_transactions::InvokeTransactionV3::from(transaction.clone()).compute_hash(chain_id, false);
+        let [r, s] = transaction.signature[..] else { panic!("Expected a signature with 2 felts") };
+        assert!(starknet_core::crypto::ecdsa_verify(
+            &executor.config.private_key.public,
+            &transaction_hash,
+            &starknet_core::crypto::Signature { r, s }
+        )
+        .unwrap());
+    }
+}
@@ -40,6 +40,13 @@ impl From<mc_mempool::MempoolError> for StarknetRpcApiError {
             mc_mempool::MempoolError::Validation(err) => {
                 StarknetRpcApiError::ValidationFailure { error: format!("{err:#}").into() }
             }
+            mc_mempool::MempoolError::Paymaster(err) => {
+                StarknetRpcApiError::ValidationFailure { error: format!("{err:#}").into() }
+            }
+            mc_mempool::MempoolError::UnsupportedTxnVersion(_) => StarknetRpcApiError::UnsupportedTxnVersion,
+            mc_mempool::MempoolError::CustomTransaction(err) => {
+                StarknetRpcApiError::ValidationFailure { error: format!("{err:#}").into() }
+            }
             mc_mempool::MempoolError::Exec(err) => {
                 StarknetRpcApiError::TxnExecutionError { tx_index: 0, error: format!("{err:#}") }
             }
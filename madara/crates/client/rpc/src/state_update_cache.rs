@@ -0,0 +1,71 @@
+//! In-memory cache of the state updates of confirmed blocks.
+//!
+//! `starknet_getStateUpdate` is heavily used by indexers, which usually follow the tip of the chain. Building a
+//! state update requires reading the state diff, the block info and the block info of the parent block (for the old
+//! root), and converting the state diff to the RPC format. Confirmed blocks never change, so the result can be kept
+//! around and served directly.
+
+use mp_rpc::StateUpdate;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Bounded cache of the RPC-formatted state updates of confirmed blocks, keyed by block number.
+///
+/// When full, the lowest block number is evicted first: recent blocks are the most requested ones.
+pub struct StateUpdateCache {
+    capacity: usize,
+    entries: Mutex<BTreeMap<u64, StateUpdate>>,
+}
+
+impl StateUpdateCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn get(&self, block_n: u64) -> Option<StateUpdate> {
+        self.entries.lock().expect("Poisoned lock").get(&block_n).cloned()
+    }
+
+    /// Only state updates of confirmed blocks must be inserted.
+    pub fn insert(&self, block_n: u64, state_update: StateUpdate) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().expect("Poisoned lock");
+        entries.insert(block_n, state_update);
+        while entries.len() > self.capacity {
+            entries.pop_first();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet_types_core::felt::Felt;
+
+    fn state_update(block_n: u64) -> StateUpdate {
+        StateUpdate {
+            block_hash: Felt::from(block_n),
+            old_root: Felt::ZERO,
+            new_root: Felt::ONE,
+            state_diff: mp_state_update::StateDiff::default().into(),
+        }
+    }
+
+    #[test]
+    fn test_state_update_cache_evicts_oldest_blocks() {
+        let cache = StateUpdateCache::new(2);
+        cache.insert(5, state_update(5));
+        cache.insert(3, state_update(3));
+        cache.insert(7, state_update(7));
+
+        assert_eq!(cache.get(3), None);
+        assert_eq!(cache.get(5), Some(state_update(5)));
+        assert_eq!(cache.get(7), Some(state_update(7)));
+
+        let disabled = StateUpdateCache::new(0);
+        disabled.insert(1, state_update(1));
+        assert_eq!(disabled.get(1), None);
+    }
+}
@@ -1,10 +1,68 @@
+use mp_block::MadaraMaybePendingBlockInfo;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
 use std::fmt;
 use std::num::ParseIntError;
 
+/// Position of the next event of a `starknet_getEvents` query.
+///
+/// Tokens pointing into a confirmed block are formatted as `<block_n>-<event_n>`. Tokens pointing into the pending
+/// block also record the transactions of the pending block their events were read from, and are formatted as
+/// `v1-<block_n>-<event_n>-<tx_count>-<fingerprint>`.
 #[derive(PartialEq, Eq, Debug, Default)]
 pub struct ContinuationToken {
     pub block_n: u64,
     pub event_n: u64,
+    pub pending: Option<PendingCursor>,
+}
+
+/// The transactions of the pending block a continuation token was issued for. The pending block keeps changing, so
+/// resuming from the token is only consistent if the block, pending or since closed, still starts with them.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct PendingCursor {
+    pub tx_count: u64,
+    /// Poseidon hash of the parent block hash and of the hashes of the first `tx_count` transactions.
+    pub fingerprint: Felt,
+}
+
+impl PendingCursor {
+    /// The cursor of the first `tx_count` transactions of the block, or `None` if it has fewer transactions.
+    pub fn new(block_info: &MadaraMaybePendingBlockInfo, tx_count: u64) -> Option<Self> {
+        let parent_block_hash = match block_info {
+            MadaraMaybePendingBlockInfo::Pending(info) => info.header.parent_block_hash,
+            MadaraMaybePendingBlockInfo::NotPending(info) => info.header.parent_block_hash,
+        };
+        let tx_hashes = block_info.tx_hashes().get(..usize::try_from(tx_count).ok()?)?;
+        let fingerprint = Poseidon::hash_array(&[&[parent_block_hash], tx_hashes].concat());
+        Some(Self { tx_count, fingerprint })
+    }
+}
+
+/// Server-side limit of the size of a page returned by a paginated method, on top of the page size requested by the
+/// client: a few items with large data arrays can otherwise make for responses of hundreds of megabytes. The page is
+/// cut short with a continuation token once the next item does not fit in the budget. It always has at least one item,
+/// so that the client makes progress.
+#[derive(Debug, Clone, Copy)]
+pub struct PageByteBudget {
+    remaining: usize,
+    empty: bool,
+}
+
+impl PageByteBudget {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { remaining: max_bytes, empty: true }
+    }
+
+    /// Takes the size of the next item of the page out of the budget. Returns `false`, leaving the budget unchanged,
+    /// when the item does not fit in the page.
+    pub fn take(&mut self, bytes: usize) -> bool {
+        if !self.empty && bytes > self.remaining {
+            return false;
+        }
+        self.remaining = self.remaining.saturating_sub(bytes);
+        self.empty = false;
+        true
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -15,20 +73,31 @@ pub enum ParseTokenError {
 
 impl fmt::Display for ContinuationToken {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}-{}", self.block_n, self.event_n)
+        match &self.pending {
+            None => write!(f, "{}-{}", self.block_n, self.event_n),
+            Some(cursor) => {
+                write!(f, "v1-{}-{}-{}-{:#x}", self.block_n, self.event_n, cursor.tx_count, cursor.fingerprint)
+            }
+        }
     }
 }
 
 impl ContinuationToken {
     pub fn parse(token: String) -> Result<Self, ParseTokenError> {
         let arr: Vec<&str> = token.split('-').collect();
-        if arr.len() != 2 {
-            return Err(ParseTokenError::WrongToken);
-        }
+        let (arr, pending) = match arr.as_slice() {
+            [block_n, event_n] => ([*block_n, *event_n], None),
+            ["v1", block_n, event_n, tx_count, fingerprint] => {
+                let tx_count = tx_count.parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
+                let fingerprint = Felt::from_hex(fingerprint).map_err(|_| ParseTokenError::WrongToken)?;
+                ([*block_n, *event_n], Some(PendingCursor { tx_count, fingerprint }))
+            }
+            _ => return Err(ParseTokenError::WrongToken),
+        };
         let block_n = arr[0].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
         let event_n = arr[1].parse::<u64>().map_err(ParseTokenError::ParseFailed)?;
 
-        Ok(ContinuationToken { block_n, event_n })
+        Ok(ContinuationToken { block_n, event_n, pending })
     }
 }
 
@@ -44,7 +113,7 @@ mod tests {
     #[case(2, 4, "2-4")]
     #[case(0, 4, "0-4")]
     fn to_string_works(#[case] block_n: u64, #[case] event_n: u64, #[case] expected: String) {
-        let token = ContinuationToken { block_n, event_n };
+        let token = ContinuationToken { block_n, event_n, pending: None };
         assert_eq!(expected, token.to_string())
     }
 
@@ -53,14 +122,42 @@ mod tests {
     #[case("1-4", 1, 4)]
     #[case("2-4", 2, 4)]
     fn parse_works(#[case] string_token: String, #[case] block_n: u64, #[case] event_n: u64) {
-        let expected = ContinuationToken { block_n, event_n };
+        let expected = ContinuationToken { block_n, event_n, pending: None };
         assert_eq!(expected, ContinuationToken::parse(string_token).unwrap());
     }
 
+    #[test]
+    fn pending_token_round_trip() {
+        let token = ContinuationToken {
+            block_n: 3,
+            event_n: 2,
+            pending: Some(PendingCursor { tx_count: 5, fingerprint: Felt::from(0xabc) }),
+        };
+        assert_eq!(token.to_string(), "v1-3-2-5-0xabc");
+        assert_eq!(ContinuationToken::parse(token.to_string()).unwrap(), token);
+    }
+
+    #[test]
+    fn page_byte_budget() {
+        let mut budget = PageByteBudget::new(10);
+        assert!(budget.take(6));
+        assert!(!budget.take(5));
+        assert!(budget.take(4));
+        assert!(!budget.take(1));
+
+        // The first item always fits.
+        let mut budget = PageByteBudget::new(10);
+        assert!(budget.take(20));
+        assert!(!budget.take(1));
+    }
+
     #[rstest]
     #[case("100")]
     #[case("0,")]
     #[case("0,0,0")]
+    #[case("v1-3-2-5")]
+    #[case("v2-3-2-5-0xabc")]
+    #[case("v1-3-2-5-xyz")]
     fn parse_should_fail(#[case] string_token: String) {
         let result = ContinuationToken::parse(string_token);
         assert!(result.is_err());
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use mc_db::MadaraStorageError;
 use mp_rpc::Event;
 use starknet_types_core::felt::Felt;
 
@@ -25,6 +26,16 @@ macro_rules! bail_internal_server_error {
     };
 }
 
+/// Reading the state at a pruned block is reported with its own error, instead of an internal server error.
+fn state_pruned_error(err: &anyhow::Error) -> Option<StarknetRpcApiError> {
+    match err.downcast_ref::<MadaraStorageError>()? {
+        MadaraStorageError::StatePruned { block_n, oldest_block_n } => {
+            Some(StarknetRpcApiError::StatePruned { block_n: *block_n, oldest_block_n: *oldest_block_n })
+        }
+        _ => None,
+    }
+}
+
 pub trait ResultExt<T, E> {
     fn or_internal_server_error<C: fmt::Display>(self, context: C) -> Result<T, StarknetRpcApiError>;
     fn or_else_internal_server_error<C: fmt::Display, F: FnOnce() -> C>(
@@ -40,7 +51,11 @@ impl<T, E: Into<anyhow::Error>> ResultExt<T, E> for Result<T, E> {
         match self {
             Ok(val) => Ok(val),
             Err(err) => {
-                display_internal_server_error(format!("{}: {:#}", context, E::into(err)));
+                let err = E::into(err);
+                if let Some(err) = state_pruned_error(&err) {
+                    return Err(err);
+                }
+                display_internal_server_error(format!("{}: {:#}", context, err));
                 Err(StarknetRpcApiError::InternalServerError)
             }
         }
@@ -54,7 +69,11 @@ impl<T, E: Into<anyhow::Error>> ResultExt<T, E> for Result<T, E> {
         match self {
             Ok(val) => Ok(val),
             Err(err) => {
-                display_internal_server_error(format!("{}: {:#}", context_fn(), E::into(err)));
+                let err = E::into(err);
+                if let Some(err) = state_pruned_error(&err) {
+                    return Err(err);
+                }
+                display_internal_server_error(format!("{}: {:#}", context_fn(), err));
                 Err(StarknetRpcApiError::InternalServerError)
             }
         }
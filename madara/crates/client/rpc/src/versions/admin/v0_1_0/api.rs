@@ -1,9 +1,26 @@
+use blockifier::bouncer::BouncerWeights;
 use jsonrpsee::core::RpcResult;
 use m_proc_macros::versioned_rpc;
-use mp_rpc::ClassAndTxnHash;
+use mc_block_import::UnverifiedFullBlock;
+use mc_db::chain_head::ChainHeadUpdate;
+use mc_db::devnet_control::DevnetControl;
+use mc_db::pipeline_gaps::PipelineGaps;
+use mc_db::sync_control::SyncControl;
+use mc_db::sync_history_db::SyncHistoryEntry;
+use mc_db::sync_status::SyncStatus;
+use mp_block::header::GasPrices;
+use mp_block::BlockId;
+use mp_rpc::{
+    AddInvokeTransactionResult, BroadcastedTxn, ClassAndTxnHash, EventFilterWithPageRequest, EventsChunk, FeeEstimate,
+    FeePayment, SimulationFlagForEstimateFee, StateDiff,
+};
 use mp_transactions::BroadcastedDeclareTransactionV0;
 use mp_utils::service::{MadaraServiceId, MadaraServiceStatus};
 use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+use std::path::PathBuf;
+
+use crate::outside_execution::OutsideExecutionRequest;
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "snake_case")]
@@ -13,6 +30,153 @@ pub enum ServiceRequest {
     Restart,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DryRunTransactionStatus {
+    /// Included in the block.
+    Succeeded,
+    /// Included in the block, but reverted.
+    Reverted,
+    /// Failed with an error which cannot be reverted, dropped from the mempool.
+    Rejected,
+    /// Not reached because the block is full, left in the mempool.
+    NotExecuted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DryRunTransaction {
+    pub transaction_hash: Felt,
+    pub sender_address: Felt,
+    pub nonce: Felt,
+    pub status: DryRunTransactionStatus,
+    /// Revert reason or rejection error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_fee: Option<FeePayment>,
+}
+
+/// The candidate block of a block production dry run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockDryRun {
+    pub block_number: u64,
+    pub parent_hash: Felt,
+    pub transactions: Vec<DryRunTransaction>,
+    /// State diff of the executed transactions, on top of the pending block.
+    pub state_diff: StateDiff,
+    /// Resources used by the executed transactions, on top of the pending block.
+    pub resources: BouncerWeights,
+    pub block_full: bool,
+}
+
+/// Number of transactions in the mempool.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxPoolStatus {
+    /// Transactions which can be included in the next block.
+    pub ready: u64,
+    /// Transactions waiting for a transaction with a lower nonce from the same account.
+    pub pending: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TxPoolTransactionType {
+    Declare,
+    DeployAccount,
+    Invoke,
+    L1Handler,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolTransaction {
+    pub transaction_hash: Felt,
+    pub nonce: Felt,
+    #[serde(rename = "type")]
+    pub tx_type: TxPoolTransactionType,
+    /// Whether the transaction can be included in the next block. Transactions which are not ready are waiting for a
+    /// transaction with a lower nonce from the same account.
+    pub ready: bool,
+    /// Unix time in milliseconds at which the transaction was received.
+    pub arrived_at: u64,
+}
+
+/// The transactions of an account in the mempool.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolAccount {
+    pub address: Felt,
+    /// Nonce of the account in the latest block, which is the nonce of the next transaction it can have included.
+    pub nonce: Felt,
+    /// Transactions by nonce.
+    pub transactions: Vec<TxPoolTransaction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxPoolContent {
+    /// Accounts by address.
+    pub accounts: Vec<TxPoolAccount>,
+    /// Token to get the next accounts, if there are more.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<Felt>,
+}
+
+/// Fee estimates of the same transactions at the gas prices of the block they are executed on, and at the gas prices
+/// the next block will be opened with.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CurrentPricesFeeEstimates {
+    /// Estimates at the gas prices of the block, as returned by `starknet_estimateFee`.
+    pub at_block_prices: Vec<FeeEstimate>,
+    /// Estimates at the current gas prices.
+    pub at_current_prices: Vec<FeeEstimate>,
+    /// The gas prices the next block will be opened with, from the L1 gas price oracle or the pending block.
+    pub current_gas_prices: GasPrices,
+}
+
+/// The outcome of `madara_backfillResources`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BackfilledResources {
+    /// The number of receipts which were enriched.
+    pub enriched_receipts: u64,
+    /// Blocks missing execution resources which cannot be re-executed, as the chain config has no versioned
+    /// constants for their Starknet version.
+    pub unsupported_blocks: Vec<u64>,
+    /// The block to continue the backfill from, if the range was cut at [`MAX_BACKFILL_RESOURCES_BLOCKS`] blocks or
+    /// the node is shutting down.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_block: Option<u64>,
+}
+
+/// The maximum number of blocks backfilled by one `madara_backfillResources` call.
+pub const MAX_BACKFILL_RESOURCES_BLOCKS: u64 = 1_000;
+
+/// An entry of the activity of an address.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AddressActivity {
+    /// A transaction sent by the address. For deploy and L1 handler transactions, which have no sender, a transaction
+    /// deploying or calling the address.
+    Transaction { block_number: u64, transaction_index: u64, transaction_hash: Felt },
+    /// An event emitted by the address.
+    Event { block_number: u64, transaction_hash: Felt, event_index: u64, keys: Vec<Felt>, data: Vec<Felt> },
+    /// A write to the storage of the address.
+    StorageWrite { block_number: u64, key: Felt, value: Felt },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AddressActivityPage {
+    /// Activity of the address, oldest first.
+    pub activity: Vec<AddressActivity>,
+    /// Token to get the next entries, if there are more.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation_token: Option<String>,
+}
+
+/// A block imported through `madara_submitFullBlock`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmittedBlock {
+    pub block_number: u64,
+    pub block_hash: Felt,
+}
+
 /// This is an admin method, so semver is different!
 #[versioned_rpc("V0_1_0", "madara")]
 pub trait MadaraWriteRpcApi {
@@ -22,6 +186,21 @@ pub trait MadaraWriteRpcApi {
         &self,
         declare_v0_transaction: BroadcastedDeclareTransactionV0,
     ) -> RpcResult<ClassAndTxnHash>;
+
+    /// Submit a complete block built outside of the node: header, transactions, receipts, state diff and declared
+    /// classes. The block is verified like a synced block before it is appended to the chain. Only available when a
+    /// block submission key is configured, and while the sync and the block production are disabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - the block to import.
+    /// * `key` - the block submission key of the node.
+    ///
+    /// # Returns
+    ///
+    /// * The number and the hash of the imported block.
+    #[method(name = "submitFullBlock")]
+    async fn submit_full_block(&self, block: UnverifiedFullBlock, key: String) -> RpcResult<SubmittedBlock>;
 }
 
 #[versioned_rpc("V0_1_0", "madara")]
@@ -49,6 +228,48 @@ pub trait MadaraStatusRpcApi {
     /// * Current time in unix time
     #[subscription(name = "pulse", unsubscribe = "unsubscribe", item = u64)]
     async fn pulse(&self) -> jsonrpsee::core::SubscriptionResult;
+
+    /// Sends an update every time a stage of the block pipeline advances.
+    ///
+    /// # Sends
+    ///
+    /// * The stage which advanced, the block it advanced to and the latest block of every stage
+    #[subscription(name = "subscribeSyncStatus", unsubscribe = "unsubscribeSyncStatus", item = ChainHeadUpdate)]
+    async fn subscribe_sync_status(&self) -> jsonrpsee::core::SubscriptionResult;
+
+    /// Returns the progress of the sync over time, as periodically recorded by the sync. The history is kept across
+    /// restarts for 30 days.
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - only return the periods which ended at or after this unix time, all of them by default.
+    ///
+    /// # Returns
+    ///
+    /// * The blocks imported and the time spent in each stage of the block pipeline for every period, oldest first.
+    #[method(name = "syncHistory")]
+    async fn sync_history(&self, since: Option<u64>) -> RpcResult<Vec<SyncHistoryEntry>>;
+
+    /// Returns the current throughput of the sync and the estimated time until it reaches the head of the chain. The
+    /// status is measured every 30 seconds.
+    ///
+    /// # Returns
+    ///
+    /// * The latest block, the latest block on the feeder gateway, the blocks and classes imported per second and the
+    ///   estimated time to the head in seconds, or null when the sync is not running or has not been measured yet.
+    #[method(name = "syncStatus")]
+    async fn sync_status(&self) -> RpcResult<Option<SyncStatus>>;
+
+    /// Explains what the node does with the block pipeline on restart: where the sync resumes, the blocks stored
+    /// without the classes they declare after a crash, whose classes are fetched again, and the blocks left to
+    /// backfill and to confirm on L1.
+    ///
+    /// # Returns
+    ///
+    /// * The latest block of each stage of the block pipeline, the blocks missing classes and the block the sync
+    ///   resumes from.
+    #[method(name = "pipelineGaps")]
+    async fn pipeline_gaps(&self) -> RpcResult<PipelineGaps>;
 }
 
 #[versioned_rpc("V0_1_0", "madara")]
@@ -60,4 +281,275 @@ pub trait MadaraServicesRpcApi {
     /// * 'on' if any service was active before being toggled, 'off' otherwise.
     #[method(name = "service")]
     async fn service(&self, service: Vec<MadaraServiceId>, status: ServiceRequest) -> RpcResult<MadaraServiceStatus>;
+
+    /// Replaces the log filter of the node at runtime, without restarting it. The change is not persisted across
+    /// restarts.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - the new filter, with the syntax of the `RUST_LOG` environment variable, such as
+    ///   `info,mc_sync::fetch=debug`. Modules which are not matched by any directive log at the info level.
+    ///
+    /// # Returns
+    ///
+    /// * The previous filter, to restore it once done.
+    #[method(name = "setLogFilter")]
+    async fn set_log_filter(&self, filter: String) -> RpcResult<String>;
+
+    /// Creates a backup of the database while the node is running. The node can be restored from it with
+    /// `--restore-backup-from <PATH>`, and resumes the sync after the latest block of the backup. Blocks are not
+    /// stored while the backup is taken.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - the backup directory, on the machine running the node. Backups are incremental: a directory can
+    ///   hold several backups, and the latest one is restored.
+    #[method(name = "backupDatabase")]
+    async fn backup_database(&self, path: PathBuf) -> RpcResult<()>;
+
+    /// Re-executes blocks to fill in the execution resources missing from their receipts, which the feeder gateway
+    /// does not serve for older blocks: the data availability and the total gas consumed. Only the resources of the
+    /// blocks older than Starknet 0.13.2 which are zero are missing, and blocks which are not missing any are not
+    /// re-executed. Blocks whose Starknet version has no versioned constants in the chain config cannot be
+    /// re-executed, and are returned as unsupported.
+    ///
+    /// At most [`MAX_BACKFILL_RESOURCES_BLOCKS`] blocks are backfilled by a call, and the block to continue from is
+    /// returned when the range is longer.
+    ///
+    /// # Arguments
+    ///
+    /// * `from_block` - the first block to backfill.
+    /// * `to_block` - the last block to backfill, included.
+    ///
+    /// # Returns
+    ///
+    /// * The number of receipts which were enriched, the blocks which could not be re-executed, and the block to
+    ///   continue from.
+    #[method(name = "backfillResources")]
+    async fn backfill_resources(&self, from_block: u64, to_block: u64) -> RpcResult<BackfilledResources>;
+
+    /// Pauses the sync. No new block is fetched until the sync is resumed, but the blocks being fetched are still
+    /// imported. The node keeps serving the RPC meanwhile. The sync is not paused anymore after a restart.
+    ///
+    /// # Returns
+    ///
+    /// * The updated sync control.
+    #[method(name = "pauseSync")]
+    async fn pause_sync(&self) -> RpcResult<SyncControl>;
+
+    /// Resumes the sync after `madara_pauseSync`. The sync target, if any, still applies.
+    ///
+    /// # Returns
+    ///
+    /// * The updated sync control.
+    #[method(name = "resumeSync")]
+    async fn resume_sync(&self) -> RpcResult<SyncControl>;
+
+    /// Makes the sync stop after a block, until the target is raised or cleared. Unlike `--n-blocks-to-sync`, the
+    /// node keeps running once the target is reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_n` - the last block to sync, or `null` to sync to the tip of the chain.
+    ///
+    /// # Returns
+    ///
+    /// * The updated sync control.
+    #[method(name = "setSyncTarget")]
+    async fn set_sync_target(&self, block_n: Option<u64>) -> RpcResult<SyncControl>;
+}
+
+#[versioned_rpc("V0_1_0", "madara")]
+pub trait MadaraBlockProductionRpcApi {
+    /// Executes the transactions the next pending tick would take from the mempool on top of the pending block,
+    /// without taking them from the mempool and without storing anything. Only available in sequencer mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_transactions` - the maximum number of mempool transactions to execute, 1000 by default.
+    ///
+    /// # Returns
+    ///
+    /// * The candidate block: the outcome of every transaction, the state diff and the resources used.
+    #[method(name = "buildBlockDryRun")]
+    async fn build_block_dry_run(&self, max_transactions: Option<u64>) -> RpcResult<BlockDryRun>;
+
+    /// Sets the timestamp of the next block. The following blocks continue from this timestamp, so the chain can be
+    /// moved forward or backward in time. The pending block takes it right away if it has no transaction yet. Only
+    /// available on a devnet.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp` - the unix timestamp of the next block, in seconds, at most `i64::MAX`.
+    ///
+    /// # Returns
+    ///
+    /// * The updated devnet control.
+    #[method(name = "setNextBlockTimestamp")]
+    async fn set_next_block_timestamp(&self, timestamp: u64) -> RpcResult<DevnetControl>;
+
+    /// Sets the gas prices of the next blocks, instead of the ones from L1. Only available on a devnet.
+    ///
+    /// # Arguments
+    ///
+    /// * `gas_prices` - the gas prices of the next blocks, or `null` to use the ones from L1 again.
+    ///
+    /// # Returns
+    ///
+    /// * The updated devnet control.
+    #[method(name = "setNextBlockGasPrices")]
+    async fn set_next_block_gas_prices(&self, gas_prices: Option<GasPrices>) -> RpcResult<DevnetControl>;
+
+    /// Closes blocks right away, without waiting for the block time. The first block includes the transactions of
+    /// the pending block and the mempool, the following ones are empty. Only available on a devnet.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_blocks` - the number of blocks to close, 1 by default.
+    ///
+    /// # Returns
+    ///
+    /// * The number of the latest block once they are closed.
+    #[method(name = "mineBlocks")]
+    async fn mine_blocks(&self, n_blocks: Option<u64>) -> RpcResult<u64>;
+}
+
+#[versioned_rpc("V0_1_0", "madara")]
+pub trait MadaraMempoolRpcApi {
+    /// Counts the transactions in the mempool.
+    ///
+    /// # Returns
+    ///
+    /// * The number of transactions which are ready to be included and which are waiting for a lower nonce.
+    #[method(name = "txpoolStatus")]
+    async fn txpool_status(&self) -> RpcResult<TxPoolStatus>;
+
+    /// Lists the transactions in the mempool, grouped by account.
+    ///
+    /// # Arguments
+    ///
+    /// * `sender_address` - only list the transactions of this account.
+    /// * `continuation_token` - the token returned by the previous call, to get the next accounts.
+    /// * `chunk_size` - the maximum number of accounts to return, 100 by default.
+    ///
+    /// # Returns
+    ///
+    /// * The accounts and their nonce, with their transactions and whether each one is ready to be included.
+    #[method(name = "txpoolContent")]
+    async fn txpool_content(
+        &self,
+        sender_address: Option<Felt>,
+        continuation_token: Option<Felt>,
+        chunk_size: Option<u64>,
+    ) -> RpcResult<TxPoolContent>;
+}
+
+/// Madara methods which are also served on the user endpoint: like the `starknet` methods, they are safe to expose to
+/// the users of the node.
+#[versioned_rpc("V0_1_0", "madara")]
+pub trait MadaraUserRpcApi {
+    /// Estimates the fee of transactions like `starknet_estimateFee`, both at the gas prices of the block and at the
+    /// current gas prices, which the next block will be opened with. On a fast-moving fee market, the gas prices of the
+    /// latest block may already be outdated. The current gas prices are the ones of the L1 gas price oracle on a
+    /// sequencer, and the ones of the pending block of the sequencer on a full node. The transactions are executed
+    /// once per set of gas prices.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the transactions to estimate the fee of.
+    /// * `simulation_flags` - the simulation flags, as for `starknet_estimateFee`.
+    /// * `block_id` - the block to execute the transactions on top of.
+    ///
+    /// # Returns
+    ///
+    /// * The estimates at both gas prices, and the current gas prices.
+    #[method(name = "estimateFeeAtCurrentPrices")]
+    async fn estimate_fee_at_current_prices(
+        &self,
+        request: Vec<BroadcastedTxn>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<CurrentPricesFeeEstimates>;
+
+    /// Computes the address a contract is deployed at, so that it can be known before the deployment.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_hash` - the class of the contract.
+    /// * `salt` - the salt of the deployment.
+    /// * `constructor_calldata` - the arguments of the constructor.
+    /// * `deployer` - the account deploying the contract through the Universal Deployer Contract of the chain with
+    ///   `unique` set. Without it, the address is the one of a deploy account transaction, or of a deployment through
+    ///   the UDC with `unique` unset.
+    ///
+    /// # Returns
+    ///
+    /// * The address of the contract.
+    #[method(name = "computeContractAddress")]
+    async fn compute_contract_address(
+        &self,
+        class_hash: Felt,
+        salt: Felt,
+        constructor_calldata: Vec<Felt>,
+        deployer: Option<Felt>,
+    ) -> RpcResult<Felt>;
+
+    /// Submit a SNIP-9 outside execution, to be executed and paid for by the executor account of the node. Only
+    /// available in sequencer mode, when an executor account is configured. The outside execution is rejected when
+    /// its signature is not valid for the user account, or when the fee budget of the user account is exhausted.
+    #[method(name = "addOutsideExecution")]
+    async fn add_outside_execution(
+        &self,
+        outside_execution: OutsideExecutionRequest,
+    ) -> RpcResult<AddInvokeTransactionResult>;
+}
+
+#[versioned_rpc("V0_1_0", "madara")]
+pub trait MadaraExplorerRpcApi {
+    /// Lists the activity of an address as a single timeline: the transactions it sent, the events it emitted and the
+    /// writes to its storage. Only the blocks imported since the node was upgraded to index the activity of addresses
+    /// are covered.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - the address to list the activity of.
+    /// * `from_block` - the first block of the range, the genesis block by default.
+    /// * `to_block` - the last block of the range, the latest block by default.
+    /// * `continuation_token` - the token returned by the previous call, to get the next entries.
+    /// * `chunk_size` - the maximum number of entries to return, 100 by default.
+    ///
+    /// # Returns
+    ///
+    /// * The entries in the range, oldest first. Within a block, transactions come first, then events and storage
+    ///   writes.
+    #[method(name = "getAddressActivity")]
+    async fn get_address_activity(
+        &self,
+        address: Felt,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+        continuation_token: Option<String>,
+        chunk_size: Option<u64>,
+    ) -> RpcResult<AddressActivityPage>;
+
+    /// Returns the events matching the filter like `starknet_getEvents`, but most recent first: from `to_block` down
+    /// to `from_block`, and from the last event of each block. The continuation tokens are only valid for this
+    /// method.
+    #[method(name = "getEventsBackward")]
+    async fn get_events_backward(&self, filter: EventFilterWithPageRequest) -> RpcResult<EventsChunk>;
+
+    /// Returns the root of the storage trie of a contract, which the contract leaves of `starknet_getStorageProof`
+    /// commit to. Like storage proofs, it can only be read for the blocks within `--rpc-storage-proof-max-distance`
+    /// blocks of the latest block.
+    ///
+    /// # Arguments
+    ///
+    /// * `contract_address` - the address of the contract.
+    /// * `block_id` - the block to read the root at. The pending block falls back to the latest block.
+    ///
+    /// # Returns
+    ///
+    /// * The storage root of the contract, zero when it has no storage.
+    #[method(name = "getContractStorageRoot")]
+    async fn get_contract_storage_root(&self, contract_address: Felt, block_id: BlockId) -> RpcResult<Felt>;
 }
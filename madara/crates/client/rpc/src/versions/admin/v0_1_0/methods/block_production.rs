@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use jsonrpsee::core::{async_trait, RpcResult};
+use mc_block_production::dry_run::{build_block_dry_run, DryRunOutcome};
+use mc_db::devnet_control::DevnetControl;
+use mp_block::header::GasPrices;
+use mp_receipt::ExecutionResult;
+use mp_utils::service::MadaraServiceId;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    errors::StarknetRpcApiError,
+    utils::ResultExt,
+    versions::admin::v0_1_0::{
+        BlockDryRun, DryRunTransaction, DryRunTransactionStatus, MadaraBlockProductionRpcApiV0_1_0Server,
+    },
+    Starknet,
+};
+
+/// Number of mempool transactions executed by a dry run when no maximum is given.
+const DRY_RUN_DEFAULT_MAX_TRANSACTIONS: u64 = 1000;
+/// Maximum number of blocks closed by a single `madara_mineBlocks` call.
+const MINE_BLOCKS_MAX: u64 = 1000;
+/// How long `madara_mineBlocks` waits for the requested blocks to be closed.
+const MINE_BLOCKS_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The devnet control methods are only available when the node runs as a devnet and produces blocks.
+fn check_devnet(starknet: &Starknet) -> RpcResult<()> {
+    if !starknet.backend.get_devnet_control().enabled {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: "This method is only available on a devnet".to_string(),
+        }
+        .into());
+    }
+    if !starknet.ctx.service_status(MadaraServiceId::BlockProduction).is_on() {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: "This method is only available while the block production is running".to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl MadaraBlockProductionRpcApiV0_1_0Server for Starknet {
+    /// Runs the block production once against the mempool, without sealing or importing anything
+    ///
+    /// # Arguments
+    ///
+    /// * `max_transactions` - the maximum number of mempool transactions to execute
+    ///
+    /// # Returns
+    ///
+    /// * `block_dry_run` - the transactions of the candidate block and their outcome, its state diff and resources
+    async fn build_block_dry_run(&self, max_transactions: Option<u64>) -> RpcResult<BlockDryRun> {
+        let Some(mempool) = &self.mempool else {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Block production dry runs are not enabled on this node".to_string(),
+            }
+            .into());
+        };
+        if !self.ctx.service_status(MadaraServiceId::BlockProduction).is_on() {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Block production dry runs are only available in sequencer mode".to_string(),
+            }
+            .into());
+        }
+
+        let max_txs = max_transactions.unwrap_or(DRY_RUN_DEFAULT_MAX_TRANSACTIONS) as usize;
+        let dry_run = build_block_dry_run(&self.backend, mempool.as_ref(), max_txs)
+            .or_internal_server_error("Error running the block production dry run")?;
+
+        let transactions = dry_run
+            .transactions
+            .into_iter()
+            .map(|tx| {
+                let (status, reason, actual_fee) = match tx.outcome {
+                    DryRunOutcome::Included(receipt) => {
+                        let actual_fee = Some(receipt.actual_fee().clone().into());
+                        match receipt.execution_result() {
+                            ExecutionResult::Succeeded => (DryRunTransactionStatus::Succeeded, None, actual_fee),
+                            ExecutionResult::Reverted { reason } => {
+                                (DryRunTransactionStatus::Reverted, Some(reason), actual_fee)
+                            }
+                        }
+                    }
+                    DryRunOutcome::Rejected(reason) => (DryRunTransactionStatus::Rejected, Some(reason), None),
+                    DryRunOutcome::NotExecuted => (DryRunTransactionStatus::NotExecuted, None, None),
+                };
+                DryRunTransaction {
+                    transaction_hash: tx.tx_hash,
+                    sender_address: tx.contract_address,
+                    nonce: tx.nonce,
+                    status,
+                    reason,
+                    actual_fee,
+                }
+            })
+            .collect();
+
+        Ok(BlockDryRun {
+            block_number: dry_run.block_n,
+            parent_hash: dry_run.parent_block_hash,
+            transactions,
+            state_diff: dry_run.state_diff.into(),
+            resources: dry_run.bouncer_weights,
+            block_full: dry_run.block_full,
+        })
+    }
+
+    async fn set_next_block_timestamp(&self, timestamp: u64) -> RpcResult<DevnetControl> {
+        check_devnet(self)?;
+        let control = self
+            .backend
+            .set_next_block_timestamp(timestamp)
+            .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: err.to_string() })?;
+        tracing::info!("⏱️  Setting the next block timestamp to {timestamp}");
+        Ok(control)
+    }
+
+    async fn set_next_block_gas_prices(&self, gas_prices: Option<GasPrices>) -> RpcResult<DevnetControl> {
+        check_devnet(self)?;
+        tracing::info!("⛽ Setting the next block gas prices to {gas_prices:?}");
+        Ok(self.backend.set_next_block_gas_prices(gas_prices))
+    }
+
+    async fn mine_blocks(&self, n_blocks: Option<u64>) -> RpcResult<u64> {
+        check_devnet(self)?;
+        let n_blocks = n_blocks.unwrap_or(1);
+        if n_blocks == 0 || n_blocks > MINE_BLOCKS_MAX {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: format!("The number of blocks to mine must be between 1 and {MINE_BLOCKS_MAX}"),
+            }
+            .into());
+        }
+
+        // Subscribe before requesting the blocks, so that none of them is missed.
+        let mut block_info = self.backend.subscribe_block_info();
+        let latest_block_n =
+            self.backend.get_latest_block_n().or_internal_server_error("Getting the latest block number")?;
+        let target_block_n = latest_block_n.map_or(n_blocks - 1, |block_n| block_n + n_blocks);
+        self.backend.request_mine_blocks(n_blocks);
+
+        let wait = async {
+            loop {
+                match block_info.recv().await {
+                    Ok(info) if info.header.block_number >= target_block_n => return Ok(()),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => {
+                        let latest_block_n = self
+                            .backend
+                            .get_latest_block_n()
+                            .or_internal_server_error("Getting the latest block number")?;
+                        if latest_block_n.is_some_and(|block_n| block_n >= target_block_n) {
+                            return Ok(());
+                        }
+                    }
+                    Err(RecvError::Closed) => {
+                        return Err(StarknetRpcApiError::ErrUnexpectedError {
+                            data: "The node is shutting down".to_string(),
+                        })
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(MINE_BLOCKS_TIMEOUT, wait).await.map_err(|_| {
+            StarknetRpcApiError::ErrUnexpectedError {
+                data: format!("Timed out waiting for block #{target_block_n} to be mined"),
+            }
+        })??;
+
+        Ok(target_block_n)
+    }
+}
@@ -0,0 +1,293 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use mc_db::address_activity_db::{AddressActivityKey, AddressActivityKind};
+use mc_db::db_block_id::DbBlockId;
+use mp_block::{BlockId, MadaraBlockInner};
+use mp_rpc::{EventFilterWithPageRequest, EventsChunk};
+use mp_state_update::StateDiff;
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    errors::{StarknetRpcApiError, StarknetRpcResult},
+    utils::{OptionExt, ResultExt},
+    versions::admin::v0_1_0::{AddressActivity, AddressActivityPage, MadaraExplorerRpcApiV0_1_0Server},
+    versions::user::v0_7_1::methods::read::get_events::get_events_backward,
+    versions::user::v0_8_0::methods::read::get_storage_proof::{contract_storage_root, resolve_trie_block_n},
+    Starknet,
+};
+
+/// Number of entries returned by `madara_getAddressActivity` when no chunk size is given.
+const ADDRESS_ACTIVITY_DEFAULT_CHUNK_SIZE: u64 = 100;
+const ADDRESS_ACTIVITY_MAX_CHUNK_SIZE: u64 = 1000;
+
+#[async_trait]
+impl MadaraExplorerRpcApiV0_1_0Server for Starknet {
+    async fn get_address_activity(
+        &self,
+        address: Felt,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+        continuation_token: Option<String>,
+        chunk_size: Option<u64>,
+    ) -> RpcResult<AddressActivityPage> {
+        let chunk_size = chunk_size.unwrap_or(ADDRESS_ACTIVITY_DEFAULT_CHUNK_SIZE);
+        if chunk_size == 0 {
+            return Err(StarknetRpcApiError::InvalidParams { data: "chunk_size must be at least 1".to_string() }.into());
+        }
+        if chunk_size > ADDRESS_ACTIVITY_MAX_CHUNK_SIZE {
+            return Err(StarknetRpcApiError::PageSizeTooBig.into());
+        }
+
+        let start = match continuation_token {
+            Some(token) => parse_token(&token).ok_or(StarknetRpcApiError::InvalidContinuationToken)?,
+            None => AddressActivityKey::block_start(from_block.unwrap_or(0)),
+        };
+        // One more entry is taken to know whether there is a next chunk.
+        let mut entries = self
+            .backend
+            .get_address_activity(&address, start, to_block.unwrap_or(u64::MAX), chunk_size as usize + 1)
+            .or_internal_server_error("Getting address activity")?;
+        let continuation_token =
+            (entries.len() > chunk_size as usize).then(|| format_token(&entries.pop().expect("Not empty")));
+
+        // Entries are sorted by block, so each block is loaded once.
+        let mut block: Option<ActivityBlock> = None;
+        let activity = entries
+            .into_iter()
+            .map(|entry| {
+                if !matches!(&block, Some(block) if block.block_n == entry.block_n) {
+                    block = Some(self.activity_block(entry.block_n)?);
+                }
+                block.as_ref().expect("Block just loaded").resolve(&address, &entry)
+            })
+            .collect::<StarknetRpcResult<_>>()?;
+
+        Ok(AddressActivityPage { activity, continuation_token })
+    }
+
+    async fn get_events_backward(&self, filter: EventFilterWithPageRequest) -> RpcResult<EventsChunk> {
+        Ok(get_events_backward(self, filter).await?)
+    }
+
+    async fn get_contract_storage_root(&self, contract_address: Felt, block_id: BlockId) -> RpcResult<Felt> {
+        let block_n = resolve_trie_block_n(self, block_id)?;
+        contract_storage_root(self, block_n, &contract_address)
+    }
+}
+
+/// The data of a block the entries of the activity of an address point into.
+struct ActivityBlock {
+    block_n: u64,
+    tx_hashes: Vec<Felt>,
+    inner: MadaraBlockInner,
+    state_diff: StateDiff,
+}
+
+impl Starknet {
+    fn activity_block(&self, block_n: u64) -> StarknetRpcResult<ActivityBlock> {
+        let id = DbBlockId::Number(block_n);
+        let info = self
+            .backend
+            .get_block_info(&id)
+            .or_internal_server_error("Getting block info")?
+            .and_then(|info| info.as_nonpending_owned())
+            .ok_or_internal_server_error("Missing block info of an indexed block")?;
+        let inner = self
+            .backend
+            .get_block_inner(&id)
+            .or_internal_server_error("Getting block inner")?
+            .ok_or_internal_server_error("Missing block inner of an indexed block")?;
+        let state_diff = self
+            .backend
+            .get_block_state_diff(&id)
+            .or_internal_server_error("Getting block state diff")?
+            .ok_or_internal_server_error("Missing state diff of an indexed block")?;
+        Ok(ActivityBlock { block_n, tx_hashes: info.tx_hashes, inner, state_diff })
+    }
+}
+
+impl ActivityBlock {
+    fn resolve(&self, address: &Felt, entry: &AddressActivityKey) -> StarknetRpcResult<AddressActivity> {
+        let index = entry.index as usize;
+        let activity = match entry.kind {
+            AddressActivityKind::Transaction => self.tx_hashes.get(index).map(|hash| AddressActivity::Transaction {
+                block_number: self.block_n,
+                transaction_index: index as u64,
+                transaction_hash: *hash,
+            }),
+            AddressActivityKind::Event => self
+                .inner
+                .receipts
+                .iter()
+                .flat_map(|receipt| receipt.events().iter().map(move |event| (receipt.transaction_hash(), event)))
+                .nth(index)
+                .map(|(transaction_hash, event)| AddressActivity::Event {
+                    block_number: self.block_n,
+                    transaction_hash,
+                    event_index: index as u64,
+                    keys: event.keys.clone(),
+                    data: event.data.clone(),
+                }),
+            AddressActivityKind::StorageWrite => self
+                .state_diff
+                .storage_diffs
+                .iter()
+                .find(|diff| diff.address == *address)
+                .and_then(|diff| diff.storage_entries.get(index))
+                .map(|entry| AddressActivity::StorageWrite {
+                    block_number: self.block_n,
+                    key: entry.key,
+                    value: entry.value,
+                }),
+        };
+        activity.ok_or_else_internal_server_error(|| format!("Dangling address activity entry {}", format_token(entry)))
+    }
+}
+
+fn kind_name(kind: AddressActivityKind) -> &'static str {
+    match kind {
+        AddressActivityKind::Transaction => "tx",
+        AddressActivityKind::Event => "event",
+        AddressActivityKind::StorageWrite => "storage",
+    }
+}
+
+/// Continuation tokens are formatted as `<block_n>-<kind>-<index>`.
+fn format_token(entry: &AddressActivityKey) -> String {
+    format!("{}-{}-{}", entry.block_n, kind_name(entry.kind), entry.index)
+}
+
+fn parse_token(token: &str) -> Option<AddressActivityKey> {
+    let mut parts = token.split('-');
+    let block_n = parts.next()?.parse().ok()?;
+    let kind = parts.next()?;
+    let kind = [AddressActivityKind::Transaction, AddressActivityKind::Event, AddressActivityKind::StorageWrite]
+        .into_iter()
+        .find(|k| kind_name(*k) == kind)?;
+    let index = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some(AddressActivityKey { block_n, kind, index })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use mp_block::{Header, MadaraBlockInfo, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_receipt::{Event, InvokeTransactionReceipt, TransactionReceipt};
+    use mp_state_update::{ContractStorageDiffItem, StorageEntry};
+    use mp_transactions::{InvokeTransaction, InvokeTransactionV1, Transaction};
+
+    use crate::test_utils::rpc_test_setup;
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn get_address_activity(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let (account, contract) = (Felt::from(0x100), Felt::from(0x200));
+        for block_n in 0..2 {
+            let tx_hash = Felt::from(0x1000 + block_n);
+            let receipt = TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                transaction_hash: tx_hash,
+                events: vec![Event { from_address: contract, keys: vec![Felt::ONE], data: vec![Felt::from(block_n)] }],
+                ..Default::default()
+            });
+            backend
+                .store_block(
+                    MadaraMaybePendingBlock {
+                        info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                            header: Header { block_number: block_n, ..Default::default() },
+                            block_hash: Felt::from(block_n),
+                            tx_hashes: vec![tx_hash],
+                        }),
+                        inner: mp_block::MadaraBlockInner {
+                            transactions: vec![Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1 {
+                                sender_address: account,
+                                ..Default::default()
+                            }))],
+                            receipts: vec![receipt],
+                        },
+                    },
+                    StateDiff {
+                        storage_diffs: vec![ContractStorageDiffItem {
+                            address: contract,
+                            storage_entries: vec![StorageEntry { key: Felt::TWO, value: Felt::from(block_n) }],
+                        }],
+                        ..Default::default()
+                    },
+                    vec![],
+                    None,
+                    None,
+                )
+                .expect("Storing block");
+        }
+
+        let page = starknet.get_address_activity(account, None, None, None, None).await.unwrap();
+        assert_eq!(
+            page,
+            AddressActivityPage {
+                activity: (0..2)
+                    .map(|block_n| AddressActivity::Transaction {
+                        block_number: block_n,
+                        transaction_index: 0,
+                        transaction_hash: Felt::from(0x1000 + block_n),
+                    })
+                    .collect(),
+                continuation_token: None,
+            }
+        );
+
+        let page = starknet.get_address_activity(contract, None, None, None, Some(3)).await.unwrap();
+        assert_eq!(
+            page.activity,
+            vec![
+                AddressActivity::Event {
+                    block_number: 0,
+                    transaction_hash: Felt::from(0x1000),
+                    event_index: 0,
+                    keys: vec![Felt::ONE],
+                    data: vec![Felt::ZERO],
+                },
+                AddressActivity::StorageWrite { block_number: 0, key: Felt::TWO, value: Felt::ZERO },
+                AddressActivity::Event {
+                    block_number: 1,
+                    transaction_hash: Felt::from(0x1001),
+                    event_index: 0,
+                    keys: vec![Felt::ONE],
+                    data: vec![Felt::ONE],
+                },
+            ]
+        );
+        assert_eq!(page.continuation_token.as_deref(), Some("1-storage-0"));
+        let page = starknet.get_address_activity(contract, None, None, page.continuation_token, Some(3)).await.unwrap();
+        assert_eq!(
+            page,
+            AddressActivityPage {
+                activity: vec![AddressActivity::StorageWrite { block_number: 1, key: Felt::TWO, value: Felt::ONE }],
+                continuation_token: None,
+            }
+        );
+
+        // Block range.
+        let page = starknet.get_address_activity(contract, Some(1), Some(1), None, None).await.unwrap();
+        assert_eq!(page.activity.len(), 2);
+        assert!(starknet.get_address_activity(contract, None, None, Some("1-storage".into()), None).await.is_err());
+    }
+
+    /// An empty chunk would hand back the token it started at, and a client following it would never finish.
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn get_address_activity_chunk_size(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (_backend, starknet) = rpc_test_setup;
+
+        let err = starknet.get_address_activity(Felt::ONE, None, None, None, Some(0)).await.unwrap_err();
+        assert_eq!(err.code(), jsonrpsee::types::error::INVALID_PARAMS_CODE);
+        let err =
+            starknet.get_address_activity(Felt::ONE, None, None, Some("0-tx-0".into()), Some(0)).await.unwrap_err();
+        assert_eq!(err.code(), jsonrpsee::types::error::INVALID_PARAMS_CODE);
+
+        let err = starknet
+            .get_address_activity(Felt::ONE, None, None, None, Some(ADDRESS_ACTIVITY_MAX_CHUNK_SIZE + 1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, StarknetRpcApiError::PageSizeTooBig.into());
+    }
+}
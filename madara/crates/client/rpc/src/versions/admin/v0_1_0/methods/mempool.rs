@@ -0,0 +1,135 @@
+use blockifier::transaction::transaction_types::TransactionType;
+use jsonrpsee::core::{async_trait, RpcResult};
+use mc_mempool::inspect::MempoolTxInfo;
+use mc_mempool::Mempool;
+use mp_block::{BlockId, BlockTag};
+use starknet_types_core::felt::Felt;
+use std::time::SystemTime;
+
+use crate::{
+    errors::{StarknetRpcApiError, StarknetRpcResult},
+    utils::ResultExt,
+    versions::admin::v0_1_0::{
+        MadaraMempoolRpcApiV0_1_0Server, TxPoolAccount, TxPoolContent, TxPoolStatus, TxPoolTransaction,
+        TxPoolTransactionType,
+    },
+    Starknet,
+};
+
+/// Number of accounts returned by `madara_txpoolContent` when no chunk size is given.
+const TXPOOL_CONTENT_DEFAULT_CHUNK_SIZE: u64 = 100;
+const TXPOOL_CONTENT_MAX_CHUNK_SIZE: u64 = 1000;
+
+#[async_trait]
+impl MadaraMempoolRpcApiV0_1_0Server for Starknet {
+    async fn txpool_status(&self) -> RpcResult<TxPoolStatus> {
+        let status = self.mempool()?.status();
+        Ok(TxPoolStatus { ready: status.ready as u64, pending: status.pending as u64 })
+    }
+
+    async fn txpool_content(
+        &self,
+        sender_address: Option<Felt>,
+        continuation_token: Option<Felt>,
+        chunk_size: Option<u64>,
+    ) -> RpcResult<TxPoolContent> {
+        let chunk_size = chunk_size.unwrap_or(TXPOOL_CONTENT_DEFAULT_CHUNK_SIZE);
+        if chunk_size == 0 {
+            return Err(StarknetRpcApiError::InvalidParams { data: "chunk_size must be at least 1".to_string() }.into());
+        }
+        if chunk_size > TXPOOL_CONTENT_MAX_CHUNK_SIZE {
+            return Err(StarknetRpcApiError::PageSizeTooBig.into());
+        }
+        let mempool = self.mempool()?;
+
+        let start_at = continuation_token.or(sender_address).unwrap_or(Felt::ZERO);
+        // One more account is taken to know whether there is a next chunk.
+        let mut accounts = mempool.accounts(start_at, chunk_size as usize + 1);
+        if let Some(sender_address) = sender_address {
+            accounts.retain(|account| account.contract_address == sender_address);
+        }
+        let continuation_token =
+            (accounts.len() > chunk_size as usize).then(|| accounts.pop().expect("Not empty").contract_address);
+
+        let accounts = accounts
+            .into_iter()
+            .map(|account| {
+                let nonce = self
+                    .backend
+                    .get_contract_nonce_at(&BlockId::Tag(BlockTag::Latest), &account.contract_address)
+                    .or_internal_server_error("Getting account nonce")?
+                    .unwrap_or_default();
+                Ok(TxPoolAccount {
+                    address: account.contract_address,
+                    nonce,
+                    transactions: account.transactions.into_iter().map(to_rpc_tx).collect(),
+                })
+            })
+            .collect::<StarknetRpcResult<_>>()?;
+
+        Ok(TxPoolContent { accounts, continuation_token })
+    }
+}
+
+impl Starknet {
+    fn mempool(&self) -> StarknetRpcResult<&Mempool> {
+        self.mempool.as_deref().ok_or_else(|| StarknetRpcApiError::ErrUnexpectedError {
+            data: "The mempool is not available on this node".to_string(),
+        })
+    }
+}
+
+fn to_rpc_tx(tx: MempoolTxInfo) -> TxPoolTransaction {
+    TxPoolTransaction {
+        transaction_hash: tx.tx_hash,
+        nonce: tx.nonce,
+        tx_type: match tx.tx_type {
+            TransactionType::Declare => TxPoolTransactionType::Declare,
+            TransactionType::DeployAccount => TxPoolTransactionType::DeployAccount,
+            TransactionType::InvokeFunction => TxPoolTransactionType::Invoke,
+            TransactionType::L1Handler => TxPoolTransactionType::L1Handler,
+        },
+        ready: tx.ready,
+        arrived_at: tx.arrived_at.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use mc_mempool::{GasPriceProvider, MempoolLimits};
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    fn with_empty_mempool(backend: Arc<MadaraBackend>, rpc: Starknet) -> Starknet {
+        let limits = MempoolLimits::new(backend.chain_config());
+        rpc.with_mempool(Arc::new(Mempool::new(backend, Arc::new(GasPriceProvider::new()), limits)))
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_txpool_content_empty(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let rpc = with_empty_mempool(backend, rpc);
+
+        let content = rpc.txpool_content(None, None, None).await.unwrap();
+        assert!(content.accounts.is_empty());
+        assert_eq!(content.continuation_token, None);
+    }
+
+    /// An empty chunk would hand back its own continuation token, and a client following it would never finish.
+    #[rstest]
+    #[tokio::test]
+    async fn test_txpool_content_chunk_size(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let rpc = with_empty_mempool(backend, rpc);
+
+        let err = rpc.txpool_content(None, None, Some(0)).await.unwrap_err();
+        assert_eq!(err.code(), jsonrpsee::types::error::INVALID_PARAMS_CODE);
+
+        let err = rpc.txpool_content(None, None, Some(TXPOOL_CONTENT_MAX_CHUNK_SIZE + 1)).await.unwrap_err();
+        assert_eq!(err, StarknetRpcApiError::PageSizeTooBig.into());
+    }
+}
@@ -1,3 +1,7 @@
+pub mod block_production;
+pub mod explorer;
+pub mod mempool;
 pub mod services;
 pub mod status;
+pub mod user;
 pub mod write;
@@ -1,15 +1,34 @@
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 use jsonrpsee::core::{async_trait, RpcResult};
+use mc_db::sync_control::SyncControl;
+use mc_db::MadaraBackend;
+use mc_exec::transaction::to_blockifier_transaction;
+use mc_exec::ExecutionContext;
+use mp_block::BlockId;
+use mp_chain_config::StarknetVersion;
+use mp_receipt::L1Gas;
 use mp_utils::service::{MadaraServiceId, MadaraServiceStatus, ServiceContext};
+use mp_utils::PerfStopwatch;
+use starknet_api::transaction::TransactionHash;
 
+use crate::errors::StarknetRpcResult;
+use crate::utils::ResultExt;
 use crate::{
-    versions::admin::v0_1_0::{MadaraServicesRpcApiV0_1_0Server, ServiceRequest},
-    Starknet,
+    versions::admin::v0_1_0::{
+        BackfilledResources, MadaraServicesRpcApiV0_1_0Server, ServiceRequest, MAX_BACKFILL_RESOURCES_BLOCKS,
+    },
+    Starknet, StarknetRpcApiError,
 };
 
 const RESTART_INTERVAL: Duration = Duration::from_secs(5);
 
+/// The feeder gateway serves the data availability and the total gas consumed of the receipts from this version. A
+/// zero resource in an older block was not served, while it is the actual value in a newer block.
+const RESOURCES_SERVED_SINCE: StarknetVersion = StarknetVersion::V0_13_2;
+
 #[async_trait]
 impl MadaraServicesRpcApiV0_1_0Server for Starknet {
     async fn service(&self, service: Vec<MadaraServiceId>, status: ServiceRequest) -> RpcResult<MadaraServiceStatus> {
@@ -20,6 +39,13 @@ impl MadaraServicesRpcApiV0_1_0Server for Starknet {
                 Some(()),
             ))
         } else {
+            // A source of blocks cannot start while a block is being submitted.
+            let _submission = match (&self.block_submission, &status) {
+                (Some(submission), ServiceRequest::Start | ServiceRequest::Restart) => {
+                    submission.lock_for_services(&service).await
+                }
+                _ => None,
+            };
             match status {
                 ServiceRequest::Start => service_start(&self.ctx, &service),
                 ServiceRequest::Stop => service_stop(&self.ctx, &service),
@@ -27,6 +53,151 @@ impl MadaraServicesRpcApiV0_1_0Server for Starknet {
             }
         }
     }
+
+    #[tracing::instrument(skip(self), fields(module = "Admin"))]
+    async fn set_log_filter(&self, filter: String) -> RpcResult<String> {
+        let previous = mc_analytics::set_log_filter(&filter).map_err(|err| {
+            jsonrpsee::types::ErrorObject::owned(
+                jsonrpsee::types::ErrorCode::InvalidParams.code(),
+                format!("{err:#}"),
+                Some(()),
+            )
+        })?;
+        tracing::info!("📝 Log filter changed from `{previous}` to `{filter}`");
+        Ok(previous)
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "Admin"))]
+    async fn backup_database(&self, path: PathBuf) -> RpcResult<()> {
+        tracing::info!("⏳ Backing up database to {}...", path.display());
+        let sw = PerfStopwatch::new();
+        self.backend.backup_to(path).await.map_err(|err| StarknetRpcApiError::ErrUnexpectedError {
+            data: format!("Failed to back up the database: {err:#}"),
+        })?;
+        tracing::info!("✅ Database backup is done ({:?})", sw.elapsed());
+        Ok(())
+    }
+
+    async fn backfill_resources(&self, from_block: u64, to_block: u64) -> RpcResult<BackfilledResources> {
+        if from_block > to_block {
+            return Err(jsonrpsee::types::ErrorObject::owned(
+                jsonrpsee::types::ErrorCode::InvalidParams.code(),
+                format!("The range of blocks #{from_block} to #{to_block} is empty"),
+                Some(()),
+            ));
+        }
+        let last_block = to_block.min(from_block.saturating_add(MAX_BACKFILL_RESOURCES_BLOCKS - 1));
+        tracing::info!("⏳ Backfilling the execution resources of blocks #{from_block} to #{last_block}...");
+        let sw = PerfStopwatch::new();
+        let mut res = BackfilledResources { enriched_receipts: 0, unsupported_blocks: vec![], next_block: None };
+        for block_n in from_block..=last_block {
+            if self.ctx.is_cancelled() {
+                res.next_block = Some(block_n);
+                break;
+            }
+            let backend = self.clone_backend();
+            match tokio::task::spawn_blocking(move || backfill_block_execution_resources(backend, block_n))
+                .await
+                .or_internal_server_error("Joining the re-execution task")??
+            {
+                Some(enriched) => res.enriched_receipts += enriched,
+                None => res.unsupported_blocks.push(block_n),
+            }
+        }
+        if res.next_block.is_none() && last_block < to_block {
+            res.next_block = Some(last_block + 1);
+        }
+        if !res.unsupported_blocks.is_empty() {
+            tracing::warn!(
+                "⚠️ {} blocks miss execution resources but cannot be re-executed, as the chain config has no versioned \
+                 constants for their Starknet version",
+                res.unsupported_blocks.len()
+            );
+        }
+        tracing::info!(
+            "✅ Enriched the execution resources of {} receipts ({:?})",
+            res.enriched_receipts,
+            sw.elapsed()
+        );
+        Ok(res)
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "Admin"))]
+    async fn pause_sync(&self) -> RpcResult<SyncControl> {
+        tracing::info!("⏸️  Pausing the sync");
+        Ok(self.backend.pause_sync())
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "Admin"))]
+    async fn resume_sync(&self) -> RpcResult<SyncControl> {
+        tracing::info!("▶️  Resuming the sync");
+        Ok(self.backend.resume_sync())
+    }
+
+    #[tracing::instrument(skip(self), fields(module = "Admin"))]
+    async fn set_sync_target(&self, block_n: Option<u64>) -> RpcResult<SyncControl> {
+        match block_n {
+            Some(block_n) => tracing::info!("🎯 Syncing up to block #{block_n}"),
+            None => tracing::info!("🎯 Syncing to the tip of the chain"),
+        }
+        Ok(self.backend.set_sync_target(block_n))
+    }
+}
+
+/// Re-executes block `block_n` if its receipts are missing execution resources, and stores the receipts with the
+/// missing resources filled in. Returns the number of receipts which were enriched, or `None` when the block is
+/// missing resources but cannot be re-executed.
+fn backfill_block_execution_resources(backend: Arc<MadaraBackend>, block_n: u64) -> StarknetRpcResult<Option<u64>> {
+    let block_id = BlockId::Number(block_n);
+    let block = backend
+        .get_block(&block_id)
+        .or_internal_server_error("Error getting block from storage")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+
+    let protocol_version = *block.info.protocol_version();
+    let is_missing = |gas: &L1Gas| protocol_version < RESOURCES_SERVED_SINCE && *gas == L1Gas::default();
+    let missing_resources = block.inner.receipts.iter().any(|receipt| {
+        let resources = receipt.execution_resources();
+        is_missing(&resources.data_availability) || is_missing(&resources.total_gas_consumed)
+    });
+    if !missing_resources {
+        return Ok(Some(0));
+    }
+    if backend.chain_config().exec_constants_by_protocol_version(protocol_version).is_err() {
+        return Ok(None);
+    }
+
+    let exec_context = ExecutionContext::new_at_block_start(Arc::clone(&backend), &block.info)?;
+    let transactions: Vec<_> = block
+        .inner
+        .transactions
+        .into_iter()
+        .zip(block.info.tx_hashes())
+        .map(|(tx, hash)| {
+            to_blockifier_transaction(Arc::clone(&backend), block_id.clone(), tx, &TransactionHash(*hash))
+                .or_internal_server_error("Failed to convert transaction to blockifier format")
+        })
+        .collect::<Result<_, _>>()?;
+    let execution_results = exec_context.re_execute_transactions([], transactions, true, true)?;
+
+    let mut receipts = block.inner.receipts;
+    let mut enriched = 0;
+    for (receipt, result) in receipts.iter_mut().zip(&execution_results) {
+        let resources = receipt.execution_resources_mut();
+        let computed = &result.execution_info.transaction_receipt;
+        let mut updated = false;
+        if is_missing(&resources.data_availability) {
+            resources.data_availability = computed.da_gas.into();
+            updated = true;
+        }
+        if is_missing(&resources.total_gas_consumed) {
+            resources.total_gas_consumed = computed.gas.into();
+            updated = true;
+        }
+        enriched += u64::from(updated);
+    }
+    backend.store_block_receipts(block_n, receipts).or_internal_server_error("Storing the enriched receipts")?;
+    Ok(Some(enriched))
 }
 
 fn service_start(ctx: &ServiceContext, svcs: &[MadaraServiceId]) -> RpcResult<MadaraServiceStatus> {
@@ -65,3 +236,32 @@ async fn service_restart(ctx: &ServiceContext, svcs: &[MadaraServiceId]) -> RpcR
 
     Ok(status)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{make_sample_chain_for_block_getters, TestTransactionProvider};
+    use mp_chain_config::ChainConfig;
+
+    /// Block #0 of the sample chain is a Starknet 0.13.1.1 block whose receipts have no execution resources, and
+    /// blocks #1 and #2 are Starknet 0.13.2 blocks, whose zero resources were served by the gateway.
+    #[tokio::test]
+    async fn test_backfill_resources() {
+        let mut chain_config = ChainConfig::madara_test();
+        chain_config.versioned_constants.0.retain(|version, _| *version >= StarknetVersion::V0_13_2);
+        let backend = MadaraBackend::open_for_testing(Arc::new(chain_config));
+        make_sample_chain_for_block_getters(&backend);
+        let rpc = Starknet::new(
+            backend,
+            Arc::new(TestTransactionProvider),
+            Default::default(),
+            ServiceContext::new_for_testing(),
+        );
+
+        assert_eq!(
+            rpc.backfill_resources(0, 2).await.unwrap(),
+            BackfilledResources { enriched_receipts: 0, unsupported_blocks: vec![0], next_block: None }
+        );
+        assert!(rpc.backfill_resources(2, 1).await.is_err());
+    }
+}
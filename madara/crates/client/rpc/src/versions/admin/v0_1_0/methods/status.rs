@@ -1,8 +1,12 @@
 use std::time::{Duration, SystemTime};
 
-use jsonrpsee::core::async_trait;
+use jsonrpsee::core::{async_trait, RpcResult};
+use mc_db::pipeline_gaps::PipelineGaps;
+use mc_db::sync_history_db::SyncHistoryEntry;
+use mc_db::sync_status::SyncStatus;
+use tokio::sync::broadcast::error::RecvError;
 
-use crate::{errors::ErrorExtWs, versions::admin::v0_1_0::MadaraStatusRpcApiV0_1_0Server, Starknet};
+use crate::{errors::ErrorExtWs, utils::ResultExt, versions::admin::v0_1_0::MadaraStatusRpcApiV0_1_0Server, Starknet};
 
 #[async_trait]
 impl MadaraStatusRpcApiV0_1_0Server for Starknet {
@@ -35,8 +39,162 @@ impl MadaraStatusRpcApiV0_1_0Server for Starknet {
 
         Ok(())
     }
+
+    async fn subscribe_sync_status(
+        &self,
+        subscription_sink: jsonrpsee::PendingSubscriptionSink,
+    ) -> jsonrpsee::core::SubscriptionResult {
+        // Subscribe before accepting, so that no update is missed once the client is notified of the subscription.
+        let mut rx = self.backend.subscribe_chain_head();
+        let sink =
+            subscription_sink.accept().await.or_internal_server_error("Failed to establish websocket connection")?;
+
+        let mut ctx = self.ctx.clone();
+        loop {
+            let update = tokio::select! {
+                update = rx.recv() => update,
+                _ = sink.closed() => return Ok(()),
+                _ = ctx.cancelled() => return Ok(()),
+            };
+            let update = match update {
+                Ok(update) => update,
+                // Every update contains the whole chain head, so skipping some of them is fine.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return Ok(()),
+            };
+
+            let msg = jsonrpsee::SubscriptionMessage::from_json(&update).or_else_internal_server_error(|| {
+                format!("Failed to create response message for block {}", update.block_n)
+            })?;
+            sink.send(msg).await.or_internal_server_error("Failed to respond to websocket request")?;
+        }
+    }
+
+    async fn sync_history(&self, since: Option<u64>) -> RpcResult<Vec<SyncHistoryEntry>> {
+        Ok(self.backend.get_sync_history(since.unwrap_or(0)).or_internal_server_error("Getting sync history")?)
+    }
+
+    async fn sync_status(&self) -> RpcResult<Option<SyncStatus>> {
+        Ok(self.backend.get_sync_status())
+    }
+
+    async fn pipeline_gaps(&self) -> RpcResult<PipelineGaps> {
+        Ok(self.backend.get_pipeline_gaps().or_internal_server_error("Getting pipeline gaps")?)
+    }
 }
 
 fn unix_now() -> u64 {
     SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use jsonrpsee::ws_client::WsClientBuilder;
+    use mc_db::chain_head::{ChainHead, ChainHeadUpdate, PipelineStage};
+    use mc_db::sync_history_db::SyncStageTimes;
+
+    use crate::{test_utils::rpc_test_setup, versions::admin::v0_1_0::MadaraStatusRpcApiV0_1_0Client};
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_sync_status(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        // Server will be stopped once this is dropped
+        let _server_handle = server.start(MadaraStatusRpcApiV0_1_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+
+        let mut sub = client.subscribe_sync_status().await.expect("madara_subscribeSyncStatus");
+
+        backend
+            .store_block(
+                mp_block::MadaraMaybePendingBlock {
+                    info: mp_block::MadaraMaybePendingBlockInfo::NotPending(mp_block::MadaraBlockInfo {
+                        header: mp_block::Header { block_number: 0, ..Default::default() },
+                        block_hash: starknet_types_core::felt::Felt::ZERO,
+                        tx_hashes: vec![],
+                    }),
+                    inner: mp_block::MadaraBlockInner { transactions: vec![], receipts: vec![] },
+                },
+                mp_state_update::StateDiff::default(),
+                vec![],
+                None,
+                None,
+            )
+            .expect("Storing block");
+        backend.write_last_confirmed_block(0).expect("Writing last confirmed block");
+
+        let update = sub.next().await.expect("Waiting for update").expect("Waiting for update");
+        assert_eq!(
+            update,
+            ChainHeadUpdate {
+                stage: PipelineStage::BlockImport,
+                block_n: 0,
+                chain_head: ChainHead {
+                    latest_block_n: Some(0),
+                    l1_confirmed_block_n: None,
+                    oldest_backfilled_block: None,
+                    oldest_state_block_n: None,
+                    global_trie_block_n: Some(0)
+                }
+            }
+        );
+        let update = sub.next().await.expect("Waiting for update").expect("Waiting for update");
+        assert_eq!(
+            update,
+            ChainHeadUpdate {
+                stage: PipelineStage::L1Confirmation,
+                block_n: 0,
+                chain_head: ChainHead {
+                    latest_block_n: Some(0),
+                    l1_confirmed_block_n: Some(0),
+                    oldest_backfilled_block: None,
+                    oldest_state_block_n: None,
+                    global_trie_block_n: Some(0)
+                }
+            }
+        );
+    }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn sync_history(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let entry = |timestamp| SyncHistoryEntry {
+            timestamp,
+            period_secs: 60.0,
+            latest_block_n: Some(timestamp / 10),
+            blocks_imported: 6,
+            blocks_per_second: 0.1,
+            stage_secs: SyncStageTimes { fetch: 1.0, conversion: 2.0, verify_apply: 3.0 },
+        };
+        backend.store_sync_history_entry(&entry(60)).expect("Storing sync history entry");
+        backend.store_sync_history_entry(&entry(120)).expect("Storing sync history entry");
+
+        assert_eq!(starknet.sync_history(None).await.unwrap(), vec![entry(60), entry(120)]);
+        assert_eq!(starknet.sync_history(Some(61)).await.unwrap(), vec![entry(120)]);
+    }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn sync_status(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        assert_eq!(starknet.sync_status().await.unwrap(), None);
+
+        let status = SyncStatus {
+            timestamp: 60,
+            starting_block_n: Some(0),
+            latest_block_n: Some(10),
+            highest_block_n: Some(30),
+            highest_block_hash: Some(starknet_types_core::felt::Felt::ONE),
+            blocks_per_second: 2.0,
+            classes_per_second: 0.5,
+            eta_secs: Some(10.0),
+        };
+        backend.set_sync_status(status);
+        assert_eq!(starknet.sync_status().await.unwrap(), Some(status));
+    }
+}
@@ -0,0 +1,182 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use mp_block::header::GasPrices;
+use mp_block::{BlockId, BlockTag, MadaraMaybePendingBlockInfo};
+use mp_rpc::{AddInvokeTransactionResult, BroadcastedTxn, SimulationFlagForEstimateFee};
+use mp_transactions::compute_hash::calculate_udc_contract_address;
+use mp_utils::service::MadaraServiceId;
+use starknet_types_core::felt::Felt;
+
+use crate::{
+    errors::{StarknetRpcApiError, StarknetRpcResult},
+    outside_execution::OutsideExecutionRequest,
+    versions::admin::v0_1_0::{CurrentPricesFeeEstimates, MadaraUserRpcApiV0_1_0Server},
+    versions::user::v0_7_1::methods::read::estimate_fee::estimate_fee_at,
+    Starknet,
+};
+
+#[async_trait]
+impl MadaraUserRpcApiV0_1_0Server for Starknet {
+    async fn estimate_fee_at_current_prices(
+        &self,
+        request: Vec<BroadcastedTxn>,
+        simulation_flags: Vec<SimulationFlagForEstimateFee>,
+        block_id: BlockId,
+    ) -> RpcResult<CurrentPricesFeeEstimates> {
+        let current_gas_prices = self.current_gas_prices()?;
+        let block_info = self.get_block_info(&block_id)?;
+        let at_block_prices = estimate_fee_at(self, request.clone(), &simulation_flags, &block_info)?;
+
+        let mut current_block_info = block_info;
+        match &mut current_block_info {
+            MadaraMaybePendingBlockInfo::Pending(block) => block.header.l1_gas_price = current_gas_prices.clone(),
+            MadaraMaybePendingBlockInfo::NotPending(block) => block.header.l1_gas_price = current_gas_prices.clone(),
+        }
+        let at_current_prices = estimate_fee_at(self, request, &simulation_flags, &current_block_info)?;
+
+        Ok(CurrentPricesFeeEstimates { at_block_prices, at_current_prices, current_gas_prices })
+    }
+
+    async fn compute_contract_address(
+        &self,
+        class_hash: Felt,
+        salt: Felt,
+        constructor_calldata: Vec<Felt>,
+        deployer: Option<Felt>,
+    ) -> RpcResult<Felt> {
+        let udc_address = self.backend.chain_config().udc_address;
+        Ok(calculate_udc_contract_address(salt, class_hash, &constructor_calldata, deployer, udc_address))
+    }
+
+    async fn add_outside_execution(
+        &self,
+        outside_execution: OutsideExecutionRequest,
+    ) -> RpcResult<AddInvokeTransactionResult> {
+        let Some(outside_executor) = &self.outside_executor else {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Outside execution is not enabled on this node".to_string(),
+            }
+            .into());
+        };
+        if !self.ctx.service_status(MadaraServiceId::BlockProduction).is_on() {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Outside execution is only available in sequencer mode".to_string(),
+            }
+            .into());
+        }
+
+        outside_executor.submit(self, outside_execution).await
+    }
+}
+
+impl Starknet {
+    /// The gas prices the next block will be opened with. A sequencer takes them from its L1 gas price oracle, through
+    /// the mempool. A full node has no oracle running, and takes the ones of the pending block, which the sequencer of
+    /// the chain opened with its own oracle.
+    fn current_gas_prices(&self) -> StarknetRpcResult<GasPrices> {
+        if let Some(mempool) = &self.mempool {
+            return Ok(mempool.next_block_gas_prices());
+        }
+        Ok(match self.get_block_info(&BlockId::Tag(BlockTag::Pending))? {
+            MadaraMaybePendingBlockInfo::Pending(block) => block.header.l1_gas_price,
+            MadaraMaybePendingBlockInfo::NotPending(block) => block.header.l1_gas_price,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outside_execution::{OutsideExecution, OutsideExecutionConfig, OutsideExecutor};
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use mp_block::header::PendingHeader;
+    use mp_block::{MadaraBlockInner, MadaraMaybePendingBlock, MadaraPendingBlockInfo};
+    use mp_utils::crypto::ZeroingPrivateKey;
+    use rstest::rstest;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn outside_execution() -> OutsideExecutionRequest {
+        OutsideExecutionRequest {
+            account_address: Felt::from(0x42),
+            outside_execution: OutsideExecution {
+                caller: Felt::from(0x99),
+                nonce: Felt::ONE,
+                execute_after: 0,
+                execute_before: u64::MAX,
+                calls: vec![],
+            },
+            signature: vec![],
+            version: Default::default(),
+        }
+    }
+
+    /// Without a mempool, as on a full node, the current gas prices are the ones of the pending block.
+    #[rstest]
+    fn test_current_gas_prices_full_node(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let l1_gas_price = GasPrices {
+            eth_l1_gas_price: 1,
+            strk_l1_gas_price: 2,
+            eth_l1_data_gas_price: 3,
+            strk_l1_data_gas_price: 4,
+        };
+        backend
+            .store_block(
+                MadaraMaybePendingBlock {
+                    info: MadaraMaybePendingBlockInfo::Pending(MadaraPendingBlockInfo {
+                        header: PendingHeader { l1_gas_price: l1_gas_price.clone(), ..Default::default() },
+                        tx_hashes: vec![],
+                    }),
+                    inner: MadaraBlockInner { transactions: vec![], receipts: vec![] },
+                },
+                Default::default(),
+                vec![],
+                None,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(rpc.current_gas_prices().unwrap(), l1_gas_price);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_outside_execution_not_enabled(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (_backend, rpc) = rpc_test_setup;
+
+        let err = rpc.add_outside_execution(outside_execution()).await.unwrap_err();
+        assert_eq!(
+            err,
+            StarknetRpcApiError::ErrUnexpectedError {
+                data: "Outside execution is not enabled on this node".to_string()
+            }
+            .into()
+        );
+    }
+
+    /// Outside executions are served on the user endpoint, but only a sequencer executes them.
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_outside_execution_full_node(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (_backend, rpc) = rpc_test_setup;
+        let rpc = rpc.with_outside_executor(Arc::new(OutsideExecutor::new(OutsideExecutionConfig {
+            account_address: Felt::from(0x99),
+            private_key: Arc::new(ZeroingPrivateKey::default()),
+            max_l1_gas: 10_000,
+            fee_budget: 1_000,
+            total_fee_budget: 2_000,
+            budget_period: Duration::from_secs(3600),
+        })));
+        rpc.ctx.service_remove(MadaraServiceId::BlockProduction);
+
+        let err = rpc.add_outside_execution(outside_execution()).await.unwrap_err();
+        assert_eq!(
+            err,
+            StarknetRpcApiError::ErrUnexpectedError {
+                data: "Outside execution is only available in sequencer mode".to_string()
+            }
+            .into()
+        );
+    }
+}
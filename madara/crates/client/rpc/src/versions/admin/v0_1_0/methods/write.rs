@@ -1,8 +1,13 @@
 use jsonrpsee::core::{async_trait, RpcResult};
+use mc_block_import::UnverifiedFullBlock;
 use mp_rpc::ClassAndTxnHash;
 use mp_transactions::BroadcastedDeclareTransactionV0;
 
-use crate::{versions::admin::v0_1_0::MadaraWriteRpcApiV0_1_0Server, Starknet};
+use crate::{
+    errors::StarknetRpcApiError,
+    versions::admin::v0_1_0::{MadaraWriteRpcApiV0_1_0Server, SubmittedBlock},
+    Starknet,
+};
 
 #[async_trait]
 impl MadaraWriteRpcApiV0_1_0Server for Starknet {
@@ -21,4 +26,25 @@ impl MadaraWriteRpcApiV0_1_0Server for Starknet {
     ) -> RpcResult<ClassAndTxnHash> {
         self.add_transaction_provider.add_declare_v0_transaction(declare_transaction).await
     }
+
+    /// Import a block built outside of the node
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - the complete block, with its state diff and declared classes
+    /// * `key` - the block submission key of the node
+    ///
+    /// # Returns
+    ///
+    /// * `submitted_block` - the number and hash of the imported block
+    async fn submit_full_block(&self, block: UnverifiedFullBlock, key: String) -> RpcResult<SubmittedBlock> {
+        let Some(block_submission) = &self.block_submission else {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Block submission is not enabled on this node".to_string(),
+            }
+            .into());
+        };
+
+        block_submission.submit(self, block, &key).await
+    }
 }
@@ -65,7 +65,7 @@ pub trait StarknetReadRpcApi {
     fn get_block_transaction_count(&self, block_id: BlockId) -> RpcResult<u128>;
 
     /// Estimate the fee associated with transaction
-    #[method(name = "estimateFee", and_versions = ["V0_8_0"])]
+    #[method(name = "estimateFee")]
     async fn estimate_fee(
         &self,
         request: Vec<BroadcastedTxn>,
@@ -74,19 +74,19 @@ pub trait StarknetReadRpcApi {
     ) -> RpcResult<Vec<FeeEstimate>>;
 
     /// Estimate the L2 fee of a message sent on L1
-    #[method(name = "estimateMessageFee", and_versions = ["V0_8_0"])]
+    #[method(name = "estimateMessageFee")]
     async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate>;
 
     /// Get block information with full transactions and receipts given the block id
-    #[method(name = "getBlockWithReceipts", and_versions = ["V0_8_0"])]
+    #[method(name = "getBlockWithReceipts")]
     async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<StarknetGetBlockWithTxsAndReceiptsResult>;
 
     /// Get block information with transaction hashes given the block id
-    #[method(name = "getBlockWithTxHashes", and_versions = ["V0_8_0"])]
+    #[method(name = "getBlockWithTxHashes")]
     fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithTxHashes>;
 
     /// Get block information with full transactions given the block id
-    #[method(name = "getBlockWithTxs", and_versions = ["V0_8_0"])]
+    #[method(name = "getBlockWithTxs")]
     fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithTxs>;
 
     /// Get the contract class at a given contract address for a given block id
@@ -115,15 +115,15 @@ pub trait StarknetReadRpcApi {
     fn get_storage_at(&self, contract_address: Felt, key: Felt, block_id: BlockId) -> RpcResult<Felt>;
 
     /// Get the details of a transaction by a given block id and index
-    #[method(name = "getTransactionByBlockIdAndIndex", and_versions = ["V0_8_0"])]
+    #[method(name = "getTransactionByBlockIdAndIndex")]
     fn get_transaction_by_block_id_and_index(&self, block_id: BlockId, index: u64) -> RpcResult<TxnWithHash>;
 
     /// Returns the information about a transaction by transaction hash.
-    #[method(name = "getTransactionByHash", and_versions = ["V0_8_0"])]
+    #[method(name = "getTransactionByHash")]
     fn get_transaction_by_hash(&self, transaction_hash: Felt) -> RpcResult<TxnWithHash>;
 
     /// Returns the receipt of a transaction by transaction hash.
-    #[method(name = "getTransactionReceipt", and_versions = ["V0_8_0"])]
+    #[method(name = "getTransactionReceipt")]
     async fn get_transaction_receipt(&self, transaction_hash: Felt) -> RpcResult<TxnReceiptWithBlockInfo>;
 
     /// Gets the Transaction Status, Including Mempool Status and Execution Details
@@ -142,7 +142,7 @@ pub trait StarknetReadRpcApi {
 #[versioned_rpc("V0_7_1", "starknet")]
 pub trait StarknetTraceRpcApi {
     /// Returns the execution trace of a transaction by simulating it in the runtime.
-    #[method(name = "simulateTransactions", and_versions = ["V0_8_0"])]
+    #[method(name = "simulateTransactions")]
     async fn simulate_transactions(
         &self,
         block_id: BlockId,
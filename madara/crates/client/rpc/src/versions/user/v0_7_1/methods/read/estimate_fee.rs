@@ -4,8 +4,8 @@ use crate::utils::ResultExt;
 use crate::versions::user::v0_7_1::methods::trace::trace_transaction::EXECUTION_UNSUPPORTED_BELOW_VERSION;
 use crate::Starknet;
 use mc_exec::ExecutionContext;
-use mp_block::BlockId;
-use mp_rpc::{BroadcastedTxn, FeeEstimate, SimulationFlagForEstimateFee};
+use mp_block::{BlockId, MadaraMaybePendingBlockInfo};
+use mp_rpc::{BroadcastedInvokeTxn, BroadcastedTxn, FeeEstimate, SimulationFlagForEstimateFee};
 use mp_transactions::BroadcastedTransactionExt;
 use std::sync::Arc;
 
@@ -27,13 +27,27 @@ pub async fn estimate_fee(
 ) -> StarknetRpcResult<Vec<FeeEstimate>> {
     tracing::debug!("estimate fee on block_id {block_id:?}");
     let block_info = starknet.get_block_info(&block_id)?;
+    estimate_fee_at(starknet, request, &simulation_flags, &block_info)
+}
+
+/// Estimates the fee of the transactions on top of the block `block_info`, at the gas prices of its header.
+pub(crate) fn estimate_fee_at(
+    starknet: &Starknet,
+    request: Vec<BroadcastedTxn>,
+    simulation_flags: &[SimulationFlagForEstimateFee],
+    block_info: &MadaraMaybePendingBlockInfo,
+) -> StarknetRpcResult<Vec<FeeEstimate>> {
     let starknet_version = *block_info.protocol_version();
 
     if starknet_version < EXECUTION_UNSUPPORTED_BELOW_VERSION {
         return Err(StarknetRpcApiError::UnsupportedTxnVersion);
     }
+    // Transactions with a custom version are only executed once dispatched by the mempool.
+    if request.iter().any(|tx| matches!(tx, BroadcastedTxn::Invoke(BroadcastedInvokeTxn::Custom(_)))) {
+        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    }
 
-    let exec_context = ExecutionContext::new_at_block_end(Arc::clone(&starknet.backend), &block_info)?;
+    let exec_context = ExecutionContext::new_at_block_end(Arc::clone(&starknet.backend), block_info)?;
 
     let transactions = request
         .into_iter()
@@ -76,10 +76,16 @@ mod tests {
     use super::*;
     use crate::{
         errors::StarknetRpcApiError,
-        test_utils::{sample_chain_for_block_getters, SampleChainForBlockGetters},
+        test_utils::{rpc_test_setup, sample_chain_for_block_getters, SampleChainForBlockGetters},
     };
-    use mp_block::BlockTag;
+    use mc_db::read_scope::ReadScope;
+    use mp_block::{
+        header::PendingHeader, BlockTag, MadaraBlockInner, MadaraMaybePendingBlock, MadaraPendingBlockInfo,
+    };
+    use mp_receipt::InvokeTransactionReceipt;
     use mp_rpc::{L1DaMode, ResourcePrice};
+    use mp_state_update::StateDiff;
+    use mp_transactions::{InvokeTransactionV0, Transaction};
     use rstest::rstest;
     use starknet_types_core::felt::Felt;
 
@@ -175,4 +181,58 @@ mod tests {
             Err(StarknetRpcApiError::BlockNotFound.into())
         );
     }
+
+    #[tokio::test]
+    async fn test_get_block_with_txs_pending_concurrent_update() {
+        let (backend, rpc) = rpc_test_setup();
+
+        // Every version of the pending block tags its transactions and their hashes with its generation.
+        let pending_block = |generation: u64| MadaraMaybePendingBlock {
+            info: MadaraPendingBlockInfo::new(
+                PendingHeader::default(),
+                (0..generation).map(|i| Felt::from(generation * 1000 + i)).collect(),
+            )
+            .into(),
+            inner: MadaraBlockInner::new(
+                (0..generation)
+                    .map(|_| InvokeTransactionV0 { max_fee: generation.into(), ..Default::default() }.into())
+                    .collect(),
+                (0..generation).map(|_| InvokeTransactionReceipt::default().into()).collect(),
+            ),
+        };
+
+        std::thread::scope(|s| {
+            let writer = s.spawn(|| {
+                for generation in 1..=200 {
+                    backend.store_block(pending_block(generation), StateDiff::default(), vec![], None, None).unwrap();
+                }
+            });
+            while !writer.is_finished() {
+                let MaybePendingBlockWithTxs::Pending(block) =
+                    get_block_with_txs(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap()
+                else {
+                    panic!("Expected a pending block")
+                };
+                // A block mixing two generations would have hashes and transactions from different generations.
+                let generation = block.transactions.len() as u64;
+                for (i, tx) in block.transactions.into_iter().enumerate() {
+                    assert_eq!(tx.transaction_hash, Felt::from(generation * 1000 + i as u64));
+                    assert_eq!(
+                        tx.transaction,
+                        Transaction::from(InvokeTransactionV0 { max_fee: generation.into(), ..Default::default() })
+                            .into()
+                    );
+                }
+            }
+            writer.join().unwrap();
+        });
+
+        // Reads of the pending block in a read scope keep seeing the same version of it.
+        ReadScope::run(async {
+            let before = get_block_with_txs(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap();
+            backend.store_block(pending_block(201), StateDiff::default(), vec![], None, None).unwrap();
+            assert_eq!(get_block_with_txs(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap(), before);
+        })
+        .await;
+    }
 }
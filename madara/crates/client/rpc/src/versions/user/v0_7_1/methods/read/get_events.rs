@@ -1,11 +1,25 @@
+use mc_db::address_activity_db::{AddressActivityKey, AddressActivityKind};
+use mc_db::MadaraStorageError;
 use mp_block::{BlockId, BlockTag, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
 use mp_rpc::{EmittedEvent, Event, EventContent, EventFilterWithPageRequest, EventsChunk};
 
 use crate::constants::{MAX_EVENTS_CHUNK_SIZE, MAX_EVENTS_KEYS};
 use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
-use crate::types::ContinuationToken;
-use crate::utils::event_match_filter;
+use crate::types::{ContinuationToken, PageByteBudget, PendingCursor};
+use crate::utils::{event_match_filter, ResultExt};
 use crate::Starknet;
+use starknet_types_core::felt::Felt;
+
+/// Upper bound of the size of a felt in a JSON response: 64 hex digits, the `0x` prefix, the quotes and a comma.
+const FELT_JSON_BYTES: usize = 69;
+/// Upper bound of the size of an event in a JSON response, without its keys and data.
+const EMITTED_EVENT_JSON_BYTES: usize = 4 * FELT_JSON_BYTES + 160;
+
+/// Estimated size of an event in a JSON response, taken out of the [`PageByteBudget`] of the page.
+fn emitted_event_bytes(event: &EmittedEvent) -> usize {
+    let content = &event.event.event_content;
+    EMITTED_EVENT_JSON_BYTES + (content.keys.len() + content.data.len()) * FELT_JSON_BYTES
+}
 
 /// Returns all events matching the given filter.
 ///
@@ -27,27 +41,25 @@ use crate::Starknet;
 /// block in which they occurred, and the transaction that triggered them. In case of
 /// errors, such as `PAGE_SIZE_TOO_BIG`, `INVALID_CONTINUATION_TOKEN`, `BLOCK_NOT_FOUND`, or
 /// `TOO_MANY_KEYS_IN_FILTER`, returns a `StarknetRpcApiError` indicating the specific issue.
+///
+/// The chunk may have fewer than `chunk_size` events and a continuation token when the events are large, see
+/// [`Starknet::with_max_page_bytes`].
 pub async fn get_events(starknet: &Starknet, filter: EventFilterWithPageRequest) -> StarknetRpcResult<EventsChunk> {
+    check_filter(&filter)?;
     let from_address = filter.address;
     let keys = filter.keys;
     let chunk_size = filter.chunk_size;
 
-    if let Some(keys) = &keys {
-        if keys.len() > MAX_EVENTS_KEYS {
-            return Err(StarknetRpcApiError::TooManyKeysInFilter);
-        }
-    }
-    if chunk_size > MAX_EVENTS_CHUNK_SIZE as u64 {
-        return Err(StarknetRpcApiError::PageSizeTooBig);
-    }
-
     // Get the block numbers for the requested range
     let (from_block, to_block, latest_block) = block_range(starknet, filter.from_block, filter.to_block)?;
 
     let continuation_token = match filter.continuation_token {
         Some(token) => ContinuationToken::parse(token).map_err(|_| StarknetRpcApiError::InvalidContinuationToken)?,
-        None => ContinuationToken { block_n: from_block, event_n: 0 },
+        None => ContinuationToken { block_n: from_block, event_n: 0, pending: None },
     };
+    if let Some(cursor) = &continuation_token.pending {
+        check_pending_cursor(starknet, continuation_token.block_n, cursor, latest_block)?;
+    }
 
     // Verify that the requested range is valid
     if from_block > to_block {
@@ -56,44 +68,284 @@ pub async fn get_events(starknet: &Starknet, filter: EventFilterWithPageRequest)
 
     let from_block = continuation_token.block_n;
     let mut filtered_events: Vec<EmittedEvent> = Vec::new();
+    let mut page_budget = PageByteBudget::new(starknet.max_page_bytes);
 
-    for current_block in from_block..=to_block {
-        let (_pending, block) = if current_block <= latest_block {
-            (false, starknet.get_block(&BlockId::Number(current_block))?)
-        } else {
-            (true, starknet.get_block(&BlockId::Tag(BlockTag::Pending))?)
-        };
+    let db_to_block = to_block.min(latest_block);
+    // The blocks of the databases written by older nodes are not in the indexes.
+    let events_indexed =
+        starknet.backend.events_indexed().or_internal_server_error("Error checking whether the events are indexed")?;
+    let blocks: Box<dyn Iterator<Item = StarknetRpcResult<(u64, Vec<EmittedEvent>)>> + '_> = match events_indexed
+        .then(|| indexed_events(starknet, from_address.as_ref(), keys.as_deref(), from_block, db_to_block))
+        .flatten()
+    {
+        Some(blocks) => Box::new(blocks),
+        None => Box::new(scanned_events(starknet, from_address.as_ref(), keys.as_deref(), from_block, db_to_block)),
+    };
+    // The transactions the events of the pending block are read from, for the continuation token.
+    let pending_cursor = std::cell::OnceCell::new();
+    let pending_block = std::iter::once_with(|| {
+        let block = starknet.get_block(&BlockId::Tag(BlockTag::Pending))?;
+        let _ = pending_cursor.set(PendingCursor::new(&block.info, block.info.tx_hashes().len() as u64));
+        Ok((latest_block + 1, filter_block_events(block, from_address.as_ref(), keys.as_deref())))
+    })
+    .take(usize::from(to_block > latest_block));
 
-        let block_filtered_events: Vec<EmittedEvent> = drain_block_events(block)
-            .filter(|event| event_match_filter(&event.event, from_address.as_ref(), keys.as_deref()))
-            .collect();
+    let mut from_block_seen = false;
+    for block in blocks.chain(pending_block) {
+        let (current_block, block_filtered_events) = block?;
 
-        if current_block == from_block && (block_filtered_events.len() as u64) < continuation_token.event_n {
+        if current_block == from_block {
+            from_block_seen = true;
+            if (block_filtered_events.len() as u64) < continuation_token.event_n {
+                return Err(StarknetRpcApiError::InvalidContinuationToken);
+            }
+        } else if !from_block_seen && continuation_token.event_n > 0 {
+            // The indexes skip the blocks without matching events: the block of the token has none.
             return Err(StarknetRpcApiError::InvalidContinuationToken);
         }
 
-        #[allow(clippy::iter_skip_zero)]
-        let block_filtered_reduced_events: Vec<EmittedEvent> = block_filtered_events
-            .into_iter()
-            .skip(if current_block == from_block { continuation_token.event_n as usize } else { 0 })
-            .take(chunk_size as usize - filtered_events.len())
-            .collect();
+        let page_token = |event_n: u64| {
+            let pending = if current_block > latest_block { pending_cursor.get().copied().flatten() } else { None };
+            Some(ContinuationToken { block_n: current_block, event_n, pending }.to_string())
+        };
+        let skip = if current_block == from_block { continuation_token.event_n as usize } else { 0 };
+        for (event_n, event) in block_filtered_events.into_iter().enumerate().skip(skip) {
+            let event_n = event_n as u64;
+            if filtered_events.len() as u64 >= chunk_size || !page_budget.take(emitted_event_bytes(&event)) {
+                return Ok(EventsChunk { events: filtered_events, continuation_token: page_token(event_n) });
+            }
+            filtered_events.push(event);
+            if filtered_events.len() as u64 == chunk_size {
+                return Ok(EventsChunk { events: filtered_events, continuation_token: page_token(event_n + 1) });
+            }
+        }
+    }
+    Ok(EventsChunk { events: filtered_events, continuation_token: None })
+}
 
-        let num_events = block_filtered_reduced_events.len();
+/// Returns the events matching the given filter, most recent first.
+///
+/// This is [`get_events`] going through the blocks from `to_block` down to `from_block`, and through the events of
+/// each block from the last one. Continuation tokens have the same format, but are only valid for this method. A
+/// token issued in the pending block only covers the transactions the block had then: the events of the transactions
+/// added since are more recent than the first ones returned, and are skipped.
+///
+/// The blocks are all read, even when the filter has an emitter: this is meant for the most recent events.
+pub async fn get_events_backward(
+    starknet: &Starknet,
+    filter: EventFilterWithPageRequest,
+) -> StarknetRpcResult<EventsChunk> {
+    check_filter(&filter)?;
+    let from_address = filter.address;
+    let keys = filter.keys;
+    let chunk_size = filter.chunk_size;
 
-        filtered_events.extend(block_filtered_reduced_events);
+    let (from_block, to_block, latest_block) = block_range(starknet, filter.from_block, filter.to_block)?;
 
-        if filtered_events.len() == chunk_size as usize {
-            let event_n =
-                if current_block == from_block { continuation_token.event_n + chunk_size } else { num_events as u64 };
-            let token = Some(ContinuationToken { block_n: current_block, event_n }.to_string());
+    let continuation_token = match filter.continuation_token {
+        Some(token) => ContinuationToken::parse(token).map_err(|_| StarknetRpcApiError::InvalidContinuationToken)?,
+        None => ContinuationToken { block_n: to_block, event_n: 0, pending: None },
+    };
+    if continuation_token.block_n > to_block {
+        return Err(StarknetRpcApiError::InvalidContinuationToken);
+    }
+    if let Some(cursor) = &continuation_token.pending {
+        check_pending_cursor(starknet, continuation_token.block_n, cursor, latest_block)?;
+    }
+
+    let mut filtered_events: Vec<EmittedEvent> = Vec::new();
+    let mut page_budget = PageByteBudget::new(starknet.max_page_bytes);
+    for current_block in (from_block..=continuation_token.block_n).rev() {
+        let block_id =
+            if current_block > latest_block { BlockId::Tag(BlockTag::Pending) } else { BlockId::Number(current_block) };
+        // The blocks before a state snapshot which are not backfilled yet are skipped.
+        let Some(mut block) =
+            starknet.backend.get_block(&block_id).or_internal_server_error("Error getting block from storage")?
+        else {
+            continue;
+        };
+
+        let (skip, pending) = if current_block == continuation_token.block_n {
+            (continuation_token.event_n, continuation_token.pending)
+        } else {
+            let pending = (current_block > latest_block)
+                .then(|| PendingCursor::new(&block.info, block.info.tx_hashes().len() as u64))
+                .flatten();
+            (0, pending)
+        };
+        if let Some(cursor) = &pending {
+            block.inner.receipts.truncate(cursor.tx_count as usize);
+        }
+
+        let mut block_filtered_events = filter_block_events(block, from_address.as_ref(), keys.as_deref());
+        if (block_filtered_events.len() as u64) < skip {
+            return Err(StarknetRpcApiError::InvalidContinuationToken);
+        }
+        block_filtered_events.reverse();
 
-            return Ok(EventsChunk { events: filtered_events, continuation_token: token });
+        let page_token =
+            |event_n: u64| Some(ContinuationToken { block_n: current_block, event_n, pending }.to_string());
+        for (event_n, event) in block_filtered_events.into_iter().enumerate().skip(skip as usize) {
+            let event_n = event_n as u64;
+            if filtered_events.len() as u64 >= chunk_size || !page_budget.take(emitted_event_bytes(&event)) {
+                return Ok(EventsChunk { events: filtered_events, continuation_token: page_token(event_n) });
+            }
+            filtered_events.push(event);
+            if filtered_events.len() as u64 == chunk_size {
+                return Ok(EventsChunk { events: filtered_events, continuation_token: page_token(event_n + 1) });
+            }
         }
     }
     Ok(EventsChunk { events: filtered_events, continuation_token: None })
 }
 
+fn check_filter(filter: &EventFilterWithPageRequest) -> StarknetRpcResult<()> {
+    if let Some(keys) = &filter.keys {
+        if keys.len() > MAX_EVENTS_KEYS {
+            return Err(StarknetRpcApiError::TooManyKeysInFilter);
+        }
+    }
+    if filter.chunk_size > MAX_EVENTS_CHUNK_SIZE as u64 {
+        return Err(StarknetRpcApiError::PageSizeTooBig);
+    }
+    Ok(())
+}
+
+/// Checks that a continuation token issued in the pending block can be resumed from: the block it was issued in,
+/// whether still pending or closed since, must start with the same transactions. Otherwise the events skipped by the
+/// token are not the ones already returned.
+fn check_pending_cursor(
+    starknet: &Starknet,
+    block_n: u64,
+    cursor: &PendingCursor,
+    latest_block: u64,
+) -> StarknetRpcResult<()> {
+    let block_id = match block_n {
+        n if n == latest_block + 1 => BlockId::Tag(BlockTag::Pending),
+        n if n <= latest_block => BlockId::Number(n),
+        _ => return Err(StarknetRpcApiError::InvalidContinuationToken),
+    };
+    let block_info = starknet.get_block_info(&block_id)?;
+    if PendingCursor::new(&block_info, cursor.tx_count).as_ref() != Some(cursor) {
+        return Err(StarknetRpcApiError::InvalidContinuationToken);
+    }
+    Ok(())
+}
+
+/// The events matching the filter in the blocks `from_block..=to_block`, by block, read from the indexes of the
+/// database. Filters on first keys use the event index, with or without an emitter, and filters on an emitter alone
+/// the address activity index. The blocks without any matching event are skipped.
+///
+/// Returns `None` when the filter has neither an emitter nor first keys, in which case every block has to be scanned.
+fn indexed_events<'a>(
+    starknet: &'a Starknet,
+    address: Option<&'a Felt>,
+    keys: Option<&'a [Vec<Felt>]>,
+    from_block: u64,
+    to_block: u64,
+) -> Option<impl Iterator<Item = StarknetRpcResult<(u64, Vec<EmittedEvent>)>> + 'a> {
+    let backend = &starknet.backend;
+
+    let positions: Box<dyn Iterator<Item = Result<(u64, u32), MadaraStorageError>> + 'a> =
+        match (address, keys.and_then(|keys| keys.first()).filter(|key0| !key0.is_empty())) {
+            (address, Some(key0)) => {
+                let mut key0 = key0.clone();
+                key0.sort();
+                key0.dedup();
+                Box::new(merge_positions(
+                    key0.iter()
+                        .map(|key0| -> Box<dyn Iterator<Item = Result<(u64, u32), MadaraStorageError>> + 'a> {
+                            match address {
+                                Some(address) => {
+                                    Box::new(backend.iter_event_index(address, key0, from_block, to_block))
+                                }
+                                None => Box::new(backend.iter_event_key_index(key0, from_block, to_block)),
+                            }
+                        })
+                        .collect(),
+                ))
+            }
+            (Some(address), None) => Box::new(
+                backend
+                    .iter_address_activity(address, AddressActivityKey::block_start(from_block))
+                    .filter(|entry| !matches!(entry, Ok(entry) if entry.kind != AddressActivityKind::Event))
+                    .map(|entry| entry.map(|entry| (entry.block_n, entry.index)))
+                    .take_while(|position| !matches!(position, Ok((block_n, _)) if *block_n > to_block)),
+            ),
+            (None, None) => return None,
+        };
+
+    Some(group_by_block(positions).map(move |block| {
+        let (block_n, event_ns) = block.or_internal_server_error("Error getting events from the index")?;
+        let block = starknet.get_block(&BlockId::Number(block_n))?;
+        let events = drain_block_events(block)
+            .enumerate()
+            .filter(|(event_n, _)| event_ns.binary_search(&(*event_n as u32)).is_ok())
+            .map(|(_, event)| event)
+            .filter(|event| event_match_filter(&event.event, address, keys))
+            .collect();
+        Ok((block_n, events))
+    }))
+}
+
+/// The events matching the filter in the blocks `from_block..=to_block`, by block, found by going through every block.
+fn scanned_events<'a>(
+    starknet: &'a Starknet,
+    address: Option<&'a Felt>,
+    keys: Option<&'a [Vec<Felt>]>,
+    from_block: u64,
+    to_block: u64,
+) -> impl Iterator<Item = StarknetRpcResult<(u64, Vec<EmittedEvent>)>> + 'a {
+    starknet.backend.iter_blocks(from_block..=to_block).map(move |block| {
+        let block = block.or_internal_server_error("Error getting block from storage")?;
+        let block_n = block.info.header.block_number;
+        Ok((block_n, filter_block_events(block.into(), address, keys)))
+    })
+}
+
+fn filter_block_events(
+    block: MadaraMaybePendingBlock,
+    address: Option<&Felt>,
+    keys: Option<&[Vec<Felt>]>,
+) -> Vec<EmittedEvent> {
+    drain_block_events(block).filter(|event| event_match_filter(&event.event, address, keys)).collect()
+}
+
+/// Merges iterators of `(block_n, event_n)` positions in block order into a single one in block order. Errors come
+/// first.
+fn merge_positions<E>(
+    iters: Vec<impl Iterator<Item = Result<(u64, u32), E>>>,
+) -> impl Iterator<Item = Result<(u64, u32), E>> {
+    let mut iters: Vec<_> = iters.into_iter().map(Iterator::peekable).collect();
+    std::iter::from_fn(move || {
+        let (next, _) = iters
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, iter)| Some((i, iter.peek()?.as_ref().ok().copied())))
+            .min_by_key(|(_, position)| *position)?;
+        iters[next].next()
+    })
+}
+
+/// Groups `(block_n, event_n)` positions in block order by block.
+fn group_by_block<E>(
+    positions: impl Iterator<Item = Result<(u64, u32), E>>,
+) -> impl Iterator<Item = Result<(u64, Vec<u32>), E>> {
+    let mut positions = positions.peekable();
+    std::iter::from_fn(move || {
+        let (block_n, event_n) = match positions.next()? {
+            Ok(position) => position,
+            Err(err) => return Some(Err(err)),
+        };
+        let mut event_ns = vec![event_n];
+        while let Some(Ok((_, event_n))) = positions.next_if(|position| matches!(position, Ok((n, _)) if *n == block_n))
+        {
+            event_ns.push(event_n);
+        }
+        Some(Ok((block_n, event_ns)))
+    })
+}
+
 fn block_range(
     starknet: &Starknet,
     from_block: Option<BlockId>,
@@ -150,3 +402,253 @@ pub fn drain_block_events(block: MadaraMaybePendingBlock) -> impl Iterator<Item
         transaction_hash,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use mp_block::header::PendingHeader;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraPendingBlockInfo};
+    use mp_receipt::{InvokeTransactionReceipt, TransactionReceipt};
+    use mp_state_update::StateDiff;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    const A: Felt = Felt::from_hex_unchecked("0xa");
+    const B: Felt = Felt::from_hex_unchecked("0xb");
+
+    fn event(block_n: u64, from_address: Felt, keys: &[u64]) -> EmittedEvent {
+        EmittedEvent {
+            event: Event {
+                from_address,
+                event_content: EventContent { keys: keys.iter().copied().map(Felt::from).collect(), data: vec![] },
+            },
+            block_hash: Some(Felt::from(block_n)),
+            block_number: Some(block_n),
+            transaction_hash: Felt::from(0x100 + block_n),
+        }
+    }
+
+    fn store_blocks(backend: &MadaraBackend) -> Vec<Vec<EmittedEvent>> {
+        let blocks = vec![
+            vec![event(0, A, &[1]), event(0, B, &[1]), event(0, A, &[]), event(0, A, &[2, 3])],
+            vec![event(1, A, &[1])],
+            vec![event(2, B, &[2])],
+        ];
+        for (block_n, events) in blocks.iter().enumerate() {
+            let block_n = block_n as u64;
+            let receipt = TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                transaction_hash: Felt::from(0x100 + block_n),
+                events: events
+                    .iter()
+                    .map(|event| mp_receipt::Event {
+                        from_address: event.event.from_address,
+                        keys: event.event.event_content.keys.clone(),
+                        data: vec![],
+                    })
+                    .collect(),
+                ..Default::default()
+            });
+            backend
+                .store_block(
+                    MadaraMaybePendingBlock {
+                        info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                            header: Header { block_number: block_n, ..Default::default() },
+                            block_hash: Felt::from(block_n),
+                            tx_hashes: vec![Felt::from(0x100 + block_n)],
+                        }),
+                        inner: MadaraBlockInner { transactions: vec![], receipts: vec![receipt] },
+                    },
+                    StateDiff::default(),
+                    vec![],
+                    None,
+                    None,
+                )
+                .unwrap();
+        }
+        blocks
+    }
+
+    /// Stores block #3 with two events of `A`, as the pending block or as a confirmed block.
+    fn store_block_3(backend: &MadaraBackend, tx_hash: Felt, pending: bool) {
+        let event = mp_receipt::Event { from_address: A, keys: vec![Felt::ONE], data: vec![] };
+        let receipt = TransactionReceipt::Invoke(InvokeTransactionReceipt {
+            transaction_hash: tx_hash,
+            events: vec![event.clone(), event],
+            ..Default::default()
+        });
+        let info = if pending {
+            MadaraMaybePendingBlockInfo::Pending(MadaraPendingBlockInfo {
+                header: PendingHeader { parent_block_hash: Felt::TWO, ..Default::default() },
+                tx_hashes: vec![tx_hash],
+            })
+        } else {
+            MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                header: Header { block_number: 3, parent_block_hash: Felt::TWO, ..Default::default() },
+                block_hash: Felt::from(3),
+                tx_hashes: vec![tx_hash],
+            })
+        };
+        let block =
+            MadaraMaybePendingBlock { info, inner: MadaraBlockInner { transactions: vec![], receipts: vec![receipt] } };
+        backend.store_block(block, StateDiff::default(), vec![], None, None).unwrap();
+    }
+
+    fn filter(
+        address: Option<Felt>,
+        keys: Option<Vec<Vec<u64>>>,
+        chunk_size: u64,
+        continuation_token: Option<&str>,
+    ) -> EventFilterWithPageRequest {
+        EventFilterWithPageRequest {
+            address,
+            from_block: None,
+            keys: keys.map(|keys| keys.into_iter().map(|keys| keys.into_iter().map(Felt::from).collect()).collect()),
+            to_block: None,
+            chunk_size,
+            continuation_token: continuation_token.map(String::from),
+        }
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_get_events(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let blocks = store_blocks(&backend);
+        let rpc = &rpc;
+        let events = move |filter| async move { get_events(rpc, filter).await.unwrap() };
+
+        // Event index.
+        let chunk = events(filter(Some(A), Some(vec![vec![1]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![blocks[0][0].clone(), blocks[1][0].clone()]);
+        let chunk = events(filter(Some(A), Some(vec![vec![2, 1]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![blocks[0][0].clone(), blocks[0][3].clone(), blocks[1][0].clone()]);
+        let chunk = events(filter(Some(A), Some(vec![vec![2], vec![4]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![]);
+
+        // Address activity index, with pagination.
+        let chunk = events(filter(Some(A), None, 2, None)).await;
+        assert_eq!(chunk.events, vec![blocks[0][0].clone(), blocks[0][2].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("0-2"));
+        let chunk = events(filter(Some(A), None, 2, Some("0-2"))).await;
+        assert_eq!(chunk.events, vec![blocks[0][3].clone(), blocks[1][0].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("1-1"));
+        let chunk = events(filter(Some(A), None, 2, Some("1-1"))).await;
+        assert_eq!(chunk, EventsChunk { events: vec![], continuation_token: None });
+
+        // Event key index.
+        let chunk = events(filter(None, Some(vec![vec![1]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![blocks[0][0].clone(), blocks[0][1].clone(), blocks[1][0].clone()]);
+        let chunk = events(filter(None, Some(vec![vec![2]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![blocks[0][3].clone(), blocks[2][0].clone()]);
+
+        // Every block is scanned.
+        let chunk = events(filter(None, Some(vec![vec![], vec![3]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![blocks[0][3].clone()]);
+
+        assert_eq!(
+            get_events(rpc, filter(Some(B), None, 10, Some("1-1"))).await,
+            Err(StarknetRpcApiError::InvalidContinuationToken)
+        );
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_get_events_page_byte_budget(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let blocks = store_blocks(&backend);
+        // Each page only fits its first event.
+        let rpc = &rpc.with_max_page_bytes(emitted_event_bytes(&blocks[0][0]));
+
+        let chunk = get_events(rpc, filter(Some(A), None, 10, None)).await.unwrap();
+        assert_eq!(chunk.events, vec![blocks[0][0].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("0-1"));
+        let chunk = get_events(rpc, filter(Some(A), None, 10, Some("0-1"))).await.unwrap();
+        assert_eq!(chunk.events, vec![blocks[0][2].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("0-2"));
+        // The first event of a page is returned even when it is larger than the budget.
+        let chunk = get_events(rpc, filter(Some(A), None, 10, Some("0-2"))).await.unwrap();
+        assert_eq!(chunk.events, vec![blocks[0][3].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("1-0"));
+        let chunk = get_events(rpc, filter(Some(A), None, 10, Some("1-0"))).await.unwrap();
+        assert_eq!(chunk, EventsChunk { events: vec![blocks[1][0].clone()], continuation_token: None });
+
+        let chunk = get_events_backward(rpc, filter(Some(A), None, 10, None)).await.unwrap();
+        assert_eq!(chunk.events, vec![blocks[1][0].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("0-0"));
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_get_events_pending_continuation_token(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        store_blocks(&backend);
+        let pending_filter = |continuation_token: Option<&str>| EventFilterWithPageRequest {
+            from_block: Some(BlockId::Tag(BlockTag::Pending)),
+            to_block: Some(BlockId::Tag(BlockTag::Pending)),
+            ..filter(Some(A), None, 1, continuation_token)
+        };
+
+        store_block_3(&backend, Felt::from(0x103), true);
+        let chunk = get_events(&rpc, pending_filter(None)).await.unwrap();
+        let token = chunk.continuation_token.unwrap();
+        assert!(token.starts_with("v1-3-1-1-"), "{token}");
+        let chunk = get_events(&rpc, pending_filter(Some(&token))).await.unwrap();
+        assert_eq!(chunk.events[0].block_number, None);
+
+        // The pending block the token was issued in was replaced.
+        store_block_3(&backend, Felt::from(0x999), true);
+        assert_eq!(
+            get_events(&rpc, pending_filter(Some(&token))).await,
+            Err(StarknetRpcApiError::InvalidContinuationToken)
+        );
+
+        // The pending block is closed: the token resumes from the confirmed block.
+        store_block_3(&backend, Felt::from(0x103), false);
+        let chunk = get_events(&rpc, filter(Some(A), None, 1, Some(&token))).await.unwrap();
+        assert_eq!(chunk.events[0].block_number, Some(3));
+    }
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_get_events_backward(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let blocks = store_blocks(&backend);
+        let rpc = &rpc;
+        let events = move |filter| async move { get_events_backward(rpc, filter).await.unwrap() };
+
+        let chunk = events(filter(Some(A), None, 2, None)).await;
+        assert_eq!(chunk.events, vec![blocks[1][0].clone(), blocks[0][3].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("0-1"));
+        let chunk = events(filter(Some(A), None, 2, Some("0-1"))).await;
+        assert_eq!(chunk.events, vec![blocks[0][2].clone(), blocks[0][0].clone()]);
+        assert_eq!(chunk.continuation_token.as_deref(), Some("0-3"));
+        let chunk = events(filter(Some(A), None, 2, Some("0-3"))).await;
+        assert_eq!(chunk, EventsChunk { events: vec![], continuation_token: None });
+
+        let chunk = events(filter(None, Some(vec![vec![1]]), 10, None)).await;
+        assert_eq!(chunk.events, vec![blocks[1][0].clone(), blocks[0][1].clone(), blocks[0][0].clone()]);
+
+        // Pending block.
+        store_block_3(&backend, Felt::from(0x103), true);
+        let pending_filter = |continuation_token: Option<&str>| EventFilterWithPageRequest {
+            to_block: Some(BlockId::Tag(BlockTag::Pending)),
+            ..filter(Some(A), None, 1, continuation_token)
+        };
+        let chunk = events(pending_filter(None)).await;
+        assert_eq!(chunk.events[0].block_number, None);
+        let token = chunk.continuation_token.unwrap();
+        assert!(token.starts_with("v1-3-1-1-"), "{token}");
+        let chunk = events(pending_filter(Some(&token))).await;
+        assert_eq!(chunk.events[0].block_number, None);
+        let chunk = events(pending_filter(chunk.continuation_token.as_deref())).await;
+        assert_eq!(chunk.events, vec![blocks[1][0].clone()]);
+
+        // The token is past the end of the range.
+        assert_eq!(
+            get_events_backward(rpc, filter(Some(A), None, 1, Some(&token))).await,
+            Err(StarknetRpcApiError::InvalidContinuationToken)
+        );
+    }
+}
@@ -33,6 +33,19 @@ pub fn get_state_update(starknet: &Starknet, block_id: BlockId) -> StarknetRpcRe
         .or_internal_server_error("Error resolving block id")?
         .ok_or(StarknetRpcApiError::BlockNotFound)?;
 
+    // Fast path: confirmed blocks only change when they are reverted by a reorg, which changes their block hash.
+    if let DbBlockId::Number(block_n) = resolved_block_id {
+        if let Some(state_update) = starknet.state_update_cache.get(block_n) {
+            let block_hash = starknet
+                .backend
+                .get_block_hash(&resolved_block_id)
+                .or_internal_server_error("Error getting block hash")?;
+            if block_hash == Some(state_update.block_hash) {
+                return Ok(MaybePendingStateUpdate::Block(state_update));
+            }
+        }
+    }
+
     let state_diff = starknet
         .backend
         .get_block_state_diff(&resolved_block_id)
@@ -73,12 +86,14 @@ pub fn get_state_update(starknet: &Starknet, block_id: BlockId) -> StarknetRpcRe
                 Felt::ZERO
             };
 
-            Ok(MaybePendingStateUpdate::Block(StateUpdate {
+            let state_update = StateUpdate {
                 block_hash: block_info.block_hash,
                 old_root,
                 new_root: block_info.header.global_state_root,
                 state_diff: state_diff.into(),
-            }))
+            };
+            starknet.state_update_cache.insert(block_info.header.block_number, state_update.clone());
+            Ok(MaybePendingStateUpdate::Block(state_update))
         }
     }
 }
@@ -133,6 +148,29 @@ mod tests {
         assert_eq!(get_state_update(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap(), res);
     }
 
+    #[rstest]
+    fn test_get_state_update_reverted_block(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (SampleChainForStateUpdates { block_hashes, state_roots, state_diffs, .. }, rpc) =
+            sample_chain_for_state_updates;
+
+        // The state update of a block of the chain reverted by a reorg is not served from the cache.
+        let reverted = StateUpdate {
+            block_hash: Felt::from_hex_unchecked("0x7128638126378"),
+            old_root: state_roots[0],
+            new_root: Felt::ONE,
+            state_diff: mp_state_update::StateDiff::default().into(),
+        };
+        rpc.state_update_cache.insert(1, reverted);
+        let res = MaybePendingStateUpdate::Block(StateUpdate {
+            block_hash: block_hashes[1],
+            old_root: state_roots[0],
+            new_root: state_roots[1],
+            state_diff: state_diffs[1].clone().into(),
+        });
+        assert_eq!(get_state_update(&rpc, BlockId::Number(1)).unwrap(), res);
+        assert_eq!(rpc.state_update_cache.get(1).map(|state_update| state_update.block_hash), Some(block_hashes[1]));
+    }
+
     #[rstest]
 
     fn test_get_state_update_not_found(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
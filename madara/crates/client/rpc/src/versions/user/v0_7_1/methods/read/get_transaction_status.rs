@@ -29,13 +29,28 @@ pub fn get_transaction_status(
     starknet: &Starknet,
     transaction_hash: Felt,
 ) -> StarknetRpcResult<TxnFinalityAndExecutionStatus> {
-    let (block, tx_index) = starknet
+    transaction_status(starknet, &transaction_hash)?.ok_or(StarknetRpcApiError::TxnHashNotFound)
+}
+
+/// The status of a transaction, `None` when the node does not know about it.
+///
+/// A transaction waiting in the mempool of this node is [`TxnStatus::Received`]. Rejected transactions are not
+/// remembered, so [`TxnStatus::Rejected`] is never returned.
+pub(crate) fn transaction_status(
+    starknet: &Starknet,
+    transaction_hash: &Felt,
+) -> StarknetRpcResult<Option<TxnFinalityAndExecutionStatus>> {
+    let Some((block, tx_index)) = starknet
         .backend
-        .find_tx_hash_block(&transaction_hash)
+        .find_tx_hash_block(transaction_hash)
         .or_internal_server_error("Error find tx hash block info from db")?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
-
-    // Note: we don't support TransactionStatus::Received and TransactionStatus::Rejected yet.
+    else {
+        let received = starknet.mempool.as_ref().is_some_and(|mempool| mempool.contains_tx(transaction_hash));
+        return Ok(received.then_some(TxnFinalityAndExecutionStatus {
+            finality_status: TxnStatus::Received,
+            execution_status: None,
+        }));
+    };
 
     let tx_receipt = block.inner.receipts.get(tx_index.0 as usize).ok_or(StarknetRpcApiError::TxnHashNotFound)?;
 
@@ -55,7 +70,7 @@ pub fn get_transaction_status(
         }
     };
 
-    Ok(TxnFinalityAndExecutionStatus { finality_status, execution_status: Some(tx_execution_status) })
+    Ok(Some(TxnFinalityAndExecutionStatus { finality_status, execution_status: Some(tx_execution_status) }))
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use mp_block::{BlockId, BlockTag};
+use mp_block::BlockId;
 use mp_rpc::{SyncStatus, SyncingStatus};
 
 use crate::errors::StarknetRpcResult;
@@ -7,6 +7,10 @@ use crate::Starknet;
 
 /// Returns an object about the sync status, or false if the node is not synching
 ///
+/// The node is syncing while the sync is running and the latest block in the database is behind the latest block of
+/// the feeder gateway, as last measured by the sync. The current and starting blocks are read from the chain head, as
+/// the status of the sync is only published periodically.
+///
 /// ### Arguments
 ///
 /// This function does not take any arguments.
@@ -16,31 +20,151 @@ use crate::Starknet;
 /// * `Syncing` - An Enum that can either be a `mc_rpc_core::SyncStatus` struct representing the
 ///   sync status, or a `Boolean` (`false`) indicating that the node is not currently synchronizing.
 pub async fn syncing(starknet: &Starknet) -> StarknetRpcResult<SyncingStatus> {
-    // obtain best seen (highest) block number
-    let Some(current_block_info) = starknet
-        .backend
-        .get_block_info(&BlockId::Tag(BlockTag::Latest))
-        .or_internal_server_error("Error getting latest block")?
+    // The sync is not running, as in sequencer or devnet mode.
+    let Some(sync_status) = starknet.backend.get_sync_status() else {
+        return Ok(SyncingStatus::NotSyncing);
+    };
+    // The highest block is unknown until the sync reached the feeder gateway once.
+    let (Some(highest_block_num), Some(highest_block_hash)) =
+        (sync_status.highest_block_n, sync_status.highest_block_hash)
     else {
-        return Ok(SyncingStatus::NotSyncing); // TODO: This doesn't really make sense? This can only happen when there are no block in the db at all.
+        return Ok(SyncingStatus::NotSyncing);
     };
 
+    let chain_head = starknet.backend.get_chain_head().or_internal_server_error("Error getting the chain head")?;
+    // There is no block to report before the first one is imported.
+    let Some(current_block_num) = chain_head.latest_block_n else {
+        return Ok(SyncingStatus::NotSyncing);
+    };
+    if current_block_num >= highest_block_num {
+        return Ok(SyncingStatus::NotSyncing);
+    }
+    let current_block_info = starknet.get_block_info(&BlockId::Number(current_block_num))?;
     let current_block_info =
-        current_block_info.as_nonpending().ok_or_internal_server_error("Latest block cannot be pending")?;
-    let starting_block_num = 0; // TODO(rpc): fix this // starknet.starting_block;
+        current_block_info.as_nonpending().ok_or_internal_server_error("Block cannot be pending")?;
+    let current_block_hash = current_block_info.block_hash;
+
+    // The database was empty when the sync started: it started from the genesis block, or from the state snapshot the
+    // node was bootstrapped from. The starting block may have been reverted since.
+    let starting_block_num =
+        sync_status.starting_block_n.or(chain_head.oldest_backfilled_block).unwrap_or(0).min(current_block_num);
     let starting_block_info = starknet.get_block_info(&BlockId::Number(starting_block_num))?;
     let starting_block_info =
         starting_block_info.as_nonpending().ok_or_internal_server_error("Block cannot be pending")?;
     let starting_block_hash = starting_block_info.block_hash;
-    let current_block_num = current_block_info.header.block_number;
-    let current_block_hash = current_block_info.block_hash;
 
     Ok(SyncingStatus::Syncing(SyncStatus {
         starting_block_num,
         starting_block_hash,
-        highest_block_num: current_block_num, // TODO(merge): is this correct?
-        highest_block_hash: current_block_hash,
+        highest_block_num,
+        highest_block_hash,
         current_block_num,
         current_block_hash,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rpc_test_setup, sample_chain_for_block_getters, SampleChainForBlockGetters};
+    use mc_db::MadaraBackend;
+    use mp_block::{Header, MadaraBlock, MadaraBlockInfo, MadaraBlockInner};
+    use rstest::rstest;
+    use starknet_types_core::felt::Felt;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    #[rstest]
+    async fn test_syncing(sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet)) {
+        let (SampleChainForBlockGetters { block_hashes, .. }, rpc) = sample_chain_for_block_getters;
+
+        // The sync is not running.
+        assert_eq!(syncing(&rpc).await.unwrap(), SyncingStatus::NotSyncing);
+
+        let status = mc_db::sync_status::SyncStatus {
+            starting_block_n: Some(0),
+            latest_block_n: Some(2),
+            highest_block_n: Some(10),
+            highest_block_hash: Some(Felt::from(10)),
+            ..Default::default()
+        };
+        rpc.backend.set_sync_status(status);
+        assert_eq!(
+            syncing(&rpc).await.unwrap(),
+            SyncingStatus::Syncing(SyncStatus {
+                starting_block_num: 0,
+                starting_block_hash: block_hashes[0],
+                highest_block_num: 10,
+                highest_block_hash: Felt::from(10),
+                current_block_num: 2,
+                current_block_hash: block_hashes[2],
+            })
+        );
+
+        // The current block is the latest block in the database, even when the sync status was published before it
+        // was imported. The database was empty when the sync started.
+        rpc.backend.set_sync_status(mc_db::sync_status::SyncStatus {
+            starting_block_n: None,
+            latest_block_n: Some(1),
+            ..status
+        });
+        assert_eq!(
+            syncing(&rpc).await.unwrap(),
+            SyncingStatus::Syncing(SyncStatus {
+                starting_block_num: 0,
+                starting_block_hash: block_hashes[0],
+                highest_block_num: 10,
+                highest_block_hash: Felt::from(10),
+                current_block_num: 2,
+                current_block_hash: block_hashes[2],
+            })
+        );
+
+        // The latest block of the feeder gateway is not known yet.
+        rpc.backend.set_sync_status(mc_db::sync_status::SyncStatus {
+            highest_block_n: None,
+            highest_block_hash: None,
+            ..status
+        });
+        assert_eq!(syncing(&rpc).await.unwrap(), SyncingStatus::NotSyncing);
+
+        // Caught up with the feeder gateway.
+        rpc.backend.set_sync_status(mc_db::sync_status::SyncStatus {
+            highest_block_n: Some(2),
+            highest_block_hash: Some(block_hashes[2]),
+            ..status
+        });
+        assert_eq!(syncing(&rpc).await.unwrap(), SyncingStatus::NotSyncing);
+    }
+
+    /// A node bootstrapped from a state snapshot has no block before the block of the snapshot.
+    #[tokio::test]
+    #[rstest]
+    async fn test_syncing_from_state_snapshot(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let snapshot_block_hash = Felt::from(5);
+        let block = MadaraBlock::new(
+            MadaraBlockInfo::new(Header { block_number: 5, ..Default::default() }, vec![], snapshot_block_hash),
+            MadaraBlockInner::new(vec![], vec![]),
+        );
+        backend.store_state_snapshot(block, Default::default(), vec![]).unwrap();
+
+        backend.set_sync_status(mc_db::sync_status::SyncStatus {
+            starting_block_n: None,
+            highest_block_n: Some(10),
+            highest_block_hash: Some(Felt::from(10)),
+            ..Default::default()
+        });
+        assert_eq!(
+            syncing(&rpc).await.unwrap(),
+            SyncingStatus::Syncing(SyncStatus {
+                starting_block_num: 5,
+                starting_block_hash: snapshot_block_hash,
+                highest_block_num: 10,
+                highest_block_hash: Felt::from(10),
+                current_block_num: 5,
+                current_block_hash: snapshot_block_hash,
+            })
+        );
+    }
+}
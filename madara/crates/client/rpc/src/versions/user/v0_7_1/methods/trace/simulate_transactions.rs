@@ -4,7 +4,7 @@ use crate::utils::ResultExt;
 use crate::Starknet;
 use mc_exec::{execution_result_to_tx_trace, ExecutionContext};
 use mp_block::BlockId;
-use mp_rpc::{BroadcastedTxn, SimulateTransactionsResult, SimulationFlag};
+use mp_rpc::{BroadcastedInvokeTxn, BroadcastedTxn, SimulateTransactionsResult, SimulationFlag};
 use mp_transactions::BroadcastedTransactionExt;
 use std::sync::Arc;
 
@@ -20,6 +20,10 @@ pub async fn simulate_transactions(
     if starknet_version < EXECUTION_UNSUPPORTED_BELOW_VERSION {
         return Err(StarknetRpcApiError::UnsupportedTxnVersion);
     }
+    // Transactions with a custom version are only executed once dispatched by the mempool.
+    if transactions.iter().any(|tx| matches!(tx, BroadcastedTxn::Invoke(BroadcastedInvokeTxn::Custom(_)))) {
+        return Err(StarknetRpcApiError::UnsupportedTxnVersion);
+    }
     let exec_context = ExecutionContext::new_at_block_end(Arc::clone(&starknet.backend), &block_info)?;
 
     let charge_fee = !simulation_flags.contains(&SimulationFlag::SkipFeeCharge);
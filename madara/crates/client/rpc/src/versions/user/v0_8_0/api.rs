@@ -7,6 +7,21 @@ use starknet_types_core::felt::Felt;
 pub(crate) type NewHead = mp_rpc::BlockHeader;
 pub(crate) type EmittedEvent = mp_rpc::EmittedEvent;
 
+/// A transaction added to the pending state, notified by `starknet_subscribePendingTransactions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PendingTxnInfo {
+    Hash(Felt),
+    Full(mp_rpc::TxnWithHash),
+}
+
+/// A new status of a transaction, notified by `starknet_subscribeTransactionStatus`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewTxnStatus {
+    pub transaction_hash: Felt,
+    pub status: mp_rpc::TxnFinalityAndExecutionStatus,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContractStorageKeysItem {
     pub contract_address: Felt,
@@ -30,6 +45,9 @@ pub struct NodeHashToNodeMappingItem {
 pub struct ContractLeavesDataItem {
     pub nonce: Felt,
     pub class_hash: Felt,
+    /// Root of the storage trie of the contract. With the nonce and class hash, this is what the leaf of the contract
+    /// in the contracts trie commits to.
+    pub storage_root: Felt,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -55,8 +73,10 @@ pub struct GetStorageProofResult {
 
 #[versioned_rpc("V0_8_0", "starknet")]
 pub trait StarknetWsRpcApi {
+    /// Sends the header of every new block, starting from `block_id` and replaying the blocks already imported from
+    /// there, or from the latest block by default.
     #[subscription(name = "subscribeNewHeads", unsubscribe = "unsubscribeNewHeads", item = NewHead, param_kind = map)]
-    async fn subscribe_new_heads(&self, block: BlockId) -> jsonrpsee::core::SubscriptionResult;
+    async fn subscribe_new_heads(&self, block_id: Option<BlockId>) -> jsonrpsee::core::SubscriptionResult;
 
     #[subscription(name = "subscribeEvents", unsubscribe = "unsubscribeEvents", item = EmittedEvent, param_kind = map)]
     async fn subscribe_events(
@@ -65,6 +85,27 @@ pub trait StarknetWsRpcApi {
         keys: Option<Vec<Vec<Felt>>>,
         block: Option<BlockId>,
     ) -> jsonrpsee::core::SubscriptionResult;
+
+    #[subscription(
+        name = "subscribePendingTransactions",
+        unsubscribe = "unsubscribePendingTransactions",
+        item = PendingTxnInfo,
+        param_kind = map
+    )]
+    async fn subscribe_pending_transactions(
+        &self,
+        transaction_details: Option<bool>,
+        sender_address: Option<Vec<Felt>>,
+    ) -> jsonrpsee::core::SubscriptionResult;
+
+    /// Sends the status of a transaction every time it changes, from `RECEIVED` until `ACCEPTED_ON_L1`.
+    #[subscription(
+        name = "subscribeTransactionStatus",
+        unsubscribe = "unsubscribeTransactionStatus",
+        item = NewTxnStatus,
+        param_kind = map
+    )]
+    async fn subscribe_transaction_status(&self, transaction_hash: Felt) -> jsonrpsee::core::SubscriptionResult;
 }
 
 #[versioned_rpc("V0_8_0", "starknet")]
@@ -72,6 +113,28 @@ pub trait StarknetReadRpcApi {
     #[method(name = "specVersion")]
     fn spec_version(&self) -> RpcResult<String>;
 
+    /// Get block information with full transactions and receipts given the block id
+    #[method(name = "getBlockWithReceipts")]
+    async fn get_block_with_receipts(
+        &self,
+        block_id: BlockId,
+    ) -> RpcResult<mp_rpc::v0_8_0::StarknetGetBlockWithTxsAndReceiptsResult>;
+
+    /// Get block information with transaction hashes given the block id
+    #[method(name = "getBlockWithTxHashes")]
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<mp_rpc::v0_8_0::MaybePendingBlockWithTxHashes>;
+
+    /// Get block information with full transactions given the block id
+    #[method(name = "getBlockWithTxs")]
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<mp_rpc::v0_8_0::MaybePendingBlockWithTxs>;
+
+    /// Returns the receipt of a transaction by transaction hash.
+    #[method(name = "getTransactionReceipt")]
+    async fn get_transaction_receipt(
+        &self,
+        transaction_hash: Felt,
+    ) -> RpcResult<mp_rpc::v0_8_0::TxnReceiptWithBlockInfo>;
+
     #[method(name = "getCompiledCasm")]
     fn get_compiled_casm(&self, class_hash: Felt) -> RpcResult<serde_json::Value>;
 
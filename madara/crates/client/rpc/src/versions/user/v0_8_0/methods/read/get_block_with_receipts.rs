@@ -0,0 +1,138 @@
+use mp_block::{BlockId, MadaraMaybePendingBlockInfo};
+use mp_rpc::v0_8_0::{
+    BlockHeader, BlockWithReceipts, PendingBlockHeader, PendingBlockWithReceipts,
+    StarknetGetBlockWithTxsAndReceiptsResult, TransactionAndReceipt,
+};
+use mp_rpc::{BlockStatus, TxnFinalityStatus};
+
+use crate::errors::StarknetRpcResult;
+use crate::Starknet;
+
+/// Get block information with full transactions and receipts given the block id. Since v0.8, the execution resources
+/// of the receipts are the gas consumed by the transactions.
+pub fn get_block_with_receipts(
+    starknet: &Starknet,
+    block_id: BlockId,
+) -> StarknetRpcResult<StarknetGetBlockWithTxsAndReceiptsResult> {
+    tracing::debug!("get_block_with_receipts called with {:?}", block_id);
+    let block = starknet.get_block(&block_id)?;
+
+    let transactions = block.inner.transactions.into_iter().map(|tx| mp_rpc::Txn::from(tx).into());
+
+    let is_on_l1 = if let Some(block_n) = block.info.block_n() {
+        block_n <= starknet.get_l1_last_confirmed_block()?
+    } else {
+        false
+    };
+
+    let finality_status = if is_on_l1 { TxnFinalityStatus::L1 } else { TxnFinalityStatus::L2 };
+
+    let receipts =
+        block.inner.receipts.into_iter().map(|receipt| receipt.to_starknet_types_v0_8_0(finality_status.clone()));
+
+    let transactions_with_receipts = Iterator::zip(transactions, receipts)
+        .map(|(transaction, receipt)| TransactionAndReceipt { receipt, transaction })
+        .collect();
+
+    match block.info {
+        MadaraMaybePendingBlockInfo::Pending(block) => {
+            Ok(StarknetGetBlockWithTxsAndReceiptsResult::Pending(PendingBlockWithReceipts {
+                transactions: transactions_with_receipts,
+                pending_block_header: PendingBlockHeader {
+                    parent_hash: block.header.parent_block_hash,
+                    timestamp: block.header.block_timestamp.0,
+                    sequencer_address: block.header.sequencer_address,
+                    l1_gas_price: block.header.l1_gas_price.l1_gas_price(),
+                    l1_data_gas_price: block.header.l1_gas_price.l1_data_gas_price(),
+                    l2_gas_price: block.header.l1_gas_price.l2_gas_price(),
+                    l1_da_mode: block.header.l1_da_mode.into(),
+                    starknet_version: block.header.protocol_version.to_string(),
+                },
+            }))
+        }
+        MadaraMaybePendingBlockInfo::NotPending(block) => {
+            let status = if is_on_l1 { BlockStatus::AcceptedOnL1 } else { BlockStatus::AcceptedOnL2 };
+            Ok(StarknetGetBlockWithTxsAndReceiptsResult::Block(BlockWithReceipts {
+                transactions: transactions_with_receipts,
+                status,
+                block_header: BlockHeader {
+                    block_hash: block.block_hash,
+                    parent_hash: block.header.parent_block_hash,
+                    block_number: block.header.block_number,
+                    new_root: block.header.global_state_root,
+                    timestamp: block.header.block_timestamp.0,
+                    sequencer_address: block.header.sequencer_address,
+                    l1_gas_price: block.header.l1_gas_price.l1_gas_price(),
+                    l1_data_gas_price: block.header.l1_gas_price.l1_data_gas_price(),
+                    l2_gas_price: block.header.l1_gas_price.l2_gas_price(),
+                    l1_da_mode: block.header.l1_da_mode.into(),
+                    starknet_version: block.header.protocol_version.to_string(),
+                },
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::StarknetRpcApiError,
+        test_utils::{sample_chain_for_block_getters, SampleChainForBlockGetters},
+    };
+    use mp_block::BlockTag;
+    use mp_rpc::v0_8_0::{ExecutionResources, TxnReceipt};
+    use mp_rpc::{L1DaMode, ResourcePrice};
+    use rstest::rstest;
+    use starknet_types_core::felt::Felt;
+
+    #[rstest]
+    fn test_get_block_with_receipts(sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet)) {
+        let (SampleChainForBlockGetters { block_hashes, expected_txs, expected_receipts, .. }, rpc) =
+            sample_chain_for_block_getters;
+        // The receipts of the sample chain consume no gas.
+        let transaction_and_receipt = |i: usize| TransactionAndReceipt {
+            transaction: expected_txs[i].transaction.clone().into(),
+            receipt: TxnReceipt::from_v0_7_1(expected_receipts[i].clone(), ExecutionResources::default()),
+        };
+
+        // Block 0
+        let res = StarknetGetBlockWithTxsAndReceiptsResult::Block(BlockWithReceipts {
+            status: BlockStatus::AcceptedOnL1,
+            transactions: vec![transaction_and_receipt(0)],
+            block_header: BlockHeader {
+                block_hash: block_hashes[0],
+                parent_hash: Felt::ZERO,
+                block_number: 0,
+                new_root: Felt::from_hex_unchecked("0x88912"),
+                timestamp: 43,
+                sequencer_address: Felt::from_hex_unchecked("0xbabaa"),
+                l1_gas_price: ResourcePrice { price_in_fri: 12.into(), price_in_wei: 123.into() },
+                l1_data_gas_price: ResourcePrice { price_in_fri: 52.into(), price_in_wei: 44.into() },
+                l2_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l1_da_mode: L1DaMode::Blob,
+                starknet_version: "0.13.1.1".into(),
+            },
+        });
+        assert_eq!(get_block_with_receipts(&rpc, BlockId::Number(0)).unwrap(), res);
+        assert_eq!(get_block_with_receipts(&rpc, BlockId::Hash(block_hashes[0])).unwrap(), res);
+
+        // Pending
+        let res = StarknetGetBlockWithTxsAndReceiptsResult::Pending(PendingBlockWithReceipts {
+            transactions: vec![transaction_and_receipt(3)],
+            pending_block_header: PendingBlockHeader {
+                parent_hash: block_hashes[2],
+                timestamp: 0,
+                sequencer_address: Felt::ZERO,
+                l1_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l1_data_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l2_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l1_da_mode: L1DaMode::Blob,
+                starknet_version: "0.13.2".into(),
+            },
+        });
+        assert_eq!(get_block_with_receipts(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap(), res);
+
+        assert_eq!(get_block_with_receipts(&rpc, BlockId::Number(3)), Err(StarknetRpcApiError::BlockNotFound));
+    }
+}
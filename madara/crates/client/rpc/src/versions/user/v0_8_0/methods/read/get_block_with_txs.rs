@@ -0,0 +1,128 @@
+use mp_block::{BlockId, MadaraMaybePendingBlockInfo};
+use mp_rpc::v0_8_0::{
+    BlockHeader, BlockWithTxs, MaybePendingBlockWithTxs, PendingBlockHeader, PendingBlockWithTxs, TxnWithHash,
+};
+use mp_rpc::BlockStatus;
+
+use crate::errors::StarknetRpcResult;
+use crate::Starknet;
+
+/// Get block information with full transactions given the block id.
+///
+/// ### Arguments
+///
+/// * `block_id` - The hash of the requested block, or number (height) of the requested block, or a
+///   block tag.
+///
+/// ### Returns
+///
+/// Returns block information with full transactions. Since v0.8, the header has the L2 gas price and the v3
+/// transactions have a bound on the L1 data gas. In case the block is not found, returns a `StarknetRpcApiError` with
+/// `BlockNotFound`.
+pub fn get_block_with_txs(starknet: &Starknet, block_id: BlockId) -> StarknetRpcResult<MaybePendingBlockWithTxs> {
+    let block = starknet.get_block(&block_id)?;
+
+    let transactions_with_hash = Iterator::zip(block.inner.transactions.into_iter(), block.info.tx_hashes())
+        .map(|(transaction, hash)| TxnWithHash {
+            transaction: mp_rpc::Txn::from(transaction).into(),
+            transaction_hash: *hash,
+        })
+        .collect();
+
+    match block.info {
+        MadaraMaybePendingBlockInfo::Pending(block) => Ok(MaybePendingBlockWithTxs::Pending(PendingBlockWithTxs {
+            transactions: transactions_with_hash,
+            pending_block_header: PendingBlockHeader {
+                parent_hash: block.header.parent_block_hash,
+                timestamp: block.header.block_timestamp.0,
+                sequencer_address: block.header.sequencer_address,
+                l1_gas_price: block.header.l1_gas_price.l1_gas_price(),
+                l1_data_gas_price: block.header.l1_gas_price.l1_data_gas_price(),
+                l2_gas_price: block.header.l1_gas_price.l2_gas_price(),
+                l1_da_mode: block.header.l1_da_mode.into(),
+                starknet_version: block.header.protocol_version.to_string(),
+            },
+        })),
+        MadaraMaybePendingBlockInfo::NotPending(block) => {
+            let status = if block.header.block_number <= starknet.get_l1_last_confirmed_block()? {
+                BlockStatus::AcceptedOnL1
+            } else {
+                BlockStatus::AcceptedOnL2
+            };
+            Ok(MaybePendingBlockWithTxs::Block(BlockWithTxs {
+                transactions: transactions_with_hash,
+                status,
+                block_header: BlockHeader {
+                    block_hash: block.block_hash,
+                    parent_hash: block.header.parent_block_hash,
+                    block_number: block.header.block_number,
+                    new_root: block.header.global_state_root,
+                    timestamp: block.header.block_timestamp.0,
+                    sequencer_address: block.header.sequencer_address,
+                    l1_gas_price: block.header.l1_gas_price.l1_gas_price(),
+                    l1_data_gas_price: block.header.l1_gas_price.l1_data_gas_price(),
+                    l2_gas_price: block.header.l1_gas_price.l2_gas_price(),
+                    l1_da_mode: block.header.l1_da_mode.into(),
+                    starknet_version: block.header.protocol_version.to_string(),
+                },
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::StarknetRpcApiError,
+        test_utils::{sample_chain_for_block_getters, SampleChainForBlockGetters},
+    };
+    use mp_block::BlockTag;
+    use mp_rpc::{L1DaMode, ResourcePrice};
+    use rstest::rstest;
+    use starknet_types_core::felt::Felt;
+
+    #[rstest]
+    fn test_get_block_with_txs(sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet)) {
+        let (SampleChainForBlockGetters { block_hashes, expected_txs, .. }, rpc) = sample_chain_for_block_getters;
+
+        // Block 0
+        let res = MaybePendingBlockWithTxs::Block(BlockWithTxs {
+            status: BlockStatus::AcceptedOnL1,
+            transactions: vec![expected_txs[0].clone().into()],
+            block_header: BlockHeader {
+                block_hash: block_hashes[0],
+                parent_hash: Felt::ZERO,
+                block_number: 0,
+                new_root: Felt::from_hex_unchecked("0x88912"),
+                timestamp: 43,
+                sequencer_address: Felt::from_hex_unchecked("0xbabaa"),
+                l1_gas_price: ResourcePrice { price_in_fri: 12.into(), price_in_wei: 123.into() },
+                l1_data_gas_price: ResourcePrice { price_in_fri: 52.into(), price_in_wei: 44.into() },
+                l2_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l1_da_mode: L1DaMode::Blob,
+                starknet_version: "0.13.1.1".into(),
+            },
+        });
+        assert_eq!(get_block_with_txs(&rpc, BlockId::Number(0)).unwrap(), res);
+        assert_eq!(get_block_with_txs(&rpc, BlockId::Hash(block_hashes[0])).unwrap(), res);
+
+        // Pending
+        let res = MaybePendingBlockWithTxs::Pending(PendingBlockWithTxs {
+            transactions: vec![expected_txs[3].clone().into()],
+            pending_block_header: PendingBlockHeader {
+                parent_hash: block_hashes[2],
+                timestamp: 0,
+                sequencer_address: Felt::ZERO,
+                l1_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l1_data_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l2_gas_price: ResourcePrice { price_in_fri: 0.into(), price_in_wei: 0.into() },
+                l1_da_mode: L1DaMode::Blob,
+                starknet_version: "0.13.2".into(),
+            },
+        });
+        assert_eq!(get_block_with_txs(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap(), res);
+
+        assert_eq!(get_block_with_txs(&rpc, BlockId::Number(3)), Err(StarknetRpcApiError::BlockNotFound));
+    }
+}
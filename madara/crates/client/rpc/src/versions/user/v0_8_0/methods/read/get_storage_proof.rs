@@ -73,13 +73,10 @@ fn make_trie_proof<H: StarkHash + Send + Sync>(
     Ok((root_hash, converted_proof))
 }
 
-pub fn get_storage_proof(
-    starknet: &Starknet,
-    block_id: BlockId,
-    class_hashes: Option<Vec<Felt>>,
-    contract_addresses: Option<Vec<Felt>>,
-    contracts_storage_keys: Option<Vec<ContractStorageKeysItem>>,
-) -> RpcResult<GetStorageProofResult> {
+/// Resolves the block the tries are read at. Reading the tries at an older block means reverting them with their trie
+/// logs, so the block must be within `max_distance` blocks of the latest block, unless a snapshot of the database at
+/// that block is kept.
+pub(crate) fn resolve_trie_block_n(starknet: &Starknet, block_id: BlockId) -> RpcResult<u64> {
     // Pending block does not have a state root, so always fallback to latest.
     let block_id = match block_id {
         BlockId::Tag(BlockTag::Pending) => BlockId::Tag(BlockTag::Latest),
@@ -92,18 +89,59 @@ pub fn get_storage_proof(
         .or_internal_server_error("Resolving block number")?
         .ok_or(StarknetRpcApiError::NoBlocks)?;
 
-    let Some(latest) = starknet.backend.get_latest_block_n().or_internal_server_error("Getting latest block in db")?
+    // The global tries lag behind the blocks imported with `--disable-root`, until they are caught up.
+    let Some(trie_block_n) =
+        starknet.backend.get_global_trie_block_n().or_internal_server_error("Getting global trie block in db")?
     else {
-        return Err(StarknetRpcApiError::BlockNotFound.into());
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: "The global tries were not computed for any block yet".to_string(),
+        }
+        .into());
     };
+    if block_n > trie_block_n {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: format!("The global tries were only computed up to block #{trie_block_n}"),
+        }
+        .into());
+    }
 
-    if latest.saturating_sub(block_n) > starknet.storage_proof_config.max_distance {
+    if trie_block_n.saturating_sub(block_n) > starknet.storage_proof_config.max_distance
+        && !starknet.backend.has_trie_snapshot(block_n)
+    {
         return Err(StarknetRpcApiError::CannotMakeProofOnOldBlock.into());
     }
 
+    Ok(block_n)
+}
+
+/// Returns the root of the storage trie of `contract_address` at block `block_n`, which is zero when the contract has
+/// no storage.
+pub(crate) fn contract_storage_root(starknet: &Starknet, block_n: u64, contract_address: &Felt) -> RpcResult<Felt> {
+    let trie = starknet.backend.contract_storage_trie();
+    let storage = trie
+        .get_transactional_state(BasicId::new(block_n), trie.get_config())
+        .map_err(|err| anyhow::anyhow!("{err:#}"))
+        .or_internal_server_error("Getting transactional state")?
+        .ok_or(StarknetRpcApiError::CannotMakeProofOnOldBlock)?;
+
+    Ok(storage
+        .root_hash(&contract_address.to_bytes_be())
+        .map_err(|err| anyhow::anyhow!("{err:#}"))
+        .or_internal_server_error("Getting root hash of trie")?)
+}
+
+pub fn get_storage_proof(
+    starknet: &Starknet,
+    block_id: BlockId,
+    class_hashes: Option<Vec<Felt>>,
+    contract_addresses: Option<Vec<Felt>>,
+    contracts_storage_keys: Option<Vec<ContractStorageKeysItem>>,
+) -> RpcResult<GetStorageProofResult> {
+    let block_n = resolve_trie_block_n(starknet, block_id)?;
+
     let block_hash = starknet
         .backend
-        .get_block_hash(&block_id)
+        .get_block_hash(&BlockId::Number(block_n))
         .or_internal_server_error("Resolving block hash")?
         .ok_or(StarknetRpcApiError::NoBlocks)?;
 
@@ -165,6 +203,7 @@ pub fn get_storage_proof(
                     .get_contract_class_hash_at(&DbBlockId::Number(block_n), contract_addr)
                     .or_internal_server_error("Getting contract class hash")?
                     .unwrap_or(Felt::ZERO),
+                storage_root: contract_storage_root(starknet, block_n, contract_addr)?,
             })
         })
         .collect::<RpcResult<_>>()?;
@@ -0,0 +1,121 @@
+use mp_block::MadaraMaybePendingBlockInfo;
+use mp_rpc::v0_8_0::TxnReceiptWithBlockInfo;
+use mp_rpc::TxnFinalityStatus;
+use starknet_types_core::felt::Felt;
+
+use crate::errors::{StarknetRpcApiError, StarknetRpcResult};
+use crate::utils::ResultExt;
+use crate::Starknet;
+
+/// Get the transaction receipt by the transaction hash. Since v0.8, the execution resources of the receipt are the gas
+/// consumed by the transaction.
+///
+/// ### Errors
+///
+/// The function may return a `TXN_HASH_NOT_FOUND` error if the specified transaction hash is
+/// not found.
+pub fn get_transaction_receipt(
+    starknet: &Starknet,
+    transaction_hash: Felt,
+) -> StarknetRpcResult<TxnReceiptWithBlockInfo> {
+    let (block, tx_index) = starknet
+        .backend
+        .find_tx_hash_block(&transaction_hash)
+        .or_internal_server_error("Error getting block from tx_hash")?
+        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+
+    let is_on_l1 = if let Some(block_n) = block.info.block_n() {
+        block_n <= starknet.get_l1_last_confirmed_block()?
+    } else {
+        false
+    };
+
+    let finality_status = if is_on_l1 { TxnFinalityStatus::L1 } else { TxnFinalityStatus::L2 };
+
+    let transaction_receipt = block
+        .inner
+        .receipts
+        .get(tx_index.0 as usize)
+        .ok_or(StarknetRpcApiError::TxnHashNotFound)?
+        .clone()
+        .to_starknet_types_v0_8_0(finality_status);
+
+    let (block_number, block_hash) = match block.info {
+        MadaraMaybePendingBlockInfo::Pending(_) => (None, None),
+        MadaraMaybePendingBlockInfo::NotPending(block) => (Some(block.header.block_number), Some(block.block_hash)),
+    };
+
+    Ok(TxnReceiptWithBlockInfo { transaction_receipt, block_hash, block_number })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock};
+    use mp_receipt::{
+        ExecutionResources, ExecutionResult, FeePayment, InvokeTransactionReceipt, L1Gas, PriceUnit, TransactionReceipt,
+    };
+    use mp_rpc::v0_8_0::TxnReceipt;
+    use mp_state_update::StateDiff;
+    use mp_transactions::{InvokeTransaction, InvokeTransactionV0, Transaction};
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    fn test_get_transaction_receipt_gas_consumed(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let tx_hash = Felt::from_hex_unchecked("0x8888888");
+        let block_hash = Felt::from_hex_unchecked("0x1777177171");
+        backend
+            .store_block(
+                MadaraMaybePendingBlock {
+                    info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                        header: Header { block_number: 0, transaction_count: 1, ..Default::default() },
+                        block_hash,
+                        tx_hashes: vec![tx_hash],
+                    }),
+                    inner: MadaraBlockInner {
+                        transactions: vec![Transaction::Invoke(InvokeTransaction::V0(InvokeTransactionV0::default()))],
+                        receipts: vec![TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                            transaction_hash: tx_hash,
+                            actual_fee: FeePayment { amount: Felt::from_hex_unchecked("0x9"), unit: PriceUnit::Wei },
+                            messages_sent: vec![],
+                            events: vec![],
+                            execution_resources: ExecutionResources {
+                                steps: 100,
+                                data_availability: L1Gas { l1_gas: 1, l1_data_gas: 2 },
+                                total_gas_consumed: L1Gas { l1_gas: 30, l1_data_gas: 2 },
+                                ..Default::default()
+                            },
+                            execution_result: ExecutionResult::Succeeded,
+                        })],
+                    },
+                },
+                StateDiff::default(),
+                vec![],
+                None,
+                None,
+            )
+            .unwrap();
+
+        let receipt = get_transaction_receipt(&rpc, tx_hash).unwrap();
+        assert_eq!(receipt.block_hash, Some(block_hash));
+        assert_eq!(receipt.block_number, Some(0));
+        let TxnReceipt::Invoke(receipt) = receipt.transaction_receipt else { unreachable!("An invoke receipt") };
+        let execution_resources = receipt.common_receipt_properties.execution_resources;
+        // The L1 gas is the gas consumed by the whole transaction, not only by its data availability.
+        assert_eq!(execution_resources.l1_gas, 30);
+        assert_eq!(execution_resources.l1_data_gas, 2);
+        assert_eq!(execution_resources.l2_gas, 0);
+    }
+
+    #[rstest]
+    fn test_get_transaction_receipt_not_found(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (_backend, rpc) = rpc_test_setup;
+
+        let does_not_exist = Felt::from_hex_unchecked("0x7128638126378");
+        assert_eq!(get_transaction_receipt(&rpc, does_not_exist), Err(StarknetRpcApiError::TxnHashNotFound));
+    }
+}
@@ -3,10 +3,18 @@ use crate::Starknet;
 use jsonrpsee::core::{async_trait, RpcResult};
 use mp_block::BlockId;
 use mp_chain_config::RpcVersion;
+use mp_rpc::v0_8_0::{
+    MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs, StarknetGetBlockWithTxsAndReceiptsResult,
+    TxnReceiptWithBlockInfo,
+};
 use starknet_types_core::felt::Felt;
 
+pub mod get_block_with_receipts;
+pub mod get_block_with_tx_hashes;
+pub mod get_block_with_txs;
 pub mod get_compiled_casm;
 pub mod get_storage_proof;
+pub mod get_transaction_receipt;
 
 #[async_trait]
 impl StarknetReadRpcApiV0_8_0Server for Starknet {
@@ -14,6 +22,22 @@ impl StarknetReadRpcApiV0_8_0Server for Starknet {
         Ok(RpcVersion::RPC_VERSION_0_8_0.to_string())
     }
 
+    async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<StarknetGetBlockWithTxsAndReceiptsResult> {
+        Ok(get_block_with_receipts::get_block_with_receipts(self, block_id)?)
+    }
+
+    fn get_block_with_tx_hashes(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithTxHashes> {
+        Ok(get_block_with_tx_hashes::get_block_with_tx_hashes(self, block_id)?)
+    }
+
+    fn get_block_with_txs(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithTxs> {
+        Ok(get_block_with_txs::get_block_with_txs(self, block_id)?)
+    }
+
+    async fn get_transaction_receipt(&self, transaction_hash: Felt) -> RpcResult<TxnReceiptWithBlockInfo> {
+        Ok(get_transaction_receipt::get_transaction_receipt(self, transaction_hash)?)
+    }
+
     fn get_compiled_casm(&self, class_hash: Felt) -> RpcResult<serde_json::Value> {
         Ok(get_compiled_casm::get_compiled_casm(self, class_hash)?)
     }
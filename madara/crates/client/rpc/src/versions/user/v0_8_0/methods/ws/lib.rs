@@ -5,15 +5,17 @@ use crate::versions::user::v0_8_0::StarknetWsRpcApiV0_8_0Server;
 
 use super::subscribe_events::*;
 use super::subscribe_new_heads::*;
+use super::subscribe_pending_transactions::*;
+use super::subscribe_transaction_status::*;
 
 #[jsonrpsee::core::async_trait]
 impl StarknetWsRpcApiV0_8_0Server for crate::Starknet {
     async fn subscribe_new_heads(
         &self,
         subscription_sink: jsonrpsee::PendingSubscriptionSink,
-        block: BlockId,
+        block_id: Option<BlockId>,
     ) -> jsonrpsee::core::SubscriptionResult {
-        Ok(subscribe_new_heads(self, subscription_sink, block).await?)
+        Ok(subscribe_new_heads(self, subscription_sink, block_id).await?)
     }
 
     async fn subscribe_events(
@@ -25,4 +27,27 @@ impl StarknetWsRpcApiV0_8_0Server for crate::Starknet {
     ) -> jsonrpsee::core::SubscriptionResult {
         Ok(subscribe_events(self, subscription_sink, from_address, keys, block).await?)
     }
+
+    async fn subscribe_pending_transactions(
+        &self,
+        subscription_sink: jsonrpsee::PendingSubscriptionSink,
+        transaction_details: Option<bool>,
+        sender_address: Option<Vec<Felt>>,
+    ) -> jsonrpsee::core::SubscriptionResult {
+        Ok(subscribe_pending_transactions(
+            self,
+            subscription_sink,
+            transaction_details.unwrap_or_default(),
+            sender_address,
+        )
+        .await?)
+    }
+
+    async fn subscribe_transaction_status(
+        &self,
+        subscription_sink: jsonrpsee::PendingSubscriptionSink,
+        transaction_hash: Felt,
+    ) -> jsonrpsee::core::SubscriptionResult {
+        Ok(subscribe_transaction_status(self, subscription_sink, transaction_hash).await?)
+    }
 }
@@ -1,14 +1,93 @@
 use mp_block::BlockId;
 use starknet_types_core::felt::Felt;
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::{
-    errors::{ErrorExtWs, StarknetWsApiError},
+    errors::{ErrorExtWs, OptionExtWs, StarknetWsApiError},
     utils::event_match_filter,
     versions::user::v0_7_1::methods::read::get_events::drain_block_events,
 };
 
 use super::BLOCK_PAST_LIMIT;
 
+/// Position of a subscription in the chain: the matching events of the blocks before `block_n`, and the first `sent`
+/// matching events of block `block_n`, have been sent.
+struct Cursor {
+    block_n: u64,
+    sent: usize,
+}
+
+struct EventSubscription<'a> {
+    starknet: &'a crate::Starknet,
+    sink: jsonrpsee::SubscriptionSink,
+    from_address: Option<Felt>,
+    keys: Option<Vec<Vec<Felt>>>,
+    cursor: Cursor,
+}
+
+impl EventSubscription<'_> {
+    async fn send(&mut self, event: &mp_rpc::EmittedEvent) -> Result<(), StarknetWsApiError> {
+        let msg = jsonrpsee::SubscriptionMessage::from_json(event)
+            .or_internal_server_error("Failed to create response message")?;
+        self.sink.send(msg).await.or_internal_server_error("Failed to respond to websocket request")?;
+        self.cursor.sent += 1;
+        Ok(())
+    }
+
+    /// Sends the events of the blocks from the cursor up to `to_block_n` included, reading them from the database.
+    async fn catch_up(&mut self, to_block_n: u64) -> Result<(), StarknetWsApiError> {
+        if self.cursor.block_n > to_block_n {
+            return Ok(());
+        }
+        for block_n in self.cursor.block_n..=to_block_n {
+            let block = self
+                .starknet
+                .get_block(&BlockId::Number(block_n))
+                .or_internal_server_error("Failed to retrieve block")?;
+            let sent = if block_n == self.cursor.block_n { self.cursor.sent } else { 0 };
+            self.cursor = Cursor { block_n, sent };
+            let events: Vec<_> = drain_block_events(block)
+                .filter(|event| event_match_filter(&event.event, self.from_address.as_ref(), self.keys.as_deref()))
+                .skip(sent)
+                .collect();
+            for event in &events {
+                self.send(event).await?;
+            }
+        }
+        self.cursor = Cursor { block_n: to_block_n + 1, sent: 0 };
+        Ok(())
+    }
+
+    /// Sends the events dropped from the channel because the subscriber was too slow, reading them from the database.
+    async fn recover(&mut self, skipped: u64) -> Result<(), StarknetWsApiError> {
+        tracing::debug!("Event subscription lagged behind by {skipped} events, reading them from the database");
+        let latest_block =
+            self.starknet.backend.get_latest_block_n().or_internal_server_error("Failed to retrieve latest block")?;
+        match latest_block {
+            Some(latest_block) => self.catch_up(latest_block).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Sends an event published by the database as its block is imported. Events of blocks which were already sent
+    /// while catching up are skipped.
+    async fn send_live(&mut self, event: mp_rpc::EmittedEvent) -> Result<(), StarknetWsApiError> {
+        let block_n = event.block_number.ok_or_internal_server_error("Imported event without block number")?;
+        if block_n < self.cursor.block_n
+            || !event_match_filter(&event.event, self.from_address.as_ref(), self.keys.as_deref())
+        {
+            return Ok(());
+        }
+        if block_n > self.cursor.block_n {
+            self.cursor = Cursor { block_n, sent: 0 };
+        }
+        self.send(&event).await
+    }
+}
+
+/// Events are received as their blocks are imported. The subscription is opened before the database is read, so that
+/// no block is missed in between: the events of the blocks read from the database are then skipped when received.
+/// When the subscriber is too slow and the channel overflows, the missed events are read from the database too.
 pub async fn subscribe_events(
     starknet: &crate::Starknet,
     subscription_sink: jsonrpsee::PendingSubscriptionSink,
@@ -16,16 +95,21 @@ pub async fn subscribe_events(
     keys: Option<Vec<Vec<Felt>>>,
     block_id: Option<BlockId>,
 ) -> Result<(), StarknetWsApiError> {
+    let mut rx = starknet.backend.subscribe_events(from_address);
     let sink = subscription_sink.accept().await.or_internal_server_error("Failed to establish websocket connection")?;
 
-    let mut rx = starknet.backend.subscribe_events(from_address);
+    let latest_block =
+        starknet.backend.get_latest_block_n().or_internal_server_error("Failed to retrieve latest block")?;
+    let mut subscription = EventSubscription {
+        starknet,
+        sink,
+        from_address,
+        keys,
+        cursor: Cursor { block_n: latest_block.map_or(0, |block_n| block_n + 1), sent: 0 },
+    };
 
     if let Some(block_id) = block_id {
-        let latest_block = starknet
-            .backend
-            .get_latest_block_n()
-            .or_internal_server_error("Failed to retrieve latest block")?
-            .ok_or(StarknetWsApiError::NoBlocks)?;
+        let latest_block = latest_block.ok_or(StarknetWsApiError::NoBlocks)?;
 
         let block_n = starknet
             .backend
@@ -38,31 +122,20 @@ pub async fn subscribe_events(
         if block_n < latest_block.saturating_sub(BLOCK_PAST_LIMIT) {
             return Err(StarknetWsApiError::TooManyBlocksBack);
         }
-        for block_number in block_n..=latest_block {
-            let block = starknet
-                .get_block(&BlockId::Number(block_number))
-                .or_internal_server_error("Failed to retrieve block")?;
-            for event in drain_block_events(block)
-                .filter(|event| event_match_filter(&event.event, from_address.as_ref(), keys.as_deref()))
-            {
-                let msg = jsonrpsee::SubscriptionMessage::from_json(&event)
-                    .or_internal_server_error("Failed to create response message")?;
-                sink.send(msg).await.or_internal_server_error("Failed to respond to websocket request")?;
-            }
-        }
+        subscription.cursor = Cursor { block_n, sent: 0 };
+        subscription.catch_up(latest_block).await?;
     }
 
     loop {
         tokio::select! {
-            event = rx.recv() => {
-                let event = event.or_internal_server_error("Failed to retrieve event")?;
-                if event_match_filter(&event.event, from_address.as_ref(), keys.as_deref()) {
-                    let msg = jsonrpsee::SubscriptionMessage::from_json(&event)
-                        .or_internal_server_error("Failed to create response message")?;
-                    sink.send(msg).await.or_internal_server_error("Failed to respond to websocket request")?;
+            event = rx.recv() => match event {
+                Ok(event) => subscription.send_live(event).await?,
+                Err(RecvError::Lagged(skipped)) => subscription.recover(skipped).await?,
+                Err(RecvError::Closed) => {
+                    return Err(StarknetWsApiError::internal_server_error("Event channel closed"));
                 }
             },
-            _ = sink.closed() => {
+            _ = subscription.sink.closed() => {
                 return Ok(())
             }
         }
@@ -334,4 +407,48 @@ mod test {
             assert_eq!(received, event);
         }
     }
+
+    // Test 5: Event subscription overflowing the event channel
+    // - Imports a single block with more events than the channel can hold, before the subscription reads any of them
+    // - The missed events are read from the database: all of them are received, once and in order
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_events_lagged(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        let _server_handle = server.start(StarknetWsRpcApiV0_8_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+
+        let mut sub = client.subscribe_events(None, None, None).await.expect("Subscribing to events");
+
+        let store_block = |block_n: u64, receipt: TransactionReceipt| {
+            let block = mp_block::MadaraMaybePendingBlock {
+                info: mp_block::MadaraMaybePendingBlockInfo::NotPending(mp_block::MadaraBlockInfo {
+                    header: mp_block::Header { block_number: block_n, ..Default::default() },
+                    block_hash: Felt::from(block_n),
+                    tx_hashes: vec![],
+                }),
+                inner: mp_block::MadaraBlockInner { transactions: vec![], receipts: vec![receipt] },
+            };
+            backend
+                .store_block(block.clone(), mp_state_update::StateDiff::default(), vec![], None, None)
+                .expect("Storing block");
+            block
+        };
+
+        // The channel holds 100 events.
+        let block = store_block(0, generate_receipt(0, 150, 1));
+        for event in drain_block_events(block) {
+            let received = sub.next().await.expect("Subscribing closed").expect("Failed to retrieve event");
+            assert_eq!(received, event);
+        }
+
+        // The events of block 0 left in the channel are not sent a second time.
+        let block = store_block(1, generate_receipt(1, 1, 1));
+        for event in drain_block_events(block) {
+            let received = sub.next().await.expect("Subscribing closed").expect("Failed to retrieve event");
+            assert_eq!(received, event);
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use mp_block::{BlockId, BlockTag};
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::errors::{ErrorExtWs, OptionExtWs, StarknetWsApiError};
 
@@ -7,10 +8,14 @@ use super::BLOCK_PAST_LIMIT;
 pub async fn subscribe_new_heads(
     starknet: &crate::Starknet,
     subscription_sink: jsonrpsee::PendingSubscriptionSink,
-    block_id: BlockId,
+    block_id: Option<BlockId>,
 ) -> Result<(), StarknetWsApiError> {
+    // Subscribed before the database is read, so that no block is missed in between.
+    let mut rx = starknet.backend.subscribe_block_info();
     let sink = subscription_sink.accept().await.or_internal_server_error("Failed to establish websocket connection")?;
 
+    let block_id = block_id.unwrap_or(BlockId::Tag(BlockTag::Latest));
+
     let mut block_n = match block_id {
         BlockId::Number(block_n) => {
             let err = || format!("Failed to retrieve block info for block {block_n}");
@@ -51,67 +56,57 @@ pub async fn subscribe_new_heads(
             .get_latest_block_n()
             .or_internal_server_error("Failed to retrieve block info for latest block")?
             .ok_or(StarknetWsApiError::NoBlocks)?,
+        BlockId::Tag(BlockTag::L1Accepted) => starknet
+            .backend
+            .get_l1_last_confirmed_block()
+            .or_internal_server_error("Failed to retrieve the latest block confirmed on L1")?
+            .ok_or(StarknetWsApiError::NoBlocks)?,
         BlockId::Tag(BlockTag::Pending) => {
             return Err(StarknetWsApiError::Pending);
         }
     };
 
-    let mut rx = starknet.backend.subscribe_block_info();
-    for n in block_n.. {
-        if sink.is_closed() {
-            return Ok(());
-        }
-
-        let block_info = match starknet.backend.get_block_info(&BlockId::Number(n)) {
-            Ok(Some(block_info)) => {
-                let err = || format!("Failed to retrieve block info for block {n}");
-                block_info.as_nonpending_owned().ok_or_else_internal_server_error(err)?
-            }
-            Ok(None) => break,
-            Err(e) => {
-                let err = format!("Failed to retrieve block info for block {n}: {e}");
-                return Err(StarknetWsApiError::internal_server_error(err));
+    loop {
+        // Headers of the blocks already in the database.
+        loop {
+            if sink.is_closed() {
+                return Ok(());
             }
-        };
-
-        send_block_header(&sink, block_info, block_n).await?;
-        block_n = block_n.saturating_add(1);
-    }
 
-    // Catching up with the backend
-    loop {
-        tokio::select! {
-            block_info = rx.recv() => {
-                let block_info = block_info.or_internal_server_error("Failed to retrieve block info")?;
-                if block_info.header.block_number == block_n {
-                    break send_block_header(&sink, block_info, block_n).await?;
+            let block_info = match starknet.backend.get_block_info(&BlockId::Number(block_n)) {
+                Ok(Some(block_info)) => {
+                    let err = || format!("Failed to retrieve block info for block {block_n}");
+                    block_info.as_nonpending_owned().ok_or_else_internal_server_error(err)?
                 }
-            },
-            _ = sink.closed() => {
-                return Ok(())
-            }
+                Ok(None) => break,
+                Err(e) => {
+                    let err = format!("Failed to retrieve block info for block {block_n}: {e}");
+                    return Err(StarknetWsApiError::internal_server_error(err));
+                }
+            };
+
+            send_block_header(&sink, block_info, block_n).await?;
+            block_n = block_n.saturating_add(1);
         }
-    }
 
-    // New block headers
-    loop {
-        tokio::select! {
-            block_info = rx.recv() => {
-                let block_info = block_info.or_internal_server_error("Failed to retrieve block info")?;
-                if block_info.header.block_number == block_n + 1 {
-                    send_block_header(&sink, block_info, block_n).await?;
-                } else {
-                    let err = format!(
-                        "Received non-sequential block {}, expected {}",
-                        block_info.header.block_number,
-                        block_n + 1
-                    );
-                    return Err(StarknetWsApiError::internal_server_error(err));
+        // New block headers, as the blocks are imported. The headers of the blocks read from the database are skipped.
+        // When the subscription lags behind and headers are dropped from the channel, they are read from the database.
+        loop {
+            tokio::select! {
+                block_info = rx.recv() => match block_info {
+                    Ok(block_info) if block_info.header.block_number < block_n => {}
+                    Ok(block_info) if block_info.header.block_number == block_n => {
+                        send_block_header(&sink, block_info, block_n).await?;
+                        block_n = block_n.saturating_add(1);
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => {
+                        return Err(StarknetWsApiError::internal_server_error("Block info channel closed"));
+                    }
+                },
+                _ = sink.closed() => {
+                    return Ok(())
                 }
-                block_n = block_n.saturating_add(1);
-            },
-            _ = sink.closed() => {
-                return Ok(())
             }
         }
     }
@@ -192,7 +187,7 @@ mod test {
         let expected = generator.next().expect("Retrieving block from backend");
 
         let mut sub =
-            client.subscribe_new_heads(BlockId::Tag(BlockTag::Latest)).await.expect("starknet_subscribeNewHeads");
+            client.subscribe_new_heads(Some(BlockId::Tag(BlockTag::Latest))).await.expect("starknet_subscribeNewHeads");
 
         let next = sub.next().await;
         let header = next.expect("Waiting for block header").expect("Waiting for block header");
@@ -219,7 +214,7 @@ mod test {
         let generator = block_generator(&backend);
         let expected: Vec<_> = generator.take(BLOCK_PAST_LIMIT as usize).collect();
 
-        let mut sub = client.subscribe_new_heads(BlockId::Number(0)).await.expect("starknet_subscribeNewHeads");
+        let mut sub = client.subscribe_new_heads(Some(BlockId::Number(0))).await.expect("starknet_subscribeNewHeads");
 
         for e in expected {
             let next = sub.next().await;
@@ -248,7 +243,7 @@ mod test {
         let mut generator = block_generator(&backend);
         let expected = generator.next().expect("Retrieving block from backend");
 
-        let mut sub = client.subscribe_new_heads(BlockId::Number(0)).await.expect("starknet_subscribeNewHeads");
+        let mut sub = client.subscribe_new_heads(Some(BlockId::Number(0))).await.expect("starknet_subscribeNewHeads");
 
         let next = sub.next().await;
         let header = next.expect("Waiting for block header").expect("Waiting for block header");
@@ -278,7 +273,7 @@ mod test {
         let mut generator = block_generator(&backend);
         let _block_0 = generator.next().expect("Retrieving block from backend");
 
-        let mut sub = client.subscribe_new_heads(BlockId::Number(1)).await.expect("starknet_subscribeNewHeads");
+        let mut sub = client.subscribe_new_heads(Some(BlockId::Number(1))).await.expect("starknet_subscribeNewHeads");
 
         let block_1 = generator.next().expect("Retrieving block from backend");
 
@@ -313,7 +308,7 @@ mod test {
         let generator = block_generator(&backend);
         let _expected: Vec<_> = generator.take(BLOCK_PAST_LIMIT as usize + 2).collect();
 
-        let mut sub = client.subscribe_new_heads(BlockId::Number(0)).await.expect("starknet_subscribeNewHeads");
+        let mut sub = client.subscribe_new_heads(Some(BlockId::Number(0))).await.expect("starknet_subscribeNewHeads");
 
         // Jsonrsee seems to just close the connection and not return the error
         // to the client so this is the best we can do :/
@@ -338,7 +333,7 @@ mod test {
         let _expected: Vec<_> = generator.take(BLOCK_PAST_LIMIT as usize + 2).collect();
 
         let mut sub =
-            client.subscribe_new_heads(BlockId::Hash(Felt::from(0))).await.expect("starknet_subscribeNewHeads");
+            client.subscribe_new_heads(Some(BlockId::Hash(Felt::from(0)))).await.expect("starknet_subscribeNewHeads");
 
         // Jsonrsee seems to just close the connection and not return the error
         // to the client so this is the best we can do :/
@@ -359,12 +354,61 @@ mod test {
         let generator = block_generator(&backend);
         let _expected: Vec<_> = generator.take(BLOCK_PAST_LIMIT as usize + 2).collect();
 
-        let mut sub =
-            client.subscribe_new_heads(BlockId::Tag(BlockTag::Pending)).await.expect("starknet_subscribeNewHeads");
+        let mut sub = client
+            .subscribe_new_heads(Some(BlockId::Tag(BlockTag::Pending)))
+            .await
+            .expect("starknet_subscribeNewHeads");
 
         // Jsonrsee seems to just close the connection and not return the error
         // to the client so this is the best we can do :/
         let next = sub.next().await;
         assert!(next.is_none());
     }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_new_heads_default_latest(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        // Server will be stopped once this is dropped
+        let _server_handle = server.start(StarknetWsRpcApiV0_8_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+
+        let mut generator = block_generator(&backend);
+        let _block_0 = generator.next().expect("Retrieving block from backend");
+        let block_1 = generator.next().expect("Retrieving block from backend");
+
+        let mut sub = client.subscribe_new_heads(None).await.expect("starknet_subscribeNewHeads");
+        let block_2 = generator.next().expect("Retrieving block from backend");
+
+        for expected in [block_1, block_2] {
+            let next = sub.next().await;
+            let header = next.expect("Waiting for block header").expect("Waiting for block header");
+            assert_eq!(header, expected);
+        }
+    }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_new_heads_lagged(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        // Server will be stopped once this is dropped
+        let _server_handle = server.start(StarknetWsRpcApiV0_8_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+
+        let mut generator = block_generator(&backend);
+        let block_0 = generator.next().expect("Retrieving block from backend");
+        let mut sub = client.subscribe_new_heads(None).await.expect("starknet_subscribeNewHeads");
+
+        // The channel holds 100 headers: the missed headers are read from the database.
+        let expected: Vec<_> = std::iter::once(block_0).chain(generator.take(150)).collect();
+        for expected in expected {
+            let next = sub.next().await;
+            let header = next.expect("Waiting for block header").expect("Waiting for block header");
+            assert_eq!(header, expected);
+        }
+    }
 }
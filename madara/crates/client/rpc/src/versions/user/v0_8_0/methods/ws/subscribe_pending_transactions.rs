@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use mc_db::db_block_id::DbBlockId;
+use mp_transactions::{DeployAccountTransaction, Transaction, TransactionWithHash};
+use starknet_types_core::felt::Felt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::errors::{ErrorExtWs, StarknetWsApiError};
+use crate::versions::user::v0_8_0::PendingTxnInfo;
+
+/// Maximum number of addresses in the `sender_address` filter.
+const SENDER_ADDRESS_LIMIT: usize = 128;
+/// Interval at which the pending block is polled for new transactions when the node is not a sequencer.
+const PENDING_BLOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Notifies the transactions added to the pending state.
+///
+/// On a sequencer, transactions are notified as soon as they are accepted into the mempool. On a full node, which has
+/// no mempool, they are notified when they appear in the pending block synced from the gateway.
+pub async fn subscribe_pending_transactions(
+    starknet: &crate::Starknet,
+    subscription_sink: jsonrpsee::PendingSubscriptionSink,
+    transaction_details: bool,
+    sender_address: Option<Vec<Felt>>,
+) -> Result<(), StarknetWsApiError> {
+    if sender_address.as_ref().is_some_and(|addresses| addresses.len() > SENDER_ADDRESS_LIMIT) {
+        return Err(StarknetWsApiError::TooManyAddressesInFilter);
+    }
+    let sender_address: Option<HashSet<Felt>> = sender_address.map(|addresses| addresses.into_iter().collect());
+
+    let sink = subscription_sink.accept().await.or_internal_server_error("Failed to establish websocket connection")?;
+
+    if let Some(mempool) = &starknet.mempool {
+        let mut rx = mempool.subscribe_accepted_txs();
+        loop {
+            tokio::select! {
+                tx = rx.recv() => {
+                    match tx {
+                        Ok(tx) => send_pending_tx(&sink, tx, transaction_details, sender_address.as_ref()).await?,
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::debug!("Pending transactions subscription lagged, skipped {skipped} transactions");
+                        }
+                        Err(RecvError::Closed) => return Ok(()),
+                    }
+                },
+                _ = sink.closed() => {
+                    return Ok(())
+                }
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut interval = tokio::time::interval(PENDING_BLOCK_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let Some(block) = starknet
+                    .backend
+                    .get_block(&DbBlockId::Pending)
+                    .or_internal_server_error("Failed to retrieve pending block")?
+                else {
+                    continue;
+                };
+                let txs: Vec<_> = block
+                    .inner
+                    .transactions
+                    .into_iter()
+                    .zip(block.info.tx_hashes().iter().copied())
+                    .map(|(transaction, hash)| TransactionWithHash::new(transaction, hash))
+                    .collect();
+                // Only the hashes of the current pending block are kept: once a block is closed, its transactions do
+                // not appear in the next pending block.
+                seen.retain(|hash| txs.iter().any(|tx| &tx.hash == hash));
+                for tx in txs {
+                    if seen.insert(tx.hash) {
+                        send_pending_tx(&sink, tx, transaction_details, sender_address.as_ref()).await?;
+                    }
+                }
+            },
+            _ = sink.closed() => {
+                return Ok(())
+            }
+        }
+    }
+}
+
+async fn send_pending_tx(
+    sink: &jsonrpsee::SubscriptionSink,
+    tx: TransactionWithHash,
+    transaction_details: bool,
+    sender_address: Option<&HashSet<Felt>>,
+) -> Result<(), StarknetWsApiError> {
+    if let Some(sender_address) = sender_address {
+        if !tx_sender(&tx.transaction).is_some_and(|address| sender_address.contains(&address)) {
+            return Ok(());
+        }
+    }
+    let item = if transaction_details {
+        PendingTxnInfo::Full(mp_rpc::TxnWithHash { transaction: tx.transaction.into(), transaction_hash: tx.hash })
+    } else {
+        PendingTxnInfo::Hash(tx.hash)
+    };
+    let msg = jsonrpsee::SubscriptionMessage::from_json(&item)
+        .or_internal_server_error("Failed to create response message")?;
+    sink.send(msg).await.or_internal_server_error("Failed to respond to websocket request")
+}
+
+/// The address of the account a transaction is sent from, or of the contract an L1 handler transaction is sent to.
+fn tx_sender(tx: &Transaction) -> Option<Felt> {
+    match tx {
+        Transaction::Invoke(tx) => Some(*tx.sender_address()),
+        Transaction::Declare(tx) => Some(*tx.sender_address()),
+        Transaction::DeployAccount(DeployAccountTransaction::V1(tx)) => Some(tx.calculate_contract_address()),
+        Transaction::DeployAccount(DeployAccountTransaction::V3(tx)) => Some(tx.calculate_contract_address()),
+        Transaction::L1Handler(tx) => Some(tx.contract_address),
+        Transaction::Deploy(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::{sample_chain_for_block_getters, SampleChainForBlockGetters},
+        versions::user::v0_8_0::{StarknetWsRpcApiV0_8_0Client, StarknetWsRpcApiV0_8_0Server},
+        Starknet,
+    };
+    use jsonrpsee::ws_client::WsClientBuilder;
+
+    async fn start_server(starknet: Starknet) -> (jsonrpsee::server::ServerHandle, jsonrpsee::ws_client::WsClient) {
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        let server_handle = server.start(StarknetWsRpcApiV0_8_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+        (server_handle, client)
+    }
+
+    // The pending block of the sample chain has a single transaction, sent from 0x434b3.
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_pending_transactions_hashes(
+        sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet),
+    ) {
+        let (SampleChainForBlockGetters { tx_hashes, .. }, starknet) = sample_chain_for_block_getters;
+        let (_server_handle, client) = start_server(starknet).await;
+
+        let mut sub =
+            client.subscribe_pending_transactions(None, None).await.expect("Subscribing to pending transactions");
+        let received = sub.next().await.expect("Subscription closed").expect("Failed to retrieve transaction");
+        assert_eq!(received, PendingTxnInfo::Hash(tx_hashes[3]));
+    }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_pending_transactions_details_filter_sender(
+        sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet),
+    ) {
+        let (SampleChainForBlockGetters { expected_txs, .. }, starknet) = sample_chain_for_block_getters;
+        let (_server_handle, client) = start_server(starknet).await;
+
+        let mut sub = client
+            .subscribe_pending_transactions(Some(true), Some(vec![Felt::from_hex_unchecked("0x434b3")]))
+            .await
+            .expect("Subscribing to pending transactions");
+        let received = sub.next().await.expect("Subscription closed").expect("Failed to retrieve transaction");
+        assert_eq!(received, PendingTxnInfo::Full(expected_txs[3].clone()));
+    }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_pending_transactions_too_many_addresses(
+        sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet),
+    ) {
+        let (_, starknet) = sample_chain_for_block_getters;
+        let (_server_handle, client) = start_server(starknet).await;
+
+        let addresses = (0..=SENDER_ADDRESS_LIMIT as u64).map(Felt::from).collect();
+        assert!(client.subscribe_pending_transactions(None, Some(addresses)).await.is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use mp_rpc::{TxnFinalityAndExecutionStatus, TxnStatus};
+use starknet_types_core::felt::Felt;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::errors::{ErrorExtWs, StarknetWsApiError};
+use crate::versions::user::v0_7_1::methods::read::get_transaction_status::transaction_status;
+use crate::versions::user::v0_8_0::NewTxnStatus;
+
+/// Interval at which the status is read again, as there is no notification when a transaction is added to the pending
+/// block.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Time after which the subscription ends if the transaction is still unknown to the node.
+#[cfg(not(test))]
+const UNKNOWN_TX_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+#[cfg(test)]
+const UNKNOWN_TX_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Notifies every new status of a transaction, as it goes from `RECEIVED` to `ACCEPTED_ON_L2` and `ACCEPTED_ON_L1`.
+///
+/// The status is read again when the transaction is accepted into the mempool, when a block is imported or confirmed
+/// on L1, and periodically for the pending block. The current status is sent first, once the transaction is known to
+/// the node. The subscription ends once the transaction is accepted on L1, which is final, once it is `REJECTED`
+/// because it expired from the mempool, and with a [`StarknetWsApiError::TxnHashNotFound`] error if the transaction
+/// is still unknown after [`UNKNOWN_TX_TIMEOUT`].
+pub async fn subscribe_transaction_status(
+    starknet: &crate::Starknet,
+    subscription_sink: jsonrpsee::PendingSubscriptionSink,
+    transaction_hash: Felt,
+) -> Result<(), StarknetWsApiError> {
+    // Subscribed before the status is read, so that no transition is missed in between.
+    let mut chain_head = starknet.backend.subscribe_chain_head();
+    let mut accepted_txs = starknet.mempool.as_ref().map(|mempool| mempool.subscribe_accepted_txs());
+    let mut expired_txs = starknet.mempool.as_ref().map(|mempool| mempool.subscribe_expired_txs());
+    let sink = subscription_sink.accept().await.or_internal_server_error("Failed to establish websocket connection")?;
+
+    let mut last_status = None;
+    let mut interval = tokio::time::interval(STATUS_POLL_INTERVAL);
+    let unknown_tx_timeout = tokio::time::sleep(UNKNOWN_TX_TIMEOUT);
+    tokio::pin!(unknown_tx_timeout);
+    loop {
+        let status = transaction_status(starknet, &transaction_hash).map_err(|err| {
+            StarknetWsApiError::internal_server_error(format!("Failed to get transaction status: {err}"))
+        })?;
+        if let Some(status) = status.filter(|status| last_status.as_ref() != Some(status)) {
+            send_status(&sink, transaction_hash, status.clone()).await?;
+            if status.finality_status == TxnStatus::AcceptedOnL1 {
+                return Ok(());
+            }
+            last_status = Some(status);
+        }
+
+        let accepted_tx = async {
+            match accepted_txs.as_mut() {
+                Some(rx) => rx.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        let expired_tx = async {
+            match expired_txs.as_mut() {
+                Some(rx) => rx.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            update = chain_head.recv() => {
+                if let Err(RecvError::Closed) = update {
+                    return Err(StarknetWsApiError::internal_server_error("Chain head channel closed"));
+                }
+            },
+            tx = accepted_tx => match tx {
+                Ok(tx) if tx.hash != transaction_hash => continue,
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => accepted_txs = None,
+            },
+            tx_hash = expired_tx => match tx_hash {
+                Ok(tx_hash) if tx_hash == transaction_hash => {
+                    let status =
+                        TxnFinalityAndExecutionStatus { finality_status: TxnStatus::Rejected, execution_status: None };
+                    return send_status(&sink, transaction_hash, status).await;
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => expired_txs = None,
+            },
+            _ = interval.tick() => {},
+            _ = &mut unknown_tx_timeout, if last_status.is_none() => {
+                return Err(StarknetWsApiError::TxnHashNotFound)
+            },
+            _ = sink.closed() => {
+                return Ok(())
+            }
+        }
+    }
+}
+
+async fn send_status(
+    sink: &jsonrpsee::SubscriptionSink,
+    transaction_hash: Felt,
+    status: TxnFinalityAndExecutionStatus,
+) -> Result<(), StarknetWsApiError> {
+    let msg = jsonrpsee::SubscriptionMessage::from_json(&NewTxnStatus { transaction_hash, status })
+        .or_internal_server_error("Failed to create response message")?;
+    sink.send(msg).await.or_internal_server_error("Failed to respond to websocket request")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use jsonrpsee::ws_client::WsClientBuilder;
+    use mp_receipt::{InvokeTransactionReceipt, TransactionReceipt};
+    use mp_rpc::TxnExecutionStatus;
+    use mp_transactions::{InvokeTransaction, InvokeTransactionV1, Transaction};
+
+    use crate::{
+        test_utils::rpc_test_setup,
+        versions::user::v0_8_0::{StarknetWsRpcApiV0_8_0Client, StarknetWsRpcApiV0_8_0Server},
+        Starknet,
+    };
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_transaction_status(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (backend, starknet) = rpc_test_setup;
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        // Server will be stopped once this is dropped
+        let _server_handle = server.start(StarknetWsRpcApiV0_8_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+
+        let tx_hash = Felt::from(0x1234);
+        let mut sub = client.subscribe_transaction_status(tx_hash).await.expect("starknet_subscribeTransactionStatus");
+
+        backend
+            .store_block(
+                mp_block::MadaraMaybePendingBlock {
+                    info: mp_block::MadaraMaybePendingBlockInfo::NotPending(mp_block::MadaraBlockInfo {
+                        header: mp_block::Header { block_number: 0, ..Default::default() },
+                        block_hash: Felt::ZERO,
+                        tx_hashes: vec![tx_hash],
+                    }),
+                    inner: mp_block::MadaraBlockInner {
+                        transactions: vec![Transaction::Invoke(InvokeTransaction::V1(InvokeTransactionV1::default()))],
+                        receipts: vec![TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                            transaction_hash: tx_hash,
+                            ..Default::default()
+                        })],
+                    },
+                },
+                mp_state_update::StateDiff::default(),
+                vec![],
+                None,
+                None,
+            )
+            .expect("Storing block");
+
+        let status = |finality_status| NewTxnStatus {
+            transaction_hash: tx_hash,
+            status: TxnFinalityAndExecutionStatus {
+                finality_status,
+                execution_status: Some(TxnExecutionStatus::Succeeded),
+            },
+        };
+        let next = sub.next().await.expect("Waiting for status").expect("Waiting for status");
+        assert_eq!(next, status(TxnStatus::AcceptedOnL2));
+
+        backend.write_last_confirmed_block(0).expect("Confirming block");
+        let next = sub.next().await.expect("Waiting for status").expect("Waiting for status");
+        assert_eq!(next, status(TxnStatus::AcceptedOnL1));
+    }
+
+    #[tokio::test]
+    #[rstest::rstest]
+    async fn subscribe_transaction_status_unknown_tx(rpc_test_setup: (std::sync::Arc<mc_db::MadaraBackend>, Starknet)) {
+        let (_backend, starknet) = rpc_test_setup;
+        let server = jsonrpsee::server::Server::builder().build("127.0.0.1:0").await.expect("Starting server");
+        let server_url = format!("ws://{}", server.local_addr().expect("Retrieving server local address"));
+        // Server will be stopped once this is dropped
+        let _server_handle = server.start(StarknetWsRpcApiV0_8_0Server::into_rpc(starknet));
+        let client = WsClientBuilder::default().build(&server_url).await.expect("Building client");
+
+        let mut sub =
+            client.subscribe_transaction_status(Felt::from(0x1234)).await.expect("starknet_subscribeTransactionStatus");
+
+        // The subscription is closed once the timeout for unknown transactions expires.
+        let next = sub.next().await;
+        assert!(next.is_none());
+    }
+}
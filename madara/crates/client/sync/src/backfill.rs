@@ -0,0 +1,89 @@
+//! Backward sync of the blocks older than the state snapshot the node was bootstrapped from.
+//!
+//! A node bootstrapped from a state snapshot, see [`crate::snapshot`], only has the blocks from the snapshot onwards.
+//! When backfilling is enabled, the older blocks are downloaded in the background, from the newest to the oldest, while
+//! the forward sync keeps following the tip of the chain. Each block is checked against the parent hash of the block
+//! above it, which chains the trust in the snapshot block hash down to the genesis block. Backfilled blocks are stored
+//! with their transactions, receipts and events so that RPC can eventually serve the full history, but they do not
+//! touch the state of the chain, which is already known from the snapshot.
+
+use crate::fetch::fetchers::{fetch_block_and_updates, FetchRetryPolicy};
+use anyhow::Context;
+use futures::{stream, StreamExt};
+use mc_block_import::{BlockImporter, BlockValidationContext};
+use mc_db::MadaraBackend;
+use mc_gateway_client::GatewayProvider;
+use mp_utils::service::ServiceContext;
+use starknet_api::core::ChainId;
+use std::pin::pin;
+
+/// Number of backfilled blocks between two progress logs.
+const BACKFILL_LOG_INTERVAL: u64 = 1000;
+
+pub(crate) struct BackfillConfig {
+    pub chain_id: ChainId,
+    pub parallelism: usize,
+    pub strict_validation: bool,
+    pub verify_signatures: bool,
+    pub compute_v0_13_2_hashes: bool,
+    pub retry_policy: FetchRetryPolicy,
+}
+
+/// Backfills the blocks older than the oldest block in the database, down to the genesis block.
+pub(crate) async fn backfill(
+    backend: &MadaraBackend,
+    provider: &GatewayProvider,
+    block_importer: &BlockImporter,
+    config: BackfillConfig,
+    mut ctx: ServiceContext,
+) -> anyhow::Result<()> {
+    let oldest_block_n = match backend.get_oldest_backfilled_block_n().context("Getting oldest backfilled block")? {
+        Some(block_n) if block_n > 0 => block_n,
+        _ => return Ok(()),
+    };
+    tracing::info!("⏪ Backfilling blocks #0 to #{}", oldest_block_n - 1);
+
+    // The block hash is checked against the block above: the block order must not be ignored.
+    let BackfillConfig {
+        chain_id,
+        parallelism,
+        strict_validation,
+        verify_signatures,
+        compute_v0_13_2_hashes,
+        retry_policy,
+    } = config;
+    let validation = BlockValidationContext::new(chain_id.clone())
+        .compute_v0_13_2_hashes(compute_v0_13_2_hashes)
+        .trust_signatures(!verify_signatures);
+    let chain_id = &chain_id;
+    let blocks = stream::iter((0..oldest_block_n).rev())
+        .map(|block_n| {
+            let validation = validation.clone();
+            async move {
+                let block = fetch_block_and_updates(
+                    chain_id,
+                    block_n,
+                    provider,
+                    strict_validation,
+                    verify_signatures,
+                    &retry_policy,
+                    None,
+                )
+                .await
+                .with_context(|| format!("Fetching block #{block_n} to backfill"))?;
+                block_importer.pre_validate(block, validation).await.map_err(anyhow::Error::from)
+            }
+        })
+        .buffered(parallelism);
+    let mut blocks = pin!(blocks);
+
+    while let Some(Some(block)) = ctx.run_until_cancelled(blocks.next()).await {
+        let result = block_importer.verify_apply_backfill(block?, validation.clone()).await?;
+        let block_n = result.header.block_number;
+        if block_n % BACKFILL_LOG_INTERVAL == 0 {
+            tracing::info!("⏪ Backfilled down to block #{block_n}");
+        }
+    }
+
+    Ok(())
+}
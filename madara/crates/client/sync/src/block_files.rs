@@ -0,0 +1,244 @@
+//! Offline sync from block files.
+//!
+//! Blocks can be exported from a node to a directory, and imported into another node from it, without a feeder gateway:
+//! this is useful for air-gapped nodes and for reproducible benchmarks of the block import. Each block is a
+//! `block_<n>.json` file with the JSON serialization of an [`UnverifiedFullBlock`], which includes the classes declared
+//! in the block, like the blocks of the golden tests. The imported blocks go through the [`BlockImporter`] like the
+//! blocks fetched from the gateway, and are verified against the commitments and block hash of the file, the
+//! checkpoints of the chain registry and the sequencer signature, see [`ImportBlocksConfig`].
+
+use crate::l2::check_checkpoint;
+use anyhow::Context;
+use mc_block_import::{
+    BlockImporter, BlockValidationContext, DeclaredClass, LegacyDeclaredClass, SierraDeclaredClass,
+    UnverifiedCommitments, UnverifiedFullBlock, UnverifiedHeader,
+};
+use mc_db::MadaraBackend;
+use mp_block::{BlockId, MadaraBlock};
+use mp_class::ClassInfo;
+use starknet_types_core::felt::Felt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Number of blocks imported or exported between two progress logs, and imported between two flushes.
+const BLOCK_FILES_LOG_INTERVAL: u64 = 1000;
+
+/// How the imported blocks are checked, like the blocks fetched by the sync.
+#[derive(Clone, Debug)]
+pub struct ImportBlocksConfig {
+    /// See [`crate::l2::sync_validation_context`]. Unless the signatures are trusted, every block must be signed by
+    /// one of the sequencer public keys of the chain config.
+    pub validation: BlockValidationContext,
+    /// Block hashes pinned by the chain registry.
+    pub checkpoints: BTreeMap<u64, Felt>,
+}
+
+fn block_file_path(dir: &Path, block_n: u64) -> PathBuf {
+    dir.join(format!("block_{block_n}.json"))
+}
+
+/// Completes a stored block, read with [`MadaraBackend::iter_blocks`], with its state diff and the classes it declares.
+fn read_stored_block(backend: &MadaraBackend, block: MadaraBlock) -> anyhow::Result<UnverifiedFullBlock> {
+    let MadaraBlock { info, inner } = block;
+    let header = info.header;
+    let block_n = header.block_number;
+    let block_id = BlockId::Number(block_n);
+    if inner.transactions.is_empty() && header.transaction_count > 0 {
+        anyhow::bail!("Block #{block_n} was imported from a state snapshot, its transactions are not stored");
+    }
+    let state_diff = backend.get_block_state_diff(&block_id)?.context("Missing state diff")?;
+
+    let class_hashes = state_diff
+        .deprecated_declared_classes
+        .iter()
+        .chain(state_diff.declared_classes.iter().map(|item| &item.class_hash));
+    let declared_classes = class_hashes
+        .map(|class_hash| {
+            let class_info = backend
+                .get_class_info(&block_id, class_hash)?
+                .with_context(|| format!("Missing class {class_hash:#x}"))?;
+            Ok(match class_info {
+                ClassInfo::Sierra(info) => DeclaredClass::Sierra(SierraDeclaredClass {
+                    class_hash: *class_hash,
+                    contract_class: (*info.contract_class).clone(),
+                    compiled_class_hash: info.compiled_class_hash,
+                }),
+                ClassInfo::Legacy(info) => DeclaredClass::Legacy(LegacyDeclaredClass {
+                    class_hash: *class_hash,
+                    contract_class: (*info.contract_class).clone(),
+                }),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(UnverifiedFullBlock {
+        unverified_block_number: Some(block_n),
+        header: UnverifiedHeader {
+            parent_block_hash: Some(header.parent_block_hash),
+            sequencer_address: header.sequencer_address,
+            block_timestamp: header.block_timestamp,
+            protocol_version: header.protocol_version,
+            l1_gas_price: header.l1_gas_price,
+            l1_da_mode: header.l1_da_mode,
+        },
+        state_diff,
+        transactions: inner.transactions,
+        receipts: inner.receipts,
+        declared_classes,
+        commitments: UnverifiedCommitments {
+            transaction_count: Some(header.transaction_count),
+            transaction_commitment: Some(header.transaction_commitment),
+            event_count: Some(header.event_count),
+            event_commitment: Some(header.event_commitment),
+            state_diff_length: header.state_diff_length,
+            state_diff_commitment: header.state_diff_commitment,
+            receipt_commitment: header.receipt_commitment,
+            global_state_root: Some(header.global_state_root),
+            block_hash: Some(info.block_hash),
+        },
+        consensus_signature: backend.get_consensus_signature(&block_id)?,
+        ..Default::default()
+    })
+}
+
+/// Writes the blocks `from_block_n..=to_block_n` to `dir`, up to the latest block when `to_block_n` is `None`. Returns
+/// the number of blocks written.
+pub async fn export_blocks(
+    backend: &MadaraBackend,
+    dir: &Path,
+    from_block_n: u64,
+    to_block_n: Option<u64>,
+) -> anyhow::Result<u64> {
+    let Some(latest_block_n) = backend.get_latest_block_n().context("Getting latest block number")? else {
+        tracing::info!("📤 The database has no blocks to export");
+        return Ok(0);
+    };
+    let to_block_n = to_block_n.map_or(latest_block_n, |to_block_n| to_block_n.min(latest_block_n));
+    tokio::fs::create_dir_all(dir).await.with_context(|| format!("Creating directory {}", dir.display()))?;
+    tracing::info!("📤 Exporting blocks #{from_block_n} to #{to_block_n} to {}", dir.display());
+
+    let mut exported = 0;
+    let range = from_block_n..=to_block_n;
+    for (block_n, block) in range.clone().zip(backend.iter_blocks(range)) {
+        // The blocks iterator skips the missing blocks.
+        let block = block.with_context(|| format!("Getting block #{block_n}"))?;
+        anyhow::ensure!(block.info.header.block_number == block_n, "Block #{block_n} is not stored");
+        let block = read_stored_block(backend, block).with_context(|| format!("Reading block #{block_n}"))?;
+        let bytes = serde_json::to_vec(&block).context("Serializing block")?;
+        let path = block_file_path(dir, block_n);
+        tokio::fs::write(&path, bytes).await.with_context(|| format!("Writing {}", path.display()))?;
+        exported += 1;
+        if block_n % BLOCK_FILES_LOG_INTERVAL == 0 {
+            tracing::info!("📤 Exported blocks up to #{block_n}");
+        }
+    }
+    tracing::info!("✅ Exported {exported} blocks to {}", dir.display());
+    Ok(exported)
+}
+
+/// Imports the blocks of `dir` which follow the latest block in the database. The files must have consecutive block
+/// numbers from the next block onwards. Returns the number of blocks imported.
+pub async fn import_blocks(
+    backend: &MadaraBackend,
+    block_importer: &BlockImporter,
+    dir: &Path,
+    config: &ImportBlocksConfig,
+) -> anyhow::Result<u64> {
+    let ImportBlocksConfig { validation, checkpoints } = config;
+    let mut entries = tokio::fs::read_dir(dir).await.with_context(|| format!("Reading directory {}", dir.display()))?;
+    let mut block_numbers = BTreeSet::<u64>::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name();
+        let block_n =
+            file_name.to_str().and_then(|name| name.strip_prefix("block_")?.strip_suffix(".json")?.parse().ok());
+        block_numbers.extend(block_n);
+    }
+
+    let next_block_n = backend.get_latest_block_n().context("Getting latest block number")?.map_or(0, |n| n + 1);
+    let block_numbers: Vec<u64> = block_numbers.range(next_block_n..).copied().collect();
+    if block_numbers.is_empty() {
+        tracing::info!("📥 No block from #{next_block_n} onwards to import from {}", dir.display());
+        return Ok(0);
+    }
+    if let Some(missing) = (next_block_n..).zip(&block_numbers).find(|(expected, got)| expected != *got) {
+        anyhow::bail!("Missing block file {}", block_file_path(dir, missing.0).display());
+    }
+    tracing::info!("📥 Importing {} blocks from #{next_block_n} from {}", block_numbers.len(), dir.display());
+
+    for &block_n in &block_numbers {
+        let path = block_file_path(dir, block_n);
+        let bytes = tokio::fs::read(&path).await.with_context(|| format!("Reading {}", path.display()))?;
+        let mut block: UnverifiedFullBlock =
+            serde_json::from_slice(&bytes).with_context(|| format!("Deserializing {}", path.display()))?;
+        block.unverified_block_number.get_or_insert(block_n);
+        let import = async {
+            let block = block_importer.pre_validate(block, validation.clone()).await?;
+            check_checkpoint(checkpoints, &block)?;
+            block_importer.verify_apply(block, validation.clone()).await
+        };
+        import.await.with_context(|| format!("Importing block #{block_n}"))?;
+        if block_n % BLOCK_FILES_LOG_INTERVAL == 0 {
+            backend.flush().context("Flushing database")?;
+            tracing::info!("📥 Imported blocks up to #{block_n}");
+        }
+    }
+    backend.flush().context("Flushing database")?;
+    tracing::info!("✅ Imported {} blocks from {}", block_numbers.len(), dir.display());
+    Ok(block_numbers.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_block_import::tests::block_import_utils::create_validation_context;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_export_import_blocks() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let block_importer = BlockImporter::new(Arc::clone(&backend), None).unwrap();
+        let validation = create_validation_context(false);
+        for _ in 0..3 {
+            block_importer.add_block(UnverifiedFullBlock::default(), validation.clone()).await.unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(export_blocks(&backend, dir.path(), 0, Some(1)).await.unwrap(), 2);
+        assert_eq!(export_blocks(&backend, dir.path(), 2, None).await.unwrap(), 1);
+
+        let imported = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let imported_block_importer = BlockImporter::new(Arc::clone(&imported), None).unwrap();
+        let config = ImportBlocksConfig { validation, checkpoints: BTreeMap::new() };
+
+        // The blocks are not signed, but the chain has a sequencer public key.
+        let signed_chain_config = ChainConfig { sequencer_public_keys: vec![Felt::ONE], ..ChainConfig::madara_test() };
+        let signed = MadaraBackend::open_for_testing(Arc::new(signed_chain_config));
+        let signed_block_importer = BlockImporter::new(Arc::clone(&signed), None).unwrap();
+        assert!(import_blocks(&signed, &signed_block_importer, dir.path(), &config).await.is_err());
+        assert_eq!(signed.get_latest_block_n().unwrap(), None);
+        // The hash of block #1 is not the one pinned by the checkpoints.
+        let pinned = ImportBlocksConfig { checkpoints: BTreeMap::from([(1, Felt::ONE)]), ..config.clone() };
+        assert!(import_blocks(&imported, &imported_block_importer, dir.path(), &pinned).await.is_err());
+        assert_eq!(imported.get_latest_block_n().unwrap(), Some(0));
+
+        let import = || import_blocks(&imported, &imported_block_importer, dir.path(), &config);
+        assert_eq!(import().await.unwrap(), 2);
+        for block_n in 0..3 {
+            assert_eq!(
+                imported.get_block_hash(&BlockId::Number(block_n)).unwrap(),
+                backend.get_block_hash(&BlockId::Number(block_n)).unwrap()
+            );
+        }
+        // The blocks already imported are skipped.
+        assert_eq!(import().await.unwrap(), 0);
+
+        for _ in 0..2 {
+            block_importer.add_block(UnverifiedFullBlock::default(), config.validation.clone()).await.unwrap();
+        }
+        export_blocks(&backend, dir.path(), 4, None).await.unwrap();
+        // The file of block #3 is missing.
+        assert!(import().await.is_err());
+        assert_eq!(imported.get_latest_block_n().unwrap(), Some(2));
+    }
+}
@@ -0,0 +1,134 @@
+//! Classification of the errors which stop the sync pipeline.
+//!
+//! The tasks of the pipeline return [`anyhow::Error`]s, which are sorted at the pipeline boundary into the classes
+//! below so that the sync can react to each of them differently: a feeder gateway fault is retried, a block which fails
+//! verification leads to a reorg or to its quarantine when they are enabled, and anything else stops the node.
+
+use crate::fetch::validation::{BlockSignatureError, InconsistentBlockError};
+use crate::fetch::FetchError;
+use crate::l2::L2SyncError;
+use mc_block_import::BlockImportError;
+use mp_gateway::error::SequencerError;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SyncError {
+    /// The feeder gateway could not be reached or returned an error, even after retrying. The pipeline can be
+    /// restarted: the requests fail over to the fallback gateways when there are any.
+    #[error("Feeder gateway error: {0:#}")]
+    Source(anyhow::Error),
+    /// A block served by the feeder gateway is not valid. It cannot be imported as it is.
+    #[error("Block verification failed: {0:#}")]
+    Verification(anyhow::Error),
+    /// Unrecoverable error, such as a database error.
+    #[error("{0:#}")]
+    Internal(anyhow::Error),
+}
+
+impl SyncError {
+    pub fn classify(err: anyhow::Error) -> Self {
+        // The feeder gateway served blocks which do not follow each other: the batch is dropped and fetched again.
+        if err.chain().any(|err| matches!(err.downcast_ref::<L2SyncError>(), Some(L2SyncError::Discontinuity { .. }))) {
+            return Self::Source(err);
+        }
+
+        if let Some(import_err) = err.chain().find_map(|err| err.downcast_ref::<BlockImportError>()) {
+            return if import_err.is_internal() { Self::Internal(err) } else { Self::Verification(err) };
+        }
+
+        // `FetchError` is transparent: its variants do not show up in the chain.
+        let invalid_block = err.chain().any(|err| {
+            err.is::<InconsistentBlockError>()
+                || err.is::<BlockSignatureError>()
+                || matches!(
+                    err.downcast_ref::<FetchError>(),
+                    Some(FetchError::InconsistentBlock(_) | FetchError::InvalidSignature(_))
+                )
+        });
+        if invalid_block {
+            return Self::Verification(err);
+        }
+
+        let source_fault = err.chain().any(|err| {
+            err.is::<SequencerError>()
+                || matches!(err.downcast_ref::<FetchError>(), Some(FetchError::Sequencer(_)))
+                || matches!(err.downcast_ref::<L2SyncError>(), Some(L2SyncError::SequencerError(_)))
+        });
+        if source_fault {
+            Self::Source(err)
+        } else {
+            Self::Internal(err)
+        }
+    }
+
+    /// Whether the pipeline can be restarted from the latest block in the database.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Source(_))
+    }
+
+    /// The number of the block which failed verification, if it was rejected by the block importer.
+    pub fn block_n(&self) -> Option<u64> {
+        let Self::Verification(err) = self else { return None };
+        err.chain().find_map(|err| match err.downcast_ref::<L2SyncError>() {
+            Some(L2SyncError::InvalidBlock { block_n, .. }) => Some(*block_n),
+            _ => None,
+        })
+    }
+
+    /// The block import error behind a verification failure, if the block was rejected by the block importer.
+    pub fn import_error(&self) -> Option<&BlockImportError> {
+        match self {
+            Self::Verification(err) => err.chain().find_map(|err| err.downcast_ref::<BlockImportError>()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_db::MadaraStorageError;
+    use mp_gateway::error::StarknetError;
+    use starknet_types_core::felt::Felt;
+
+    #[test]
+    fn test_classify() {
+        let err =
+            anyhow::Error::from(FetchError::Sequencer(SequencerError::StarknetError(StarknetError::block_not_found())))
+                .context("Fetching block #5");
+        assert!(matches!(SyncError::classify(err), SyncError::Source(_)));
+        let err = anyhow::Error::from(L2SyncError::SequencerError(SequencerError::StarknetError(
+            StarknetError::block_not_found(),
+        )));
+        assert!(SyncError::classify(err).is_retryable());
+
+        let err = anyhow::Error::from(BlockImportError::GlobalStateRoot { got: Felt::ONE, expected: Felt::TWO })
+            .context("Importing block #5");
+        let err = SyncError::classify(err);
+        assert!(matches!(err.import_error(), Some(BlockImportError::GlobalStateRoot { .. })));
+        assert_eq!(err.block_n(), None);
+        assert!(!err.is_retryable());
+        let err = anyhow::Error::from(L2SyncError::InvalidBlock {
+            block_n: 7,
+            error: BlockImportError::GlobalStateRoot { got: Felt::ONE, expected: Felt::TWO },
+        });
+        let err = SyncError::classify(err);
+        assert!(matches!(err.import_error(), Some(BlockImportError::GlobalStateRoot { .. })));
+        assert_eq!(err.block_n(), Some(7));
+        let err =
+            anyhow::Error::from(FetchError::InvalidSignature(BlockSignatureError::Malformed { block_n: 5, len: 1 }));
+        let err = SyncError::classify(err);
+        assert!(matches!(err, SyncError::Verification(_)));
+        assert!(err.import_error().is_none());
+
+        let err = anyhow::Error::from(L2SyncError::Discontinuity {
+            block_n: 5,
+            error: BlockImportError::ParentHash { got: Felt::ONE, expected: Felt::TWO },
+        });
+        assert!(SyncError::classify(err).is_retryable());
+
+        let err = anyhow::Error::from(BlockImportError::Internal("Task was dropped".into()));
+        assert!(matches!(SyncError::classify(err), SyncError::Internal(_)));
+        let err = anyhow::Error::from(L2SyncError::Db(MadaraStorageError::InconsistentStorage("Missing block".into())));
+        assert!(matches!(SyncError::classify(err), SyncError::Internal(_)));
+    }
+}
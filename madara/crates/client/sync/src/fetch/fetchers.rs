@@ -1,9 +1,13 @@
 //! Contains the code required to fetch data from the network efficiently.
+use super::validation::{parse_block_signature, validate_block_strict, validate_pending_block_strict};
 use super::FetchError;
 use crate::l2::L2SyncError;
+use crate::quarantine::QuarantineConfig;
+use crate::reorg::RecoveryClasses;
+use crate::snapshot::SnapshotConfig;
+use crate::stall::StallDetectionConfig;
 use anyhow::Context;
 use core::time::Duration;
-use futures::FutureExt;
 use mc_block_import::{UnverifiedCommitments, UnverifiedFullBlock, UnverifiedPendingFullBlock};
 use mc_gateway_client::GatewayProvider;
 use mp_block::{BlockId, BlockTag};
@@ -13,16 +17,16 @@ use mp_gateway::block::{ProviderBlock, ProviderBlockPending};
 use mp_gateway::error::{SequencerError, StarknetError, StarknetErrorCode};
 use mp_gateway::state_update::ProviderStateUpdateWithBlockPendingMaybe::{self};
 use mp_gateway::state_update::{ProviderStateUpdate, ProviderStateUpdatePending, StateDiff};
+use mp_utils::http::HttpClientConfig;
 use mp_utils::service::MadaraServiceId;
 use mp_utils::{stopwatch_end, PerfStopwatch};
 use starknet_api::core::ChainId;
 use starknet_types_core::felt::Felt;
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 use url::Url;
 
-const MAX_RETRY: u32 = 15;
-const BASE_DELAY: Duration = Duration::from_secs(1);
-
 /// The configuration of the worker responsible for fetching new blocks and state updates from the
 /// feeder.
 #[derive(Clone, Debug)]
@@ -31,14 +35,29 @@ pub struct FetchConfig {
     pub gateway: Url,
     /// The URL of the feeder gateway.
     pub feeder_gateway: Url,
+    /// The URLs of the gateways and feeder gateways the requests fail over to, in order, when the gateway is
+    /// unavailable or rate limits the node.
+    pub fallback_gateways: Vec<(Url, Url)>,
+    /// Maximum number of requests sent to the gateways per second, unlimited when `None`.
+    pub gateway_max_rps: Option<NonZeroU32>,
     /// The ID of the chain served by the sequencer gateway.
     pub chain_id: ChainId,
     /// Whether to check the root of the state update.
     pub verify: bool,
+    /// Whether any inconsistency in the blocks returned by the feeder gateway should be fatal.
+    pub strict_validation: bool,
+    /// Whether the feeder gateway responses with unknown fields are rejected, instead of having these fields dropped.
+    pub strict_gateway_schema: bool,
+    /// Whether the blocks older than Starknet v0.13.2 are hashed with the v0.13.2 block hash algorithm.
+    pub compute_v0_13_2_hashes: bool,
+    /// Whether the block signatures are fetched, and verified against the sequencer public keys of the chain config.
+    pub verify_signatures: bool,
     /// The optional API_KEY to avoid rate limiting from the sequencer gateway.
     pub api_key: Option<String>,
     /// Polling interval.
     pub sync_polling_interval: Option<Duration>,
+    /// Whether the feeder gateway pushes the new blocks as soon as they are sealed, instead of being polled.
+    pub sync_push: bool,
     /// Number of blocks to sync (for testing purposes).
     pub n_blocks_to_sync: Option<u64>,
     /// Number of blocks between db flushes
@@ -49,8 +68,27 @@ pub struct FetchConfig {
     pub stop_on_sync: bool,
     /// Number of blocks to fetch in parallel during the sync process
     pub sync_parallelism: u8,
+    /// Retries of the feeder gateway requests which fail
+    pub retry_policy: FetchRetryPolicy,
+    /// Timeouts, keep-alive and proxy of the connections to the gateways
+    pub http: HttpClientConfig,
     /// Warp update configuration
     pub warp_update: Option<WarpUpdateConfig>,
+    /// Detection of a wedged sync, disabled when `None`
+    pub stall_detection: Option<StallDetectionConfig>,
+    /// Quarantine of the blocks which fail verification, disabled when `None`
+    pub quarantine: Option<QuarantineConfig>,
+    /// Maximum number of blocks reverted to recover from a reorg, disabled when 0. By default, this is
+    /// [`crate::reorg::DEFAULT_MAX_REORG_DEPTH`] or as far as the trie logs go back.
+    pub max_reorg_depth: Option<u64>,
+    /// Block hashes pinned by the chain registry, by block number
+    pub checkpoints: Arc<BTreeMap<u64, Felt>>,
+    /// State snapshot to bootstrap an empty database from, disabled when `None`
+    pub snapshot: Option<SnapshotConfig>,
+    /// Whether to download the blocks older than the state snapshot in the background
+    pub backfill: bool,
+    /// Whether to apply the blocks imported without updating the global tries to them in the background
+    pub trie_catch_up: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -69,10 +107,107 @@ pub struct WarpUpdateConfig {
     pub deferred_service_stop: Vec<MadaraServiceId>,
 }
 
+/// Retries of the feeder gateway requests which fail, by class of error.
+///
+/// The delay before a retry doubles with each retry of the request, up to `max_delay`, and is shortened by a random
+/// fraction of up to `jitter` so that the requests made in parallel do not all hit the gateway again at once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FetchRetryPolicy {
+    /// Maximum number of retries of a request failing with a server or network error.
+    pub max_retries: u32,
+    /// Maximum number of retries of a rate limited request. These are always retried after `max_delay`.
+    pub max_rate_limited_retries: u32,
+    /// Maximum number of retries of a request whose response could not be parsed.
+    pub max_parse_error_retries: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Maximum delay between two retries.
+    pub max_delay: Duration,
+    /// Fraction of the delay which is randomized, between 0 and 1.
+    pub jitter: f64,
+}
+
+impl Default for FetchRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 15,
+            max_rate_limited_retries: 15,
+            max_parse_error_retries: 2,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(6),
+            jitter: 0.2,
+        }
+    }
+}
+
+/// How a failed request is retried, see [`FetchRetryPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RetryClass {
+    RateLimited,
+    /// Server errors and network errors.
+    Transient,
+    ParseError,
+}
+
+impl RetryClass {
+    /// `None` when the request should not be retried: the block does not exist yet, or the request could not be
+    /// built.
+    fn of(err: &SequencerError) -> Option<Self> {
+        match err {
+            SequencerError::StarknetError(StarknetError { code: StarknetErrorCode::BlockNotFound, .. }) => None,
+            SequencerError::StarknetError(StarknetError { code: StarknetErrorCode::RateLimited, .. }) => {
+                Some(Self::RateLimited)
+            }
+            SequencerError::StarknetError(_)
+            | SequencerError::ReqwestError(_)
+            | SequencerError::HttpError(_)
+            | SequencerError::HttpCallError(_) => Some(Self::Transient),
+            SequencerError::InvalidStarknetError { http_status, .. } if http_status.is_server_error() => {
+                Some(Self::Transient)
+            }
+            SequencerError::InvalidStarknetError { .. } | SequencerError::DeserializeBody { .. } => {
+                Some(Self::ParseError)
+            }
+            SequencerError::InvalidUrl(_) | SequencerError::SerializeRequest(_) | SequencerError::CompressError(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl FetchRetryPolicy {
+    fn max_retries(&self, class: RetryClass) -> u32 {
+        match class {
+            RetryClass::RateLimited => self.max_rate_limited_retries,
+            RetryClass::Transient => self.max_retries,
+            RetryClass::ParseError => self.max_parse_error_retries,
+        }
+    }
+
+    /// Delay before the retry following the `retry`-th retry of a request, without jitter.
+    fn backoff(&self, class: RetryClass, retry: u32) -> Duration {
+        match class {
+            RetryClass::RateLimited => self.max_delay,
+            RetryClass::Transient | RetryClass::ParseError => {
+                self.base_delay.saturating_mul(1_u32.checked_shl(retry).unwrap_or(u32::MAX)).min(self.max_delay)
+            }
+        }
+    }
+
+    fn delay(&self, class: RetryClass, retry: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+        self.backoff(class, retry).mul_f64(1.0 - jitter)
+    }
+}
+
+/// Fetches the pending block on top of `parent_block_hash` with its state update and class updates. In strict mode,
+/// the pending block is rejected if it is not fully consistent, see [`validate_pending_block_strict`].
 pub async fn fetch_pending_block_and_updates(
     parent_block_hash: Felt,
     chain_id: &ChainId,
     provider: &GatewayProvider,
+    strict: bool,
+    retry_policy: &FetchRetryPolicy,
 ) -> Result<Option<UnverifiedPendingFullBlock>, FetchError> {
     let block_id = BlockId::Tag(BlockTag::Pending);
     let sw = PerfStopwatch::new();
@@ -89,8 +224,7 @@ pub async fn fetch_pending_block_and_updates(
                 Err(err) => Err(err),
             }
         },
-        MAX_RETRY,
-        BASE_DELAY,
+        retry_policy,
     )
     .await?;
 
@@ -109,7 +243,11 @@ pub async fn fetch_pending_block_and_updates(
         );
         return Ok(None);
     }
-    let class_update = fetch_class_updates(chain_id, &state_update.state_diff, block_id.clone(), provider).await?;
+    if strict {
+        validate_pending_block_strict(&block)?;
+    }
+    let class_update =
+        fetch_class_updates(chain_id, &state_update.state_diff, block_id.clone(), provider, retry_policy, None).await?;
 
     stopwatch_end!(sw, "fetching {:?}: {:?}", block_id);
 
@@ -119,76 +257,132 @@ pub async fn fetch_pending_block_and_updates(
     Ok(Some(converted))
 }
 
+/// Fetches a block with its state update and class updates. In strict mode, the block is rejected if it is not
+/// fully consistent, see [`validate_block_strict`]. After a reorg, the classes of the reverted blocks are reused from
+/// `recovery`.
 pub async fn fetch_block_and_updates(
     chain_id: &ChainId,
     block_n: u64,
     provider: &GatewayProvider,
+    strict: bool,
+    verify_signatures: bool,
+    retry_policy: &FetchRetryPolicy,
+    recovery: Option<&RecoveryClasses>,
+) -> Result<UnverifiedFullBlock, FetchError> {
+    fetch_block_and_updates_inner(chain_id, block_n, provider, strict, verify_signatures, retry_policy, recovery, false)
+        .await
+}
+
+/// Same as [`fetch_block_and_updates`] for the next block of the chain, which may not be sealed yet: the feeder gateway
+/// is asked to answer as soon as it is, see [`GatewayProvider::wait_for_state_update_with_block`].
+pub async fn wait_for_block_and_updates(
+    chain_id: &ChainId,
+    block_n: u64,
+    provider: &GatewayProvider,
+    strict: bool,
+    verify_signatures: bool,
+    retry_policy: &FetchRetryPolicy,
+    recovery: Option<&RecoveryClasses>,
+) -> Result<UnverifiedFullBlock, FetchError> {
+    fetch_block_and_updates_inner(chain_id, block_n, provider, strict, verify_signatures, retry_policy, recovery, true)
+        .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn fetch_block_and_updates_inner(
+    chain_id: &ChainId,
+    block_n: u64,
+    provider: &GatewayProvider,
+    strict: bool,
+    verify_signatures: bool,
+    retry_policy: &FetchRetryPolicy,
+    recovery: Option<&RecoveryClasses>,
+    wait_for_block: bool,
 ) -> Result<UnverifiedFullBlock, FetchError> {
     let block_id = BlockId::Number(block_n);
 
     let sw = PerfStopwatch::new();
     let (state_update, block) = retry(
         || async {
-            provider
-                .get_state_update_with_block(block_id.clone())
-                .await
-                .map(ProviderStateUpdateWithBlockPendingMaybe::as_update_and_block)
+            let update = if wait_for_block {
+                provider
+                    .wait_for_state_update_with_block(block_n)
+                    .await
+                    .map(ProviderStateUpdateWithBlockPendingMaybe::NonPending)
+            } else {
+                provider.get_state_update_with_block(block_id.clone()).await
+            };
+            update.map(ProviderStateUpdateWithBlockPendingMaybe::as_update_and_block)
         },
-        MAX_RETRY,
-        BASE_DELAY,
+        retry_policy,
     )
     .await?;
-    let class_update = fetch_class_updates(chain_id, state_update.state_diff(), block_id, provider).await?;
+    let class_update =
+        fetch_class_updates(chain_id, state_update.state_diff(), block_id.clone(), provider, retry_policy, recovery)
+            .await?;
+    let signature = if verify_signatures {
+        Some(retry(|| provider.get_signature(block_id.clone()), retry_policy).await?)
+    } else {
+        None
+    };
 
     stopwatch_end!(sw, "fetching {:?}: {:?}", block_n);
 
-    let converted = convert_sequencer_block_non_pending(
-        block.non_pending_owned().expect("Block called on block number should not be pending"),
-        state_update.non_pending_ownded().expect("State update called on block number should not be pending"),
-        class_update,
-    )
-    .context("Parsing the FGW full block format")?;
+    let block = block.non_pending_owned().expect("Block called on block number should not be pending");
+    let state_update =
+        state_update.non_pending_ownded().expect("State update called on block number should not be pending");
+    if strict {
+        validate_block_strict(block_n, &block, &state_update)?;
+    }
+
+    let consensus_signature =
+        signature.map(|signature| parse_block_signature(block_n, block.block_hash, &signature)).transpose()?;
+
+    let mut converted = convert_sequencer_block_non_pending(block, state_update, class_update)
+        .context("Parsing the FGW full block format")?;
+    converted.consensus_signature = consensus_signature;
     Ok(converted)
 }
 
 // TODO: should we be checking for cancellation here? This might take a while
-async fn retry<F, Fut, T>(mut f: F, max_retries: u32, base_delay: Duration) -> Result<T, SequencerError>
+pub(crate) async fn retry<F, Fut, T>(mut f: F, policy: &FetchRetryPolicy) -> Result<T, SequencerError>
 where
     F: FnMut() -> Fut,
     Fut: std::future::Future<Output = Result<T, SequencerError>>,
 {
-    let mut attempt = 0;
+    // Retries of the request so far, by class of error.
+    let mut retries = [0_u32; 3];
     loop {
-        match f().await {
+        let err = match f().await {
             Ok(res) => return Ok(res),
-            Err(SequencerError::StarknetError(StarknetError { code: StarknetErrorCode::BlockNotFound, .. })) => {
-                break Err(SequencerError::StarknetError(StarknetError::block_not_found()));
-            }
-            Err(err) => {
-                let delay = base_delay * 2_u32.pow(attempt).min(6); // Cap to prevent overly long delays
-                attempt += 1;
-                if attempt > max_retries {
-                    break Err(err);
-                }
-
-                if let SequencerError::StarknetError(StarknetError { code: StarknetErrorCode::RateLimited, .. }) = err {
-                    tracing::info!("The fetching process has been rate limited, retrying in {:?}", delay)
-                } else {
-                    tracing::warn!("The provider has returned an error: {}, retrying in {:?}", err, delay)
-                }
+            Err(err) => err,
+        };
+        let Some(class) = RetryClass::of(&err) else { return Err(err) };
+        let class_retries = &mut retries[class as usize];
+        if *class_retries >= policy.max_retries(class) {
+            return Err(err);
+        }
+        let delay = policy.delay(class, *class_retries);
+        *class_retries += 1;
 
-                tokio::time::sleep(delay).await;
-            }
+        if class == RetryClass::RateLimited {
+            tracing::info!("The fetching process has been rate limited, retrying in {:?}", delay)
+        } else {
+            tracing::warn!("The provider has returned an error: {}, retrying in {:?}", err, delay)
         }
+
+        tokio::time::sleep(delay).await;
     }
 }
 
 /// retrieves class updates from Starknet sequencer
-async fn fetch_class_updates(
+pub(crate) async fn fetch_class_updates(
     chain_id: &ChainId,
     state_diff: &StateDiff,
     block_id: BlockId,
     provider: &GatewayProvider,
+    retry_policy: &FetchRetryPolicy,
+    recovery: Option<&RecoveryClasses>,
 ) -> anyhow::Result<Vec<ClassUpdate>> {
     // for blocks before 2597 on mainnet new classes are not declared in the state update
     // https://github.com/madara-alliance/madara/issues/233
@@ -205,57 +399,84 @@ async fn fetch_class_updates(
         .map(|declared_class| (declared_class.class_hash, &declared_class.compiled_class_hash))
         .collect();
 
-    let legacy_class_futures = legacy_classes.into_iter().map(|class_hash| {
-        let block_id = block_id.clone();
-        async move {
-            let (class_hash, contract_class) =
-                retry(|| fetch_class(class_hash, block_id.clone(), provider), MAX_RETRY, BASE_DELAY).await?;
-
-            let ContractClass::Legacy(contract_class) = contract_class else {
-                return Err(L2SyncError::UnexpectedClassType { class_hash });
-            };
-            let contract_class = Arc::try_unwrap(contract_class)
-                .expect("Contract class should only have one referenced when it is fetched");
-
-            Ok::<_, L2SyncError>(ClassUpdate::Legacy(LegacyClassUpdate { class_hash, contract_class }))
-        }
-        .boxed()
-    });
-
-    let sierra_class_futures = sierra_classes.into_iter().map(|(class_hash, &compiled_class_hash)| {
-        let block_id = block_id.clone();
-        async move {
-            let (class_hash, contract_class) =
-                retry(|| fetch_class(class_hash, block_id.clone(), provider), MAX_RETRY, BASE_DELAY).await?;
+    if legacy_classes.is_empty() && sierra_classes.is_empty() {
+        return Ok(vec![]);
+    }
 
-            let ContractClass::Sierra(contract_class) = contract_class else {
-                return Err(L2SyncError::UnexpectedClassType { class_hash });
-            };
-            let contract_class = Arc::try_unwrap(contract_class)
-                .expect("Contract class should only have one referenced when it is fetchd");
-
-            Ok::<_, L2SyncError>(ClassUpdate::Sierra(SierraClassUpdate {
-                class_hash,
-                contract_class,
-                compiled_class_hash,
-            }))
+    let class_hashes: Vec<_> =
+        legacy_classes.iter().copied().chain(sierra_classes.iter().map(|(class_hash, _)| *class_hash)).collect();
+    // After a reorg, the classes of the reverted blocks are reused when the new chain declares them again.
+    let reused: Vec<_> = match recovery {
+        Some(recovery) => legacy_classes
+            .iter()
+            .map(|class_hash| recovery.take(class_hash, None))
+            .chain(sierra_classes.iter().map(|(class_hash, compiled)| recovery.take(class_hash, Some(compiled))))
+            .collect(),
+        None => vec![None; class_hashes.len()],
+    };
+    let missing: Vec<_> = class_hashes
+        .iter()
+        .zip(&reused)
+        .filter(|(_, class)| class.is_none())
+        .map(|(class_hash, _)| *class_hash)
+        .collect();
+    let mut fetched = if missing.is_empty() {
+        vec![]
+    } else {
+        if let (Some(recovery), BlockId::Number(block_n)) = (recovery, &block_id) {
+            recovery.throttle(*block_n).await;
         }
-        .boxed()
-    });
+        retry(|| fetch_classes(&missing, block_id.clone(), provider), retry_policy).await?
+    }
+    .into_iter();
+    if missing.len() < class_hashes.len() {
+        tracing::debug!(
+            "Reused {} classes of the reverted blocks for {block_id:?}",
+            class_hashes.len() - missing.len()
+        );
+    }
+    let contract_classes = reused.into_iter().map(|class| class.or_else(|| fetched.next()));
+    let mut contract_classes = class_hashes
+        .into_iter()
+        .zip(contract_classes)
+        .map(|(class_hash, class)| class.map(|class| (class_hash, class)).context("Missing class in the response"))
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter();
+
+    let mut class_updates = Vec::with_capacity(legacy_classes.len() + sierra_classes.len());
+    for (class_hash, contract_class) in contract_classes.by_ref().take(legacy_classes.len()) {
+        let ContractClass::Legacy(contract_class) = contract_class else {
+            return Err(L2SyncError::UnexpectedClassType { class_hash }.into());
+        };
+        let contract_class =
+            Arc::try_unwrap(contract_class).expect("Contract class should only have one referenced when it is fetched");
+
+        class_updates.push(ClassUpdate::Legacy(LegacyClassUpdate { class_hash, contract_class }));
+    }
+    for ((class_hash, contract_class), (_, &compiled_class_hash)) in contract_classes.zip(sierra_classes) {
+        let ContractClass::Sierra(contract_class) = contract_class else {
+            return Err(L2SyncError::UnexpectedClassType { class_hash }.into());
+        };
+        let contract_class =
+            Arc::try_unwrap(contract_class).expect("Contract class should only have one referenced when it is fetched");
+
+        class_updates.push(ClassUpdate::Sierra(SierraClassUpdate { class_hash, contract_class, compiled_class_hash }));
+    }
 
-    Ok(futures::future::try_join_all(legacy_class_futures.chain(sierra_class_futures)).await?)
+    Ok(class_updates)
 }
 
-/// Downloads a class definition from the Starknet sequencer. Note that because
-/// of the current type hell we decided to deal with raw JSON data instead of starknet-providers `DeployedContract`.
-async fn fetch_class(
-    class_hash: Felt,
+/// Downloads class definitions from the Starknet sequencer, in a single request when the feeder gateway supports it.
+/// Note that because of the current type hell we decided to deal with raw JSON data instead of starknet-providers
+/// `DeployedContract`.
+async fn fetch_classes(
+    class_hashes: &[Felt],
     block_id: BlockId,
     provider: &GatewayProvider,
-) -> Result<(Felt, ContractClass), SequencerError> {
-    let contract_class = provider.get_class_by_hash(class_hash, block_id).await?;
-    tracing::debug!("Got the contract class {:?}", class_hash);
-    Ok((class_hash, contract_class))
+) -> Result<Vec<ContractClass>, SequencerError> {
+    let contract_classes = provider.get_classes(class_hashes, block_id).await?;
+    tracing::debug!("Got the contract classes {:?}", class_hashes);
+    Ok(contract_classes)
 }
 
 fn convert_sequencer_block_pending(
@@ -319,6 +540,7 @@ mod fetchers_real_fgw_test;
 #[cfg(test)]
 mod test_l2_fetchers {
     use super::*;
+    use crate::fetch::validation::BlockAnomaly;
     use crate::tests::utils::gateway::{test_setup, TestContext};
     use mc_block_import::UnverifiedPendingFullBlock;
     use mc_db::MadaraBackend;
@@ -351,6 +573,8 @@ mod test_l2_fetchers {
             Felt::from_hex_unchecked("0x1db054847816dbc0098c88915430c44da2c1e3f910fbcb454e14282baba0e75"),
             &ctx.backend.chain_config().chain_id,
             &ctx.provider,
+            false,
+            &FetchRetryPolicy::default(),
         )
         .await;
 
@@ -419,6 +643,83 @@ mod test_l2_fetchers {
         );
     }
 
+    /// In strict mode, a consistent pending block is returned as in the default mode.
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_pending_block_and_updates_strict(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+        ctx.mock_block_pending();
+
+        let result = fetch_pending_block_and_updates(
+            Felt::from_hex_unchecked("0x1db054847816dbc0098c88915430c44da2c1e3f910fbcb454e14282baba0e75"),
+            &ctx.backend.chain_config().chain_id,
+            &ctx.provider,
+            true,
+            &FetchRetryPolicy::default(),
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Some(_))), "Expected a pending block, got {result:?}");
+    }
+
+    /// In strict mode, a pending block whose transactions and receipts do not match is rejected.
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_pending_block_and_updates_strict_inconsistent(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+        ctx.mock_block_pending_missing_receipt();
+
+        let result = fetch_pending_block_and_updates(
+            Felt::from_hex_unchecked("0x1db054847816dbc0098c88915430c44da2c1e3f910fbcb454e14282baba0e75"),
+            &ctx.backend.chain_config().chain_id,
+            &ctx.provider,
+            true,
+            &FetchRetryPolicy::default(),
+        )
+        .await;
+
+        let Err(FetchError::InconsistentBlock(err)) = result else {
+            panic!("Expected an inconsistent block error, got {result:?}")
+        };
+        assert_eq!(err.block_n, None);
+        assert_eq!(err.anomalies, vec![BlockAnomaly::ReceiptCountMismatch { transactions: 1, receipts: 0 }]);
+    }
+
+    /// In strict mode, a block missing the commitments of its Starknet version is rejected, with every missing
+    /// commitment reported.
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_block_and_updates_strict(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+        ctx.mock_block(5);
+        ctx.mock_block_missing_commitments(6);
+        ctx.mock_class_hash(m_cairo_test_contracts::TEST_CONTRACT_SIERRA);
+
+        let chain_id = &ctx.backend.chain_config().chain_id;
+        let retry_policy = FetchRetryPolicy::default();
+        let block = fetch_block_and_updates(chain_id, 5, &ctx.provider, true, false, &retry_policy, None).await;
+        assert!(block.is_ok(), "Expected block #5, got {block:?}");
+
+        let result = fetch_block_and_updates(chain_id, 6, &ctx.provider, true, false, &retry_policy, None).await;
+        let Err(FetchError::InconsistentBlock(err)) = result else {
+            panic!("Expected an inconsistent block error, got {result:?}")
+        };
+        let starknet_version = StarknetVersion::new(0, 13, 2, 1);
+        assert_eq!(err.block_n, Some(6));
+        assert_eq!(
+            err.anomalies,
+            vec![
+                BlockAnomaly::MissingCommitment { commitment: "receipt commitment", starknet_version },
+                BlockAnomaly::MissingCommitment { commitment: "state diff commitment", starknet_version },
+                BlockAnomaly::MissingCommitment { commitment: "state diff length", starknet_version },
+            ]
+        );
+
+        // The block is converted as it is by default.
+        let block = fetch_block_and_updates(chain_id, 6, &ctx.provider, false, false, &retry_policy, None).await;
+        assert!(block.is_ok(), "Expected block #6, got {block:?}");
+    }
+
     /// Test error handling when fetching a pending block fails due to a provider error.
     ///
     /// Verifies that:
@@ -436,6 +737,8 @@ mod test_l2_fetchers {
             Felt::from_hex_unchecked("0x1db054847816dbc0098c88915430c44da2c1e3f910fbcb454e14282baba0e75"),
             &ctx.backend.chain_config().chain_id,
             &ctx.provider,
+            false,
+            &FetchRetryPolicy::default(),
         )
         .await;
 
@@ -643,10 +946,16 @@ mod test_l2_fetchers {
             .state_update();
         let state_diff = state_update.state_diff();
 
-        let class_updates =
-            fetch_class_updates(&ctx.backend.chain_config().chain_id, state_diff, BlockId::Number(5), &ctx.provider)
-                .await
-                .expect("Failed to fetch class updates");
+        let class_updates = fetch_class_updates(
+            &ctx.backend.chain_config().chain_id,
+            state_diff,
+            BlockId::Number(5),
+            &ctx.provider,
+            &FetchRetryPolicy::default(),
+            None,
+        )
+        .await
+        .expect("Failed to fetch class updates");
 
         assert!(!class_updates.is_empty(), "Should have fetched at least one class update");
 
@@ -675,9 +984,15 @@ mod test_l2_fetchers {
         let state_diff = state_update.state_diff();
 
         ctx.mock_class_hash_not_found("0x40fe2533528521fc49a8ad8440f8a1780c50337a94d0fce43756015fa816a8a".to_string());
-        let result =
-            fetch_class_updates(&ctx.backend.chain_config().chain_id, state_diff, BlockId::Number(5), &ctx.provider)
-                .await;
+        let result = fetch_class_updates(
+            &ctx.backend.chain_config().chain_id,
+            state_diff,
+            BlockId::Number(5),
+            &ctx.provider,
+            &FetchRetryPolicy::default(),
+            None,
+        )
+        .await;
 
         assert!(matches!(
         result,
@@ -701,13 +1016,49 @@ mod test_l2_fetchers {
         let class_hash = Felt::from_hex_unchecked("0x78401746828463e2c3f92ebb261fc82f7d4d4c8d9a80a356c44580dab124cb0");
         ctx.mock_class_hash(m_cairo_test_contracts::TEST_CONTRACT_SIERRA);
 
-        let (fetched_hash, _contract_class) =
-            fetch_class(class_hash, BlockId::Number(5), &ctx.provider).await.expect("Failed to fetch class");
+        let contract_classes =
+            fetch_classes(&[class_hash], BlockId::Number(5), &ctx.provider).await.expect("Failed to fetch class");
+
+        assert_eq!(contract_classes.len(), 1, "Should have fetched exactly one class");
+        assert!(matches!(contract_classes[0], ContractClass::Sierra(_)), "Fetched class should be a sierra class");
+    }
+
+    /// Test fetching of several class definitions at once.
+    ///
+    /// Verifies that:
+    /// 1. The batched endpoint is used when the feeder gateway supports it.
+    /// 2. Classes are returned in the order they were requested in.
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_classes_batched(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+
+        let class_hashes = [felt!("0x1234"), felt!("0x5678")];
+        let mock = ctx.mock_classes_by_hash(m_cairo_test_contracts::TEST_CONTRACT_SIERRA, class_hashes.len());
+
+        let contract_classes =
+            fetch_classes(&class_hashes, BlockId::Number(5), &ctx.provider).await.expect("Failed to fetch classes");
+
+        mock.assert();
+        assert_eq!(contract_classes.len(), class_hashes.len(), "Should have fetched one class per class hash");
+    }
+
+    /// Test the fallback to one request per class when the batched endpoint is not available.
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_classes_batched_unsupported(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+
+        let class_hashes = [felt!("0x1234"), felt!("0x5678")];
+        ctx.mock_class_hash(m_cairo_test_contracts::TEST_CONTRACT_SIERRA);
+
+        let contract_classes =
+            fetch_classes(&class_hashes, BlockId::Number(5), &ctx.provider).await.expect("Failed to fetch classes");
 
-        assert_eq!(fetched_hash, class_hash, "Fetched class hash should match the requested one");
+        assert_eq!(contract_classes.len(), class_hashes.len(), "Should have fetched one class per class hash");
     }
 
-    /// Test error handling in fetch_class.
+    /// Test error handling in fetch_classes.
     ///
     /// Verifies that:
     /// 1. The function properly handles provider errors.
@@ -720,7 +1071,7 @@ mod test_l2_fetchers {
         let class_hash = felt!("0x1234");
         ctx.mock_class_hash_not_found("0x1234".to_string());
 
-        let result = fetch_class(class_hash, BlockId::Number(5), &ctx.provider).await;
+        let result = fetch_classes(&[class_hash], BlockId::Number(5), &ctx.provider).await;
 
         assert!(
             matches!(
@@ -811,4 +1162,45 @@ mod test_l2_fetchers {
         assert!(block.transaction_receipts.is_empty());
         assert_eq!(block.starknet_version, Some("0.13.2.1".to_string()));
     }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = FetchRetryPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            ..Default::default()
+        };
+
+        let parse_error = || serde_json::from_str::<u64>("not a number").unwrap_err();
+        assert_eq!(
+            RetryClass::of(&SequencerError::StarknetError(StarknetError::rate_limited())),
+            Some(RetryClass::RateLimited)
+        );
+        assert_eq!(RetryClass::of(&SequencerError::StarknetError(StarknetError::block_not_found())), None);
+        assert_eq!(
+            RetryClass::of(&SequencerError::DeserializeBody { serde_error: parse_error() }),
+            Some(RetryClass::ParseError)
+        );
+        assert_eq!(
+            RetryClass::of(&SequencerError::InvalidStarknetError {
+                http_status: hyper::StatusCode::BAD_GATEWAY,
+                serde_error: parse_error()
+            }),
+            Some(RetryClass::Transient)
+        );
+        assert_eq!(policy.max_retries(RetryClass::ParseError), policy.max_parse_error_retries);
+
+        // Exponential backoff, capped to the max delay.
+        let backoff: Vec<_> = (0..5).map(|retry| policy.backoff(RetryClass::Transient, retry).as_secs()).collect();
+        assert_eq!(backoff, [1, 2, 4, 8, 10]);
+        assert_eq!(policy.backoff(RetryClass::Transient, 100), policy.max_delay);
+        assert_eq!(policy.backoff(RetryClass::RateLimited, 0), policy.max_delay);
+
+        // The jitter only shortens the delay.
+        for retry in 0..5 {
+            let delay = policy.delay(RetryClass::Transient, retry);
+            let backoff = policy.backoff(RetryClass::Transient, retry);
+            assert!(delay <= backoff && delay >= backoff.mul_f64(1.0 - policy.jitter));
+        }
+    }
 }
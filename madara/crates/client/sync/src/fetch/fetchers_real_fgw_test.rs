@@ -11,7 +11,15 @@ fn client_mainnet_fixture() -> GatewayProvider {
 #[rstest]
 #[tokio::test]
 async fn test_can_fetch_pending_block(client_mainnet_fixture: GatewayProvider) {
-    let block = fetch_pending_block_and_updates(Felt::ZERO, &ChainId::Mainnet, &client_mainnet_fixture).await.unwrap();
+    let block = fetch_pending_block_and_updates(
+        Felt::ZERO,
+        &ChainId::Mainnet,
+        &client_mainnet_fixture,
+        false,
+        &FetchRetryPolicy::default(),
+    )
+    .await
+    .unwrap();
     // ignore as we can't check much here :/
     drop(block);
 }
@@ -25,7 +33,17 @@ async fn test_can_fetch_and_convert_block(client_mainnet_fixture: GatewayProvide
     // Sorting is necessary since we store storage diffs and nonces in a
     // hashmap in the fgw types before converting them to a Vec in the mp
     // types, resulting in unpredictable ordering
-    let mut block = fetch_block_and_updates(&ChainId::Mainnet, block_n, &client_mainnet_fixture).await.unwrap();
+    let mut block = fetch_block_and_updates(
+        &ChainId::Mainnet,
+        block_n,
+        &client_mainnet_fixture,
+        false,
+        false,
+        &FetchRetryPolicy::default(),
+        None,
+    )
+    .await
+    .unwrap();
     block.state_diff.storage_diffs.sort_by(|a, b| a.address.cmp(&b.address));
     block.state_diff.nonces.sort_by(|a, b| a.contract_address.cmp(&b.contract_address));
 
@@ -40,3 +58,50 @@ async fn test_can_fetch_and_convert_block(client_mainnet_fixture: GatewayProvide
 
     assert_eq!(block, expected)
 }
+
+async fn block_starknet_version(client: &GatewayProvider, block_n: u64) -> mp_chain_config::StarknetVersion {
+    let block = client.get_block(BlockId::Number(block_n)).await.unwrap();
+    let block = block.non_pending().expect("Block should not be pending");
+    // Blocks older than Starknet v0.9.1 do not carry their version.
+    block
+        .starknet_version
+        .as_deref()
+        .map(|version| version.parse().unwrap())
+        .unwrap_or(mp_chain_config::StarknetVersion::V_0_0_0)
+}
+
+/// Writes the fixtures of the golden block hash tests, `tests::golden_blocks`: the first mainnet block of each
+/// Starknet version whose block hash or commitments are computed differently, found by bisection. Run it with
+/// `cargo test -p mc-sync fetch_golden_blocks -- --ignored`: the golden tests check every block in `test-data`.
+#[rstest]
+#[tokio::test]
+#[ignore = "writes the golden block fixtures"]
+async fn fetch_golden_blocks(client_mainnet_fixture: GatewayProvider) {
+    for version in ["0.11.0", "0.12.0", "0.13.0", "0.13.1"] {
+        let version: mp_chain_config::StarknetVersion = version.parse().unwrap();
+        let (mut low, mut high) = (0, 724_130);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if block_starknet_version(&client_mainnet_fixture, mid).await >= version {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        let block = fetch_block_and_updates(
+            &ChainId::Mainnet,
+            low,
+            &client_mainnet_fixture,
+            false,
+            false,
+            &FetchRetryPolicy::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let path = &format!("test-data/block_{low}.json");
+        serde_json::to_writer(std::fs::File::create(path).unwrap(), &block).unwrap();
+        tracing::info!("Starknet v{version}: {path}");
+    }
+}
@@ -1,31 +1,58 @@
-use std::time::Duration;
+use std::pin::pin;
+use std::time::{Duration, Instant};
 use std::{num::NonZeroUsize, sync::Arc};
 
+use anyhow::Context;
 use futures::prelude::*;
 use mc_block_import::UnverifiedFullBlock;
+use mc_db::db_block_id::DbBlockId;
 use mc_db::MadaraBackend;
 use mc_gateway_client::GatewayProvider;
 use mc_rpc::versions::admin::v0_1_0::MadaraStatusRpcApiV0_1_0Client;
+use mp_block::BlockId;
 use mp_gateway::error::{SequencerError, StarknetError, StarknetErrorCode};
 use mp_utils::service::ServiceContext;
 use tokio::sync::{mpsc, oneshot};
+use tracing::Instrument;
 use url::Url;
 
-use crate::fetch::fetchers::fetch_block_and_updates;
+use crate::fetch::fetchers::{fetch_block_and_updates, wait_for_block_and_updates};
+use crate::history::SyncHistory;
+use crate::reorg::RecoveryClasses;
 
-use self::fetchers::WarpUpdateConfig;
+use self::fetchers::{retry, FetchRetryPolicy, WarpUpdateConfig};
+use self::validation::{BlockSignatureError, InconsistentBlockError};
 
 pub mod fetchers;
+pub mod validation;
+
+/// Minimum time between two requests for the next block when the feeder gateway pushes the blocks.
+const PUSH_MIN_WAIT: Duration = Duration::from_secs(1);
+
+/// Root span of a block going through the sync pipeline, with the block number as the `block_n` attribute. It is sent
+/// between the stages with the block, and the work of each stage on the block (fetch, pre-validation, verification and
+/// storage) happens in its child spans. Exported through `--analytics-collection-endpoint`, this gives one trace per
+/// block, showing where the time is spent importing it.
+pub fn block_span(block_n: u64) -> tracing::Span {
+    tracing::info_span!(parent: None, "sync_block", block_n)
+}
 
 pub struct L2FetchConfig {
     pub first_block: u64,
-    pub fetch_stream_sender: mpsc::Sender<UnverifiedFullBlock>,
+    pub fetch_stream_sender: mpsc::Sender<(tracing::Span, UnverifiedFullBlock)>,
     pub once_caught_up_sender: oneshot::Sender<()>,
     pub sync_polling_interval: Option<Duration>,
+    pub sync_push: bool,
     pub n_blocks_to_sync: Option<u64>,
     pub stop_on_sync: bool,
     pub sync_parallelism: usize,
+    pub strict_validation: bool,
+    pub verify_signatures: bool,
+    pub retry_policy: FetchRetryPolicy,
     pub warp_update: Option<WarpUpdateConfig>,
+    pub history: Arc<SyncHistory>,
+    /// Classes of the blocks reverted by the last reorg, see [`RecoveryClasses`].
+    pub recovery: Option<Arc<RecoveryClasses>>,
 }
 
 pub async fn l2_fetch_task(
@@ -63,6 +90,11 @@ pub async fn l2_fetch_task(
                 .expect("Failed to parse warp update sender feeder gateway url. This should not fail in prod"),
         ));
 
+        if let Some(block_n) = first_block.checked_sub(1) {
+            check_warp_update_checkpoint(backend.as_ref(), &provider, block_n, &config.retry_policy).await?;
+            tracing::info!("⏩ Resuming warp update from block #{first_block}");
+        }
+
         let save = config.sync_parallelism;
         let available_parallelism = std::thread::available_parallelism()
             .unwrap_or(NonZeroUsize::new(1usize).expect("1 should always be in usize bound"));
@@ -110,7 +142,20 @@ pub async fn l2_fetch_task(
         return anyhow::Ok(());
     }
 
-    let L2FetchConfig { fetch_stream_sender, once_caught_up_sender, sync_polling_interval, stop_on_sync, .. } = config;
+    let L2FetchConfig {
+        fetch_stream_sender,
+        once_caught_up_sender,
+        sync_polling_interval,
+        sync_push,
+        stop_on_sync,
+        strict_validation,
+        verify_signatures,
+        retry_policy,
+        history,
+        recovery,
+        ..
+    } = config;
+    let recovery = recovery.as_deref();
 
     // We do not call cancellation here as we still want the blocks to be stored
     if stop_on_sync {
@@ -120,7 +165,54 @@ pub async fn l2_fetch_task(
     // TODO: replace this with a tokio::sync::Notify
     let _ = once_caught_up_sender.send(());
 
-    if let Some(sync_polling_interval) = sync_polling_interval {
+    if sync_push {
+        // The feeder gateway holds the request for the next block until it is sealed.
+        let chain_id = &backend.chain_config().chain_id;
+        loop {
+            if ctx.run_until_cancelled(wait_sync_allowed(&backend, next_block)).await.is_none() {
+                break;
+            }
+            let started = Instant::now();
+            let span = block_span(next_block);
+            let fetch = wait_for_block_and_updates(
+                chain_id,
+                next_block,
+                &provider,
+                strict_validation,
+                verify_signatures,
+                &retry_policy,
+                recovery,
+            )
+            .instrument(tracing::info_span!(parent: &span, "fetch"));
+            // The fetch time is not recorded here: it is mostly spent waiting for the block to be sealed.
+            match ctx.run_until_cancelled(fetch).await {
+                None => break,
+                Some(Err(FetchError::Sequencer(SequencerError::StarknetError(StarknetError {
+                    code: StarknetErrorCode::BlockNotFound,
+                    ..
+                })))) => {
+                    // A feeder gateway which does not hold the requests answers right away.
+                    if started.elapsed() < PUSH_MIN_WAIT
+                        && ctx.run_until_cancelled(tokio::time::sleep(PUSH_MIN_WAIT)).await.is_none()
+                    {
+                        break;
+                    }
+                }
+                Some(Err(e)) => {
+                    tracing::debug!("Failed to wait for the next block: {e}");
+                    return Err(e.into());
+                }
+                Some(Ok(unverified_block)) => {
+                    if fetch_stream_sender.send((span, unverified_block)).await.is_err() {
+                        // stream closed
+                        break;
+                    }
+                    backend.set_sync_fetched_block_n(next_block);
+                    next_block += 1;
+                }
+            }
+        }
+    } else if let Some(sync_polling_interval) = sync_polling_interval {
         // Polling
 
         let mut interval = tokio::time::interval(sync_polling_interval);
@@ -130,9 +222,27 @@ pub async fn l2_fetch_task(
             // a single loop iteration, so we keep fetching until we reach the
             // tip again.
             let chain_id = &backend.chain_config().chain_id;
-            let fetch = |next_block: u64| fetch_block_and_updates(chain_id, next_block, &provider);
+            let (backend, provider, history) = (backend.as_ref(), &provider, &history);
+            let fetch = |next_block: u64| async move {
+                wait_sync_allowed(backend, next_block).await;
+                let started = Instant::now();
+                let span = block_span(next_block);
+                let fetched = fetch_block_and_updates(
+                    chain_id,
+                    next_block,
+                    provider,
+                    strict_validation,
+                    verify_signatures,
+                    &retry_policy,
+                    recovery,
+                )
+                .instrument(tracing::info_span!(parent: &span, "fetch"))
+                .await;
+                history.record_fetch(started.elapsed());
+                (span, fetched)
+            };
 
-            while let Some(block) = ctx.run_until_cancelled(fetch(next_block)).await {
+            while let Some((span, block)) = ctx.run_until_cancelled(fetch(next_block)).await {
                 match block {
                     Err(FetchError::Sequencer(SequencerError::StarknetError(StarknetError {
                         code: StarknetErrorCode::BlockNotFound,
@@ -145,10 +255,11 @@ pub async fn l2_fetch_task(
                         return Err(e.into());
                     }
                     Ok(unverified_block) => {
-                        if fetch_stream_sender.send(unverified_block).await.is_err() {
+                        if fetch_stream_sender.send((span, unverified_block)).await.is_err() {
                             // stream closed
                             break;
                         }
+                        backend.set_sync_fetched_block_n(next_block);
                     }
                 }
 
@@ -160,6 +271,54 @@ pub async fn l2_fetch_task(
     anyhow::Ok(())
 }
 
+/// Checks that block `block_n`, the latest block of the receiver, is the block the warp update sender has at this
+/// height, before resuming an interrupted warp update after it. Blocks are imported in order, each one checked against
+/// its hash and its parent hash, so the blocks before it are also those of the sender.
+///
+/// This is the only checkpoint of a warp update:
+/// - the blocks are the only data transferred, the other stages of the pipeline (such as the global tries with
+///   `--disable-root`) catch up from the blocks stored on the receiver, so there is no other head to resume from;
+/// - the blocks are verified against their hash and commitments before being stored, which covers what a checksum of
+///   the transferred batches would.
+///
+/// Transient errors of the sender are retried with `retry_policy`.
+async fn check_warp_update_checkpoint(
+    backend: &MadaraBackend,
+    provider: &GatewayProvider,
+    block_n: u64,
+    retry_policy: &FetchRetryPolicy,
+) -> anyhow::Result<()> {
+    let sender_hash = match retry(|| provider.get_block(BlockId::Number(block_n)), retry_policy).await {
+        Ok(block) => block.non_pending().map(|block| block.block_hash),
+        Err(SequencerError::StarknetError(StarknetError { code: StarknetErrorCode::BlockNotFound, .. })) => None,
+        Err(err) => return Err(err).context("Getting the block of the warp update sender"),
+    };
+    let local_hash = backend
+        .get_block_hash(&DbBlockId::Number(block_n))
+        .context("Getting the latest block hash")?
+        .with_context(|| format!("Latest block #{block_n} not found"))?;
+    match sender_hash {
+        Some(sender_hash) if sender_hash == local_hash => Ok(()),
+        Some(sender_hash) => anyhow::bail!(
+            "Cannot resume the warp update: block #{block_n} has hash {local_hash:#x} on the receiver but \
+             {sender_hash:#x} on the sender"
+        ),
+        None => {
+            anyhow::bail!("Cannot resume the warp update: the receiver has block #{block_n}, which the sender does not")
+        }
+    }
+}
+
+/// Waits until the operator allows the sync to fetch block `block_n`, see [`mc_db::sync_control`].
+async fn wait_sync_allowed(backend: &MadaraBackend, block_n: u64) {
+    if backend.get_sync_control().allows(block_n) {
+        return;
+    }
+    tracing::info!("⏸️  Sync paused before block #{block_n}");
+    backend.wait_sync_allowed(block_n).await;
+    tracing::info!("▶️  Sync resumed at block #{block_n}");
+}
+
 /// Whether a chain has been caught up to the tip or only a certain block number
 ///
 /// This is mostly relevant in the context of the `--n-blocks-to-sync` cli
@@ -188,21 +347,54 @@ async fn sync_blocks(
     ctx: &mut ServiceContext,
     config: &L2FetchConfig,
 ) -> anyhow::Result<SyncStatus> {
-    let L2FetchConfig { first_block, fetch_stream_sender, n_blocks_to_sync, sync_parallelism, .. } = config;
-
-    // Fetch blocks and updates in parallel one time before looping
-    let fetch_stream = (*first_block..).take(n_blocks_to_sync.unwrap_or(u64::MAX) as _).map(|block_n| {
+    let L2FetchConfig {
+        first_block,
+        fetch_stream_sender,
+        n_blocks_to_sync,
+        sync_parallelism,
+        strict_validation,
+        verify_signatures,
+        retry_policy,
+        history,
+        recovery,
+        ..
+    } = config;
+
+    // Fetch blocks and updates in parallel one time before looping. No new fetch is started while the sync is paused,
+    // but the fetches in flight are still imported.
+    let blocks =
+        stream::iter(*first_block..).take(n_blocks_to_sync.unwrap_or(u64::MAX) as _).then(|block_n| async move {
+            wait_sync_allowed(backend, block_n).await;
+            block_n
+        });
+    let fetch_stream = blocks.map(|block_n| {
         let provider = Arc::clone(provider);
         let chain_id = &backend.chain_config().chain_id;
-        async move { (block_n, fetch_block_and_updates(chain_id, block_n, &provider).await) }
+        async move {
+            let started = Instant::now();
+            let span = block_span(block_n);
+            let fetched = fetch_block_and_updates(
+                chain_id,
+                block_n,
+                &provider,
+                *strict_validation,
+                verify_signatures,
+                retry_policy,
+                recovery.as_deref(),
+            )
+            .instrument(tracing::info_span!(parent: &span, "fetch"))
+            .await;
+            history.record_fetch(started.elapsed());
+            (block_n, span, fetched)
+        }
     });
 
     // Have `sync_parallelism` fetches in parallel at once, using futures Buffered
     let mut next_block = *first_block;
-    let mut fetch_stream = stream::iter(fetch_stream).buffered(*sync_parallelism);
+    let mut fetch_stream = pin!(fetch_stream.buffered(*sync_parallelism));
 
     while let Some(next) = ctx.run_until_cancelled(fetch_stream.next()).await {
-        let Some((block_n, val)) = next else {
+        let Some((block_n, span, val)) = next else {
             return anyhow::Ok(SyncStatus::UpTo(next_block));
         };
 
@@ -214,10 +406,11 @@ async fn sync_blocks(
                 return anyhow::Ok(SyncStatus::Full(next_block));
             }
             val => {
-                if fetch_stream_sender.send(val?).await.is_err() {
+                if fetch_stream_sender.send((span, val?)).await.is_err() {
                     // join error
                     return anyhow::Ok(SyncStatus::UpTo(next_block));
                 }
+                backend.set_sync_fetched_block_n(block_n);
             }
         }
 
@@ -232,6 +425,10 @@ pub enum FetchError {
     #[error(transparent)]
     Sequencer(#[from] SequencerError),
     #[error(transparent)]
+    InconsistentBlock(#[from] InconsistentBlockError),
+    #[error(transparent)]
+    InvalidSignature(#[from] BlockSignatureError),
+    #[error(transparent)]
     Internal(#[from] anyhow::Error),
 }
 
@@ -280,10 +477,16 @@ mod test_l2_fetch_task {
                             fetch_stream_sender,
                             once_caught_up_sender,
                             sync_polling_interval: Some(polling_interval),
+                            sync_push: false,
                             n_blocks_to_sync: Some(5),
                             stop_on_sync: false,
                             sync_parallelism: 10,
+                            strict_validation: false,
+                            verify_signatures: false,
+                            retry_policy: FetchRetryPolicy::default(),
                             warp_update: None,
+                            history: Default::default(),
+                            recovery: None,
                         },
                     ),
                 )
@@ -293,7 +496,7 @@ mod test_l2_fetch_task {
 
         for expected_block_number in 0..5 {
             match tokio::time::timeout(Duration::from_secs(1), ctx.fetch_stream_receiver.recv()).await {
-                Ok(Some(block)) => {
+                Ok(Some((_, block))) => {
                     assert_eq!(block.unverified_block_number, Some(expected_block_number));
                 }
                 Ok(None) => panic!("Channel closed unexpectedly"),
@@ -311,7 +514,68 @@ mod test_l2_fetch_task {
 
         for expected_block_number in 5..8 {
             match tokio::time::timeout(Duration::from_secs(1), ctx.fetch_stream_receiver.recv()).await {
-                Ok(Some(block)) => {
+                Ok(Some((_, block))) => {
+                    assert_eq!(block.unverified_block_number, Some(expected_block_number));
+                }
+                Ok(None) => panic!("Channel closed unexpectedly"),
+                Err(_) => panic!("Timeout waiting for block {}", expected_block_number),
+            }
+        }
+
+        assert!(!task.is_finished());
+
+        task.abort();
+    }
+
+    /// Once caught up, the blocks are fetched as soon as the feeder gateway pushes them, and the task keeps waiting
+    /// when there is no new block.
+    #[rstest]
+    #[tokio::test]
+    async fn test_l2_fetch_task_push(test_setup: Arc<MadaraBackend>) {
+        let mut ctx = TestContext::new(test_setup);
+
+        for block_number in 0..8 {
+            ctx.mock_block(block_number);
+        }
+        ctx.mock_block_not_found(8);
+
+        ctx.mock_class_hash(m_cairo_test_contracts::TEST_CONTRACT_SIERRA);
+        ctx.mock_signature();
+
+        let task = tokio::spawn({
+            let backend = Arc::clone(&ctx.backend);
+            let provider = Arc::clone(&ctx.provider);
+            let fetch_stream_sender = ctx.fetch_stream_sender.clone();
+            let once_caught_up_sender = ctx.once_caught_up_sender;
+            async move {
+                l2_fetch_task(
+                    backend,
+                    provider,
+                    ServiceContext::new_for_testing(),
+                    L2FetchConfig {
+                        first_block: 0,
+                        fetch_stream_sender,
+                        once_caught_up_sender,
+                        sync_polling_interval: None,
+                        sync_push: true,
+                        n_blocks_to_sync: Some(5),
+                        stop_on_sync: false,
+                        sync_parallelism: 10,
+                        strict_validation: false,
+                        verify_signatures: false,
+                        retry_policy: FetchRetryPolicy::default(),
+                        warp_update: None,
+                        history: Default::default(),
+                        recovery: None,
+                    },
+                )
+                .await
+            }
+        });
+
+        for expected_block_number in 0..8 {
+            match tokio::time::timeout(Duration::from_secs(1), ctx.fetch_stream_receiver.recv()).await {
+                Ok(Some((_, block))) => {
                     assert_eq!(block.unverified_block_number, Some(expected_block_number));
                 }
                 Ok(None) => panic!("Channel closed unexpectedly"),
@@ -319,8 +583,41 @@ mod test_l2_fetch_task {
             }
         }
 
+        tokio::time::sleep(Duration::from_millis(300)).await;
         assert!(!task.is_finished());
 
         task.abort();
     }
+
+    /// Transient errors of the warp update sender are retried before resuming, a missing block is not.
+    #[rstest]
+    #[tokio::test]
+    async fn test_warp_update_checkpoint_retries(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+        let retry_policy = FetchRetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let internal_error = ctx.mock_server.mock(|when, then| {
+            when.method("GET").path_contains("get_block").query_param("blockNumber", "3");
+            then.status(500).body("Internal Server Error");
+        });
+        let err = check_warp_update_checkpoint(&ctx.backend, &ctx.provider, 3, &retry_policy).await.unwrap_err();
+        assert!(format!("{err:#}").contains("Getting the block of the warp update sender"), "{err:#}");
+        internal_error.assert_hits(3);
+
+        let not_found = ctx.mock_server.mock(|when, then| {
+            when.method("GET").path_contains("get_block").query_param("blockNumber", "4");
+            then.status(400).header("content-type", "application/json").json_body(serde_json::json!({
+                "code": "StarknetErrorCode.BLOCK_NOT_FOUND",
+                "message": "Block not found"
+            }));
+        });
+        let err = check_warp_update_checkpoint(&ctx.backend, &ctx.provider, 4, &retry_policy).await.unwrap_err();
+        assert!(format!("{err:#}").contains("which the sender does not"), "{err:#}");
+        not_found.assert_hits(1);
+    }
 }
@@ -0,0 +1,428 @@
+//! Strict consistency checks for the blocks returned by the feeder gateway.
+//!
+//! The feeder gateway is not trusted: by default, the block import pipeline verifies the block hash and the state
+//! root, but a lot of inconsistencies in the gateway data are silently tolerated during conversion (a missing receipt
+//! is dropped, receipts are matched to transactions by position...). In strict mode, any such inconsistency is fatal
+//! and reported in full, so that it can be investigated.
+
+use mp_block::ConsensusSignature;
+use mp_chain_config::StarknetVersion;
+use mp_gateway::block::{ProviderBlock, ProviderBlockPending, ProviderBlockSignature};
+use mp_gateway::receipt::{ConfirmedReceipt, ExecutionStatus};
+use mp_gateway::state_update::ProviderStateUpdate;
+use mp_gateway::transaction::Transaction;
+use starknet_types_core::felt::Felt;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A single inconsistency found in a block returned by the feeder gateway.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockAnomaly {
+    #[error("Requested block #{expected} but the gateway returned block #{got}")]
+    BlockNumberMismatch { expected: u64, got: u64 },
+    #[error("The state update is for block hash {state_update:#x} but the block hash is {block:#x}")]
+    StateUpdateBlockHashMismatch { block: Felt, state_update: Felt },
+    #[error("Invalid block header: {0}")]
+    InvalidHeader(String),
+    #[error("Missing {commitment} in a block with Starknet version {starknet_version}")]
+    MissingCommitment { commitment: &'static str, starknet_version: StarknetVersion },
+    #[error("The block has {transactions} transactions but {receipts} receipts")]
+    ReceiptCountMismatch { transactions: usize, receipts: usize },
+    #[error("Receipt at position {position} has transaction index {transaction_index}")]
+    ReceiptIndexMismatch { position: usize, transaction_index: u64 },
+    #[error(
+        "Receipt at position {position} is for transaction {receipt_transaction_hash:#x} but the transaction at this \
+         position is {transaction_hash:#x}"
+    )]
+    ReceiptHashMismatch { position: usize, transaction_hash: Felt, receipt_transaction_hash: Felt },
+    #[error("Transaction {transaction_hash:#x} appears at positions {first} and {second}")]
+    DuplicateTransaction { transaction_hash: Felt, first: usize, second: usize },
+    #[error("Reverted receipt at position {position} has {events} events and {messages} L2 to L1 messages")]
+    RevertedReceiptWithSideEffects { position: usize, events: usize, messages: usize },
+}
+
+/// A block returned by the feeder gateway failed strict validation.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub struct InconsistentBlockError {
+    /// `None` for the pending block.
+    pub block_n: Option<u64>,
+    pub anomalies: Vec<BlockAnomaly>,
+}
+
+impl fmt::Display for InconsistentBlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.block_n {
+            Some(block_n) => write!(f, "Block #{block_n}")?,
+            None => write!(f, "The pending block")?,
+        }
+        write!(f, " from the feeder gateway is inconsistent ({} issues found):", self.anomalies.len())?;
+        for anomaly in &self.anomalies {
+            write!(f, "\n  - {anomaly}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The signature of a block returned by the feeder gateway does not sign this block.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockSignatureError {
+    #[error("The signature of block #{block_n} is for block hash {signed_block_hash:#x} but the block hash is {block_hash:#x}")]
+    BlockHashMismatch { block_n: u64, block_hash: Felt, signed_block_hash: Felt },
+    #[error("Malformed signature for block #{block_n}: expected 2 felts, got {len}")]
+    Malformed { block_n: u64, len: usize },
+}
+
+/// Checks that the signature returned by the feeder gateway is for this block. The signature itself is verified
+/// against the sequencer public keys of the chain config by the block import, once the block hash is verified.
+pub fn parse_block_signature(
+    block_n: u64,
+    block_hash: Felt,
+    signature: &ProviderBlockSignature,
+) -> Result<ConsensusSignature, BlockSignatureError> {
+    if signature.block_hash != block_hash {
+        return Err(BlockSignatureError::BlockHashMismatch {
+            block_n,
+            block_hash,
+            signed_block_hash: signature.block_hash,
+        });
+    }
+    let [r, s] = signature.signature[..] else {
+        return Err(BlockSignatureError::Malformed { block_n, len: signature.signature.len() });
+    };
+    Ok(ConsensusSignature { r, s })
+}
+
+/// Returns every inconsistency found in a block and its state update.
+pub fn check_block_consistency(
+    block_n: u64,
+    block: &ProviderBlock,
+    state_update: &ProviderStateUpdate,
+) -> Vec<BlockAnomaly> {
+    let mut anomalies = Vec::new();
+
+    if block.block_number != block_n {
+        anomalies.push(BlockAnomaly::BlockNumberMismatch { expected: block_n, got: block.block_number });
+    }
+    if state_update.block_hash != block.block_hash {
+        anomalies.push(BlockAnomaly::StateUpdateBlockHashMismatch {
+            block: block.block_hash,
+            state_update: state_update.block_hash,
+        });
+    }
+
+    // All the block commitments are part of the block hash from 0.13.2 onwards.
+    match block.header() {
+        Ok(header) if header.protocol_version >= StarknetVersion::V0_13_2 => {
+            let starknet_version = header.protocol_version;
+            let missing = [
+                ("receipt commitment", block.receipt_commitment.is_none()),
+                ("state diff commitment", block.state_diff_commitment.is_none()),
+                ("state diff length", block.state_diff_length.is_none()),
+            ];
+            anomalies.extend(
+                missing
+                    .into_iter()
+                    .filter(|(_, is_missing)| *is_missing)
+                    .map(|(commitment, _)| BlockAnomaly::MissingCommitment { commitment, starknet_version }),
+            );
+        }
+        Ok(_) => {}
+        Err(err) => anomalies.push(BlockAnomaly::InvalidHeader(format!("{err:#}"))),
+    }
+
+    check_transactions_consistency(&block.transactions, &block.transaction_receipts, &mut anomalies);
+    anomalies
+}
+
+/// Returns every inconsistency found in the pending block. It has no block hash nor commitments: only its header and
+/// its transactions are checked.
+pub fn check_pending_block_consistency(block: &ProviderBlockPending) -> Vec<BlockAnomaly> {
+    let mut anomalies = Vec::new();
+    if let Err(err) = block.header() {
+        anomalies.push(BlockAnomaly::InvalidHeader(format!("{err:#}")));
+    }
+    check_transactions_consistency(&block.transactions, &block.transaction_receipts, &mut anomalies);
+    anomalies
+}
+
+fn check_transactions_consistency(
+    transactions: &[Transaction],
+    receipts: &[ConfirmedReceipt],
+    anomalies: &mut Vec<BlockAnomaly>,
+) {
+    if transactions.len() != receipts.len() {
+        anomalies
+            .push(BlockAnomaly::ReceiptCountMismatch { transactions: transactions.len(), receipts: receipts.len() });
+    }
+
+    let mut seen = HashMap::with_capacity(transactions.len());
+    for (position, tx) in transactions.iter().enumerate() {
+        match seen.get(tx.transaction_hash()) {
+            Some(&first) => anomalies.push(BlockAnomaly::DuplicateTransaction {
+                transaction_hash: *tx.transaction_hash(),
+                first,
+                second: position,
+            }),
+            None => {
+                seen.insert(*tx.transaction_hash(), position);
+            }
+        }
+    }
+
+    for (position, receipt) in receipts.iter().enumerate() {
+        if receipt.transaction_index != position as u64 {
+            anomalies
+                .push(BlockAnomaly::ReceiptIndexMismatch { position, transaction_index: receipt.transaction_index });
+        }
+        if let Some(tx) = transactions.get(position) {
+            if *tx.transaction_hash() != receipt.transaction_hash {
+                anomalies.push(BlockAnomaly::ReceiptHashMismatch {
+                    position,
+                    transaction_hash: *tx.transaction_hash(),
+                    receipt_transaction_hash: receipt.transaction_hash,
+                });
+            }
+        }
+        // The side effects of a reverted transaction are discarded.
+        if receipt.execution_status == ExecutionStatus::Reverted
+            && (!receipt.events.is_empty() || !receipt.l2_to_l1_messages.is_empty())
+        {
+            anomalies.push(BlockAnomaly::RevertedReceiptWithSideEffects {
+                position,
+                events: receipt.events.len(),
+                messages: receipt.l2_to_l1_messages.len(),
+            });
+        }
+    }
+}
+
+/// Fails with a report of every inconsistency found in the block, if any.
+pub fn validate_block_strict(
+    block_n: u64,
+    block: &ProviderBlock,
+    state_update: &ProviderStateUpdate,
+) -> Result<(), InconsistentBlockError> {
+    let anomalies = check_block_consistency(block_n, block, state_update);
+    if anomalies.is_empty() {
+        Ok(())
+    } else {
+        Err(InconsistentBlockError { block_n: Some(block_n), anomalies })
+    }
+}
+
+/// Fails with a report of every inconsistency found in the pending block, if any.
+pub fn validate_pending_block_strict(block: &ProviderBlockPending) -> Result<(), InconsistentBlockError> {
+    let anomalies = check_pending_block_consistency(block);
+    if anomalies.is_empty() {
+        Ok(())
+    } else {
+        Err(InconsistentBlockError { block_n: None, anomalies })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::header::L1DataAvailabilityMode;
+    use mp_gateway::block::{BlockStatus, ResourcePrice};
+    use mp_gateway::receipt::ExecutionResources;
+    use mp_gateway::state_update::StateDiff;
+    use mp_gateway::transaction::L1HandlerTransaction;
+    use mp_receipt::Event;
+    use mp_utils::crypto::ZeroingPrivateKey;
+    use rstest::*;
+    use starknet_api::felt;
+
+    fn transaction(hash: Felt) -> Transaction {
+        Transaction::L1Handler(L1HandlerTransaction {
+            contract_address: felt!("0x1"),
+            entry_point_selector: felt!("0x2"),
+            nonce: Felt::ZERO,
+            calldata: vec![],
+            transaction_hash: hash,
+            version: Felt::ZERO,
+        })
+    }
+
+    fn receipt(hash: Felt, index: u64) -> ConfirmedReceipt {
+        ConfirmedReceipt {
+            transaction_hash: hash,
+            transaction_index: index,
+            actual_fee: Felt::ZERO,
+            execution_resources: ExecutionResources::default(),
+            l2_to_l1_messages: vec![],
+            l1_to_l2_consumed_message: None,
+            events: vec![],
+            execution_status: ExecutionStatus::Succeeded,
+            revert_error: None,
+        }
+    }
+
+    #[fixture]
+    fn block() -> ProviderBlock {
+        let hashes = [felt!("0xa"), felt!("0xb"), felt!("0xc")];
+        ProviderBlock {
+            block_hash: felt!("0x1234"),
+            block_number: 5,
+            parent_block_hash: felt!("0x1233"),
+            timestamp: 1725974819,
+            sequencer_address: Some(felt!("0x99")),
+            state_root: felt!("0x5"),
+            transaction_commitment: felt!("0x6"),
+            event_commitment: felt!("0x7"),
+            receipt_commitment: Some(felt!("0x8")),
+            state_diff_commitment: Some(felt!("0x9")),
+            state_diff_length: Some(0),
+            status: BlockStatus::AcceptedOnL2,
+            l1_da_mode: L1DataAvailabilityMode::Calldata,
+            l1_gas_price: ResourcePrice { price_in_wei: 1, price_in_fri: 1 },
+            l1_data_gas_price: ResourcePrice { price_in_wei: 1, price_in_fri: 1 },
+            transactions: hashes.iter().copied().map(transaction).collect(),
+            transaction_receipts: hashes.iter().copied().zip(0..).map(|(hash, i)| receipt(hash, i)).collect(),
+            starknet_version: Some("0.13.2".into()),
+        }
+    }
+
+    #[fixture]
+    fn state_update() -> ProviderStateUpdate {
+        ProviderStateUpdate {
+            block_hash: felt!("0x1234"),
+            new_root: felt!("0x5"),
+            old_root: felt!("0x4"),
+            state_diff: StateDiff::default(),
+        }
+    }
+
+    #[rstest]
+    fn test_consistent_block(block: ProviderBlock, state_update: ProviderStateUpdate) {
+        assert_eq!(validate_block_strict(5, &block, &state_update), Ok(()));
+    }
+
+    #[rstest]
+    fn test_missing_commitments(mut block: ProviderBlock, state_update: ProviderStateUpdate) {
+        block.receipt_commitment = None;
+        block.state_diff_length = None;
+        assert_eq!(
+            check_block_consistency(5, &block, &state_update),
+            vec![
+                BlockAnomaly::MissingCommitment {
+                    commitment: "receipt commitment",
+                    starknet_version: StarknetVersion::V0_13_2
+                },
+                BlockAnomaly::MissingCommitment {
+                    commitment: "state diff length",
+                    starknet_version: StarknetVersion::V0_13_2
+                },
+            ]
+        );
+
+        // Commitments are optional before 0.13.2.
+        block.starknet_version = Some("0.13.1".into());
+        assert_eq!(check_block_consistency(5, &block, &state_update), vec![]);
+    }
+
+    #[rstest]
+    fn test_receipt_ordering_anomalies(mut block: ProviderBlock, state_update: ProviderStateUpdate) {
+        block.transaction_receipts.swap(0, 1);
+        block.transaction_receipts.pop();
+        assert_eq!(
+            check_block_consistency(5, &block, &state_update),
+            vec![
+                BlockAnomaly::ReceiptCountMismatch { transactions: 3, receipts: 2 },
+                BlockAnomaly::ReceiptIndexMismatch { position: 0, transaction_index: 1 },
+                BlockAnomaly::ReceiptHashMismatch {
+                    position: 0,
+                    transaction_hash: felt!("0xa"),
+                    receipt_transaction_hash: felt!("0xb")
+                },
+                BlockAnomaly::ReceiptIndexMismatch { position: 1, transaction_index: 0 },
+                BlockAnomaly::ReceiptHashMismatch {
+                    position: 1,
+                    transaction_hash: felt!("0xb"),
+                    receipt_transaction_hash: felt!("0xa")
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_duplicate_transaction_and_reverted_events(mut block: ProviderBlock, state_update: ProviderStateUpdate) {
+        block.transactions[2] = transaction(felt!("0xa"));
+        block.transaction_receipts[2].transaction_hash = felt!("0xa");
+        block.transaction_receipts[1].execution_status = ExecutionStatus::Reverted;
+        block.transaction_receipts[1].events = vec![Event { from_address: felt!("0x1"), keys: vec![], data: vec![] }];
+
+        let err = validate_block_strict(5, &block, &state_update).unwrap_err();
+        assert_eq!(
+            err.anomalies,
+            vec![
+                BlockAnomaly::DuplicateTransaction { transaction_hash: felt!("0xa"), first: 0, second: 2 },
+                BlockAnomaly::RevertedReceiptWithSideEffects { position: 1, events: 1, messages: 0 },
+            ]
+        );
+        assert!(err.to_string().starts_with("Block #5 from the feeder gateway is inconsistent (2 issues found):"));
+    }
+
+    #[rstest]
+    fn test_pending_block(block: ProviderBlock) {
+        let mut block = ProviderBlockPending {
+            parent_block_hash: block.parent_block_hash,
+            status: BlockStatus::Pending,
+            l1_da_mode: block.l1_da_mode,
+            l1_gas_price: block.l1_gas_price,
+            l1_data_gas_price: block.l1_data_gas_price,
+            transactions: block.transactions,
+            timestamp: block.timestamp,
+            sequencer_address: felt!("0x99"),
+            transaction_receipts: block.transaction_receipts,
+            starknet_version: block.starknet_version,
+        };
+        assert_eq!(validate_pending_block_strict(&block), Ok(()));
+
+        block.transaction_receipts.pop();
+        let err = validate_pending_block_strict(&block).unwrap_err();
+        assert_eq!(err.anomalies, vec![BlockAnomaly::ReceiptCountMismatch { transactions: 3, receipts: 2 }]);
+        assert!(err
+            .to_string()
+            .starts_with("The pending block from the feeder gateway is inconsistent (1 issues found):"));
+    }
+
+    #[test]
+    fn test_parse_block_signature() {
+        let key = ZeroingPrivateKey::default();
+        let block_hash = felt!("0x1234");
+        let signature = key.sign(&block_hash).unwrap();
+        let mut gateway_signature = ProviderBlockSignature { block_hash, signature: vec![signature.r, signature.s] };
+
+        assert_eq!(
+            parse_block_signature(5, block_hash, &gateway_signature),
+            Ok(ConsensusSignature { r: signature.r, s: signature.s })
+        );
+        assert_eq!(
+            parse_block_signature(5, felt!("0x4321"), &gateway_signature),
+            Err(BlockSignatureError::BlockHashMismatch {
+                block_n: 5,
+                block_hash: felt!("0x4321"),
+                signed_block_hash: block_hash
+            })
+        );
+
+        gateway_signature.signature.pop();
+        assert_eq!(
+            parse_block_signature(5, block_hash, &gateway_signature),
+            Err(BlockSignatureError::Malformed { block_n: 5, len: 1 })
+        );
+    }
+
+    #[rstest]
+    fn test_mismatched_block(block: ProviderBlock, mut state_update: ProviderStateUpdate) {
+        state_update.block_hash = felt!("0x4321");
+        assert_eq!(
+            check_block_consistency(6, &block, &state_update),
+            vec![
+                BlockAnomaly::BlockNumberMismatch { expected: 6, got: 5 },
+                BlockAnomaly::StateUpdateBlockHashMismatch { block: felt!("0x1234"), state_update: felt!("0x4321") },
+            ]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Sync progress history.
+//!
+//! The stages of the sync pipeline record the blocks they process and the time spent on them, and the totals are
+//! periodically written to the database as a [`SyncHistoryEntry`]. Unlike the metrics, the history survives restarts
+//! and does not depend on a scraper: it is served by the `madara_syncHistory` admin RPC method. The time spent on each
+//! block is also exported as a metric, see [`SyncMetrics::stage_duration_seconds`].
+
+use crate::metrics::sync_metrics::{SyncMetrics, STAGE_CONVERSION, STAGE_FETCH, STAGE_VERIFY_APPLY};
+use anyhow::Context;
+use mc_db::sync_history_db::{SyncHistoryEntry, SyncStageTimes};
+use mc_db::MadaraBackend;
+use opentelemetry::KeyValue;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Period covered by each entry of the history.
+pub const SYNC_HISTORY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Progress of the sync since the last entry of the history was written.
+#[derive(Debug)]
+pub struct SyncHistory {
+    period_start: Mutex<Instant>,
+    blocks_imported: AtomicU64,
+    fetch_micros: AtomicU64,
+    conversion_micros: AtomicU64,
+    verify_apply_micros: AtomicU64,
+    metrics: Arc<SyncMetrics>,
+}
+
+impl Default for SyncHistory {
+    fn default() -> Self {
+        Self::new(Arc::new(SyncMetrics::register()))
+    }
+}
+
+impl SyncHistory {
+    pub fn new(metrics: Arc<SyncMetrics>) -> Self {
+        Self {
+            period_start: Mutex::new(Instant::now()),
+            blocks_imported: AtomicU64::new(0),
+            fetch_micros: AtomicU64::new(0),
+            conversion_micros: AtomicU64::new(0),
+            verify_apply_micros: AtomicU64::new(0),
+            metrics,
+        }
+    }
+
+    fn record_stage(&self, micros: &AtomicU64, stage: &'static str, elapsed: Duration) {
+        micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.metrics.stage_duration_seconds.record(elapsed.as_secs_f64(), &[KeyValue::new("stage", stage)]);
+    }
+
+    pub fn record_fetch(&self, elapsed: Duration) {
+        self.record_stage(&self.fetch_micros, STAGE_FETCH, elapsed);
+    }
+
+    pub fn record_conversion(&self, elapsed: Duration) {
+        self.record_stage(&self.conversion_micros, STAGE_CONVERSION, elapsed);
+    }
+
+    /// Records a block which was verified and applied to the database.
+    pub fn record_verify_apply(&self, elapsed: Duration) {
+        self.record_stage(&self.verify_apply_micros, STAGE_VERIFY_APPLY, elapsed);
+        self.blocks_imported.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the number of blocks waiting for a stage of the pipeline. This is only exported as a metric, see
+    /// [`SyncMetrics::stage_queue_depth`].
+    pub fn record_queue_depth(&self, stage: &'static str, depth: usize) {
+        self.metrics.stage_queue_depth.record(depth as u64, &[KeyValue::new("stage", stage)]);
+    }
+
+    /// Ends the current period and starts a new one.
+    fn take_entry(&self, timestamp: u64, latest_block_n: Option<u64>) -> SyncHistoryEntry {
+        let period_secs = {
+            let mut period_start = self.period_start.lock().expect("Poisoned lock");
+            let now = Instant::now();
+            let period = now.duration_since(*period_start);
+            *period_start = now;
+            period.as_secs_f64()
+        };
+        let take_secs = |micros: &AtomicU64| micros.swap(0, Ordering::Relaxed) as f64 / 1_000_000.0;
+        let blocks_imported = self.blocks_imported.swap(0, Ordering::Relaxed);
+
+        SyncHistoryEntry {
+            timestamp,
+            period_secs,
+            latest_block_n,
+            blocks_imported,
+            blocks_per_second: if period_secs > 0.0 { blocks_imported as f64 / period_secs } else { 0.0 },
+            stage_secs: SyncStageTimes {
+                fetch: take_secs(&self.fetch_micros),
+                conversion: take_secs(&self.conversion_micros),
+                verify_apply: take_secs(&self.verify_apply_micros),
+            },
+        }
+    }
+
+    /// Writes the progress since the last entry to the database.
+    pub fn persist(&self, backend: &MadaraBackend) -> anyhow::Result<()> {
+        let timestamp =
+            SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).context("Getting unix time")?.as_secs();
+        let latest_block_n = backend.get_latest_block_n().context("Getting latest block_n")?;
+        let entry = self.take_entry(timestamp, latest_block_n);
+        backend.store_sync_history_entry(&entry).context("Storing sync history entry")?;
+        Ok(())
+    }
+
+    /// Writes an entry to the database every [`SYNC_HISTORY_PERIOD`]. This never returns: a failed write is logged, and
+    /// the next entry is written at the next period.
+    pub async fn persist_periodically(&self, backend: &MadaraBackend) {
+        let mut interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + SYNC_HISTORY_PERIOD, SYNC_HISTORY_PERIOD);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.persist(backend) {
+                tracing::warn!("⚠️ Failed to persist the sync history: {err:#}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_entry() {
+        let history = SyncHistory::default();
+        history.record_fetch(Duration::from_millis(1500));
+        history.record_fetch(Duration::from_millis(500));
+        history.record_conversion(Duration::from_millis(250));
+        history.record_verify_apply(Duration::from_secs(1));
+        history.record_verify_apply(Duration::from_secs(1));
+
+        let entry = history.take_entry(1000, Some(41));
+        assert_eq!(entry.timestamp, 1000);
+        assert_eq!(entry.latest_block_n, Some(41));
+        assert_eq!(entry.blocks_imported, 2);
+        assert_eq!(entry.stage_secs, SyncStageTimes { fetch: 2.0, conversion: 0.25, verify_apply: 2.0 });
+
+        // The next period starts from zero.
+        let entry = history.take_entry(1060, Some(41));
+        assert_eq!(entry.blocks_imported, 0);
+        assert_eq!(entry.stage_secs, SyncStageTimes::default());
+    }
+}
@@ -1,30 +1,43 @@
 //! Contains the code required to sync data from the feeder efficiently.
-use crate::fetch::fetchers::fetch_pending_block_and_updates;
+use crate::error::SyncError;
 use crate::fetch::fetchers::WarpUpdateConfig;
+use crate::fetch::fetchers::{fetch_pending_block_and_updates, FetchRetryPolicy};
 use crate::fetch::l2_fetch_task;
 use crate::fetch::L2FetchConfig;
+use crate::history::SyncHistory;
+use crate::metrics::sync_metrics::{SyncMetrics, STAGE_CONVERSION, STAGE_VERIFY_APPLY};
+use crate::quarantine::{self, QuarantineConfig};
+use crate::reorg::{self, RecoveryClasses};
+use crate::stall::{wait_for_stall, StallDetectionConfig, SyncStall};
+use crate::status::SyncProgress;
 use anyhow::Context;
 use futures::{stream, StreamExt};
 use mc_block_import::{
-    BlockImportResult, BlockImporter, BlockValidationContext, PreValidatedBlock, UnverifiedFullBlock,
+    BlockImportError, BlockImportResult, BlockImporter, BlockValidationContext, PreValidatedBlock, UnverifiedFullBlock,
+    UnverifiedPendingFullBlock,
 };
+use mc_db::db_block_id::DbBlockId;
 use mc_db::MadaraBackend;
 use mc_db::MadaraStorageError;
 use mc_gateway_client::GatewayProvider;
 use mc_telemetry::{TelemetryHandle, VerbosityLevel};
 use mp_block::BlockId;
 use mp_block::BlockTag;
+use mp_block::MadaraMaybePendingBlockInfo;
 use mp_gateway::error::SequencerError;
+use mp_utils::alerts::{Alert, AlertHandle, AlertKind};
 use mp_utils::service::ServiceContext;
 use mp_utils::trim_hash;
 use mp_utils::PerfStopwatch;
 use starknet_api::core::ChainId;
 use starknet_types_core::felt::Felt;
+use std::collections::BTreeMap;
 use std::pin::pin;
 use std::sync::Arc;
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinSet;
 use tokio::time::Duration;
+use tracing::Instrument;
 
 // TODO: add more explicit error variants
 #[derive(thiserror::Error, Debug)]
@@ -37,8 +50,23 @@ pub enum L2SyncError {
     BlockImport(#[from] mc_block_import::BlockImportError),
     #[error("Unexpected class type for class hash {class_hash:#x}")]
     UnexpectedClassType { class_hash: Felt },
+    #[error("Block #{block_n} does not follow the block fetched before it")]
+    Discontinuity {
+        block_n: u64,
+        #[source]
+        error: BlockImportError,
+    },
+    #[error("Block #{block_n} was rejected")]
+    InvalidBlock {
+        block_n: u64,
+        #[source]
+        error: BlockImportError,
+    },
 }
 
+/// Delay before the sync pipeline is restarted after the feeder gateway failed.
+const SOURCE_RETRY_DELAY: Duration = Duration::from_secs(10);
+
 /// Contains the latest Starknet verified state on L2
 #[derive(Debug, Clone)]
 pub struct L2StateUpdate {
@@ -55,7 +83,49 @@ pub struct L2VerifyApplyConfig {
     stop_on_sync: bool,
     telemetry: Arc<TelemetryHandle>,
     validation: BlockValidationContext,
-    block_conv_receiver: mpsc::Receiver<PreValidatedBlock>,
+    block_conv_receiver: mpsc::Receiver<(tracing::Span, PreValidatedBlock)>,
+    history: Arc<SyncHistory>,
+    progress: Arc<SyncProgress>,
+}
+
+/// Decides when the imported blocks are flushed from the memtables to disk: after `every_n_blocks` blocks, or once
+/// `interval` has elapsed since the last flush. The interval also applies while no block is being imported, so that the
+/// latest blocks are not only in memory when the sync is idle.
+struct FlushScheduler {
+    every_n_blocks: u64,
+    interval: Duration,
+    last_flush: tokio::time::Instant,
+    unflushed_blocks: u64,
+}
+
+impl FlushScheduler {
+    fn new(every_n_blocks: u64, interval: Duration) -> Self {
+        Self { every_n_blocks, interval, last_flush: tokio::time::Instant::now(), unflushed_blocks: 0 }
+    }
+
+    /// Records an imported block, and returns whether a flush is due.
+    fn on_block(&mut self) -> bool {
+        self.unflushed_blocks += 1;
+        self.unflushed_blocks >= self.every_n_blocks || self.last_flush.elapsed() >= self.interval
+    }
+
+    /// Resolves when the interval has elapsed and blocks are left unflushed.
+    async fn idle_deadline(&self) {
+        if self.unflushed_blocks == 0 {
+            return std::future::pending().await;
+        }
+        tokio::time::sleep_until(self.last_flush + self.interval).await
+    }
+
+    fn flush(&mut self, backend: &MadaraBackend) -> anyhow::Result<()> {
+        if self.unflushed_blocks == 0 {
+            return Ok(());
+        }
+        backend.flush().context("Flushing database")?;
+        self.unflushed_blocks = 0;
+        self.last_flush = tokio::time::Instant::now();
+        Ok(())
+    }
 }
 
 #[tracing::instrument(skip(backend, ctx, config), fields(module = "Sync"))]
@@ -73,19 +143,40 @@ async fn l2_verify_and_apply_task(
         telemetry,
         validation,
         mut block_conv_receiver,
+        history,
+        progress,
     } = config;
 
-    let mut last_block_n = 0;
-    let mut instant = std::time::Instant::now();
-    let target_duration = std::time::Duration::from_secs(flush_every_n_seconds);
+    let mut flush = FlushScheduler::new(flush_every_n_blocks, Duration::from_secs(flush_every_n_seconds));
+
+    loop {
+        let next = async {
+            tokio::select! {
+                block = block_conv_receiver.recv() => Some(block),
+                _ = flush.idle_deadline() => None,
+            }
+        };
+        let Some(next) = ctx.run_until_cancelled(next).await else { break };
+        let Some(next) = next else {
+            flush.flush(&backend)?;
+            continue;
+        };
+        let Some((span, block)) = next else { break };
+        history.record_queue_depth(STAGE_VERIFY_APPLY, block_conv_receiver.len());
 
-    while let Some(Some(block)) = ctx.run_until_cancelled(pin!(block_conv_receiver.recv())).await {
-        let BlockImportResult { header, block_hash } = block_import.verify_apply(block, validation.clone()).await?;
+        let started = std::time::Instant::now();
+        let n_classes = block.converted_classes.len();
+        let block_n = block.unverified_block_number;
+        let BlockImportResult { header, block_hash } = block_import
+            .verify_apply(block, validation.clone())
+            .instrument(span)
+            .await
+            .map_err(|err| invalid_block(block_n, err))?;
+        history.record_verify_apply(started.elapsed());
+        progress.record_block(n_classes);
 
-        if header.block_number - last_block_n >= flush_every_n_blocks || instant.elapsed() >= target_duration {
-            last_block_n = header.block_number;
-            instant = std::time::Instant::now();
-            backend.flush().context("Flushing database")?;
+        if flush.on_block() {
+            flush.flush(&backend)?;
         }
 
         tracing::info!(
@@ -118,6 +209,7 @@ async fn l2_verify_and_apply_task(
             tracing::info!("✅ Database backup is done ({:?})", sw.elapsed());
         }
     }
+    flush.flush(&backend)?;
 
     if stop_on_sync {
         ctx.cancel_global()
@@ -127,31 +219,46 @@ async fn l2_verify_and_apply_task(
 }
 
 async fn l2_block_conversion_task(
-    updates_receiver: mpsc::Receiver<UnverifiedFullBlock>,
-    output: mpsc::Sender<PreValidatedBlock>,
+    updates_receiver: mpsc::Receiver<(tracing::Span, UnverifiedFullBlock)>,
+    output: mpsc::Sender<(tracing::Span, PreValidatedBlock)>,
     block_import: Arc<BlockImporter>,
     validation: BlockValidationContext,
+    checkpoints: Arc<BTreeMap<u64, Felt>>,
+    history: Arc<SyncHistory>,
     mut ctx: ServiceContext,
 ) -> anyhow::Result<()> {
     // Items of this stream are futures that resolve to blocks, which becomes a regular stream of blocks
     // using futures buffered.
     let conversion_stream = stream::unfold(
-        (updates_receiver, block_import, validation.clone(), ctx.clone()),
-        |(mut updates_recv, block_import, validation, ctx)| async move {
-            updates_recv.recv().await.map(|block| {
+        (updates_receiver, block_import, validation.clone(), history, ctx.clone()),
+        |(mut updates_recv, block_import, validation, history, ctx)| async move {
+            updates_recv.recv().await.map(|(span, block)| {
+                history.record_queue_depth(STAGE_CONVERSION, updates_recv.len());
                 let block_import_ = Arc::clone(&block_import);
                 let validation_ = validation.clone();
+                let history_ = Arc::clone(&history);
                 (
-                    async move { block_import_.pre_validate(block, validation_).await },
-                    (updates_recv, block_import, validation, ctx),
+                    async move {
+                        let started = std::time::Instant::now();
+                        let block_n = block.unverified_block_number;
+                        let block = block_import_.pre_validate(block, validation_).instrument(span.clone()).await;
+                        history_.record_conversion(started.elapsed());
+                        block.map_err(|err| invalid_block(block_n, err)).map(|block| (span, block))
+                    },
+                    (updates_recv, block_import, validation, history, ctx),
                 )
             })
         },
     );
 
     let mut stream = pin!(conversion_stream.buffered(10));
+    let mut previous = None;
     while let Some(Some(block)) = ctx.run_until_cancelled(stream.next()).await {
-        if output.send(block?).await.is_err() {
+        let (span, block) = block?;
+        check_continuity(previous, &block)?;
+        check_checkpoint(&checkpoints, &block).map_err(|err| invalid_block(block.unverified_block_number, err))?;
+        previous = block.unverified_block_number.zip(block.unverified_block_hash);
+        if output.send((span, block)).await.is_err() {
             // channel closed
             break;
         }
@@ -160,11 +267,79 @@ async fn l2_block_conversion_task(
     anyhow::Ok(())
 }
 
+/// The error of block `block_n`, which was rejected by the block importer. The blocks are converted in parallel and
+/// the pipeline drops the blocks in flight when it stops, so the number of the block is kept with the error to know
+/// which block to quarantine, see [`SyncError::block_n`].
+fn invalid_block(block_n: Option<u64>, error: BlockImportError) -> anyhow::Error {
+    match block_n {
+        Some(block_n) => L2SyncError::InvalidBlock { block_n, error }.into(),
+        None => error.into(),
+    }
+}
+
+/// Rejects a block whose parent is not the block converted before it, given as its block number and hash. The blocks
+/// are fetched in parallel, and the feeder gateway may serve blocks from both sides of a reorg in the same batch. The
+/// parent of the first block of a batch is checked against the database when the block is applied.
+fn check_continuity(previous: Option<(u64, Felt)>, block: &PreValidatedBlock) -> Result<(), L2SyncError> {
+    let (Some((previous_n, previous_hash)), Some(block_n), Some(parent_block_hash)) =
+        (previous, block.unverified_block_number, block.header.parent_block_hash)
+    else {
+        return Ok(());
+    };
+    if block_n == previous_n + 1 && parent_block_hash != previous_hash {
+        return Err(L2SyncError::Discontinuity {
+            block_n,
+            error: BlockImportError::ParentHash { got: parent_block_hash, expected: previous_hash },
+        });
+    }
+    Ok(())
+}
+
+/// How the blocks imported by the sync are validated. This is also used for the blocks imported from files, see
+/// [`crate::block_files::import_blocks`].
+pub fn sync_validation_context(
+    chain_id: ChainId,
+    verify: bool,
+    strict_validation: bool,
+    compute_v0_13_2_hashes: bool,
+    verify_signatures: bool,
+) -> BlockValidationContext {
+    BlockValidationContext {
+        trust_transaction_hashes: false,
+        trust_global_tries: !verify,
+        chain_id,
+        trust_class_hashes: false,
+        ignore_block_order: false,
+        trust_signatures: !verify_signatures,
+        compute_v0_13_2_hashes,
+        strict_declared_classes: strict_validation,
+        trust_commitments: false,
+    }
+}
+
+/// Rejects a block whose hash differs from the one pinned for its block number by the chain registry.
+pub(crate) fn check_checkpoint(
+    checkpoints: &BTreeMap<u64, Felt>,
+    block: &PreValidatedBlock,
+) -> Result<(), BlockImportError> {
+    let Some(expected) = block.unverified_block_number.and_then(|block_n| checkpoints.get(&block_n)) else {
+        return Ok(());
+    };
+    let got = block.unverified_block_hash.unwrap_or_default();
+    if got != *expected {
+        return Err(BlockImportError::BlockHash { got, expected: *expected });
+    }
+    Ok(())
+}
+
 struct L2PendingBlockConfig {
     block_import: Arc<BlockImporter>,
     once_caught_up_receiver: oneshot::Receiver<()>,
     pending_block_poll_interval: Duration,
     validation: BlockValidationContext,
+    strict_validation: bool,
+    retry_policy: FetchRetryPolicy,
+    metrics: Arc<SyncMetrics>,
 }
 
 async fn l2_pending_block_task(
@@ -173,8 +348,15 @@ async fn l2_pending_block_task(
     mut ctx: ServiceContext,
     config: L2PendingBlockConfig,
 ) -> anyhow::Result<()> {
-    let L2PendingBlockConfig { block_import, once_caught_up_receiver, pending_block_poll_interval, validation } =
-        config;
+    let L2PendingBlockConfig {
+        block_import,
+        once_caught_up_receiver,
+        pending_block_poll_interval,
+        validation,
+        strict_validation,
+        retry_policy,
+        metrics,
+    } = config;
 
     // clear pending status
     {
@@ -201,13 +383,19 @@ async fn l2_pending_block_task(
             .unwrap_or(/* genesis parent block hash */ Felt::ZERO);
 
         let chain_id = &backend.chain_config().chain_id;
-        let Some(block) = fetch_pending_block_and_updates(current_block_hash, chain_id, &provider)
-            .await
-            .context("Getting pending block from FGW")?
+        let Some(block) =
+            fetch_pending_block_and_updates(current_block_hash, chain_id, &provider, strict_validation, &retry_policy)
+                .await
+                .context("Getting pending block from FGW")?
         else {
             continue;
         };
 
+        if is_pending_block_unchanged(&backend, &block).context("Comparing with the stored pending block")? {
+            tracing::debug!("Pending block unchanged");
+            continue;
+        }
+
         // HACK(see issue #239): The latest block in db may not match the pending parent block hash
         // Just silently ignore it for now and move along.
         let import_block = || async {
@@ -216,21 +404,48 @@ async fn l2_pending_block_task(
             anyhow::Ok(())
         };
 
-        if let Err(err) = import_block().await {
-            tracing::debug!("Failed to import pending block: {err:#}");
+        match import_block().await {
+            Ok(()) => {}
+            // The next block was imported while this pending block was being fetched: the database refused it, so that
+            // the RPC does not serve a pending state which does not apply on top of the latest block.
+            Err(err) => match err.downcast_ref::<BlockImportError>() {
+                Some(BlockImportError::StalePendingBlock { parent_block_hash }) => {
+                    tracing::info!(
+                        "🗑️  Dropped the pending block, its parent {parent_block_hash:#x} is not the latest block anymore"
+                    );
+                    metrics.pending_abandoned_counter.add(1, &[]);
+                }
+                _ => tracing::debug!("Failed to import pending block: {err:#}"),
+            },
         }
     }
 
     Ok(())
 }
 
+/// Whether the pending block has the same parent and transactions as the pending block stored in the database. The
+/// gateway keeps serving the same pending block until new transactions are added to it: there is no need to import it
+/// again until then.
+fn is_pending_block_unchanged(backend: &MadaraBackend, block: &UnverifiedPendingFullBlock) -> anyhow::Result<bool> {
+    let Some(MadaraMaybePendingBlockInfo::Pending(stored)) = backend.get_block_info(&DbBlockId::Pending)? else {
+        return Ok(false);
+    };
+    Ok(block.header.parent_block_hash == Some(stored.header.parent_block_hash)
+        && block.receipts.iter().map(|receipt| receipt.transaction_hash()).eq(stored.tx_hashes.iter().copied()))
+}
+
 pub struct L2SyncConfig {
     pub first_block: u64,
     pub n_blocks_to_sync: Option<u64>,
     pub stop_on_sync: bool,
     pub sync_parallelism: u8,
     pub verify: bool,
+    pub strict_validation: bool,
+    pub verify_signatures: bool,
+    pub compute_v0_13_2_hashes: bool,
+    pub retry_policy: FetchRetryPolicy,
     pub sync_polling_interval: Option<Duration>,
+    pub sync_push: bool,
     pub backup_every_n_blocks: Option<u64>,
     pub flush_every_n_blocks: u64,
     pub flush_every_n_seconds: u64,
@@ -238,11 +453,29 @@ pub struct L2SyncConfig {
     pub ignore_block_order: bool,
     pub chain_id: ChainId,
     pub telemetry: Arc<TelemetryHandle>,
+    /// The stalls, quarantined blocks and reorgs are reported to the operators through this handle.
+    pub alerts: AlertHandle,
     pub block_importer: Arc<BlockImporter>,
     pub warp_update: Option<WarpUpdateConfig>,
+    pub stall_detection: Option<StallDetectionConfig>,
+    pub quarantine: Option<QuarantineConfig>,
+    /// Maximum number of blocks reverted to recover from a reorg, disabled when 0.
+    pub max_reorg_depth: u64,
+    /// Block hashes pinned by the chain registry, by block number.
+    pub checkpoints: Arc<BTreeMap<u64, Felt>>,
+    pub history: Arc<SyncHistory>,
+    pub progress: Arc<SyncProgress>,
+    pub metrics: Arc<SyncMetrics>,
 }
 
 /// Spawns workers to fetch blocks and state updates from the feeder.
+///
+/// When stall detection is enabled and the pipeline is restarted, the workers are respawned from the block following
+/// the latest block in the database. This is also the case when a block fails verification and a quarantine directory
+/// is configured, once the diagnostics bundle of the block has been written. When the parent of the next block is not
+/// the latest block in the database, the database is first reverted to the latest block in common with the feeder
+/// gateway, see [`crate::reorg`]. When the feeder gateway keeps failing, the workers are respawned after a delay,
+/// while any other error stops the sync, see [`SyncError`].
 #[tracing::instrument(skip(backend, provider, ctx, config), fields(module = "Sync"))]
 pub async fn sync(
     backend: Arc<MadaraBackend>,
@@ -250,9 +483,149 @@ pub async fn sync(
     ctx: ServiceContext,
     config: L2SyncConfig,
 ) -> anyhow::Result<()> {
+    let provider = Arc::new(provider);
+    let mut first_block = config.first_block;
+    let mut last_quarantined = None;
+    let mut recovery = None;
+
+    loop {
+        // The classes of the reverted blocks are dropped once the sync is past the heights of the reorg.
+        if recovery.as_ref().is_some_and(|recovery: &Arc<RecoveryClasses>| first_block > recovery.until_block_n) {
+            recovery = None;
+        }
+        let mut join_set = spawn_sync_tasks(&backend, &provider, &ctx, &config, first_block, recovery.as_ref());
+
+        let tasks = async {
+            while let Some(res) = join_set.join_next().await {
+                res.context("task was dropped").map_err(SyncError::Internal)?.map_err(SyncError::classify)?;
+            }
+            Ok::<_, SyncError>(())
+        };
+        let watchdog = async {
+            let Some(stall_detection) = &config.stall_detection else {
+                return std::future::pending().await;
+            };
+            loop {
+                let SyncStall { head, target, since } = wait_for_stall(&backend, &provider, stall_detection).await;
+                config.metrics.sync_stall_counter.add(1, &[]);
+                let message = format!(
+                    "Sync is stalled: no block imported for {since:?}, the latest block is {} but the gateway is at \
+                     block #{target}",
+                    head.map(|head| format!("#{head}")).unwrap_or_else(|| "none".into()),
+                );
+                tracing::warn!("⚠️ {message}");
+                config.alerts.send(Alert::new(AlertKind::SyncStalled, message));
+                if stall_detection.restart {
+                    return;
+                }
+            }
+        };
+
+        let mut reorg_err = None;
+        let mut source_err = None;
+        let failure = tokio::select! {
+            res = tasks => match (res, &config.quarantine) {
+                (Ok(()), _) => return Ok(()),
+                (Err(SyncError::Verification(err)), _) if config.max_reorg_depth > 0 && reorg::is_reorg(&err) => {
+                    reorg_err = Some(err);
+                    None
+                }
+                (Err(err), Some(quarantine_config)) if err.import_error().is_some() => Some((err, quarantine_config)),
+                (Err(err), _) if err.is_retryable() => {
+                    source_err = Some(err);
+                    None
+                }
+                (Err(err), _) => return Err(err.into()),
+            },
+            _ = watchdog => None,
+        };
+
+        join_set.shutdown().await;
+        // Blocks which were being applied when the tasks were aborted are still written to the database.
+        config.block_importer.wait_idle().await;
+        first_block = backend.get_latest_block_n().context("Getting latest block_n")?.map(|n| n + 1).unwrap_or(0);
+
+        if let Some(err) = reorg_err {
+            tracing::warn!("🔀 Reorg detected at block #{first_block}: {err:#}");
+            let Some((ancestor, reverted_classes)) =
+                reorg::revert_to_common_ancestor(&backend, &provider, config.max_reorg_depth).await?
+            else {
+                tracing::warn!(
+                    "🔀 The latest block is still on the chain of the feeder gateway, retrying block #{first_block} in \
+                     {SOURCE_RETRY_DELAY:?}"
+                );
+                if ctx.clone().run_until_cancelled(tokio::time::sleep(SOURCE_RETRY_DELAY)).await.is_none() {
+                    return Ok(());
+                }
+                continue;
+            };
+            tracing::warn!(
+                "🔀 Reverted the database to block #{ancestor}, keeping the {} classes of the reverted blocks",
+                reverted_classes.len()
+            );
+            let depth = first_block.saturating_sub(ancestor + 1);
+            config.alerts.send(
+                Alert::new(
+                    AlertKind::Reorg { depth },
+                    format!("Reorg of {depth} blocks: the database was reverted to block #{ancestor}"),
+                )
+                .with_block_n(ancestor),
+            );
+            first_block = ancestor + 1;
+            recovery = Some(Arc::new(reverted_classes));
+        }
+
+        if let Some(err) = source_err {
+            tracing::warn!("⚠️ Could not fetch block #{first_block}, retrying in {SOURCE_RETRY_DELAY:?}: {err:#}");
+            if ctx.clone().run_until_cancelled(tokio::time::sleep(SOURCE_RETRY_DELAY)).await.is_none() {
+                return Ok(());
+            }
+        }
+
+        if let Some((err, quarantine_config)) = failure {
+            let block_n = err.block_n().unwrap_or(first_block);
+            tracing::error!("❗ Block #{block_n} failed verification: {err:#}");
+            if last_quarantined != Some(block_n) {
+                last_quarantined = Some(block_n);
+                let import_err = err.import_error().expect("Checked above");
+                let bundle =
+                    quarantine::write_bundle(&quarantine_config.dir, block_n, import_err, &backend, &provider, &config);
+                let bundle = match bundle.await {
+                    Ok(path) => {
+                        tracing::warn!("📦 Diagnostics bundle for block #{block_n} written to {}", path.display());
+                        format!("its diagnostics bundle was written to {}", path.display())
+                    }
+                    Err(err) => {
+                        tracing::warn!("Could not write the diagnostics bundle for block #{block_n}: {err:#}");
+                        format!("its diagnostics bundle could not be written: {err:#}")
+                    }
+                };
+                config.alerts.send(
+                    Alert::new(
+                        AlertKind::VerificationFailure,
+                        format!("Block #{block_n} failed verification and was quarantined, {bundle}: {err:#}"),
+                    )
+                    .with_block_n(block_n),
+                );
+            }
+            if ctx.clone().run_until_cancelled(tokio::time::sleep(quarantine_config.retry_delay)).await.is_none() {
+                return Ok(());
+            }
+        }
+        tracing::info!("🔁 Restarting the sync pipeline from block #{first_block}");
+    }
+}
+
+fn spawn_sync_tasks(
+    backend: &Arc<MadaraBackend>,
+    provider: &Arc<GatewayProvider>,
+    ctx: &ServiceContext,
+    config: &L2SyncConfig,
+    first_block: u64,
+    recovery: Option<&Arc<RecoveryClasses>>,
+) -> JoinSet<anyhow::Result<()>> {
     let (fetch_stream_sender, fetch_stream_receiver) = mpsc::channel(8);
     let (block_conv_sender, block_conv_receiver) = mpsc::channel(4);
-    let provider = Arc::new(provider);
     let (once_caught_up_sender, once_caught_up_receiver) = oneshot::channel();
 
     // [Fetch task] ==new blocks and updates=> [Block conversion task] ======> [Verification and apply
@@ -265,11 +638,14 @@ pub async fn sync(
     // we are using separate tasks so that fetches don't get clogged up if by any chance the verify task
     // starves the tokio worker
     let validation = BlockValidationContext {
-        trust_transaction_hashes: false,
-        trust_global_tries: !config.verify,
-        chain_id: config.chain_id,
-        trust_class_hashes: false,
         ignore_block_order: config.ignore_block_order,
+        ..sync_validation_context(
+            config.chain_id.clone(),
+            config.verify,
+            config.strict_validation,
+            config.compute_v0_13_2_hashes,
+            config.verify_signatures,
+        )
     };
 
     let mut join_set = JoinSet::new();
@@ -277,18 +653,24 @@ pub async fn sync(
         config.warp_update.as_ref().map(|w| w.warp_update_shutdown_receiver).unwrap_or(false);
 
     join_set.spawn(l2_fetch_task(
-        Arc::clone(&backend),
-        Arc::clone(&provider),
+        Arc::clone(backend),
+        Arc::clone(provider),
         ctx.clone(),
         L2FetchConfig {
-            first_block: config.first_block,
+            first_block,
             fetch_stream_sender,
             once_caught_up_sender,
             sync_polling_interval: config.sync_polling_interval,
+            sync_push: config.sync_push,
             n_blocks_to_sync: config.n_blocks_to_sync,
             stop_on_sync: config.stop_on_sync,
             sync_parallelism: config.sync_parallelism as usize,
-            warp_update: config.warp_update,
+            strict_validation: config.strict_validation,
+            verify_signatures: config.verify_signatures,
+            retry_policy: config.retry_policy,
+            warp_update: config.warp_update.clone(),
+            history: Arc::clone(&config.history),
+            recovery: recovery.cloned(),
         },
     ));
     join_set.spawn(l2_block_conversion_task(
@@ -296,10 +678,12 @@ pub async fn sync(
         block_conv_sender,
         Arc::clone(&config.block_importer),
         validation.clone(),
+        Arc::clone(&config.checkpoints),
+        Arc::clone(&config.history),
         ctx.clone(),
     ));
     join_set.spawn(l2_verify_and_apply_task(
-        Arc::clone(&backend),
+        Arc::clone(backend),
         ctx.clone(),
         L2VerifyApplyConfig {
             block_import: Arc::clone(&config.block_importer),
@@ -307,28 +691,29 @@ pub async fn sync(
             flush_every_n_blocks: config.flush_every_n_blocks,
             flush_every_n_seconds: config.flush_every_n_seconds,
             stop_on_sync: config.stop_on_sync || warp_update_shutdown_sender,
-            telemetry: config.telemetry,
+            telemetry: Arc::clone(&config.telemetry),
             validation: validation.clone(),
             block_conv_receiver,
+            history: Arc::clone(&config.history),
+            progress: Arc::clone(&config.progress),
         },
     ));
     join_set.spawn(l2_pending_block_task(
-        Arc::clone(&backend),
-        provider,
+        Arc::clone(backend),
+        Arc::clone(provider),
         ctx.clone(),
         L2PendingBlockConfig {
             block_import: Arc::clone(&config.block_importer),
             once_caught_up_receiver,
             pending_block_poll_interval: config.pending_block_poll_interval,
-            validation: validation.clone(),
+            validation,
+            strict_validation: config.strict_validation,
+            retry_policy: config.retry_policy,
+            metrics: Arc::clone(&config.metrics),
         },
     ));
 
-    while let Some(res) = join_set.join_next().await {
-        res.context("task was dropped")??;
-    }
-
-    Ok(())
+    join_set
 }
 
 #[cfg(test)]
@@ -348,6 +733,17 @@ mod tests {
     use std::sync::Arc;
     use tokio::sync::mpsc;
 
+    #[test]
+    fn test_flush_scheduler() {
+        let mut flush = FlushScheduler::new(3, Duration::from_secs(3600));
+        assert!(!flush.on_block());
+        assert!(!flush.on_block());
+        assert!(flush.on_block());
+
+        let mut flush = FlushScheduler::new(1_000, Duration::ZERO);
+        assert!(flush.on_block());
+    }
+
     /// Test the `l2_verify_and_apply_task` function.
     ///
     ///
@@ -388,11 +784,13 @@ mod tests {
                 telemetry,
                 validation: validation.clone(),
                 block_conv_receiver,
+                history: Default::default(),
+                progress: Default::default(),
             },
         ));
 
         let mock_pre_validated_block = block_import.pre_validate(mock_block, validation.clone()).await.unwrap();
-        block_conv_sender.send(mock_pre_validated_block).await.unwrap();
+        block_conv_sender.send((tracing::Span::none(), mock_pre_validated_block)).await.unwrap();
 
         drop(block_conv_sender);
 
@@ -440,19 +838,21 @@ mod tests {
 
         let mock_block = create_dummy_unverified_full_block();
 
-        updates_sender.send(mock_block).await.unwrap();
+        updates_sender.send((tracing::Span::none(), mock_block)).await.unwrap();
 
         let task_handle = tokio::spawn(l2_block_conversion_task(
             updates_receiver,
             output_sender,
             block_import,
             validation,
+            Default::default(),
+            Default::default(),
             ServiceContext::new_for_testing(),
         ));
 
         let result = tokio::time::timeout(std::time::Duration::from_secs(5), output_receiver.recv()).await;
         match result {
-            Ok(Some(b)) => {
+            Ok(Some((_, b))) => {
                 assert_eq!(b.unverified_block_number, Some(0), "Block number does not match");
             }
             Ok(None) => panic!("Channel closed without receiving a result"),
@@ -469,6 +869,96 @@ mod tests {
         }
     }
 
+    /// Blocks whose hash differs from the one pinned by the chain registry are rejected by the conversion task.
+    #[rstest]
+    #[tokio::test]
+    async fn test_l2_block_conversion_task_checkpoint(test_setup: Arc<MadaraBackend>) {
+        let backend = test_setup;
+        let (updates_sender, updates_receiver) = mpsc::channel(100);
+        let (output_sender, mut output_receiver) = mpsc::channel(100);
+        let block_import = Arc::new(BlockImporter::new(backend.clone(), None).unwrap());
+        let validation = BlockValidationContext::new(backend.chain_config().chain_id.clone());
+
+        let mut mock_block = create_dummy_unverified_full_block();
+        mock_block.commitments.block_hash = Some(Felt::ONE);
+        updates_sender.send((tracing::Span::none(), mock_block)).await.unwrap();
+        drop(updates_sender);
+
+        let checkpoints = Arc::new(BTreeMap::from([(0, Felt::TWO)]));
+        let res = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            l2_block_conversion_task(
+                updates_receiver,
+                output_sender,
+                block_import,
+                validation,
+                checkpoints,
+                Default::default(),
+                ServiceContext::new_for_testing(),
+            ),
+        )
+        .await
+        .expect("Timeout reached while waiting for task completion");
+
+        let err = res.expect_err("The block should be rejected");
+        assert!(matches!(
+            err.downcast_ref::<L2SyncError>(),
+            Some(L2SyncError::InvalidBlock {
+                block_n: 0,
+                error: BlockImportError::BlockHash { got, expected }
+            }) if *got == Felt::ONE && *expected == Felt::TWO
+        ));
+        assert!(output_receiver.try_recv().is_err());
+    }
+
+    /// A block whose parent is not the block converted before it is rejected by the conversion task, after the blocks
+    /// before it have been passed on.
+    #[rstest]
+    #[tokio::test]
+    async fn test_l2_block_conversion_task_continuity(test_setup: Arc<MadaraBackend>) {
+        let backend = test_setup;
+        let (updates_sender, updates_receiver) = mpsc::channel(100);
+        let (output_sender, mut output_receiver) = mpsc::channel(100);
+        let block_import = Arc::new(BlockImporter::new(backend.clone(), None).unwrap());
+        let validation = BlockValidationContext::new(backend.chain_config().chain_id.clone());
+
+        for (block_n, parent_block_hash) in [(0, Felt::ZERO), (1, Felt::ONE), (2, Felt::THREE)] {
+            let mut mock_block = create_dummy_unverified_full_block();
+            mock_block.unverified_block_number = Some(block_n);
+            mock_block.header.parent_block_hash = Some(parent_block_hash);
+            mock_block.commitments.block_hash = Some(Felt::from(block_n + 1));
+            updates_sender.send((tracing::Span::none(), mock_block)).await.unwrap();
+        }
+        drop(updates_sender);
+
+        let res = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            l2_block_conversion_task(
+                updates_receiver,
+                output_sender,
+                block_import,
+                validation,
+                Default::default(),
+                Default::default(),
+                ServiceContext::new_for_testing(),
+            ),
+        )
+        .await
+        .expect("Timeout reached while waiting for task completion");
+
+        let err = res.expect_err("Block #2 should be rejected");
+        assert!(matches!(
+            err.downcast_ref::<L2SyncError>(),
+            Some(L2SyncError::Discontinuity {
+                block_n: 2,
+                error: BlockImportError::ParentHash { got, expected }
+            }) if *got == Felt::THREE && *expected == Felt::TWO
+        ));
+        assert_eq!(output_receiver.try_recv().unwrap().1.unverified_block_number, Some(0));
+        assert_eq!(output_receiver.try_recv().unwrap().1.unverified_block_number, Some(1));
+        assert!(output_receiver.try_recv().is_err());
+    }
+
     /// Test the `l2_pending_block_task` function.
     ///
     /// This test function verifies the behavior of the `l2_pending_block_task`.
@@ -501,6 +991,9 @@ mod tests {
                 once_caught_up_receiver: ctx.once_caught_up_receiver,
                 pending_block_poll_interval: std::time::Duration::from_secs(5),
                 validation: validation.clone(),
+                strict_validation: false,
+                retry_policy: FetchRetryPolicy::default(),
+                metrics: Arc::new(SyncMetrics::register()),
             },
         ));
 
@@ -514,4 +1007,42 @@ mod tests {
             Err(_) => panic!("Timeout reached while waiting for task completion"),
         }
     }
+
+    #[rstest]
+    fn test_is_pending_block_unchanged(test_setup: Arc<MadaraBackend>) {
+        use mp_block::{MadaraBlockInner, MadaraMaybePendingBlock, MadaraPendingBlockInfo, PendingHeader};
+        use mp_receipt::{InvokeTransactionReceipt, TransactionReceipt};
+
+        let backend = test_setup;
+        let receipt = |tx_hash: u64| {
+            TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                transaction_hash: Felt::from(tx_hash),
+                ..Default::default()
+            })
+        };
+        let pending_block = |receipts: Vec<TransactionReceipt>| UnverifiedPendingFullBlock {
+            header: mc_block_import::UnverifiedHeader { parent_block_hash: Some(Felt::ONE), ..Default::default() },
+            receipts,
+            ..Default::default()
+        };
+
+        backend
+            .store_block(
+                MadaraMaybePendingBlock {
+                    info: MadaraMaybePendingBlockInfo::Pending(MadaraPendingBlockInfo {
+                        header: PendingHeader { parent_block_hash: Felt::ONE, ..Default::default() },
+                        tx_hashes: vec![Felt::from(1)],
+                    }),
+                    inner: MadaraBlockInner { transactions: vec![], receipts: vec![receipt(1)] },
+                },
+                Default::default(),
+                vec![],
+                None,
+                None,
+            )
+            .unwrap();
+
+        assert!(is_pending_block_unchanged(&backend, &pending_block(vec![receipt(1)])).unwrap());
+        assert!(!is_pending_block_unchanged(&backend, &pending_block(vec![receipt(1), receipt(2)])).unwrap());
+    }
 }
@@ -1,20 +1,36 @@
+use crate::backfill::BackfillConfig;
+use crate::history::SyncHistory;
 use crate::l2::L2SyncConfig;
+use crate::metrics::sync_metrics::SyncMetrics;
+use crate::status::SyncProgress;
 use anyhow::Context;
-use fetch::fetchers::FetchConfig;
+use fetch::fetchers::{fetch_class_updates, FetchConfig, FetchRetryPolicy};
 use hyper::header::{HeaderName, HeaderValue};
-use mc_block_import::BlockImporter;
+use mc_block_import::{BlockImporter, BlockValidationContext};
 use mc_db::MadaraBackend;
 use mc_gateway_client::GatewayProvider;
 use mc_telemetry::TelemetryHandle;
 use mp_block::{BlockId, BlockTag};
+use mp_utils::alerts::AlertHandle;
 use mp_utils::service::ServiceContext;
 use std::{sync::Arc, time::Duration};
+use tokio::task::JoinSet;
 
+pub mod backfill;
+pub mod block_files;
+pub mod error;
 pub mod fetch;
+pub mod history;
 pub mod l2;
 pub mod metrics;
+pub mod quarantine;
+pub mod reorg;
+pub mod snapshot;
+pub mod stall;
+pub mod status;
 #[cfg(test)]
 pub mod tests;
+pub mod trie_catch_up;
 
 pub struct SyncConfig {
     pub block_importer: Arc<BlockImporter>,
@@ -22,6 +38,59 @@ pub struct SyncConfig {
     pub backup_every_n_blocks: Option<u64>,
     pub telemetry: Arc<TelemetryHandle>,
     pub pending_block_poll_interval: Duration,
+    pub alerts: AlertHandle,
+}
+
+/// Fetches again the classes declared in the blocks which were stored without them, see [`mc_db::pipeline_gaps`].
+/// The node still starts when they cannot be fetched: the classes stay missing, and this is retried on the next start.
+async fn repair_classes_gap(
+    backend: &Arc<MadaraBackend>,
+    provider: &GatewayProvider,
+    block_importer: &BlockImporter,
+    retry_policy: &FetchRetryPolicy,
+) -> anyhow::Result<()> {
+    let finder = Arc::clone(backend);
+    let Some(gap) = tokio::task::spawn_blocking(move || finder.find_classes_gap())
+        .await
+        .context("Classes gap task was dropped")?
+        .context("Looking for blocks missing classes")?
+    else {
+        return Ok(());
+    };
+
+    tracing::warn!(
+        "🩹 The classes declared in {} blocks from block #{} are missing, fetching them again",
+        gap.blocks.len(),
+        gap.first_block()
+    );
+    let chain_id = backend.chain_config().chain_id.clone();
+    for block_n in gap.blocks {
+        let repair = async {
+            let state_diff = backend
+                .get_block_state_diff(&BlockId::Number(block_n))?
+                .with_context(|| format!("Block #{block_n} is not stored"))?;
+            let classes = fetch_class_updates(
+                &chain_id,
+                &state_diff.into(),
+                BlockId::Number(block_n),
+                provider,
+                retry_policy,
+                None,
+            )
+            .await?;
+            let classes = classes.into_iter().map(Into::into).collect();
+            let validation = BlockValidationContext::new(chain_id.clone());
+            block_importer.store_missing_classes(block_n, classes, validation).await?;
+            anyhow::Ok(())
+        };
+        if let Err(err) = repair.await {
+            tracing::warn!(
+                "Failed to fetch the classes declared in block #{block_n} again, executing transactions which use them \
+                 will fail: {err:#}"
+            );
+        }
+    }
+    Ok(())
 }
 
 #[tracing::instrument(skip(backend, ctx, fetch_config, sync_config))]
@@ -31,6 +100,10 @@ pub async fn l2_sync_worker(
     fetch_config: FetchConfig,
     sync_config: SyncConfig,
 ) -> anyhow::Result<()> {
+    if let Some(snapshot) = &fetch_config.snapshot {
+        snapshot::import_snapshot(&backend, &sync_config.block_importer, snapshot).await?;
+    }
+
     let (starting_block, ignore_block_order) = if let Some(starting_block) = sync_config.starting_block {
         tracing::warn!("Forcing unordered state. This will most probably break your database.");
         (starting_block, true)
@@ -47,7 +120,11 @@ pub async fn l2_sync_worker(
 
     tracing::info!("⛓️  Starting L2 sync from block {}", starting_block);
 
-    let mut provider = GatewayProvider::new(fetch_config.gateway, fetch_config.feeder_gateway);
+    let mut provider = GatewayProvider::new(fetch_config.gateway, fetch_config.feeder_gateway)
+        .with_http_config(fetch_config.http)
+        .with_fallbacks(fetch_config.fallback_gateways)
+        .with_max_requests_per_second(fetch_config.gateway_max_rps)
+        .with_strict_schema(fetch_config.strict_gateway_schema);
     if let Some(api_key) = fetch_config.api_key {
         provider.add_header(
             HeaderName::from_static("x-throttling-bypass"),
@@ -55,12 +132,36 @@ pub async fn l2_sync_worker(
         )
     }
 
+    if sync_config.starting_block.is_none() {
+        repair_classes_gap(&backend, &provider, &sync_config.block_importer, &fetch_config.retry_policy).await?;
+    }
+
+    let backfill_config = fetch_config.backfill.then(|| BackfillConfig {
+        chain_id: backend.chain_config().chain_id.clone(),
+        parallelism: fetch_config.sync_parallelism as usize,
+        strict_validation: fetch_config.strict_validation,
+        verify_signatures: fetch_config.verify_signatures,
+        compute_v0_13_2_hashes: fetch_config.compute_v0_13_2_hashes,
+        retry_policy: fetch_config.retry_policy,
+    });
+    let max_reorg_depth = reorg::max_reorg_depth(&backend, fetch_config.max_reorg_depth, fetch_config.verify)?;
+    let trie_catch_up_enabled = fetch_config.trie_catch_up;
+    let block_importer = Arc::clone(&sync_config.block_importer);
+    let metrics = Arc::new(SyncMetrics::register());
+    let history = Arc::new(SyncHistory::new(Arc::clone(&metrics)));
+    let progress = Arc::new(SyncProgress::new(starting_block.checked_sub(1)));
+
     let l2_config = L2SyncConfig {
         first_block: starting_block,
         n_blocks_to_sync: fetch_config.n_blocks_to_sync,
         stop_on_sync: fetch_config.stop_on_sync,
         verify: fetch_config.verify,
+        strict_validation: fetch_config.strict_validation,
+        verify_signatures: fetch_config.verify_signatures,
+        compute_v0_13_2_hashes: fetch_config.compute_v0_13_2_hashes,
+        retry_policy: fetch_config.retry_policy,
         sync_polling_interval: fetch_config.sync_polling_interval,
+        sync_push: fetch_config.sync_push,
         backup_every_n_blocks: sync_config.backup_every_n_blocks,
         flush_every_n_blocks: fetch_config.flush_every_n_blocks,
         flush_every_n_seconds: fetch_config.flush_every_n_seconds,
@@ -69,11 +170,56 @@ pub async fn l2_sync_worker(
         sync_parallelism: fetch_config.sync_parallelism,
         chain_id: backend.chain_config().chain_id.clone(),
         telemetry: sync_config.telemetry,
+        alerts: sync_config.alerts,
         block_importer: sync_config.block_importer,
         warp_update: fetch_config.warp_update,
+        stall_detection: fetch_config.stall_detection,
+        quarantine: fetch_config.quarantine,
+        max_reorg_depth,
+        checkpoints: fetch_config.checkpoints,
+        history: Arc::clone(&history),
+        progress: Arc::clone(&progress),
+        metrics: Arc::clone(&metrics),
+    };
+
+    // The blocks before the state snapshot are only history: a failed backfill does not stop the sync either.
+    let backfill = async {
+        let Some(config) = backfill_config else { return anyhow::Ok(()) };
+        if let Err(err) = backfill::backfill(&backend, &provider, &block_importer, config, ctx.clone()).await {
+            tracing::error!("❗ The backfill of the blocks before the state snapshot stopped: {err:#}");
+        }
+        anyhow::Ok(())
+    };
+    // The tries are only needed for storage proofs: a failed catch-up does not stop the sync.
+    let trie_catch_up = async {
+        if !trie_catch_up_enabled {
+            return anyhow::Ok(());
+        }
+        if let Err(err) = trie_catch_up::trie_catch_up(&backend, &block_importer, ctx.clone()).await {
+            tracing::error!("❗ The global trie catch-up stopped: {err:#}");
+        }
+        anyhow::Ok(())
+    };
+    let forward = async {
+        // The history and the status are only reporting: they run on their own tasks, which log their failures and
+        // never stop the sync. They are aborted when the set is dropped, once the sync returns.
+        let mut reporting = JoinSet::new();
+        reporting.spawn({
+            let (history, backend) = (Arc::clone(&history), Arc::clone(&backend));
+            async move { history.persist_periodically(&backend).await }
+        });
+        reporting.spawn({
+            let (progress, backend, provider, metrics) =
+                (Arc::clone(&progress), Arc::clone(&backend), provider.clone(), Arc::clone(&metrics));
+            async move { progress.publish_periodically(&backend, &provider, &metrics).await }
+        });
+        l2::sync(Arc::clone(&backend), provider.clone(), ctx.clone(), l2_config).await?;
+        drop(reporting);
+        // Keep the progress of the last, partial period.
+        history.persist(&backend)
     };
 
-    l2::sync(backend, provider, ctx, l2_config).await?;
+    tokio::try_join!(forward, backfill, trie_catch_up)?;
 
     Ok(())
 }
@@ -1 +1,2 @@
 pub mod block_metrics;
+pub mod sync_metrics;
@@ -0,0 +1,106 @@
+use mc_analytics::{
+    register_counter_metric_instrument, register_gauge_metric_instrument, register_histogram_metric_instrument,
+};
+use opentelemetry::metrics::{Counter, Gauge, Histogram};
+use opentelemetry::{global, KeyValue};
+
+/// Stages of the sync pipeline, as the `stage` attribute of the per-stage metrics.
+pub const STAGE_FETCH: &str = "fetch";
+pub const STAGE_CONVERSION: &str = "conversion";
+pub const STAGE_VERIFY_APPLY: &str = "verify_apply";
+
+#[derive(Debug)]
+pub struct SyncMetrics {
+    pub sync_stall_counter: Counter<u64>,
+    pub blocks_per_second: Gauge<f64>,
+    pub classes_per_second: Gauge<f64>,
+    pub eta_seconds: Gauge<f64>,
+    pub gateway_rate_limit_wait_seconds: Gauge<f64>,
+    pub pending_abandoned_counter: Counter<u64>,
+    /// Time spent on a block by each stage of the pipeline, with a `stage` attribute.
+    pub stage_duration_seconds: Histogram<f64>,
+    /// Blocks waiting for each stage of the pipeline, with a `stage` attribute.
+    pub stage_queue_depth: Gauge<u64>,
+}
+
+impl SyncMetrics {
+    pub fn register() -> Self {
+        let common_scope_attributes = vec![KeyValue::new("crate", "sync")];
+        let sync_meter = global::meter_with_version(
+            "crates.sync.opentelemetry",
+            Some("0.17"),
+            Some("https://opentelemetry.io/schemas/1.2.0"),
+            Some(common_scope_attributes.clone()),
+        );
+
+        let sync_stall_counter = register_counter_metric_instrument(
+            &sync_meter,
+            "l2_sync_stall_count".to_string(),
+            "A counter of the times the sync made no progress while behind the gateway".to_string(),
+            "stall".to_string(),
+        );
+
+        let blocks_per_second = register_gauge_metric_instrument(
+            &sync_meter,
+            "l2_sync_blocks_per_second".to_string(),
+            "Gauge for the number of blocks imported per second".to_string(),
+            "block/s".to_string(),
+        );
+
+        let classes_per_second = register_gauge_metric_instrument(
+            &sync_meter,
+            "l2_sync_classes_per_second".to_string(),
+            "Gauge for the number of classes imported per second".to_string(),
+            "class/s".to_string(),
+        );
+
+        let eta_seconds = register_gauge_metric_instrument(
+            &sync_meter,
+            "l2_sync_eta_seconds".to_string(),
+            "Gauge for the estimated time until the sync reaches the head of the gateway".to_string(),
+            "s".to_string(),
+        );
+
+        let gateway_rate_limit_wait_seconds = register_gauge_metric_instrument(
+            &sync_meter,
+            "l2_sync_gateway_rate_limit_wait_seconds".to_string(),
+            "Gauge for the time the requests to the gateway waited for `--gateway-max-rps` since the node started"
+                .to_string(),
+            "s".to_string(),
+        );
+
+        let pending_abandoned_counter = register_counter_metric_instrument(
+            &sync_meter,
+            "l2_sync_pending_abandoned_count".to_string(),
+            "A counter of the pending blocks dropped because their parent was not the latest block anymore".to_string(),
+            "block".to_string(),
+        );
+
+        let stage_duration_seconds = register_histogram_metric_instrument(
+            &sync_meter,
+            "l2_sync_stage_duration_seconds".to_string(),
+            "Histogram of the time spent on a block by each stage of the sync pipeline: the fetch and the conversion \
+             of the blocks run in parallel, and their verification and application to the database in sequence"
+                .to_string(),
+            "s".to_string(),
+        );
+
+        let stage_queue_depth = register_gauge_metric_instrument(
+            &sync_meter,
+            "l2_sync_stage_queue_depth".to_string(),
+            "Gauge for the number of blocks waiting for each stage of the sync pipeline".to_string(),
+            "block".to_string(),
+        );
+
+        Self {
+            sync_stall_counter,
+            blocks_per_second,
+            classes_per_second,
+            eta_seconds,
+            gateway_rate_limit_wait_seconds,
+            pending_abandoned_counter,
+            stage_duration_seconds,
+            stage_queue_depth,
+        }
+    }
+}
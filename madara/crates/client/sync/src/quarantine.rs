@@ -0,0 +1,121 @@
+//! Quarantine of the blocks which fail verification.
+//!
+//! Without this, a block failing verification stops the sync, and reporting the failure requires an operator to
+//! capture the block and the node configuration by hand. When a quarantine directory is configured, a diagnostics
+//! bundle is written there instead, and the sync pipeline is restarted from the latest block in the database.
+//!
+//! A bundle is a directory named after the block number and the time of the failure, containing:
+//! - `diagnostics.json`: the error, the computed and expected values when the failure is a mismatch, the node version
+//!   and the sync configuration.
+//! - `gateway_payload.json`: the block and state update served by the feeder gateway, fetched again after the failure.
+use crate::l2::L2SyncConfig;
+use mc_block_import::BlockImportError;
+use mc_db::MadaraBackend;
+use mc_gateway_client::GatewayProvider;
+use mp_block::BlockId;
+use serde_json::json;
+use starknet_types_core::felt::Felt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+#[derive(Clone, Debug)]
+pub struct QuarantineConfig {
+    /// Directory the diagnostics bundles are written to.
+    pub dir: PathBuf,
+    /// Delay before the sync pipeline is restarted after a verification failure.
+    pub retry_delay: Duration,
+}
+
+/// Returns the block import error if `err` is a verification failure, as opposed to an internal or provider error.
+pub(crate) fn verification_failure(err: &anyhow::Error) -> Option<&BlockImportError> {
+    err.chain().find_map(|err| err.downcast_ref::<BlockImportError>()).filter(|err| !err.is_internal())
+}
+
+/// Name, computed value and expected value of the mismatch behind a verification failure.
+fn mismatch(err: &BlockImportError) -> Option<(&'static str, Felt, Felt)> {
+    let (name, got, expected) = match *err {
+        BlockImportError::TransactionHash { got, expected, .. } => ("transaction_hash", got, expected),
+        BlockImportError::TransactionCount { got, expected } => ("transaction_count", got.into(), expected.into()),
+        BlockImportError::TransactionCommitment { got, expected } => ("transaction_commitment", got, expected),
+        BlockImportError::EventCount { got, expected } => ("event_count", got.into(), expected.into()),
+        BlockImportError::EventCommitment { got, expected } => ("event_commitment", got, expected),
+        BlockImportError::StateDiffLength { got, expected } => ("state_diff_length", got.into(), expected.into()),
+        BlockImportError::StateDiffCommitment { got, expected } => ("state_diff_commitment", got, expected),
+        BlockImportError::ReceiptCommitment { got, expected } => ("receipt_commitment", got, expected),
+        BlockImportError::ClassHash { got, expected } => ("class_hash", got, expected),
+        BlockImportError::CompiledClassHash { got, expected, .. } => ("compiled_class_hash", got, expected),
+        BlockImportError::DeclareCompiledClassHash { got, expected, .. } => {
+            ("declare_compiled_class_hash", got, expected)
+        }
+        BlockImportError::BlockHash { got, expected } => ("block_hash", got, expected),
+        BlockImportError::ParentHash { got, expected } => ("parent_hash", got, expected),
+        BlockImportError::GlobalStateRoot { got, expected } => ("global_state_root", got, expected),
+        _ => return None,
+    };
+    Some((name, got, expected))
+}
+
+/// Writes the diagnostics bundle of a block which failed verification, and returns its path.
+pub(crate) async fn write_bundle(
+    dir: &Path,
+    block_n: u64,
+    err: &BlockImportError,
+    backend: &MadaraBackend,
+    provider: &GatewayProvider,
+    config: &L2SyncConfig,
+) -> anyhow::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let bundle_dir = dir.join(format!("block-{block_n}-{timestamp}"));
+    tokio::fs::create_dir_all(&bundle_dir).await?;
+
+    let chain_config = backend.chain_config();
+    let diagnostics = json!({
+        "block_number": block_n,
+        "timestamp": timestamp,
+        "error": format!("{err:#}"),
+        "mismatch": mismatch(err).map(|(name, got, expected)| json!({
+            "name": name,
+            "computed": format!("{got:#x}"),
+            "expected": format!("{expected:#x}"),
+        })),
+        "node_version": env!("CARGO_PKG_VERSION"),
+        "config": {
+            "chain_name": chain_config.chain_name,
+            "chain_id": chain_config.chain_id.to_string(),
+            "latest_protocol_version": chain_config.latest_protocol_version.to_string(),
+            "verify": config.verify,
+            "strict_validation": config.strict_validation,
+            "compute_v0_13_2_hashes": config.compute_v0_13_2_hashes,
+            "ignore_block_order": config.ignore_block_order,
+            "sync_parallelism": config.sync_parallelism,
+            "warp_update": config.warp_update.is_some(),
+        },
+    });
+    tokio::fs::write(bundle_dir.join("diagnostics.json"), serde_json::to_vec_pretty(&diagnostics)?).await?;
+
+    // The payload is fetched again: the pipeline does not keep the raw blocks around once they are converted.
+    let payload = match provider.get_state_update_with_block(BlockId::Number(block_n)).await {
+        Ok(payload) => serde_json::to_value(payload)?,
+        Err(err) => json!({ "error": format!("Could not fetch the block from the feeder gateway: {err:#}") }),
+    };
+    tokio::fs::write(bundle_dir.join("gateway_payload.json"), serde_json::to_vec_pretty(&payload)?).await?;
+
+    Ok(bundle_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verification_failure() {
+        let err = anyhow::Error::from(BlockImportError::GlobalStateRoot { got: Felt::ONE, expected: Felt::TWO })
+            .context("Importing block");
+        let failure = verification_failure(&err).expect("Should be a verification failure");
+        assert_eq!(mismatch(failure), Some(("global_state_root", Felt::ONE, Felt::TWO)));
+
+        let err = anyhow::Error::from(BlockImportError::Internal("oops".into()));
+        assert!(verification_failure(&err).is_none());
+        assert!(verification_failure(&anyhow::anyhow!("Some other error")).is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! Recovery from the reorgs of the chain being synced.
+//!
+//! The sync assumes the chain only moves forward. When the feeder gateway serves a block whose parent is not the latest
+//! block in the database, the chain was reorganized: the sync walks back to the latest block both chains have in
+//! common, reverts the database to it and resumes from the block after it. The global tries are reverted with their trie
+//! logs, so the depth of the reorgs the sync recovers from is bounded by `--db-max-saved-trie-logs`.
+//!
+//! The new chain usually declares again most of the classes of the reverted blocks. These are kept as
+//! [`RecoveryClasses`] so that they are not downloaded again, and the classes which still have to be downloaded for the
+//! reverted heights are downloaded one request at a time, so that the recovery does not stampede the feeder gateway.
+
+use crate::quarantine;
+use anyhow::Context;
+use mc_block_import::BlockImportError;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mc_gateway_client::GatewayProvider;
+use mp_block::BlockId;
+use mp_class::{ClassInfo, ContractClass};
+use starknet_types_core::felt::Felt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::time::{Duration, Instant};
+
+/// Minimum time between two class downloads for the blocks at the heights reverted by a reorg.
+const RECOVERY_CLASS_DOWNLOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maximum number of blocks reverted to recover from a reorg, when the trie logs go back as far.
+pub const DEFAULT_MAX_REORG_DEPTH: u64 = 64;
+
+/// Checks the maximum depth of the reorgs the sync recovers from against the trie logs, which the global tries are
+/// reverted with. When `max_reorg_depth` is not set, this is [`DEFAULT_MAX_REORG_DEPTH`] or as far as the trie logs go
+/// back. The tries are not reverted when the blocks are imported without updating them (`verify` is false).
+pub(crate) fn max_reorg_depth(
+    backend: &MadaraBackend,
+    max_reorg_depth: Option<u64>,
+    verify: bool,
+) -> anyhow::Result<u64> {
+    let max_saved_trie_logs = if verify { backend.max_saved_trie_logs() as u64 } else { u64::MAX };
+    match max_reorg_depth {
+        Some(depth) if depth > max_saved_trie_logs => anyhow::bail!(
+            "--sync-max-reorg-depth is {depth} blocks, but only {max_saved_trie_logs} trie logs are saved: increase \
+             --db-max-saved-trie-logs, or lower --sync-max-reorg-depth"
+        ),
+        Some(depth) => Ok(depth),
+        None => Ok(DEFAULT_MAX_REORG_DEPTH.min(max_saved_trie_logs)),
+    }
+}
+
+/// Whether `err` is a block whose parent is not the latest block in the database.
+pub(crate) fn is_reorg(err: &anyhow::Error) -> bool {
+    matches!(quarantine::verification_failure(err), Some(BlockImportError::ParentHash { .. }))
+}
+
+/// Finds the latest block the database has in common with the feeder gateway, at most `max_depth` blocks below the
+/// latest block in the database.
+pub(crate) async fn find_common_ancestor(
+    backend: &MadaraBackend,
+    provider: &GatewayProvider,
+    max_depth: u64,
+) -> anyhow::Result<Option<u64>> {
+    let Some(latest_block_n) = backend.get_latest_block_n().context("Getting latest block_n")? else {
+        return Ok(None);
+    };
+    for block_n in (latest_block_n.saturating_sub(max_depth)..=latest_block_n).rev() {
+        let ours = backend
+            .get_block_hash(&BlockId::Number(block_n))
+            .context("Getting block hash")?
+            .with_context(|| format!("Block #{block_n} not found in database"))?;
+        let theirs = provider
+            .get_block(BlockId::Number(block_n))
+            .await
+            .with_context(|| format!("Getting block #{block_n} from the feeder gateway"))?
+            .non_pending_owned()
+            .with_context(|| format!("Feeder gateway returned a pending block for block #{block_n}"))?
+            .block_hash;
+        if ours == theirs {
+            return Ok(Some(block_n));
+        }
+    }
+    Ok(None)
+}
+
+/// Reverts the database to the latest block it has in common with the feeder gateway. Returns that block, and the
+/// classes declared in the reverted blocks.
+///
+/// Returns `None` when the latest block in the database is still on the chain of the feeder gateway: the parent of the
+/// block it served did not match its own previous block, which happens while a reorg propagates between the replicas
+/// of the feeder gateway. Nothing is reverted then, and the block should be fetched again later.
+pub(crate) async fn revert_to_common_ancestor(
+    backend: &Arc<MadaraBackend>,
+    provider: &GatewayProvider,
+    max_depth: u64,
+) -> anyhow::Result<Option<(u64, RecoveryClasses)>> {
+    let ancestor = find_common_ancestor(backend, provider, max_depth)
+        .await?
+        .with_context(|| format!("No common block with the feeder gateway in the last {max_depth} blocks"))?;
+    if backend.get_latest_block_n().context("Getting latest block_n")? == Some(ancestor) {
+        return Ok(None);
+    }
+
+    let backend = Arc::clone(backend);
+    let recovery = tokio::task::spawn_blocking(move || {
+        let latest_block_n = backend.get_latest_block_n().context("Getting latest block_n")?.unwrap_or(ancestor);
+        let recovery = RecoveryClasses::collect(&backend, ancestor + 1, latest_block_n)?;
+        backend.revert_to(ancestor).with_context(|| format!("Reverting the database to block #{ancestor}"))?;
+        anyhow::Ok(recovery)
+    })
+    .await
+    .context("Revert task was dropped")??;
+    Ok(Some((ancestor, recovery)))
+}
+
+/// Classes declared in the blocks reverted by a reorg, reused when the new chain declares them again.
+pub struct RecoveryClasses {
+    /// Latest block before the reorg. The classes of the blocks up to it are downloaded one request at a time.
+    pub(crate) until_block_n: u64,
+    classes: Mutex<HashMap<Felt, ClassInfo>>,
+    /// Earliest time of the next class download for the reverted heights.
+    next_download: tokio::sync::Mutex<Instant>,
+}
+
+impl RecoveryClasses {
+    /// Keeps the classes declared in blocks `first_block_n..=last_block_n`, before they are reverted.
+    fn collect(backend: &MadaraBackend, first_block_n: u64, last_block_n: u64) -> anyhow::Result<Self> {
+        let mut classes = HashMap::new();
+        for block_n in first_block_n..=last_block_n {
+            let block_id = DbBlockId::Number(block_n);
+            let state_diff = backend
+                .get_block_state_diff(&block_id)
+                .context("Getting state diff")?
+                .with_context(|| format!("State diff of block #{block_n} not found in database"))?;
+            let declared = state_diff
+                .deprecated_declared_classes
+                .into_iter()
+                .chain(state_diff.declared_classes.into_iter().map(|item| item.class_hash));
+            for class_hash in declared {
+                if let Some(class_info) =
+                    backend.get_class_info(&block_id, &class_hash).context("Getting class info")?
+                {
+                    classes.insert(class_hash, class_info);
+                }
+            }
+        }
+        Ok(Self::new(last_block_n, classes))
+    }
+
+    fn new(until_block_n: u64, classes: HashMap<Felt, ClassInfo>) -> Self {
+        Self { until_block_n, classes: Mutex::new(classes), next_download: tokio::sync::Mutex::new(Instant::now()) }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.classes.lock().expect("Poisoned lock").len()
+    }
+
+    /// Takes the class `class_hash` of a reverted block, if it was declared with the same compiled class hash. Legacy
+    /// classes have no compiled class hash: their class hash alone commits to their definition.
+    pub(crate) fn take(&self, class_hash: &Felt, compiled_class_hash: Option<&Felt>) -> Option<ContractClass> {
+        let mut classes = self.classes.lock().expect("Poisoned lock");
+        if classes.get(class_hash)?.compiled_class_hash().as_ref() != compiled_class_hash {
+            return None;
+        }
+        match classes.remove(class_hash)? {
+            ClassInfo::Sierra(info) => Some(ContractClass::Sierra(info.contract_class)),
+            ClassInfo::Legacy(info) => Some(ContractClass::Legacy(info.contract_class)),
+        }
+    }
+
+    /// Waits before downloading the classes of block `block_n`. The downloads for the reverted heights are spaced by
+    /// [`RECOVERY_CLASS_DOWNLOAD_INTERVAL`], and wait in turn for the previous ones. The blocks are fetched in order,
+    /// so the lowest blocks, which the import waits for first, download their classes first.
+    pub(crate) async fn throttle(&self, block_n: u64) {
+        if block_n > self.until_block_n {
+            return;
+        }
+        let mut next_download = self.next_download.lock().await;
+        tokio::time::sleep_until(*next_download).await;
+        *next_download = Instant::now() + RECOVERY_CLASS_DOWNLOAD_INTERVAL;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use mp_class::{EntryPointsByType, FlattenedSierraClass, SierraClassInfo};
+
+    #[test]
+    fn test_is_reorg() {
+        let err = anyhow::Error::from(BlockImportError::ParentHash { got: Felt::ONE, expected: Felt::TWO })
+            .context("Importing block");
+        assert!(is_reorg(&err));
+
+        let err = anyhow::Error::from(BlockImportError::GlobalStateRoot { got: Felt::ONE, expected: Felt::TWO });
+        assert!(!is_reorg(&err));
+    }
+
+    #[test]
+    fn test_max_reorg_depth() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        // No trie log is saved by default.
+        assert_eq!(max_reorg_depth(&backend, None, true).unwrap(), 0);
+        assert_eq!(max_reorg_depth(&backend, Some(0), true).unwrap(), 0);
+        assert!(max_reorg_depth(&backend, Some(1), true).is_err());
+        // The tries are not reverted when the blocks are imported without updating them.
+        assert_eq!(max_reorg_depth(&backend, None, false).unwrap(), DEFAULT_MAX_REORG_DEPTH);
+        assert_eq!(max_reorg_depth(&backend, Some(100), false).unwrap(), 100);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_recovery_classes() {
+        let sierra = ClassInfo::Sierra(SierraClassInfo {
+            contract_class: Arc::new(FlattenedSierraClass {
+                sierra_program: vec![],
+                contract_class_version: "0.1.0".into(),
+                entry_points_by_type: EntryPointsByType { constructor: vec![], external: vec![], l1_handler: vec![] },
+                abi: String::new(),
+            }),
+            compiled_class_hash: Felt::TWO,
+        });
+        let recovery = RecoveryClasses::new(10, [(Felt::ONE, sierra)].into());
+        assert_eq!(recovery.len(), 1);
+
+        // The compiled class hash declared by the new chain differs: the class has to be downloaded.
+        assert!(recovery.take(&Felt::ONE, Some(&Felt::THREE)).is_none());
+        assert!(matches!(recovery.take(&Felt::ONE, Some(&Felt::TWO)), Some(ContractClass::Sierra(_))));
+        assert!(recovery.take(&Felt::ONE, Some(&Felt::TWO)).is_none());
+        assert_eq!(recovery.len(), 0);
+
+        let started = Instant::now();
+        recovery.throttle(5).await;
+        recovery.throttle(6).await;
+        assert_eq!(started.elapsed(), RECOVERY_CLASS_DOWNLOAD_INTERVAL);
+        // Blocks past the reorg are not throttled.
+        recovery.throttle(11).await;
+        assert_eq!(started.elapsed(), RECOVERY_CLASS_DOWNLOAD_INTERVAL);
+    }
+}
@@ -0,0 +1,107 @@
+//! Checkpoint sync from a trusted state snapshot.
+//!
+//! A fresh node normally replays every block from genesis. When a snapshot is configured, the node instead bootstraps
+//! from the state of the chain at some block, published by a node it trusts, and the L2 sync starts from the block after
+//! it. The snapshot is only trusted through the hash of its block, given by the operator: the header must hash to it,
+//! and the global tries are rebuilt from the snapshot state and must match the state root of the header.
+//!
+//! The snapshot is the JSON serialization of an [`UnverifiedStateSnapshot`]. Blocks older than the snapshot are not
+//! available until they are backfilled, see [`crate::backfill`], and the snapshot block itself is stored without its
+//! transactions, and with an empty state diff.
+
+use anyhow::Context;
+use mc_block_import::{BlockImporter, BlockValidationContext, UnverifiedStateSnapshot};
+use mc_db::MadaraBackend;
+use starknet_types_core::felt::Felt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use url::Url;
+
+#[derive(Clone, Debug)]
+pub struct SnapshotConfig {
+    /// Where the snapshot is downloaded from. `file://` urls are read from the local filesystem.
+    pub url: Url,
+    /// Hash of the snapshot block.
+    pub trusted_block_hash: Felt,
+}
+
+/// Imports the configured snapshot, unless the database already has blocks.
+pub(crate) async fn import_snapshot(
+    backend: &MadaraBackend,
+    block_importer: &BlockImporter,
+    config: &SnapshotConfig,
+) -> anyhow::Result<()> {
+    if let Some(block_n) = backend.get_latest_block_n().context("Getting latest block in database")? {
+        tracing::info!("📸 Database already has blocks up to #{block_n}, not importing the state snapshot");
+        return Ok(());
+    }
+
+    tracing::info!("📸 Downloading state snapshot from {}", config.url);
+    let url = config.url.clone();
+    let snapshot = tokio::task::spawn_blocking(move || read_snapshot(&url))
+        .await
+        .context("Joining the state snapshot download")?
+        .with_context(|| format!("Fetching state snapshot {}", config.url))?;
+
+    let block_n = snapshot.header.block_number;
+    tracing::info!("📸 Importing state snapshot at block #{block_n}");
+    let validation = BlockValidationContext::new(backend.chain_config().chain_id.clone());
+    block_importer
+        .import_state_snapshot(snapshot, config.trusted_block_hash, validation)
+        .await
+        .with_context(|| format!("Importing state snapshot at block #{block_n}"))?;
+    tracing::info!("📸 Imported state snapshot at block #{block_n} with hash {:#x}", config.trusted_block_hash);
+
+    Ok(())
+}
+
+/// Deserializes the snapshot as it is read, so that the whole file is never held in memory next to the state it
+/// decodes to.
+fn read_snapshot(url: &Url) -> anyhow::Result<UnverifiedStateSnapshot> {
+    let reader: Box<dyn Read> = if url.scheme() == "file" {
+        let path = url.to_file_path().map_err(|_| anyhow::anyhow!("Invalid file url"))?;
+        Box::new(File::open(path)?)
+    } else {
+        Box::new(reqwest::blocking::get(url.clone())?.error_for_status()?)
+    };
+    serde_json::from_reader(BufReader::new(reader)).context("Deserializing the state snapshot")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_block_import::tests::block_import_utils::create_dummy_header;
+    use mp_chain_config::ChainConfig;
+    use mp_convert::ToFelt;
+    use mp_state_update::StateDiff;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_import_snapshot() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let block_importer = BlockImporter::new(Arc::clone(&backend), None).unwrap();
+
+        let mut header = create_dummy_header();
+        header.block_number = 5;
+        header.global_state_root = Felt::ZERO;
+        let trusted_block_hash = header.compute_hash(backend.chain_config().chain_id.to_felt(), false);
+        let snapshot = UnverifiedStateSnapshot { header, state_diff: StateDiff::default(), declared_classes: vec![] };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot.json");
+        std::fs::write(&path, serde_json::to_vec(&snapshot).unwrap()).unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let config = SnapshotConfig { url: url.clone(), trusted_block_hash: Felt::ONE };
+        assert!(import_snapshot(&backend, &block_importer, &config).await.is_err());
+        assert_eq!(backend.get_latest_block_n().unwrap(), None);
+
+        let config = SnapshotConfig { url, trusted_block_hash };
+        import_snapshot(&backend, &block_importer, &config).await.unwrap();
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(5));
+
+        // Restarting the node does not import the snapshot again.
+        import_snapshot(&backend, &block_importer, &config).await.unwrap();
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(5));
+    }
+}
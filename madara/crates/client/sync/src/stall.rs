@@ -0,0 +1,124 @@
+//! Detection of a wedged sync pipeline.
+//!
+//! The sync is stalled when no new block has been imported for a while, even though the feeder gateway has blocks we
+//! do not have yet. Without this, a stuck fetch or import would go unnoticed until an operator looks at the chain
+//! head. A sync which the operator paused, or which reached its sync target, is waiting on purpose and is not
+//! stalled, see [`mc_db::sync_control`].
+use mc_db::MadaraBackend;
+use mc_gateway_client::GatewayProvider;
+use mp_block::{BlockId, BlockTag};
+use std::time::{Duration, Instant};
+
+/// Minimum delay between two checks of the chain head.
+const MIN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct StallDetectionConfig {
+    /// The sync is stalled when the chain head has not moved for this long while the gateway is ahead of us.
+    pub timeout: Duration,
+    /// Restart the sync pipeline when it is stalled, instead of only reporting it.
+    pub restart: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SyncStall {
+    /// Latest block in the database.
+    pub head: Option<u64>,
+    /// Latest block on the feeder gateway.
+    pub target: u64,
+    /// Time since the last imported block.
+    pub since: Duration,
+}
+
+/// Resolves once the sync is stalled. This never resolves for as long as new blocks are being imported, when we are
+/// caught up with the gateway, or when the sync control does not allow the next block.
+pub(crate) async fn wait_for_stall(
+    backend: &MadaraBackend,
+    provider: &GatewayProvider,
+    config: &StallDetectionConfig,
+) -> SyncStall {
+    let mut interval = tokio::time::interval((config.timeout / 4).max(MIN_CHECK_INTERVAL));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    let mut head = None;
+    let mut last_progress = Instant::now();
+    loop {
+        interval.tick().await;
+
+        let current_head = match backend.get_latest_block_n() {
+            Ok(current_head) => current_head,
+            Err(err) => {
+                tracing::debug!("Stall detection: failed to get the latest block: {err:#}");
+                continue;
+            }
+        };
+        let next_block_n = current_head.map_or(0, |head| head + 1);
+        if current_head != head || !backend.get_sync_control().allows(next_block_n) {
+            head = current_head;
+            last_progress = Instant::now();
+            continue;
+        }
+
+        let since = last_progress.elapsed();
+        if since < config.timeout {
+            continue;
+        }
+
+        let target = match provider.get_block_header(BlockId::Tag(BlockTag::Latest)).await {
+            Ok(header) => header.block_number,
+            Err(err) => {
+                tracing::warn!("Stall detection: failed to get the latest block from the gateway: {err:#}");
+                continue;
+            }
+        };
+        if head.map_or(true, |head| head < target) {
+            return SyncStall { head, target, since };
+        }
+        // Caught up with the gateway, check again after another timeout.
+        last_progress = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::utils::gateway::{test_setup, TestContext};
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_wait_for_stall(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+        ctx.mock_block_latest(5);
+
+        let config = StallDetectionConfig { timeout: Duration::from_millis(100), restart: false };
+        let stall = tokio::time::timeout(Duration::from_secs(10), wait_for_stall(&ctx.backend, &ctx.provider, &config))
+            .await
+            .expect("Stall should be detected");
+
+        assert_eq!(stall.head, None);
+        assert_eq!(stall.target, 5);
+        assert!(stall.since >= config.timeout);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_no_stall_when_sync_is_held(test_setup: Arc<MadaraBackend>) {
+        let ctx = TestContext::new(test_setup);
+        ctx.mock_block_latest(5);
+        let config = StallDetectionConfig { timeout: Duration::from_millis(100), restart: false };
+
+        ctx.backend.pause_sync();
+        assert!(tokio::time::timeout(Duration::from_secs(2), wait_for_stall(&ctx.backend, &ctx.provider, &config))
+            .await
+            .is_err());
+
+        // Block #0 is the last block the sync may fetch.
+        ctx.backend.resume_sync();
+        ctx.backend.set_sync_target(Some(0));
+        assert!(tokio::time::timeout(Duration::from_secs(2), wait_for_stall(&ctx.backend, &ctx.provider, &config))
+            .await
+            .is_ok());
+    }
+}
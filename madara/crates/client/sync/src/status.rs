@@ -0,0 +1,295 @@
+//! Sync throughput and estimated time to the head of the chain.
+//!
+//! The block import records the blocks and classes it imports, and the throughput is periodically averaged and
+//! published to the backend as a [`SyncStatus`] along with the latest block of the feeder gateway. It is served by the
+//! `madara_syncStatus` admin RPC method, and reported in the logs and the metrics while the sync is catching up.
+
+use crate::metrics::sync_metrics::SyncMetrics;
+use anyhow::Context;
+use mc_db::sync_status::SyncStatus;
+use mc_db::MadaraBackend;
+use mc_gateway_client::GatewayProvider;
+use mp_block::{BlockId, BlockTag};
+use starknet_types_core::felt::Felt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Delay between two measurements of the throughput.
+pub const SYNC_STATUS_PERIOD: Duration = Duration::from_secs(30);
+/// Weight of the latest period in the averaged throughput. The average covers the last few minutes, so that the
+/// estimated time to the head does not jump around with the size of the blocks.
+const SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Throughput {
+    blocks_per_second: f64,
+    classes_per_second: f64,
+}
+
+/// Blocks and classes imported since the last measurement, and the averaged throughput.
+#[derive(Debug)]
+pub struct SyncProgress {
+    /// Latest block in the database when the sync started.
+    starting_block_n: Option<u64>,
+    period_start: Mutex<Instant>,
+    blocks: AtomicU64,
+    classes: AtomicU64,
+    /// `None` until the end of the first period.
+    throughput: Mutex<Option<Throughput>>,
+}
+
+impl Default for SyncProgress {
+    fn default() -> Self {
+        Self {
+            starting_block_n: None,
+            period_start: Mutex::new(Instant::now()),
+            blocks: AtomicU64::new(0),
+            classes: AtomicU64::new(0),
+            throughput: Mutex::new(None),
+        }
+    }
+}
+
+impl SyncProgress {
+    pub fn new(starting_block_n: Option<u64>) -> Self {
+        Self { starting_block_n, ..Default::default() }
+    }
+
+    /// Records a block which was imported, with the number of classes it declares.
+    pub fn record_block(&self, n_classes: usize) {
+        self.blocks.fetch_add(1, Ordering::Relaxed);
+        self.classes.fetch_add(n_classes as u64, Ordering::Relaxed);
+    }
+
+    /// Ends the current period and returns the throughput averaged with the previous periods.
+    fn measure(&self) -> Throughput {
+        let period_secs = {
+            let mut period_start = self.period_start.lock().expect("Poisoned lock");
+            let now = Instant::now();
+            let period = now.duration_since(*period_start);
+            *period_start = now;
+            period.as_secs_f64()
+        };
+        let rate = |count: &AtomicU64| {
+            let count = count.swap(0, Ordering::Relaxed) as f64;
+            if period_secs > 0.0 {
+                count / period_secs
+            } else {
+                0.0
+            }
+        };
+        let latest = Throughput { blocks_per_second: rate(&self.blocks), classes_per_second: rate(&self.classes) };
+
+        let mut throughput = self.throughput.lock().expect("Poisoned lock");
+        let averaged = match *throughput {
+            Some(previous) => Throughput {
+                blocks_per_second: SMOOTHING * latest.blocks_per_second
+                    + (1.0 - SMOOTHING) * previous.blocks_per_second,
+                classes_per_second: SMOOTHING * latest.classes_per_second
+                    + (1.0 - SMOOTHING) * previous.classes_per_second,
+            },
+            None => latest,
+        };
+        *throughput = Some(averaged);
+        averaged
+    }
+
+    /// Measures the throughput and builds the status of the sync.
+    fn status(&self, timestamp: u64, latest_block_n: Option<u64>, highest_block: Option<(u64, Felt)>) -> SyncStatus {
+        let Throughput { blocks_per_second, classes_per_second } = self.measure();
+        let highest_block_n = highest_block.map(|(block_n, _)| block_n);
+        SyncStatus {
+            timestamp,
+            starting_block_n: self.starting_block_n,
+            latest_block_n,
+            highest_block_n,
+            highest_block_hash: highest_block.map(|(_, block_hash)| block_hash),
+            blocks_per_second,
+            classes_per_second,
+            eta_secs: eta_secs(latest_block_n, highest_block_n, blocks_per_second),
+        }
+    }
+
+    /// Publishes the status of the sync right away, then every [`SYNC_STATUS_PERIOD`]. This never returns: a failure
+    /// to publish the status is logged, and the status is published again at the next period.
+    pub async fn publish_periodically(
+        &self,
+        backend: &MadaraBackend,
+        provider: &GatewayProvider,
+        metrics: &SyncMetrics,
+    ) {
+        // The throughput is only known at the end of the first period, but the latest block of the feeder gateway is
+        // published right away: `starknet_syncing` reports the sync as soon as it starts.
+        if let Err(err) = self.publish_initial(backend, provider).await {
+            tracing::warn!("⚠️ Failed to publish the sync status: {err:#}");
+        }
+        let mut interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + SYNC_STATUS_PERIOD, SYNC_STATUS_PERIOD);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(err) = self.publish(backend, provider, metrics).await {
+                tracing::warn!("⚠️ Failed to publish the sync status: {err:#}");
+            }
+        }
+    }
+
+    /// Publishes the status of the sync before any throughput was measured.
+    async fn publish_initial(&self, backend: &MadaraBackend, provider: &GatewayProvider) -> anyhow::Result<()> {
+        let highest_block = highest_block(provider).await;
+        let latest_block_n = backend.get_latest_block_n().context("Getting latest block_n")?;
+        backend.set_sync_status(self.initial_status(unix_timestamp(), latest_block_n, highest_block));
+        Ok(())
+    }
+
+    /// The status of the sync before any throughput was measured: the estimated time to the head is unknown.
+    fn initial_status(
+        &self,
+        timestamp: u64,
+        latest_block_n: Option<u64>,
+        highest_block: Option<(u64, Felt)>,
+    ) -> SyncStatus {
+        SyncStatus {
+            timestamp,
+            starting_block_n: self.starting_block_n,
+            latest_block_n,
+            highest_block_n: highest_block.map(|(block_n, _)| block_n),
+            highest_block_hash: highest_block.map(|(_, block_hash)| block_hash),
+            ..Default::default()
+        }
+    }
+
+    async fn publish(
+        &self,
+        backend: &MadaraBackend,
+        provider: &GatewayProvider,
+        metrics: &SyncMetrics,
+    ) -> anyhow::Result<()> {
+        let highest_block = highest_block(provider).await;
+        let latest_block_n = backend.get_latest_block_n().context("Getting latest block_n")?;
+
+        let status = self.status(unix_timestamp(), latest_block_n, highest_block);
+        metrics.blocks_per_second.record(status.blocks_per_second, &[]);
+        metrics.classes_per_second.record(status.classes_per_second, &[]);
+        if let Some(eta_secs) = status.eta_secs {
+            metrics.eta_seconds.record(eta_secs, &[]);
+        }
+        metrics.gateway_rate_limit_wait_seconds.record(provider.rate_limit_wait().as_secs_f64(), &[]);
+        if let (Some(highest_block_n), Some(eta_secs)) =
+            (status.highest_block_n, status.eta_secs.filter(|eta| *eta > 0.0))
+        {
+            tracing::info!(
+                "📊 Syncing block {}/#{highest_block_n} at {:.2} blocks/s and {:.2} classes/s, {} to the head",
+                latest_block_n.map(|n| format!("#{n}")).unwrap_or_else(|| "none".into()),
+                status.blocks_per_second,
+                status.classes_per_second,
+                format_eta(eta_secs),
+            );
+        }
+        backend.set_sync_status(status);
+        Ok(())
+    }
+}
+
+/// The number and hash of the latest block of the feeder gateway, when it can be fetched.
+async fn highest_block(provider: &GatewayProvider) -> Option<(u64, Felt)> {
+    match provider.get_block_header(BlockId::Tag(BlockTag::Latest)).await {
+        Ok(header) => Some((header.block_number, header.block_hash)),
+        Err(err) => {
+            tracing::debug!("Sync status: failed to get the latest block from the gateway: {err:#}");
+            None
+        }
+    }
+}
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// Estimated time for the latest block to reach `highest_block_n` at `blocks_per_second`, in seconds.
+fn eta_secs(latest_block_n: Option<u64>, highest_block_n: Option<u64>, blocks_per_second: f64) -> Option<f64> {
+    let highest_block_n = highest_block_n?;
+    let remaining = match latest_block_n {
+        Some(latest_block_n) => highest_block_n.saturating_sub(latest_block_n),
+        None => highest_block_n + 1,
+    };
+    if remaining == 0 {
+        Some(0.0)
+    } else if blocks_per_second > 0.0 {
+        Some(remaining as f64 / blocks_per_second)
+    } else {
+        None
+    }
+}
+
+fn format_eta(eta_secs: f64) -> String {
+    let secs = eta_secs.round() as u64;
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, _) => format!("{h}h{m:02}m"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta() {
+        assert_eq!(eta_secs(Some(100), Some(200), 10.0), Some(10.0));
+        assert_eq!(eta_secs(None, Some(9), 2.0), Some(5.0));
+        // Caught up, even when nothing is being imported.
+        assert_eq!(eta_secs(Some(200), Some(200), 0.0), Some(0.0));
+        assert_eq!(eta_secs(Some(100), Some(200), 0.0), None);
+        assert_eq!(eta_secs(Some(100), None, 10.0), None);
+
+        assert_eq!(format_eta(42.4), "42s");
+        assert_eq!(format_eta(125.0), "2m05s");
+        assert_eq!(format_eta(3.0 * 3600.0 + 7.0 * 60.0 + 30.0), "3h07m");
+    }
+
+    #[test]
+    fn test_status() {
+        let progress = SyncProgress::new(Some(8));
+        for _ in 0..10 {
+            progress.record_block(1);
+        }
+        *progress.period_start.lock().unwrap() = Instant::now() - Duration::from_secs(10);
+        let status = progress.status(1000, Some(9), Some((109, Felt::ONE)));
+        assert_eq!(status.timestamp, 1000);
+        assert_eq!(status.starting_block_n, Some(8));
+        assert_eq!(status.highest_block_hash, Some(Felt::ONE));
+        assert!((status.blocks_per_second - 1.0).abs() < 0.01, "{status:?}");
+        assert!((status.classes_per_second - 1.0).abs() < 0.01, "{status:?}");
+        assert!((status.eta_secs.unwrap() - 100.0).abs() < 1.0, "{status:?}");
+
+        // Nothing was imported during the next period: the average only falls by the smoothing factor.
+        *progress.period_start.lock().unwrap() = Instant::now() - Duration::from_secs(10);
+        let status = progress.status(1010, Some(9), Some((109, Felt::ONE)));
+        assert!((status.blocks_per_second - (1.0 - SMOOTHING)).abs() < 0.01, "{status:?}");
+    }
+
+    #[test]
+    fn test_initial_status() {
+        let progress = SyncProgress::new(Some(8));
+        progress.record_block(1);
+        let status = progress.initial_status(1000, Some(9), Some((109, Felt::ONE)));
+        assert_eq!(
+            status,
+            SyncStatus {
+                timestamp: 1000,
+                starting_block_n: Some(8),
+                latest_block_n: Some(9),
+                highest_block_n: Some(109),
+                highest_block_hash: Some(Felt::ONE),
+                blocks_per_second: 0.0,
+                classes_per_second: 0.0,
+                eta_secs: None,
+            }
+        );
+        // The first period is not averaged with the initial status.
+        assert_eq!(*progress.throughput.lock().unwrap(), None);
+    }
+}
@@ -0,0 +1,134 @@
+//! Golden tests of the block hash and commitments computed by the [`BlockImporter`].
+//!
+//! The blocks in `test-data` were fetched from the feeder gateway of their chain, see
+//! `fetch::fetchers_real_fgw_test`, and carry the block hash and global state root the gateway returned for them. The
+//! commitments computed by the importer are checked by hashing the block header with them: the block hash commits to
+//! the transactions and events, and from Starknet v0.13.2 onwards to the receipts and the state diff as well. When the
+//! hashing code changes for a new protocol version, a block of that version should be added here.
+//!
+//! Every mainnet block in `test-data` is checked. Only the genesis block and a v0.13.2.1 block are committed so far.
+//! The first blocks of v0.11, v0.12, v0.13.0 and v0.13.1, whose block hashes are computed differently, are written to
+//! `test-data` by the ignored `fetch::fetchers_real_fgw_test::fetch_golden_blocks` test.
+
+use mc_block_import::{BlockImporter, BlockValidationContext, UnverifiedFullBlock, ValidatedCommitments};
+use mc_db::MadaraBackend;
+use mp_block::Header;
+use mp_chain_config::{ChainConfig, StarknetVersion};
+use mp_convert::ToFelt;
+use starknet_api::core::ChainId;
+use std::path::Path;
+use std::sync::Arc;
+
+fn read_golden_block(block_n: u64) -> UnverifiedFullBlock {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("test-data/block_{block_n}.json"));
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// The numbers of the blocks in `test-data`.
+fn golden_blocks() -> Vec<u64> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data");
+    let mut blocks: Vec<u64> = std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|entry| {
+            let name = entry.unwrap().file_name().into_string().ok()?;
+            name.strip_prefix("block_")?.strip_suffix(".json")?.parse().ok()
+        })
+        .collect();
+    blocks.sort();
+    blocks
+}
+
+#[tokio::test]
+async fn test_golden_block_hashes() {
+    let blocks = golden_blocks();
+    assert!(blocks.contains(&0) && blocks.contains(&724_130), "{blocks:?}");
+    for block_n in blocks {
+        check_golden_block_hash(ChainId::Mainnet, block_n).await;
+    }
+}
+
+async fn check_golden_block_hash(chain_id: ChainId, block_n: u64) {
+    let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+    let block_importer = BlockImporter::new(backend, None).unwrap();
+    let block = read_golden_block(block_n);
+    let expected_block_hash = block.commitments.block_hash.unwrap();
+    let global_state_root = block.commitments.global_state_root.unwrap();
+    let parent_block_hash = block.header.parent_block_hash.unwrap();
+
+    // The transaction hashes are checked against the receipts, and the class hashes against the declared classes.
+    let validation = BlockValidationContext::new(chain_id.clone());
+    let block = block_importer.pre_validate(block, validation).await.unwrap();
+
+    let ValidatedCommitments {
+        transaction_count,
+        transaction_commitment,
+        event_count,
+        event_commitment,
+        state_diff_length,
+        state_diff_commitment,
+        receipt_commitment,
+    } = block.commitments;
+    let header = Header {
+        parent_block_hash,
+        block_number: block_n,
+        global_state_root,
+        sequencer_address: block.header.sequencer_address,
+        block_timestamp: block.header.block_timestamp,
+        transaction_count,
+        transaction_commitment,
+        event_count,
+        event_commitment,
+        state_diff_length: Some(state_diff_length),
+        state_diff_commitment: Some(state_diff_commitment),
+        receipt_commitment: Some(receipt_commitment),
+        protocol_version: block.header.protocol_version,
+        l1_gas_price: block.header.l1_gas_price,
+        l1_da_mode: block.header.l1_da_mode,
+    };
+    assert_eq!(
+        header.compute_hash(chain_id.to_felt(), false),
+        expected_block_hash,
+        "Block #{block_n} ({})",
+        header.protocol_version
+    );
+}
+
+/// With `--compute-v0-13-2-hashes`, the commitments of a block older than Starknet v0.13.2 are computed with the
+/// v0.13.2 scheme, which its recomputed block hash commits to: they are the commitments of the same block as a v0.13.2
+/// block.
+#[tokio::test]
+async fn test_golden_block_v0_13_2_hashes() {
+    let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+    let block_importer = BlockImporter::new(backend, None).unwrap();
+    let mut block = read_golden_block(0);
+    // The block files have the legacy commitments.
+    block.commitments.transaction_commitment = None;
+    block.commitments.event_commitment = None;
+    let validation = BlockValidationContext::new(ChainId::Mainnet);
+
+    let legacy = block_importer.pre_validate(block.clone(), validation.clone()).await.unwrap();
+    let recomputed =
+        block_importer.pre_validate(block.clone(), validation.clone().compute_v0_13_2_hashes(true)).await.unwrap();
+    // The transaction hashes of a v0.13.2 block are computed differently: the ones of the receipts are kept.
+    block.header.protocol_version = StarknetVersion::V0_13_2;
+    let v0_13_2 = block_importer.pre_validate(block, validation.trust_transaction_hashes(true)).await.unwrap();
+
+    assert_eq!(recomputed.commitments, v0_13_2.commitments);
+    assert_ne!(recomputed.commitments.transaction_commitment, legacy.commitments.transaction_commitment);
+}
+
+/// The genesis block can be imported into an empty database, which also checks the global state root computed from its
+/// state diff.
+#[tokio::test]
+async fn test_golden_genesis_block_import() {
+    let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+    let block_importer = BlockImporter::new(Arc::clone(&backend), None).unwrap();
+    let block = read_golden_block(0);
+    let expected_block_hash = block.commitments.block_hash.unwrap();
+    let expected_global_state_root = block.commitments.global_state_root.unwrap();
+
+    let result = block_importer.add_block(block, BlockValidationContext::new(ChainId::Mainnet)).await.unwrap();
+    assert_eq!(result.block_hash, expected_block_hash);
+    assert_eq!(result.header.global_state_root, expected_global_state_root);
+    assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+}
@@ -1 +1,2 @@
+pub mod golden_blocks;
 pub mod utils;
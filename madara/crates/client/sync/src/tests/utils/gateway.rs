@@ -13,8 +13,8 @@ pub struct TestContext {
     pub mock_server: MockServer,
     pub provider: Arc<GatewayProvider>,
     pub backend: Arc<MadaraBackend>,
-    pub fetch_stream_sender: mpsc::Sender<UnverifiedFullBlock>,
-    pub fetch_stream_receiver: mpsc::Receiver<UnverifiedFullBlock>,
+    pub fetch_stream_sender: mpsc::Sender<(tracing::Span, UnverifiedFullBlock)>,
+    pub fetch_stream_receiver: mpsc::Receiver<(tracing::Span, UnverifiedFullBlock)>,
     pub once_caught_up_sender: oneshot::Sender<()>,
     pub once_caught_up_receiver: oneshot::Receiver<()>,
 }
@@ -153,6 +153,38 @@ impl TestContext {
         });
     }
 
+    pub fn mock_block_latest(&self, block_number: u64) {
+        self.mock_server.mock(|when, then| {
+            when.method("GET").path_contains("get_block").query_param("blockNumber", "latest");
+            then.status(200).header("content-type", "application/json").json_body(json!({
+                "block_hash": "0x541112d5d5937a66ff09425a0256e53ac5c4f554be7e24917fc21a71aa3cf32",
+                "parent_block_hash": "0x6dc4eb6311529b941e3963f477b1d13928b38dd4c6ec0206bfba73c8a87198d",
+                "block_number": block_number,
+                "state_root": "0x704b7fe29fa070cf3737173acd1d0790fe318f68cc07a49ddfa9c1cd94c804f",
+                "transaction_commitment": "0x4ff55c4b2d1784ba40da993ab03e0476c6466431681112000dca0eb6d7a29ae",
+                "event_commitment": "0x51f9c6962c8f93324ccf0b97a817f2e8ffbdd9c164d362bd1ea078c203677f4",
+                "receipt_commitment": "0x75b61baea9980d332a14fa78042e51b734f12bb69227ac2bd3acff9fbab0200",
+                "state_diff_commitment": "0x34e002b2f6c8723d62433f34716f5e6c0627b2981959bd76cfe0a1416c5900b",
+                "state_diff_length": 43,
+                "status": "ACCEPTED_ON_L2",
+                "l1_da_mode": "CALLDATA",
+                "l1_gas_price": {
+                    "price_in_wei": "0x3bf1322e5",
+                    "price_in_fri": "0x55dfe7f2de82"
+                },
+                "l1_data_gas_price": {
+                    "price_in_wei": "0x3f9ffec0e7",
+                    "price_in_fri": "0x5b269552db6fa"
+                },
+                "transactions": [],
+                "timestamp": 1725974819,
+                "sequencer_address": "0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8",
+                "transaction_receipts": [],
+                "starknet_version": "0.13.2.1"
+            }));
+        });
+    }
+
     pub fn mock_block_pending(&self) {
         self.mock_server.mock(|when, then| {
             when.method("GET").path_contains("get_state_update").query_param("blockNumber", "pending");
@@ -214,18 +246,7 @@ impl TestContext {
     }
 
     pub fn mock_class_hash(&self, contract_file: &[u8]) {
-        let json: Value = serde_json::from_slice(contract_file).expect("Failed to parse JSON");
-
-        // Convert ABI to string
-        let abi_string = serde_json::to_string(&json["abi"]).expect("Failed to serialize ABI");
-
-        // Transform the JSON to match the expected API response format
-        let api_response = json!({
-            "contract_class_version": json["contract_class_version"],
-            "sierra_program": json["sierra_program"],
-            "entry_points_by_type": json["entry_points_by_type"],
-            "abi": abi_string,
-        });
+        let api_response = sierra_class_response(contract_file);
 
         self.mock_server.mock(|when, then| {
             when.method("GET").path_contains("get_class_by_hash");
@@ -233,6 +254,16 @@ impl TestContext {
         });
     }
 
+    /// Mocks the batched classes endpoint, answering `n_classes` times the same class.
+    pub fn mock_classes_by_hash(&self, contract_file: &[u8], n_classes: usize) -> httpmock::Mock<'_> {
+        let api_response = Value::Array(vec![sierra_class_response(contract_file); n_classes]);
+
+        self.mock_server.mock(|when, then| {
+            when.method("GET").path_contains("get_classes_by_hash");
+            then.status(200).header("content-type", "application/json").json_body(api_response);
+        })
+    }
+
     pub fn mock_signature(&self) {
         self.mock_server.mock(|when, then| {
             when.method("GET").path_contains("get_signature");
@@ -266,6 +297,99 @@ impl TestContext {
         });
     }
 
+    /// A v0.13.2.1 block without its receipt and state diff commitments, which are part of its block hash.
+    pub fn mock_block_missing_commitments(&self, block_number: u64) {
+        self.mock_server.mock(|when, then| {
+            when.method("GET").path_contains("get_state_update").query_param("blockNumber", block_number.to_string());
+            then.status(200).header("content-type", "application/json").json_body(json!({
+                "block": {
+                    "block_hash": "0x541112d5d5937a66ff09425a0256e53ac5c4f554be7e24917fc21a71aa3cf32",
+                    "parent_block_hash": "0x6dc4eb6311529b941e3963f477b1d13928b38dd4c6ec0206bfba73c8a87198d",
+                    "block_number": block_number,
+                    "state_root": "0x704b7fe29fa070cf3737173acd1d0790fe318f68cc07a49ddfa9c1cd94c804f",
+                    "transaction_commitment": "0x4ff55c4b2d1784ba40da993ab03e0476c6466431681112000dca0eb6d7a29ae",
+                    "event_commitment": "0x51f9c6962c8f93324ccf0b97a817f2e8ffbdd9c164d362bd1ea078c203677f4",
+                    "status": "ACCEPTED_ON_L1",
+                    "l1_da_mode": "CALLDATA",
+                    "l1_gas_price": {
+                        "price_in_wei": "0x3bf1322e5",
+                        "price_in_fri": "0x55dfe7f2de82"
+                    },
+                    "l1_data_gas_price": {
+                        "price_in_wei": "0x3f9ffec0e7",
+                        "price_in_fri": "0x5b269552db6fa"
+                    },
+                    "transactions": [],
+                    "timestamp": 1725974819,
+                    "sequencer_address": "0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8",
+                    "transaction_receipts": [],
+                    "starknet_version": "0.13.2.1"
+                },
+                "state_update": {
+                    "block_hash": "0x541112d5d5937a66ff09425a0256e53ac5c4f554be7e24917fc21a71aa3cf32",
+                    "new_root": "0x704b7fe29fa070cf3737173acd1d0790fe318f68cc07a49ddfa9c1cd94c804f",
+                    "old_root": "0x6152bda357cb522337756c71bcab298d88c5d829a479ad8247b82b969912713",
+                    "state_diff": {
+                        "storage_diffs": {},
+                        "nonces": {},
+                        "deployed_contracts": [],
+                        "old_declared_contracts": [],
+                        "declared_classes": [],
+                        "replaced_classes": []
+                    }
+                }
+            }));
+        });
+    }
+
+    /// A pending block with a transaction but no receipt for it.
+    pub fn mock_block_pending_missing_receipt(&self) {
+        self.mock_server.mock(|when, then| {
+            when.method("GET").path_contains("get_state_update").query_param("blockNumber", "pending");
+            then.status(200).header("content-type", "application/json").json_body(json!({
+                "block": {
+                    "parent_block_hash": "0x1db054847816dbc0098c88915430c44da2c1e3f910fbcb454e14282baba0e75",
+                    "status": "PENDING",
+                    "l1_da_mode": "CALLDATA",
+                    "l1_gas_price": {
+                        "price_in_wei": "0x274287586",
+                        "price_in_fri": "0x363cc34e29f8"
+                    },
+                    "l1_data_gas_price": {
+                        "price_in_wei": "0x2bc1e42413",
+                        "price_in_fri": "0x3c735d85586c2"
+                    },
+                    "transactions": [
+                        {
+                            "transaction_hash": "0x30a541df2547ed9f94602c35daf61ce3a8e179ec75d26cbe34e0ec61f823695",
+                            "version": "0x0",
+                            "contract_address": "0x1",
+                            "entry_point_selector": "0x2",
+                            "nonce": "0x0",
+                            "calldata": [],
+                            "type": "L1_HANDLER"
+                        }
+                    ],
+                    "timestamp": 1725950824,
+                    "sequencer_address": "0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8",
+                    "transaction_receipts": [],
+                    "starknet_version": "0.13.2.1",
+                },
+                "state_update": {
+                    "old_root": "0x37817010d31db557217addb3b4357c2422c8d8de0290c3f6a867bbdc49c32a0",
+                    "state_diff": {
+                        "storage_diffs": {},
+                        "nonces": {},
+                        "deployed_contracts": [],
+                        "old_declared_contracts": [],
+                        "declared_classes": [],
+                        "replaced_classes": []
+                    }
+                }
+            }));
+        });
+    }
+
     pub fn mock_block_pending_not_found(&self) {
         self.mock_server.mock(|when, then| {
             when.method("GET").path_contains("get_state_update").query_param("blockNumber", "pending");
@@ -350,3 +474,18 @@ impl TestContext {
         });
     }
 }
+
+fn sierra_class_response(contract_file: &[u8]) -> Value {
+    let json: Value = serde_json::from_slice(contract_file).expect("Failed to parse JSON");
+
+    // Convert ABI to string
+    let abi_string = serde_json::to_string(&json["abi"]).expect("Failed to serialize ABI");
+
+    // Transform the JSON to match the expected API response format
+    json!({
+        "contract_class_version": json["contract_class_version"],
+        "sierra_program": json["sierra_program"],
+        "entry_points_by_type": json["entry_points_by_type"],
+        "abi": abi_string,
+    })
+}
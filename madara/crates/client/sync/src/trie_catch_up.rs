@@ -0,0 +1,57 @@
+//! Background catch-up of the global tries.
+//!
+//! Blocks imported with `--disable-root` do not update the contract and class tries, and the node cannot serve storage
+//! proofs for them, see [`mc_db::global_trie_progress`]. When the trie catch-up is enabled, the state diffs of these
+//! blocks are applied to the tries in the background, a few blocks at a time and only while the block import is idle,
+//! so that the tries can be enabled later without syncing the chain again. The computed state roots are checked
+//! against the stored headers.
+
+use anyhow::Context;
+use mc_block_import::BlockImporter;
+use mc_db::MadaraBackend;
+use mp_utils::service::ServiceContext;
+use std::time::Duration;
+
+/// Number of blocks applied to the tries between two checks of the sync pressure.
+const TRIE_CATCH_UP_BATCH: usize = 16;
+/// Number of blocks applied to the tries between two progress logs.
+const TRIE_CATCH_UP_LOG_INTERVAL: u64 = 1000;
+/// Delay before checking again when the node is busy or the tries are caught up.
+const TRIE_CATCH_UP_IDLE_DELAY: Duration = Duration::from_secs(5);
+
+/// Whether the block import is idle enough for the tries to be caught up.
+fn is_idle(backend: &MadaraBackend) -> anyhow::Result<bool> {
+    Ok(backend.import_backlog().context("Getting the import backlog")? <= 1 && !backend.is_write_stalled())
+}
+
+/// Applies the blocks imported without updating the global tries to them, until the service is cancelled.
+pub(crate) async fn trie_catch_up(
+    backend: &MadaraBackend,
+    block_importer: &BlockImporter,
+    mut ctx: ServiceContext,
+) -> anyhow::Result<()> {
+    let mut last_logged = None;
+    loop {
+        let trie_block_n = backend.get_global_trie_block_n().context("Getting global trie block number")?;
+        let latest_block_n = backend.get_latest_block_n().context("Getting latest block number")?;
+        let caught_up = latest_block_n.is_none() || trie_block_n >= latest_block_n;
+
+        if caught_up || !is_idle(backend)? {
+            if ctx.run_until_cancelled(tokio::time::sleep(TRIE_CATCH_UP_IDLE_DELAY)).await.is_none() {
+                return Ok(());
+            }
+            continue;
+        }
+
+        let Some(res) = ctx.run_until_cancelled(block_importer.catch_up_global_tries(TRIE_CATCH_UP_BATCH)).await else {
+            return Ok(());
+        };
+        let Some(trie_block_n) = res.context("Catching up the global tries")? else { continue };
+
+        let log_interval = trie_block_n / TRIE_CATCH_UP_LOG_INTERVAL;
+        if last_logged != Some(log_interval) {
+            tracing::info!("🌳 Global tries caught up to block #{trie_block_n}");
+            last_logged = Some(log_interval);
+        }
+    }
+}
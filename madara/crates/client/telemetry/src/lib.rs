@@ -2,6 +2,7 @@ use std::time::SystemTime;
 
 use anyhow::Context;
 use futures::SinkExt;
+use mp_utils::http::HttpClientConfig;
 use mp_utils::service::{MadaraServiceId, PowerOfTwo, Service, ServiceContext, ServiceId, ServiceRunner};
 use reqwest_websocket::{Message, RequestBuilderExt, WebSocket};
 
@@ -35,12 +36,18 @@ impl TelemetryHandle {
 pub struct TelemetryService {
     telemetry_endpoints: Vec<(String, u8)>,
     telemetry_handle: TelemetryHandle,
+    http_config: HttpClientConfig,
 }
 
 impl TelemetryService {
     pub fn new(telemetry_endpoints: Vec<(String, u8)>) -> anyhow::Result<Self> {
         let telemetry_handle = TelemetryHandle(tokio::sync::broadcast::channel(1024).0);
-        Ok(Self { telemetry_endpoints, telemetry_handle })
+        Ok(Self { telemetry_endpoints, telemetry_handle, http_config: HttpClientConfig::default() })
+    }
+
+    /// Sets the connect timeout, keep-alive and proxy of the connections to the telemetry endpoints.
+    pub fn with_http_config(self, http_config: HttpClientConfig) -> Self {
+        Self { http_config, ..self }
     }
 
     pub fn new_handle(&self) -> TelemetryHandle {
@@ -82,7 +89,7 @@ impl TelemetryService {
 impl Service for TelemetryService {
     async fn start<'a>(&mut self, runner: ServiceRunner<'a>) -> anyhow::Result<()> {
         let rx = self.telemetry_handle.0.subscribe();
-        let clients = start_clients(&self.telemetry_endpoints).await;
+        let clients = start_clients(&self.telemetry_endpoints, &self.http_config).await?;
 
         runner.service_loop(move |ctx| start_telemetry(rx, ctx, clients));
 
@@ -97,9 +104,13 @@ impl ServiceId for TelemetryService {
     }
 }
 
-async fn start_clients(telemetry_endpoints: &[(String, u8)]) -> Vec<Option<(WebSocket, u8, String)>> {
-    let client = &reqwest::Client::default();
-    futures::future::join_all(telemetry_endpoints.iter().map(|(endpoint, pr)| async move {
+async fn start_clients(
+    telemetry_endpoints: &[(String, u8)],
+    http_config: &HttpClientConfig,
+) -> anyhow::Result<Vec<Option<(WebSocket, u8, String)>>> {
+    // The websockets stay open: the requests have no timeout.
+    let client = &http_config.reqwest_builder().build().context("Creating the telemetry http client")?;
+    let clients = futures::future::join_all(telemetry_endpoints.iter().map(|(endpoint, pr)| async move {
         let websocket = match client.get(endpoint).upgrade().send().await {
             Ok(ws) => ws,
             Err(err) => {
@@ -116,7 +127,8 @@ async fn start_clients(telemetry_endpoints: &[(String, u8)]) -> Vec<Option<(WebS
         };
         Some((websocket, *pr, endpoint.clone()))
     }))
-    .await
+    .await;
+    Ok(clients)
 }
 
 async fn start_telemetry(
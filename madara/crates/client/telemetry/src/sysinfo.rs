@@ -51,3 +51,32 @@ impl SysInfo {
         }
     }
 }
+
+/// Space of a disk, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+    pub available: u64,
+    pub total: u64,
+}
+
+impl DiskSpace {
+    /// Space of the disk holding `path`, which is the disk with the longest mount point `path` is under. `None` when
+    /// no disk is found.
+    pub fn probe(path: &std::path::Path) -> Option<Self> {
+        let path = path.canonicalize().ok()?;
+        let disks = sysinfo::Disks::new_with_refreshed_list();
+        disks
+            .iter()
+            .filter(|disk| path.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+            .map(|disk| DiskSpace { available: disk.available_space(), total: disk.total_space() })
+    }
+
+    /// Percentage of the disk which is available.
+    pub fn available_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.available as f64 * 100.0 / self.total as f64
+    }
+}
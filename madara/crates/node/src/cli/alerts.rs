@@ -0,0 +1,60 @@
+use clap::Args;
+use mp_utils::parsers::{parse_duration, parse_url};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+/// Parameters used to config the operator alerts.
+#[derive(Debug, Clone, Args)]
+pub struct AlertParams {
+    /// Webhook the critical events of the node are posted to: sync stalled, block quarantined after a verification
+    /// failure, L1 sync stalled, disk nearly full and deep reorg. Pass this flag multiple times, or a comma separated
+    /// list, to post to several webhooks. Alerts are disabled when no webhook is given.
+    #[arg(env = "MADARA_ALERT_WEBHOOK", long, value_parser = parse_url, value_delimiter = ',', value_name = "URL")]
+    pub alert_webhook: Vec<Url>,
+
+    /// File with the JSON payload posted to the webhooks, where `{{event}}`, `{{message}}`, `{{block_n}}`, `{{node}}`,
+    /// `{{chain_id}}` and `{{timestamp}}` are replaced by the values of the alert. The values are escaped to be placed
+    /// in JSON strings. By default, the payload has a `text` field with the message, which Slack webhooks display, and
+    /// a field for each value.
+    #[arg(env = "MADARA_ALERT_WEBHOOK_TEMPLATE", long, value_name = "PATH")]
+    pub alert_webhook_template: Option<PathBuf>,
+
+    /// Minimum delay between two alerts for the same event, so that an ongoing issue does not flood the webhooks. The
+    /// alerts about another block, or about a reorg at least twice as deep, are still sent.
+    #[arg(
+        env = "MADARA_ALERT_COOLDOWN",
+        long,
+        value_parser = parse_duration,
+        default_value = "10min",
+        value_name = "ALERT COOLDOWN",
+        help = "Set the minimum delay between two alerts for the same event and block (e.g., '10min', '1h')"
+    )]
+    pub alert_cooldown: Duration,
+
+    /// Reorgs reverting more blocks than this are alerted on.
+    #[arg(env = "MADARA_ALERT_REORG_DEPTH", long, value_name = "BLOCKS", default_value_t = 0)]
+    pub alert_reorg_depth: u64,
+
+    /// Percentage of free space of the disk of the database below which an alert is sent.
+    #[arg(env = "MADARA_ALERT_DISK_FREE_PERCENT", long, value_name = "PERCENT", default_value_t = 10.0)]
+    pub alert_disk_free_percent: f64,
+
+    /// Time without a new state update confirmed on L1 after which the L1 sync is considered stalled. Not checked when
+    /// the L1 sync is disabled.
+    #[arg(
+        env = "MADARA_ALERT_L1_STALL_TIMEOUT",
+        long,
+        value_parser = parse_duration,
+        default_value = "1h",
+        value_name = "ALERT L1 STALL TIMEOUT",
+        help = "Set the time without a new L1 state update after which an alert is sent (e.g., '1h', '30min')"
+    )]
+    pub alert_l1_stall_timeout: Duration,
+}
+
+impl AlertParams {
+    pub fn alerts_enabled(&self) -> bool {
+        !self.alert_webhook.is_empty()
+    }
+}
@@ -9,4 +9,13 @@ pub struct BlockProductionParams {
     /// Create this number of contracts in the genesis block for the devnet configuration.
     #[arg(env = "MADARA_DEVNET_CONTRACTS", long, default_value_t = 10)]
     pub devnet_contracts: u64,
+
+    /// Number of contracts, by usage in the latest blocks, whose classes and states are loaded into the execution
+    /// caches on startup, before accepting transactions. Set to 0 to disable the warm-up.
+    #[arg(env = "MADARA_EXEC_WARMUP_CONTRACTS", long, value_name = "COUNT", default_value_t = 64)]
+    pub exec_warmup_contracts: usize,
+
+    /// Number of latest blocks the contract usage is counted over for the execution cache warm-up.
+    #[arg(env = "MADARA_EXEC_WARMUP_BLOCKS", long, value_name = "BLOCKS", default_value_t = 100)]
+    pub exec_warmup_blocks: u64,
 }
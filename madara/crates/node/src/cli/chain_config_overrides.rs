@@ -7,6 +7,7 @@ use mp_utils::crypto::ZeroingPrivateKey;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
 use starknet_api::core::{ChainId, ContractAddress};
+use starknet_types_core::felt::Felt;
 
 use mp_block::H160;
 use mp_chain_config::{
@@ -71,6 +72,9 @@ pub struct ChainConfigOverrideParams {
     ///   * private_key: private key used by the node in sequencer mode to sign
     ///     the blocks it provides. This is zeroed.
     ///
+    ///   * sequencer_public_keys: public keys of the sequencer signing the
+    ///     blocks of the chain, used to check the signatures of synced blocks.
+    ///
     ///   * mempool_tx_limit: max number of transactions allowed in the mempool
     ///     in sequencer mode.
     ///
@@ -79,6 +83,13 @@ pub struct ChainConfigOverrideParams {
     ///
     ///   * mempool_tx_max_age: max age of transactions in the mempool.
     ///     Transactions which are too old will be removed.
+    ///
+    ///   * custom_transaction_versions: invoke transaction versions accepted
+    ///     on top of the Starknet ones, handled by the custom transaction
+    ///     handler of the mempool.
+    ///
+    ///   * udc_address: address of the Universal Deployer Contract of the
+    ///     chain.
     #[clap(env = "MADARA_CHAIN_CONFIG_OVERRIDE", long = "chain-config-override", value_parser = parse_key_value_yaml, use_value_delimiter = true, value_delimiter = ',')]
     pub overrides: Vec<(String, Value)>,
 }
@@ -107,10 +118,15 @@ pub struct ChainConfigOverridesInner {
     #[serde(skip_serializing)]
     #[serde(deserialize_with = "deserialize_private_key")]
     pub private_key: ZeroingPrivateKey,
+    #[serde(default)]
+    pub sequencer_public_keys: Vec<Felt>,
     pub mempool_tx_limit: usize,
     pub mempool_declare_tx_limit: usize,
     #[serde(deserialize_with = "deserialize_optional_duration", serialize_with = "serialize_optional_duration")]
     pub mempool_tx_max_age: Option<Duration>,
+    #[serde(default)]
+    pub custom_transaction_versions: Vec<Felt>,
+    pub udc_address: Felt,
 }
 
 impl ChainConfigOverrideParams {
@@ -131,9 +147,12 @@ impl ChainConfigOverrideParams {
             eth_core_contract_address: chain_config.eth_core_contract_address,
             eth_gps_statement_verifier: chain_config.eth_gps_statement_verifier,
             private_key: chain_config.private_key,
+            sequencer_public_keys: chain_config.sequencer_public_keys,
             mempool_tx_limit: chain_config.mempool_tx_limit,
             mempool_declare_tx_limit: chain_config.mempool_declare_tx_limit,
             mempool_tx_max_age: chain_config.mempool_tx_max_age,
+            custom_transaction_versions: chain_config.custom_transaction_versions,
+            udc_address: chain_config.udc_address,
             feeder_gateway_url: chain_config.feeder_gateway_url,
             gateway_url: chain_config.gateway_url,
         })
@@ -168,7 +187,7 @@ impl ChainConfigOverrideParams {
         let chain_config_overrides: ChainConfigOverridesInner = serde_yaml::from_value(chain_config_overrides)
             .context("Failed to convert Value to ChainConfigOverridesInner")?;
 
-        Ok(ChainConfig {
+        let chain_config = ChainConfig {
             chain_name: chain_config_overrides.chain_name,
             chain_id: chain_config_overrides.chain_id,
             feeder_gateway_url: chain_config_overrides.feeder_gateway_url,
@@ -185,9 +204,14 @@ impl ChainConfigOverrideParams {
             versioned_constants,
             eth_gps_statement_verifier: chain_config_overrides.eth_gps_statement_verifier,
             private_key: chain_config_overrides.private_key,
+            sequencer_public_keys: chain_config_overrides.sequencer_public_keys,
             mempool_tx_limit: chain_config_overrides.mempool_tx_limit,
             mempool_declare_tx_limit: chain_config_overrides.mempool_declare_tx_limit,
             mempool_tx_max_age: chain_config_overrides.mempool_tx_max_age,
-        })
+            custom_transaction_versions: chain_config_overrides.custom_transaction_versions,
+            udc_address: chain_config_overrides.udc_address,
+        };
+        chain_config.check_custom_transaction_versions()?;
+        Ok(chain_config)
     }
 }
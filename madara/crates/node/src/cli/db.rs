@@ -1,5 +1,10 @@
 use std::path::PathBuf;
 
+use mc_db::pruning::PruningMode;
+use mc_db::TrieLogConfig;
+
+use super::SyncProfile;
+
 #[derive(Clone, Debug, clap::Args)]
 pub struct DbParams {
     /// The path where madara will store the database. You should probably change it.
@@ -14,11 +19,77 @@ pub struct DbParams {
     #[clap(env = "MADARA_RESTORE_FROM_LATEST_BACKUP", long)]
     pub restore_from_latest_backup: bool,
 
+    /// Restore the database at startup from the latest backup in this directory, such as one created with the
+    /// `madara_backupDatabase` admin RPC method. The current database is replaced, and the sync resumes after the
+    /// latest block of the backup.
+    #[clap(
+        env = "MADARA_RESTORE_BACKUP_FROM",
+        long,
+        value_name = "PATH",
+        conflicts_with = "restore_from_latest_backup"
+    )]
+    pub restore_backup_from: Option<PathBuf>,
+
+    /// Lists the migrations of the database schema which would run on startup, and exits without running them.
+    /// Migrations otherwise run every time the node starts with a database written by an older node.
+    #[clap(env = "MADARA_DB_MIGRATE_DRY_RUN", long)]
+    pub db_migrate_dry_run: bool,
+
+    /// Verifies the blocks in the database and exits. Their transaction hashes, commitments and block hashes are
+    /// computed again and checked against the stored headers, and every block which does not verify is reported. The
+    /// global state root is only checked at the head of the global tries, as the state root of older blocks is not
+    /// kept. The database is opened read-only, and must have been opened once by this version of the node, to run its
+    /// migrations. This is useful to audit a copied database, or one which a node is running on.
+    #[clap(env = "MADARA_VERIFY_CHAIN", long)]
+    pub verify_chain: bool,
+
+    /// First block checked by `--verify-chain`.
+    #[clap(
+        env = "MADARA_VERIFY_CHAIN_FROM",
+        long,
+        default_value_t = 0,
+        value_name = "BLOCK",
+        requires = "verify_chain"
+    )]
+    pub verify_chain_from: u64,
+
+    /// Imports the blocks of this directory into the database and exits, without a feeder gateway. The directory has
+    /// a `block_<n>.json` file per block, as written by `--export-blocks`, and the blocks following the latest block in
+    /// the database are imported and verified like the blocks fetched from the gateway.
+    #[clap(
+        env = "MADARA_IMPORT_BLOCKS",
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["verify_chain", "export_blocks"]
+    )]
+    pub import_blocks: Option<PathBuf>,
+
+    /// Exports the blocks of the database to this directory and exits, with the classes they declare, to be imported
+    /// by another node with `--import-blocks`.
+    #[clap(env = "MADARA_EXPORT_BLOCKS", long, value_name = "DIR", conflicts_with = "verify_chain")]
+    pub export_blocks: Option<PathBuf>,
+
+    /// First block exported by `--export-blocks`.
+    #[clap(
+        env = "MADARA_EXPORT_BLOCKS_FROM",
+        long,
+        default_value_t = 0,
+        value_name = "BLOCK",
+        requires = "export_blocks"
+    )]
+    pub export_blocks_from: u64,
+
+    /// Last block exported by `--export-blocks`, the latest block by default.
+    #[clap(env = "MADARA_EXPORT_BLOCKS_TO", long, value_name = "BLOCK", requires = "export_blocks")]
+    pub export_blocks_to: Option<u64>,
+
     /// This is the number of blocks for which you can get storage proofs using the storage proof endpoints.
     /// Blocks older than this limit will not be stored for retrieving historical merkle trie state. By default,
     /// the value 0 means that no historical merkle trie state access is allowed.
-    #[clap(env = "MADARA_DB_MAX_SAVED_TRIE_LOGS", long, default_value_t = 0)]
-    pub db_max_saved_trie_logs: usize,
+    ///
+    /// Defaults to the value from --sync-profile (0 for `balanced`).
+    #[clap(env = "MADARA_DB_MAX_SAVED_TRIE_LOGS", long)]
+    pub db_max_saved_trie_logs: Option<usize>,
 
     /// This affects the performance of the storage proof endpoint.
     /// How many databse snapshots are kept at a given time, older ones will be discarded.
@@ -26,12 +97,38 @@ pub struct DbParams {
     /// when getting a storage proof.
     /// Higher values cause more database space usage, while lower values prevent the efficient reverting and historical access for
     /// the global state trie at older blocks.
-    #[clap(env = "MADARA_DB_MAX_SNAPSHOTS", long, default_value_t = 0)]
-    pub db_max_kept_snapshots: usize,
+    /// Storage proofs are also served at the blocks of the kept snapshots, even when they are further back than
+    /// `--rpc-storage-proof-max-distance`. Snapshots are kept in memory, and are lost on restart.
+    ///
+    /// Defaults to the value from --sync-profile (0 for `balanced`).
+    #[clap(env = "MADARA_DB_MAX_SNAPSHOTS", long)]
+    pub db_max_kept_snapshots: Option<usize>,
 
     /// This affects the performance of the storage proof endpoint.
     /// A database snapshot is created every `db_snapshot_interval` blocks.
     /// See `--db-max-kept-snapshots` to understand what snapshots are used for.
-    #[clap(env = "MADARA_DB_SNAPSHOT_INTERVAL", long, default_value_t = 5)]
-    pub db_snapshot_interval: u64,
+    ///
+    /// Defaults to the value from --sync-profile (5 for `balanced`).
+    #[clap(env = "MADARA_DB_SNAPSHOT_INTERVAL", long)]
+    pub db_snapshot_interval: Option<u64>,
+
+    /// Which part of the historical state is kept. In `archive` mode, the state can be read at every block. When this
+    /// is a number of blocks, the state is only kept at the latest blocks: reading the state at older blocks returns a
+    /// "state pruned" error. The trie logs are also capped to this number of blocks, see `--db-max-saved-trie-logs`.
+    /// Blocks, transactions, receipts and state diffs are kept in both modes. Defaults to `archive`, or to 1000 blocks
+    /// with `--light`.
+    #[clap(env = "MADARA_PRUNING", long, value_name = "archive|BLOCKS")]
+    pub pruning: Option<PruningMode>,
+}
+
+impl DbParams {
+    /// Resolves the trie log settings, the values which were not passed explicitly are taken from the sync profile.
+    pub fn trie_log_config(&self, sync_profile: SyncProfile) -> TrieLogConfig {
+        let settings = sync_profile.settings();
+        TrieLogConfig {
+            max_saved_trie_logs: self.db_max_saved_trie_logs.unwrap_or(settings.db_max_saved_trie_logs),
+            max_kept_snapshots: self.db_max_kept_snapshots.unwrap_or(settings.db_max_kept_snapshots),
+            snapshot_interval: self.db_snapshot_interval.unwrap_or(settings.db_snapshot_interval),
+        }
+    }
 }
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use url::Url;
+
+use mp_utils::http::{HttpClientConfig, ProxyConfig};
+use mp_utils::parsers::{parse_duration, parse_url};
+
+/// Parameters of the outbound http connections: to the feeder gateway, the L1 RPC endpoint, the beacon node, the price
+/// oracle and the telemetry endpoints.
+#[derive(Clone, Debug, clap::Args)]
+pub struct HttpParams {
+    /// Maximum time to establish a connection.
+    #[clap(env = "MADARA_HTTP_CONNECT_TIMEOUT", long, default_value = "10s", value_parser = parse_duration)]
+    pub http_connect_timeout: Duration,
+
+    /// Maximum time for a request, from sending it to receiving the whole response.
+    #[clap(env = "MADARA_HTTP_TIMEOUT", long, default_value = "20s", value_parser = parse_duration)]
+    pub http_timeout: Duration,
+
+    /// Time an idle connection is kept open to be reused.
+    #[clap(env = "MADARA_HTTP_POOL_IDLE_TIMEOUT", long, default_value = "90s", value_parser = parse_duration)]
+    pub http_pool_idle_timeout: Duration,
+
+    /// Interval of the TCP keep-alive probes.
+    #[clap(env = "MADARA_HTTP_TCP_KEEPALIVE", long, default_value = "60s", value_parser = parse_duration)]
+    pub http_tcp_keepalive: Duration,
+
+    /// Proxy all the outbound connections go through. By default, the proxies are read from the `HTTP_PROXY`,
+    /// `HTTPS_PROXY` and `NO_PROXY` environment variables.
+    #[clap(env = "MADARA_HTTP_PROXY", long, value_parser = parse_url, value_name = "PROXY URL")]
+    pub http_proxy: Option<Url>,
+
+    /// Connect directly, ignoring the proxy environment variables.
+    #[clap(env = "MADARA_NO_PROXY", long, conflicts_with = "http_proxy")]
+    pub no_proxy: bool,
+}
+
+impl HttpParams {
+    pub fn http_client_config(&self) -> HttpClientConfig {
+        let proxy = match &self.http_proxy {
+            Some(proxy) => ProxyConfig::all(proxy.clone()),
+            None if self.no_proxy => ProxyConfig::default(),
+            None => ProxyConfig::from_env(),
+        };
+        HttpClientConfig {
+            connect_timeout: self.http_connect_timeout,
+            timeout: self.http_timeout,
+            pool_idle_timeout: self.http_pool_idle_timeout,
+            tcp_keepalive: Some(self.http_tcp_keepalive),
+            proxy,
+        }
+    }
+}
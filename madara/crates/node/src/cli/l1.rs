@@ -46,4 +46,10 @@ pub struct L1SyncParams {
         value_parser = parse_duration,
     )]
     pub gas_price_poll: Duration,
+
+    /// Audit the state diffs posted on L1: they are decoded from the blobs fetched from this beacon node API, or from
+    /// the calldata memory pages, and compared with the state diffs of the synced blocks. Mismatches are reported in
+    /// the logs.
+    #[clap(env = "MADARA_L1_DA_AUDIT_BEACON_URL", long, value_parser = parse_url, value_name = "BEACON API URL")]
+    pub l1_da_audit_beacon_url: Option<Url>,
 }
@@ -1,11 +1,17 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::BTreeMap, fmt, num::NonZeroU32, num::NonZeroUsize, path::PathBuf, sync::Arc, time::Duration};
 
+use anyhow::Context;
 use mc_sync::fetch::fetchers::WarpUpdateConfig;
 use mp_chain_config::ChainConfig;
 use starknet_api::core::ChainId;
+use starknet_types_core::felt::Felt;
 
-use mc_sync::fetch::fetchers::FetchConfig;
-use mp_utils::parsers::{parse_duration, parse_url};
+use mc_sync::fetch::fetchers::{FetchConfig, FetchRetryPolicy};
+use mc_sync::quarantine::QuarantineConfig;
+use mc_sync::snapshot::SnapshotConfig;
+use mc_sync::stall::StallDetectionConfig;
+use mp_utils::http::HttpClientConfig;
+use mp_utils::parsers::{parse_duration, parse_felt, parse_url};
 use url::Url;
 
 use super::FGW_DEFAULT_PORT;
@@ -21,19 +27,88 @@ pub struct L2SyncParams {
     #[clap(env = "MADARA_UNSAFE_STARTING_BLOCK", long, value_name = "BLOCK NUMBER")]
     pub unsafe_starting_block: Option<u64>,
 
+    /// Bootstrap an empty database from the state snapshot at this url, instead of syncing every block from genesis.
+    /// The sync then starts from the block after the snapshot, and older blocks are not available. `file://` urls are
+    /// read from the local filesystem. Requires `--snapshot-trusted-hash`.
+    #[clap(
+        env = "MADARA_SNAPSHOT_URL",
+        long,
+        value_parser = parse_url,
+        value_name = "URL",
+        requires = "snapshot_trusted_hash",
+        conflicts_with = "unsafe_starting_block"
+    )]
+    pub snapshot_url: Option<Url>,
+
+    /// Hash of the block of the state snapshot given with `--snapshot-url`. The snapshot is rejected unless its header
+    /// hashes to this value and its state matches the state root of the header.
+    #[clap(
+        env = "MADARA_SNAPSHOT_TRUSTED_HASH",
+        long,
+        value_parser = parse_felt,
+        value_name = "BLOCK HASH",
+        requires = "snapshot_url"
+    )]
+    pub snapshot_trusted_hash: Option<Felt>,
+
+    /// Download the blocks older than the state snapshot the node was bootstrapped from in the background, down to the
+    /// genesis block, so that RPC can serve the full history of the chain. The state of the chain is not affected.
+    #[clap(env = "MADARA_SYNC_BACKFILL", long)]
+    pub sync_backfill: bool,
+
     /// Disable state root verification. When importing a block, the state root verification is the most expensive operation.
     /// Disabling it will mean the sync service will have a huge speed-up, at a security cost
     // TODO(docs): explain the security cost
     #[clap(env = "MADARA_DISABLE_ROOT", long)]
     pub disable_root: bool,
 
+    /// Compute the global tries in the background for the blocks imported with `--disable-root`, while the node is not
+    /// busy importing blocks. Once they are caught up, the node can serve storage proofs for these blocks, and can be
+    /// restarted without `--disable-root` without syncing the chain again.
+    #[clap(env = "MADARA_SYNC_TRIE_CATCH_UP", long)]
+    pub sync_trie_catch_up: bool,
+
+    /// Reject any block from the feeder gateway which is not fully consistent, such as a block with missing
+    /// commitments, receipts that do not match its transactions, duplicated transactions, or a state diff which does
+    /// not declare its classes in the order of its declare transactions. The pending block is checked as well. The sync
+    /// stops with a detailed report of every inconsistency found in the offending block.
+    #[clap(env = "MADARA_SYNC_STRICT_VALIDATION", long)]
+    pub sync_strict_validation: bool,
+
+    /// Reject the gateway responses with fields the node does not know. By default, these fields are dropped with a
+    /// warning the first time each of them is seen, so that a field added by a new Starknet version does not stop the
+    /// sync before the node is upgraded. This is meant for CI, to notice changes of the gateway schema.
+    #[clap(env = "MADARA_GATEWAY_STRICT_SCHEMA", long)]
+    pub gateway_strict_schema: bool,
+
+    /// Hash the blocks older than Starknet v0.13.2 with the v0.13.2 block hash algorithm instead of the algorithm of
+    /// their protocol version. Only use this with a feeder gateway serving the recomputed hashes of these blocks,
+    /// otherwise they will fail verification.
+    #[clap(env = "MADARA_COMPUTE_V0_13_2_HASHES", long = "compute-v0-13-2-hashes")]
+    pub compute_v0_13_2_hashes: bool,
+
+    /// Verify the sequencer signature of every block fetched from the feeder gateway, and store it in the database.
+    /// Unsigned blocks are rejected. The signatures are checked against the `sequencer_public_keys` of the chain
+    /// config, which are set for the official networks and can be given with
+    /// `--chain-config-override sequencer_public_keys=[...]` otherwise.
+    #[clap(env = "MADARA_SYNC_VERIFY_SIGNATURES", long)]
+    pub sync_verify_signatures: bool,
+
     /// Gateway api key to avoid rate limiting (optional).
     #[clap(env = "MADARA_GATEWAY_KEY", long, value_name = "API KEY")]
     pub gateway_key: Option<String>,
 
-    /// Feeder gateway url used to sync blocks, state updates and classes
-    #[clap(env = "MADARA_GATEWAY_URL", long, value_parser = parse_url, value_name = "URL")]
-    pub gateway_url: Option<Url>,
+    /// Feeder gateway url used to sync blocks, state updates and classes. When several urls are given, the first one
+    /// is used as long as it answers, and the requests fail over to the next ones in order when it is unavailable or
+    /// rate limits the node.
+    #[clap(env = "MADARA_GATEWAY_URL", long, value_parser = parse_url, value_name = "URL", value_delimiter = ',')]
+    pub gateway_url: Vec<Url>,
+
+    /// Maximum number of requests sent to the gateways per second, shared between the fetches of the blocks and of
+    /// the classes. Without an API key, the gateways ban the nodes which send them too many requests. Unlimited by
+    /// default.
+    #[clap(env = "MADARA_GATEWAY_MAX_RPS", long, value_name = "REQUESTS PER SECOND")]
+    pub gateway_max_rps: Option<NonZeroU32>,
 
     /// The port used for nodes to make rpc calls during a warp update.
     #[arg(env = "MADARA_WARP_UPDATE_PORT_RPC", long, value_name = "WARP UPDATE PORT RPC", default_value_t = RPC_DEFAULT_PORT_ADMIN)]
@@ -62,6 +137,106 @@ pub struct L2SyncParams {
     )]
     pub sync_polling_interval: Duration,
 
+    /// Time without any new block being imported, while the feeder gateway is ahead of us, after which the sync is
+    /// considered stalled. A stall is logged and counted in the `l2_sync_stall_count` metric.
+    #[clap(
+		env = "MADARA_SYNC_STALL_TIMEOUT",
+        long,
+        value_parser = parse_duration,
+        value_name = "SYNC STALL TIMEOUT",
+        help = "Enable sync stall detection with the given timeout (e.g., '5min', '90s')"
+    )]
+    pub sync_stall_timeout: Option<Duration>,
+
+    /// Restart the sync pipeline from the latest imported block when it is stalled. Requires `--sync-stall-timeout`.
+    #[clap(env = "MADARA_SYNC_RESTART_ON_STALL", long, requires = "sync_stall_timeout")]
+    pub sync_restart_on_stall: bool,
+
+    /// Directory where a diagnostics bundle is written when a block fails verification. When set, the sync is
+    /// restarted from the latest imported block after such a failure, instead of stopping the node.
+    #[clap(env = "MADARA_SYNC_QUARANTINE_DIR", long, value_name = "PATH")]
+    pub sync_quarantine_dir: Option<PathBuf>,
+
+    /// Delay before the sync is restarted after a block failed verification. Only used with `--sync-quarantine-dir`.
+    #[clap(
+        env = "MADARA_SYNC_QUARANTINE_RETRY_DELAY",
+        long,
+        value_parser = parse_duration,
+        default_value = "30s",
+        value_name = "SYNC QUARANTINE RETRY DELAY",
+        help = "Set the delay before retrying a block which failed verification (e.g., '30s', '5min')"
+    )]
+    pub sync_quarantine_retry_delay: Duration,
+
+    /// Maximum number of blocks reverted to recover from a reorg of the chain. Reverting blocks requires the trie logs
+    /// to go back as far, see `--db-max-saved-trie-logs`: the node does not start when they do not. By default, this is
+    /// 64 blocks, or as far as the trie logs go back. Set to 0 to stop the sync on a reorg instead.
+    #[clap(env = "MADARA_SYNC_MAX_REORG_DEPTH", long, value_name = "BLOCKS")]
+    pub sync_max_reorg_depth: Option<u64>,
+
+    /// Number of compiled classes kept in memory, so that the classes declared again, such as the classes of the
+    /// pending block on each poll, are not compiled again. Set to 0 to disable the cache.
+    #[clap(
+        env = "MADARA_SYNC_CLASS_CACHE_SIZE",
+        long,
+        value_name = "CLASSES",
+        default_value_t = mc_block_import::class_cache::DEFAULT_CLASS_CACHE_SIZE
+    )]
+    pub sync_class_cache_size: usize,
+
+    /// Number of threads compiling the declared Sierra classes to CASM, half of the cores by default. The blocks
+    /// declaring classes wait for this pool, so that a burst of classes does not starve the rest of the block import.
+    #[clap(env = "MADARA_CLASS_COMPILATION_THREADS", long, value_name = "THREADS")]
+    pub class_compilation_threads: Option<NonZeroUsize>,
+
+    /// Maximum number of retries of a feeder gateway request failing with a server or network error. The delay
+    /// between the retries starts at `--sync-retry-base-delay` and doubles with each retry, up to
+    /// `--sync-retry-max-delay`.
+    #[clap(env = "MADARA_SYNC_RETRY_MAX", long, value_name = "RETRIES", default_value_t = 15)]
+    pub sync_retry_max: u32,
+
+    /// Maximum number of retries of a feeder gateway request which is rate limited. Rate limited requests are
+    /// retried after `--sync-retry-max-delay`.
+    #[clap(env = "MADARA_SYNC_RETRY_RATE_LIMITED_MAX", long, value_name = "RETRIES", default_value_t = 15)]
+    pub sync_retry_rate_limited_max: u32,
+
+    /// Maximum number of retries of a feeder gateway request whose response could not be parsed.
+    #[clap(env = "MADARA_SYNC_RETRY_PARSE_ERROR_MAX", long, value_name = "RETRIES", default_value_t = 2)]
+    pub sync_retry_parse_error_max: u32,
+
+    /// Delay before the first retry of a failed feeder gateway request.
+    #[clap(
+        env = "MADARA_SYNC_RETRY_BASE_DELAY",
+        long,
+        value_parser = parse_duration,
+        default_value = "1s",
+        value_name = "SYNC RETRY BASE DELAY",
+        help = "Set the delay before the first retry of a failed feeder gateway request (e.g., '1s', '500ms')"
+    )]
+    pub sync_retry_base_delay: Duration,
+
+    /// Maximum delay between two retries of a failed feeder gateway request.
+    #[clap(
+        env = "MADARA_SYNC_RETRY_MAX_DELAY",
+        long,
+        value_parser = parse_duration,
+        default_value = "6s",
+        value_name = "SYNC RETRY MAX DELAY",
+        help = "Set the maximum delay between two retries of a failed feeder gateway request (e.g., '6s', '1min')"
+    )]
+    pub sync_retry_max_delay: Duration,
+
+    /// Fraction of the retry delay which is randomized, between 0 and 1, so that the blocks fetched in parallel are
+    /// not all requested again at once.
+    #[clap(
+        env = "MADARA_SYNC_RETRY_JITTER",
+        long,
+        value_parser = parse_fraction,
+        default_value_t = 0.2,
+        value_name = "FRACTION"
+    )]
+    pub sync_retry_jitter: f64,
+
     /// Pending block polling interval, in seconds. This only affects the sync service once it has caught up with the blockchain tip.
     #[clap(
 		env = "MADARA_PENDING_BLOCK_POLL_INTERVAL",
@@ -78,6 +253,12 @@ pub struct L2SyncParams {
     #[clap(env = "MADARA_NO_SYNC_POLLING", long)]
     pub no_sync_polling: bool,
 
+    /// Get the new blocks pushed by the feeder gateway as soon as they are sealed, instead of polling it every
+    /// `--sync-polling-interval`. This is meant for the followers of an appchain sequencer, and requires the feeder
+    /// gateway to be served by a Madara node.
+    #[clap(env = "MADARA_SYNC_PUSH", long, conflicts_with = "no_sync_polling")]
+    pub sync_push: bool,
+
     /// Number of blocks to sync. May be useful for benchmarking the sync service.
     #[clap(env = "MADARA_N_BLOCKS_TO_SYNC", long, value_name = "NUMBER OF BLOCKS")]
     pub n_blocks_to_sync: Option<u64>,
@@ -93,6 +274,18 @@ pub struct L2SyncParams {
     #[clap(env = "MADARA_BACKUP_EVERY_N_BLOCKS", long, value_name = "NUMBER OF BLOCKS")]
     pub backup_every_n_blocks: Option<u64>,
 
+    /// Curated sync performance settings. This sets the sync parallelism, the database flush policy and the
+    /// database snapshot settings in one go. Any of these settings which is passed explicitly overrides the value
+    /// from the profile.
+    ///
+    /// - `fast`: fetch more blocks in parallel and flush less often, at the cost of higher cpu and ram usage.
+    /// - `balanced`: the default.
+    /// - `low-resource`: keep cpu and ram usage low, at the cost of slower sync.
+    /// - `archive`: keep the trie logs of every block, and the 10 latest database snapshots, taken every 1000 blocks,
+    ///   so that storage proofs can be served for older blocks, at the cost of a lot more disk and ram usage.
+    #[clap(env = "MADARA_SYNC_PROFILE", long, value_name = "PROFILE", default_value_t = SyncProfile::Balanced)]
+    pub sync_profile: SyncProfile,
+
     /// Periodically flushes the database from ram to disk based on the number
     /// of blocks synchronized since the last flush. You can set this to a
     /// higher number depending on how fast your machine is at synchronizing
@@ -104,14 +297,15 @@ pub struct L2SyncParams {
     /// Note that keeping this value high could lead to blocks being stored in
     /// ram for longer periods of time before they are written to disk. This
     /// might be an issue for chains which synchronize slowly.
+    ///
+    /// Defaults to the value from --sync-profile (1000 for `balanced`).
     #[clap(
         env = "MADARA_FLUSH_EVERY_N_BLOCKS",
         value_name = "FLUSH EVERY N BLOCKS",
         long,
-        value_parser = clap::value_parser!(u64).range(..=10_000),
-        default_value_t = 1_000
+        value_parser = clap::value_parser!(u64).range(..=10_000)
     )]
-    pub flush_every_n_blocks: u64,
+    pub flush_every_n_blocks: Option<u64>,
 
     /// Periodically flushes the database from ram to disk based on the elapsed
     /// time since the last flush. You can set this to a higher number
@@ -119,63 +313,209 @@ pub struct L2SyncParams {
     /// much ram it has available.
     ///
     /// Be aware that blocks might still be flushed to db earlier based on the
-    /// value of --flush-every-n-blocks.
+    /// value of --flush-every-n-blocks. This also applies when the sync is idle:
+    /// blocks imported since the last flush are flushed once this delay has
+    /// elapsed, even if no new block arrives.
     ///
     /// Note that keeping this value high could lead to blocks being stored in
     /// ram for longer periods of time before they are written to disk. This
     /// might be an issue for chains which synchronize slowly.
+    ///
+    /// Defaults to the value from --sync-profile (5 for `balanced`).
     #[clap(
-        env = "MADARA_FLUSH_EVERY_N_BLOCKS",
-        value_name = "FLUSH EVERY N BLOCKS",
+        env = "MADARA_FLUSH_EVERY_N_SECONDS",
+        value_name = "FLUSH EVERY N SECONDS",
         long,
-        value_parser = clap::value_parser!(u64).range(..=3_600),
-        default_value_t = 5
+        value_parser = clap::value_parser!(u64).range(..=3_600)
     )]
-    pub flush_every_n_seconds: u64,
+    pub flush_every_n_seconds: Option<u64>,
 
     /// Number of blocks to fetch in parallel. This only affects sync time, and
     /// does not affect the node once it has reached the tip of the chain.
     /// Increasing this can lead to lower sync times at the cost of higher cpu
     /// and ram utilization.
+    ///
+    /// Defaults to the value from --sync-profile (10 for `balanced`).
     #[clap(
         env = "MADARA_SYNC_PARALLELISM",
         long, value_name = "SYNC PARALLELISM",
-        default_value_t = 10,
         value_parser = clap::value_parser!(u8).range(1..)
     )]
+    pub sync_parallelism: Option<u8>,
+}
+
+/// Sync performance presets, see `--sync-profile`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SyncProfile {
+    Fast,
+    #[default]
+    Balanced,
+    LowResource,
+    Archive,
+}
+
+impl fmt::Display for SyncProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fast => write!(f, "fast"),
+            Self::Balanced => write!(f, "balanced"),
+            Self::LowResource => write!(f, "low-resource"),
+            Self::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+/// The values a [`SyncProfile`] gives to the settings it covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProfileSettings {
     pub sync_parallelism: u8,
+    pub flush_every_n_blocks: u64,
+    pub flush_every_n_seconds: u64,
+    pub db_max_saved_trie_logs: usize,
+    pub db_max_kept_snapshots: usize,
+    pub db_snapshot_interval: u64,
+}
+
+impl SyncProfile {
+    pub fn settings(self) -> SyncProfileSettings {
+        match self {
+            Self::Fast => SyncProfileSettings {
+                sync_parallelism: 30,
+                flush_every_n_blocks: 5_000,
+                flush_every_n_seconds: 30,
+                db_max_saved_trie_logs: 0,
+                db_max_kept_snapshots: 0,
+                db_snapshot_interval: 5,
+            },
+            Self::Balanced => SyncProfileSettings {
+                sync_parallelism: 10,
+                flush_every_n_blocks: 1_000,
+                flush_every_n_seconds: 5,
+                db_max_saved_trie_logs: 0,
+                db_max_kept_snapshots: 0,
+                db_snapshot_interval: 5,
+            },
+            Self::LowResource => SyncProfileSettings {
+                sync_parallelism: 2,
+                flush_every_n_blocks: 100,
+                flush_every_n_seconds: 5,
+                db_max_saved_trie_logs: 0,
+                db_max_kept_snapshots: 0,
+                db_snapshot_interval: 5,
+            },
+            Self::Archive => SyncProfileSettings {
+                sync_parallelism: 10,
+                flush_every_n_blocks: 1_000,
+                flush_every_n_seconds: 5,
+                // Effectively all of them.
+                db_max_saved_trie_logs: u32::MAX as usize,
+                db_max_kept_snapshots: 10,
+                db_snapshot_interval: 1_000,
+            },
+        }
+    }
 }
 
 impl L2SyncParams {
+    pub fn flush_every_n_blocks(&self) -> u64 {
+        self.flush_every_n_blocks.unwrap_or(self.sync_profile.settings().flush_every_n_blocks)
+    }
+
+    pub fn flush_every_n_seconds(&self) -> u64 {
+        self.flush_every_n_seconds.unwrap_or(self.sync_profile.settings().flush_every_n_seconds)
+    }
+
+    pub fn sync_parallelism(&self) -> u8 {
+        self.sync_parallelism.unwrap_or(self.sync_profile.settings().sync_parallelism)
+    }
+
+    /// Whether the block signatures are verified, see `--sync-verify-signatures`. The chain config must have
+    /// sequencer public keys to verify them against.
+    pub fn verify_signatures(&self, chain_config: &ChainConfig) -> anyhow::Result<bool> {
+        if self.sync_verify_signatures && chain_config.sequencer_public_keys.is_empty() {
+            anyhow::bail!(
+                "`--sync-verify-signatures` requires the sequencer public keys of chain {}, please provide them with \
+                 `--chain-config-override sequencer_public_keys=[...]`",
+                chain_config.chain_name
+            );
+        }
+        Ok(self.sync_verify_signatures)
+    }
+
     pub fn block_fetch_config(
         &self,
         chain_id: ChainId,
         chain_config: Arc<ChainConfig>,
         warp_update: Option<WarpUpdateConfig>,
-    ) -> FetchConfig {
-        let (gateway, feeder_gateway) = match &self.gateway_url {
-            Some(url) => (
+        checkpoints: Arc<BTreeMap<u64, Felt>>,
+        http: HttpClientConfig,
+    ) -> anyhow::Result<FetchConfig> {
+        let mut gateways = self.gateway_url.iter().map(|url| {
+            (
                 url.join("/gateway/").expect("Error parsing url"),
                 url.join("/feeder_gateway/").expect("Error parsing url"),
-            ),
-            None => (chain_config.gateway_url.clone(), chain_config.feeder_gateway_url.clone()),
-        };
+            )
+        });
+        let (gateway, feeder_gateway) = gateways
+            .next()
+            .unwrap_or_else(|| (chain_config.gateway_url.clone(), chain_config.feeder_gateway_url.clone()));
+        let fallback_gateways = gateways.collect();
 
         let polling = if self.no_sync_polling { None } else { Some(self.sync_polling_interval) };
 
-        FetchConfig {
+        let verify_signatures = self.verify_signatures(&chain_config)?;
+
+        Ok(FetchConfig {
             gateway,
             feeder_gateway,
+            fallback_gateways,
+            gateway_max_rps: self.gateway_max_rps,
             chain_id,
             verify: !self.disable_root,
+            strict_validation: self.sync_strict_validation,
+            strict_gateway_schema: self.gateway_strict_schema,
+            compute_v0_13_2_hashes: self.compute_v0_13_2_hashes,
+            verify_signatures,
             api_key: self.gateway_key.clone(),
             sync_polling_interval: polling,
+            sync_push: self.sync_push,
             n_blocks_to_sync: self.n_blocks_to_sync,
-            flush_every_n_blocks: self.flush_every_n_blocks,
-            flush_every_n_seconds: self.flush_every_n_seconds,
+            flush_every_n_blocks: self.flush_every_n_blocks(),
+            flush_every_n_seconds: self.flush_every_n_seconds(),
             stop_on_sync: self.stop_on_sync,
-            sync_parallelism: self.sync_parallelism,
+            sync_parallelism: self.sync_parallelism(),
+            retry_policy: FetchRetryPolicy {
+                max_retries: self.sync_retry_max,
+                max_rate_limited_retries: self.sync_retry_rate_limited_max,
+                max_parse_error_retries: self.sync_retry_parse_error_max,
+                base_delay: self.sync_retry_base_delay,
+                max_delay: self.sync_retry_max_delay,
+                jitter: self.sync_retry_jitter,
+            },
+            http,
             warp_update,
-        }
+            stall_detection: self
+                .sync_stall_timeout
+                .map(|timeout| StallDetectionConfig { timeout, restart: self.sync_restart_on_stall }),
+            quarantine: self
+                .sync_quarantine_dir
+                .clone()
+                .map(|dir| QuarantineConfig { dir, retry_delay: self.sync_quarantine_retry_delay }),
+            max_reorg_depth: self.sync_max_reorg_depth,
+            checkpoints,
+            snapshot: self
+                .snapshot_url
+                .clone()
+                .zip(self.snapshot_trusted_hash)
+                .map(|(url, trusted_block_hash)| SnapshotConfig { url, trusted_block_hash }),
+            backfill: self.sync_backfill,
+            trie_catch_up: self.sync_trie_catch_up,
+        })
     }
 }
+
+fn parse_fraction(s: &str) -> anyhow::Result<f64> {
+    let fraction: f64 = s.parse().context("Invalid number")?;
+    anyhow::ensure!((0.0..=1.0).contains(&fraction), "Must be between 0 and 1");
+    Ok(fraction)
+}
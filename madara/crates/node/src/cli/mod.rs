@@ -1,13 +1,18 @@
+pub mod alerts;
 pub mod analytics;
 pub mod block_production;
 pub mod chain_config_overrides;
 pub mod db;
 pub mod gateway;
+pub mod http;
 pub mod l1;
 pub mod l2;
+pub mod role;
 pub mod rpc;
 pub mod telemetry;
+use crate::cli::http::HttpParams;
 use crate::cli::l1::L1SyncParams;
+use alerts::AlertParams;
 use analytics::AnalyticsParams;
 use anyhow::Context;
 pub use block_production::*;
@@ -15,12 +20,16 @@ pub use chain_config_overrides::*;
 pub use db::*;
 pub use gateway::*;
 pub use l2::*;
+pub use role::*;
 pub use rpc::*;
 use std::str::FromStr;
 pub use telemetry::*;
 
 use clap::ArgGroup;
+use mp_chain_config::registry::ChainRegistry;
 use mp_chain_config::ChainConfig;
+use mp_utils::parsers::parse_felt;
+use starknet_types_core::felt::Felt;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -118,10 +127,15 @@ impl ArgsPresetParams {
 #[clap(
     group(
         ArgGroup::new("mode")
-            .args(&["sequencer", "full", "devnet"])
+            .args(&["sequencer", "full", "archive", "light", "devnet"])
             .required(true)
             .multiple(false)
     ),
+    group(
+        ArgGroup::new("sync_mode")
+            .args(&["full", "archive", "light"])
+            .multiple(false)
+    ),
     group(
         ArgGroup::new("chain_config")
             .args(&["chain_config_path", "preset"])
@@ -130,7 +144,7 @@ impl ArgsPresetParams {
     group(
         ArgGroup::new("full_mode_config")
             .args(&["network", "chain_config_path", "preset"])
-            .requires("full")
+            .requires("sync_mode")
     ),
 )]
 pub struct RunCmd {
@@ -163,6 +177,14 @@ pub struct RunCmd {
     #[clap(flatten)]
     pub telemetry_params: TelemetryParams,
 
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub alert_params: AlertParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub http_params: HttpParams,
+
     #[allow(missing_docs)]
     #[clap(flatten)]
     pub gateway_params: GatewayParams,
@@ -183,6 +205,15 @@ pub struct RunCmd {
     #[arg(env = "MADARA_FULL", long, group = "mode")]
     pub full: bool,
 
+    /// The node will run as a full node which keeps the state at every block. It cannot be run with `--pruning`.
+    #[arg(env = "MADARA_ARCHIVE", long, group = "mode")]
+    pub archive: bool,
+
+    /// The node will run as a full node for constrained machines: only the state at the latest blocks is kept (1000
+    /// unless set with `--pruning`).
+    #[arg(env = "MADARA_LIGHT", long, group = "mode")]
+    pub light: bool,
+
     /// The node will run as a testing sequencer with predeployed contracts.
     #[arg(env = "MADARA_DEVNET", long, group = "mode")]
     pub devnet: bool,
@@ -203,6 +234,28 @@ pub struct RunCmd {
     #[clap(env = "MADARA_PRESET", long, value_name = "PRESET NAME", group = "chain_config")]
     pub preset: Option<ChainPreset>,
 
+    /// Signed chain registry file published by the operator of an appchain. The chain config is read from the
+    /// registry, and the node refuses to sync or start on blocks which differ from the genesis and checkpoint block
+    /// hashes it pins.
+    #[clap(
+        env = "MADARA_REGISTRY_FILE",
+        long,
+        value_name = "REGISTRY FILE PATH",
+        conflicts_with_all = ["network", "chain_config_path", "preset", "overrides"],
+        requires = "registry_public_key"
+    )]
+    pub registry_file: Option<PathBuf>,
+
+    /// Public key of the operator, used to verify the signature of the chain registry file.
+    #[clap(
+        env = "MADARA_REGISTRY_PUBLIC_KEY",
+        long,
+        value_parser = parse_felt,
+        value_name = "PUBLIC KEY",
+        requires = "registry_file"
+    )]
+    pub registry_public_key: Option<Felt>,
+
     /// Overrides parameters from the Chain Config.
     #[allow(missing_docs)]
     #[clap(flatten)]
@@ -272,6 +325,16 @@ impl RunCmd {
         Ok(Arc::new(chain_config))
     }
 
+    /// Reads the chain registry file and verifies its signature, when one is provided.
+    pub fn chain_registry(&self) -> anyhow::Result<Option<ChainRegistry>> {
+        let (Some(path), Some(public_key)) = (&self.registry_file, &self.registry_public_key) else {
+            return Ok(None);
+        };
+        let registry = ChainRegistry::from_file(path)?;
+        registry.verify(public_key)?;
+        Ok(Some(registry))
+    }
+
     /// Assigns a specific ChainConfig based on a defined network.
     pub fn set_preset_from_network(&self) -> anyhow::Result<Arc<ChainConfig>> {
         let mut chain_config = match self.network {
@@ -292,12 +355,27 @@ impl RunCmd {
         Ok(Arc::new(chain_config))
     }
 
+    /// The role of the node, selected by the mode flags.
+    pub fn role(&self) -> NodeRole {
+        if self.devnet {
+            NodeRole::Devnet
+        } else if self.sequencer {
+            NodeRole::Sequencer
+        } else if self.archive {
+            NodeRole::Archive
+        } else if self.light {
+            NodeRole::Light
+        } else {
+            NodeRole::Full
+        }
+    }
+
     pub fn is_sequencer(&self) -> bool {
-        self.sequencer || self.devnet
+        self.role().is_sequencer()
     }
 
     pub fn is_devnet(&self) -> bool {
-        self.devnet
+        self.role().is_devnet()
     }
 }
 
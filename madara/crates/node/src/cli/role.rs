@@ -0,0 +1,100 @@
+use std::fmt;
+
+use mc_db::pruning::PruningMode;
+use mp_utils::service::MadaraServiceId;
+
+/// Number of blocks whose state is kept by a light node, unless `--pruning` keeps fewer of them.
+pub const LIGHT_KEEP_BLOCKS: u64 = 1_000;
+
+/// What the node does, which decides the services it starts and how its database is set up. This is selected with one
+/// of `--sequencer`, `--devnet`, `--full`, `--archive` or `--light`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeRole {
+    /// Produces the blocks of its own chain from its mempool.
+    Sequencer,
+    /// A sequencer for local testing, with predeployed contracts.
+    Devnet,
+    /// Syncs a chain from the feeder gateway. The historical state is kept according to `--pruning`.
+    Full,
+    /// A full node which keeps the state at every block, and refuses to prune it.
+    Archive,
+    /// A full node for constrained machines: the historical state is pruned.
+    Light,
+}
+
+impl NodeRole {
+    /// Whether the node produces blocks, in which case it has a mempool and does not sync from the feeder gateway.
+    pub fn is_sequencer(&self) -> bool {
+        matches!(self, Self::Sequencer | Self::Devnet)
+    }
+
+    pub fn is_devnet(&self) -> bool {
+        *self == Self::Devnet
+    }
+
+    /// The service which brings the new blocks to the node: block production for a sequencer, the sync otherwise.
+    pub fn block_source(&self) -> MadaraServiceId {
+        if self.is_sequencer() {
+            MadaraServiceId::BlockProduction
+        } else {
+            MadaraServiceId::L2Sync
+        }
+    }
+
+    /// Whether the admin RPC serves the block production and mempool namespaces, which are only meaningful on a node
+    /// producing blocks.
+    pub fn serves_block_production_rpc(&self) -> bool {
+        self.is_sequencer()
+    }
+
+    /// The pruning mode of the database given the one requested with `--pruning`, if any.
+    pub fn pruning_mode(&self, requested: Option<PruningMode>) -> anyhow::Result<PruningMode> {
+        match (self, requested) {
+            (Self::Archive, Some(PruningMode::Pruned { .. })) => {
+                anyhow::bail!("An archive node keeps the state at every block, it cannot be run with `--pruning`")
+            }
+            (Self::Light, Some(PruningMode::Archive)) => {
+                anyhow::bail!("A light node prunes the historical state, it cannot be run with `--pruning archive`")
+            }
+            (Self::Light, None) => Ok(PruningMode::Pruned { keep_blocks: LIGHT_KEEP_BLOCKS }),
+            (_, requested) => Ok(requested.unwrap_or(PruningMode::Archive)),
+        }
+    }
+}
+
+impl fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sequencer => write!(f, "Sequencer"),
+            Self::Devnet => write!(f, "Devnet"),
+            Self::Full => write!(f, "Full Node"),
+            Self::Archive => write!(f, "Archive Node"),
+            Self::Light => write!(f, "Light Node"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_role_pruning_mode() {
+        let pruned = PruningMode::Pruned { keep_blocks: 10 };
+        assert_eq!(NodeRole::Full.pruning_mode(None).unwrap(), PruningMode::Archive);
+        assert_eq!(NodeRole::Full.pruning_mode(Some(PruningMode::Archive)).unwrap(), PruningMode::Archive);
+        assert_eq!(NodeRole::Full.pruning_mode(Some(pruned)).unwrap(), pruned);
+        assert!(NodeRole::Archive.pruning_mode(Some(pruned)).is_err());
+        assert_eq!(NodeRole::Light.pruning_mode(None).unwrap(), PruningMode::Pruned { keep_blocks: LIGHT_KEEP_BLOCKS });
+        assert_eq!(NodeRole::Light.pruning_mode(Some(pruned)).unwrap(), pruned);
+        assert!(NodeRole::Light.pruning_mode(Some(PruningMode::Archive)).is_err());
+    }
+
+    #[test]
+    fn test_node_role_services() {
+        assert_eq!(NodeRole::Devnet.block_source(), MadaraServiceId::BlockProduction);
+        assert_eq!(NodeRole::Light.block_source(), MadaraServiceId::L2Sync);
+        assert!(NodeRole::Sequencer.serves_block_production_rpc());
+        assert!(!NodeRole::Archive.serves_block_production_rpc());
+    }
+}
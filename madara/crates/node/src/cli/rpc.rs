@@ -1,9 +1,17 @@
 use std::convert::Infallible;
 use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use jsonrpsee::server::BatchRequestConfig;
+use mc_rpc::outside_execution::OutsideExecutionConfig;
 use mc_rpc::StorageProofConfig;
+use mp_utils::crypto::ZeroingPrivateKey;
+use mp_utils::parsers::{parse_duration, parse_felt};
+use starknet_types_core::felt::Felt;
 
 /// The default port.
 pub const RPC_DEFAULT_PORT: u16 = 9944;
@@ -15,6 +23,8 @@ pub const RPC_DEFAULT_MAX_SUBS_PER_CONN: u32 = 1024;
 pub const RPC_DEFAULT_MAX_REQUEST_SIZE_MB: u32 = 15;
 /// The default max response size in MB.
 pub const RPC_DEFAULT_MAX_RESPONSE_SIZE_MB: u32 = 15;
+/// The default max size of a page of a paginated method in MB.
+pub const RPC_DEFAULT_MAX_PAGE_SIZE_MB: u32 = 10;
 /// The default number of connection..
 pub const RPC_DEFAULT_MAX_CONNECTIONS: u32 = 100;
 /// The default number of messages the RPC server
@@ -86,6 +96,12 @@ pub struct RpcParams {
     #[arg(env = "MADARA_RPC_MAX_RESPONSE_SIZE", long, default_value_t = RPC_DEFAULT_MAX_RESPONSE_SIZE_MB)]
     pub rpc_max_response_size: u32,
 
+    /// Set the maximum size of a page of a paginated method, such as `starknet_getEvents`, in megabytes. Pages with
+    /// large events are cut short with a continuation token, even when they have fewer items than requested. This
+    /// should be lower than the maximum response size.
+    #[arg(env = "MADARA_RPC_MAX_PAGE_SIZE", long, default_value_t = RPC_DEFAULT_MAX_PAGE_SIZE_MB)]
+    pub rpc_max_page_size: u32,
+
     /// Set the maximum concurrent subscriptions per connection.
     #[arg(env = "MADARA_RPC_MAX_SUBSCRIPTIONS_PER_CONNECTION", long, default_value_t = RPC_DEFAULT_MAX_SUBS_PER_CONN)]
     pub rpc_max_subscriptions_per_connection: u32,
@@ -111,6 +127,12 @@ pub struct RpcParams {
     #[arg(env = "MADARA_RPC_DISABLE_BATCH_REQUESTS", long, alias = "rpc_no_batch_requests", conflicts_with_all = &["rpc_max_batch_request_len"])]
     pub rpc_disable_batch_requests: bool,
 
+    /// Methods which are not served, as if they did not exist, on every version. Each entry is either a method name,
+    /// such as `starknet_traceTransaction`, or a prefix followed by `*`, such as `starknet_trace*` to disable the
+    /// traces on public infrastructure.
+    #[arg(env = "MADARA_RPC_DISABLE_METHODS", long, value_name = "METHOD", value_delimiter = ',')]
+    pub rpc_disable_methods: Vec<String>,
+
     /// Limit the max length for an RPC batch request.
     #[arg(env = "MADARA_RPC_MAX_BATCH_REQUEST_LEN", long, conflicts_with_all = &["rpc_disable_batch_requests"], value_name = "LEN")]
     pub rpc_max_batch_request_len: Option<u32>,
@@ -141,7 +163,8 @@ pub struct RpcParams {
     /// When getting a storage proof, the database will revert the global merkle trie in-memory up until the
     /// block_n specified in the request. If that block_n is too far back in the past, this could make
     /// the node vulnerable to DoS attacks.
-    /// By default, this is set to 0: we do not serve storage proofs except for the current latest block.
+    /// By default, this is set to 0: we do not serve storage proofs except for the current latest block, and the
+    /// blocks of the database snapshots kept with `--db-max-kept-snapshots`, which need no reverting.
     /// For best performance, you should also set `--db-max-saved-trie-logs`, `--db-max-kept-snapshots` and
     /// `--db-snapshot-interval` to make reverting much faster.
     #[arg(env = "MADARA_RPC_STORAGE_PROOF_MAX_DISTANCE", long, default_value_t = 0)]
@@ -156,6 +179,124 @@ pub struct RpcParams {
     /// storage is queried count as one each.
     #[arg(env = "MADARA_RPC_STORAGE_PROOF_MAX_TRIES", long, default_value_t = 5)]
     pub rpc_storage_proof_max_tries: usize,
+
+    /// Address of the account used to submit SNIP-9 outside executions received on the
+    /// `madara_addOutsideExecution` method, in sequencer mode. This account pays for the outside executions.
+    #[arg(
+        env = "MADARA_RPC_OUTSIDE_EXECUTION_ACCOUNT",
+        long,
+        value_parser = parse_felt,
+        value_name = "ADDRESS",
+        requires = "rpc_outside_execution_private_key"
+    )]
+    pub rpc_outside_execution_account: Option<Felt>,
+
+    /// Private key of the outside execution account.
+    #[arg(
+        env = "MADARA_RPC_OUTSIDE_EXECUTION_PRIVATE_KEY",
+        long,
+        value_parser = parse_private_key,
+        value_name = "PRIVATE KEY",
+        hide_env_values = true,
+        requires = "rpc_outside_execution_account"
+    )]
+    pub rpc_outside_execution_private_key: Option<Arc<ZeroingPrivateKey>>,
+
+    /// Max amount of L1 gas a single outside execution can use.
+    #[arg(env = "MADARA_RPC_OUTSIDE_EXECUTION_MAX_L1_GAS", long, default_value_t = 100_000)]
+    pub rpc_outside_execution_max_l1_gas: u64,
+
+    /// Max fees, in fri, the outside execution account sponsors for a single user account over
+    /// `--rpc-outside-execution-budget-period`. The max fee of each outside execution is counted. Default: 10 STRK.
+    #[arg(env = "MADARA_RPC_OUTSIDE_EXECUTION_FEE_BUDGET", long, default_value_t = 10_000_000_000_000_000_000)]
+    pub rpc_outside_execution_fee_budget: u128,
+
+    /// Max fees, in fri, the outside execution account sponsors for all the user accounts together over
+    /// `--rpc-outside-execution-budget-period`. Default: 100 STRK.
+    #[arg(env = "MADARA_RPC_OUTSIDE_EXECUTION_TOTAL_FEE_BUDGET", long, default_value_t = 100_000_000_000_000_000_000)]
+    pub rpc_outside_execution_total_fee_budget: u128,
+
+    /// Period over which the outside execution fee budgets apply.
+    #[arg(
+        env = "MADARA_RPC_OUTSIDE_EXECUTION_BUDGET_PERIOD",
+        long,
+        value_parser = parse_duration,
+        default_value = "1h"
+    )]
+    pub rpc_outside_execution_budget_period: Duration,
+
+    /// Enable `madara_submitFullBlock` on the admin endpoint, to import blocks built outside of the node, and set the
+    /// key the submissions are authenticated with. Blocks can only be submitted while the sync and the block
+    /// production are disabled.
+    #[arg(env = "MADARA_RPC_BLOCK_SUBMISSION_KEY", long, value_name = "KEY", hide_env_values = true)]
+    pub rpc_block_submission_key: Option<String>,
+
+    /// Compress the responses of getClass and getCompiledCasm with brotli or gzip, when the client accepts it in its
+    /// `Accept-Encoding` header. Classes weigh up to a few megabytes and compress well.
+    #[arg(env = "MADARA_RPC_COMPRESSION", long)]
+    pub rpc_compression: bool,
+
+    /// Reject the expensive requests (traces, simulations, and event or address activity scans over large block
+    /// ranges) with a retryable error while the sync is under pressure, so that they do not slow it down further. The
+    /// sync is under pressure when the block import falls behind the fetched blocks, or when the database stalls
+    /// writes.
+    #[arg(env = "MADARA_RPC_LOAD_SHEDDING", long)]
+    pub rpc_load_shedding: bool,
+
+    /// Number of fetched blocks waiting to be imported above which the sync is considered under pressure.
+    #[arg(env = "MADARA_RPC_LOAD_SHEDDING_IMPORT_BACKLOG", long, default_value_t = 16)]
+    pub rpc_load_shedding_import_backlog: u64,
+
+    /// Event and address activity scans (getEvents, madara_getEventsBackward and madara_getAddressActivity) over more
+    /// blocks than this are rejected while the sync is under pressure.
+    #[arg(env = "MADARA_RPC_LOAD_SHEDDING_MAX_EVENTS_RANGE", long, default_value_t = 100)]
+    pub rpc_load_shedding_max_events_range: u64,
+
+    /// How long expensive requests keep being rejected once the sync is no longer under pressure.
+    #[arg(env = "MADARA_RPC_LOAD_SHEDDING_COOLDOWN", long, value_parser = parse_duration, default_value = "10s")]
+    pub rpc_load_shedding_cooldown: Duration,
+
+    /// Account the usage of the user RPC endpoint per tenant: requests and compute units per method class, and
+    /// bandwidth. The usage is periodically exported to this file, so that it can be used to bill or rate limit
+    /// tenants. Requests are accounted to the tenant of their API key, see `--rpc-usage-api-keys`, or else to their
+    /// IP address.
+    #[arg(env = "MADARA_RPC_USAGE_EXPORT_PATH", long, value_name = "PATH")]
+    pub rpc_usage_export_path: Option<PathBuf>,
+
+    /// Format of the usage export. `json-lines` appends the usage of every tenant over the last interval to the file,
+    /// `prometheus` overwrites the file with the total usage in the Prometheus text format, for the textfile
+    /// collector of the node exporter.
+    #[arg(env = "MADARA_RPC_USAGE_EXPORT_FORMAT", long, value_enum, default_value_t = UsageExportFormat::JsonLines)]
+    pub rpc_usage_export_format: UsageExportFormat,
+
+    /// Interval between two usage exports.
+    #[arg(env = "MADARA_RPC_USAGE_EXPORT_INTERVAL", long, value_parser = parse_duration, default_value = "1min")]
+    pub rpc_usage_export_interval: Duration,
+
+    /// HTTP header holding the API key of the requests.
+    #[arg(env = "MADARA_RPC_USAGE_API_KEY_HEADER", long, default_value = "x-api-key")]
+    pub rpc_usage_api_key_header: String,
+
+    /// File with the API keys of the tenants, one `<tenant> <api key>` pair per line. Requests with an API key which
+    /// is not in this file are accounted to their IP address.
+    #[arg(env = "MADARA_RPC_USAGE_API_KEYS", long, value_name = "PATH")]
+    pub rpc_usage_api_keys: Option<PathBuf>,
+
+    /// Maximum number of tenants accounted separately. The usage of the tenants over it is accounted as `other`.
+    #[arg(env = "MADARA_RPC_USAGE_MAX_TENANTS", long, default_value_t = 10_000)]
+    pub rpc_usage_max_tenants: usize,
+}
+
+/// Format of the RPC usage export, see `--rpc-usage-export-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UsageExportFormat {
+    JsonLines,
+    Prometheus,
+}
+
+fn parse_private_key(s: &str) -> anyhow::Result<Arc<ZeroingPrivateKey>> {
+    let mut private_key = parse_felt(s)?;
+    Ok(Arc::new(ZeroingPrivateKey::new(&mut private_key)))
 }
 
 impl RpcParams {
@@ -216,4 +357,22 @@ impl RpcParams {
             max_distance: self.rpc_storage_proof_max_distance,
         }
     }
+
+    /// Maximum size in bytes of a page of a paginated method.
+    pub fn max_page_bytes(&self) -> usize {
+        (self.rpc_max_page_size as usize).saturating_mul(1024 * 1024)
+    }
+
+    pub fn outside_execution_config(&self) -> Option<OutsideExecutionConfig> {
+        let (account_address, private_key) =
+            (self.rpc_outside_execution_account?, self.rpc_outside_execution_private_key.clone()?);
+        Some(OutsideExecutionConfig {
+            account_address,
+            private_key,
+            max_l1_gas: self.rpc_outside_execution_max_l1_gas,
+            fee_budget: self.rpc_outside_execution_fee_budget,
+            total_fee_budget: self.rpc_outside_execution_total_fee_budget,
+            budget_period: self.rpc_outside_execution_budget_period,
+        })
+    }
 }
@@ -0,0 +1,29 @@
+//! Madara node.
+//!
+//! Besides the `madara` binary, the node can be embedded in another process, for example by integration tests or by
+//! products built on top of Madara, with [`MadaraNodeBuilder`]:
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use clap::Parser;
+//! use madara::{MadaraNodeBuilder, RunCmd};
+//!
+//! let run_cmd = RunCmd::parse_from(["madara", "--name", "embedded", "--devnet", "--no-l1-sync"]);
+//! let node =
+//!     MadaraNodeBuilder::new(run_cmd).with_base_path("/tmp/madara-embedded").with_rpc_ports(0, 0).start().await?;
+//! let rpc_addr = node.rpc_addr().await;
+//! // ...
+//! node.shutdown();
+//! node.stopped().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod cli;
+mod node;
+mod service;
+mod util;
+
+pub use cli::RunCmd;
+pub use node::{MadaraNode, MadaraNodeBuilder};
+pub use util::{raise_fdlimit, setup_rayon_threadpool};
@@ -0,0 +1,697 @@
+//! Setup of the node services, shared by the `madara` binary and the nodes embedded in another process.
+
+use crate::cli::{NodeRole, RunCmd};
+use crate::service::{AlertService, BlockProductionService, GatewayService, L1SyncService, L2SyncService, RpcService};
+use crate::util;
+use anyhow::{bail, Context};
+use http::{HeaderName, HeaderValue};
+use mc_block_import::{BlockImporter, BlockValidationContext};
+use mc_block_production::warmup::{warm_up_execution_caches, WarmupConfig};
+use mc_db::{DatabaseService, MadaraBackend};
+use mc_gateway_client::GatewayProvider;
+use mc_mempool::custom_transaction::CustomTransactionHandler;
+use mc_mempool::paymaster::PaymasterPolicy;
+use mc_mempool::{GasPriceProvider, L1DataProvider, Mempool, MempoolLimits};
+use mc_rpc::outside_execution::OutsideExecutor;
+use mc_rpc::providers::{AddTransactionProvider, ForwardToProvider, MempoolAddTxProvider};
+use mc_sync::block_files::ImportBlocksConfig;
+use mc_sync::fetch::fetchers::WarpUpdateConfig;
+use mc_telemetry::{SysInfo, TelemetryService};
+use mp_chain_config::registry::ChainRegistry;
+use mp_chain_config::ChainConfig;
+use mp_oracle::pragma::PragmaOracleBuilder;
+use mp_utils::service::{MadaraServiceId, ServiceContext, ServiceMonitor};
+use starknet_api::core::ChainId;
+use starknet_types_core::felt::Felt;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+const GREET_IMPL_NAME: &str = "Madara";
+const GREET_SUPPORT_URL: &str = "https://github.com/madara-alliance/madara/issues";
+
+/// Configures and starts a Madara node in the current process.
+///
+/// The node is configured with a [`RunCmd`], as parsed from the command line arguments of the `madara` binary, for
+/// example with `RunCmd::parse_from(["madara", "--devnet"])`. Use port 0 for the RPC servers to listen on a random
+/// port, see [`MadaraNode::rpc_addr`].
+pub struct MadaraNodeBuilder {
+    run_cmd: RunCmd,
+    chain_config: Option<Arc<ChainConfig>>,
+    paymaster_policy: Option<Arc<dyn PaymasterPolicy>>,
+    custom_transaction_handler: Option<Arc<dyn CustomTransactionHandler>>,
+}
+
+impl MadaraNodeBuilder {
+    /// The arguments presets of `run_cmd` are applied.
+    pub fn new(run_cmd: RunCmd) -> Self {
+        Self {
+            run_cmd: run_cmd.apply_arg_preset(),
+            chain_config: None,
+            paymaster_policy: None,
+            custom_transaction_handler: None,
+        }
+    }
+
+    /// Runs the node with this chain config, instead of the one given by the network, preset or chain config file.
+    pub fn with_chain_config(mut self, chain_config: Arc<ChainConfig>) -> Self {
+        self.chain_config = Some(chain_config);
+        self
+    }
+
+    /// Uses this paymaster policy in the mempool and block production, instead of the default one which sponsors
+    /// nothing. See [`PaymasterPolicy`].
+    pub fn with_paymaster_policy(mut self, paymaster_policy: Arc<dyn PaymasterPolicy>) -> Self {
+        self.paymaster_policy = Some(paymaster_policy);
+        self
+    }
+
+    /// Hands the custom transactions enabled in the chain config to this handler, instead of the default one which
+    /// rejects them. See [`CustomTransactionHandler`].
+    pub fn with_custom_transaction_handler(
+        mut self,
+        custom_transaction_handler: Arc<dyn CustomTransactionHandler>,
+    ) -> Self {
+        self.custom_transaction_handler = Some(custom_transaction_handler);
+        self
+    }
+
+    /// Stores the database in `base_path`.
+    pub fn with_base_path(mut self, base_path: impl Into<PathBuf>) -> Self {
+        self.run_cmd.db_params.base_path = base_path.into();
+        self
+    }
+
+    /// Enables or disables the user RPC server.
+    pub fn with_rpc(mut self, enabled: bool) -> Self {
+        self.run_cmd.rpc_params.rpc_disable = !enabled;
+        self
+    }
+
+    /// Enables or disables the admin RPC server.
+    pub fn with_rpc_admin(mut self, enabled: bool) -> Self {
+        self.run_cmd.rpc_params.rpc_admin = enabled;
+        self
+    }
+
+    /// Sets the ports of the user and admin RPC servers.
+    pub fn with_rpc_ports(mut self, user_port: u16, admin_port: u16) -> Self {
+        self.run_cmd.rpc_params.rpc_port = user_port;
+        self.run_cmd.rpc_params.rpc_admin_port = admin_port;
+        self
+    }
+
+    /// Enables or disables the feeder gateway.
+    pub fn with_gateway(mut self, enabled: bool) -> Self {
+        self.run_cmd.gateway_params.feeder_gateway_enable = enabled;
+        self
+    }
+
+    /// Enables or disables the L1 sync.
+    pub fn with_l1_sync(mut self, enabled: bool) -> Self {
+        self.run_cmd.l1_sync_params.l1_sync_disabled = !enabled;
+        self
+    }
+
+    /// Resolves the chain config. When a chain registry is provided, the chain config is the one it holds. Otherwise,
+    /// if it's a sequencer or a devnet we set the mandatory chain config. If it's a full node we set the chain config
+    /// from the network or the custom chain config.
+    fn chain_config(&self) -> anyhow::Result<(Option<ChainRegistry>, Arc<ChainConfig>)> {
+        let chain_registry = self.run_cmd.chain_registry().context("Loading the chain registry")?;
+        let chain_config = if let Some(chain_config) = &self.chain_config {
+            Arc::clone(chain_config)
+        } else if let Some(chain_registry) = &chain_registry {
+            Arc::new(chain_registry.chain_config()?)
+        } else if self.run_cmd.is_sequencer() {
+            self.run_cmd.chain_config()?
+        } else if self.run_cmd.network.is_some() {
+            self.run_cmd.set_preset_from_network()?
+        } else {
+            self.run_cmd.chain_config()?
+        };
+        Ok((chain_registry, chain_config))
+    }
+
+    /// Opens the database, and checks it against the block hashes pinned by the chain registry.
+    async fn open_database(
+        &self,
+        chain_config: &Arc<ChainConfig>,
+        chain_registry: Option<&ChainRegistry>,
+    ) -> anyhow::Result<(DatabaseService, BTreeMap<u64, Felt>)> {
+        let run_cmd = &self.run_cmd;
+        if let Some(backup_dir) = &run_cmd.db_params.restore_backup_from {
+            mc_db::restore_latest_backup(backup_dir, &run_cmd.db_params.base_path)
+                .context("Restoring database backup")?;
+        }
+
+        let service_db = DatabaseService::new(
+            &run_cmd.db_params.base_path,
+            run_cmd.db_params.backup_dir.clone(),
+            run_cmd.db_params.restore_from_latest_backup,
+            Arc::clone(chain_config),
+            run_cmd.db_params.trie_log_config(run_cmd.l2_sync_params.sync_profile),
+            run_cmd.role().pruning_mode(run_cmd.db_params.pruning)?,
+        )
+        .await
+        .context("Initializing db service")?;
+
+        let checkpoints = match chain_registry {
+            Some(chain_registry) => chain_registry.checkpoints()?,
+            None => Default::default(),
+        };
+        util::check_checkpoints(service_db.backend(), &checkpoints)?;
+
+        Ok((service_db, checkpoints))
+    }
+
+    /// Verifies the blocks in the database instead of starting the node, see `--verify-chain`. The database is opened
+    /// read-only, so that it is audited as it is, and can be verified while a node is running on it.
+    pub async fn verify_chain(self) -> anyhow::Result<()> {
+        let (chain_registry, chain_config) = self.chain_config()?;
+        let run_cmd = &self.run_cmd;
+        let service_db = DatabaseService::open_read_only(&run_cmd.db_params.base_path, Arc::clone(&chain_config))
+            .context("Opening the database read-only")?;
+        let checkpoints = match &chain_registry {
+            Some(chain_registry) => chain_registry.checkpoints()?,
+            None => Default::default(),
+        };
+        util::check_checkpoints(service_db.backend(), &checkpoints)?;
+
+        let importer =
+            BlockImporter::new(Arc::clone(service_db.backend()), None).context("Initializing importer service")?;
+        let validation = BlockValidationContext::new(chain_config.chain_id.clone())
+            .compute_v0_13_2_hashes(self.run_cmd.l2_sync_params.compute_v0_13_2_hashes);
+        util::verify_chain(service_db.backend(), &importer, self.run_cmd.db_params.verify_chain_from, validation).await
+    }
+
+    /// Imports the blocks of `dir` instead of starting the node, see `--import-blocks`. The blocks are checked like
+    /// the blocks fetched by the sync.
+    pub async fn import_blocks(self, dir: &Path) -> anyhow::Result<()> {
+        let (chain_registry, chain_config) = self.chain_config()?;
+        let (service_db, checkpoints) = self.open_database(&chain_config, chain_registry.as_ref()).await?;
+
+        let importer =
+            BlockImporter::new(Arc::clone(service_db.backend()), None).context("Initializing importer service")?;
+        let l2_sync_params = &self.run_cmd.l2_sync_params;
+        let config = ImportBlocksConfig {
+            validation: mc_sync::l2::sync_validation_context(
+                chain_config.chain_id.clone(),
+                !l2_sync_params.disable_root,
+                l2_sync_params.sync_strict_validation,
+                l2_sync_params.compute_v0_13_2_hashes,
+                l2_sync_params.verify_signatures(&chain_config)?,
+            ),
+            checkpoints,
+        };
+        mc_sync::block_files::import_blocks(service_db.backend(), &importer, dir, &config).await?;
+        Ok(())
+    }
+
+    /// Exports the blocks `from_block_n..=to_block_n` to `dir` instead of starting the node, see `--export-blocks`.
+    pub async fn export_blocks(self, dir: &Path, from_block_n: u64, to_block_n: Option<u64>) -> anyhow::Result<()> {
+        let (chain_registry, chain_config) = self.chain_config()?;
+        let (service_db, _) = self.open_database(&chain_config, chain_registry.as_ref()).await?;
+        mc_sync::block_files::export_blocks(service_db.backend(), dir, from_block_n, to_block_n).await?;
+        Ok(())
+    }
+
+    /// Sets up the services and starts them. The node keeps running until [`MadaraNode::shutdown`] is called or the
+    /// process receives `SIGINT` or `SIGTERM`.
+    pub async fn start(mut self) -> anyhow::Result<MadaraNode> {
+        let (chain_registry, chain_config) = self.chain_config()?;
+
+        // If block time is inferior to the tick time, then only empty blocks will
+        // be produced as we will never update the pending block before storing it.
+        if self.run_cmd.is_sequencer() && chain_config.block_time < chain_config.pending_block_update_time {
+            anyhow::bail!(
+                "Block time ({}s) cannot be less than the pending block update time ({}s), as this will yield only empty blocks",
+                chain_config.block_time.as_secs(),
+                chain_config.pending_block_update_time.as_secs()
+            );
+        }
+
+        // Check if the devnet is running with the correct chain id. This is purely
+        // to avoid accidental setups which would allow for replay attacks. This is
+        // possible if the devnet has the same chain id as another popular chain,
+        // allowing txs which occur on it to also be replayed on that other chain.
+        if self.run_cmd.is_devnet()
+            && (chain_config.chain_id == ChainId::Mainnet || chain_config.chain_id == ChainId::Sepolia)
+            && !self.run_cmd.devnet_unsafe
+        {
+            anyhow::bail!("You're running a devnet with the network config of {0}. This means that devnet transactions can be replayed on the actual {0} network. Use `--network=devnet` instead or force this configuration with `--devnet-unsafe`.", chain_config.chain_name);
+        }
+
+        let node_name = self.run_cmd.node_name_or_provide().await.to_string();
+        let run_cmd = &self.run_cmd;
+        let node_version = env!("MADARA_BUILD_VERSION");
+
+        tracing::info!("🥷 {} Node", GREET_IMPL_NAME);
+        tracing::info!("✌️  Version {}", node_version);
+        tracing::info!("💁 Support URL: {}", GREET_SUPPORT_URL);
+        tracing::info!("🏷 Node Name: {}", node_name);
+        let role = run_cmd.role();
+        tracing::info!("👤 Role: {}", role);
+        tracing::info!("🌐 Network: {} (chain id `{}`)", chain_config.chain_name, chain_config.chain_id);
+        run_cmd.args_preset.greet();
+
+        let sys_info = SysInfo::probe();
+        sys_info.show();
+
+        // ===================================================================== //
+        //                             SERVICES (SETUP)                          //
+        // ===================================================================== //
+
+        // Telemetry
+
+        let http_config = run_cmd.http_params.http_client_config();
+
+        let service_telemetry: TelemetryService =
+            TelemetryService::new(run_cmd.telemetry_params.telemetry_endpoints.clone())
+                .context("Initializing telemetry service")?
+                .with_http_config(http_config.clone());
+
+        // Database
+
+        let (service_db, checkpoints) = self.open_database(&chain_config, chain_registry.as_ref()).await?;
+        let backend = Arc::clone(service_db.backend());
+
+        // Alerts
+
+        let l1_sync_enabled = !run_cmd.l1_sync_params.l1_sync_disabled;
+        let l1_endpoint_some = run_cmd.l1_sync_params.l1_endpoint.is_some();
+        let l1_sync_active = l1_sync_enabled && (l1_endpoint_some || !run_cmd.is_devnet());
+
+        let service_alerts = AlertService::new(
+            run_cmd.alert_params.clone(),
+            Arc::clone(&backend),
+            run_cmd.db_params.base_path.clone(),
+            node_name.clone(),
+            l1_sync_active,
+            http_config.clone(),
+        )
+        .await
+        .context("Initializing alert service")?;
+
+        // L1 Sync
+
+        let mut l1_gas_setter = GasPriceProvider::new();
+
+        if let Some(fix_gas) = run_cmd.l1_sync_params.gas_price {
+            l1_gas_setter.update_eth_l1_gas_price(fix_gas as u128);
+            l1_gas_setter.set_gas_price_sync_enabled(false);
+        }
+        if let Some(fix_blob_gas) = run_cmd.l1_sync_params.blob_gas_price {
+            l1_gas_setter.update_eth_l1_data_gas_price(fix_blob_gas as u128);
+            l1_gas_setter.set_data_gas_price_sync_enabled(false);
+        }
+        if let Some(strk_fix_gas) = run_cmd.l1_sync_params.strk_gas_price {
+            l1_gas_setter.update_strk_l1_gas_price(strk_fix_gas as u128);
+            l1_gas_setter.set_strk_gas_price_sync_enabled(false);
+        }
+        if let Some(strk_fix_blob_gas) = run_cmd.l1_sync_params.strk_blob_gas_price {
+            l1_gas_setter.update_strk_l1_data_gas_price(strk_fix_blob_gas as u128);
+            l1_gas_setter.set_strk_data_gas_price_sync_enabled(false);
+        }
+        if let Some(ref oracle_url) = run_cmd.l1_sync_params.oracle_url {
+            if let Some(ref oracle_api_key) = run_cmd.l1_sync_params.oracle_api_key {
+                let oracle = PragmaOracleBuilder::new()
+                    .with_api_url(oracle_url.clone())
+                    .with_api_key(oracle_api_key.clone())
+                    .with_http_config(http_config.clone())
+                    .build();
+                l1_gas_setter.set_oracle_provider(oracle);
+            }
+        }
+
+        if role == NodeRole::Sequencer
+            && !run_cmd.l1_sync_params.l1_sync_disabled
+            && l1_gas_setter.is_oracle_needed()
+            && l1_gas_setter.oracle_provider.is_none()
+        {
+            bail!("STRK gas is not fixed and oracle is not provided");
+        }
+
+        let l1_data_provider: Arc<dyn L1DataProvider> = Arc::new(l1_gas_setter.clone());
+
+        // declare mempool here so that it can be used to process l1->l2 messages in the l1 service
+        let mempool_limits = MempoolLimits {
+            outside_execution_executor: self
+                .run_cmd
+                .rpc_params
+                .outside_execution_config()
+                .map(|outside_execution| outside_execution.account_address),
+            ..MempoolLimits::new(&chain_config)
+        };
+        let mut mempool = Mempool::new(Arc::clone(service_db.backend()), Arc::clone(&l1_data_provider), mempool_limits);
+        if let Some(paymaster_policy) = &self.paymaster_policy {
+            mempool = mempool.with_paymaster_policy(Arc::clone(paymaster_policy));
+        }
+        if let Some(custom_transaction_handler) = &self.custom_transaction_handler {
+            mempool = mempool.with_custom_transaction_handler(Arc::clone(custom_transaction_handler));
+        }
+        mempool.load_txs_from_db().context("Loading mempool transactions")?;
+        let mempool = Arc::new(mempool);
+
+        let service_l1_sync = L1SyncService::new(
+            &run_cmd.l1_sync_params,
+            &service_db,
+            l1_gas_setter,
+            chain_config.chain_id.clone(),
+            chain_config.eth_core_contract_address,
+            run_cmd.is_sequencer(),
+            run_cmd.is_devnet(),
+            Arc::clone(&mempool),
+            &http_config,
+        )
+        .await
+        .context("Initializing the l1 sync service")?;
+
+        // L2 Sync
+
+        let mut importer =
+            BlockImporter::new(Arc::clone(service_db.backend()), run_cmd.l2_sync_params.unsafe_starting_block)
+                .context("Initializing importer service")?
+                .with_class_cache_size(run_cmd.l2_sync_params.sync_class_cache_size);
+        if let Some(n_threads) = run_cmd.l2_sync_params.class_compilation_threads {
+            importer = importer.with_class_compilation_threads(n_threads.get())?;
+        }
+        let importer = Arc::new(importer);
+
+        let warp_update = if run_cmd.args_preset.warp_update_receiver {
+            let mut deferred_service_start = vec![];
+            let mut deferred_service_stop = vec![];
+
+            if !run_cmd.rpc_params.rpc_disable {
+                deferred_service_start.push(MadaraServiceId::RpcUser);
+            }
+
+            if run_cmd.rpc_params.rpc_admin {
+                deferred_service_start.push(MadaraServiceId::RpcAdmin);
+            }
+
+            if run_cmd.gateway_params.feeder_gateway_enable {
+                deferred_service_start.push(MadaraServiceId::Gateway);
+            }
+
+            if run_cmd.telemetry_params.telemetry {
+                deferred_service_start.push(MadaraServiceId::Telemetry);
+            }
+
+            // Once the warp update is done, the sync hands over to the block source of the node.
+            if role.block_source() != MadaraServiceId::L2Sync {
+                deferred_service_start.push(role.block_source());
+                deferred_service_stop.push(MadaraServiceId::L2Sync);
+            }
+
+            Some(WarpUpdateConfig {
+                warp_update_port_rpc: run_cmd.l2_sync_params.warp_update_port_rpc,
+                warp_update_port_fgw: run_cmd.l2_sync_params.warp_update_port_fgw,
+                warp_update_shutdown_sender: run_cmd.l2_sync_params.warp_update_shutdown_sender,
+                warp_update_shutdown_receiver: run_cmd.l2_sync_params.warp_update_shutdown_receiver,
+                deferred_service_start,
+                deferred_service_stop,
+            })
+        } else {
+            None
+        };
+
+        let service_l2_sync = L2SyncService::new(
+            &run_cmd.l2_sync_params,
+            Arc::clone(&chain_config),
+            &service_db,
+            importer,
+            service_telemetry.new_handle(),
+            service_alerts.handle(),
+            warp_update,
+            checkpoints,
+            http_config.clone(),
+        )
+        .await
+        .context("Initializing sync service")?;
+
+        let mut provider =
+            GatewayProvider::new(chain_config.gateway_url.clone(), chain_config.feeder_gateway_url.clone())
+                .with_http_config(http_config)
+                .with_strict_schema(run_cmd.l2_sync_params.gateway_strict_schema);
+
+        // gateway api key is needed for declare transactions on mainnet
+        if let Some(api_key) = run_cmd.l2_sync_params.gateway_key.clone() {
+            provider.add_header(
+                HeaderName::from_static("x-throttling-bypass"),
+                HeaderValue::from_str(&api_key).with_context(|| "Invalid API key format")?,
+            )
+        }
+
+        // Block production
+
+        let importer = Arc::new(
+            BlockImporter::new(Arc::clone(service_db.backend()), run_cmd.l2_sync_params.unsafe_starting_block)
+                .context("Initializing importer service")?,
+        );
+        let service_block_production = BlockProductionService::new(
+            &run_cmd.block_production_params,
+            &service_db,
+            Arc::clone(&mempool),
+            Arc::clone(&importer),
+            Arc::clone(&l1_data_provider),
+        )?;
+
+        // Add transaction provider
+        let add_tx_provider_l2_sync: Arc<dyn AddTransactionProvider> = Arc::new(ForwardToProvider::new(provider));
+        let add_tx_provider_mempool: Arc<dyn AddTransactionProvider> =
+            Arc::new(MempoolAddTxProvider::new(Arc::clone(&mempool)));
+
+        // Outside executions are accepted on both RPC endpoints, through a single executor.
+        let outside_executor =
+            run_cmd.rpc_params.outside_execution_config().map(|config| Arc::new(OutsideExecutor::new(config)));
+
+        // User-facing RPC
+
+        let service_rpc_user = RpcService::user(
+            run_cmd.rpc_params.clone(),
+            Arc::clone(service_db.backend()),
+            Arc::clone(&add_tx_provider_l2_sync),
+            Arc::clone(&add_tx_provider_mempool),
+            run_cmd.is_sequencer().then(|| Arc::clone(&mempool)),
+            outside_executor.clone(),
+            role,
+        );
+
+        // Admin-facing RPC (for node operators)
+
+        let service_rpc_admin = RpcService::admin(
+            run_cmd.rpc_params.clone(),
+            Arc::clone(service_db.backend()),
+            Arc::clone(&add_tx_provider_l2_sync),
+            Arc::clone(&add_tx_provider_mempool),
+            run_cmd.is_sequencer().then_some(mempool),
+            importer,
+            outside_executor,
+            role,
+        );
+
+        // Feeder gateway
+
+        let service_gateway = GatewayService::new(
+            run_cmd.gateway_params.clone(),
+            Arc::clone(service_db.backend()),
+            Arc::clone(&add_tx_provider_l2_sync),
+            Arc::clone(&add_tx_provider_mempool),
+        )
+        .await
+        .context("Initializing gateway service")?;
+
+        service_telemetry.send_connected(&node_name, node_version, &chain_config.chain_name, &sys_info);
+
+        // ===================================================================== //
+        //                             SERVICES (START)                          //
+        // ===================================================================== //
+
+        if run_cmd.is_devnet() {
+            service_db.backend().enable_devnet_control();
+            service_block_production.setup_devnet().await?;
+        }
+
+        // The execution caches are warmed up before the mempool and gateway start accepting transactions.
+        if run_cmd.is_sequencer() && !run_cmd.args_preset.warp_update_receiver {
+            let backend = Arc::clone(service_db.backend());
+            let config = WarmupConfig {
+                n_blocks: run_cmd.block_production_params.exec_warmup_blocks,
+                n_contracts: run_cmd.block_production_params.exec_warmup_contracts,
+            };
+            let start = std::time::Instant::now();
+            let stats = tokio::task::spawn_blocking(move || warm_up_execution_caches(&backend, config))
+                .await?
+                .context("Warming up the execution caches")?;
+            if stats.contracts > 0 {
+                tracing::info!(
+                    "🔥 Warmed up the execution caches with {} classes, {} contracts and {} storage entries in {:?}",
+                    stats.classes,
+                    stats.contracts,
+                    stats.storage_entries,
+                    start.elapsed()
+                );
+            }
+        }
+
+        let rpc_addr = service_rpc_user.local_addr();
+        let rpc_admin_addr = service_rpc_admin.local_addr();
+
+        let app = ServiceMonitor::default()
+            .with(service_db)?
+            .with(service_l1_sync)?
+            .with(service_l2_sync)?
+            .with(service_block_production)?
+            .with(service_rpc_user)?
+            .with(service_rpc_admin)?
+            .with(service_gateway)?
+            .with(service_telemetry)?
+            .with(service_alerts)?;
+
+        // Since the database is not implemented as a proper service, we do not
+        // active it, as it would never be marked as stopped by the existing logic
+        //
+        // app.activate(MadaraService::Database);
+
+        let warp_update_receiver = run_cmd.args_preset.warp_update_receiver;
+
+        if l1_sync_active {
+            app.activate(MadaraServiceId::L1Sync);
+        }
+
+        if warp_update_receiver {
+            app.activate(MadaraServiceId::L2Sync);
+        } else if role.block_source() != MadaraServiceId::L2Sync || !run_cmd.l2_sync_params.l2_sync_disabled {
+            app.activate(role.block_source());
+        }
+
+        let rpc_user_enabled = !run_cmd.rpc_params.rpc_disable && !warp_update_receiver;
+        if rpc_user_enabled {
+            app.activate(MadaraServiceId::RpcUser);
+        }
+
+        let rpc_admin_enabled = run_cmd.rpc_params.rpc_admin && !warp_update_receiver;
+        if rpc_admin_enabled {
+            app.activate(MadaraServiceId::RpcAdmin);
+        }
+
+        if run_cmd.gateway_params.feeder_gateway_enable && !warp_update_receiver {
+            app.activate(MadaraServiceId::Gateway);
+        }
+
+        if run_cmd.telemetry_params.telemetry && !warp_update_receiver {
+            app.activate(MadaraServiceId::Telemetry);
+        }
+
+        if run_cmd.alert_params.alerts_enabled() {
+            app.activate(MadaraServiceId::Alerts);
+        }
+
+        let ctx = app.context();
+        let services = tokio::spawn(app.start());
+
+        Ok(MadaraNode {
+            backend,
+            rpc_addr: rpc_user_enabled.then_some(rpc_addr),
+            rpc_admin_addr: rpc_admin_enabled.then_some(rpc_admin_addr),
+            ctx,
+            services,
+        })
+    }
+}
+
+/// A running Madara node, see [`MadaraNodeBuilder`].
+pub struct MadaraNode {
+    backend: Arc<MadaraBackend>,
+    rpc_addr: Option<watch::Receiver<Option<SocketAddr>>>,
+    rpc_admin_addr: Option<watch::Receiver<Option<SocketAddr>>>,
+    ctx: ServiceContext,
+    services: JoinHandle<anyhow::Result<()>>,
+}
+
+impl MadaraNode {
+    /// Database of the node.
+    pub fn backend(&self) -> &Arc<MadaraBackend> {
+        &self.backend
+    }
+
+    /// Address the user RPC server listens at, once it is started. Returns `None` when the server is disabled or
+    /// failed to start.
+    pub async fn rpc_addr(&self) -> Option<SocketAddr> {
+        Self::wait_for_addr(self.rpc_addr.clone()).await
+    }
+
+    /// Address the admin RPC server listens at, see [`MadaraNode::rpc_addr`].
+    pub async fn rpc_admin_addr(&self) -> Option<SocketAddr> {
+        Self::wait_for_addr(self.rpc_admin_addr.clone()).await
+    }
+
+    async fn wait_for_addr(addr: Option<watch::Receiver<Option<SocketAddr>>>) -> Option<SocketAddr> {
+        let mut addr = addr?;
+        let res = addr.wait_for(Option::is_some).await.ok()?;
+        *res
+    }
+
+    /// Requests the services of the node to stop, as `SIGINT` and `SIGTERM` do. See [`MadaraNode::stopped`].
+    pub fn shutdown(&self) {
+        self.ctx.cancel_global();
+    }
+
+    /// Completes once every service of the node has stopped.
+    pub async fn stopped(self) -> anyhow::Result<()> {
+        self.services.await.context("Joining the node services")?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::time::Duration;
+
+    fn devnet(name: &str) -> (MadaraNodeBuilder, PathBuf) {
+        let base_path = std::env::temp_dir().join(format!("madara-node-{name}-{}", std::process::id()));
+        let run_cmd = RunCmd::parse_from(["madara", "--name", name, "--devnet", "--no-l1-sync"]);
+        (MadaraNodeBuilder::new(run_cmd).with_base_path(base_path.clone()), base_path)
+    }
+
+    async fn stop(node: MadaraNode, base_path: PathBuf) -> anyhow::Result<()> {
+        node.shutdown();
+        let res = node.stopped().await;
+        let _ = std::fs::remove_dir_all(base_path);
+        res
+    }
+
+    #[tokio::test]
+    async fn test_rpc_addr_port_0() {
+        let (builder, base_path) = devnet("port-0");
+        let node = builder.with_rpc_admin(true).with_rpc_ports(0, 0).start().await.unwrap();
+
+        let rpc_addr = tokio::time::timeout(Duration::from_secs(30), node.rpc_addr()).await.unwrap().unwrap();
+        let rpc_admin_addr =
+            tokio::time::timeout(Duration::from_secs(30), node.rpc_admin_addr()).await.unwrap().unwrap();
+        assert_ne!(rpc_addr.port(), 0);
+        assert_ne!(rpc_admin_addr.port(), 0);
+        assert_ne!(rpc_addr, rpc_admin_addr);
+        tokio::net::TcpStream::connect(rpc_addr).await.unwrap();
+
+        stop(node, base_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rpc_addr_bind_failure() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let (builder, base_path) = devnet("bind-failure");
+        let node = builder.with_rpc_ports(port, 0).start().await.unwrap();
+
+        // The address is released instead of being awaited forever.
+        let rpc_addr = tokio::time::timeout(Duration::from_secs(30), node.rpc_addr()).await.unwrap();
+        assert_eq!(rpc_addr, None);
+
+        let _ = stop(node, base_path).await;
+    }
+}
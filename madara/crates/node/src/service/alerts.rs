@@ -0,0 +1,418 @@
+//! Operator alerts, see [`mp_utils::alerts`].
+//!
+//! The alerts sent by the other services are posted to the webhooks, with a payload rendered from a template. This
+//! service also raises the alerts which no other service is in a position to detect: the disk of the database being
+//! nearly full, and the L1 sync not confirming any new state update.
+
+use crate::cli::alerts::AlertParams;
+use anyhow::Context;
+use mc_db::MadaraBackend;
+use mc_telemetry::DiskSpace;
+use mp_utils::alerts::{Alert, AlertHandle, AlertKind};
+use mp_utils::http::HttpClientConfig;
+use mp_utils::service::{MadaraServiceId, PowerOfTwo, Service, ServiceContext, ServiceId, ServiceRunner};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast::error::RecvError;
+use url::Url;
+
+/// Payload posted to the webhooks when no template is given. Slack webhooks display the `text` field.
+const DEFAULT_TEMPLATE: &str = r#"{
+  "text": "[{{node}}] {{event}}: {{message}}",
+  "event": "{{event}}",
+  "message": "{{message}}",
+  "block_n": "{{block_n}}",
+  "node": "{{node}}",
+  "chain_id": "{{chain_id}}",
+  "timestamp": "{{timestamp}}"
+}"#;
+
+/// Interval of the checks of the disk space and of the L1 sync.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone)]
+pub struct AlertService {
+    config: AlertParams,
+    template: String,
+    alerts: AlertHandle,
+    backend: Arc<MadaraBackend>,
+    base_path: PathBuf,
+    node_name: String,
+    l1_sync_enabled: bool,
+    http_config: HttpClientConfig,
+}
+
+impl AlertService {
+    pub async fn new(
+        config: AlertParams,
+        backend: Arc<MadaraBackend>,
+        base_path: PathBuf,
+        node_name: String,
+        l1_sync_enabled: bool,
+        http_config: HttpClientConfig,
+    ) -> anyhow::Result<Self> {
+        let template = match &config.alert_webhook_template {
+            Some(path) => tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Reading alert webhook template {}", path.display()))?,
+            None => DEFAULT_TEMPLATE.into(),
+        };
+        let sample = Alert::new(AlertKind::SyncStalled, "\"Test\" alert").with_block_n(0);
+        serde_json::from_str::<serde_json::Value>(&render(&template, &sample, "node", "chain", 0))
+            .context("The alert webhook template does not render to JSON")?;
+
+        let alerts = if config.alerts_enabled() { AlertHandle::new() } else { AlertHandle::disabled() };
+        Ok(Self { config, template, alerts, backend, base_path, node_name, l1_sync_enabled, http_config })
+    }
+
+    /// Handle the other services send their alerts to. It is disabled when no webhook is configured.
+    pub fn handle(&self) -> AlertHandle {
+        self.alerts.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for AlertService {
+    async fn start<'a>(&mut self, runner: ServiceRunner<'a>) -> anyhow::Result<()> {
+        let Some(receiver) = self.alerts.subscribe() else { return Ok(()) };
+        let client = self.http_config.reqwest_client().context("Creating the alert webhook http client")?;
+        let notifier = Notifier {
+            client,
+            webhooks: self.config.alert_webhook.clone(),
+            template: self.template.clone(),
+            node_name: self.node_name.clone(),
+            chain_id: self.backend.chain_config().chain_id.to_string(),
+            cooldown: self.config.alert_cooldown,
+            reorg_depth: self.config.alert_reorg_depth,
+            last_sent: HashMap::new(),
+        };
+        let backend = Arc::clone(&self.backend);
+        let monitor = HealthMonitor {
+            base_path: self.base_path.clone(),
+            disk_free_percent: self.config.alert_disk_free_percent,
+            l1_stall_timeout: self.l1_sync_enabled.then_some(self.config.alert_l1_stall_timeout),
+            l1_block_n: None,
+            l1_progress: Instant::now(),
+        };
+
+        runner.service_loop(move |ctx| alert_task(receiver, notifier, monitor, backend, ctx));
+        Ok(())
+    }
+}
+
+impl ServiceId for AlertService {
+    #[inline(always)]
+    fn svc_id(&self) -> PowerOfTwo {
+        MadaraServiceId::Alerts.svc_id()
+    }
+}
+
+async fn alert_task(
+    mut receiver: tokio::sync::broadcast::Receiver<Alert>,
+    mut notifier: Notifier,
+    mut monitor: HealthMonitor,
+    backend: Arc<MadaraBackend>,
+    mut ctx: ServiceContext,
+) -> anyhow::Result<()> {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        let next = async {
+            tokio::select! {
+                alert = receiver.recv() => Some(alert),
+                _ = interval.tick() => None,
+            }
+        };
+        let Some(next) = ctx.run_until_cancelled(next).await else { break };
+        match next {
+            Some(Ok(alert)) => notifier.notify(&alert).await,
+            Some(Err(RecvError::Lagged(n))) => tracing::warn!("{n} alerts were dropped, the webhooks are too slow"),
+            Some(Err(RecvError::Closed)) => break,
+            None => {
+                for alert in monitor.check(&backend) {
+                    notifier.notify(&alert).await;
+                }
+            }
+        }
+    }
+
+    anyhow::Ok(())
+}
+
+struct Notifier {
+    client: reqwest::Client,
+    webhooks: Vec<Url>,
+    template: String,
+    node_name: String,
+    chain_id: String,
+    cooldown: Duration,
+    reorg_depth: u64,
+    /// Last time each event was sent, see [`cooldown_key`].
+    last_sent: HashMap<(&'static str, Option<u64>), Instant>,
+}
+
+impl Notifier {
+    async fn notify(&mut self, alert: &Alert) {
+        if !self.should_send(alert, Instant::now()) {
+            return;
+        }
+
+        let event = alert.kind.name();
+        let timestamp = SystemTime::UNIX_EPOCH.elapsed().map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+        let payload = render(&self.template, alert, &self.node_name, &self.chain_id, timestamp);
+        futures::future::join_all(self.webhooks.iter().map(|webhook| {
+            let request = self
+                .client
+                .post(webhook.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(payload.clone());
+            async move {
+                if let Err(err) = request.send().await.and_then(|response| response.error_for_status()) {
+                    // The webhook URLs usually embed a secret token, only their host is logged.
+                    let host = webhook.host_str().unwrap_or_default();
+                    tracing::warn!("Failed to send the {event} alert to webhook at '{host}': {:#}", err.without_url());
+                }
+            }
+        }))
+        .await;
+    }
+
+    /// Whether the alert is sent, which is not the case for shallow reorgs and during the cooldown of the alerts
+    /// with the same [`cooldown_key`].
+    fn should_send(&mut self, alert: &Alert, now: Instant) -> bool {
+        if matches!(alert.kind, AlertKind::Reorg { depth } if depth <= self.reorg_depth) {
+            return false;
+        }
+        let key = cooldown_key(alert);
+        if self.last_sent.get(&key).is_some_and(|sent| now.duration_since(*sent) < self.cooldown) {
+            tracing::debug!("Not sending the {} alert, another one was sent less than {:?} ago", key.0, self.cooldown);
+            return false;
+        }
+        self.last_sent.retain(|_, sent| now.duration_since(*sent) < self.cooldown);
+        self.last_sent.insert(key, now);
+        true
+    }
+}
+
+/// Alerts with the same key share their cooldown: the key is the event and the block of the alert, or the power of
+/// two bucket of the depth of a reorg. This way, an alert about another block or a deeper reorg is still sent.
+fn cooldown_key(alert: &Alert) -> (&'static str, Option<u64>) {
+    let bucket = match alert.kind {
+        AlertKind::Reorg { depth } => Some(depth.max(1).ilog2().into()),
+        _ => alert.block_n,
+    };
+    (alert.kind.name(), bucket)
+}
+
+/// Detects a nearly full disk and a stalled L1 sync.
+struct HealthMonitor {
+    base_path: PathBuf,
+    disk_free_percent: f64,
+    /// `None` when the L1 sync is disabled.
+    l1_stall_timeout: Option<Duration>,
+    /// Latest block confirmed on L1 at the previous check.
+    l1_block_n: Option<u64>,
+    /// Time the latest block confirmed on L1 last changed.
+    l1_progress: Instant,
+}
+
+impl HealthMonitor {
+    fn check(&mut self, backend: &MadaraBackend) -> Vec<Alert> {
+        let mut alerts = vec![];
+
+        if let Some(alert) = DiskSpace::probe(&self.base_path).and_then(|disk| self.check_disk(&disk)) {
+            alerts.push(alert);
+        }
+
+        if self.l1_stall_timeout.is_some() {
+            match backend.get_l1_last_confirmed_block() {
+                Ok(l1_block_n) => alerts.extend(self.check_l1(l1_block_n, Instant::now())),
+                Err(err) => tracing::debug!("Alerts: failed to get the latest block confirmed on L1: {err:#}"),
+            }
+        }
+
+        alerts
+    }
+
+    fn check_disk(&self, disk: &DiskSpace) -> Option<Alert> {
+        let free_percent = disk.available_percent();
+        if free_percent >= self.disk_free_percent {
+            return None;
+        }
+        let message = format!(
+            "Only {free_percent:.1}% of the disk of the database is free ({} MB available)",
+            disk.available / 1024 / 1024
+        );
+        tracing::warn!("⚠️ {message}");
+        Some(Alert::new(AlertKind::DiskNearlyFull, message))
+    }
+
+    /// `l1_block_n` is the latest block confirmed on L1 at `now`.
+    fn check_l1(&mut self, l1_block_n: Option<u64>, now: Instant) -> Option<Alert> {
+        let timeout = self.l1_stall_timeout?;
+        if l1_block_n != self.l1_block_n {
+            self.l1_block_n = l1_block_n;
+            self.l1_progress = now;
+            return None;
+        }
+        let stalled_for = now.duration_since(self.l1_progress);
+        if stalled_for < timeout {
+            return None;
+        }
+        let message = format!(
+            "No new state update was confirmed on L1 for {stalled_for:?}, the latest block confirmed on L1 is {}",
+            l1_block_n.map(|block_n| format!("#{block_n}")).unwrap_or_else(|| "none".into()),
+        );
+        tracing::warn!("⚠️ {message}");
+        // Checked again after another timeout.
+        self.l1_progress = now;
+        Some(Alert::new(AlertKind::L1SyncStalled, message))
+    }
+}
+
+/// Renders the payload of an alert, replacing the `{{placeholders}}` of the template. The values are escaped to be
+/// placed in JSON strings, and the unknown placeholders are left as is.
+fn render(template: &str, alert: &Alert, node_name: &str, chain_id: &str, timestamp: u64) -> String {
+    let escape = |value: &str| {
+        let quoted = serde_json::to_string(value).expect("Serializing a string");
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    let value = |name: &str| match name {
+        "event" => Some(alert.kind.name().to_string()),
+        "message" => Some(escape(&alert.message)),
+        "block_n" => Some(alert.block_n.map(|block_n| block_n.to_string()).unwrap_or_default()),
+        "node" => Some(escape(node_name)),
+        "chain_id" => Some(escape(chain_id)),
+        "timestamp" => Some(timestamp.to_string()),
+        _ => None,
+    };
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else { break };
+        rendered.push_str(&rest[..start]);
+        match value(&rest[start + 2..end]) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_alert() {
+        let alert = Alert::new(AlertKind::Reorg { depth: 3 }, "Reorg of \"3\" blocks {{node}}").with_block_n(12);
+        let payload = render(DEFAULT_TEMPLATE, &alert, "my node", "MADARA", 1000);
+        let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(payload["text"], "[my node] reorg: Reorg of \"3\" blocks {{node}}");
+        assert_eq!(payload["block_n"], "12");
+        assert_eq!(payload["chain_id"], "MADARA");
+        assert_eq!(payload["timestamp"], "1000");
+
+        let alert = Alert::new(AlertKind::DiskNearlyFull, "Disk");
+        assert_eq!(render("{{event}} {{unknown}} {{block_n}}{{", &alert, "", "", 0), "disk_nearly_full {{unknown}} {{");
+    }
+
+    fn notifier() -> Notifier {
+        Notifier {
+            client: reqwest::Client::new(),
+            webhooks: vec![],
+            template: DEFAULT_TEMPLATE.into(),
+            node_name: "node".into(),
+            chain_id: "MADARA".into(),
+            cooldown: Duration::from_secs(60),
+            reorg_depth: 2,
+            last_sent: HashMap::new(),
+        }
+    }
+
+    fn monitor() -> HealthMonitor {
+        HealthMonitor {
+            base_path: PathBuf::new(),
+            disk_free_percent: 10.0,
+            l1_stall_timeout: Some(Duration::from_secs(60)),
+            l1_block_n: None,
+            l1_progress: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_notifier_cooldown() {
+        let mut notifier = notifier();
+        let now = Instant::now();
+        let stalled = |block_n| Alert::new(AlertKind::SyncStalled, "Stalled").with_block_n(block_n);
+
+        assert!(notifier.should_send(&stalled(10), now));
+        assert!(!notifier.should_send(&stalled(10), now + Duration::from_secs(30)));
+        // Another block or another event is not in the cooldown.
+        assert!(notifier.should_send(&stalled(11), now + Duration::from_secs(30)));
+        assert!(notifier.should_send(&Alert::new(AlertKind::DiskNearlyFull, "Disk"), now + Duration::from_secs(30)));
+        // The cooldown is over.
+        assert!(notifier.should_send(&stalled(10), now + Duration::from_secs(60)));
+        // The expired entries are dropped.
+        assert!(notifier.should_send(&stalled(12), now + Duration::from_secs(200)));
+        assert_eq!(notifier.last_sent.len(), 1);
+    }
+
+    #[test]
+    fn test_notifier_reorgs() {
+        let mut notifier = notifier();
+        let now = Instant::now();
+        let reorg = |depth| Alert::new(AlertKind::Reorg { depth }, "Reorg");
+
+        // Up to `reorg_depth`, reorgs are not sent.
+        assert!(!notifier.should_send(&reorg(2), now));
+        assert!(notifier.should_send(&reorg(3), now));
+        // Same power of two bucket.
+        assert!(!notifier.should_send(&reorg(2), now));
+        assert!(!notifier.should_send(&reorg(3), now));
+        // A deeper reorg is sent during the cooldown.
+        assert!(notifier.should_send(&reorg(4), now));
+        assert!(!notifier.should_send(&reorg(7), now));
+        assert!(notifier.should_send(&reorg(100), now));
+    }
+
+    #[test]
+    fn test_monitor_disk() {
+        let monitor = monitor();
+        assert_eq!(monitor.check_disk(&DiskSpace { available: 50, total: 100 }), None);
+        let alert = monitor.check_disk(&DiskSpace { available: 5 * 1024 * 1024, total: 100 * 1024 * 1024 }).unwrap();
+        assert_eq!(alert.kind, AlertKind::DiskNearlyFull);
+        assert_eq!(alert.message, "Only 5.0% of the disk of the database is free (5 MB available)");
+    }
+
+    #[test]
+    fn test_monitor_l1_stall() {
+        let mut monitor = monitor();
+        let start = monitor.l1_progress;
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(monitor.check_l1(None, at(30)), None);
+        let alert = monitor.check_l1(None, at(60)).unwrap();
+        assert_eq!(alert.kind, AlertKind::L1SyncStalled);
+        // Checked again after another timeout.
+        assert_eq!(monitor.check_l1(None, at(90)), None);
+        assert!(monitor.check_l1(None, at(120)).is_some());
+
+        // Progress resets the timeout.
+        assert_eq!(monitor.check_l1(Some(5), at(150)), None);
+        assert_eq!(monitor.check_l1(Some(5), at(200)), None);
+        assert_eq!(monitor.check_l1(Some(6), at(230)), None);
+        assert_eq!(monitor.check_l1(Some(6), at(280)), None);
+        assert!(monitor.check_l1(Some(6), at(290)).unwrap().message.ends_with("#6"));
+
+        // Disabled with the L1 sync.
+        let mut monitor = HealthMonitor { l1_stall_timeout: None, ..monitor };
+        assert_eq!(monitor.check_l1(None, at(1000)), None);
+    }
+}
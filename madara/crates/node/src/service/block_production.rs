@@ -81,8 +81,8 @@ impl BlockProductionService {
             // deploy devnet genesis
             tracing::info!("⛏️  Deploying devnet genesis block");
 
-            let mut genesis_config =
-                ChainGenesisDescription::base_config().context("Failed to create base genesis config")?;
+            let mut genesis_config = ChainGenesisDescription::base_config(backend.chain_config().udc_address)
+                .context("Failed to create base genesis config")?;
             let contracts =
                 genesis_config.add_devnet_contracts(*n_devnet_contracts).context("Failed to add devnet contracts")?;
 
@@ -92,7 +92,9 @@ impl BlockProductionService {
             block_import
                 .add_block(
                     genesis_block,
-                    BlockValidationContext::new(backend.chain_config().chain_id.clone()).trust_class_hashes(true),
+                    BlockValidationContext::new(backend.chain_config().chain_id.clone())
+                        .trust_class_hashes(true)
+                        .trust_signatures(true),
                 )
                 .await
                 .context("Importing devnet genesis block")?;
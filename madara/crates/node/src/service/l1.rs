@@ -3,8 +3,10 @@ use alloy::primitives::Address;
 use anyhow::Context;
 use mc_db::{DatabaseService, MadaraBackend};
 use mc_eth::client::{EthereumClient, L1BlockMetrics};
+use mc_eth::da_audit::DaAuditConfig;
 use mc_mempool::{GasPriceProvider, Mempool};
 use mp_block::H160;
+use mp_utils::http::HttpClientConfig;
 use mp_utils::service::{MadaraServiceId, PowerOfTwo, Service, ServiceId, ServiceRunner};
 use starknet_api::core::ChainId;
 use std::sync::Arc;
@@ -19,6 +21,7 @@ pub struct L1SyncService {
     gas_price_sync_disabled: bool,
     gas_price_poll: Duration,
     mempool: Arc<Mempool>,
+    da_audit: Option<DaAuditConfig>,
 }
 
 impl L1SyncService {
@@ -32,12 +35,13 @@ impl L1SyncService {
         authority: bool,
         devnet: bool,
         mempool: Arc<Mempool>,
+        http_config: &HttpClientConfig,
     ) -> anyhow::Result<Self> {
         let eth_client = if !config.l1_sync_disabled && (config.l1_endpoint.is_some() || !devnet) {
             if let Some(l1_rpc_url) = &config.l1_endpoint {
                 let core_address = Address::from_slice(l1_core_address.as_bytes());
                 let l1_block_metrics = L1BlockMetrics::register().expect("Registering metrics");
-                let client = EthereumClient::new(l1_rpc_url.clone(), core_address, l1_block_metrics)
+                let client = EthereumClient::new(l1_rpc_url.clone(), core_address, l1_block_metrics, http_config)
                     .await
                     .context("Creating ethereum client")?;
 
@@ -76,6 +80,10 @@ impl L1SyncService {
             gas_price_sync_disabled: !gas_price_sync_enabled,
             gas_price_poll,
             mempool,
+            da_audit: config
+                .l1_da_audit_beacon_url
+                .clone()
+                .map(|beacon_url| DaAuditConfig { beacon_url, http: http_config.clone() }),
         })
     }
 }
@@ -90,6 +98,7 @@ impl Service for L1SyncService {
             gas_price_sync_disabled,
             gas_price_poll,
             mempool,
+            da_audit,
             ..
         } = self.clone();
 
@@ -106,6 +115,7 @@ impl Service for L1SyncService {
                     gas_price_sync_disabled,
                     gas_price_poll,
                     mempool,
+                    da_audit,
                     ctx,
                 )
             });
@@ -5,7 +5,11 @@ use mc_sync::fetch::fetchers::{FetchConfig, WarpUpdateConfig};
 use mc_sync::SyncConfig;
 use mc_telemetry::TelemetryHandle;
 use mp_chain_config::ChainConfig;
+use mp_utils::alerts::AlertHandle;
+use mp_utils::http::HttpClientConfig;
 use mp_utils::service::{MadaraServiceId, PowerOfTwo, Service, ServiceId, ServiceRunner};
+use starknet_types_core::felt::Felt;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -17,19 +21,30 @@ pub struct L2SyncService {
     backup_every_n_blocks: Option<u64>,
     starting_block: Option<u64>,
     telemetry: Arc<TelemetryHandle>,
+    alerts: AlertHandle,
     pending_block_poll_interval: Duration,
 }
 
 impl L2SyncService {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         config: &L2SyncParams,
         chain_config: Arc<ChainConfig>,
         db: &DatabaseService,
         block_importer: Arc<BlockImporter>,
         telemetry: TelemetryHandle,
+        alerts: AlertHandle,
         warp_update: Option<WarpUpdateConfig>,
+        checkpoints: BTreeMap<u64, Felt>,
+        http_config: HttpClientConfig,
     ) -> anyhow::Result<Self> {
-        let fetch_config = config.block_fetch_config(chain_config.chain_id.clone(), chain_config.clone(), warp_update);
+        let fetch_config = config.block_fetch_config(
+            chain_config.chain_id.clone(),
+            chain_config.clone(),
+            warp_update,
+            Arc::new(checkpoints),
+            http_config,
+        )?;
 
         tracing::info!("🛰️ Using feeder gateway URL: {}", fetch_config.feeder_gateway.as_str());
 
@@ -40,6 +55,7 @@ impl L2SyncService {
             backup_every_n_blocks: config.backup_every_n_blocks,
             block_importer,
             telemetry: Arc::new(telemetry),
+            alerts,
             pending_block_poll_interval: config.pending_block_poll_interval,
         })
     }
@@ -56,6 +72,7 @@ impl Service for L2SyncService {
             pending_block_poll_interval,
             block_importer,
             telemetry,
+            alerts,
         } = self.clone();
         let telemetry = Arc::clone(&telemetry);
 
@@ -70,6 +87,7 @@ impl Service for L2SyncService {
                     backup_every_n_blocks,
                     telemetry,
                     pending_block_poll_interval,
+                    alerts,
                 },
             )
         });
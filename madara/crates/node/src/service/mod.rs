@@ -1,9 +1,11 @@
+mod alerts;
 mod block_production;
 mod gateway;
 mod l1;
 mod l2;
 mod rpc;
 
+pub use alerts::AlertService;
 pub use block_production::BlockProductionService;
 pub use gateway::GatewayService;
 pub use l1::L1SyncService;
@@ -0,0 +1,470 @@
+//! Transport of the class artifacts returned by `starknet_getClass` and `starknet_getCompiledCasm`.
+//!
+//! Classes weigh up to a few megabytes and make up most of the bandwidth of some RPC providers, while they never change
+//! once declared. Their responses are tagged with a weak ETag derived from the class hash and the block id: clients
+//! caching them send it back in `If-None-Match`, and get an empty `304 Not Modified` response instead of the class,
+//! without the call being made. With `--rpc-compression`, they are also compressed with brotli or gzip when the client
+//! accepts it.
+//!
+//! Class responses are sent with chunked transfer encoding, and compressed chunk by chunk as they are sent. Only single
+//! calls with a small body are recognized: batches go through unchanged.
+
+use futures::future::BoxFuture;
+use hyper::body::Bytes;
+use hyper::header::{self, HeaderValue};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use starknet_types_core::felt::Felt;
+use std::io::Write;
+use std::task::{Context, Poll};
+
+use super::methods::base_name;
+
+/// Largest request body inspected for a class call.
+const MAX_CALL_SIZE: u64 = 4 * 1024;
+/// Smallest response body worth compressing.
+const MIN_COMPRESSED_SIZE: usize = 1024;
+/// Size of the chunks the class responses are sent in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+/// Brotli quality, from 0 to 11. Higher levels are too slow to compress responses on the fly.
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW_BITS: u32 = 22;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// The preferred encoding among those accepted in an `Accept-Encoding` header.
+    fn negotiate(accept_encoding: &str) -> Option<Self> {
+        let accepted = |name: &str| {
+            accept_encoding.split(',').any(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let rejected = |param: &str| param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0);
+                parts.next().is_some_and(|coding| coding.eq_ignore_ascii_case(name)) && !parts.any(rejected)
+            })
+        };
+        if accepted("br") {
+            Some(Self::Brotli)
+        } else if accepted("gzip") {
+            Some(Self::Gzip)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    /// Compresses `data` into `out`, which receives the compressed data as it is produced.
+    fn compress(self, data: &[u8], out: &mut impl Write) -> std::io::Result<()> {
+        match self {
+            Self::Brotli => {
+                let mut encoder =
+                    brotli::CompressorWriter::new(out, BROTLI_BUFFER_SIZE, BROTLI_QUALITY, BROTLI_WINDOW_BITS);
+                encoder.write_all(data)?;
+                // Ends the stream.
+                encoder.into_inner();
+                Ok(())
+            }
+            Self::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.try_finish()
+            }
+        }
+    }
+}
+
+/// A call to `getClass` or `getCompiledCasm`.
+struct ClassCall {
+    etag: HeaderValue,
+}
+
+impl ClassCall {
+    fn parse(path: &str, body: &[u8]) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct Call {
+            method: String,
+            #[serde(default)]
+            params: serde_json::Value,
+        }
+
+        let Call { method, params } = serde_json::from_slice(body).ok()?;
+        // The class returned by `getClass` depends on the block it is requested at, as it may not be declared yet.
+        let (class_hash_index, block_id_index) = match base_name(&method) {
+            "getClass" => (1, Some(0)),
+            "getCompiledCasm" => (0, None),
+            _ => return None,
+        };
+        let param = |name: &str, index: usize| match &params {
+            serde_json::Value::Array(params) => params.get(index),
+            serde_json::Value::Object(params) => params.get(name),
+            _ => None,
+        };
+        let class_hash: Felt = serde_json::from_value(param("class_hash", class_hash_index)?.clone()).ok()?;
+        let block_id = match block_id_index {
+            Some(index) => match param("block_id", index)? {
+                serde_json::Value::String(tag) if tag == "latest" || tag == "pending" => tag.clone(),
+                serde_json::Value::Object(block_id) => match (block_id.get("block_number"), block_id.get("block_hash"))
+                {
+                    (Some(block_n), None) => block_n.as_u64()?.to_string(),
+                    (None, Some(block_hash)) => {
+                        format!("{:#x}", serde_json::from_value::<Felt>(block_hash.clone()).ok()?)
+                    }
+                    _ => return None,
+                },
+                _ => return None,
+            },
+            None => String::new(),
+        };
+
+        // The format of the response depends on the version of the API, selected by the path or the method name.
+        let etag = HeaderValue::from_str(&format!("W/\"{class_hash:#x}-{block_id}-{method}-{path}\"")).ok()?;
+        Some(Self { etag })
+    }
+
+    /// Adds the headers the responses to the call are cached with.
+    fn tag(&self, headers: &mut header::HeaderMap, compression: bool) {
+        headers.insert(header::ETAG, self.etag.clone());
+        if compression {
+            headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        }
+    }
+
+    /// Whether the `If-None-Match` header of the request matches the response. Weak comparison is used, as the
+    /// responses only differ by the id of the call.
+    fn matches(&self, if_none_match: &HeaderValue) -> bool {
+        let (Ok(if_none_match), Ok(etag)) = (if_none_match.to_str(), self.etag.to_str()) else {
+            return false;
+        };
+        let opaque_tag = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        if_none_match.split(',').any(|tag| tag.trim() == "*" || opaque_tag(tag) == opaque_tag(etag))
+    }
+}
+
+/// Whether `body` is the response of a successful call.
+fn is_success(body: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct Outcome {
+        result: Option<IgnoredAny>,
+    }
+
+    serde_json::from_slice::<Outcome>(body).is_ok_and(|outcome| outcome.result.is_some())
+}
+
+/// Sends `body` in chunks of [`CHUNK_SIZE`] bytes.
+fn chunked(body: Bytes) -> Body {
+    let (mut sender, chunked) = Body::channel();
+    tokio::spawn(async move {
+        for start in (0..body.len()).step_by(CHUNK_SIZE) {
+            let chunk = body.slice(start..body.len().min(start + CHUNK_SIZE));
+            if sender.send_data(chunk).await.is_err() {
+                // The client went away.
+                break;
+            }
+        }
+    });
+    chunked
+}
+
+/// Sends the data written to it in chunks of [`CHUNK_SIZE`] bytes. Writes block until the chunks are sent, and fail
+/// once the client went away.
+struct ChunkWriter {
+    sender: hyper::body::Sender,
+    chunk: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn send_chunk(&mut self) -> std::io::Result<()> {
+        let chunk = Bytes::from(std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE)));
+        futures::executor::block_on(self.sender.send_data(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Client went away"))
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(CHUNK_SIZE - self.chunk.len());
+        self.chunk.extend_from_slice(&buf[..len]);
+        if self.chunk.len() == CHUNK_SIZE {
+            self.send_chunk()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        self.send_chunk()
+    }
+}
+
+/// Sends `body` compressed with `encoding`, in chunks of [`CHUNK_SIZE`] bytes sent as soon as they are compressed. The
+/// body is aborted if the compression fails.
+fn compressed(body: Bytes, encoding: Encoding) -> Body {
+    let (sender, compressed) = Body::channel();
+    tokio::task::spawn_blocking(move || {
+        let mut writer = ChunkWriter { sender, chunk: Vec::with_capacity(CHUNK_SIZE) };
+        match encoding.compress(&body, &mut writer).and_then(|()| writer.flush()) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => {}
+            Err(err) => {
+                tracing::warn!("Compressing class response: {err:#}");
+                writer.sender.abort();
+            }
+        }
+    });
+    compressed
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassArtifactsLayer {
+    compression: bool,
+}
+
+impl ClassArtifactsLayer {
+    pub fn new(compression: bool) -> Self {
+        Self { compression }
+    }
+}
+
+impl<S> tower::Layer<S> for ClassArtifactsLayer {
+    type Service = ClassArtifacts<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ClassArtifacts { inner, compression: self.compression }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassArtifacts<S> {
+    inner: S,
+    compression: bool,
+}
+
+impl<S> tower::Service<Request<Body>> for ClassArtifacts<S>
+where
+    S: tower::Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: From<hyper::Error> + Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The service which was polled ready is the one to call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let compression = self.compression;
+
+        Box::pin(async move {
+            let content_length =
+                req.headers().get(header::CONTENT_LENGTH).and_then(|value| value.to_str().ok()?.parse::<u64>().ok());
+            if req.method() != hyper::Method::POST || !content_length.is_some_and(|len| len <= MAX_CALL_SIZE) {
+                return inner.call(req).await;
+            }
+
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let Some(call) = ClassCall::parse(parts.uri.path(), &body) else {
+                return inner.call(Request::from_parts(parts, Body::from(body))).await;
+            };
+            // A class never changes once declared: a client which has it does not need the call to be made.
+            if parts.headers.get(header::IF_NONE_MATCH).is_some_and(|if_none_match| call.matches(if_none_match)) {
+                let mut res = Response::new(Body::empty());
+                *res.status_mut() = StatusCode::NOT_MODIFIED;
+                call.tag(res.headers_mut(), compression);
+                return Ok(res);
+            }
+            let encoding = parts
+                .headers
+                .get(header::ACCEPT_ENCODING)
+                .and_then(|value| Encoding::negotiate(value.to_str().ok()?))
+                .filter(|_| compression);
+
+            let res = inner.call(Request::from_parts(parts, Body::from(body))).await?;
+            if res.status() != StatusCode::OK || res.headers().contains_key(header::CONTENT_ENCODING) {
+                return Ok(res);
+            }
+            // The server builds its responses in memory, they are only streamed from here.
+            let (mut parts, body) = res.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            if !is_success(&body) {
+                return Ok(Response::from_parts(parts, Body::from(body)));
+            }
+
+            call.tag(&mut parts.headers, compression);
+            parts.headers.remove(header::CONTENT_LENGTH);
+            let body = match encoding {
+                Some(encoding) if body.len() >= MIN_COMPRESSED_SIZE => {
+                    parts.headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.name()));
+                    compressed(body, encoding)
+                }
+                _ => chunked(body),
+            };
+            Ok(Response::from_parts(parts, body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const PATH: &str = "/rpc/v0_7_1";
+
+    fn class_call(block_id: &str) -> String {
+        format!(r#"{{"jsonrpc":"2.0","id":1,"method":"starknet_getClass","params":[{block_id},"0x1"]}}"#)
+    }
+
+    /// A server answering every call with `response`, counting the calls.
+    fn service(
+        response: String,
+        calls: Arc<AtomicUsize>,
+    ) -> impl tower::Service<Request<Body>, Response = Response<Body>, Error = hyper::Error> {
+        let inner = tower::service_fn(move |_: Request<Body>| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            let response = response.clone();
+            async move { Ok::<_, hyper::Error>(Response::new(Body::from(response))) }
+        });
+        ClassArtifacts { inner, compression: true }
+    }
+
+    fn request(body: &str, headers: &[(header::HeaderName, &str)]) -> Request<Body> {
+        let mut req = Request::post(PATH).header(header::CONTENT_LENGTH, body.len());
+        for (name, value) in headers {
+            req = req.header(name, *value);
+        }
+        req.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn call(
+        service: &mut impl tower::Service<Request<Body>, Response = Response<Body>, Error = hyper::Error>,
+        req: Request<Body>,
+    ) -> (Response<()>, Bytes) {
+        let res = tower::ServiceExt::oneshot(service, req).await.unwrap();
+        let (parts, body) = res.into_parts();
+        (Response::from_parts(parts, ()), hyper::body::to_bytes(body).await.unwrap())
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Encoding::negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+        assert_eq!(Encoding::negotiate("gzip, br;q=0"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("GZIP;q=0.5"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("deflate, identity"), None);
+    }
+
+    #[test]
+    fn test_etag() {
+        let etag = |path: &str, body: &str| ClassCall::parse(path, body.as_bytes()).map(|call| call.etag);
+
+        let latest = etag(PATH, &class_call(r#""latest""#)).unwrap();
+        assert_eq!(etag(PATH, &class_call(r#""latest""#)), Some(latest.clone()));
+        // The class depends on the block, and the format of the response on the version.
+        assert_ne!(etag(PATH, &class_call(r#"{"block_number":1}"#)), Some(latest.clone()));
+        assert_ne!(etag(PATH, &class_call(r#"{"block_number":1}"#)), etag(PATH, &class_call(r#"{"block_number":2}"#)));
+        assert_ne!(etag("/rpc/v0_8_0", &class_call(r#""latest""#)), Some(latest.clone()));
+        let named_params = r#"{"class_hash":"0x1","block_id":"latest"}"#;
+        let named_call = format!(r#"{{"jsonrpc":"2.0","id":2,"method":"starknet_getClass","params":{named_params}}}"#);
+        assert_eq!(etag(PATH, &named_call), Some(latest.clone()));
+        assert!(etag(
+            PATH,
+            r#"{"jsonrpc":"2.0","id":1,"method":"starknet_getCompiledCasm","params":{"class_hash":"0x1"}}"#
+        )
+        .is_some());
+        assert_eq!(
+            etag(PATH, r#"{"jsonrpc":"2.0","id":1,"method":"starknet_getNonce","params":["latest","0x1"]}"#),
+            None
+        );
+        assert_eq!(etag(PATH, &class_call(r#"{"block_number":"one"}"#)), None);
+
+        let call = ClassCall::parse(PATH, class_call(r#""latest""#).as_bytes()).unwrap();
+        let strong = HeaderValue::from_str(latest.to_str().unwrap().trim_start_matches("W/")).unwrap();
+        assert!(call.matches(&latest));
+        assert!(call.matches(&strong));
+        assert!(call.matches(&HeaderValue::from_static("\"other\", *")));
+        assert!(!call.matches(&HeaderValue::from_static("\"other\"")));
+    }
+
+    #[tokio::test]
+    async fn test_not_modified_without_call() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut service = service(r#"{"jsonrpc":"2.0","id":1,"result":{"abi":"[]"}}"#.to_string(), Arc::clone(&calls));
+
+        let (res, body) = call(&mut service, request(&class_call(r#""latest""#), &[])).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body, r#"{"jsonrpc":"2.0","id":1,"result":{"abi":"[]"}}"#);
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (res, body) =
+            call(&mut service, request(&class_call(r#""latest""#), &[(header::IF_NONE_MATCH, &etag)])).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers()[header::ETAG], etag);
+        assert!(body.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Another block may not have the class.
+        let (res, _) =
+            call(&mut service, request(&class_call(r#"{"block_number":0}"#), &[(header::IF_NONE_MATCH, &etag)])).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_not_tagged() {
+        let response = r#"{"jsonrpc":"2.0","id":1,"error":{"code":28,"message":"Class hash not found"}}"#;
+        let mut service = service(response.to_string(), Default::default());
+
+        let (res, body) = call(&mut service, request(&class_call(r#""latest""#), &[])).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+        assert_eq!(body, response);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_stream() {
+        // Larger than a chunk, to be sent in several.
+        let program = "0x1234567890abcdef".repeat(3 * CHUNK_SIZE / 16);
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":{{"program":"{program}"}}}}"#);
+        let mut service = service(response.clone(), Default::default());
+
+        for (accept_encoding, encoding) in [("gzip", Some("gzip")), ("br, gzip", Some("br")), ("identity", None)] {
+            let req = request(&class_call(r#""latest""#), &[(header::ACCEPT_ENCODING, accept_encoding)]);
+            let (res, body) = call(&mut service, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert!(!res.headers().contains_key(header::CONTENT_LENGTH));
+            assert_eq!(res.headers().get(header::CONTENT_ENCODING).map(|value| value.to_str().unwrap()), encoding);
+
+            let mut decompressed = String::new();
+            match encoding {
+                Some("gzip") => flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut decompressed).unwrap(),
+                Some(_) => {
+                    brotli::Decompressor::new(&body[..], BROTLI_BUFFER_SIZE).read_to_string(&mut decompressed).unwrap()
+                }
+                None => body.as_ref().read_to_string(&mut decompressed).unwrap(),
+            };
+            assert_eq!(decompressed, response);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Compatibility between the RPC versions.
+//!
+//! A version only implements the methods which changed since the version before it. A call to a method which the
+//! requested version does not implement is served by the newest older version which does, so that the latest version
+//! can be exposed by default while clients of the older versions keep being served on their versioned paths. The calls
+//! made on the paths of older versions are counted, to know when a version can be removed.
+//!
+//! The result of an older implementation is then converted to the shape of the requested version by the
+//! [`RESULT_SHIMS`], which map the fields renamed, removed or added between the two versions.
+
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+use jsonrpsee::types::Id;
+use jsonrpsee::{MethodResponse, ResponsePayload};
+use mp_chain_config::RpcVersion;
+use serde_json::{json, Map, Value};
+
+/// A change of the result of a method, applied when a call made on `since` or a newer version is served by the
+/// implementation of a version older than `since`.
+struct ResultShim {
+    namespace: &'static str,
+    method: &'static str,
+    since: RpcVersion,
+    apply: fn(&mut Value),
+}
+
+impl ResultShim {
+    const fn v0_8_0(method: &'static str, apply: fn(&mut Value)) -> Self {
+        Self { namespace: "starknet", method, since: RpcVersion::RPC_VERSION_0_8_0, apply }
+    }
+}
+
+/// The changes of the results between the RPC versions. The traces are not converted. The changes which need data
+/// missing from the results of the older version, such as the gas consumed by the receipts, are implemented by the
+/// newer version instead.
+const RESULT_SHIMS: &[ResultShim] = &[
+    ResultShim::v0_8_0("getTransactionByHash", v0_8_0::transaction),
+    ResultShim::v0_8_0("getTransactionByBlockIdAndIndex", v0_8_0::transaction),
+    ResultShim::v0_8_0("estimateFee", v0_8_0::fee_estimates),
+    ResultShim::v0_8_0("estimateMessageFee", v0_8_0::fee_estimate),
+    ResultShim::v0_8_0("simulateTransactions", v0_8_0::simulated_transactions),
+];
+
+#[derive(Debug, Default)]
+pub struct RpcCompat {
+    /// Versions implementing each method, by namespace and method name.
+    versions: HashMap<(String, String), BTreeSet<RpcVersion>>,
+    /// Maximum size of a response, in bytes, once its result is converted.
+    max_response_size: usize,
+}
+
+impl RpcCompat {
+    pub fn new(methods: &jsonrpsee::Methods, max_response_size: usize) -> Self {
+        let mut versions: HashMap<_, BTreeSet<_>> = HashMap::new();
+        for name in methods.method_names() {
+            // Versioned methods are named `{namespace}_V{major}_{minor}_{patch}_{method}`.
+            let [namespace, major, minor, patch, method] = name.split('_').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+            let Some(version) = major
+                .strip_prefix('V')
+                .and_then(|major| RpcVersion::from_str(&format!("{major}_{minor}_{patch}")).ok())
+            else {
+                continue;
+            };
+            versions.entry((namespace.to_string(), method.to_string())).or_default().insert(version);
+        }
+        Self { versions, max_response_size }
+    }
+
+    /// The version whose implementation serves `method` when it is called on `version`: `version` itself when it
+    /// implements the method, or else the newest older version which does.
+    pub fn resolve(&self, namespace: &str, version: RpcVersion, method: &str) -> Option<RpcVersion> {
+        self.versions.get(&(namespace.to_string(), method.to_string()))?.range(..=version).next_back().copied()
+    }
+
+    fn shims<'a>(
+        namespace: &'a str,
+        version: RpcVersion,
+        served_version: RpcVersion,
+        method: &'a str,
+    ) -> impl Iterator<Item = &'static ResultShim> + 'a {
+        RESULT_SHIMS.iter().filter(move |shim| {
+            shim.namespace == namespace && shim.method == method && served_version < shim.since && shim.since <= version
+        })
+    }
+
+    /// Whether the result of `method` changed between `served_version` and `version`.
+    pub fn needs_shim(&self, namespace: &str, version: RpcVersion, served_version: RpcVersion, method: &str) -> bool {
+        Self::shims(namespace, version, served_version, method).next().is_some()
+    }
+
+    /// Converts the result of a call to `method` on `version`, served by the implementation of `served_version`, to
+    /// the shape of `version`. Errors are returned as is.
+    pub fn shim_response(
+        &self,
+        id: Id<'static>,
+        response: MethodResponse,
+        namespace: &str,
+        version: RpcVersion,
+        served_version: RpcVersion,
+        method: &str,
+    ) -> MethodResponse {
+        if !response.is_success() {
+            return response;
+        }
+        let Some(mut result) = serde_json::from_str::<Value>(response.as_result())
+            .ok()
+            .and_then(|mut response| response.get_mut("result").map(Value::take))
+        else {
+            return response;
+        };
+        for shim in Self::shims(namespace, version, served_version, method) {
+            (shim.apply)(&mut result);
+        }
+        MethodResponse::response(id, ResponsePayload::success(result), self.max_response_size)
+    }
+}
+
+/// Applies `f` to every object of an array.
+fn for_each_object(value: &mut Value, f: impl Fn(&mut Map<String, Value>)) {
+    if let Value::Array(values) = value {
+        values.iter_mut().filter_map(Value::as_object_mut).for_each(f);
+    }
+}
+
+/// From v0.7 to v0.8.
+mod v0_8_0 {
+    use super::*;
+
+    /// The v3 transactions have a bound on the L1 data gas, which the v0.7 transactions do not have.
+    pub fn transaction(transaction: &mut Value) {
+        if let Some(Value::Object(bounds)) = transaction.get_mut("resource_bounds") {
+            bounds.entry("l1_data_gas").or_insert_with(|| json!({ "max_amount": "0x0", "max_price_per_unit": "0x0" }));
+        }
+    }
+
+    /// The gas amounts and prices are named by resource, with the L2 gas added. The execution of the transactions is
+    /// charged in L1 gas, so they consume no L2 gas, see [`mp_block::header::GasPrices::l2_gas_price`].
+    pub fn fee_estimate(estimate: &mut Value) {
+        let Some(estimate) = estimate.as_object_mut() else { return };
+        fee_estimate_object(estimate)
+    }
+
+    fn fee_estimate_object(estimate: &mut Map<String, Value>) {
+        for (from, to) in [
+            ("gas_consumed", "l1_gas_consumed"),
+            ("gas_price", "l1_gas_price"),
+            ("data_gas_consumed", "l1_data_gas_consumed"),
+            ("data_gas_price", "l1_data_gas_price"),
+        ] {
+            if let Some(value) = estimate.remove(from) {
+                estimate.insert(to.into(), value);
+            }
+        }
+        estimate.entry("l2_gas_consumed").or_insert_with(|| json!("0x0"));
+        estimate.entry("l2_gas_price").or_insert_with(|| json!("0x0"));
+    }
+
+    pub fn fee_estimates(estimates: &mut Value) {
+        for_each_object(estimates, fee_estimate_object)
+    }
+
+    pub fn simulated_transactions(simulated: &mut Value) {
+        for_each_object(simulated, |simulated| {
+            if let Some(Value::Object(estimate)) = simulated.get_mut("fee_estimation") {
+                fee_estimate_object(estimate)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0_7_1: RpcVersion = RpcVersion::RPC_VERSION_0_7_1;
+    const V0_8_0: RpcVersion = RpcVersion::RPC_VERSION_0_8_0;
+
+    fn compat() -> RpcCompat {
+        let mut module = jsonrpsee::RpcModule::new(());
+        for name in
+            ["starknet_V0_7_1_getTransactionByHash", "starknet_V0_7_1_specVersion", "starknet_V0_8_0_specVersion"]
+        {
+            module.register_method(name, |_, _| "").unwrap();
+        }
+        RpcCompat::new(&module.into(), usize::MAX)
+    }
+
+    fn shim(method: &str, result: Value) -> Value {
+        let response = MethodResponse::response(Id::Number(1), ResponsePayload::success(result), usize::MAX);
+        let response = compat().shim_response(Id::Number(1), response, "starknet", V0_8_0, V0_7_1, method);
+        serde_json::from_str::<Value>(response.as_result()).unwrap()["result"].take()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let compat = compat();
+        assert_eq!(compat.resolve("starknet", V0_8_0, "getTransactionByHash"), Some(V0_7_1));
+        assert_eq!(compat.resolve("starknet", V0_8_0, "specVersion"), Some(V0_8_0));
+        assert_eq!(compat.resolve("starknet", V0_7_1, "specVersion"), Some(V0_7_1));
+        assert_eq!(compat.resolve("starknet", V0_8_0, "getNonce"), None);
+
+        assert!(compat.needs_shim("starknet", V0_8_0, V0_7_1, "getTransactionByHash"));
+        assert!(!compat.needs_shim("starknet", V0_7_1, V0_7_1, "getTransactionByHash"));
+        assert!(!compat.needs_shim("starknet", V0_8_0, V0_7_1, "getNonce"));
+    }
+
+    #[test]
+    fn test_shim_transaction() {
+        let l1_gas = json!({ "max_amount": "0x1", "max_price_per_unit": "0x2" });
+        let l2_gas = json!({ "max_amount": "0x0", "max_price_per_unit": "0x0" });
+        let transaction = json!({
+            "type": "INVOKE",
+            "version": "0x3",
+            "transaction_hash": "0x3",
+            "resource_bounds": { "l1_gas": l1_gas, "l2_gas": l2_gas },
+        });
+
+        let shimmed = shim("getTransactionByHash", transaction);
+        let l1_data_gas = json!({ "max_amount": "0x0", "max_price_per_unit": "0x0" });
+        assert_eq!(
+            shimmed["resource_bounds"],
+            json!({ "l1_gas": l1_gas, "l1_data_gas": l1_data_gas, "l2_gas": l2_gas })
+        );
+        assert_eq!(shimmed["transaction_hash"], json!("0x3"));
+
+        let transaction = json!({ "type": "INVOKE", "version": "0x1", "transaction_hash": "0x4" });
+        assert_eq!(shim("getTransactionByBlockIdAndIndex", transaction.clone()), transaction);
+    }
+
+    #[test]
+    fn test_shim_fee_estimates() {
+        let estimate = json!({
+            "gas_consumed": "0x1",
+            "gas_price": "0x2",
+            "data_gas_consumed": "0x3",
+            "data_gas_price": "0x4",
+            "overall_fee": "0x5",
+            "unit": "FRI",
+        });
+        let expected = json!({
+            "l1_gas_consumed": "0x1",
+            "l1_gas_price": "0x2",
+            "l1_data_gas_consumed": "0x3",
+            "l1_data_gas_price": "0x4",
+            "l2_gas_consumed": "0x0",
+            "l2_gas_price": "0x0",
+            "overall_fee": "0x5",
+            "unit": "FRI",
+        });
+
+        assert_eq!(shim("estimateFee", json!([estimate.clone()])), json!([expected.clone()]));
+        assert_eq!(shim("estimateMessageFee", estimate.clone()), expected);
+        assert_eq!(
+            shim("simulateTransactions", json!([{ "transaction_trace": {}, "fee_estimation": estimate }])),
+            json!([{ "transaction_trace": {}, "fee_estimation": expected }])
+        );
+    }
+
+    #[test]
+    fn test_shim_errors_and_unchanged_methods() {
+        let compat = compat();
+        let error = MethodResponse::error(
+            Id::Number(1),
+            jsonrpsee::types::ErrorObject::owned(24, "Block not found", None::<()>),
+        );
+        let shimmed = compat.shim_response(Id::Number(1), error, "starknet", V0_8_0, V0_7_1, "getTransactionByHash");
+        assert_eq!(shimmed.as_error_code(), Some(24));
+
+        assert_eq!(shim("getNonce", json!("0x1")), json!("0x1"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Shedding of the expensive RPC requests while the sync is under pressure.
+//!
+//! When the block import falls behind the fetched blocks, or when RocksDB stalls writes, the expensive requests
+//! (traces, simulations, and event or address activity scans over large block ranges) are rejected with a retryable
+//! error instead of competing with the sync for the CPU and the database. The node keeps shedding for a cooldown
+//! period after the pressure is gone, so that it does not flap between the two states.
+
+use futures::future::{BoxFuture, FutureExt};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mc_rpc::StarknetRpcApiError;
+use mp_block::BlockId;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::methods::MethodClass;
+use super::metrics::RpcMetrics;
+
+#[derive(Clone, Debug)]
+pub struct LoadSheddingConfig {
+    /// Number of fetched blocks waiting to be imported above which the sync is considered under pressure.
+    pub import_backlog_threshold: u64,
+    /// Event and address activity scans over more blocks than this are considered expensive.
+    pub max_events_range: u64,
+    /// How long expensive requests keep being shed once the pressure is gone.
+    pub cooldown: Duration,
+}
+
+pub struct LoadShedder {
+    backend: Arc<MadaraBackend>,
+    config: LoadSheddingConfig,
+    shedding_until: Mutex<Option<Instant>>,
+}
+
+impl LoadShedder {
+    pub fn new(backend: Arc<MadaraBackend>, config: LoadSheddingConfig) -> Self {
+        Self { backend, config, shedding_until: Mutex::new(None) }
+    }
+
+    /// Whether the sync is currently under pressure, or was within the cooldown period.
+    fn is_overloaded(&self) -> bool {
+        let now = Instant::now();
+        let backlog = self.backend.import_backlog().unwrap_or_default();
+        let under_pressure = backlog > self.config.import_backlog_threshold || self.backend.is_write_stalled();
+
+        let mut shedding_until = self.shedding_until.lock().expect("Poisoned lock");
+        if under_pressure {
+            if !shedding_until.is_some_and(|until| now < until) {
+                tracing::warn!(
+                    "🚦 Sync is under pressure (import backlog of {backlog} blocks), shedding expensive RPC requests"
+                );
+            }
+            *shedding_until = Some(now + self.config.cooldown);
+        }
+        shedding_until.is_some_and(|until| now < until)
+    }
+
+    fn is_expensive(&self, req: &jsonrpsee::types::Request) -> bool {
+        match MethodClass::of(req.method_name()) {
+            MethodClass::Trace => true,
+            MethodClass::Events => self.events_range(req).is_some_and(|range| range > self.config.max_events_range),
+            MethodClass::Activity => self.activity_range(req).is_some_and(|range| range > self.config.max_events_range),
+            _ => false,
+        }
+    }
+
+    /// Number of blocks covered by the filter of a getEvents or getEventsBackward call. Returns `None` when the filter
+    /// is invalid, in which case the call is left to fail in the method itself.
+    fn events_range(&self, req: &jsonrpsee::types::Request) -> Option<u64> {
+        #[derive(Deserialize)]
+        struct EventsRange {
+            from_block: Option<BlockId>,
+            to_block: Option<BlockId>,
+        }
+
+        let filter = match req.params().parse::<serde_json::Value>().ok()? {
+            serde_json::Value::Array(params) => params.into_iter().next()?,
+            serde_json::Value::Object(mut params) => params.remove("filter")?,
+            _ => return None,
+        };
+        let EventsRange { from_block, to_block } = serde_json::from_value(filter).ok()?;
+
+        let latest_block_n = self.backend.get_latest_block_n().ok()?.unwrap_or_default();
+        let resolve = |block_id: Option<BlockId>, default: u64| match block_id {
+            None => Some(default),
+            Some(block_id) => match self.backend.resolve_block_id(&block_id).ok()?? {
+                DbBlockId::Number(block_n) => Some(block_n),
+                DbBlockId::Pending => Some(latest_block_n + 1),
+            },
+        };
+        let from_block_n = resolve(from_block, 0)?;
+        let to_block_n = resolve(to_block, latest_block_n)?;
+        Some(to_block_n.saturating_sub(from_block_n))
+    }
+
+    /// Number of blocks covered by a getAddressActivity call, whose bounds are block numbers. Returns `None` when the
+    /// bounds are invalid.
+    fn activity_range(&self, req: &jsonrpsee::types::Request) -> Option<u64> {
+        let (from_block, to_block) = match req.params().parse::<serde_json::Value>().ok()? {
+            serde_json::Value::Array(params) => (params.get(1).cloned(), params.get(2).cloned()),
+            serde_json::Value::Object(mut params) => (params.remove("from_block"), params.remove("to_block")),
+            _ => return None,
+        };
+        let block_n = |block_n: Option<serde_json::Value>| -> Option<Option<u64>> {
+            match block_n {
+                None | Some(serde_json::Value::Null) => Some(None),
+                Some(block_n) => serde_json::from_value(block_n).ok().map(Some),
+            }
+        };
+
+        let latest_block_n = self.backend.get_latest_block_n().ok()?.unwrap_or_default();
+        let from_block_n = block_n(from_block)?.unwrap_or(0);
+        let to_block_n = block_n(to_block)?.unwrap_or(latest_block_n).min(latest_block_n);
+        Some(to_block_n.saturating_sub(from_block_n))
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcMiddlewareServiceLoadShedding<S> {
+    inner: S,
+    shedder: Option<Arc<LoadShedder>>,
+    metrics: RpcMetrics,
+}
+
+impl<S> RpcMiddlewareServiceLoadShedding<S> {
+    pub fn new(inner: S, shedder: Option<Arc<LoadShedder>>, metrics: RpcMetrics) -> Self {
+        Self { inner, shedder, metrics }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcMiddlewareServiceLoadShedding<S>
+where
+    S: Send + Sync + Clone + RpcServiceT<'a> + 'static,
+{
+    type Future = BoxFuture<'a, jsonrpsee::MethodResponse>;
+
+    fn call(&self, req: jsonrpsee::types::Request<'a>) -> Self::Future {
+        let inner = self.inner.clone();
+        let shedder = self.shedder.clone();
+        let metrics = self.metrics.clone();
+
+        async move {
+            if let Some(shedder) = shedder {
+                if shedder.is_expensive(&req) && shedder.is_overloaded() {
+                    metrics.on_shed(&req);
+                    let err = StarknetRpcApiError::Overloaded { retry_after_secs: shedder.config.cooldown.as_secs() };
+                    return jsonrpsee::MethodResponse::error(req.id, jsonrpsee::types::ErrorObjectOwned::from(err));
+                }
+            }
+
+            inner.call(req).await
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::{Id, Request, ResponsePayload};
+    use mp_chain_config::ChainConfig;
+    use serde_json::value::RawValue;
+
+    /// Answers every call, to see which calls make it through the middleware.
+    #[derive(Clone)]
+    struct Echo;
+
+    impl<'a> RpcServiceT<'a> for Echo {
+        type Future = futures::future::Ready<jsonrpsee::MethodResponse>;
+
+        fn call(&self, req: Request<'a>) -> Self::Future {
+            futures::future::ready(jsonrpsee::MethodResponse::response(
+                req.id,
+                ResponsePayload::success("ok"),
+                usize::MAX,
+            ))
+        }
+    }
+
+    fn shedder(cooldown: Duration) -> LoadShedder {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        LoadShedder::new(backend, LoadSheddingConfig { import_backlog_threshold: 10, max_events_range: 100, cooldown })
+    }
+
+    fn params(params: serde_json::Value) -> Box<RawValue> {
+        RawValue::from_string(params.to_string()).unwrap()
+    }
+
+    fn request<'a>(method: &'a str, params: &'a RawValue) -> Request<'a> {
+        Request::new(method.into(), Some(params), Id::Number(1))
+    }
+
+    #[test]
+    fn test_is_expensive() {
+        let shedder = shedder(Duration::from_secs(60));
+        let no_params = params(serde_json::json!([]));
+        assert!(shedder.is_expensive(&request("starknet_V0_7_1_traceTransaction", &no_params)));
+        assert!(shedder.is_expensive(&request("starknet_V0_8_0_traceBlockTransactions", &no_params)));
+        assert!(shedder.is_expensive(&request("starknet_V0_7_1_simulateTransactions", &no_params)));
+        assert!(!shedder.is_expensive(&request("starknet_V0_7_1_estimateFee", &no_params)));
+        assert!(!shedder.is_expensive(&request("starknet_V0_7_1_getNonce", &no_params)));
+
+        let small =
+            params(serde_json::json!([{ "from_block": { "block_number": 0 }, "to_block": { "block_number": 100 } }]));
+        let large =
+            params(serde_json::json!([{ "from_block": { "block_number": 0 }, "to_block": { "block_number": 101 } }]));
+        assert!(!shedder.is_expensive(&request("starknet_V0_7_1_getEvents", &small)));
+        assert!(shedder.is_expensive(&request("starknet_V0_7_1_getEvents", &large)));
+        assert!(!shedder.is_expensive(&request("madara_V0_1_0_getEventsBackward", &small)));
+        assert!(shedder.is_expensive(&request("madara_V0_1_0_getEventsBackward", &large)));
+        // Invalid filters are left to the method.
+        assert!(!shedder.is_expensive(&request("starknet_V0_7_1_getEvents", &no_params)));
+    }
+
+    #[test]
+    fn test_events_range() {
+        let shedder = shedder(Duration::from_secs(60));
+        let range = |params_value: serde_json::Value| {
+            let params = params(params_value);
+            shedder.events_range(&request("starknet_V0_7_1_getEvents", &params))
+        };
+
+        let filter = serde_json::json!({ "from_block": { "block_number": 10 }, "to_block": { "block_number": 50 } });
+        assert_eq!(range(serde_json::json!([filter.clone()])), Some(40));
+        assert_eq!(range(serde_json::json!({ "filter": filter })), Some(40));
+        // The missing bounds default to the genesis block and to the latest block.
+        assert_eq!(range(serde_json::json!([{ "to_block": { "block_number": 50 } }])), Some(50));
+        assert_eq!(range(serde_json::json!([{ "from_block": { "block_number": 10 } }])), Some(0));
+        // The pending block is the one after the latest block.
+        assert_eq!(range(serde_json::json!([{ "to_block": "pending" }])), Some(1));
+        // There is no latest block in an empty database.
+        assert_eq!(range(serde_json::json!([{ "to_block": "latest" }])), None);
+        assert_eq!(range(serde_json::json!([{ "to_block": "not a block" }])), None);
+        assert_eq!(range(serde_json::json!("filter")), None);
+    }
+
+    #[test]
+    fn test_activity_range() {
+        let shedder = shedder(Duration::from_secs(60));
+        let range = |params_value: serde_json::Value| {
+            let params = params(params_value);
+            shedder.activity_range(&request("madara_V0_1_0_getAddressActivity", &params))
+        };
+
+        // The range is bounded by the latest block, which is the genesis block in an empty database.
+        assert_eq!(range(serde_json::json!(["0x1", 0, 101])), Some(0));
+        assert_eq!(range(serde_json::json!({ "address": "0x1", "from_block": 0 })), Some(0));
+        assert_eq!(range(serde_json::json!(["0x1", null, null])), Some(0));
+        assert_eq!(range(serde_json::json!(["0x1", "not a block"])), None);
+        assert_eq!(range(serde_json::json!("0x1")), None);
+    }
+
+    #[tokio::test]
+    async fn test_shedding_under_pressure() {
+        let shedder = Arc::new(shedder(Duration::from_millis(200)));
+        let service =
+            RpcMiddlewareServiceLoadShedding::new(Echo, Some(shedder.clone()), RpcMetrics::register().unwrap());
+        let no_params = params(serde_json::json!([]));
+        let call = |method: &'static str| service.call(request(method, &no_params));
+
+        assert!(call("starknet_V0_7_1_traceTransaction").await.is_success());
+
+        // The block import falls behind the fetched blocks.
+        shedder.backend.set_sync_fetched_block_n(20);
+        assert_eq!(call("starknet_V0_7_1_traceTransaction").await.as_error_code(), Some(10002));
+        assert!(call("starknet_V0_7_1_getNonce").await.is_success());
+
+        // The pressure is gone, but the node keeps shedding until the cooldown has elapsed.
+        shedder.backend.set_sync_fetched_block_n(0);
+        assert_eq!(call("starknet_V0_7_1_traceTransaction").await.as_error_code(), Some(10002));
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(call("starknet_V0_7_1_traceTransaction").await.is_success());
+    }
+}
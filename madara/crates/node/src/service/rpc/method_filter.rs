@@ -0,0 +1,43 @@
+//! Methods disabled by the operator.
+//!
+//! Some methods are too expensive or too sensitive to be exposed everywhere, such as the traces on public
+//! infrastructure. Calls to a disabled method are answered as if the method did not exist, on every version.
+
+/// Patterns of the disabled methods, see [`RpcMethodFilter::is_disabled`].
+#[derive(Debug, Clone, Default)]
+pub struct RpcMethodFilter {
+    patterns: Vec<String>,
+}
+
+impl RpcMethodFilter {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    /// Whether the method `{namespace}_{method}` is disabled. It is when it matches one of the patterns: either the
+    /// method name, such as `starknet_traceTransaction`, or a prefix followed by `*`, such as `starknet_trace*` or
+    /// `madara_*`.
+    pub fn is_disabled(&self, namespace: &str, method: &str) -> bool {
+        let name = format!("{namespace}_{method}");
+        self.patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => &name == pattern,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_filter() {
+        let filter = RpcMethodFilter::new(vec!["starknet_trace*".into(), "starknet_estimateFee".into()]);
+        assert!(filter.is_disabled("starknet", "traceTransaction"));
+        assert!(filter.is_disabled("starknet", "traceBlockTransactions"));
+        assert!(filter.is_disabled("starknet", "estimateFee"));
+        assert!(!filter.is_disabled("starknet", "estimateMessageFee"));
+        assert!(!filter.is_disabled("starknet", "getBlockWithTxs"));
+        assert!(!RpcMethodFilter::default().is_disabled("starknet", "traceTransaction"));
+    }
+}
@@ -0,0 +1,78 @@
+//! Classification of the RPC methods by cost, shared by the middlewares which treat the methods differently.
+
+use serde::Serialize;
+
+/// Name of a method without its namespace and version. Method names are versioned by the time they reach the
+/// middlewares, e.g. `starknet_V0_7_1_getEvents`.
+pub fn base_name(method_name: &str) -> &str {
+    method_name.rsplit('_').next().unwrap_or_default()
+}
+
+/// Class of an RPC method, with the compute units a call costs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodClass {
+    Read,
+    /// Scans of the events over a block range.
+    Events,
+    /// Scans of the activity of an address over a block range.
+    Activity,
+    Write,
+    Execution,
+    Proof,
+    Trace,
+}
+
+impl MethodClass {
+    pub fn of(method_name: &str) -> Self {
+        match base_name(method_name) {
+            "getEvents" | "getEventsBackward" => Self::Events,
+            "getAddressActivity" => Self::Activity,
+            "addInvokeTransaction" | "addDeclareTransaction" | "addDeployAccountTransaction" => Self::Write,
+            "call" | "estimateFee" | "estimateMessageFee" | "estimateFeeAtCurrentPrices" => Self::Execution,
+            "getStorageProof" => Self::Proof,
+            "traceTransaction" | "traceBlockTransactions" | "simulateTransactions" => Self::Trace,
+            _ => Self::Read,
+        }
+    }
+
+    pub fn compute_units(&self) -> u64 {
+        match self {
+            Self::Read => 1,
+            Self::Events => 10,
+            Self::Activity => 10,
+            Self::Write => 10,
+            Self::Execution => 20,
+            Self::Proof => 20,
+            Self::Trace => 50,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Events => "events",
+            Self::Activity => "activity",
+            Self::Write => "write",
+            Self::Execution => "execution",
+            Self::Proof => "proof",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_class() {
+        assert_eq!(base_name("starknet_V0_7_1_getEvents"), "getEvents");
+        assert_eq!(base_name("getClass"), "getClass");
+        assert_eq!(MethodClass::of("starknet_V0_7_1_getEvents"), MethodClass::Events);
+        assert_eq!(MethodClass::of("madara_V0_1_0_getEventsBackward"), MethodClass::Events);
+        assert_eq!(MethodClass::of("madara_V0_1_0_getAddressActivity"), MethodClass::Activity);
+        assert_eq!(MethodClass::of("starknet_V0_8_0_traceBlockTransactions"), MethodClass::Trace);
+        assert_eq!(MethodClass::of("starknet_V0_7_1_getNonce"), MethodClass::Read);
+    }
+}
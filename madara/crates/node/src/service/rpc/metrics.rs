@@ -8,6 +8,7 @@ use opentelemetry::{
 };
 
 use mc_analytics::{register_counter_metric_instrument, register_histogram_metric_instrument};
+use mp_chain_config::RpcVersion;
 use opentelemetry::{global, KeyValue};
 
 /// Metrics for RPC middleware storing information about the number of requests started/completed,
@@ -20,6 +21,14 @@ pub struct RpcMetrics {
     calls_started: Counter<u64>,
     /// Number of calls completed.
     calls_finished: Counter<u64>,
+    /// Number of calls which returned an error.
+    calls_failed: Counter<u64>,
+    /// Number of calls rejected because the sync is under pressure.
+    calls_shed: Counter<u64>,
+    /// Number of calls made on the path of an older version than the default one.
+    calls_legacy: Counter<u64>,
+    /// Number of calls served by the implementation of an older version than the one requested.
+    calls_compat: Counter<u64>,
     /// Number of Websocket sessions opened.
     ws_sessions_opened: Option<Counter<u64>>,
     /// Number of Websocket sessions closed.
@@ -53,6 +62,35 @@ impl RpcMetrics {
             "".to_string(),
         );
 
+        let calls_failed = register_counter_metric_instrument(
+            &rpc_meter,
+            "calls_failed".to_string(),
+            "A counter to show the number of RPC calls which returned an error, by error code".to_string(),
+            "".to_string(),
+        );
+
+        let calls_shed = register_counter_metric_instrument(
+            &rpc_meter,
+            "calls_shed".to_string(),
+            "A counter to show the number of calls rejected because the sync is under pressure".to_string(),
+            "".to_string(),
+        );
+
+        let calls_legacy = register_counter_metric_instrument(
+            &rpc_meter,
+            "calls_legacy".to_string(),
+            "A counter to show the number of calls made on the path of an older RPC version than the default one"
+                .to_string(),
+            "".to_string(),
+        );
+
+        let calls_compat = register_counter_metric_instrument(
+            &rpc_meter,
+            "calls_compat".to_string(),
+            "A counter to show the number of calls served by the implementation of an older RPC version".to_string(),
+            "".to_string(),
+        );
+
         let calls_time = register_histogram_metric_instrument(
             &rpc_meter,
             "calls_time".to_string(),
@@ -81,7 +119,18 @@ impl RpcMetrics {
             "".to_string(),
         );
 
-        Ok(Self { calls_time, calls_started, calls_finished, ws_sessions_opened, ws_sessions_closed, ws_sessions_time })
+        Ok(Self {
+            calls_time,
+            calls_started,
+            calls_finished,
+            calls_failed,
+            calls_shed,
+            calls_legacy,
+            calls_compat,
+            ws_sessions_opened,
+            ws_sessions_closed,
+            ws_sessions_time,
+        })
     }
 
     pub(crate) fn ws_connect(&self) {
@@ -109,6 +158,26 @@ impl RpcMetrics {
         self.calls_started.add(1, &[KeyValue::new("method", req.method_name().to_string())]);
     }
 
+    pub(crate) fn on_shed(&self, req: &Request) {
+        self.calls_shed.add(1, &[KeyValue::new("method", req.method_name().to_string())]);
+    }
+
+    pub(crate) fn on_legacy_call(&self, version: RpcVersion, method: &str) {
+        self.calls_legacy
+            .add(1, &[KeyValue::new("version", version.to_string()), KeyValue::new("method", method.to_string())]);
+    }
+
+    pub(crate) fn on_compat_call(&self, version: RpcVersion, served_version: RpcVersion, method: &str) {
+        self.calls_compat.add(
+            1,
+            &[
+                KeyValue::new("version", version.to_string()),
+                KeyValue::new("served_version", served_version.to_string()),
+                KeyValue::new("method", method.to_string()),
+            ],
+        );
+    }
+
     pub(crate) fn on_response(&self, req: &Request, rp: &MethodResponse, transport_label: &'static str, now: Instant) {
         tracing::trace!(target: "rpc_metrics", "[{transport_label}] on_response started_at={:?}", now);
         tracing::trace!(target: "rpc_metrics::extra", "[{transport_label}] result={}", rp.as_result());
@@ -121,14 +190,45 @@ impl RpcMetrics {
             millis,
         );
 
-        self.calls_time.record(millis as f64, &[KeyValue::new("method", req.method_name().to_string())]);
+        let [method, version] = method_labels(req.method_name());
+        self.calls_time.record(millis as f64, &[method.clone(), version.clone()]);
 
-        self.calls_finished.add(
-            1,
-            &[
-                KeyValue::new("method", req.method_name().to_string()),
-                KeyValue::new("success", rp.is_success().to_string()),
-            ],
+        self.calls_finished
+            .add(1, &[method.clone(), version.clone(), KeyValue::new("success", rp.is_success().to_string())]);
+
+        if let Some(code) = rp.as_error_code() {
+            self.calls_failed.add(1, &[method, version, KeyValue::new("code", code.to_string())]);
+        }
+    }
+}
+
+/// The `method` and `version` labels of a call. The version middleware renames versioned methods to
+/// `{namespace}_V{major}_{minor}_{patch}_{method}`, with the version which serves the call: they are labelled
+/// `{namespace}_{method}` and `{major}.{minor}.{patch}`, so that the calls to a method can be aggregated across
+/// versions. Other methods have an empty version.
+fn method_labels(name: &str) -> [KeyValue; 2] {
+    let (method, version) = match name.split('_').collect::<Vec<_>>()[..] {
+        [namespace, major, minor, patch, method] if major.starts_with('V') => {
+            (format!("{namespace}_{method}"), format!("{}.{minor}.{patch}", &major[1..]))
+        }
+        _ => (name.to_string(), String::new()),
+    };
+    [KeyValue::new("method", method), KeyValue::new("version", version)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_labels() {
+        assert_eq!(
+            method_labels("starknet_V0_7_1_getBlockWithTxs"),
+            [KeyValue::new("method", "starknet_getBlockWithTxs"), KeyValue::new("version", "0.7.1")]
+        );
+        assert_eq!(
+            method_labels("rpc_methods"),
+            [KeyValue::new("method", "rpc_methods"), KeyValue::new("version", "")]
         );
     }
 }
@@ -4,9 +4,13 @@ use futures::future::{BoxFuture, FutureExt};
 use jsonrpsee::server::middleware::rpc::RpcServiceT;
 use mc_rpc::utils::ResultExt;
 use mp_chain_config::RpcVersion;
+use std::sync::Arc;
 use std::time::Instant;
 
+use super::compat::RpcCompat;
+use super::method_filter::RpcMethodFilter;
 pub use super::metrics::Metrics;
+use super::metrics::RpcMetrics;
 
 #[derive(Debug, Clone)]
 pub struct RpcMiddlewareLayerMetrics {
@@ -87,11 +91,21 @@ pub struct RpcMiddlewareServiceVersion<S> {
     inner: S,
     path: String,
     version_default: RpcVersion,
+    compat: Arc<RpcCompat>,
+    method_filter: Arc<RpcMethodFilter>,
+    metrics: RpcMetrics,
 }
 
 impl<S> RpcMiddlewareServiceVersion<S> {
-    pub fn new(inner: S, path: String, version_default: RpcVersion) -> Self {
-        Self { inner, path, version_default }
+    pub fn new(
+        inner: S,
+        path: String,
+        version_default: RpcVersion,
+        compat: Arc<RpcCompat>,
+        method_filter: Arc<RpcMethodFilter>,
+        metrics: RpcMetrics,
+    ) -> Self {
+        Self { inner, path, version_default, compat, method_filter, metrics }
     }
 }
 
@@ -105,6 +119,9 @@ where
         let inner = self.inner.clone();
         let path = self.path.clone();
         let version_default = self.version_default;
+        let compat = Arc::clone(&self.compat);
+        let method_filter = Arc::clone(&self.method_filter);
+        let metrics = self.metrics.clone();
 
         async move {
             if req.method == "rpc_methods" {
@@ -112,7 +129,6 @@ where
             }
 
             let version = match RpcVersion::from_request_path(&path, version_default)
-                .map(|v| v.name())
                 .or_internal_server_error("Failed to get request path")
             {
                 Ok(version) => version,
@@ -139,11 +155,39 @@ where
                 );
             };
 
-            let method = method.replacen(&format!("{version}_"), "", 1);
-            let method_new = format!("{namespace}_{version}_{method}");
+            let namespace = namespace.to_string();
+            let method = method.replacen(&format!("{}_", version.name()), "", 1);
+            if method_filter.is_disabled(&namespace, &method) {
+                return jsonrpsee::MethodResponse::error(
+                    req.id(),
+                    jsonrpsee::types::ErrorObject::owned(
+                        jsonrpsee::types::error::METHOD_NOT_FOUND_CODE,
+                        jsonrpsee::types::error::METHOD_NOT_FOUND_MSG,
+                        Some(req.method_name()),
+                    ),
+                );
+            }
+            if version < version_default {
+                metrics.on_legacy_call(version, &method);
+            }
+            // Methods which did not change since an older version are served by the implementation of that version.
+            let served_version = match compat.resolve(&namespace, version, &method) {
+                Some(served_version) if served_version != version => {
+                    metrics.on_compat_call(version, served_version, &method);
+                    served_version
+                }
+                _ => version,
+            };
+            let method_new = format!("{namespace}_{}_{method}", served_version.name());
             req.method = jsonrpsee::core::Cow::from(method_new);
 
-            inner.call(req).await
+            // Their results are then converted to the shape of the requested version.
+            if !compat.needs_shim(&namespace, version, served_version, &method) {
+                return inner.call(req).await;
+            }
+            let id = req.id().into_owned();
+            let response = inner.call(req).await;
+            compat.shim_response(id, response, &namespace, version, served_version, &method)
         }
         .boxed()
     }
@@ -1,24 +1,39 @@
+use anyhow::Context;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use jsonrpsee::server::ServerHandle;
+use tokio::sync::watch;
 
+use mc_block_import::BlockImporter;
 use mc_db::MadaraBackend;
+use mc_mempool::Mempool;
 use mc_rpc::{
+    outside_execution::OutsideExecutor,
     providers::{AddTransactionProvider, AddTransactionProviderGroup},
     rpc_api_admin, rpc_api_user, Starknet,
 };
 use mp_utils::service::{MadaraServiceId, PowerOfTwo, Service, ServiceId, ServiceRunner};
 
+use load_shedding::{LoadShedder, LoadSheddingConfig};
+use method_filter::RpcMethodFilter;
 use metrics::RpcMetrics;
 use server::{start_server, ServerConfig};
+use usage::{UsageAccounting, UsageConfig};
 
-use crate::cli::RpcParams;
+use crate::cli::{NodeRole, RpcParams};
 
 use self::server::rpc_api_build;
 
+mod class_artifacts;
+mod compat;
+mod load_shedding;
+mod method_filter;
+mod methods;
 mod metrics;
 mod middleware;
 mod server;
+mod usage;
 
 #[derive(Clone)]
 pub enum RpcType {
@@ -31,7 +46,19 @@ pub struct RpcService {
     backend: Arc<MadaraBackend>,
     add_txs_provider_l2_sync: Arc<dyn AddTransactionProvider>,
     add_txs_provider_mempool: Arc<dyn AddTransactionProvider>,
+    /// Mempool of a sequencer, which the block production dry runs are made against, the txpool methods inspect and
+    /// the current gas prices are taken from.
+    mempool: Option<Arc<Mempool>>,
+    /// Importer of the blocks submitted on the admin endpoint.
+    block_importer: Option<Arc<BlockImporter>>,
+    /// Executor of the outside executions, shared by the user and admin endpoints.
+    outside_executor: Option<Arc<OutsideExecutor>>,
+    /// Role of the node, which decides the namespaces of the admin endpoint.
+    role: NodeRole,
     server_handle: Option<ServerHandle>,
+    /// Address the server listens at, once it is bound. The sender is moved into the server when it starts, so that
+    /// it is dropped when the server stops or fails to bind and the waiters on the address are released.
+    bound_addr: Option<watch::Sender<Option<SocketAddr>>>,
     rpc_type: RpcType,
 }
 
@@ -41,13 +68,21 @@ impl RpcService {
         backend: Arc<MadaraBackend>,
         add_txs_provider_l2_sync: Arc<dyn AddTransactionProvider>,
         add_txs_provider_mempool: Arc<dyn AddTransactionProvider>,
+        mempool: Option<Arc<Mempool>>,
+        outside_executor: Option<Arc<OutsideExecutor>>,
+        role: NodeRole,
     ) -> Self {
         Self {
             config,
             backend,
             add_txs_provider_l2_sync,
             add_txs_provider_mempool,
+            mempool,
+            block_importer: None,
+            outside_executor,
+            role,
             server_handle: None,
+            bound_addr: Some(watch::Sender::new(None)),
             rpc_type: RpcType::User,
         }
     }
@@ -57,16 +92,36 @@ impl RpcService {
         backend: Arc<MadaraBackend>,
         add_txs_provider_l2_sync: Arc<dyn AddTransactionProvider>,
         add_txs_provider_mempool: Arc<dyn AddTransactionProvider>,
+        mempool: Option<Arc<Mempool>>,
+        block_importer: Arc<BlockImporter>,
+        outside_executor: Option<Arc<OutsideExecutor>>,
+        role: NodeRole,
     ) -> Self {
         Self {
             config,
             backend,
             add_txs_provider_l2_sync,
             add_txs_provider_mempool,
+            mempool,
+            block_importer: Some(block_importer),
+            outside_executor,
+            role,
             server_handle: None,
+            bound_addr: Some(watch::Sender::new(None)),
             rpc_type: RpcType::Admin,
         }
     }
+
+    /// Address the server listens at, which is `None` until it is started. This is the actual port when the server
+    /// is configured to listen on port 0. The channel is closed without an address if the server fails to start.
+    ///
+    /// Only the first start of the server is reported.
+    pub fn local_addr(&self) -> watch::Receiver<Option<SocketAddr>> {
+        match &self.bound_addr {
+            Some(bound_addr) => bound_addr.subscribe(),
+            None => watch::Sender::new(None).subscribe(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -76,7 +131,12 @@ impl Service for RpcService {
         let backend = Arc::clone(&self.backend);
         let add_tx_provider_l2_sync = Arc::clone(&self.add_txs_provider_l2_sync);
         let add_tx_provider_mempool = Arc::clone(&self.add_txs_provider_mempool);
+        let mempool = self.mempool.clone();
+        let block_importer = self.block_importer.clone();
+        let outside_executor = self.outside_executor.clone();
         let rpc_type = self.rpc_type.clone();
+        let role = self.role;
+        let bound_addr = self.bound_addr.take().unwrap_or_else(|| watch::Sender::new(None));
 
         let (stop_handle, server_handle) = jsonrpsee::server::stop_channel();
 
@@ -89,8 +149,46 @@ impl Service for RpcService {
                 ctx.clone(),
             ));
 
-            let starknet = Starknet::new(backend.clone(), add_tx_provider, config.storage_proof_config(), ctx.clone());
+            let mut starknet =
+                Starknet::new(backend.clone(), add_tx_provider, config.storage_proof_config(), ctx.clone())
+                    .with_max_page_bytes(config.max_page_bytes());
+            if let Some(outside_executor) = outside_executor {
+                starknet = starknet.with_outside_executor(outside_executor);
+            }
+            if let Some(mempool) = mempool {
+                starknet = starknet.with_mempool(mempool);
+            }
+            if let (Some(block_importer), Some(key)) = (block_importer, config.rpc_block_submission_key.clone()) {
+                starknet = starknet.with_block_submission(block_importer, key);
+            }
             let metrics = RpcMetrics::register()?;
+            let load_shedder = config.rpc_load_shedding.then(|| {
+                Arc::new(LoadShedder::new(
+                    backend.clone(),
+                    LoadSheddingConfig {
+                        import_backlog_threshold: config.rpc_load_shedding_import_backlog,
+                        max_events_range: config.rpc_load_shedding_max_events_range,
+                        cooldown: config.rpc_load_shedding_cooldown,
+                    },
+                ))
+            });
+
+            // Usage is only accounted on the user endpoint, the admin endpoint is not meant to be exposed to tenants.
+            let usage = match (&rpc_type, &config.rpc_usage_export_path) {
+                (RpcType::User, Some(path)) => Some(Arc::new(UsageAccounting::new(UsageConfig {
+                    api_key_header: config.rpc_usage_api_key_header.clone(),
+                    api_keys: match &config.rpc_usage_api_keys {
+                        Some(api_keys) => usage::read_api_keys(api_keys)
+                            .with_context(|| format!("Reading the RPC API keys from {}", api_keys.display()))?,
+                        None => Default::default(),
+                    },
+                    max_tenants: config.rpc_usage_max_tenants,
+                    path: path.clone(),
+                    format: config.rpc_usage_export_format,
+                    interval: config.rpc_usage_export_interval,
+                }))),
+                _ => None,
+            };
 
             let server_config = {
                 let (name, addr, api_rpc, rpc_version_default) = match rpc_type {
@@ -103,7 +201,7 @@ impl Service for RpcService {
                     RpcType::Admin => (
                         "JSON-RPC (Admin)".to_string(),
                         config.addr_admin(),
-                        rpc_api_admin(&starknet)?,
+                        rpc_api_admin(&starknet, role.serves_block_production_rpc())?,
                         mp_chain_config::RpcVersion::RPC_VERSION_LATEST_ADMIN,
                     ),
                 };
@@ -122,10 +220,21 @@ impl Service for RpcService {
                     metrics,
                     cors: config.cors(),
                     rpc_version_default,
+                    load_shedder,
+                    usage: usage.clone(),
+                    compression: config.rpc_compression,
+                    method_filter: Arc::new(RpcMethodFilter::new(config.rpc_disable_methods.clone())),
+                    bound_addr,
                 }
             };
 
-            start_server(server_config, ctx.clone(), stop_handle).await?;
+            let usage_export = async {
+                match &usage {
+                    Some(usage) => usage.run_export(ctx.clone()).await,
+                    None => anyhow::Ok(()),
+                }
+            };
+            tokio::try_join!(start_server(server_config, ctx.clone(), stop_handle), usage_export)?;
 
             anyhow::Ok(())
         });
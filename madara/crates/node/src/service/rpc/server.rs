@@ -3,14 +3,22 @@
 
 use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
+use mc_db::read_scope::ReadScope;
 use mp_utils::service::ServiceContext;
 use tower::Service;
 
 use crate::service::rpc::middleware::RpcMiddlewareServiceVersion;
 
+use super::class_artifacts::ClassArtifactsLayer;
+use super::compat::RpcCompat;
+use super::load_shedding::{LoadShedder, RpcMiddlewareServiceLoadShedding};
+use super::method_filter::RpcMethodFilter;
+use super::usage::{RpcMiddlewareServiceUsage, UsageAccounting};
+
 use super::metrics::RpcMetrics;
 use super::middleware::{Metrics, RpcMiddlewareLayerMetrics};
 
@@ -32,6 +40,16 @@ pub struct ServerConfig {
     pub methods: jsonrpsee::Methods,
     /// Batch request config.
     pub batch_config: jsonrpsee::server::BatchRequestConfig,
+    /// Shedding of the expensive requests while the sync is under pressure, disabled when `None`.
+    pub load_shedder: Option<Arc<LoadShedder>>,
+    /// Accounting of the usage per API key, disabled when `None`.
+    pub usage: Option<Arc<UsageAccounting>>,
+    /// Compression of the class responses.
+    pub compression: bool,
+    /// Methods disabled by the operator.
+    pub method_filter: Arc<RpcMethodFilter>,
+    /// Receives the address the server listens at, once it is bound.
+    pub bound_addr: tokio::sync::watch::Sender<Option<SocketAddr>>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +57,8 @@ struct PerConnection<RpcMiddleware, HttpMiddleware> {
     methods: jsonrpsee::Methods,
     stop_handle: jsonrpsee::server::StopHandle,
     metrics: RpcMetrics,
+    load_shedder: Option<Arc<LoadShedder>>,
+    usage: Option<Arc<UsageAccounting>>,
     service_builder: jsonrpsee::server::TowerServiceBuilder<RpcMiddleware, HttpMiddleware>,
 }
 
@@ -63,12 +83,18 @@ pub async fn start_server<'a>(
         message_buffer_capacity,
         methods,
         batch_config,
+        load_shedder,
+        usage,
+        compression,
+        method_filter,
+        bound_addr,
     } = config;
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .with_context(|| format!("Binding TCP listener to address: {addr}"))?;
     let local_addr = listener.local_addr().context("Failed to retrieve local address after binding TCP listener")?;
+    bound_addr.send_replace(Some(local_addr));
 
     let ping_config = jsonrpsee::server::PingConfig::new()
         .ping_interval(Duration::from_secs(30))
@@ -77,7 +103,8 @@ pub async fn start_server<'a>(
 
     let http_middleware = tower::ServiceBuilder::new()
         .option_layer(host_filtering(cors.is_some(), local_addr))
-        .layer(try_into_cors(cors.as_ref())?);
+        .layer(try_into_cors(cors.as_ref())?)
+        .layer(ClassArtifactsLayer::new(compression));
 
     let builder = jsonrpsee::server::Server::builder()
         .max_request_body_size(max_payload_in_mb.saturating_mul(MEGABYTE))
@@ -90,35 +117,59 @@ pub async fn start_server<'a>(
         .set_http_middleware(http_middleware)
         .set_id_provider(jsonrpsee::server::RandomStringIdProvider::new(16));
 
+    let compat = Arc::new(RpcCompat::new(&methods, max_payload_out_mb.saturating_mul(MEGABYTE) as usize));
     let cfg = PerConnection {
         methods,
         stop_handle: stop_handle.clone(),
         metrics,
+        load_shedder,
+        usage,
         service_builder: builder.to_service_builder(),
     };
     let ctx1 = ctx.clone();
 
-    let make_service = hyper::service::make_service_fn(move |_| {
+    let make_service = hyper::service::make_service_fn(move |conn: &hyper::server::conn::AddrStream| {
+        let remote_ip = conn.remote_addr().ip();
         let cfg = cfg.clone();
         let ctx1 = ctx1.clone();
+        let compat = Arc::clone(&compat);
+        let method_filter = Arc::clone(&method_filter);
 
         async move {
             let cfg = cfg.clone();
 
             Ok::<_, Infallible>(hyper::service::service_fn(move |req| {
-                let PerConnection { service_builder, metrics, stop_handle, methods } = cfg.clone();
+                let PerConnection { service_builder, metrics, load_shedder, usage, stop_handle, methods } = cfg.clone();
                 let ctx1 = ctx1.clone();
+                let compat = Arc::clone(&compat);
+                let method_filter = Arc::clone(&method_filter);
+                let version_metrics = metrics.clone();
 
                 let is_websocket = jsonrpsee::server::ws::is_upgrade_request(&req);
                 let transport_label = if is_websocket { "ws" } else { "http" };
                 let path = req.uri().path().to_string();
-                let metrics_layer = RpcMiddlewareLayerMetrics::new(Metrics::new(metrics, transport_label));
+                let tenant = usage.as_ref().map(|usage| {
+                    let api_key = req.headers().get(usage.api_key_header()).and_then(|value| value.to_str().ok());
+                    usage.tenant(api_key, remote_ip)
+                });
+                let metrics_layer = RpcMiddlewareLayerMetrics::new(Metrics::new(metrics.clone(), transport_label));
 
                 let rpc_middleware = jsonrpsee::server::RpcServiceBuilder::new()
                     .layer_fn(move |service| {
-                        RpcMiddlewareServiceVersion::new(service, path.clone(), rpc_version_default)
+                        RpcMiddlewareServiceVersion::new(
+                            service,
+                            path.clone(),
+                            rpc_version_default,
+                            Arc::clone(&compat),
+                            Arc::clone(&method_filter),
+                            version_metrics.clone(),
+                        )
                     })
-                    .layer(metrics_layer.clone());
+                    .layer(metrics_layer.clone())
+                    .layer_fn(move |service| RpcMiddlewareServiceUsage::new(service, usage.clone(), tenant.clone()))
+                    .layer_fn(move |service| {
+                        RpcMiddlewareServiceLoadShedding::new(service, load_shedder.clone(), metrics.clone())
+                    });
 
                 let mut svc = service_builder.set_rpc_middleware(rpc_middleware).build(methods, stop_handle);
 
@@ -142,9 +193,14 @@ pub async fn start_server<'a>(
                                 on_disconnect.await;
                                 metrics_layer.ws_disconnect(now);
                             });
-                        }
 
-                        svc.call(req).await
+                            svc.call(req).await
+                        } else {
+                            // All the calls of an http request (and so of a batch) share a read scope, so that
+                            // they read from the same database snapshot and blocks referenced by many calls are only
+                            // resolved once.
+                            ReadScope::run(svc.call(req)).await
+                        }
                     }
                 }
             }))
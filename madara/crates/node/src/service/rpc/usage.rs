@@ -0,0 +1,323 @@
+//! Accounting of the RPC usage per tenant.
+//!
+//! RPC providers running Madara for several tenants need to know how much each of them uses the node, to bill or rate
+//! limit them. Every request is accounted to a tenant: the number of requests and the compute units spent per method
+//! class, and the bytes received and sent. The usage is periodically exported to a file.
+//!
+//! A request is accounted to the tenant of its API key when the key is one of the configured keys, and to its IP
+//! address otherwise, so that a client cannot charge its usage to another tenant by sending an arbitrary key. The
+//! number of tenants is bounded, the usage of the tenants over the bound is accounted as [`OTHER`].
+
+use futures::future::{BoxFuture, FutureExt};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use mp_utils::service::ServiceContext;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncWriteExt;
+
+use super::methods::MethodClass;
+use crate::cli::UsageExportFormat;
+
+/// Tenant of the requests over the bound on the number of tenants.
+const OTHER: &str = "other";
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ClassUsage {
+    pub requests: u64,
+    pub compute_units: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TenantUsage {
+    pub classes: BTreeMap<MethodClass, ClassUsage>,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct UsageConfig {
+    /// HTTP header holding the API key of the requests.
+    pub api_key_header: String,
+    /// Tenants by API key.
+    pub api_keys: HashMap<String, Arc<str>>,
+    /// Maximum number of tenants accounted separately.
+    pub max_tenants: usize,
+    pub path: PathBuf,
+    pub format: UsageExportFormat,
+    pub interval: Duration,
+}
+
+pub struct UsageAccounting {
+    config: UsageConfig,
+    /// Usage since the last export.
+    interval_usage: Mutex<HashMap<String, TenantUsage>>,
+    /// Usage since the node started.
+    total_usage: Mutex<HashMap<String, TenantUsage>>,
+}
+
+impl UsageAccounting {
+    pub fn new(config: UsageConfig) -> Self {
+        Self { config, interval_usage: Default::default(), total_usage: Default::default() }
+    }
+
+    pub fn api_key_header(&self) -> &str {
+        &self.config.api_key_header
+    }
+
+    /// The tenant of a request: the tenant of its API key if it is a configured one, or else its IP address.
+    pub fn tenant(&self, api_key: Option<&str>, ip: IpAddr) -> Arc<str> {
+        match api_key.and_then(|api_key| self.config.api_keys.get(api_key)) {
+            Some(tenant) => Arc::clone(tenant),
+            None => ip.to_string().into(),
+        }
+    }
+
+    fn record(&self, tenant: &str, method_name: &str, bytes_in: u64, bytes_out: u64) {
+        let class = MethodClass::of(method_name);
+        for usage in [&self.interval_usage, &self.total_usage] {
+            let mut usage = usage.lock().expect("Poisoned lock");
+            let tenant =
+                if usage.contains_key(tenant) || usage.len() < self.config.max_tenants { tenant } else { OTHER };
+            let tenant = usage.entry(tenant.to_string()).or_default();
+            let class_usage = tenant.classes.entry(class).or_default();
+            class_usage.requests += 1;
+            class_usage.compute_units += class.compute_units();
+            tenant.bytes_in += bytes_in;
+            tenant.bytes_out += bytes_out;
+        }
+    }
+
+    /// Exports the usage to the configured file every interval, until the service is stopped.
+    pub async fn run_export(&self, mut ctx: ServiceContext) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(self.config.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        interval.tick().await;
+        while ctx.run_until_cancelled(interval.tick()).await.is_some() {
+            if let Err(err) = self.export().await {
+                tracing::warn!("Failed to export the RPC usage to {}: {err:#}", self.config.path.display());
+            }
+        }
+        Ok(())
+    }
+
+    async fn export(&self) -> anyhow::Result<()> {
+        match self.config.format {
+            UsageExportFormat::JsonLines => {
+                let usage = std::mem::take(&mut *self.interval_usage.lock().expect("Poisoned lock"));
+                let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+                let mut lines = String::new();
+                for (tenant, usage) in usage {
+                    let line = serde_json::json!({
+                        "timestamp": timestamp,
+                        "interval_secs": self.config.interval.as_secs(),
+                        "tenant": tenant,
+                        "usage": usage,
+                    });
+                    writeln!(lines, "{line}")?;
+                }
+
+                let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.config.path).await?;
+                file.write_all(lines.as_bytes()).await?;
+            }
+            UsageExportFormat::Prometheus => {
+                let usage = self.total_usage.lock().expect("Poisoned lock").clone();
+                // Written to a temporary file first, so that the collector never reads a partial export.
+                let tmp_path = self.config.path.with_extension("tmp");
+                tokio::fs::write(&tmp_path, prometheus_text(&usage)).await?;
+                tokio::fs::rename(&tmp_path, &self.config.path).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the tenants of the API keys from a file with a `<tenant> <api key>` pair per line. Empty lines and lines
+/// starting with `#` are ignored.
+pub fn read_api_keys(path: &Path) -> anyhow::Result<HashMap<String, Arc<str>>> {
+    let file = std::fs::read_to_string(path)?;
+    parse_api_keys(&file)
+}
+
+fn parse_api_keys(file: &str) -> anyhow::Result<HashMap<String, Arc<str>>> {
+    let mut api_keys = HashMap::new();
+    for (i, line) in file.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((tenant, api_key)) = line.split_once(char::is_whitespace) else {
+            anyhow::bail!("Line {i}: expected a tenant and an API key");
+        };
+        let api_key = api_key.trim();
+        anyhow::ensure!(!api_key.contains(char::is_whitespace), "Line {i}: the API key contains a whitespace");
+        anyhow::ensure!(tenant != OTHER, "Line {i}: the tenant `{OTHER}` is reserved");
+        anyhow::ensure!(api_keys.insert(api_key.to_string(), tenant.into()).is_none(), "Line {i}: duplicate API key");
+    }
+    Ok(api_keys)
+}
+
+fn prometheus_text(usage: &HashMap<String, TenantUsage>) -> String {
+    let mut tenants: Vec<_> = usage.iter().collect();
+    tenants.sort_by(|a, b| a.0.cmp(b.0));
+    let label = |name: &str| name.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut out = String::new();
+    out.push_str("# TYPE madara_rpc_usage_requests_total counter\n");
+    for (name, tenant) in &tenants {
+        for (class, class_usage) in &tenant.classes {
+            let _ = writeln!(
+                out,
+                "madara_rpc_usage_requests_total{{tenant=\"{}\",class=\"{}\"}} {}",
+                label(name),
+                class.name(),
+                class_usage.requests
+            );
+        }
+    }
+    out.push_str("# TYPE madara_rpc_usage_compute_units_total counter\n");
+    for (name, tenant) in &tenants {
+        for (class, class_usage) in &tenant.classes {
+            let _ = writeln!(
+                out,
+                "madara_rpc_usage_compute_units_total{{tenant=\"{}\",class=\"{}\"}} {}",
+                label(name),
+                class.name(),
+                class_usage.compute_units
+            );
+        }
+    }
+    out.push_str("# TYPE madara_rpc_usage_bytes_total counter\n");
+    for (name, tenant) in &tenants {
+        let _ = writeln!(
+            out,
+            "madara_rpc_usage_bytes_total{{tenant=\"{}\",direction=\"in\"}} {}",
+            label(name),
+            tenant.bytes_in
+        );
+        let _ = writeln!(
+            out,
+            "madara_rpc_usage_bytes_total{{tenant=\"{}\",direction=\"out\"}} {}",
+            label(name),
+            tenant.bytes_out
+        );
+    }
+    out
+}
+
+#[derive(Clone)]
+pub struct RpcMiddlewareServiceUsage<S> {
+    inner: S,
+    usage: Option<Arc<UsageAccounting>>,
+    tenant: Option<Arc<str>>,
+}
+
+impl<S> RpcMiddlewareServiceUsage<S> {
+    pub fn new(inner: S, usage: Option<Arc<UsageAccounting>>, tenant: Option<Arc<str>>) -> Self {
+        Self { inner, usage, tenant }
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcMiddlewareServiceUsage<S>
+where
+    S: Send + Sync + Clone + RpcServiceT<'a> + 'static,
+{
+    type Future = BoxFuture<'a, jsonrpsee::MethodResponse>;
+
+    fn call(&self, req: jsonrpsee::types::Request<'a>) -> Self::Future {
+        let inner = self.inner.clone();
+        let usage = self.usage.clone();
+        let tenant = self.tenant.clone();
+
+        async move {
+            let (Some(usage), Some(tenant)) = (usage, tenant) else {
+                return inner.call(req).await;
+            };
+
+            let method_name = req.method_name().to_string();
+            let bytes_in = req.params().as_str().map_or(0, str::len) as u64;
+            let rp = inner.call(req).await;
+            usage.record(&tenant, &method_name, bytes_in, rp.as_result().len() as u64);
+            rp
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounting(max_tenants: usize) -> UsageAccounting {
+        UsageAccounting::new(UsageConfig {
+            api_key_header: "x-api-key".into(),
+            api_keys: parse_api_keys("# Tenants\nalice key-a\n\nbob   key-b\n").unwrap(),
+            max_tenants,
+            path: PathBuf::new(),
+            format: UsageExportFormat::JsonLines,
+            interval: Duration::from_secs(60),
+        })
+    }
+
+    #[test]
+    fn test_parse_api_keys() {
+        let api_keys = parse_api_keys("# Tenants\nalice key-a\n\nbob   key-b\n").unwrap();
+        assert_eq!(api_keys.len(), 2);
+        assert_eq!(api_keys["key-a"].as_ref(), "alice");
+        assert_eq!(api_keys["key-b"].as_ref(), "bob");
+
+        assert!(parse_api_keys("alice").is_err());
+        assert!(parse_api_keys("alice key a").is_err());
+        assert!(parse_api_keys("alice key-a\nbob key-a").is_err());
+        assert!(parse_api_keys("other key-a").is_err());
+    }
+
+    #[test]
+    fn test_tenant() {
+        let usage = accounting(10);
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        assert_eq!(usage.tenant(Some("key-a"), ip).as_ref(), "alice");
+        // Unknown keys cannot be used to charge the usage to another tenant.
+        assert_eq!(usage.tenant(Some("alice"), ip).as_ref(), "10.0.0.1");
+        assert_eq!(usage.tenant(None, ip).as_ref(), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_record_bounded() {
+        let usage = accounting(2);
+        usage.record("alice", "starknet_V0_7_1_getEvents", 10, 100);
+        usage.record("10.0.0.1", "starknet_V0_7_1_call", 10, 100);
+        usage.record("10.0.0.2", "starknet_V0_7_1_call", 10, 100);
+        usage.record("10.0.0.3", "starknet_V0_7_1_traceTransaction", 10, 100);
+        // Tenants already accounted keep being accounted separately.
+        usage.record("alice", "starknet_V0_7_1_getNonce", 10, 100);
+
+        let total = usage.total_usage.lock().unwrap();
+        let mut tenants: Vec<_> = total.keys().map(String::as_str).collect();
+        tenants.sort();
+        assert_eq!(tenants, ["10.0.0.1", "alice", OTHER]);
+
+        let alice = &total["alice"];
+        assert_eq!(alice.classes[&MethodClass::Events].compute_units, 10);
+        assert_eq!(alice.classes[&MethodClass::Read].requests, 1);
+        assert_eq!((alice.bytes_in, alice.bytes_out), (20, 200));
+        let other = &total[OTHER];
+        assert_eq!(other.classes[&MethodClass::Execution].requests, 1);
+        assert_eq!(other.classes[&MethodClass::Trace].compute_units, 50);
+    }
+
+    #[test]
+    fn test_prometheus_text() {
+        let usage = accounting(10);
+        usage.record("al\"ice", "starknet_V0_7_1_getEvents", 1, 2);
+
+        let text = prometheus_text(&usage.total_usage.lock().unwrap());
+        assert!(text.contains("madara_rpc_usage_requests_total{tenant=\"al\\\"ice\",class=\"events\"} 1\n"));
+        assert!(text.contains("madara_rpc_usage_compute_units_total{tenant=\"al\\\"ice\",class=\"events\"} 10\n"));
+        assert!(text.contains("madara_rpc_usage_bytes_total{tenant=\"al\\\"ice\",direction=\"in\"} 1\n"));
+        assert!(text.contains("madara_rpc_usage_bytes_total{tenant=\"al\\\"ice\",direction=\"out\"} 2\n"));
+    }
+}
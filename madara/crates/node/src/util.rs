@@ -1,5 +1,11 @@
-use anyhow::Context;
+use anyhow::{bail, Context};
+use mc_block_import::{BlockImporter, BlockValidationContext, StoredBlockVerification};
+use mc_db::MadaraBackend;
+use mp_block::BlockId;
+use starknet_types_core::felt::Felt;
+use std::collections::BTreeMap;
 
+/// Sets up the global rayon thread pool, with one thread per core. This can only be called once per process.
 pub fn setup_rayon_threadpool() -> anyhow::Result<()> {
     let available_parallelism = std::thread::available_parallelism()?;
     rayon::ThreadPoolBuilder::new()
@@ -9,6 +15,7 @@ pub fn setup_rayon_threadpool() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Raises the limit of open file descriptors of the process, which the database needs a lot of.
 pub fn raise_fdlimit() {
     use fdlimit::Outcome;
     let recommended = 10000;
@@ -45,3 +52,77 @@ pub async fn get_random_pokemon_name() -> anyhow::Result<String> {
 
     Ok(random_pokemon["name"].as_str().context("Getting name from pokemon object")?.to_string())
 }
+
+/// Checks that the blocks already in the database match the block hashes pinned by the chain registry.
+pub fn check_checkpoints(backend: &MadaraBackend, checkpoints: &BTreeMap<u64, Felt>) -> anyhow::Result<()> {
+    for (block_n, expected) in checkpoints {
+        let Some(block_hash) = backend.get_block_hash(&BlockId::Number(*block_n))? else {
+            break;
+        };
+        if block_hash != *expected {
+            bail!(
+                "Block #{block_n} in the database has hash {block_hash:#x}, but the chain registry pins {expected:#x}. \
+                 The database belongs to a different chain."
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Number of blocks verified by `--verify-chain` between two progress logs.
+const VERIFY_CHAIN_LOG_INTERVAL: u64 = 10_000;
+
+/// Verifies the blocks in the database from `from_block_n` onwards, see `--verify-chain`. The blocks which do not
+/// verify are logged, and an error is returned if there is any.
+pub async fn verify_chain(
+    backend: &MadaraBackend,
+    importer: &BlockImporter,
+    from_block_n: u64,
+    validation: BlockValidationContext,
+) -> anyhow::Result<()> {
+    let Some(latest_block_n) = backend.get_latest_block_n().context("Getting latest block number")? else {
+        tracing::info!("🔎 The database has no blocks to verify");
+        return Ok(());
+    };
+    // The blocks before a state snapshot are not stored until they are backfilled.
+    let oldest_block_n = backend.get_oldest_backfilled_block_n().context("Getting oldest backfilled block")?;
+    let from_block_n = from_block_n.max(oldest_block_n.unwrap_or(0));
+    tracing::info!("🔎 Verifying blocks #{from_block_n} to #{latest_block_n}");
+    if let Some(trie_block_n) = backend.get_global_trie_block_n().context("Getting global trie block number")? {
+        tracing::info!(
+            "🔎 The global state root is only checked at block #{trie_block_n}, the head of the global tries"
+        );
+    }
+
+    let mut failed = 0u64;
+    let range = from_block_n..=latest_block_n;
+    for (block_n, block) in range.clone().zip(backend.iter_blocks(range)) {
+        // The blocks iterator skips the missing blocks.
+        let block = block.with_context(|| format!("Getting block #{block_n}"))?;
+        if block.info.header.block_number != block_n {
+            bail!("Block #{block_n} is missing from the database");
+        }
+        match importer.verify_stored_block(block, validation.clone()).await {
+            Ok(StoredBlockVerification::Verified) => {}
+            Ok(StoredBlockVerification::StateSnapshot) => {
+                tracing::info!(
+                    "🔎 Block #{block_n} was imported from a state snapshot, only its parent hash was checked"
+                )
+            }
+            Err(err) if err.is_internal() => return Err(err).with_context(|| format!("Verifying block #{block_n}")),
+            Err(err) => {
+                tracing::error!("❌ Block #{block_n} does not verify: {err:#}");
+                failed += 1;
+            }
+        }
+        if block_n % VERIFY_CHAIN_LOG_INTERVAL == 0 {
+            tracing::info!("🔎 Verified blocks up to #{block_n}");
+        }
+    }
+
+    if failed > 0 {
+        bail!("{failed} blocks between #{from_block_n} and #{latest_block_n} do not verify");
+    }
+    tracing::info!("✅ Blocks #{from_block_n} to #{latest_block_n} verify");
+    Ok(())
+}
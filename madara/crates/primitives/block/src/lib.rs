@@ -294,6 +294,14 @@ pub struct VisitedSegmentEntry {
     pub segments: Vec<usize>,
 }
 
+/// Signature of a block hash by the sequencer which produced the block. This is what the feeder gateway serves
+/// through its `get_signature` endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ConsensusSignature {
+    pub r: Felt,
+    pub s: Felt,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,6 +47,15 @@ pub mod public_key {
     pub const SEPOLIA_INTEGRATION: &str = "0x4e4856eb36dbd5f4a7dca29f7bb5232974ef1fb7eb5b597c58077174c294da1";
 }
 
+/// Address of the Universal Deployer Contract on the public Starknet networks, and the default of
+/// [`ChainConfig::udc_address`].
+pub const UDC_CONTRACT_ADDRESS: Felt =
+    Felt::from_hex_unchecked("0x041a78e741e5af2fec34b695679bc6891742439f7afb8484ecd7766661ad02bf");
+
+fn default_udc_address() -> Felt {
+    UDC_CONTRACT_ADDRESS
+}
+
 const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0: &[u8] = include_bytes!("../resources/versioned_constants_13_0.json");
 const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1: &[u8] = include_bytes!("../resources/versioned_constants_13_1.json");
 const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1_1: &[u8] =
@@ -131,6 +140,11 @@ pub struct ChainConfig {
     #[serde(deserialize_with = "deserialize_private_key")]
     pub private_key: ZeroingPrivateKey,
 
+    /// Public keys of the sequencer signing the blocks of this chain. Synced blocks carrying a signature are rejected
+    /// when it was not made by one of these keys. No check is done when this is empty.
+    #[serde(default)]
+    pub sequencer_public_keys: Vec<Felt>,
+
     /// Transaction limit in the mempool.
     pub mempool_tx_limit: usize,
     /// Transaction limit in the mempool, we have an additional limit for declare transactions.
@@ -138,13 +152,31 @@ pub struct ChainConfig {
     /// Max age of a transaction in the mempool.
     #[serde(deserialize_with = "deserialize_optional_duration")]
     pub mempool_tx_max_age: Option<Duration>,
+
+    /// Invoke transaction versions accepted on top of the Starknet ones, for appchains experimenting with new
+    /// transaction types. Transactions with one of these versions have the layout of invoke v3 transactions, and are
+    /// turned into Starknet transactions by the custom transaction handler of the mempool. No custom version is
+    /// accepted when this is empty.
+    #[serde(default)]
+    pub custom_transaction_versions: Vec<Felt>,
+
+    /// Address of the Universal Deployer Contract of the chain, which the addresses of the contracts deployed through
+    /// it with `unique` set depend on. This is the address of the public Starknet networks unless an appchain deploys
+    /// its own UDC elsewhere.
+    #[serde(default = "default_udc_address")]
+    pub udc_address: Felt,
 }
 
 impl ChainConfig {
     pub fn from_yaml(path: &Path) -> anyhow::Result<Self> {
         let config_str = fs::read_to_string(path)?;
+        Self::from_yaml_str(&config_str)
+    }
+
+    /// Parses a chain config from the content of a chain config file.
+    pub fn from_yaml_str(config_str: &str) -> anyhow::Result<Self> {
         let config_value: serde_yaml::Value =
-            serde_yaml::from_str(&config_str).context("While deserializing chain config")?;
+            serde_yaml::from_str(config_str).context("While deserializing chain config")?;
 
         let versioned_constants_file_paths: BTreeMap<String, String> =
             serde_yaml::from_value(config_value.get("versioned_constants_path").cloned().unwrap_or_default())
@@ -157,8 +189,8 @@ impl ChainConfig {
             versioned_constants
         };
 
-        let chain_config: ChainConfig =
-            serde_yaml::from_str(&config_str).context("While deserializing chain config")?;
+        let chain_config: ChainConfig = serde_yaml::from_str(config_str).context("While deserializing chain config")?;
+        chain_config.check_custom_transaction_versions()?;
 
         Ok(ChainConfig { versioned_constants, ..chain_config })
     }
@@ -178,6 +210,17 @@ impl ChainConfig {
         Ok(())
     }
 
+    /// Custom transaction versions cannot shadow the version of a Starknet transaction, nor the query version of one.
+    pub fn check_custom_transaction_versions(&self) -> anyhow::Result<()> {
+        let query_version_offset = Felt::TWO.pow(128u128);
+        for version in &self.custom_transaction_versions {
+            if *version <= Felt::THREE || *version >= query_version_offset {
+                bail!("Custom transaction version {version:#x} collides with the Starknet transaction versions.")
+            }
+        }
+        Ok(())
+    }
+
     pub fn starknet_mainnet() -> Self {
         // Sources:
         // - https://docs.starknet.io/tools/important-addresses
@@ -244,10 +287,15 @@ impl ChainConfig {
             ),
 
             private_key: ZeroingPrivateKey::default(),
+            sequencer_public_keys: vec![Felt::from_hex_unchecked(public_key::MAINNET)],
 
             mempool_tx_limit: 10_000,
             mempool_declare_tx_limit: 20,
             mempool_tx_max_age: Some(Duration::from_secs(60 * 60)), // an hour?
+
+            custom_transaction_versions: vec![],
+
+            udc_address: UDC_CONTRACT_ADDRESS,
         }
     }
 
@@ -261,6 +309,7 @@ impl ChainConfig {
             eth_gps_statement_verifier: eth_gps_statement_verifier::SEPOLIA_TESTNET
                 .parse()
                 .expect("parsing a constant"),
+            sequencer_public_keys: vec![Felt::from_hex_unchecked(public_key::SEPOLIA_TESTNET)],
             ..Self::starknet_mainnet()
         }
     }
@@ -277,6 +326,7 @@ impl ChainConfig {
             eth_gps_statement_verifier: eth_gps_statement_verifier::SEPOLIA_INTEGRATION
                 .parse()
                 .expect("parsing a constant"),
+            sequencer_public_keys: vec![Felt::from_hex_unchecked(public_key::SEPOLIA_INTEGRATION)],
             ..Self::starknet_mainnet()
         }
     }
@@ -288,6 +338,7 @@ impl ChainConfig {
             feeder_gateway_url: Url::parse("http://localhost:8080/feeder_gateway/").unwrap(),
             gateway_url: Url::parse("http://localhost:8080/gateway/").unwrap(),
             sequencer_address: Felt::from_hex_unchecked("0x123").try_into().unwrap(),
+            sequencer_public_keys: vec![],
             ..ChainConfig::starknet_sepolia()
         }
     }
@@ -304,6 +355,7 @@ impl ChainConfig {
             )
             .try_into()
             .unwrap(),
+            sequencer_public_keys: vec![],
             ..ChainConfig::starknet_sepolia()
         }
     }
@@ -555,6 +607,7 @@ mod tests {
             chain_config.eth_core_contract_address,
             H160::from_str("0xc662c410C0ECf747543f5bA90660f6ABeBD9C8c4").unwrap()
         );
+        assert_eq!(chain_config.sequencer_public_keys, vec![Felt::from_hex_unchecked(public_key::MAINNET)]);
     }
 
     #[rstest]
@@ -620,4 +673,13 @@ mod tests {
         );
         assert!(chain_config.exec_constants_by_protocol_version(StarknetVersion::new(0, 0, 0, 0)).is_err(),);
     }
+
+    #[rstest]
+    #[case(Felt::from(0x100), true)]
+    #[case(Felt::THREE, false)]
+    #[case(Felt::TWO.pow(128u128) + Felt::from(0x100), false)]
+    fn test_custom_transaction_versions(#[case] version: Felt, #[case] valid: bool) {
+        let chain_config = ChainConfig { custom_transaction_versions: vec![version], ..ChainConfig::madara_test() };
+        assert_eq!(chain_config.check_custom_transaction_versions().is_ok(), valid);
+    }
 }
@@ -1,4 +1,5 @@
 mod chain_config;
+pub mod registry;
 mod rpc_version;
 mod starknet_version;
 
@@ -0,0 +1,160 @@
+//! Signed chain registries.
+//!
+//! An appchain operator publishes a registry file alongside the launch of a chain: it holds the chain config, the
+//! genesis block hash and a list of checkpoint block hashes, and is signed with the operator key. A node started from a
+//! registry file only accepts the chain config and the blocks it pins, which prevents it from being pointed at a
+//! forked or tampered genesis.
+//!
+//! The registry file is JSON:
+//!
+//! ```json
+//! {
+//!   "chain_config": "<content of the chain config YAML file>",
+//!   "genesis_block_hash": "0x...",
+//!   "checkpoints": [{ "block_number": 1000, "block_hash": "0x..." }],
+//!   "signature": { "r": "0x...", "s": "0x..." }
+//! }
+//! ```
+//!
+//! The signature is a Stark ECDSA signature of [`ChainRegistry::message_hash`].
+
+use crate::ChainConfig;
+use anyhow::{bail, Context};
+use mp_utils::crypto::ZeroingPrivateKey;
+use serde::{Deserialize, Serialize};
+use starknet_core::crypto::{ecdsa_verify, Signature};
+use starknet_core::utils::starknet_keccak;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Domain separator of the registry message hash, `'MADARA_CHAIN_REGISTRY'` as a short string.
+const REGISTRY_PREFIX: Felt = Felt::from_hex_unchecked("0x4d41444152415f434841494e5f5245474953545259");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub block_number: u64,
+    pub block_hash: Felt,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistrySignature {
+    pub r: Felt,
+    pub s: Felt,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainRegistry {
+    /// Content of the chain config file, in the YAML format of the presets.
+    pub chain_config: String,
+    pub genesis_block_hash: Felt,
+    #[serde(default)]
+    pub checkpoints: Vec<Checkpoint>,
+    #[serde(default)]
+    pub signature: RegistrySignature,
+}
+
+impl ChainRegistry {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading the chain registry file at {}", path.display()))?;
+        serde_json::from_str(&content).context("Deserializing the chain registry file")
+    }
+
+    /// The hash signed by the operator: the poseidon hash of the registry prefix, the keccak of the chain config, the
+    /// genesis block hash, the number of checkpoints and each of the checkpoints.
+    pub fn message_hash(&self) -> Felt {
+        let mut data = vec![
+            REGISTRY_PREFIX,
+            starknet_keccak(self.chain_config.as_bytes()),
+            self.genesis_block_hash,
+            Felt::from(self.checkpoints.len()),
+        ];
+        for checkpoint in &self.checkpoints {
+            data.extend([Felt::from(checkpoint.block_number), checkpoint.block_hash]);
+        }
+        Poseidon::hash_array(&data)
+    }
+
+    pub fn sign(&mut self, private_key: &ZeroingPrivateKey) -> anyhow::Result<()> {
+        let signature = private_key.sign(&self.message_hash()).context("Signing the chain registry")?;
+        self.signature = RegistrySignature { r: signature.r, s: signature.s };
+        Ok(())
+    }
+
+    /// Checks the registry signature against the operator public key.
+    pub fn verify(&self, public_key: &Felt) -> anyhow::Result<()> {
+        let signature = Signature { r: self.signature.r, s: self.signature.s };
+        let valid = ecdsa_verify(public_key, &self.message_hash(), &signature)
+            .context("Verifying the chain registry signature")?;
+        if !valid {
+            bail!("The chain registry is not signed by the operator key {public_key:#x}");
+        }
+        Ok(())
+    }
+
+    pub fn chain_config(&self) -> anyhow::Result<ChainConfig> {
+        ChainConfig::from_yaml_str(&self.chain_config).context("Parsing the chain config of the chain registry")
+    }
+
+    /// The block hashes pinned by the registry, including the genesis block.
+    pub fn checkpoints(&self) -> anyhow::Result<BTreeMap<u64, Felt>> {
+        let mut checkpoints = BTreeMap::from([(0, self.genesis_block_hash)]);
+        for Checkpoint { block_number, block_hash } in &self.checkpoints {
+            if let Some(pinned) = checkpoints.insert(*block_number, *block_hash) {
+                if pinned != *block_hash {
+                    bail!("The chain registry pins two different hashes for block #{block_number}");
+                }
+            }
+        }
+        Ok(checkpoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> ChainRegistry {
+        ChainRegistry {
+            chain_config: "chain_name: Test\n".into(),
+            genesis_block_hash: Felt::from(0x1234),
+            checkpoints: vec![Checkpoint { block_number: 10, block_hash: Felt::from(0x5678) }],
+            signature: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_registry_signature() {
+        let key = ZeroingPrivateKey::default();
+        let mut registry = registry();
+        registry.sign(&key).unwrap();
+        registry.verify(&key.public).unwrap();
+
+        let roundtrip: ChainRegistry = serde_json::from_str(&serde_json::to_string(&registry).unwrap()).unwrap();
+        roundtrip.verify(&key.public).unwrap();
+
+        assert!(registry.verify(&ZeroingPrivateKey::default().public).is_err());
+
+        let mut tampered = registry.clone();
+        tampered.genesis_block_hash = Felt::from(0x4321);
+        assert!(tampered.verify(&key.public).is_err());
+
+        let mut tampered = registry.clone();
+        tampered.chain_config.push_str("chain_id: OTHER\n");
+        assert!(tampered.verify(&key.public).is_err());
+    }
+
+    #[test]
+    fn test_registry_checkpoints() {
+        let mut registry = registry();
+        assert_eq!(
+            registry.checkpoints().unwrap(),
+            BTreeMap::from([(0, Felt::from(0x1234)), (10, Felt::from(0x5678))])
+        );
+
+        registry.checkpoints.push(Checkpoint { block_number: 10, block_hash: Felt::ONE });
+        assert!(registry.checkpoints().is_err());
+    }
+}
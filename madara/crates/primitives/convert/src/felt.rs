@@ -19,6 +19,19 @@ pub fn felt_to_h160(felt: &Felt) -> Result<H160, FeltToH160Error> {
     Ok(H160::from(h160_bytes))
 }
 
+#[derive(Debug, thiserror::Error)]
+#[error("Felt is too big to convert to u64.")]
+pub struct FeltToU64Error;
+
+pub fn felt_to_u64(felt: &Felt) -> Result<u64, FeltToU64Error> {
+    let bytes = felt.to_bytes_be();
+    let (high, low) = bytes.split_at(24);
+    if high.iter().any(|b| *b != 0) {
+        return Err(FeltToU64Error);
+    }
+    Ok(u64::from_be_bytes(low.try_into().expect("8 bytes")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,4 +49,13 @@ mod tests {
         assert_matches!(felt_to_h160(&(Felt::from_bytes_be_slice(&MAX_H160) + Felt::ONE)), Err(FeltToH160Error));
         assert_matches!(felt_to_h160(&Felt::MAX), Err(FeltToH160Error));
     }
+
+    #[test]
+    fn test_felt_to_u64() {
+        assert_eq!(felt_to_u64(&Felt::ZERO).unwrap(), 0);
+        assert_eq!(felt_to_u64(&Felt::THREE).unwrap(), 3);
+        assert_eq!(felt_to_u64(&Felt::from(u64::MAX)).unwrap(), u64::MAX);
+        assert_matches!(felt_to_u64(&(Felt::from(u64::MAX) + Felt::ONE)), Err(FeltToU64Error));
+        assert_matches!(felt_to_u64(&Felt::MAX), Err(FeltToU64Error));
+    }
 }
@@ -2,7 +2,7 @@ mod felt;
 pub mod hex_serde;
 mod to_felt;
 
-pub use felt::felt_to_h160;
+pub use felt::{felt_to_h160, felt_to_u64};
 pub use to_felt::{DisplayFeltAsHex, FeltHexDisplay, ToFelt};
 
 pub mod test {
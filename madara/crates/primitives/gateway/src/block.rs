@@ -3,6 +3,7 @@ use super::{
     transaction::Transaction,
 };
 use anyhow::Context;
+use mc_block_import::commitments::CommitmentScheme;
 use mp_block::header::{BlockTimestamp, L1DataAvailabilityMode};
 use mp_chain_config::StarknetVersion;
 use mp_convert::hex_serde::U128AsHex;
@@ -110,7 +111,7 @@ impl ProviderBlock {
         };
 
         let (receipt_commitment, state_diff_commitment) =
-            if block.info.header.protocol_version >= StarknetVersion::V0_13_2 {
+            if CommitmentScheme::for_version(block.info.header.protocol_version).header_commitments {
                 (block.info.header.receipt_commitment, block.info.header.state_diff_commitment)
             } else {
                 (None, None)
@@ -248,6 +249,13 @@ impl ProviderBlockPending {
     }
 }
 
+/// A `get_block` response with `headerOnly=true`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderBlockHeader {
+    pub block_hash: Felt,
+    pub block_number: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 // #[serde(deny_unknown_fields)] // TODO(v0.13.4): Re-add this attribute when v0.13.4 is supported.
 #[cfg_attr(test, derive(Eq))]
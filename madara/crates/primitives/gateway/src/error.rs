@@ -73,6 +73,13 @@ impl StarknetError {
         Self { code: StarknetErrorCode::MalformedRequest, message: format!("Invalid class_hash: {}", e) }
     }
 
+    pub fn too_many_class_hashes(max: usize) -> Self {
+        Self {
+            code: StarknetErrorCode::MalformedRequest,
+            message: format!("Too many class hashes, at most {max} can be requested at once"),
+        }
+    }
+
     pub fn class_not_found(class_hash: Felt) -> Self {
         Self {
             code: StarknetErrorCode::UndeclaredClass,
@@ -4,3 +4,7 @@ pub mod receipt;
 pub mod state_update;
 pub mod transaction;
 pub mod user_transaction;
+
+/// Maximum number of classes served by a single `get_classes_by_hash` request. This is a Madara extension of the
+/// feeder gateway, the client splits larger requests accordingly.
+pub const MAX_CLASSES_PER_REQUEST: usize = 100;
@@ -90,6 +90,16 @@ impl Transaction {
             Transaction::DeployAccount(tx) => tx.version(),
         }
     }
+
+    pub fn transaction_hash(&self) -> &Felt {
+        match self {
+            Transaction::Invoke(tx) => tx.transaction_hash(),
+            Transaction::L1Handler(tx) => &tx.transaction_hash,
+            Transaction::Declare(tx) => tx.transaction_hash(),
+            Transaction::Deploy(tx) => &tx.transaction_hash,
+            Transaction::DeployAccount(tx) => tx.transaction_hash(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -112,6 +122,14 @@ impl InvokeTransaction {
             InvokeTransaction::V3(_) => 3,
         }
     }
+
+    pub fn transaction_hash(&self) -> &Felt {
+        match self {
+            InvokeTransaction::V0(tx) => &tx.transaction_hash,
+            InvokeTransaction::V1(tx) => &tx.transaction_hash,
+            InvokeTransaction::V3(tx) => &tx.transaction_hash,
+        }
+    }
 }
 
 impl From<InvokeTransaction> for mp_transactions::InvokeTransaction {
@@ -316,6 +334,15 @@ impl DeclareTransaction {
             DeclareTransaction::V3(_) => 3,
         }
     }
+
+    pub fn transaction_hash(&self) -> &Felt {
+        match self {
+            DeclareTransaction::V0(tx) => &tx.transaction_hash,
+            DeclareTransaction::V1(tx) => &tx.transaction_hash,
+            DeclareTransaction::V2(tx) => &tx.transaction_hash,
+            DeclareTransaction::V3(tx) => &tx.transaction_hash,
+        }
+    }
 }
 
 impl From<DeclareTransaction> for mp_transactions::DeclareTransaction {
@@ -556,6 +583,13 @@ impl DeployAccountTransaction {
             DeployAccountTransaction::V3(_) => 3,
         }
     }
+
+    pub fn transaction_hash(&self) -> &Felt {
+        match self {
+            DeployAccountTransaction::V1(tx) => &tx.transaction_hash,
+            DeployAccountTransaction::V3(tx) => &tx.transaction_hash,
+        }
+    }
 }
 
 impl From<DeployAccountTransaction> for mp_transactions::DeployAccountTransaction {
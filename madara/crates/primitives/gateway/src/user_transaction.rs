@@ -28,9 +28,11 @@
 //! The module defines [`UserTransactionConversionError`] for handling conversion failures:
 //!
 //! - [`UnsupportedQueryTransaction`]: When attempting to convert a query-only transaction
+//! - [`UnsupportedCustomTransaction`]: When attempting to convert a transaction with a custom version
 //! - [`ContractClassDecodeError`]: When contract class decoding fails
 //!
 //! [`UnsupportedQueryTransaction`]: UserTransactionConversionError::UnsupportedQueryTransaction
+//! [`UnsupportedCustomTransaction`]: UserTransactionConversionError::UnsupportedCustomTransaction
 //! [`ContractClassDecodeError`]: UserTransactionConversionError::ContractClassDecodeError
 
 use mp_class::{CompressedLegacyContractClass, CompressedSierraClass, FlattenedSierraClass};
@@ -90,6 +92,8 @@ pub enum AddTransactionCode {
 pub enum UserTransactionConversionError {
     #[error("User transaction can't be a query only transaction")]
     UnsupportedQueryTransaction,
+    #[error("User transaction can't have the custom version {0:#x}")]
+    UnsupportedCustomTransaction(Felt),
     #[error("Error while decoding the contract class: {0}")]
     ContractClassDecodeError(#[from] std::io::Error),
 }
@@ -337,6 +341,9 @@ impl TryFrom<BroadcastedInvokeTxn> for UserInvokeFunctionTransaction {
             BroadcastedInvokeTxn::QueryV0(_) | BroadcastedInvokeTxn::QueryV1(_) | BroadcastedInvokeTxn::QueryV3(_) => {
                 Err(UserTransactionConversionError::UnsupportedQueryTransaction)
             }
+            BroadcastedInvokeTxn::Custom(tx) => {
+                Err(UserTransactionConversionError::UnsupportedCustomTransaction(tx.version))
+            }
         }
     }
 }
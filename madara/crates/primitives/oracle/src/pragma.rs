@@ -2,6 +2,7 @@ use std::fmt;
 
 use anyhow::{bail, Context};
 use async_trait::async_trait;
+use mp_utils::http::HttpClientConfig;
 use mp_utils::serde::{deserialize_url, serialize_url};
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
@@ -26,6 +27,9 @@ pub struct PragmaOracle {
     pub interval: Interval,
     #[serde(default)]
     pub price_bounds: PriceBounds,
+    /// Set from the http settings of the node rather than from the oracle configuration.
+    #[serde(skip)]
+    pub http: HttpClientConfig,
 }
 
 impl Default for PragmaOracle {
@@ -36,6 +40,7 @@ impl Default for PragmaOracle {
             aggregation_method: AggregationMethod::Median,
             interval: Interval::OneMinute,
             price_bounds: Default::default(),
+            http: HttpClientConfig::default(),
         }
     }
 }
@@ -58,7 +63,10 @@ impl Oracle for PragmaOracle {
     /// Ok((u128, u32)) : return the price tuple as (price, decimals)
     /// Err(e) : return an error if anything went wrong in the fetching process or eth/strk price is 0
     async fn fetch_eth_strk_price(&self) -> anyhow::Result<(u128, u32)> {
-        let response = reqwest::Client::new()
+        let response = self
+            .http
+            .reqwest_client()
+            .context("failed to create the http client")?
             .get(self.get_fetch_url(String::from("eth"), String::from("strk")))
             .header("x-api-key", self.api_key.clone())
             .send()
@@ -154,11 +162,16 @@ struct PragmaApiResponse {
 pub struct PragmaOracleBuilder {
     api_url: Url,
     api_key: String,
+    http: HttpClientConfig,
 }
 
 impl Default for PragmaOracleBuilder {
     fn default() -> Self {
-        Self { api_url: Url::parse("about:blank").expect("valid URL"), api_key: String::default() }
+        Self {
+            api_url: Url::parse("about:blank").expect("valid URL"),
+            api_key: String::default(),
+            http: HttpClientConfig::default(),
+        }
     }
 }
 
@@ -177,6 +190,11 @@ impl PragmaOracleBuilder {
         self
     }
 
+    pub fn with_http_config(mut self, http: HttpClientConfig) -> Self {
+        self.http = http;
+        self
+    }
+
     pub fn build(self) -> PragmaOracle {
         PragmaOracle {
             api_url: self.api_url,
@@ -184,6 +202,7 @@ impl PragmaOracleBuilder {
             aggregation_method: AggregationMethod::default(),
             interval: Interval::default(),
             price_bounds: PriceBounds::default(),
+            http: self.http,
         }
     }
 }
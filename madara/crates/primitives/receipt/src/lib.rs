@@ -140,6 +140,16 @@ impl TransactionReceipt {
         }
     }
 
+    pub fn execution_resources_mut(&mut self) -> &mut ExecutionResources {
+        match self {
+            TransactionReceipt::Invoke(receipt) => &mut receipt.execution_resources,
+            TransactionReceipt::L1Handler(receipt) => &mut receipt.execution_resources,
+            TransactionReceipt::Declare(receipt) => &mut receipt.execution_resources,
+            TransactionReceipt::Deploy(receipt) => &mut receipt.execution_resources,
+            TransactionReceipt::DeployAccount(receipt) => &mut receipt.execution_resources,
+        }
+    }
+
     pub fn contract_address(&self) -> Option<Felt> {
         match self {
             TransactionReceipt::Deploy(receipt) => Some(receipt.contract_address),
@@ -26,6 +26,14 @@ impl TransactionReceipt {
             }
         }
     }
+
+    /// The receipt of the v0.8 API, whose execution resources are the gas consumed by the transaction. The execution of
+    /// the transactions is charged in L1 gas, so the receipts never record any L2 gas.
+    pub fn to_starknet_types_v0_8_0(self, finality_status: mp_rpc::TxnFinalityStatus) -> mp_rpc::v0_8_0::TxnReceipt {
+        let L1Gas { l1_gas, l1_data_gas } = self.total_gas_consumed().clone();
+        let execution_resources = mp_rpc::v0_8_0::ExecutionResources { l1_gas, l1_data_gas, l2_gas: 0 };
+        mp_rpc::v0_8_0::TxnReceipt::from_v0_7_1(self.to_starknet_types(finality_status), execution_resources)
+    }
 }
 
 impl InvokeTransactionReceipt {
@@ -207,7 +215,10 @@ impl From<ExecutionResult> for mp_rpc::ExecutionStatus {
 mod test {
     use primitive_types::H256;
 
-    use crate::{to_starknet_types::hash_as_string, L1HandlerTransactionReceipt};
+    use crate::{
+        to_starknet_types::hash_as_string, ExecutionResources, InvokeTransactionReceipt, L1Gas,
+        L1HandlerTransactionReceipt, TransactionReceipt,
+    };
 
     #[test]
     fn test_hash_as_string() {
@@ -232,4 +243,27 @@ mod test {
         assert_eq!(message_hash, hash);
         assert!(!message_hash.contains("."));
     }
+
+    #[test]
+    fn test_to_starknet_types_v0_8_0_execution_resources() {
+        let receipt = TransactionReceipt::Invoke(InvokeTransactionReceipt {
+            execution_resources: ExecutionResources {
+                steps: 100,
+                data_availability: L1Gas { l1_gas: 1, l1_data_gas: 2 },
+                total_gas_consumed: L1Gas { l1_gas: 30, l1_data_gas: 2 },
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mp_rpc::v0_8_0::TxnReceipt::Invoke(receipt) =
+            receipt.to_starknet_types_v0_8_0(mp_rpc::TxnFinalityStatus::L2)
+        else {
+            unreachable!("The receipt of an invoke transaction")
+        };
+        assert_eq!(
+            receipt.common_receipt_properties.execution_resources,
+            mp_rpc::v0_8_0::ExecutionResources { l1_gas: 30, l1_data_gas: 2, l2_gas: 0 }
+        );
+    }
 }
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 use crate::{BlockHash, BlockNumber, BlockTag};
 
@@ -13,6 +15,59 @@ pub enum BlockId {
     Number(BlockNumber),
 }
 
+impl From<BlockTag> for BlockId {
+    fn from(tag: BlockTag) -> Self {
+        Self::Tag(tag)
+    }
+}
+
+impl From<BlockNumber> for BlockId {
+    fn from(block_number: BlockNumber) -> Self {
+        Self::Number(block_number)
+    }
+}
+
+impl BlockTag {
+    /// The name of the tag, as used by the JSON-RPC and the feeder gateway.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockTag::Latest => "latest",
+            BlockTag::Pending => "pending",
+            BlockTag::L1Accepted => "l1_accepted",
+        }
+    }
+}
+
+impl fmt::Display for BlockTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownBlockTag(pub String);
+
+impl fmt::Display for UnknownBlockTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown block tag `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownBlockTag {}
+
+impl FromStr for BlockTag {
+    type Err = UnknownBlockTag;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "latest" => Ok(BlockTag::Latest),
+            "pending" => Ok(BlockTag::Pending),
+            "l1_accepted" => Ok(BlockTag::L1Accepted),
+            _ => Err(UnknownBlockTag(s.to_string())),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct BlockHashHelper {
     block_hash: BlockHash,
@@ -94,6 +149,23 @@ fn block_id_from_pending() {
     assert_eq!(block_id, BlockId::Tag(BlockTag::Pending));
 }
 
+#[test]
+fn block_id_from_l1_accepted() {
+    let s = "\"l1_accepted\"";
+    let block_id: BlockId = serde_json::from_str(s).unwrap();
+    assert_eq!(block_id, BlockId::Tag(BlockTag::L1Accepted));
+    assert_eq!(serde_json::to_string(&block_id).unwrap(), s);
+}
+
+#[test]
+fn block_tag_from_str() {
+    for tag in [BlockTag::Latest, BlockTag::Pending, BlockTag::L1Accepted] {
+        assert_eq!(tag.as_str().parse::<BlockTag>(), Ok(tag.clone()));
+        assert_eq!(serde_json::to_string(&tag).unwrap(), format!("\"{tag}\""));
+    }
+    assert_eq!("accepted".parse::<BlockTag>(), Err(UnknownBlockTag("accepted".to_string())));
+}
+
 #[cfg(test)]
 #[test]
 fn block_id_to_hash() {
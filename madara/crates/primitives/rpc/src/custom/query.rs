@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
 
 use crate::{
     BroadcastedDeclareTxnV1, BroadcastedDeclareTxnV2, BroadcastedDeclareTxnV3, DeployAccountTxnV1, DeployAccountTxnV3,
@@ -81,6 +82,19 @@ pub enum BroadcastedInvokeTxn {
     /// Query-only broadcasted invoke transaction.
     #[serde(rename = "0x100000000000000000000000000000003")]
     QueryV3(InvokeTxnV3),
+
+    /// Invoke transaction with any other version, see [`CustomInvokeTxn`].
+    #[serde(untagged)]
+    Custom(CustomInvokeTxn),
+}
+
+/// An invoke transaction with a version which is not a Starknet one. It has the layout of an invoke v3 transaction,
+/// and is only accepted by the chains enabling its version.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CustomInvokeTxn {
+    pub version: Felt,
+    #[serde(flatten)]
+    pub transaction: InvokeTxnV3,
 }
 
 impl BroadcastedInvokeTxn {
@@ -89,7 +103,39 @@ impl BroadcastedInvokeTxn {
             BroadcastedInvokeTxn::QueryV0(_) | BroadcastedInvokeTxn::QueryV1(_) | BroadcastedInvokeTxn::QueryV3(_) => {
                 true
             }
-            BroadcastedInvokeTxn::V0(_) | BroadcastedInvokeTxn::V1(_) | BroadcastedInvokeTxn::V3(_) => false,
+            BroadcastedInvokeTxn::V0(_)
+            | BroadcastedInvokeTxn::V1(_)
+            | BroadcastedInvokeTxn::V3(_)
+            | BroadcastedInvokeTxn::Custom(_) => false,
         }
     }
 }
+
+#[cfg(test)]
+#[test]
+fn broadcasted_invoke_custom_version() {
+    let tx = serde_json::json!({
+        "version": "0x100",
+        "sender_address": "0x1",
+        "calldata": [],
+        "signature": [],
+        "nonce": "0x0",
+        "resource_bounds": {
+            "l1_gas": { "max_amount": "0x0", "max_price_per_unit": "0x0" },
+            "l2_gas": { "max_amount": "0x0", "max_price_per_unit": "0x0" }
+        },
+        "tip": "0x0",
+        "paymaster_data": [],
+        "account_deployment_data": [],
+        "nonce_data_availability_mode": "L1",
+        "fee_data_availability_mode": "L1"
+    });
+    let custom: BroadcastedInvokeTxn = serde_json::from_value(tx.clone()).unwrap();
+    assert!(matches!(&custom, BroadcastedInvokeTxn::Custom(custom) if custom.version == Felt::from(0x100)));
+    assert_eq!(serde_json::to_value(&custom).unwrap(), tx);
+
+    // Starknet versions are not custom ones.
+    let mut tx = tx;
+    tx["version"] = "0x3".into();
+    assert!(matches!(serde_json::from_value(tx).unwrap(), BroadcastedInvokeTxn::V3(_)));
+}
@@ -4,7 +4,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::SyncStatus;
 
 /// The syncing status of a node.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SyncingStatus {
     /// The node is not syncing.
     NotSyncing,
@@ -2,5 +2,6 @@ mod custom;
 mod custom_serde;
 
 pub mod v0_7_1;
+pub mod v0_8_0;
 
 pub use self::v0_7_1::*;
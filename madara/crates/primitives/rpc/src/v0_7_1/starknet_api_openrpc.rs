@@ -67,6 +67,9 @@ pub enum BlockTag {
     Latest,
     #[serde(rename = "pending")]
     Pending,
+    /// The latest block confirmed on L1. This tag is a Madara extension of the v0.7.1 specification.
+    #[serde(rename = "l1_accepted")]
+    L1Accepted,
 }
 
 /// The block object
@@ -0,0 +1,142 @@
+//! Conversions of the v0.7.1 types whose shape changed in v0.8.0.
+//!
+//! The transactions of v0.7.1 have no bound on the L1 data gas, which is converted to a zero bound. The execution
+//! resources of the receipts are not part of the v0.7.1 receipts and have to be provided, see
+//! [`TxnReceipt::from_v0_7_1`].
+
+use super::*;
+use crate::v0_7_1;
+
+impl From<v0_7_1::ResourceBoundsMapping> for ResourceBoundsMapping {
+    fn from(bounds: v0_7_1::ResourceBoundsMapping) -> Self {
+        Self {
+            l1_gas: bounds.l1_gas,
+            l1_data_gas: v0_7_1::ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+            l2_gas: bounds.l2_gas,
+        }
+    }
+}
+
+impl From<v0_7_1::InvokeTxnV3> for InvokeTxnV3 {
+    fn from(tx: v0_7_1::InvokeTxnV3) -> Self {
+        Self {
+            account_deployment_data: tx.account_deployment_data,
+            calldata: tx.calldata,
+            fee_data_availability_mode: tx.fee_data_availability_mode,
+            nonce: tx.nonce,
+            nonce_data_availability_mode: tx.nonce_data_availability_mode,
+            paymaster_data: tx.paymaster_data,
+            resource_bounds: tx.resource_bounds.into(),
+            sender_address: tx.sender_address,
+            signature: tx.signature,
+            tip: tx.tip,
+        }
+    }
+}
+
+impl From<v0_7_1::DeclareTxnV3> for DeclareTxnV3 {
+    fn from(tx: v0_7_1::DeclareTxnV3) -> Self {
+        Self {
+            account_deployment_data: tx.account_deployment_data,
+            class_hash: tx.class_hash,
+            compiled_class_hash: tx.compiled_class_hash,
+            fee_data_availability_mode: tx.fee_data_availability_mode,
+            nonce: tx.nonce,
+            nonce_data_availability_mode: tx.nonce_data_availability_mode,
+            paymaster_data: tx.paymaster_data,
+            resource_bounds: tx.resource_bounds.into(),
+            sender_address: tx.sender_address,
+            signature: tx.signature,
+            tip: tx.tip,
+        }
+    }
+}
+
+impl From<v0_7_1::DeployAccountTxnV3> for DeployAccountTxnV3 {
+    fn from(tx: v0_7_1::DeployAccountTxnV3) -> Self {
+        Self {
+            class_hash: tx.class_hash,
+            constructor_calldata: tx.constructor_calldata,
+            contract_address_salt: tx.contract_address_salt,
+            fee_data_availability_mode: tx.fee_data_availability_mode,
+            nonce: tx.nonce,
+            nonce_data_availability_mode: tx.nonce_data_availability_mode,
+            paymaster_data: tx.paymaster_data,
+            resource_bounds: tx.resource_bounds.into(),
+            signature: tx.signature,
+            tip: tx.tip,
+        }
+    }
+}
+
+impl From<v0_7_1::Txn> for Txn {
+    fn from(tx: v0_7_1::Txn) -> Self {
+        match tx {
+            v0_7_1::Txn::Invoke(tx) => Self::Invoke(match tx {
+                v0_7_1::InvokeTxn::V0(tx) => InvokeTxn::V0(tx),
+                v0_7_1::InvokeTxn::V1(tx) => InvokeTxn::V1(tx),
+                v0_7_1::InvokeTxn::V3(tx) => InvokeTxn::V3(tx.into()),
+            }),
+            v0_7_1::Txn::L1Handler(tx) => Self::L1Handler(tx),
+            v0_7_1::Txn::Declare(tx) => Self::Declare(match tx {
+                v0_7_1::DeclareTxn::V0(tx) => DeclareTxn::V0(tx),
+                v0_7_1::DeclareTxn::V1(tx) => DeclareTxn::V1(tx),
+                v0_7_1::DeclareTxn::V2(tx) => DeclareTxn::V2(tx),
+                v0_7_1::DeclareTxn::V3(tx) => DeclareTxn::V3(tx.into()),
+            }),
+            v0_7_1::Txn::Deploy(tx) => Self::Deploy(tx),
+            v0_7_1::Txn::DeployAccount(tx) => Self::DeployAccount(match tx {
+                v0_7_1::DeployAccountTxn::V1(tx) => DeployAccountTxn::V1(tx),
+                v0_7_1::DeployAccountTxn::V3(tx) => DeployAccountTxn::V3(tx.into()),
+            }),
+        }
+    }
+}
+
+impl From<v0_7_1::TxnWithHash> for TxnWithHash {
+    fn from(tx: v0_7_1::TxnWithHash) -> Self {
+        Self { transaction: tx.transaction.into(), transaction_hash: tx.transaction_hash }
+    }
+}
+
+impl CommonReceiptProperties {
+    fn from_v0_7_1(receipt: v0_7_1::CommonReceiptProperties, execution_resources: ExecutionResources) -> Self {
+        Self {
+            actual_fee: receipt.actual_fee,
+            events: receipt.events,
+            execution_resources,
+            finality_status: receipt.finality_status,
+            messages_sent: receipt.messages_sent,
+            transaction_hash: receipt.transaction_hash,
+            execution_status: receipt.execution_status,
+        }
+    }
+}
+
+impl TxnReceipt {
+    /// Replaces the execution resources of a v0.7.1 receipt, which are the Cairo resources used by the transaction,
+    /// with `execution_resources`, the gas consumed by the transaction.
+    pub fn from_v0_7_1(receipt: v0_7_1::TxnReceipt, execution_resources: ExecutionResources) -> Self {
+        let common = |properties| CommonReceiptProperties::from_v0_7_1(properties, execution_resources);
+        match receipt {
+            v0_7_1::TxnReceipt::Invoke(receipt) => {
+                Self::Invoke(InvokeTxnReceipt { common_receipt_properties: common(receipt.common_receipt_properties) })
+            }
+            v0_7_1::TxnReceipt::L1Handler(receipt) => Self::L1Handler(L1HandlerTxnReceipt {
+                message_hash: receipt.message_hash,
+                common_receipt_properties: common(receipt.common_receipt_properties),
+            }),
+            v0_7_1::TxnReceipt::Declare(receipt) => Self::Declare(DeclareTxnReceipt {
+                common_receipt_properties: common(receipt.common_receipt_properties),
+            }),
+            v0_7_1::TxnReceipt::Deploy(receipt) => Self::Deploy(DeployTxnReceipt {
+                common_receipt_properties: common(receipt.common_receipt_properties),
+                contract_address: receipt.contract_address,
+            }),
+            v0_7_1::TxnReceipt::DeployAccount(receipt) => Self::DeployAccount(DeployAccountTxnReceipt {
+                common_receipt_properties: common(receipt.common_receipt_properties),
+                contract_address: receipt.contract_address,
+            }),
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! v0.8.0 of the API. Only the types which changed since v0.7.1 are defined here, the others are the ones of
+//! [`crate::v0_7_1`].
+mod from_v0_7_1;
+mod starknet_api_openrpc;
+
+pub use self::starknet_api_openrpc::*;
@@ -0,0 +1,562 @@
+//! Encoding of a state diff in the onchain data format.
+//!
+//! This is the format the Starknet core contract expects for the state diffs published on L1, either as calldata or
+//! inside blobs:
+//!
+//! ```text
+//! number of updated contracts
+//! for each updated contract, sorted by address:
+//!     address
+//!     DA word: |---padding---|---class flag---|---new nonce---|---number of storage updates---|
+//!                 127 bits          1 bit          64 bits                64 bits
+//!     new class hash, only when the class flag is set
+//!     (key, value) for each storage update, sorted by key
+//! number of declared classes
+//! (class hash, compiled class hash) for each declared class, sorted by class hash
+//! ```
+//!
+//! Legacy (Cairo 0) class declarations are not part of the format.
+//!
+//! From Starknet 0.13.3, the DA word is packed tighter and the onchain data is compressed, see
+//! [`StateDiff::to_compressed_onchain_data`]:
+//!
+//! ```text
+//! DA word: |---class flag---|---new nonce---|---number of storage updates---|---small flag---|
+//!               1 bit            64 bits         8 bits when small, else 64        1 bit
+//! ```
+
+use crate::{
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff,
+    StorageEntry,
+};
+use mp_convert::felt_to_u64;
+use starknet_types_core::felt::{Felt, NonZeroFelt};
+use std::collections::{BTreeMap, HashMap};
+
+/// Version of the compression of the onchain data.
+const COMPRESSION_VERSION: u64 = 0;
+/// Bound of the elements of the compression header.
+const HEADER_ELM_BOUND: u128 = 1 << 20;
+/// Version, data length, length of each bucket of unique values and number of repeated values.
+const HEADER_LEN: usize = 2 + N_BITS_PER_BUCKET.len() + 1;
+/// Max number of bits packed in a felt.
+const MAX_N_BITS: u32 = 251;
+/// Size of the values of each bucket of unique values. A value goes to the smallest bucket it fits in.
+const N_BITS_PER_BUCKET: [u32; 6] = [252, 125, 83, 62, 31, 15];
+/// The buckets of unique values, and the bucket of repeated values.
+const TOTAL_N_BUCKETS: usize = N_BITS_PER_BUCKET.len() + 1;
+/// Number of storage updates below which the packed DA word uses 8 bits for it.
+const N_UPDATES_SMALL_BOUND: u64 = 1 << 8;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum OnchainDataError {
+    #[error("Nonce {nonce:#x} of contract {address:#x} does not fit in 64 bits")]
+    NonceTooLarge { address: Felt, nonce: Felt },
+    #[error("Unexpected end of the onchain data")]
+    UnexpectedEnd,
+    #[error("Invalid DA word {word:#x} for contract {address:#x}")]
+    InvalidDaWord { address: Felt, word: Felt },
+    #[error("Invalid length {0:#x} in the onchain data")]
+    InvalidLength(Felt),
+    #[error("{0} unexpected trailing felts in the onchain data")]
+    TrailingData(usize),
+    #[error("Invalid compressed onchain data: {0}")]
+    InvalidCompression(&'static str),
+}
+
+#[derive(Default)]
+struct ContractUpdate {
+    class_hash: Option<Felt>,
+    nonce: Option<Felt>,
+    storage_entries: Vec<StorageEntry>,
+}
+
+impl StateDiff {
+    /// Encodes this state diff in the onchain data format.
+    ///
+    /// The core contract expects the nonce of every updated contract, even when it did not change: the current nonce of
+    /// the contracts with storage updates should be added to [`StateDiff::nonces`] beforehand. Contracts missing from
+    /// it are encoded with a nonce of zero.
+    pub fn to_onchain_data(&self) -> Result<Vec<Felt>, OnchainDataError> {
+        let mut contracts: BTreeMap<Felt, ContractUpdate> = BTreeMap::new();
+        for ContractStorageDiffItem { address, storage_entries } in &self.storage_diffs {
+            contracts.entry(*address).or_default().storage_entries.extend(storage_entries.iter().cloned());
+        }
+        for DeployedContractItem { address, class_hash } in &self.deployed_contracts {
+            contracts.entry(*address).or_default().class_hash = Some(*class_hash);
+        }
+        for ReplacedClassItem { contract_address, class_hash } in &self.replaced_classes {
+            contracts.entry(*contract_address).or_default().class_hash = Some(*class_hash);
+        }
+        for NonceUpdate { contract_address, nonce } in &self.nonces {
+            contracts.entry(*contract_address).or_default().nonce = Some(*nonce);
+        }
+
+        let mut data = vec![Felt::from(contracts.len())];
+        for (address, mut update) in contracts {
+            let nonce = update.nonce.unwrap_or_default();
+            let nonce = felt_to_u64(&nonce).map_err(|_| OnchainDataError::NonceTooLarge { address, nonce })?;
+
+            data.push(address);
+            data.push(da_word(update.class_hash.is_some(), nonce, update.storage_entries.len() as u64));
+            data.extend(update.class_hash);
+
+            update.storage_entries.sort_by_key(|entry| entry.key);
+            for StorageEntry { key, value } in update.storage_entries {
+                data.extend([key, value]);
+            }
+        }
+
+        let mut declared_classes = self.declared_classes.clone();
+        declared_classes.sort_by_key(|class| class.class_hash);
+        data.push(Felt::from(declared_classes.len()));
+        for DeclaredClassItem { class_hash, compiled_class_hash } in declared_classes {
+            data.extend([class_hash, compiled_class_hash]);
+        }
+
+        Ok(data)
+    }
+
+    /// Decodes a state diff from the onchain data format.
+    ///
+    /// The format does not tell deployed contracts apart from replaced classes, so all the class changes are returned
+    /// in [`StateDiff::replaced_classes`]. The nonce of every contract is returned in [`StateDiff::nonces`], except
+    /// for the ones which are zero.
+    pub fn from_onchain_data(data: &[Felt]) -> Result<Self, OnchainDataError> {
+        Self::decode_onchain_data(data, parse_da_word)
+    }
+
+    /// Encodes this state diff in the compressed onchain data format of Starknet 0.13.3 and later, see
+    /// [`StateDiff::to_onchain_data`].
+    pub fn to_compressed_onchain_data(&self) -> Result<Vec<Felt>, OnchainDataError> {
+        let data = self.to_onchain_data()?;
+        let mut packed = Vec::with_capacity(data.len());
+        let mut data = data.into_iter();
+        let n_contracts = data.next().expect("Number of contracts");
+        packed.push(n_contracts);
+        for _ in 0..read_len(n_contracts)? {
+            let address = data.next().expect("Contract address");
+            let word = data.next().expect("DA word");
+            let (class_flag, nonce, n_updates) = parse_da_word(&word).expect("Valid DA word");
+            packed.extend([address, packed_da_word(class_flag, nonce, n_updates)]);
+            let n_felts = class_flag as u64 + 2 * n_updates;
+            packed.extend(data.by_ref().take(n_felts as usize));
+        }
+        packed.extend(data);
+        Ok(compress(&packed))
+    }
+
+    /// Decodes a state diff from the compressed onchain data format, see [`StateDiff::from_onchain_data`].
+    pub fn from_compressed_onchain_data(data: &[Felt]) -> Result<Self, OnchainDataError> {
+        let (decompressed, len) = decompress(data)?;
+        let state_diff = Self::decode_onchain_data(&decompressed, parse_packed_da_word)?;
+        match data.len() - len {
+            0 => Ok(state_diff),
+            n => Err(OnchainDataError::TrailingData(n)),
+        }
+    }
+
+    fn decode_onchain_data(
+        data: &[Felt],
+        parse_da_word: fn(&Felt) -> Option<(bool, u64, u64)>,
+    ) -> Result<Self, OnchainDataError> {
+        let mut data = data.iter().copied();
+        let mut next = || data.next().ok_or(OnchainDataError::UnexpectedEnd);
+
+        let mut state_diff = StateDiff::default();
+        let n_contracts = read_len(next()?)?;
+        for _ in 0..n_contracts {
+            let address = next()?;
+            let word = next()?;
+            let (class_flag, nonce, n_updates) =
+                parse_da_word(&word).ok_or(OnchainDataError::InvalidDaWord { address, word })?;
+
+            if class_flag {
+                state_diff.replaced_classes.push(ReplacedClassItem { contract_address: address, class_hash: next()? });
+            }
+            if nonce != 0 {
+                state_diff.nonces.push(NonceUpdate { contract_address: address, nonce: nonce.into() });
+            }
+            if n_updates != 0 {
+                let storage_entries = (0..n_updates)
+                    .map(|_| Ok(StorageEntry { key: next()?, value: next()? }))
+                    .collect::<Result<_, OnchainDataError>>()?;
+                state_diff.storage_diffs.push(ContractStorageDiffItem { address, storage_entries });
+            }
+        }
+
+        let n_declared_classes = read_len(next()?)?;
+        for _ in 0..n_declared_classes {
+            state_diff.declared_classes.push(DeclaredClassItem { class_hash: next()?, compiled_class_hash: next()? });
+        }
+
+        match data.count() {
+            0 => Ok(state_diff),
+            n => Err(OnchainDataError::TrailingData(n)),
+        }
+    }
+}
+
+fn read_len(felt: Felt) -> Result<u64, OnchainDataError> {
+    felt_to_u64(&felt).map_err(|_| OnchainDataError::InvalidLength(felt))
+}
+
+/// Packs the class flag, the new nonce and the number of storage updates of a contract.
+pub fn da_word(class_flag: bool, nonce: u64, n_updates: u64) -> Felt {
+    let mut bytes = [0u8; 32];
+    bytes[15] = class_flag as u8;
+    bytes[16..24].copy_from_slice(&nonce.to_be_bytes());
+    bytes[24..32].copy_from_slice(&n_updates.to_be_bytes());
+    Felt::from_bytes_be(&bytes)
+}
+
+/// Packs the class flag, the new nonce and the number of storage updates of a contract, in the format of Starknet
+/// 0.13.3 and later.
+fn packed_da_word(class_flag: bool, nonce: u64, n_updates: u64) -> Felt {
+    let small = n_updates < N_UPDATES_SMALL_BOUND;
+    let n_updates_bound = if small { N_UPDATES_SMALL_BOUND as u128 } else { 1 << 64 };
+    let word = Felt::from(class_flag as u128) * Felt::from(1u128 << 64) + Felt::from(nonce);
+    (word * Felt::from(n_updates_bound) + Felt::from(n_updates)) * Felt::TWO + Felt::from(small as u8)
+}
+
+fn parse_packed_da_word(word: &Felt) -> Option<(bool, u64, u64)> {
+    fn take(word: &mut Felt, bits: u32) -> u64 {
+        let (quotient, remainder) = word.div_rem(&NonZeroFelt::from_felt_unchecked(Felt::from(1u128 << bits)));
+        *word = quotient;
+        felt_to_u64(&remainder).expect("Remainder of at most 64 bits")
+    }
+
+    let mut word = *word;
+    let small = take(&mut word, 1) == 1;
+    let n_updates = take(&mut word, if small { 8 } else { 64 });
+    let nonce = take(&mut word, 64);
+    let class_flag = match felt_to_u64(&word).ok()? {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+    Some((class_flag, nonce, n_updates))
+}
+
+/// Number of elements below `bound` packed in a felt. The elements of more than 125 bits are not packed.
+fn n_elms_per_felt(bound: u128) -> usize {
+    if bound <= 1 {
+        return MAX_N_BITS as usize;
+    }
+    if bound > 1 << (MAX_N_BITS / 2) {
+        return 1;
+    }
+    let log2_ceil = u128::BITS - (bound - 1).leading_zeros();
+    (MAX_N_BITS / log2_ceil) as usize
+}
+
+/// Bound of the values of a bucket of unique values, `u128::MAX` when they are not packed.
+fn bucket_bound(n_bits: u32) -> u128 {
+    if n_bits < u128::BITS {
+        1 << n_bits
+    } else {
+        u128::MAX
+    }
+}
+
+/// Packs elements below `bound`, as little-endian digits in base `bound`.
+fn pack(elms: &[Felt], bound: u128) -> Vec<Felt> {
+    elms.chunks(n_elms_per_felt(bound))
+        .map(|chunk| chunk.iter().rev().fold(Felt::ZERO, |packed, elm| packed * Felt::from(bound) + elm))
+        .collect()
+}
+
+/// Unpacks `n_elms` elements below `bound`, see [`pack`].
+fn unpack(data: &mut impl Iterator<Item = Felt>, n_elms: usize, bound: u128) -> Result<Vec<Felt>, OnchainDataError> {
+    let n_elms_per_felt = n_elms_per_felt(bound);
+    let divisor = NonZeroFelt::from_felt_unchecked(Felt::from(bound.max(1)));
+    let mut elms = Vec::with_capacity(n_elms);
+    while elms.len() < n_elms {
+        let mut packed = data.next().ok_or(OnchainDataError::UnexpectedEnd)?;
+        if n_elms_per_felt == 1 {
+            elms.push(packed);
+            continue;
+        }
+        for _ in 0..n_elms_per_felt.min(n_elms - elms.len()) {
+            let (quotient, remainder) = packed.div_rem(&divisor);
+            elms.push(remainder);
+            packed = quotient;
+        }
+        if packed != Felt::ZERO {
+            return Err(OnchainDataError::InvalidCompression("packed felt with extra elements"));
+        }
+    }
+    Ok(elms)
+}
+
+/// Compresses the onchain data as the Starknet OS does: the unique values are sorted by size in buckets and packed,
+/// the repeated values are replaced with a pointer to their first occurrence, and the bucket of each value is
+/// packed in order.
+fn compress(data: &[Felt]) -> Vec<Felt> {
+    let mut buckets: [Vec<Felt>; N_BITS_PER_BUCKET.len()] = Default::default();
+    let mut indices: HashMap<Felt, (usize, usize)> = HashMap::new();
+    let mut repeated = vec![];
+    let mut bucket_per_elm = Vec::with_capacity(data.len());
+    for value in data {
+        let bits = value.bits() as u32;
+        let bucket = N_BITS_PER_BUCKET.iter().rposition(|n_bits| bits <= *n_bits).expect("Felts fit in 252 bits");
+        match indices.get(value) {
+            Some(index) => {
+                repeated.push(*index);
+                bucket_per_elm.push(Felt::from(N_BITS_PER_BUCKET.len()));
+            }
+            None => {
+                indices.insert(*value, (bucket, buckets[bucket].len()));
+                buckets[bucket].push(*value);
+                bucket_per_elm.push(Felt::from(bucket));
+            }
+        }
+    }
+
+    let offsets: Vec<usize> =
+        buckets.iter().scan(0, |offset, bucket| Some(std::mem::replace(offset, *offset + bucket.len()))).collect();
+    let n_unique = buckets.iter().map(Vec::len).sum::<usize>();
+    let header: Vec<Felt> = [COMPRESSION_VERSION as usize, data.len()]
+        .into_iter()
+        .chain(buckets.iter().map(Vec::len))
+        .chain([repeated.len()])
+        .map(Felt::from)
+        .collect();
+    let pointers: Vec<Felt> = repeated.iter().map(|(bucket, index)| Felt::from(offsets[*bucket] + index)).collect();
+
+    let mut compressed = pack(&header, HEADER_ELM_BOUND);
+    for (bucket, n_bits) in buckets.iter().zip(N_BITS_PER_BUCKET) {
+        compressed.extend(pack(bucket, bucket_bound(n_bits)));
+    }
+    compressed.extend(pack(&pointers, n_unique as u128));
+    compressed.extend(pack(&bucket_per_elm, TOTAL_N_BUCKETS as u128));
+    compressed
+}
+
+/// Decompresses the onchain data, see [`compress`]. Returns the data and the number of compressed felts read.
+fn decompress(compressed: &[Felt]) -> Result<(Vec<Felt>, usize), OnchainDataError> {
+    let mut iter = compressed.iter().copied();
+    let header = unpack(&mut iter, HEADER_LEN, HEADER_ELM_BOUND)?;
+    let header: Vec<usize> =
+        header.iter().map(|elm| felt_to_u64(elm).expect("Header elements of 20 bits") as usize).collect();
+    if header[0] != COMPRESSION_VERSION as usize {
+        return Err(OnchainDataError::InvalidCompression("unsupported version"));
+    }
+    let data_len = header[1];
+    let bucket_lens = &header[2..2 + N_BITS_PER_BUCKET.len()];
+    let n_repeated = header[HEADER_LEN - 1];
+
+    let mut buckets = vec![];
+    for (len, n_bits) in bucket_lens.iter().zip(N_BITS_PER_BUCKET) {
+        buckets.push(unpack(&mut iter, *len, bucket_bound(n_bits))?);
+    }
+    let unique: Vec<Felt> = buckets.iter().flatten().copied().collect();
+    let repeated = unpack(&mut iter, n_repeated, unique.len() as u128)?
+        .iter()
+        .map(|pointer| {
+            felt_to_u64(pointer)
+                .ok()
+                .and_then(|pointer| unique.get(pointer as usize).copied())
+                .ok_or(OnchainDataError::InvalidCompression("invalid pointer to a repeated value"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    buckets.push(repeated);
+
+    let mut buckets: Vec<_> = buckets.into_iter().map(|bucket| bucket.into_iter()).collect();
+    let data = unpack(&mut iter, data_len, TOTAL_N_BUCKETS as u128)?
+        .iter()
+        .map(|bucket| {
+            felt_to_u64(bucket)
+                .ok()
+                .and_then(|bucket| buckets.get_mut(bucket as usize)?.next())
+                .ok_or(OnchainDataError::InvalidCompression("invalid bucket index"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((data, compressed.len() - iter.count()))
+}
+
+fn parse_da_word(word: &Felt) -> Option<(bool, u64, u64)> {
+    let bytes = word.to_bytes_be();
+    if bytes[..15].iter().any(|b| *b != 0) || bytes[15] > 1 {
+        return None;
+    }
+    let nonce = u64::from_be_bytes(bytes[16..24].try_into().expect("8 bytes"));
+    let n_updates = u64::from_be_bytes(bytes[24..32].try_into().expect("8 bytes"));
+    Some((bytes[15] == 1, nonce, n_updates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_diff() -> StateDiff {
+        StateDiff {
+            storage_diffs: vec![
+                ContractStorageDiffItem {
+                    address: Felt::from(0x20),
+                    storage_entries: vec![
+                        StorageEntry { key: Felt::from(2), value: Felt::from(0xb) },
+                        StorageEntry { key: Felt::from(1), value: Felt::from(0xa) },
+                    ],
+                },
+                ContractStorageDiffItem {
+                    address: Felt::from(0x10),
+                    storage_entries: vec![StorageEntry { key: Felt::from(3), value: Felt::from(0xc) }],
+                },
+            ],
+            deprecated_declared_classes: vec![],
+            declared_classes: vec![DeclaredClassItem {
+                class_hash: Felt::from(0x55),
+                compiled_class_hash: Felt::from(0x56),
+            }],
+            deployed_contracts: vec![DeployedContractItem { address: Felt::from(0x30), class_hash: Felt::from(0x55) }],
+            replaced_classes: vec![],
+            nonces: vec![
+                NonceUpdate { contract_address: Felt::from(0x20), nonce: Felt::from(5) },
+                NonceUpdate { contract_address: Felt::from(0x30), nonce: Felt::ONE },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_da_word() {
+        assert_eq!(da_word(false, 0, 0), Felt::ZERO);
+        assert_eq!(da_word(false, 0, 3), Felt::from(3));
+        assert_eq!(da_word(false, 2, 3), Felt::from((2u128 << 64) + 3));
+        assert_eq!(da_word(true, 2, 3), Felt::from((2u128 << 64) + 3) + Felt::TWO.pow(128u128));
+        assert_eq!(parse_da_word(&da_word(true, u64::MAX, 7)), Some((true, u64::MAX, 7)));
+        assert_eq!(parse_da_word(&Felt::TWO.pow(129u128)), None);
+    }
+
+    #[test]
+    fn test_to_onchain_data() {
+        let class_word = Felt::TWO.pow(128u128);
+        assert_eq!(
+            state_diff().to_onchain_data().unwrap(),
+            vec![
+                Felt::from(3),
+                // 0x10: one storage update
+                Felt::from(0x10),
+                Felt::ONE,
+                Felt::from(3),
+                Felt::from(0xc),
+                // 0x20: nonce 5, two storage updates
+                Felt::from(0x20),
+                Felt::from((5u128 << 64) + 2),
+                Felt::ONE,
+                Felt::from(0xa),
+                Felt::TWO,
+                Felt::from(0xb),
+                // 0x30: deployed, nonce 1
+                Felt::from(0x30),
+                class_word + Felt::from(1u128 << 64),
+                Felt::from(0x55),
+                // Declared classes
+                Felt::ONE,
+                Felt::from(0x55),
+                Felt::from(0x56),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_onchain_data_roundtrip() {
+        let data = state_diff().to_onchain_data().unwrap();
+        let decoded = StateDiff::from_onchain_data(&data).unwrap();
+
+        let mut expected = state_diff();
+        expected.replaced_classes =
+            vec![ReplacedClassItem { contract_address: Felt::from(0x30), class_hash: Felt::from(0x55) }];
+        expected.deployed_contracts.clear();
+        expected.sort();
+        assert_eq!(decoded, expected);
+        assert_eq!(decoded.to_onchain_data().unwrap(), data);
+    }
+
+    #[test]
+    fn test_packed_da_word() {
+        assert_eq!(packed_da_word(false, 0, 0), Felt::ONE);
+        assert_eq!(packed_da_word(false, 2, 3), Felt::from((((2u128 << 8) + 3) << 1) + 1));
+        assert_eq!(
+            packed_da_word(true, 2, 300),
+            (Felt::from((1u128 << 64) + 2) * Felt::TWO.pow(64u128) + Felt::from(300)) * Felt::TWO
+        );
+        for (class_flag, nonce, n_updates) in
+            [(true, u64::MAX, 7), (false, 1, 255), (true, 0, 256), (false, 5, u64::MAX)]
+        {
+            assert_eq!(
+                parse_packed_da_word(&packed_da_word(class_flag, nonce, n_updates)),
+                Some((class_flag, nonce, n_updates))
+            );
+        }
+        assert_eq!(parse_packed_da_word(&Felt::TWO.pow(131u128)), None);
+    }
+
+    #[test]
+    fn test_decompress() {
+        // 5 and 2^100 are unique values, in the 15 and 125 bits buckets, and the second 5 is a repeated value.
+        let header = [0u64, 3, 0, 1, 0, 0, 0, 1, 1]
+            .iter()
+            .rev()
+            .fold(Felt::ZERO, |packed, elm| packed * Felt::from(1u64 << 20) + Felt::from(*elm));
+        let compressed = vec![
+            header,
+            // Unique values
+            Felt::TWO.pow(100u128),
+            Felt::from(5),
+            // Pointer to the first unique value, 5
+            Felt::ONE,
+            // Bucket of each value: 5, repeated, 125 bits
+            Felt::from(5 + 6 * 7 + 49),
+        ];
+        let data = vec![Felt::from(5), Felt::from(5), Felt::TWO.pow(100u128)];
+        assert_eq!(decompress(&compressed).unwrap(), (data.clone(), 5));
+        assert_eq!(compress(&data), compressed);
+
+        assert_eq!(decompress(&[header + Felt::ONE]), Err(OnchainDataError::InvalidCompression("unsupported version")));
+        assert_eq!(decompress(&compressed[..4]), Err(OnchainDataError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_compressed_onchain_data_roundtrip() {
+        let mut state_diff = state_diff();
+        // Repeated values
+        state_diff.storage_diffs[0].storage_entries[1].value = Felt::from(0xb);
+        let data = state_diff.to_compressed_onchain_data().unwrap();
+        assert!(data.len() < state_diff.to_onchain_data().unwrap().len());
+        let decoded = StateDiff::from_compressed_onchain_data(&data).unwrap();
+
+        let mut expected = state_diff.clone();
+        expected.replaced_classes =
+            vec![ReplacedClassItem { contract_address: Felt::from(0x30), class_hash: Felt::from(0x55) }];
+        expected.deployed_contracts.clear();
+        expected.sort();
+        assert_eq!(decoded, expected);
+        assert_eq!(decoded.to_compressed_onchain_data().unwrap(), data);
+
+        assert_eq!(
+            StateDiff::from_compressed_onchain_data(&[data.clone(), vec![Felt::ZERO; 2]].concat()),
+            Err(OnchainDataError::TrailingData(2))
+        );
+        // The uncompressed format is not mistaken for the compressed one.
+        assert!(StateDiff::from_compressed_onchain_data(&state_diff.to_onchain_data().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_onchain_data_errors() {
+        let mut too_large = state_diff();
+        too_large.nonces[0].nonce = Felt::from(u128::MAX);
+        assert_eq!(
+            too_large.to_onchain_data(),
+            Err(OnchainDataError::NonceTooLarge { address: Felt::from(0x20), nonce: Felt::from(u128::MAX) })
+        );
+
+        let data = state_diff().to_onchain_data().unwrap();
+        assert_eq!(StateDiff::from_onchain_data(&data[..data.len() - 1]), Err(OnchainDataError::UnexpectedEnd));
+        assert_eq!(
+            StateDiff::from_onchain_data(&[data.clone(), vec![Felt::ONE]].concat()),
+            Err(OnchainDataError::TrailingData(1))
+        );
+    }
+}
@@ -5,10 +5,10 @@ use starknet_types_core::felt::Felt;
 use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
 
 use crate::{
-    DataAvailabilityMode, DeclareTransaction, DeclareTransactionV0, DeclareTransactionV1, DeclareTransactionV2,
-    DeclareTransactionV3, DeployAccountTransaction, DeployAccountTransactionV1, DeployAccountTransactionV3,
-    DeployTransaction, InvokeTransaction, InvokeTransactionV0, InvokeTransactionV1, InvokeTransactionV3,
-    L1HandlerTransaction, ResourceBoundsMapping, Transaction,
+    CustomInvokeTransaction, DataAvailabilityMode, DeclareTransaction, DeclareTransactionV0, DeclareTransactionV1,
+    DeclareTransactionV2, DeclareTransactionV3, DeployAccountTransaction, DeployAccountTransactionV1,
+    DeployAccountTransactionV3, DeployTransaction, InvokeTransaction, InvokeTransactionV0, InvokeTransactionV1,
+    InvokeTransactionV3, L1HandlerTransaction, ResourceBoundsMapping, Transaction,
 };
 
 use super::SIMULATE_TX_VERSION_OFFSET;
@@ -175,9 +175,21 @@ impl InvokeTransactionV1 {
     }
 }
 
+impl CustomInvokeTransaction {
+    /// Hashed as an invoke v3 transaction with the custom version in place of 3, so that the signature of a custom
+    /// transaction can never be replayed as a Starknet transaction.
+    pub fn compute_hash(&self, chain_id: Felt) -> Felt {
+        self.transaction.compute_hash_with_version(chain_id, self.version)
+    }
+}
+
 impl InvokeTransactionV3 {
     pub fn compute_hash(&self, chain_id: Felt, offset_version: bool) -> Felt {
         let version = if offset_version { SIMULATE_TX_VERSION_OFFSET + Felt::THREE } else { Felt::THREE };
+        self.compute_hash_with_version(chain_id, version)
+    }
+
+    fn compute_hash_with_version(&self, chain_id: Felt, version: Felt) -> Felt {
         let gas_hash = compute_gas_hash(self.tip, &self.resource_bounds);
         let paymaster_hash = Poseidon::hash_array(&self.paymaster_data);
         let data_availability_modes =
@@ -542,6 +554,30 @@ pub fn calculate_contract_address(
     address
 }
 
+/// The address of a contract deployed by the Universal Deployer Contract at `udc_address`, see
+/// [`mp_chain_config::ChainConfig::udc_address`].
+///
+/// With `unique` deployments, `deployer_address` is the account calling the UDC: the salt is hashed with it, so that
+/// no other account can deploy at the same address. Otherwise, the address only depends on the class, the salt and the
+/// constructor calldata, as for a deploy account transaction.
+pub fn calculate_udc_contract_address(
+    salt: Felt,
+    class_hash: Felt,
+    constructor_calldata: &[Felt],
+    deployer_address: Option<Felt>,
+    udc_address: Felt,
+) -> Felt {
+    match deployer_address {
+        Some(deployer_address) => calculate_contract_address(
+            Pedersen::hash(&deployer_address, &salt),
+            class_hash,
+            constructor_calldata,
+            udc_address,
+        ),
+        None => calculate_contract_address(salt, class_hash, constructor_calldata, Felt::ZERO),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tests::{
@@ -679,6 +715,15 @@ mod tests {
         assert_eq!(hash, expected_hash);
     }
 
+    #[test]
+    fn test_compute_hash_custom_invoke() {
+        let v3_hash = dummy_tx_invoke_v3().compute_hash(CHAIN_ID, false);
+        let tx = CustomInvokeTransaction { version: Felt::from(0x100), transaction: dummy_tx_invoke_v3() };
+        assert_ne!(tx.compute_hash(CHAIN_ID), v3_hash);
+        let tx = CustomInvokeTransaction { version: Felt::THREE, ..tx };
+        assert_eq!(tx.compute_hash(CHAIN_ID), v3_hash);
+    }
+
     #[test]
     fn test_compute_hash_pre_v0_7_l1_handler() {
         let tx: Transaction = dummy_l1_handler().into();
@@ -703,6 +748,47 @@ mod tests {
         assert_eq!(contract_address, expected_contract_address,);
     }
 
+    #[test]
+    fn test_calculate_udc_contract_address() {
+        let udc = mp_chain_config::UDC_CONTRACT_ADDRESS;
+        let tx = dummy_tx_deploy_account_v1();
+        let (salt, class_hash, calldata) = (tx.contract_address_salt, tx.class_hash, &tx.constructor_calldata);
+        // Not unique: same address as a deploy account transaction.
+        assert_eq!(
+            calculate_udc_contract_address(salt, class_hash, calldata, None, udc),
+            tx.calculate_contract_address()
+        );
+
+        // Unique: the address depends on the deployer and on the UDC.
+        let address_a = calculate_udc_contract_address(salt, class_hash, calldata, Some(Felt::from(0x100)), udc);
+        let address_b = calculate_udc_contract_address(salt, class_hash, calldata, Some(Felt::from(0x200)), udc);
+        assert_ne!(address_a, address_b);
+        assert_ne!(address_a, tx.calculate_contract_address());
+        let address_c = calculate_udc_contract_address(salt, class_hash, calldata, Some(Felt::from(0x100)), Felt::ONE);
+        assert_ne!(address_a, address_c);
+    }
+
+    /// The reference deployments of the UDC tests of starknet-rs.
+    #[test]
+    fn test_calculate_udc_contract_address_known() {
+        let udc = mp_chain_config::UDC_CONTRACT_ADDRESS;
+        let class_hash = Felt::from_hex_unchecked("0x562fc1d911530d18a86ea3ef4be50018923898d3c573288c5abb9c2344459ed");
+        let calldata = [Felt::from_hex_unchecked("0x1234")];
+
+        let salt = Felt::from_hex_unchecked("0x6df0e9a9842d97ff3f4c6de7494d6e69d0a107a72150f9c53d59515b91ed9cb");
+        assert_eq!(
+            calculate_udc_contract_address(salt, class_hash, &calldata, None, udc),
+            Felt::from_hex_unchecked("0x288e5952d2f2f0e897ea0c5401c6e9f584a89eebfb08b5b26f090a8bbf67eb6")
+        );
+
+        let salt = Felt::from_hex_unchecked("0x1f65976b95bf17ae1cb04afc9fc1eeee26d3e1aaa1f30aa535bf261e4322ab8");
+        let deployer = Felt::from_hex_unchecked("0xb1461de04c6a1aa3375bdf9b7723a8779c082ffe21311d683a0b15c078b5dc");
+        assert_eq!(
+            calculate_udc_contract_address(salt, class_hash, &calldata, Some(deployer), udc),
+            Felt::from_hex_unchecked("0x2406943b25942021f213b047c8765e531dddce3b981722f7aeb2ca137e18dbf")
+        );
+    }
+
     #[test]
     fn test_pedersen_empty() {
         assert_eq!(PEDERSEN_EMPTY, Pedersen::hash_array(&[]))
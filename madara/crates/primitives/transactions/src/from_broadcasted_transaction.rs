@@ -37,6 +37,9 @@ impl From<mp_rpc::BroadcastedInvokeTxn> for InvokeTransaction {
             mp_rpc::BroadcastedInvokeTxn::QueryV0(tx) => InvokeTransaction::V0(tx.into()),
             mp_rpc::BroadcastedInvokeTxn::QueryV1(tx) => InvokeTransaction::V1(tx.into()),
             mp_rpc::BroadcastedInvokeTxn::QueryV3(tx) => InvokeTransaction::V3(tx.into()),
+            // Custom transactions are turned into Starknet transactions by the mempool before being executed: only
+            // their invoke v3 layout is kept here.
+            mp_rpc::BroadcastedInvokeTxn::Custom(tx) => InvokeTransaction::V3(tx.transaction.into()),
         }
     }
 }
@@ -1,10 +1,10 @@
 use starknet_types_core::felt::Felt;
 
 use crate::{
-    DeclareTransaction, DeclareTransactionV0, DeclareTransactionV1, DeclareTransactionV2, DeclareTransactionV3,
-    DeployAccountTransaction, DeployAccountTransactionV1, DeployAccountTransactionV3, DeployTransaction,
-    InvokeTransaction, InvokeTransactionV0, InvokeTransactionV1, InvokeTransactionV3, L1HandlerTransaction,
-    Transaction,
+    CustomInvokeTransaction, DeclareTransaction, DeclareTransactionV0, DeclareTransactionV1, DeclareTransactionV2,
+    DeclareTransactionV3, DeployAccountTransaction, DeployAccountTransactionV1, DeployAccountTransactionV3,
+    DeployTransaction, InvokeTransaction, InvokeTransactionV0, InvokeTransactionV1, InvokeTransactionV3,
+    L1HandlerTransaction, Transaction,
 };
 
 impl From<mp_rpc::Txn> for Transaction {
@@ -53,6 +53,12 @@ impl From<mp_rpc::InvokeTxnV1> for InvokeTransactionV1 {
     }
 }
 
+impl From<mp_rpc::CustomInvokeTxn> for CustomInvokeTransaction {
+    fn from(tx: mp_rpc::CustomInvokeTxn) -> Self {
+        Self { version: tx.version, transaction: tx.transaction.into() }
+    }
+}
+
 impl From<mp_rpc::InvokeTxnV3> for InvokeTransactionV3 {
     fn from(tx: mp_rpc::InvokeTxnV3) -> Self {
         Self {
@@ -371,6 +371,14 @@ impl InvokeTransactionV3 {
     }
 }
 
+/// An invoke transaction with a version which is not a Starknet one, accepted by the chains listing it in
+/// [`mp_chain_config::ChainConfig::custom_transaction_versions`]. It has the layout of an invoke v3 transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomInvokeTransaction {
+    pub version: Felt,
+    pub transaction: InvokeTransactionV3,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct L1HandlerTransaction {
     pub version: Felt,
@@ -11,7 +11,7 @@ use mp_class::{
     class_hash, compile::ClassCompilationError, CompressedLegacyContractClass, ConvertedClass, FlattenedSierraClass,
     LegacyClassInfo, LegacyConvertedClass, SierraClassInfo, SierraConvertedClass,
 };
-use mp_rpc::{BroadcastedDeclareTxn, BroadcastedTxn};
+use mp_rpc::{BroadcastedDeclareTxn, BroadcastedInvokeTxn, BroadcastedTxn};
 use starknet_api::transaction::{Fee, TransactionHash};
 use starknet_types_core::felt::Felt;
 use std::sync::Arc;
@@ -90,6 +90,9 @@ impl BroadcastedTransactionExt for BroadcastedTxn {
                     handle_class_sierra(Arc::new((tx.contract_class).clone().into()), tx.compiled_class_hash)?
                 }
             },
+            BroadcastedTxn::Invoke(BroadcastedInvokeTxn::Custom(tx)) => {
+                return Err(ToBlockifierError::CustomTransactionVersion(tx.version))
+            }
             _ => (None, None, None),
         };
 
@@ -180,6 +183,8 @@ pub enum ToBlockifierError {
     Base64ToCairoError(#[from] std::io::Error),
     #[error("Missing class")]
     MissingClass,
+    #[error("Transactions with custom version {0:#x} cannot be executed directly")]
+    CustomTransactionVersion(Felt),
 }
 
 #[allow(clippy::type_complexity)]
@@ -0,0 +1,86 @@
+//! Alerts on the critical events of the node.
+//!
+//! The services report the events an operator should be paged for to an [`AlertHandle`]. The alert service of the
+//! node sends them to the webhooks configured with `--alert-webhook`. The handle does nothing when no webhook is
+//! configured.
+
+use tokio::sync::broadcast;
+
+/// Number of alerts kept for the alert service when it falls behind.
+const ALERT_CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    /// No block was imported for a while, even though the feeder gateway is ahead of the node.
+    SyncStalled,
+    /// A block failed verification, and was quarantined.
+    VerificationFailure,
+    /// No new state update was confirmed on L1 for a while.
+    L1SyncStalled,
+    /// The disk of the database is nearly full.
+    DiskNearlyFull,
+    /// The database was reverted to recover from a reorg of `depth` blocks.
+    Reorg { depth: u64 },
+}
+
+impl AlertKind {
+    /// Name of the event in the alert payloads.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SyncStalled => "sync_stalled",
+            Self::VerificationFailure => "verification_failure",
+            Self::L1SyncStalled => "l1_sync_stalled",
+            Self::DiskNearlyFull => "disk_nearly_full",
+            Self::Reorg { .. } => "reorg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// Description of the event for the operator.
+    pub message: String,
+    /// Block the event is about, if any.
+    pub block_n: Option<u64>,
+}
+
+impl Alert {
+    pub fn new(kind: AlertKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into(), block_n: None }
+    }
+
+    pub fn with_block_n(self, block_n: u64) -> Self {
+        Self { block_n: Some(block_n), ..self }
+    }
+}
+
+/// Sends alerts to the alert service, see the [module documentation](self). The default handle is disabled.
+#[derive(Debug, Clone, Default)]
+pub struct AlertHandle(Option<broadcast::Sender<Alert>>);
+
+impl AlertHandle {
+    pub fn new() -> Self {
+        Self(Some(broadcast::channel(ALERT_CHANNEL_CAPACITY).0))
+    }
+
+    pub fn disabled() -> Self {
+        Self(None)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Receives the alerts sent from now on, `None` when the handle is disabled.
+    pub fn subscribe(&self) -> Option<broadcast::Receiver<Alert>> {
+        self.0.as_ref().map(|sender| sender.subscribe())
+    }
+
+    pub fn send(&self, alert: Alert) {
+        if let Some(sender) = &self.0 {
+            // There is no receiver when the alert service is stopped.
+            let _ = sender.send(alert);
+        }
+    }
+}
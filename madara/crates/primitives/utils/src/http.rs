@@ -0,0 +1,141 @@
+//! Settings shared by all the outbound HTTP clients of the node: the feeder gateway client, the L1 RPC client, the
+//! price oracle, the beacon node client and telemetry.
+
+use std::time::Duration;
+
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    /// Maximum time to establish a connection.
+    pub connect_timeout: Duration,
+    /// Maximum time for a request, from sending it to receiving the whole response.
+    pub timeout: Duration,
+    /// Time an idle connection is kept open in the pool to be reused.
+    pub pool_idle_timeout: Duration,
+    /// Interval of the TCP keep-alive probes, disabled when `None`.
+    pub tcp_keepalive: Option<Duration>,
+    pub proxy: ProxyConfig,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(20),
+            pool_idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            proxy: ProxyConfig::from_env(),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    /// A [`reqwest`] client builder with every setting but the request timeout, for the clients which keep their
+    /// connection open such as websockets.
+    pub fn reqwest_builder(&self) -> reqwest::ClientBuilder {
+        let proxy = self.proxy.clone();
+        reqwest::Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            // Replaces the proxies read from the environment by reqwest.
+            .proxy(reqwest::Proxy::custom(move |url| proxy.proxy_for(url).cloned()))
+    }
+
+    pub fn reqwest_client(&self) -> reqwest::Result<reqwest::Client> {
+        self.reqwest_builder().timeout(self.timeout).build()
+    }
+}
+
+/// Proxies the outbound requests go through, by url scheme.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub http: Option<Url>,
+    pub https: Option<Url>,
+    /// Hosts which are reached directly. A host matches an entry when it is equal to it, or is a subdomain of it. An
+    /// entry of `*` matches every host.
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Reads the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment variables, or their lowercase versions.
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name.to_uppercase()).or_else(|_| std::env::var(name)).ok().filter(|value| !value.is_empty())
+        };
+        let proxy_url = |name: &str| {
+            let value = var(name)?;
+            match Url::parse(&value) {
+                Ok(url) => Some(url),
+                Err(err) => {
+                    tracing::warn!("Ignoring invalid proxy url in {}: {err}", name.to_uppercase());
+                    None
+                }
+            }
+        };
+
+        Self {
+            http: proxy_url("http_proxy"),
+            https: proxy_url("https_proxy"),
+            no_proxy: var("no_proxy").map(|value| Self::parse_no_proxy(&value)).unwrap_or_default(),
+        }
+    }
+
+    /// Sends the requests of every scheme through `proxy`.
+    pub fn all(proxy: Url) -> Self {
+        Self { http: Some(proxy.clone()), https: Some(proxy), no_proxy: vec![] }
+    }
+
+    /// Parses a comma separated list of hosts, as in the `NO_PROXY` environment variable.
+    pub fn parse_no_proxy(s: &str) -> Vec<String> {
+        s.split(',')
+            .map(|host| host.trim().trim_start_matches('.').to_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect()
+    }
+
+    /// The proxy `url` should be requested through, if any.
+    pub fn proxy_for(&self, url: &Url) -> Option<&Url> {
+        let proxy = match url.scheme() {
+            "https" | "wss" => self.https.as_ref(),
+            "http" | "ws" => self.http.as_ref(),
+            _ => None,
+        }?;
+
+        let host = url.host_str()?.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+        let bypass = self.no_proxy.iter().any(|entry| {
+            entry == "*"
+                || host == *entry
+                || host.strip_suffix(entry.as_str()).is_some_and(|subdomain| subdomain.ends_with('.'))
+        });
+        (!bypass).then_some(proxy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_for() {
+        let proxy = Url::parse("http://proxy.internal:3128").unwrap();
+        let config = ProxyConfig {
+            http: None,
+            https: Some(proxy.clone()),
+            no_proxy: ProxyConfig::parse_no_proxy("localhost, .svc.cluster.local,10.0.0.1"),
+        };
+
+        let url = |s: &str| Url::parse(s).unwrap();
+        assert_eq!(config.proxy_for(&url("https://alpha-mainnet.starknet.io/feeder_gateway/")), Some(&proxy));
+        assert_eq!(config.proxy_for(&url("http://alpha-mainnet.starknet.io/feeder_gateway/")), None);
+        assert_eq!(config.proxy_for(&url("https://localhost:8080")), None);
+        assert_eq!(config.proxy_for(&url("https://beacon.svc.cluster.local")), None);
+        assert_eq!(config.proxy_for(&url("https://10.0.0.1")), None);
+        // Not a subdomain.
+        assert_eq!(config.proxy_for(&url("https://notlocalhost")), Some(&proxy));
+
+        let config = ProxyConfig { no_proxy: vec!["*".into()], ..ProxyConfig::all(proxy) };
+        assert_eq!(config.proxy_for(&url("https://alpha-mainnet.starknet.io")), None);
+    }
+}
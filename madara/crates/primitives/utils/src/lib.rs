@@ -1,7 +1,9 @@
 #![allow(clippy::new_without_default)]
 
+pub mod alerts;
 pub mod crypto;
 pub mod hash;
+pub mod http;
 pub mod parsers;
 pub mod serde;
 pub mod service;
@@ -377,6 +377,7 @@ pub enum MadaraServiceId {
     RpcAdmin,
     Gateway,
     Telemetry,
+    Alerts,
 }
 
 impl ServiceId for MadaraServiceId {
@@ -392,6 +393,7 @@ impl ServiceId for MadaraServiceId {
             MadaraServiceId::RpcAdmin => PowerOfTwo::P5,
             MadaraServiceId::Gateway => PowerOfTwo::P6,
             MadaraServiceId::Telemetry => PowerOfTwo::P7,
+            MadaraServiceId::Alerts => PowerOfTwo::P8,
         }
     }
 }
@@ -411,6 +413,7 @@ impl Display for MadaraServiceId {
                 Self::RpcAdmin => "rpc admin",
                 Self::Gateway => "gateway",
                 Self::Telemetry => "telemetry",
+                Self::Alerts => "alerts",
             }
         )
     }
@@ -443,7 +446,8 @@ impl From<PowerOfTwo> for MadaraServiceId {
             PowerOfTwo::P4 => Self::RpcUser,
             PowerOfTwo::P5 => Self::RpcAdmin,
             PowerOfTwo::P6 => Self::Gateway,
-            _ => Self::Telemetry,
+            PowerOfTwo::P7 => Self::Telemetry,
+            _ => Self::Alerts,
         }
     }
 }
@@ -586,7 +590,7 @@ impl MadaraServiceMask {
     }
 
     fn active_set(&self) -> Vec<MadaraServiceId> {
-        let mut i = MadaraServiceId::Telemetry.svc_id() as u64;
+        let mut i = MadaraServiceId::Alerts.svc_id() as u64;
         let state = self.value();
         let mut set = Vec::with_capacity(SERVICE_COUNT_MAX);
 
@@ -973,10 +977,10 @@ pub struct ServiceTransport {
 ///     #[inline(always)]
 ///     fn svc_id(&self) -> PowerOfTwo {
 ///         match self {
-///             // PowerOfTwo::P0 up until PowerOfTwo::P7 are already in use by
+///             // PowerOfTwo::P0 up until PowerOfTwo::P8 are already in use by
 ///             // MadaraServiceId, you should not use them!
-///             Self::MyServiceA => PowerOfTwo::P8,
-///             Self::MyServiceB => PowerOfTwo::P9,
+///             Self::MyServiceA => PowerOfTwo::P9,
+///             Self::MyServiceB => PowerOfTwo::P10,
 ///         }
 ///     }
 /// }
@@ -1191,14 +1195,17 @@ pub struct ServiceMonitor {
     join_set: JoinSet<anyhow::Result<PowerOfTwo>>,
     status_request: Arc<MadaraServiceMask>,
     status_actual: Arc<MadaraServiceMask>,
+    ctx: ServiceContext,
 }
 
 impl Default for ServiceMonitor {
     fn default() -> Self {
+        let status_request = Arc::<MadaraServiceMask>::default();
         Self {
             services: [const { None }; SERVICE_COUNT_MAX],
             join_set: JoinSet::new(),
-            status_request: Arc::default(),
+            ctx: ServiceContext::new_with_services(Arc::clone(&status_request)),
+            status_request,
             status_actual: Arc::default(),
         }
     }
@@ -1237,6 +1244,13 @@ impl ServiceMonitor {
         self.status_request.activate(id);
     }
 
+    /// The global [ServiceContext] of the services. Calling
+    /// [ServiceContext::cancel_global] on it stops them, as `SIGINT` and
+    /// `SIGTERM` do.
+    pub fn context(&self) -> ServiceContext {
+        self.ctx.clone()
+    }
+
     /// Starts all activate [Service]s and runs them to completion. Services
     /// are activated by calling [ServiceMonitor::activate]. This function
     /// completes once all services have been run to completion.
@@ -1245,7 +1259,7 @@ impl ServiceMonitor {
     /// are running (otherwise the node would shutdown).
     #[tracing::instrument(skip(self), fields(module = "Service"))]
     pub async fn start(mut self) -> anyhow::Result<()> {
-        let mut ctx = ServiceContext::new_with_services(Arc::clone(&self.status_request));
+        let mut ctx = self.ctx.clone();
 
         // start only the initially active services
         for svc in self.services.iter_mut() {
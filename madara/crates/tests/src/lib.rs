@@ -60,6 +60,12 @@ impl MadaraCmd {
         &self.json_rpc
     }
 
+    /// The url of the v0.7.1 RPC API, which the starknet-rs client implements. The default path serves the latest
+    /// version, whose results have a different shape.
+    pub fn rpc_url_v0_7_1(&self) -> Url {
+        self.rpc_url.join("rpc/v0_7_1/").unwrap()
+    }
+
     pub fn db_dir(&self) -> &Path {
         self.tempdir.path()
     }
@@ -236,7 +242,7 @@ impl MadaraCmdBuilder {
         MadaraCmd {
             process: Some(process),
             ready: false,
-            json_rpc: JsonRpcClient::new(HttpTransport::new(rpc_url.clone())),
+            json_rpc: JsonRpcClient::new(HttpTransport::new(rpc_url.join("rpc/v0_7_1/").unwrap())),
             rpc_url,
             tempdir: self.tempdir,
             _port: self.port,
@@ -287,3 +293,52 @@ async fn madara_can_sync_a_few_blocks() {
         }
     );
 }
+
+#[rstest]
+#[tokio::test]
+async fn madara_can_sync_from_another_madara_node() {
+    let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+
+    let gateway_port = get_port();
+    let mut source = MadaraCmdBuilder::new()
+        .args([
+            "--full",
+            "--network",
+            "sepolia",
+            "--no-sync-polling",
+            "--n-blocks-to-sync",
+            "20",
+            "--no-l1-sync",
+            "--gas-price",
+            "0",
+            "--feeder-gateway-enable",
+            "--gateway-port",
+            &gateway_port.0.to_string(),
+        ])
+        .run();
+    source.wait_for_ready().await;
+    source.wait_for_sync_to(19).await;
+
+    let mut node = MadaraCmdBuilder::new()
+        .args([
+            "--full",
+            "--network",
+            "sepolia",
+            "--gateway-url",
+            &format!("http://127.0.0.1:{}/", gateway_port.0),
+            "--no-sync-polling",
+            "--n-blocks-to-sync",
+            "20",
+            "--no-l1-sync",
+            "--gas-price",
+            "0",
+        ])
+        .run();
+    node.wait_for_ready().await;
+    node.wait_for_sync_to(19).await;
+
+    assert_eq!(
+        node.json_rpc().block_hash_and_number().await.unwrap(),
+        source.json_rpc().block_hash_and_number().await.unwrap()
+    );
+}
@@ -99,7 +99,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_block_hash_and_number_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let result = { json_client.block_hash_and_number().await.unwrap() };
         assert_eq!(
             result,
@@ -133,7 +133,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_block_txn_count_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let result = { json_client.get_block_transaction_count(BlockId::Number(2)).await.unwrap() };
         assert_eq!(result, 1);
     }
@@ -246,7 +246,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_block_txn_with_receipts_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let block = json_client
             .get_block_with_receipts(BlockId::Number(2))
             .await
@@ -326,7 +326,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_block_txn_with_tx_hashes_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let block = { json_client.get_block_with_tx_hashes(BlockId::Number(2)).await.unwrap() };
 
         let expected_block = MaybePendingBlockWithTxHashes::Block(BlockWithTxHashes {
@@ -368,7 +368,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_block_txn_with_tx_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let block = json_client.get_block_with_txs(BlockId::Number(2)).await.unwrap();
 
         let expected_block = MaybePendingBlockWithTxs::Block(BlockWithTxs {
@@ -417,7 +417,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_class_hash_at_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let class_hash = {
             json_client
                 .get_class_hash_at(
@@ -455,7 +455,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_nonce_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let nonce = {
             json_client
                 .get_nonce(
@@ -493,7 +493,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_txn_by_block_id_and_index_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let txn = { json_client.get_transaction_by_block_id_and_index(BlockId::Number(16), 1).await.unwrap() };
         let expected_txn = Transaction::L1Handler(L1HandlerTransaction {
             transaction_hash: felt!("0x68fa87ed202095170a2f551017bf646180f43f4687553dc45e61598349a9a8a"),
@@ -532,7 +532,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_txn_by_hash_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let txn = {
             json_client
                 .get_transaction_by_hash(felt!("0x68fa87ed202095170a2f551017bf646180f43f4687553dc45e61598349a9a8a"))
@@ -577,7 +577,7 @@ mod test_rpc_read_calls {
     // TODO: replace this with jsonrpsee client
     async fn test_get_txn_receipt_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let txn_receipt = {
             json_client
                 .get_transaction_receipt(felt!("0x701d9adb9c60bc2fd837fe3989e15aeba4be1a6e72bb6f61ffe35a42866c772"))
@@ -646,7 +646,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_txn_status_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let txn_status = {
             json_client
                 .get_transaction_status(felt!("0x68fa87ed202095170a2f551017bf646180f43f4687553dc45e61598349a9a8a"))
@@ -686,7 +686,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_storage_at_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let storage_response = {
             json_client
                 .get_storage_at(
@@ -724,7 +724,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_state_update_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let state_update = json_client
             .get_state_update(BlockId::Number(13))
             .await
@@ -814,7 +814,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_events_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let events = {
             json_client
                 .get_events(
@@ -893,7 +893,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_events_with_continuation_token_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let events = {
             json_client
                 .get_events(
@@ -984,7 +984,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_call_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let call_response = {
             json_client
                 .call(
@@ -1057,7 +1057,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_class_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let contract_class = {
             json_client
                 .get_class(
@@ -1153,7 +1153,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_get_class_at_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let contract_class = {
             json_client
                 .get_class_at(
@@ -1217,7 +1217,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_estimate_fee_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let call_response = {
             json_client
                 .estimate_fee(
@@ -1295,7 +1295,7 @@ mod test_rpc_read_calls {
     #[tokio::test]
     async fn test_estimate_message_fee_works() {
         let madara = get_shared_state().await;
-        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url.clone()));
+        let json_client = JsonRpcClient::new(HttpTransport::new(madara.rpc_url_v0_7_1()));
         let call_response = {
             json_client
                 .estimate_message_fee(
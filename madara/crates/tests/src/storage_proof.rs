@@ -1,5 +1,7 @@
 use crate::{MadaraCmd, MadaraCmdBuilder};
 use rstest::rstest;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, StarkHash};
 
 fn normalize(json: &mut serde_json::Value) {
     match json {
@@ -12,6 +14,15 @@ fn normalize(json: &mut serde_json::Value) {
     }
 }
 
+/// The hash of the leaf of a contract in the contracts trie, from its `contract_leaves_data` item.
+fn contract_leaf_hash(leaf_data: &serde_json::Value) -> Felt {
+    let felt = |key: &str| serde_json::from_value::<Felt>(leaf_data[key].clone()).unwrap();
+    Pedersen::hash(
+        &Pedersen::hash(&Pedersen::hash(&felt("class_hash"), &felt("storage_root")), &felt("nonce")),
+        &Felt::ZERO,
+    )
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_storage_proof_snapshots() {
@@ -217,6 +228,14 @@ async fn test_storage_proof_inner(node: MadaraCmd) {
       }
     });
 
+    // The leaf of the contract, at the end of the first edge node, commits to its storage root.
+    let leaf_data = &ret["result"]["contracts_proof"]["contract_leaves_data"][0];
+    assert_eq!(
+        contract_leaf_hash(leaf_data),
+        Felt::from_hex_unchecked("0x3873db7e0ca5c27a75e9e867f61124c2df15095124b413cccf94e1f50427d1f")
+    );
+    expected["contracts_proof"]["contract_leaves_data"][0]["storage_root"] = leaf_data["storage_root"].clone();
+
     normalize(&mut ret["result"]);
     normalize(&mut expected);
     assert_eq!(ret["result"], expected);
@@ -246,7 +265,8 @@ async fn test_storage_proof_inner(node: MadaraCmd) {
         "contract_leaves_data": [
           {
             "class_hash": "0x0",
-            "nonce": "0x0"
+            "nonce": "0x0",
+            "storage_root": "0x0"
           }
         ],
         "nodes": [
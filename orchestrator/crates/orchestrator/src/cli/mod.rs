@@ -604,6 +604,17 @@ pub mod validate_params {
     }
 
     pub(crate) fn validate_service_params(service_args: &ServiceCliArgs) -> Result<ServiceParams, String> {
+        // A limit of 0 would block the jobs of the dependency forever.
+        for (limit, arg) in [
+            (service_args.max_concurrent_da_jobs, "--max-concurrent-da-jobs"),
+            (service_args.max_concurrent_proving_jobs, "--max-concurrent-proving-jobs"),
+            (service_args.max_concurrent_settlement_jobs, "--max-concurrent-settlement-jobs"),
+        ] {
+            if limit == Some(0) {
+                return Err(format!("{arg} must be greater than 0"));
+            }
+        }
+
         Ok(ServiceParams {
             // return None if the value is empty string
             max_block_to_process: service_args.max_block_to_process.clone().and_then(|s| {
@@ -620,6 +631,10 @@ pub mod validate_params {
                     Some(s.parse::<u64>().expect("Failed to parse min block to process"))
                 }
             }),
+            max_concurrent_da_jobs: service_args.max_concurrent_da_jobs,
+            max_concurrent_proving_jobs: service_args.max_concurrent_proving_jobs,
+            max_concurrent_settlement_jobs: service_args.max_concurrent_settlement_jobs,
+            settlement_dry_run: service_args.settlement_dry_run,
         })
     }
 
@@ -900,12 +915,40 @@ pub mod validate_params {
             let service_args: ServiceCliArgs = ServiceCliArgs {
                 max_block_to_process: Some("66645".to_string()),
                 min_block_to_process: Some("100".to_string()),
+                max_concurrent_da_jobs: None,
+                max_concurrent_proving_jobs: Some(4),
+                max_concurrent_settlement_jobs: Some(1),
+                settlement_dry_run: true,
             };
             let service_params = validate_service_params(&service_args);
             assert!(service_params.is_ok());
             let service_params = service_params.unwrap();
             assert_eq!(service_params.max_block_to_process, Some(66645));
             assert_eq!(service_params.min_block_to_process, Some(100));
+            assert_eq!(service_params.max_concurrent_da_jobs, None);
+            assert_eq!(service_params.max_concurrent_proving_jobs, Some(4));
+            assert_eq!(service_params.max_concurrent_settlement_jobs, Some(1));
+            assert!(service_params.settlement_dry_run);
+        }
+
+        #[rstest]
+        #[case(Some(0), None, None)]
+        #[case(None, Some(0), None)]
+        #[case(None, None, Some(0))]
+        fn test_validate_service_params_zero_concurrency(
+            #[case] max_concurrent_da_jobs: Option<usize>,
+            #[case] max_concurrent_proving_jobs: Option<usize>,
+            #[case] max_concurrent_settlement_jobs: Option<usize>,
+        ) {
+            let service_args: ServiceCliArgs = ServiceCliArgs {
+                max_block_to_process: None,
+                min_block_to_process: None,
+                max_concurrent_da_jobs,
+                max_concurrent_proving_jobs,
+                max_concurrent_settlement_jobs,
+                settlement_dry_run: false,
+            };
+            assert!(validate_service_params(&service_args).is_err());
         }
     }
 }
@@ -9,4 +9,24 @@ pub struct ServiceCliArgs {
     /// The minimum block to process.
     #[arg(env = "MADARA_ORCHESTRATOR_MIN_BLOCK_NO_TO_PROCESS", long)]
     pub min_block_to_process: Option<String>,
+
+    /// The maximum number of DA jobs in-flight against the DA layer at the same time. Must be greater than 0.
+    #[arg(env = "MADARA_ORCHESTRATOR_MAX_CONCURRENT_DA_JOBS", long)]
+    pub max_concurrent_da_jobs: Option<usize>,
+
+    /// The maximum number of proving and proof registration jobs in-flight against the prover at the same time. Must
+    /// be greater than 0.
+    #[arg(env = "MADARA_ORCHESTRATOR_MAX_CONCURRENT_PROVING_JOBS", long)]
+    pub max_concurrent_proving_jobs: Option<usize>,
+
+    /// The maximum number of state update jobs in-flight against the settlement layer at the same time. Must be
+    /// greater than 0.
+    #[arg(env = "MADARA_ORCHESTRATOR_MAX_CONCURRENT_SETTLEMENT_JOBS", long)]
+    pub max_concurrent_settlement_jobs: Option<usize>,
+
+    /// Simulate the state updates instead of sending them to the settlement layer. The estimated gas and calldata
+    /// size of each block are recorded in the job metadata. The settlement layer does not advance in this mode, so
+    /// the jobs end as simulated, and each job simulates the blocks of the previous ones again before its own.
+    #[arg(env = "MADARA_ORCHESTRATOR_SETTLEMENT_DRY_RUN", long)]
+    pub settlement_dry_run: bool,
 }
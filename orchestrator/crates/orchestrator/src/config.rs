@@ -35,6 +35,7 @@ use crate::data_storage::aws_s3::AWSS3;
 use crate::data_storage::DataStorage;
 use crate::database::mongodb::MongoDb;
 use crate::database::Database;
+use crate::jobs::concurrency::JobConcurrencyLimiter;
 use crate::queue::sqs::SqsQueue;
 use crate::queue::QueueProvider;
 use crate::routes::ServerParams;
@@ -60,12 +61,18 @@ pub struct Config {
     storage: Box<dyn DataStorage>,
     /// Alerts client
     alerts: Box<dyn Alerts>,
+    /// Limits the jobs in-flight against each external dependency
+    job_concurrency_limiter: JobConcurrencyLimiter,
 }
 
 #[derive(Debug, Clone)]
 pub struct ServiceParams {
     pub max_block_to_process: Option<u64>,
     pub min_block_to_process: Option<u64>,
+    pub max_concurrent_da_jobs: Option<usize>,
+    pub max_concurrent_proving_jobs: Option<usize>,
+    pub max_concurrent_settlement_jobs: Option<usize>,
+    pub settlement_dry_run: bool,
 }
 
 pub struct OrchestratorParams {
@@ -189,6 +196,7 @@ impl Config {
         storage: Box<dyn DataStorage>,
         alerts: Box<dyn Alerts>,
     ) -> Self {
+        let job_concurrency_limiter = JobConcurrencyLimiter::from_service_params(&orchestrator_params.service_config);
         Self {
             orchestrator_params,
             starknet_client,
@@ -199,6 +207,7 @@ impl Config {
             queue,
             storage,
             alerts,
+            job_concurrency_limiter,
         }
     }
 
@@ -262,6 +271,11 @@ impl Config {
         self.alerts.as_ref()
     }
 
+    /// Returns the job concurrency limiter
+    pub fn job_concurrency_limiter(&self) -> &JobConcurrencyLimiter {
+        &self.job_concurrency_limiter
+    }
+
     /// Returns the snos proof layout
     pub fn snos_layout_name(&self) -> &LayoutName {
         &self.orchestrator_params.snos_layout_name
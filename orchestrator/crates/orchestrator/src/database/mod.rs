@@ -4,7 +4,8 @@ use color_eyre::Result;
 use mockall::automock;
 use uuid::Uuid;
 
-use crate::jobs::types::{JobItem, JobStatus, JobType};
+use crate::jobs::internal_id::InternalId;
+use crate::jobs::types::{JobItem, JobStatus, JobTransition, JobType};
 use crate::jobs::JobError;
 
 /// MongoDB
@@ -27,7 +28,12 @@ pub trait Database: Send + Sync {
     async fn create_job(&self, job: JobItem) -> Result<JobItem, JobError>;
     async fn get_job_by_id(&self, id: Uuid) -> Result<Option<JobItem>>;
     async fn get_job_by_internal_id_and_type(&self, internal_id: &str, job_type: &JobType) -> Result<Option<JobItem>>;
+    /// Returns the jobs of type `job_type` which cover at least one of the blocks of `internal_id`, excluding the job
+    /// with exactly this internal id.
+    async fn get_jobs_overlapping_blocks(&self, job_type: &JobType, internal_id: &InternalId) -> Result<Vec<JobItem>>;
     async fn update_job(&self, current_job: &JobItem, updates: crate::jobs::types::JobItemUpdates) -> Result<JobItem>;
+    /// Returns the status transitions of the job, oldest first, or `None` if the job does not exist.
+    async fn get_job_history(&self, id: Uuid) -> Result<Option<Vec<JobTransition>>>;
     async fn get_latest_job_by_type(&self, job_type: JobType) -> Result<Option<JobItem>>;
     async fn get_jobs_without_successor(
         &self,
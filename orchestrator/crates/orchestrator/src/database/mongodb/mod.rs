@@ -13,12 +13,14 @@ use mongodb::options::{
 };
 use mongodb::{bson, Client, Collection};
 use opentelemetry::KeyValue;
+use serde::Deserialize;
 use url::Url;
 use utils::ToDocument;
 use uuid::Uuid;
 
 use crate::database::Database;
-use crate::jobs::types::{JobItem, JobItemUpdates, JobStatus, JobType};
+use crate::jobs::internal_id::InternalId;
+use crate::jobs::types::{JobItem, JobItemUpdates, JobStatus, JobTransition, JobType};
 use crate::jobs::JobError;
 use crate::metrics::ORCHESTRATOR_METRICS;
 
@@ -65,6 +67,18 @@ impl MongoDb {
     fn get_job_collection(&self) -> Collection<JobItem> {
         self.client.database(&self.database_name).collection("jobs")
     }
+
+    /// The job history is embedded in the job documents.
+    fn get_job_history_collection(&self) -> Collection<JobHistory> {
+        self.client.database(&self.database_name).collection("jobs")
+    }
+}
+
+/// Projection of a job document on its history. Jobs created before the history was recorded have no history field.
+#[derive(Deserialize)]
+struct JobHistory {
+    #[serde(default)]
+    history: Vec<JobTransition>,
 }
 
 #[async_trait]
@@ -74,7 +88,9 @@ impl Database for MongoDb {
         let start = Instant::now();
         let options = UpdateOptions::builder().upsert(true).build();
 
-        let updates = job.to_document().map_err(|e| JobError::Other(e.into()))?;
+        let mut updates = job.to_document().map_err(|e| JobError::Other(e.into()))?;
+        let created = JobTransition::created(&job).to_document().map_err(|e| JobError::Other(e.into()))?;
+        updates.insert("history", vec![Bson::Document(created)]);
         let job_type =
             updates.get("job_type").ok_or(eyre!("Job type not found")).map_err(|e| JobError::Other(e.into()))?;
         let internal_id =
@@ -134,6 +150,41 @@ impl Database for MongoDb {
         Ok(self.get_job_collection().find_one(filter, None).await?)
     }
 
+    #[tracing::instrument(skip(self), fields(function_type = "db_call"), ret, err)]
+    async fn get_jobs_overlapping_blocks(&self, job_type: &JobType, internal_id: &InternalId) -> Result<Vec<JobItem>> {
+        let start = Instant::now();
+        // Internal ids are stored as `<block>` or `<first block>-<last block>`. Ids which are not block numbers
+        // evaluate to null bounds and never overlap.
+        let bound = |index: i32| {
+            doc! {
+                "$convert": {
+                    "input": { "$arrayElemAt": [{ "$split": ["$internal_id", "-"] }, index] },
+                    "to": "long",
+                    "onError": Bson::Null,
+                    "onNull": Bson::Null,
+                }
+            }
+        };
+        let filter = doc! {
+            "job_type": mongodb::bson::to_bson(job_type)?,
+            "internal_id": { "$ne": internal_id.to_string() },
+            "$expr": {
+                "$and": [
+                    { "$ne": [bound(0), Bson::Null] },
+                    { "$lte": [bound(0), internal_id.last_block() as i64] },
+                    { "$gte": [bound(-1), internal_id.first_block() as i64] },
+                ]
+            }
+        };
+
+        let jobs: Vec<JobItem> = self.get_job_collection().find(filter, None).await?.try_collect().await?;
+        tracing::debug!(job_type = ?job_type, internal_id = %internal_id, count = jobs.len(), category = "db_call", "Fetched jobs overlapping blocks");
+        let attributes = [KeyValue::new("db_operation_name", "get_jobs_overlapping_blocks")];
+        let duration = start.elapsed();
+        ORCHESTRATOR_METRICS.db_calls_response_time.record(duration.as_secs_f64(), &attributes);
+        Ok(jobs)
+    }
+
     #[tracing::instrument(skip(self), fields(function_type = "db_call"), ret, err)]
     async fn update_job(&self, current_job: &JobItem, updates: JobItemUpdates) -> Result<JobItem> {
         let start = Instant::now();
@@ -144,6 +195,8 @@ impl Database for MongoDb {
         };
         let options = FindOneAndUpdateOptions::builder().upsert(false).return_document(ReturnDocument::After).build();
 
+        let now = Utc::now().round_subsecs(0);
+        let transition = JobTransition::from_updates(current_job, &updates, now);
         let mut updates = updates.to_document()?;
 
         // remove null values from the updates
@@ -161,11 +214,15 @@ impl Database for MongoDb {
 
         // Add additional fields that are always updated
         non_null_updates.insert("version", Bson::Int32(current_job.version + 1));
-        non_null_updates.insert("updated_at", Bson::DateTime(Utc::now().round_subsecs(0).into()));
+        non_null_updates.insert("updated_at", Bson::DateTime(now.into()));
 
-        let update = doc! {
+        let mut update = doc! {
             "$set": non_null_updates
         };
+        // The history is append-only: status changes are pushed along with the update itself.
+        if let Some(transition) = transition {
+            update.insert("$push", doc! { "history": bson::to_bson(&transition)? });
+        }
 
         let result = self.get_job_collection().find_one_and_update(filter, update, options).await?;
         match result {
@@ -183,6 +240,22 @@ impl Database for MongoDb {
         }
     }
 
+    #[tracing::instrument(skip(self), fields(function_type = "db_call"), ret, err)]
+    async fn get_job_history(&self, id: Uuid) -> Result<Option<Vec<JobTransition>>> {
+        let start = Instant::now();
+        let filter = doc! {
+            "id": id
+        };
+        let options = FindOneOptions::builder().projection(doc! { "_id": 0, "history": 1 }).build();
+
+        let history = self.get_job_history_collection().find_one(filter, options).await?;
+        tracing::debug!(job_id = %id, category = "db_call", "Fetched job history");
+        let attributes = [KeyValue::new("db_operation_name", "get_job_history")];
+        let duration = start.elapsed();
+        ORCHESTRATOR_METRICS.db_calls_response_time.record(duration.as_secs_f64(), &attributes);
+        Ok(history.map(|history| history.history))
+    }
+
     #[tracing::instrument(skip(self), fields(function_type = "db_call"), ret, err)]
     async fn get_latest_job_by_type(&self, job_type: JobType) -> Result<Option<JobItem>> {
         let start = Instant::now();
@@ -194,7 +267,10 @@ impl Database for MongoDb {
             },
             doc! {
                 "$addFields": {
-                    "numeric_internal_id": { "$toLong": "$internal_id" }
+                    // the internal id of a job about a range of blocks starts with its first block
+                    "numeric_internal_id": {
+                        "$toLong": { "$arrayElemAt": [{ "$split": ["$internal_id", "-"] }, 0] }
+                    }
                 }
             },
             doc! {
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::config::ServiceParams;
+use crate::jobs::types::JobType;
+
+/// An external service that jobs talk to while being processed or verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalDependency {
+    /// The DA layer
+    Da,
+    /// The proving service
+    Prover,
+    /// The settlement layer (L1)
+    Settlement,
+}
+
+impl ExternalDependency {
+    /// Returns the external service the job type depends on, if any.
+    pub fn for_job_type(job_type: &JobType) -> Option<Self> {
+        match job_type {
+            JobType::SnosRun => None,
+            JobType::DataSubmission => Some(Self::Da),
+            JobType::ProofCreation | JobType::ProofRegistration => Some(Self::Prover),
+            JobType::StateTransition => Some(Self::Settlement),
+        }
+    }
+}
+
+impl fmt::Display for ExternalDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Da => write!(f, "DA layer"),
+            Self::Prover => write!(f, "prover"),
+            Self::Settlement => write!(f, "settlement layer"),
+        }
+    }
+}
+
+/// Limits the number of jobs which can be in-flight against each external dependency at the same time, regardless
+/// of how many jobs are waiting in the queues. A job holds a permit while its handler is processing or verifying it.
+///
+/// A dependency without a limit is not throttled.
+#[derive(Debug, Default)]
+pub struct JobConcurrencyLimiter {
+    da: Option<Semaphore>,
+    prover: Option<Semaphore>,
+    settlement: Option<Semaphore>,
+}
+
+impl JobConcurrencyLimiter {
+    /// The limits are validated to be greater than 0 when the service params are parsed, a limit of 0 would block the
+    /// jobs of the dependency forever.
+    pub fn new(da: Option<usize>, prover: Option<usize>, settlement: Option<usize>) -> Self {
+        Self {
+            da: da.map(Semaphore::new),
+            prover: prover.map(Semaphore::new),
+            settlement: settlement.map(Semaphore::new),
+        }
+    }
+
+    pub fn from_service_params(params: &ServiceParams) -> Self {
+        Self::new(
+            params.max_concurrent_da_jobs,
+            params.max_concurrent_proving_jobs,
+            params.max_concurrent_settlement_jobs,
+        )
+    }
+
+    fn semaphore(&self, dependency: ExternalDependency) -> Option<&Semaphore> {
+        match dependency {
+            ExternalDependency::Da => self.da.as_ref(),
+            ExternalDependency::Prover => self.prover.as_ref(),
+            ExternalDependency::Settlement => self.settlement.as_ref(),
+        }
+    }
+
+    /// Waits until the job type can talk to its external dependency. The returned permit must be kept alive for as
+    /// long as the job handler is running. Returns `None` when the job type is not throttled.
+    pub async fn acquire(&self, job_type: &JobType) -> Option<SemaphorePermit<'_>> {
+        let dependency = ExternalDependency::for_job_type(job_type)?;
+        let semaphore = self.semaphore(dependency)?;
+        if semaphore.available_permits() == 0 {
+            tracing::debug!(job_type = ?job_type, dependency = %dependency, "Concurrency limit reached, waiting for a permit");
+        }
+        // The semaphores are never closed.
+        semaphore.acquire().await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limiter_caps_in_flight_jobs() {
+        let limiter = JobConcurrencyLimiter::new(None, Some(2), None);
+
+        let first = limiter.acquire(&JobType::ProofCreation).await;
+        let second = limiter.acquire(&JobType::ProofRegistration).await;
+        assert!(first.is_some() && second.is_some());
+
+        // Prover is saturated.
+        assert!(tokio::time::timeout(Duration::from_millis(50), limiter.acquire(&JobType::ProofCreation))
+            .await
+            .is_err());
+
+        // Other dependencies are not affected.
+        assert!(limiter.acquire(&JobType::DataSubmission).await.is_none());
+        assert!(limiter.acquire(&JobType::SnosRun).await.is_none());
+
+        drop(first);
+        assert!(limiter.acquire(&JobType::ProofCreation).await.is_some());
+    }
+}
@@ -4,6 +4,10 @@ pub const JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY: &str = "blocks_number_
 pub const JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS: &str = "fetch_from_test_data";
 pub const JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX: &str = "attempt_tx_hashes_";
 pub const JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO: &str = "last_failed_block_no";
+pub const JOB_METADATA_STATE_UPDATE_DRY_RUN: &str = "dry_run";
+pub const JOB_METADATA_STATE_UPDATE_DRY_RUN_BLOCK_NUMBERS: &str = "dry_run_block_numbers";
+pub const JOB_METADATA_STATE_UPDATE_DRY_RUN_ESTIMATED_GAS: &str = "dry_run_estimated_gas";
+pub const JOB_METADATA_STATE_UPDATE_DRY_RUN_CALLDATA_SIZE: &str = "dry_run_calldata_size";
 pub const JOB_METADATA_SNOS_BLOCK: &str = "block_number_to_run";
 pub const JOB_METADATA_SNOS_FACT: &str = "snos_fact";
 pub const JOB_METADATA_FAILURE_REASON: &str = "failure_reason";
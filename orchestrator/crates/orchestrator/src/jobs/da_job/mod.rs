@@ -10,7 +10,7 @@ use lazy_static::lazy_static;
 use num_bigint::{BigUint, ToBigUint};
 use num_traits::{Num, Zero};
 use starknet::core::types::{
-    BlockId, ContractStorageDiffItem, DeclaredClassItem, Felt, MaybePendingStateUpdate, StateDiff, StateUpdate,
+    BlockId, ContractStorageDiffItem, Felt, MaybePendingStateUpdate, NonceUpdate, StateDiff, StateUpdate,
 };
 use starknet::providers::Provider;
 use thiserror::Error;
@@ -285,62 +285,66 @@ pub async fn state_update_to_blob_data(
     let mut state_diff = state_update.state_diff;
     refactor_state_update(&mut state_diff);
 
-    let mut blob_data: Vec<Felt> = vec![Felt::from(state_diff.storage_diffs.len())];
+    let known_nonces: HashSet<Felt> = state_diff.nonces.iter().map(|item| item.contract_address).collect();
 
-    let deployed_contracts: HashMap<Felt, Felt> =
-        state_diff.deployed_contracts.into_iter().map(|item| (item.address, item.class_hash)).collect();
-    let replaced_classes: HashMap<Felt, Felt> =
-        state_diff.replaced_classes.into_iter().map(|item| (item.contract_address, item.class_hash)).collect();
-    let mut nonces: HashMap<Felt, Felt> =
-        state_diff.nonces.into_iter().map(|item| (item.contract_address, item.nonce)).collect();
-
-    // sort storage diffs
-    state_diff.storage_diffs.sort_by_key(|diff| diff.address);
-
-    // Loop over storage diffs
-    for ContractStorageDiffItem { address, mut storage_entries } in state_diff.storage_diffs.into_iter() {
-        let class_flag = deployed_contracts.get(&address).or_else(|| replaced_classes.get(&address));
-
-        let mut nonce = nonces.remove(&address);
-
-        // @note: if nonce is null and there is some len of writes, make an api call to get the contract
-        // nonce for the block
-
-        if nonce.is_none() && !storage_entries.is_empty() && address != Felt::ONE {
-            let get_current_nonce_result = config
-                .starknet_client()
-                .get_nonce(BlockId::Number(block_no), address)
-                .await
-                .wrap_err("Failed to get nonce ".to_string())?;
-
-            nonce = Some(get_current_nonce_result);
-        }
-        let da_word = da_word(class_flag.is_some(), nonce, storage_entries.len() as u64)?;
-        blob_data.push(address);
-        blob_data.push(da_word);
-
-        if let Some(class_hash) = class_flag {
-            blob_data.push(*class_hash);
-        }
-
-        storage_entries.sort_by_key(|entry| entry.key);
-        for entry in storage_entries {
-            blob_data.push(entry.key);
-            blob_data.push(entry.value);
+    // The onchain data holds the nonce of every updated contract, including the ones whose nonce did not change.
+    for ContractStorageDiffItem { address, storage_entries } in &state_diff.storage_diffs {
+        if known_nonces.contains(address) || storage_entries.is_empty() || *address == Felt::ONE {
+            continue;
         }
+        let nonce = config
+            .starknet_client()
+            .get_nonce(BlockId::Number(block_no), address)
+            .await
+            .wrap_err("Failed to get nonce ".to_string())?;
+        state_diff.nonces.push(NonceUpdate { contract_address: *address, nonce });
     }
-    // Handle declared classes
-    blob_data.push(Felt::from(state_diff.declared_classes.len()));
 
-    // sort storage diffs
-    state_diff.declared_classes.sort_by_key(|class| class.class_hash);
+    to_madara_state_diff(state_diff).to_onchain_data().wrap_err("Failed to encode the state diff")
+}
 
-    for DeclaredClassItem { class_hash, compiled_class_hash } in state_diff.declared_classes.into_iter() {
-        blob_data.push(class_hash);
-        blob_data.push(compiled_class_hash);
+fn to_madara_state_diff(state_diff: StateDiff) -> mp_state_update::StateDiff {
+    mp_state_update::StateDiff {
+        storage_diffs: state_diff
+            .storage_diffs
+            .into_iter()
+            .map(|item| mp_state_update::ContractStorageDiffItem {
+                address: item.address,
+                storage_entries: item
+                    .storage_entries
+                    .into_iter()
+                    .map(|entry| mp_state_update::StorageEntry { key: entry.key, value: entry.value })
+                    .collect(),
+            })
+            .collect(),
+        deprecated_declared_classes: state_diff.deprecated_declared_classes,
+        declared_classes: state_diff
+            .declared_classes
+            .into_iter()
+            .map(|item| mp_state_update::DeclaredClassItem {
+                class_hash: item.class_hash,
+                compiled_class_hash: item.compiled_class_hash,
+            })
+            .collect(),
+        deployed_contracts: state_diff
+            .deployed_contracts
+            .into_iter()
+            .map(|item| mp_state_update::DeployedContractItem { address: item.address, class_hash: item.class_hash })
+            .collect(),
+        replaced_classes: state_diff
+            .replaced_classes
+            .into_iter()
+            .map(|item| mp_state_update::ReplacedClassItem {
+                contract_address: item.contract_address,
+                class_hash: item.class_hash,
+            })
+            .collect(),
+        nonces: state_diff
+            .nonces
+            .into_iter()
+            .map(|item| mp_state_update::NonceUpdate { contract_address: item.contract_address, nonce: item.nonce })
+            .collect(),
     }
-
-    Ok(blob_data)
 }
 
 /// To store the blob data using the storage client with path <block_number>/blob_data.txt
@@ -357,48 +361,6 @@ async fn store_blob_data(blob_data: Vec<BigUint>, block_number: u64, config: Arc
     Ok(())
 }
 
-/// DA word encoding:
-/// |---padding---|---class flag---|---new nonce---|---num changes---|
-///     127 bits        1 bit           64 bits          64 bits
-fn da_word(class_flag: bool, nonce_change: Option<Felt>, num_changes: u64) -> Result<Felt, JobError> {
-    // padding of 127 bits
-    let mut binary_string = "0".repeat(127);
-
-    // class flag of one bit
-    if class_flag {
-        binary_string += "1"
-    } else {
-        binary_string += "0"
-    }
-
-    // checking for nonce here
-    if let Some(new_nonce) = nonce_change {
-        let bytes: [u8; 32] = new_nonce.to_bytes_be();
-        let biguint = BigUint::from_bytes_be(&bytes);
-        let binary_string_local = format!("{:b}", biguint);
-        let padded_binary_string = format!("{:0>64}", binary_string_local);
-        binary_string += &padded_binary_string;
-    } else {
-        let binary_string_local = "0".repeat(64);
-        binary_string += &binary_string_local;
-    }
-
-    let binary_representation = format!("{:b}", num_changes);
-    let padded_binary_string = format!("{:0>64}", binary_representation);
-    binary_string += &padded_binary_string;
-
-    let biguint = BigUint::from_str_radix(binary_string.as_str(), 2)
-        .wrap_err("Failed to convert binary string to BigUint")
-        .map_err(|e| JobError::Other(OtherError(e)))?;
-
-    // Now convert the BigUint to a decimal string
-    let decimal_string = biguint.to_str_radix(10);
-
-    Felt::from_dec_str(&decimal_string)
-        .wrap_err("Failed to convert decimal string to FieldElement")
-        .map_err(|e| JobError::Other(OtherError(e)))
-}
-
 fn refactor_state_update(state_update: &mut StateDiff) {
     let existing_storage: HashSet<_> = state_update.storage_diffs.iter().map(|item| item.address).collect();
 
@@ -428,6 +390,7 @@ pub mod test {
     use httpmock::prelude::*;
     use majin_blob_core::blob;
     use majin_blob_types::serde;
+    use mp_state_update::onchain_data::da_word;
     use orchestrator_da_client_interface::MockDaClient;
     use rstest::rstest;
     use serde_json::json;
@@ -438,7 +401,7 @@ pub mod test {
     use starknet::providers::JsonRpcClient;
     use url::Url;
 
-    use crate::jobs::da_job::{da_word, refactor_state_update};
+    use crate::jobs::da_job::refactor_state_update;
 
     /// Tests `da_word` function with various inputs for class flag, new nonce, and number of
     /// changes. Verifies that `da_word` produces the correct Felt based on the provided
@@ -456,8 +419,7 @@ pub mod test {
         #[case] num_changes: u64,
         #[case] expected: String,
     ) {
-        let new_nonce = if new_nonce > 0 { Some(Felt::from(new_nonce)) } else { None };
-        let da_word = da_word(class_flag, new_nonce, num_changes).expect("Failed to create DA word");
+        let da_word = da_word(class_flag, new_nonce, num_changes);
         let expected = Felt::from_dec_str(expected.as_str()).unwrap();
         assert_eq!(da_word, expected);
     }
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Separator between the first and last block of a range in its string representation.
+const RANGE_SEPARATOR: char = '-';
+
+/// The blocks a job is about. This is stored as the `internal_id` of the job: a single block is formatted as its
+/// number (`"42"`) and a range of blocks as its inclusive bounds (`"42-50"`).
+///
+/// The representation is canonical: a range of a single block is always a [`InternalId::Block`], so that two jobs
+/// about the same blocks always have the same internal id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InternalId {
+    /// A single block.
+    Block(u64),
+    /// An inclusive range of blocks, `start < end`.
+    Range { start: u64, end: u64 },
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum InternalIdError {
+    #[error("Invalid internal id {0:?}: expected a block number or a block range")]
+    Malformed(String),
+    #[error("Invalid internal id range {start}-{end}: the first block is after the last block")]
+    EmptyRange { start: u64, end: u64 },
+}
+
+impl InternalId {
+    /// Creates the internal id of an inclusive range of blocks.
+    pub fn range(start: u64, end: u64) -> Result<Self, InternalIdError> {
+        match start.cmp(&end) {
+            std::cmp::Ordering::Less => Ok(Self::Range { start, end }),
+            std::cmp::Ordering::Equal => Ok(Self::Block(start)),
+            std::cmp::Ordering::Greater => Err(InternalIdError::EmptyRange { start, end }),
+        }
+    }
+
+    pub fn first_block(&self) -> u64 {
+        match *self {
+            Self::Block(block) => block,
+            Self::Range { start, .. } => start,
+        }
+    }
+
+    pub fn last_block(&self) -> u64 {
+        match *self {
+            Self::Block(block) => block,
+            Self::Range { end, .. } => end,
+        }
+    }
+
+    pub fn blocks(&self) -> RangeInclusive<u64> {
+        self.first_block()..=self.last_block()
+    }
+
+    /// Returns true when both ids have at least one block in common.
+    pub fn overlaps(&self, other: &InternalId) -> bool {
+        self.first_block() <= other.last_block() && other.first_block() <= self.last_block()
+    }
+}
+
+impl fmt::Display for InternalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Block(block) => write!(f, "{block}"),
+            Self::Range { start, end } => write!(f, "{start}{RANGE_SEPARATOR}{end}"),
+        }
+    }
+}
+
+impl FromStr for InternalId {
+    type Err = InternalIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_block = |block: &str| {
+            // Reject signs and surrounding whitespace so that the formatting round-trips.
+            if block.is_empty() || !block.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(InternalIdError::Malformed(s.to_string()));
+            }
+            block.parse::<u64>().map_err(|_| InternalIdError::Malformed(s.to_string()))
+        };
+
+        match s.split_once(RANGE_SEPARATOR) {
+            None => parse_block(s).map(Self::Block),
+            Some((start, end)) => Self::range(parse_block(start)?, parse_block(end)?),
+        }
+    }
+}
+
+impl From<u64> for InternalId {
+    fn from(block: u64) -> Self {
+        Self::Block(block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("0", InternalId::Block(0))]
+    #[case("42", InternalId::Block(42))]
+    #[case("42-50", InternalId::Range { start: 42, end: 50 })]
+    #[case("42-42", InternalId::Block(42))]
+    fn test_parse_internal_id(#[case] s: &str, #[case] expected: InternalId) {
+        assert_eq!(s.parse::<InternalId>(), Ok(expected));
+        assert_eq!(expected.to_string().parse::<InternalId>(), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("-1")]
+    #[case("+1")]
+    #[case(" 1")]
+    #[case("1-")]
+    #[case("1-2-3")]
+    #[case("0x10")]
+    fn test_parse_malformed_internal_id(#[case] s: &str) {
+        assert_eq!(s.parse::<InternalId>(), Err(InternalIdError::Malformed(s.to_string())));
+    }
+
+    #[test]
+    fn test_parse_empty_range() {
+        assert_eq!("50-42".parse::<InternalId>(), Err(InternalIdError::EmptyRange { start: 50, end: 42 }));
+    }
+
+    #[rstest]
+    #[case(InternalId::Block(5), InternalId::Block(5), true)]
+    #[case(InternalId::Block(5), InternalId::Block(6), false)]
+    #[case(InternalId::Block(5), InternalId::Range { start: 0, end: 5 }, true)]
+    #[case(InternalId::Block(6), InternalId::Range { start: 0, end: 5 }, false)]
+    #[case(InternalId::Range { start: 0, end: 5 }, InternalId::Range { start: 5, end: 10 }, true)]
+    #[case(InternalId::Range { start: 0, end: 4 }, InternalId::Range { start: 5, end: 10 }, false)]
+    #[case(InternalId::Range { start: 2, end: 3 }, InternalId::Range { start: 0, end: 10 }, true)]
+    fn test_internal_id_overlaps(#[case] a: InternalId, #[case] b: InternalId, #[case] expected: bool) {
+        assert_eq!(a.overlaps(&b), expected);
+        assert_eq!(b.overlaps(&a), expected);
+    }
+}
@@ -23,15 +23,18 @@ use uuid::Uuid;
 
 use crate::config::Config;
 use crate::jobs::constants::{JOB_PROCESS_ATTEMPT_METADATA_KEY, JOB_VERIFICATION_ATTEMPT_METADATA_KEY};
+use crate::jobs::internal_id::{InternalId, InternalIdError};
 #[double]
 use crate::jobs::job_handler_factory::factory;
 use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
 use crate::metrics::ORCHESTRATOR_METRICS;
 use crate::queue::job_queue::{add_job_to_process_queue, add_job_to_verification_queue, ConsumptionError};
 
+pub mod concurrency;
 pub mod constants;
 pub mod conversion;
 pub mod da_job;
+pub mod internal_id;
 pub mod job_handler_factory;
 pub mod proving_job;
 pub mod register_proof_job;
@@ -48,6 +51,14 @@ pub enum JobError {
     #[error("Job already exists for internal_id {internal_id:?} and job_type {job_type:?}. Skipping!")]
     JobAlreadyExists { internal_id: String, job_type: JobType },
 
+    #[error("{0}")]
+    InvalidInternalId(#[from] InternalIdError),
+
+    #[error(
+        "Job with internal_id {internal_id:?} and job_type {job_type:?} overlaps with existing job {existing_internal_id:?}"
+    )]
+    OverlappingJob { internal_id: String, existing_internal_id: String, job_type: JobType },
+
     #[error("Invalid status {job_status:?} for job with id {id:?}. Cannot process.")]
     InvalidStatus { id: Uuid, job_status: JobStatus },
 
@@ -173,6 +184,17 @@ pub async fn create_job(
         return Ok(());
     }
 
+    // jobs of the same type must never cover the same block twice
+    let blocks: InternalId = internal_id.parse()?;
+    let overlapping_jobs = config
+        .database()
+        .get_jobs_overlapping_blocks(&job_type, &blocks)
+        .await
+        .map_err(|e| JobError::Other(OtherError(e)))?;
+    if let Some(existing_job) = overlapping_jobs.into_iter().next() {
+        return Err(JobError::OverlappingJob { internal_id, existing_internal_id: existing_job.internal_id, job_type });
+    }
+
     let job_handler = factory::get_job_handler(&job_type).await;
     let job_item = job_handler.create_job(config.clone(), internal_id.clone(), metadata).await?;
     config.database().create_job(job_item.clone()).await?;
@@ -186,7 +208,7 @@ pub async fn create_job(
 
     tracing::info!(log_type = "completed", category = "general", function_type = "create_job", block_no = %internal_id, "General create job completed for block");
     let duration = start.elapsed();
-    ORCHESTRATOR_METRICS.block_gauge.record(parse_string(&internal_id)?, &attributes);
+    ORCHESTRATOR_METRICS.block_gauge.record(blocks.last_block() as f64, &attributes);
     ORCHESTRATOR_METRICS.successful_job_operations.add(1.0, &attributes);
     ORCHESTRATOR_METRICS.jobs_response_time.record(duration.as_secs_f64(), &attributes);
     Ok(())
@@ -232,6 +254,7 @@ pub async fn process_job(id: Uuid, config: Arc<Config>) -> Result<(), JobError>
 
     tracing::debug!(job_id = ?id, job_type = ?job.job_type, "Getting job handler");
     let job_handler = factory::get_job_handler(&job.job_type).await;
+    let permit = config.job_concurrency_limiter().acquire(&job.job_type).await;
     let external_id = match AssertUnwindSafe(job_handler.process_job(config.clone(), &mut job)).catch_unwind().await {
         Ok(Ok(external_id)) => {
             tracing::debug!(job_id = ?id, "Successfully processed job");
@@ -263,6 +286,7 @@ pub async fn process_job(id: Uuid, config: Arc<Config>) -> Result<(), JobError>
             .await;
         }
     };
+    drop(permit);
     tracing::debug!(job_id = ?id, "Incrementing process attempt count in metadata");
     let metadata = increment_key_in_metadata(&job.metadata, JOB_PROCESS_ATTEMPT_METADATA_KEY)?;
 
@@ -345,7 +369,9 @@ pub async fn verify_job(id: Uuid, config: Arc<Config>) -> Result<(), JobError> {
 
     let job_handler = factory::get_job_handler(&job.job_type).await;
     tracing::debug!(job_id = ?id, "Verifying job with handler");
+    let permit = config.job_concurrency_limiter().acquire(&job.job_type).await;
     let verification_status = job_handler.verify_job(config.clone(), &mut job).await?;
+    drop(permit);
     tracing::Span::current().record("verification_status", format!("{:?}", &verification_status));
 
     let mut attributes = vec![
@@ -384,6 +410,23 @@ pub async fn verify_job(id: Uuid, config: Arc<Config>) -> Result<(), JobError> {
                 })?;
             operation_job_status = Some(JobStatus::Completed);
         }
+        JobVerificationStatus::Simulated => {
+            tracing::info!(job_id = ?id, "Job was a dry run, marking it as simulated");
+            let mut metadata = job.metadata.clone();
+            metadata.remove(JOB_METADATA_PROCESSING_COMPLETED_AT);
+            config
+                .database()
+                .update_job(
+                    &job,
+                    JobItemUpdates::new().update_metadata(metadata).update_status(JobStatus::Simulated).build(),
+                )
+                .await
+                .map_err(|e| {
+                    tracing::error!(job_id = ?id, error = ?e, "Failed to update job status to Simulated");
+                    JobError::Other(OtherError(e))
+                })?;
+            operation_job_status = Some(JobStatus::Simulated);
+        }
         JobVerificationStatus::Rejected(e) => {
             tracing::warn!(job_id = ?id, error = ?e, "Job verification rejected");
             let mut new_job_metadata = job.metadata.clone();
@@ -512,7 +555,7 @@ fn register_block_gauge(
                 .map_err(|e| JobError::Other(OtherError::from(format!("Could not parse string: {e}"))))?,
         )
     } else {
-        parse_string(internal_id)
+        Ok(internal_id.parse::<InternalId>()?.last_block() as f64)
     }?;
 
     ORCHESTRATOR_METRICS.block_gauge.record(block_number, attributes);
@@ -520,7 +563,7 @@ fn register_block_gauge(
 }
 
 async fn move_job_to_failed(job: &JobItem, config: Arc<Config>, reason: String) -> Result<(), JobError> {
-    if job.status == JobStatus::Completed {
+    if matches!(job.status, JobStatus::Completed | JobStatus::Simulated) {
         tracing::error!(job_id = ?job.id, job_status = ?job.status, "Invalid state exists on DL queue");
         return Ok(());
     }
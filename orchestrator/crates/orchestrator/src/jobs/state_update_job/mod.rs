@@ -8,20 +8,22 @@ use async_trait::async_trait;
 use cairo_vm::Felt252;
 use chrono::{SubsecRound, Utc};
 use color_eyre::eyre::eyre;
-use orchestrator_settlement_client_interface::SettlementVerificationStatus;
+use orchestrator_settlement_client_interface::{BlobStateUpdate, CalldataStateUpdate, SettlementVerificationStatus};
 use starknet_os::io::output::StarknetOsOutput;
 use thiserror::Error;
 use uuid::Uuid;
 
 use super::constants::{
-    JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO,
+    JOB_METADATA_STATE_UPDATE_ATTEMPT_PREFIX, JOB_METADATA_STATE_UPDATE_DRY_RUN,
+    JOB_METADATA_STATE_UPDATE_DRY_RUN_BLOCK_NUMBERS, JOB_METADATA_STATE_UPDATE_DRY_RUN_CALLDATA_SIZE,
+    JOB_METADATA_STATE_UPDATE_DRY_RUN_ESTIMATED_GAS, JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO,
     JOB_PROCESS_ATTEMPT_METADATA_KEY,
 };
 use super::{JobError, OtherError};
 use crate::config::Config;
 use crate::constants::{PROGRAM_OUTPUT_FILE_NAME, SNOS_OUTPUT_FILE_NAME};
 use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
-use crate::jobs::state_update_job::utils::fetch_blob_data_for_block;
+use crate::jobs::state_update_job::utils::{fetch_blob_data_for_block, fetch_onchain_data_hash_and_size_for_block};
 use crate::jobs::types::{JobItem, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::Job;
 
@@ -66,6 +68,9 @@ pub enum StateUpdateError {
     #[error("Block #{block_no:?} - SNOS error, [use_kzg_da] should be either 0 or 1.")]
     UseKZGDaError { block_no: u64 },
 
+    #[error("Block #{block_no:?} - its DA mode differs from the previous blocks, they cannot be simulated together.")]
+    DryRunMixedDa { block_no: u64 },
+
     #[error("Other error: {0}")]
     Other(#[from] OtherError),
 }
@@ -118,7 +123,8 @@ impl Job for StateUpdateJob {
         // Read the metadata to get the blocks for which state update will be performed.
         // We assume that blocks nbrs are formatted as follow: "2,3,4,5,6".
         let mut block_numbers = self.get_block_numbers_from_metadata(job)?;
-        self.validate_block_numbers(config.clone(), &block_numbers).await?;
+        let dry_run = config.service_config().settlement_dry_run;
+        let last_settled_block = self.validate_block_numbers(config.clone(), &block_numbers, dry_run).await?;
 
         if let Some(last_failed_block) = job.metadata.get(JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO) {
             let last_failed_block =
@@ -126,6 +132,13 @@ impl Job for StateUpdateJob {
             block_numbers = block_numbers.into_iter().filter(|&block| block >= last_failed_block).collect::<Vec<u64>>();
         }
 
+        if dry_run {
+            self.simulate_state_update_for_blocks(config.clone(), job, last_settled_block, &block_numbers).await?;
+            let val = block_numbers.last().ok_or_else(|| StateUpdateError::LastNumberReturnedError)?;
+            tracing::info!(log_type = "completed", category = "state_update", function_type = "process_job", job_id = %job.id,  block_no = %internal_id, last_simulated_block = %val, "State update job simulated successfully.");
+            return Ok(val.to_string());
+        }
+
         let mut nonce = config.settlement_client().get_nonce().await.map_err(|e| JobError::Other(OtherError(e)))?;
         let mut sent_tx_hashes: Vec<String> = Vec::with_capacity(block_numbers.len());
         for block_no in block_numbers.iter() {
@@ -169,6 +182,12 @@ impl Job for StateUpdateJob {
             .ok_or_else(|| StateUpdateError::AttemptNumberNotFound)?;
         tracing::debug!(job_id = %job.internal_id, attempt_no = %attempt_no, "Retrieved attempt number");
 
+        // Nothing was sent to the settlement layer, so the job must not be completed. It ends as simulated.
+        if job.metadata.get(JOB_METADATA_STATE_UPDATE_DRY_RUN).is_some_and(|dry_run| dry_run == "true") {
+            tracing::info!(log_type = "completed", category = "state_update", function_type = "verify_job", job_id = %job.id,  block_no = %internal_id, "State update job was a dry run, nothing to verify.");
+            return Ok(JobVerificationStatus::Simulated);
+        }
+
         // We are doing attempt_no - 1 because the attempt number is increased in the
         // global process job function and the transaction hash is stored with attempt
         // number : 0
@@ -284,8 +303,16 @@ impl StateUpdateJob {
         Ok(block_numbers)
     }
 
-    /// Validate that the list of block numbers to process is valid.
-    async fn validate_block_numbers(&self, config: Arc<Config>, block_numbers: &[u64]) -> Result<(), JobError> {
+    /// Validate that the list of block numbers to process is valid, and returns the last settled block.
+    ///
+    /// In a settlement dry run, the blocks simulated by the previous jobs were not settled: there can be a gap between
+    /// the last settled block and the first block to settle.
+    async fn validate_block_numbers(
+        &self,
+        config: Arc<Config>,
+        block_numbers: &[u64],
+        dry_run: bool,
+    ) -> Result<u64, JobError> {
         if block_numbers.is_empty() {
             Err(StateUpdateError::BlockNumberNotFound)?;
         }
@@ -298,10 +325,15 @@ impl StateUpdateJob {
         // Check for gap between the last settled block and the first block to settle
         let last_settled_block: u64 =
             config.settlement_client().get_last_settled_block().await.map_err(|e| JobError::Other(OtherError(e)))?;
-        if last_settled_block + 1 != block_numbers[0] {
+        let has_gap = if dry_run {
+            last_settled_block + 1 > block_numbers[0]
+        } else {
+            last_settled_block + 1 != block_numbers[0]
+        };
+        if has_gap {
             Err(StateUpdateError::GapBetweenFirstAndLastBlock)?;
         }
-        Ok(())
+        Ok(last_settled_block)
     }
 
     /// Update the state for the corresponding block using the settlement layer.
@@ -335,6 +367,76 @@ impl StateUpdateJob {
         Ok(last_tx_hash_executed)
     }
 
+    /// Simulates the state updates of the blocks using the settlement layer, each one on top of the
+    /// previous ones, and records the estimated gas and calldata size of each block in the job
+    /// metadata.
+    ///
+    /// The blocks simulated by the previous jobs of the dry run were not settled, so they are
+    /// simulated again first, from the block after `last_settled_block`.
+    async fn simulate_state_update_for_blocks(
+        &self,
+        config: Arc<Config>,
+        job: &mut JobItem,
+        last_settled_block: u64,
+        block_numbers: &[u64],
+    ) -> Result<(), JobError> {
+        let first_block = block_numbers.first().ok_or(StateUpdateError::EmptyBlockNumberList)?;
+        let unsettled_blocks = last_settled_block + 1..*first_block;
+        let n_unsettled_blocks = unsettled_blocks.clone().count();
+
+        let mut blob_updates = Vec::new();
+        let mut calldata_updates = Vec::new();
+        for block_no in unsettled_blocks.chain(block_numbers.iter().copied()) {
+            let snos = self.fetch_snos_for_block(block_no, config.clone()).await?;
+            let program_output = self.fetch_program_output_for_block(block_no, config.clone()).await?;
+            if snos.use_kzg_da == Felt252::ZERO {
+                if !blob_updates.is_empty() {
+                    Err(StateUpdateError::DryRunMixedDa { block_no })?
+                }
+                let (onchain_data_hash, onchain_data_size) =
+                    fetch_onchain_data_hash_and_size_for_block(block_no, config.clone())
+                        .await
+                        .map_err(|e| JobError::Other(OtherError(e)))?;
+                calldata_updates.push(CalldataStateUpdate { program_output, onchain_data_hash, onchain_data_size });
+            } else if snos.use_kzg_da == Felt252::ONE {
+                if !calldata_updates.is_empty() {
+                    Err(StateUpdateError::DryRunMixedDa { block_no })?
+                }
+                let state_diff = fetch_blob_data_for_block(block_no, config.clone())
+                    .await
+                    .map_err(|e| JobError::Other(OtherError(e)))?;
+                blob_updates.push(BlobStateUpdate { program_output, state_diff });
+            } else {
+                Err(StateUpdateError::UseKZGDaError { block_no })?
+            }
+        }
+
+        let settlement_client = config.settlement_client();
+        let simulations = if calldata_updates.is_empty() {
+            settlement_client.simulate_update_state_with_blobs(blob_updates).await
+        } else {
+            settlement_client.simulate_update_state_calldata(calldata_updates).await
+        }
+        .map_err(|e| JobError::Other(OtherError(eyre!("State update simulation failed: {e}"))))?;
+        let simulations = simulations.get(n_unsettled_blocks..).unwrap_or_default();
+
+        let join = |values: Vec<String>| values.join(",");
+        job.metadata.insert(JOB_METADATA_STATE_UPDATE_DRY_RUN.into(), "true".into());
+        job.metadata.insert(
+            JOB_METADATA_STATE_UPDATE_DRY_RUN_BLOCK_NUMBERS.into(),
+            join(block_numbers.iter().map(u64::to_string).collect()),
+        );
+        job.metadata.insert(
+            JOB_METADATA_STATE_UPDATE_DRY_RUN_ESTIMATED_GAS.into(),
+            join(simulations.iter().map(|simulation| simulation.estimated_gas.to_string()).collect()),
+        );
+        job.metadata.insert(
+            JOB_METADATA_STATE_UPDATE_DRY_RUN_CALLDATA_SIZE.into(),
+            join(simulations.iter().map(|simulation| simulation.calldata_size.to_string()).collect()),
+        );
+        Ok(())
+    }
+
     /// Retrieves the SNOS output for the corresponding block.
     async fn fetch_snos_for_block(&self, block_no: u64, config: Arc<Config>) -> Result<StarknetOsOutput, JobError> {
         let storage_client = config.storage();
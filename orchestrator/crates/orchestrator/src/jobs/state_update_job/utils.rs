@@ -3,12 +3,15 @@ use std::io::{BufRead, Cursor};
 use std::str::FromStr;
 use std::sync::Arc;
 
-use alloy::primitives::U256;
-use color_eyre::eyre::eyre;
+use alloy::primitives::{keccak256, U256};
+use color_eyre::eyre::{eyre, WrapErr};
 use num_bigint::BigUint;
+use starknet::core::types::{BlockId, MaybePendingStateUpdate};
+use starknet::providers::Provider;
 
 use crate::config::Config;
 use crate::constants::{BLOB_DATA_FILE_NAME, PROGRAM_OUTPUT_FILE_NAME};
+use crate::jobs::da_job::state_update_to_blob_data;
 
 /// Fetching the blob data (stored in remote storage during DA job) for a particular block
 pub async fn fetch_blob_data_for_block(block_number: u64, config: Arc<Config>) -> color_eyre::Result<Vec<Vec<u8>>> {
@@ -18,6 +21,29 @@ pub async fn fetch_blob_data_for_block(block_number: u64, config: Arc<Config>) -
     Ok(vec![blob_data.to_vec()])
 }
 
+/// Builds the onchain data hash and size sent to the core contract for a block whose state diff is published in
+/// calldata. The onchain data is the state diff of the block encoded in the onchain data format, and its hash is the
+/// keccak of its felts.
+pub async fn fetch_onchain_data_hash_and_size_for_block(
+    block_number: u64,
+    config: Arc<Config>,
+) -> color_eyre::Result<([u8; 32], [u8; 32])> {
+    let state_update = match config
+        .starknet_client()
+        .get_state_update(BlockId::Number(block_number))
+        .await
+        .wrap_err("Failed to get state update")?
+    {
+        MaybePendingStateUpdate::Update(state_update) => state_update,
+        MaybePendingStateUpdate::PendingUpdate(_) => return Err(eyre!("Block #{block_number} is still pending")),
+    };
+    let onchain_data = state_update_to_blob_data(block_number, state_update, config).await?;
+
+    let onchain_data_hash = keccak256(onchain_data.iter().flat_map(|felt| felt.to_bytes_be()).collect::<Vec<u8>>());
+    let onchain_data_size = U256::from(onchain_data.len()).to_be_bytes::<32>();
+    Ok((onchain_data_hash.0, onchain_data_size))
+}
+
 /// Fetching the blob data (stored in remote storage during DA job) for a particular block
 pub async fn fetch_program_data_for_block(block_number: u64, config: Arc<Config>) -> color_eyre::Result<Vec<[u8; 32]>> {
     let storage_client = config.storage();
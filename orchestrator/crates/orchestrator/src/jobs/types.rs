@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 // TODO: job types shouldn't depend on mongodb
 use chrono::{DateTime, Utc};
@@ -11,6 +12,8 @@ use orchestrator_settlement_client_interface::SettlementVerificationStatus;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::jobs::constants::{JOB_METADATA_ERROR, JOB_METADATA_FAILURE_REASON};
+
 /// An external id.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(untagged)]
@@ -97,6 +100,8 @@ pub enum JobStatus {
     PendingVerification,
     /// The job has been processed and verified. No other actions needs to be taken
     Completed,
+    /// The job was a dry run and has been simulated. Nothing was sent, so no other actions can be taken
+    Simulated,
     /// The job was processed but the was unable to be verified under the given time
     VerificationTimeout,
     /// The job failed processing
@@ -131,6 +136,70 @@ pub struct JobItem {
     pub updated_at: DateTime<Utc>,
 }
 
+/// A single status change of a job. The history of a job starts with its creation, and transitions
+/// are appended to it every time its status is updated and are never modified afterwards.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JobTransition {
+    /// timestamp of the transition
+    #[cfg_attr(feature = "with_mongodb", serde(with = "chrono_datetime_as_bson_datetime"))]
+    pub timestamp: DateTime<Utc>,
+    /// status of the job before the transition, `None` for the creation of the job
+    pub from: Option<JobStatus>,
+    /// status of the job after the transition
+    pub to: JobStatus,
+    /// the orchestrator instance which made the transition
+    pub worker_id: String,
+    /// the error which caused the transition, if any
+    pub error: Option<String>,
+}
+
+impl JobTransition {
+    /// Maximum length of the error summary stored in a transition.
+    const MAX_ERROR_LEN: usize = 512;
+
+    /// Returns the transition recording the creation of `job`.
+    pub fn created(job: &JobItem) -> Self {
+        Self {
+            timestamp: job.created_at,
+            from: None,
+            to: job.status.clone(),
+            worker_id: worker_id().to_string(),
+            error: None,
+        }
+    }
+
+    /// Returns the transition resulting from applying `updates` to `current_job`, if the status changes.
+    pub fn from_updates(current_job: &JobItem, updates: &JobItemUpdates, timestamp: DateTime<Utc>) -> Option<Self> {
+        let to = updates.status.clone().filter(|status| status != &current_job.status)?;
+
+        // Only report an error when it has been set by this update.
+        let error = updates.metadata.as_ref().and_then(|metadata| {
+            [JOB_METADATA_FAILURE_REASON, JOB_METADATA_ERROR]
+                .into_iter()
+                .find_map(|key| metadata.get(key).filter(|value| current_job.metadata.get(key) != Some(*value)))
+                .map(|error| Self::summarize(error))
+        });
+
+        Some(Self { timestamp, from: Some(current_job.status.clone()), to, worker_id: worker_id().to_string(), error })
+    }
+
+    fn summarize(error: &str) -> String {
+        match error.char_indices().nth(Self::MAX_ERROR_LEN) {
+            Some((idx, _)) => format!("{}...", &error[..idx]),
+            None => error.to_string(),
+        }
+    }
+}
+
+/// Identifies this orchestrator instance in the job history, as `<hostname>:<pid>`.
+pub fn worker_id() -> &'static str {
+    static WORKER_ID: OnceLock<String> = OnceLock::new();
+    WORKER_ID.get_or_init(|| {
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        format!("{}:{}", hostname, std::process::id())
+    })
+}
+
 /// Defining a structure that contains the changes to be made in the job object,
 /// id and created at are not allowed to be changed
 // version and updated_at will always be updated when this object updates the job
@@ -191,6 +260,8 @@ pub enum JobVerificationStatus {
     Verified,
     #[allow(dead_code)]
     Rejected(String),
+    /// The job was only simulated, there is nothing to verify
+    Simulated,
 }
 
 impl From<DaVerificationStatus> for JobVerificationStatus {
@@ -10,6 +10,7 @@ use uuid::Uuid;
 
 use super::ApiResponse;
 use crate::config::Config;
+use crate::jobs::types::JobTransition;
 use crate::jobs::{process_job, verify_job, JobError};
 use crate::metrics::ORCHESTRATOR_METRICS;
 
@@ -24,6 +25,12 @@ struct JobApiResponse {
     status: String,
 }
 
+#[derive(Serialize)]
+struct JobHistoryApiResponse {
+    job_id: String,
+    history: Vec<JobTransition>,
+}
+
 async fn handle_process_job_request(
     Path(JobId { id }): Path<JobId>,
     State(config): State<Arc<Config>>,
@@ -73,6 +80,30 @@ async fn handle_verify_job_request(
         }
     }
 }
+async fn handle_job_history_request(
+    Path(JobId { id }): Path<JobId>,
+    State(config): State<Arc<Config>>,
+) -> impl IntoResponse {
+    // Parse UUID
+    let job_id = match Uuid::parse_str(&id) {
+        Ok(id) => id,
+        Err(_) => {
+            return ApiResponse::<JobHistoryApiResponse>::error((JobError::InvalidId { id }).to_string())
+                .into_response();
+        }
+    };
+
+    match config.database().get_job_history(job_id).await {
+        Ok(Some(history)) => {
+            let response = JobHistoryApiResponse { job_id: job_id.to_string(), history };
+            ApiResponse::success(response).into_response()
+        }
+        Ok(None) => ApiResponse::<JobHistoryApiResponse>::error((JobError::JobNotFound { id: job_id }).to_string())
+            .into_response(),
+        Err(e) => ApiResponse::<JobHistoryApiResponse>::error(e.to_string()).into_response(),
+    }
+}
+
 pub fn job_router(config: Arc<Config>) -> Router {
     Router::new().nest("/jobs", trigger_router(config.clone()))
 }
@@ -81,5 +112,6 @@ fn trigger_router(config: Arc<Config>) -> Router {
     Router::new()
         .route("/:id/process", get(handle_process_job_request))
         .route("/:id/verify", get(handle_verify_job_request))
+        .route("/:id/history", get(handle_job_history_request))
         .with_state(config)
 }
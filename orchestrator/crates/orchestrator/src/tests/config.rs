@@ -117,6 +117,8 @@ pub struct TestConfigBuilder {
     storage_type: ConfigType,
     /// API Service
     api_server_type: ConfigType,
+    /// Simulate the state updates instead of sending them
+    settlement_dry_run: bool,
 }
 
 impl Default for TestConfigBuilder {
@@ -146,6 +148,7 @@ impl TestConfigBuilder {
             storage_type: ConfigType::default(),
             alerts_type: ConfigType::default(),
             api_server_type: ConfigType::default(),
+            settlement_dry_run: false,
         }
     }
 
@@ -198,10 +201,15 @@ impl TestConfigBuilder {
         self
     }
 
+    pub fn configure_settlement_dry_run(mut self, settlement_dry_run: bool) -> TestConfigBuilder {
+        self.settlement_dry_run = settlement_dry_run;
+        self
+    }
+
     pub async fn build(self) -> TestConfigBuilderReturns {
         dotenvy::from_filename("../.env.test").expect("Failed to load the .env.test file");
 
-        let params = get_env_params();
+        let mut params = get_env_params();
 
         let provider_config = Arc::new(ProviderConfig::AWS(Box::new(get_aws_config(&params.aws_params).await)));
 
@@ -216,8 +224,11 @@ impl TestConfigBuilder {
             queue_type,
             storage_type,
             api_server_type,
+            settlement_dry_run,
         } = self;
 
+        params.orchestrator_params.service_config.settlement_dry_run = settlement_dry_run;
+
         let (_starknet_rpc_url, starknet_client, starknet_server) =
             implement_client::init_starknet_client(starknet_rpc_url_type, starknet_client_type).await;
 
@@ -544,7 +555,14 @@ fn get_env_params() -> EnvParams {
     let env = get_env_var_optional("MADARA_ORCHESTRATOR_MIN_BLOCK_NO_TO_PROCESS").expect("Couldn't get min block");
     let min_block: Option<u64> = env.and_then(|s| if s.is_empty() { None } else { Some(s.parse::<u64>().unwrap()) });
 
-    let service_config = ServiceParams { max_block_to_process: max_block, min_block_to_process: min_block };
+    let service_config = ServiceParams {
+        max_block_to_process: max_block,
+        min_block_to_process: min_block,
+        max_concurrent_da_jobs: None,
+        max_concurrent_proving_jobs: None,
+        max_concurrent_settlement_jobs: None,
+        settlement_dry_run: false,
+    };
 
     let server_config = ServerParams {
         host: get_env_var_or_panic("MADARA_ORCHESTRATOR_HOST"),
@@ -4,7 +4,9 @@ use chrono::{SubsecRound, Utc};
 use rstest::*;
 use uuid::Uuid;
 
-use crate::jobs::types::{ExternalId, JobItem, JobItemUpdates, JobStatus, JobType};
+use crate::jobs::constants::JOB_METADATA_ERROR;
+use crate::jobs::internal_id::InternalId;
+use crate::jobs::types::{worker_id, ExternalId, JobItem, JobItemUpdates, JobStatus, JobType};
 use crate::jobs::{increment_key_in_metadata, JobError};
 use crate::tests::config::{ConfigType, TestConfigBuilder};
 
@@ -233,6 +235,99 @@ async fn database_test_update_job() {
     }
 }
 
+/// Tests that status changes are appended to the job history, with the error which caused them.
+#[rstest]
+#[tokio::test]
+async fn database_update_job_records_history() {
+    let services = TestConfigBuilder::new().configure_database(ConfigType::Actual).build().await;
+    let config = services.config;
+    let database_client = config.database();
+
+    let job = build_job_item(JobType::DataSubmission, JobStatus::Created, 789);
+    database_client.create_job(job.clone()).await.unwrap();
+    let history = database_client.get_job_history(job.id).await.unwrap().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!((&history[0].from, &history[0].to), (&None, &JobStatus::Created));
+
+    let job = database_client
+        .update_job(&job, JobItemUpdates::new().update_status(JobStatus::PendingVerification).build())
+        .await
+        .unwrap();
+    // Metadata only updates are not transitions.
+    let job = database_client
+        .update_job(
+            &job,
+            JobItemUpdates::new().update_metadata(HashMap::from([("key".into(), "value".into())])).build(),
+        )
+        .await
+        .unwrap();
+    let mut metadata = job.metadata.clone();
+    metadata.insert(JOB_METADATA_ERROR.to_string(), "Verification rejected".to_string());
+    database_client
+        .update_job(
+            &job,
+            JobItemUpdates::new().update_status(JobStatus::VerificationFailed).update_metadata(metadata).build(),
+        )
+        .await
+        .unwrap();
+
+    let history = database_client.get_job_history(job.id).await.unwrap().unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!((&history[0].from, &history[0].to), (&None, &JobStatus::Created));
+    assert_eq!((&history[1].from, &history[1].to), (&Some(JobStatus::Created), &JobStatus::PendingVerification));
+    assert_eq!(history[1].error, None);
+    assert_eq!(history[1].worker_id, worker_id());
+    assert_eq!(
+        (&history[2].from, &history[2].to),
+        (&Some(JobStatus::PendingVerification), &JobStatus::VerificationFailed)
+    );
+    assert_eq!(history[2].error.as_deref(), Some("Verification rejected"));
+    assert!(history[0].timestamp <= history[1].timestamp && history[1].timestamp <= history[2].timestamp);
+
+    assert_eq!(database_client.get_job_history(Uuid::new_v4()).await.unwrap(), None);
+}
+
+/// Tests for `get_jobs_overlapping_blocks` operation in database trait.
+/// Jobs of other types and jobs which are not about blocks are never considered overlapping.
+#[rstest]
+#[tokio::test]
+async fn database_get_jobs_overlapping_blocks_works() {
+    let services = TestConfigBuilder::new().configure_database(ConfigType::Actual).build().await;
+    let config = services.config;
+    let database_client = config.database();
+
+    let mut range_job = build_job_item(JobType::ProofCreation, JobStatus::Created, 0);
+    range_job.internal_id = InternalId::range(10, 20).unwrap().to_string();
+    let mut non_block_job = build_job_item(JobType::ProofCreation, JobStatus::Created, 0);
+    non_block_job.internal_id = "not-a-block".to_string();
+    let job_vec = [
+        build_job_item(JobType::ProofCreation, JobStatus::Created, 5),
+        range_job,
+        non_block_job,
+        build_job_item(JobType::DataSubmission, JobStatus::Created, 15),
+    ];
+    for job in &job_vec {
+        database_client.create_job(job.clone()).await.unwrap();
+    }
+
+    let overlapping = |internal_id: InternalId| async move {
+        database_client
+            .get_jobs_overlapping_blocks(&JobType::ProofCreation, &internal_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|job| job.internal_id)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(overlapping(InternalId::Block(15)).await, vec!["10-20".to_string()]);
+    assert_eq!(overlapping(InternalId::range(0, 10).unwrap()).await, vec!["5".to_string(), "10-20".to_string()]);
+    assert_eq!(overlapping(InternalId::range(21, 30).unwrap()).await, Vec::<String>::new());
+    // The job with the exact same internal id is not returned.
+    assert_eq!(overlapping(InternalId::Block(5)).await, Vec::<String>::new());
+    assert_eq!(overlapping(InternalId::range(10, 20).unwrap()).await, Vec::<String>::new());
+}
+
 // Test Util Functions
 // ==========================================
 
@@ -111,6 +111,38 @@ async fn create_job_job_exists_in_db_works() {
     assert_matches!(consumed_messages, QueueError::NoData);
 }
 
+/// Tests `create_job` function when a job of the same type already covers some of the blocks.
+#[rstest]
+#[tokio::test]
+async fn create_job_overlapping_job_exists_in_db_fails() {
+    let job_item = build_job_item_by_type_and_status(JobType::DataSubmission, JobStatus::Created, "10-20".to_string());
+
+    let services = TestConfigBuilder::new()
+        .configure_database(ConfigType::Actual)
+        .configure_queue_client(ConfigType::Actual)
+        .build()
+        .await;
+
+    let database_client = services.config.database();
+    database_client.create_job(job_item.clone()).await.unwrap();
+
+    assert_eq!(
+        create_job(JobType::DataSubmission, "15".to_string(), HashMap::new(), services.config.clone()).await,
+        Err(JobError::OverlappingJob {
+            internal_id: "15".to_string(),
+            existing_internal_id: "10-20".to_string(),
+            job_type: JobType::DataSubmission,
+        })
+    );
+    assert_matches!(
+        create_job(JobType::DataSubmission, "20-19".to_string(), HashMap::new(), services.config.clone()).await,
+        Err(JobError::InvalidInternalId(_))
+    );
+    // There should be only 1 job in the db
+    let jobs_in_db = database_client.get_jobs_by_statuses(vec![JobStatus::Created], None).await.unwrap();
+    assert_eq!(jobs_in_db.len(), 1);
+}
+
 /// Tests `create_job` function when job handler is not implemented in the `get_job_handler`
 /// This test should fail as job handler is not implemented in the `factory.rs`
 #[rstest]
@@ -435,6 +467,52 @@ async fn verify_job_with_verified_status_works() {
     assert_matches!(consumed_messages_processing_queue, QueueError::NoData);
 }
 
+/// Tests `verify_job` function when job is having expected status
+/// and returns a `Simulated` verification status.
+#[rstest]
+#[tokio::test]
+async fn verify_job_with_simulated_status_works() {
+    let job_item =
+        build_job_item_by_type_and_status(JobType::StateTransition, JobStatus::PendingVerification, "1".to_string());
+
+    // building config
+    let services = TestConfigBuilder::new()
+        .configure_database(ConfigType::Actual)
+        .configure_queue_client(ConfigType::Actual)
+        .build()
+        .await;
+
+    let database_client = services.config.database();
+    let mut job_handler = MockJob::new();
+
+    // creating job in database
+    database_client.create_job(job_item.clone()).await.unwrap();
+    job_handler.expect_verify_job().times(1).returning(move |_, _| Ok(JobVerificationStatus::Simulated));
+    job_handler.expect_max_process_attempts().returning(move || 2u64);
+
+    let job_handler: Arc<Box<dyn Job>> = Arc::new(Box::new(job_handler));
+    let ctx = mock_factory::get_job_handler_context();
+    // Mocking the `get_job_handler` call in create_job function.
+    ctx.expect().times(1).with(eq(JobType::StateTransition)).returning(move |_| Arc::clone(&job_handler));
+
+    assert!(verify_job(job_item.id, services.config.clone()).await.is_ok());
+
+    // DB checks.
+    let updated_job = database_client.get_job_by_id(job_item.id).await.unwrap().unwrap();
+    assert_eq!(updated_job.status, JobStatus::Simulated);
+
+    // Waiting for 5 secs for message to be passed into the queue
+    sleep(Duration::from_secs(5)).await;
+
+    // Queue checks.
+    let consumed_messages_verification_queue =
+        services.config.queue().consume_message_from_queue(QueueType::UpdateStateJobVerification).await.unwrap_err();
+    assert_matches!(consumed_messages_verification_queue, QueueError::NoData);
+    let consumed_messages_processing_queue =
+        services.config.queue().consume_message_from_queue(QueueType::UpdateStateJobProcessing).await.unwrap_err();
+    assert_matches!(consumed_messages_processing_queue, QueueError::NoData);
+}
+
 /// Tests `verify_job` function when job is having expected status
 /// and returns a `Rejected` verification status.
 #[rstest]
@@ -10,7 +10,7 @@ use httpmock::prelude::*;
 use lazy_static::lazy_static;
 use mockall::predicate::{always, eq};
 use num_bigint::BigUint;
-use orchestrator_settlement_client_interface::MockSettlementClient;
+use orchestrator_settlement_client_interface::{BlobStateUpdate, MockSettlementClient, UpdateStateSimulation};
 use rstest::*;
 use starknet::providers::jsonrpc::HttpTransport;
 use starknet::providers::JsonRpcClient;
@@ -19,12 +19,14 @@ use url::Url;
 use crate::constants::{BLOB_DATA_FILE_NAME, PROGRAM_OUTPUT_FILE_NAME, SNOS_OUTPUT_FILE_NAME};
 use crate::data_storage::MockDataStorage;
 use crate::jobs::constants::{
-    JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY, JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS,
+    JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY, JOB_METADATA_STATE_UPDATE_DRY_RUN,
+    JOB_METADATA_STATE_UPDATE_DRY_RUN_BLOCK_NUMBERS, JOB_METADATA_STATE_UPDATE_DRY_RUN_CALLDATA_SIZE,
+    JOB_METADATA_STATE_UPDATE_DRY_RUN_ESTIMATED_GAS, JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS,
     JOB_METADATA_STATE_UPDATE_LAST_FAILED_BLOCK_NO, JOB_PROCESS_ATTEMPT_METADATA_KEY,
 };
 use crate::jobs::state_update_job::utils::hex_string_to_u8_vec;
 use crate::jobs::state_update_job::{StateUpdateError, StateUpdateJob};
-use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::types::{JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::{Job, JobError};
 use crate::tests::common::default_job_item;
 use crate::tests::config::{ConfigType, TestConfigBuilder};
@@ -269,6 +271,104 @@ async fn process_job_works_unit_test() {
     assert_eq!(StateUpdateJob.process_job(services.config, &mut job).await.unwrap(), "651056".to_string())
 }
 
+/// Blocks simulated by a previous job of the dry run were not settled, and are simulated again first.
+#[rstest]
+#[case::first_job("651053,651054", "1234567,1234000", "420,416")]
+#[case::after_simulated_job("651054", "1234000", "416")]
+#[tokio::test]
+async fn process_job_dry_run_simulates_all_blocks(
+    #[case] blocks_to_settle: &str,
+    #[case] expected_estimated_gas: &str,
+    #[case] expected_calldata_size: &str,
+) {
+    let mut settlement_client = MockSettlementClient::new();
+    let mut storage_client = MockDataStorage::new();
+
+    settlement_client.expect_get_last_settled_block().returning(|| Ok(651052_u64));
+    // Nothing must be sent to the settlement layer.
+    settlement_client.expect_update_state_with_blobs().never();
+
+    let mut updates = vec![];
+    for block_no in ["651053", "651054"] {
+        let snos_output_data = fs::read_to_string(
+            CURRENT_PATH
+                .join(format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, SNOS_OUTPUT_FILE_NAME)),
+        )
+        .expect("Failed to read the snos output data json file");
+        storage_client
+            .expect_get_data()
+            .with(eq(block_no.to_owned() + "/" + SNOS_OUTPUT_FILE_NAME))
+            .returning(move |_| Ok(Bytes::from(snos_output_data.clone())));
+
+        let blob_data = fs::read_to_string(
+            CURRENT_PATH
+                .join(format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, BLOB_DATA_FILE_NAME)),
+        )
+        .expect("Failed to read the blob data txt file");
+        let blob_data_vec = hex_string_to_u8_vec(&blob_data).unwrap();
+        let blob_data_vec_clone = blob_data_vec.clone();
+        storage_client
+            .expect_get_data()
+            .with(eq(block_no.to_owned() + "/" + BLOB_DATA_FILE_NAME))
+            .returning(move |_| Ok(Bytes::from(blob_data_vec.clone())));
+
+        let program_output = read_file_to_vec_u8_32(
+            CURRENT_PATH
+                .join(format!("src/tests/jobs/state_update_job/test_data/{}/{}", block_no, PROGRAM_OUTPUT_FILE_NAME))
+                .to_str()
+                .unwrap(),
+        )
+        .unwrap();
+        let program_output_clone = program_output.clone();
+        storage_client
+            .expect_get_data()
+            .with(eq(block_no.to_owned() + "/" + PROGRAM_OUTPUT_FILE_NAME))
+            .returning(move |_| Ok(Bytes::from(bincode::serialize(&program_output).unwrap())));
+
+        updates.push(BlobStateUpdate { program_output: program_output_clone, state_diff: vec![blob_data_vec_clone] });
+    }
+
+    // Both blocks are simulated at once, the second one on top of the first one.
+    settlement_client.expect_simulate_update_state_with_blobs().with(eq(updates)).times(1).returning(|_| {
+        Ok(vec![
+            UpdateStateSimulation { estimated_gas: 1_234_567, calldata_size: 420 },
+            UpdateStateSimulation { estimated_gas: 1_234_000, calldata_size: 416 },
+        ])
+    });
+
+    let services = TestConfigBuilder::new()
+        .configure_settlement_client(settlement_client.into())
+        .configure_storage_client(storage_client.into())
+        .configure_settlement_dry_run(true)
+        .build()
+        .await;
+
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_FETCH_FROM_TESTS), String::from("TRUE"));
+    metadata.insert(String::from(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY), blocks_to_settle.to_string());
+    metadata.insert(String::from(JOB_PROCESS_ATTEMPT_METADATA_KEY), String::from("0"));
+
+    let mut job =
+        StateUpdateJob.create_job(services.config.clone(), String::from("internal_id"), metadata).await.unwrap();
+    assert_eq!(StateUpdateJob.process_job(services.config.clone(), &mut job).await.unwrap(), "651054".to_string());
+
+    assert_eq!(job.metadata.get(JOB_METADATA_STATE_UPDATE_DRY_RUN), Some(&"true".to_string()));
+    assert_eq!(job.metadata.get(JOB_METADATA_STATE_UPDATE_DRY_RUN_BLOCK_NUMBERS), Some(&blocks_to_settle.to_string()));
+    assert_eq!(
+        job.metadata.get(JOB_METADATA_STATE_UPDATE_DRY_RUN_ESTIMATED_GAS),
+        Some(&expected_estimated_gas.to_string())
+    );
+    assert_eq!(
+        job.metadata.get(JOB_METADATA_STATE_UPDATE_DRY_RUN_CALLDATA_SIZE),
+        Some(&expected_calldata_size.to_string())
+    );
+
+    // The global process job function increments the attempt number.
+    job.metadata.insert(String::from(JOB_PROCESS_ATTEMPT_METADATA_KEY), String::from("1"));
+    // Nothing was settled: the job is not verified, it ends as simulated.
+    assert_eq!(StateUpdateJob.verify_job(services.config, &mut job).await.unwrap(), JobVerificationStatus::Simulated);
+}
+
 #[rstest]
 #[case(String::from("651052, 651054, 651051, 651056"), "numbers aren't sorted in increasing order")]
 #[case(String::from("651052, 651052, 651052, 651052"), "Duplicated block numbers")]
@@ -14,7 +14,7 @@ use uuid::Uuid;
 
 use crate::config::Config;
 use crate::jobs::job_handler_factory::mock_factory;
-use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType, JobVerificationStatus};
+use crate::jobs::types::{ExternalId, JobItem, JobItemUpdates, JobStatus, JobType, JobVerificationStatus};
 use crate::jobs::{Job, MockJob};
 use crate::queue::init_consumers;
 use crate::tests::config::{ConfigType, TestConfigBuilder};
@@ -119,6 +119,53 @@ async fn test_trigger_verify_job(#[future] setup_trigger: (SocketAddr, Arc<Confi
     }
 }
 
+#[tokio::test]
+#[rstest]
+async fn test_job_history(#[future] setup_trigger: (SocketAddr, Arc<Config>)) {
+    let (addr, config) = setup_trigger.await;
+
+    let job_item = build_job_item(JobType::DataSubmission, JobStatus::Created, 1);
+    config.database().create_job(job_item.clone()).await.unwrap();
+    config
+        .database()
+        .update_job(&job_item, JobItemUpdates::new().update_status(JobStatus::LockedForProcessing).build())
+        .await
+        .unwrap();
+
+    let client = hyper::Client::new();
+    let response = client
+        .request(
+            Request::builder()
+                .uri(format!("http://{}/jobs/{}/history", addr, job_item.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let history = body["data"]["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["from"], serde_json::Value::Null);
+    assert_eq!(history[0]["to"], "Created");
+    assert_eq!(history[1]["from"], "Created");
+    assert_eq!(history[1]["to"], "LockedForProcessing");
+
+    // Unknown job
+    let response = client
+        .request(
+            Request::builder()
+                .uri(format!("http://{}/jobs/{}/history", addr, Uuid::new_v4()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 500);
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_init_consumer() {
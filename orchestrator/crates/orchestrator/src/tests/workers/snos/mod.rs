@@ -12,6 +12,7 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::database::MockDatabase;
+use crate::jobs::internal_id::InternalId;
 use crate::jobs::job_handler_factory::mock_factory;
 use crate::jobs::types::JobType;
 use crate::jobs::{Job, MockJob};
@@ -71,6 +72,9 @@ async fn test_snos_worker(#[case] db_val: bool) -> Result<(), Box<dyn Error>> {
         db.expect_get_job_by_internal_id_and_type()
             .with(eq(i.clone().to_string()), eq(JobType::SnosRun))
             .returning(|_, _| Ok(None));
+        db.expect_get_jobs_overlapping_blocks()
+            .with(eq(JobType::SnosRun), eq(InternalId::Block(i)))
+            .returning(|_, _| Ok(vec![]));
 
         let uuid = Uuid::new_v4();
 
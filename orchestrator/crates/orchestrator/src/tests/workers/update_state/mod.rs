@@ -128,6 +128,7 @@ async fn update_state_worker_selects_consective_blocks() {
     // update state worker should not create any job
     assert_eq!(latest_job.status, JobStatus::Created);
     assert_eq!(latest_job.job_type, JobType::StateTransition);
+    assert_eq!(latest_job.internal_id, "0-1");
     assert_eq!(latest_job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY).unwrap(), "0,1");
 }
 
@@ -147,7 +148,7 @@ async fn update_state_worker_continues_from_previous_state_update() {
     services.config.database().create_job(job_item).await.unwrap();
 
     // add state transition job for blocks 0-4
-    let mut job_item = get_job_item_mock_by_id("0".to_string(), Uuid::new_v4());
+    let mut job_item = get_job_item_mock_by_id("0-4".to_string(), Uuid::new_v4());
     job_item.status = JobStatus::Completed;
     job_item.job_type = JobType::StateTransition;
     let mut metadata = HashMap::new();
@@ -167,6 +168,47 @@ async fn update_state_worker_continues_from_previous_state_update() {
     // update state worker should not create any job
     assert_eq!(latest_job.status, JobStatus::Created);
     assert_eq!(latest_job.job_type, JobType::StateTransition);
+    assert_eq!(latest_job.internal_id, "5");
+    assert_eq!(latest_job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY).unwrap(), "5");
+}
+
+#[rstest]
+#[tokio::test]
+async fn update_state_worker_continues_after_dry_run() {
+    let services = TestConfigBuilder::new()
+        .configure_database(ConfigType::Actual)
+        .configure_queue_client(ConfigType::Actual)
+        .build()
+        .await;
+
+    // add DA completion job for block 5
+    let mut job_item = get_job_item_mock_by_id("5".to_string(), Uuid::new_v4());
+    job_item.status = JobStatus::Completed;
+    job_item.job_type = JobType::DataSubmission;
+    services.config.database().create_job(job_item).await.unwrap();
+
+    // add simulated state transition job for blocks 0-4
+    let unique_id = Uuid::new_v4();
+    let mut job_item = get_job_item_mock_by_id("0-4".to_string(), unique_id);
+    job_item.status = JobStatus::Simulated;
+    job_item.job_type = JobType::StateTransition;
+    let mut metadata = HashMap::new();
+    metadata.insert(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY.to_string(), "0,1,2,3,4".to_string());
+    job_item.metadata = metadata;
+    services.config.database().create_job(job_item).await.unwrap();
+
+    let ctx = mock_factory::get_job_handler_context();
+    ctx.expect().with(eq(JobType::StateTransition)).returning(move |_| Arc::new(Box::new(StateUpdateJob)));
+
+    let update_state_worker = UpdateStateWorker {};
+    assert!(update_state_worker.run_worker(services.config.clone()).await.is_ok());
+
+    // update state worker should create the job of the next block
+    let latest_job =
+        services.config.database().get_latest_job_by_type(JobType::StateTransition).await.unwrap().unwrap();
+    assert_ne!(latest_job.id, unique_id);
+    assert_eq!(latest_job.status, JobStatus::Created);
+    assert_eq!(latest_job.internal_id, "5");
     assert_eq!(latest_job.metadata.get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY).unwrap(), "5");
 }
 
@@ -187,7 +229,7 @@ async fn update_state_worker_next_block_missing() {
 
     // add state transition job for blocks 0-4
     let unique_id = Uuid::new_v4();
-    let mut job_item = get_job_item_mock_by_id("0".to_string(), unique_id);
+    let mut job_item = get_job_item_mock_by_id("0-4".to_string(), unique_id);
     job_item.status = JobStatus::Completed;
     job_item.job_type = JobType::StateTransition;
     let mut metadata = HashMap::new();
@@ -5,6 +5,7 @@ use mockall::predicate::eq;
 use uuid::Uuid;
 
 use crate::database::MockDatabase;
+use crate::jobs::internal_id::InternalId;
 use crate::jobs::types::{ExternalId, JobItem, JobStatus, JobType};
 use crate::jobs::MockJob;
 
@@ -79,6 +80,10 @@ pub fn db_checks_proving_worker(id: i32, db: &mut MockDatabase, mock_job: &mut M
         .times(1)
         .with(eq(id.clone().to_string()), eq(JobType::ProofCreation))
         .returning(|_, _| Ok(None));
+    db.expect_get_jobs_overlapping_blocks()
+        .times(1)
+        .with(eq(JobType::ProofCreation), eq(InternalId::Block(id as u64)))
+        .returning(|_, _| Ok(vec![]));
 
     let job_item = get_job_item_mock_by_id(id);
     let job_item_cloned = job_item.clone();
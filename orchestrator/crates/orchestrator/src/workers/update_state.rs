@@ -6,8 +6,10 @@ use opentelemetry::KeyValue;
 
 use crate::config::Config;
 use crate::jobs::constants::JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY;
-use crate::jobs::create_job;
+use crate::jobs::internal_id::InternalId;
+use crate::jobs::state_update_job::StateUpdateError;
 use crate::jobs::types::{JobStatus, JobType};
+use crate::jobs::{create_job, JobError};
 use crate::metrics::ORCHESTRATOR_METRICS;
 use crate::workers::Worker;
 
@@ -22,7 +24,8 @@ impl Worker for UpdateStateWorker {
 
         let (completed_da_jobs, last_block_processed_in_last_job) = match latest_job {
             Some(job) => {
-                if job.status != JobStatus::Completed {
+                // The jobs of a settlement dry run end as simulated, the next blocks are simulated after them.
+                if !matches!(job.status, JobStatus::Completed | JobStatus::Simulated) {
                     log::warn!(
                         "There's already a pending update state job. Parallel jobs can cause nonce issues or can \
                          completely fail as the update logic needs to be strictly ordered. Returning safely..."
@@ -30,19 +33,18 @@ impl Worker for UpdateStateWorker {
                     return Ok(());
                 }
 
-                let mut blocks_processed_in_last_job: Vec<u64> = job
+                let blocks_processed_in_last_job = job
                     .metadata
                     .get(JOB_METADATA_STATE_UPDATE_BLOCKS_TO_SETTLE_KEY)
-                    .unwrap()
-                    .split(',')
-                    .filter_map(|s| s.parse().ok())
-                    .collect();
-
-                // ideally it's already sorted, but just to be safe
-                blocks_processed_in_last_job.sort();
+                    .ok_or_else(|| StateUpdateError::UnspecifiedBlockNumber { internal_id: job.internal_id.clone() })
+                    .map_err(JobError::from)?;
 
-                let last_block_processed_in_last_job =
-                    blocks_processed_in_last_job[blocks_processed_in_last_job.len() - 1];
+                let last_block_processed_in_last_job = blocks_processed_in_last_job
+                    .split(',')
+                    .filter_map(|s| s.parse::<u64>().ok())
+                    .max()
+                    .ok_or(StateUpdateError::BlockNumberNotFound)
+                    .map_err(JobError::from)?;
 
                 (
                     config
@@ -73,8 +75,10 @@ impl Worker for UpdateStateWorker {
             }
         };
 
-        let mut blocks_to_process: Vec<u64> =
-            completed_da_jobs.iter().map(|j| j.internal_id.parse::<u64>().unwrap()).collect();
+        let mut blocks_to_process = completed_da_jobs
+            .iter()
+            .map(|j| j.internal_id.parse::<u64>().map_err(|_| JobError::InvalidId { id: j.internal_id.clone() }))
+            .collect::<Result<Vec<u64>, _>>()?;
         blocks_to_process.sort();
 
         // no DA jobs completed after the last settled block
@@ -115,8 +119,9 @@ impl Worker for UpdateStateWorker {
             blocks_to_process.iter().map(|ele| ele.to_string()).collect::<Vec<String>>().join(","),
         );
 
-        // Creating a single job for all the pending blocks.
-        let new_job_id = blocks_to_process[0].to_string();
+        // Creating a single job for all the pending blocks, identified by the range of blocks it settles.
+        let new_job_id =
+            InternalId::range(blocks_to_process[0], blocks_to_process[blocks_to_process.len() - 1])?.to_string();
         match create_job(JobType::StateTransition, new_job_id.clone(), metadata, config.clone()).await {
             Ok(_) => tracing::info!(block_id = %new_job_id, "Successfully created new state transition job"),
             Err(e) => {
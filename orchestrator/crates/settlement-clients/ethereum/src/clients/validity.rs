@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use alloy::network::Ethereum;
-use alloy::primitives::Address;
+use alloy::primitives::{Address, U256};
+use alloy::rpc::types::TransactionRequest;
 use alloy::transports::http::Http;
 
 use crate::clients::interfaces::validity_interface::StarknetValidityContract;
@@ -26,6 +27,16 @@ impl StarknetValidityContractClient {
     pub fn contract_address(&self) -> Address {
         *self.core_contract.address()
     }
+
+    /// The request of the `updateState` transaction sent when the state diff is published in calldata.
+    pub fn update_state_request(
+        &self,
+        program_output: Vec<U256>,
+        onchain_data_hash: U256,
+        onchain_data_size: U256,
+    ) -> TransactionRequest {
+        self.core_contract.updateState(program_output, onchain_data_hash, onchain_data_size).into_transaction_request()
+    }
 }
 
 impl
@@ -10,11 +10,13 @@ use alloy::consensus::{
 use alloy::eips::eip2718::Encodable2718;
 use alloy::eips::eip2930::AccessList;
 use alloy::eips::eip4844::BYTES_PER_BLOB;
+use alloy::eips::BlockNumberOrTag;
 use alloy::hex;
 use alloy::network::EthereumWallet;
-use alloy::primitives::{Address, B256, U256};
+use alloy::primitives::{keccak256, Address, B256, U256, U64};
 use alloy::providers::{Provider, ProviderBuilder};
-use alloy::rpc::types::TransactionReceipt;
+use alloy::rpc::types::state::{AccountOverride, StateOverride};
+use alloy::rpc::types::{TransactionReceipt, TransactionRequest};
 use alloy::signers::local::PrivateKeySigner;
 use alloy_primitives::Bytes;
 use async_trait::async_trait;
@@ -22,7 +24,9 @@ use c_kzg::{Blob, Bytes32, KzgCommitment, KzgProof, KzgSettings};
 use color_eyre::eyre::{bail, Ok};
 use color_eyre::Result;
 use conversion::{get_input_data_for_eip_4844, prepare_sidecar};
-use orchestrator_settlement_client_interface::{SettlementClient, SettlementVerificationStatus};
+use orchestrator_settlement_client_interface::{
+    BlobStateUpdate, CalldataStateUpdate, SettlementClient, SettlementVerificationStatus, UpdateStateSimulation,
+};
 #[cfg(feature = "testing")]
 use orchestrator_utils::env_utils::get_env_var_or_panic;
 use url::Url;
@@ -48,6 +52,14 @@ pub const ENV_PRIVATE_KEY: &str = "MADARA_ORCHESTRATOR_ETHEREUM_PRIVATE_KEY";
 const X_0_POINT_OFFSET: usize = 10;
 const Y_LOW_POINT_OFFSET: usize = 14;
 const Y_HIGH_POINT_OFFSET: usize = Y_LOW_POINT_OFFSET + 1;
+// Offsets of the state left by a block in the program output
+const NEW_STATE_ROOT_OFFSET: usize = 1;
+const NEW_BLOCK_NUMBER_OFFSET: usize = 3;
+const NEW_BLOCK_HASH_OFFSET: usize = 5;
+
+/// Tag of the storage slot of the state (global root, block number and block hash) in the core contract, see
+/// `StarknetState.sol`.
+const STARKNET_STATE_STRUCT_TAG: &str = "STARKNET_1.0_INIT_STARKNET_STATE_STRUCT";
 
 // Ethereum Transaction Finality
 const MAX_TX_FINALISATION_ATTEMPTS: usize = 30;
@@ -170,59 +182,14 @@ impl EthereumSettlementClient {
         }
         Ok(kzg_proof)
     }
-}
-
-#[automock]
-#[async_trait]
-impl SettlementClient for EthereumSettlementClient {
-    /// Should register the proof on the base layer and return an external id
-    /// which can be used to track the status.
-    #[allow(unused)]
-    async fn register_proof(&self, proof: [u8; 32]) -> Result<String> {
-        todo!("register_proof is not implemented yet")
-    }
 
-    /// Should be used to update state on core contract when DA is done in calldata
-    async fn update_state_calldata(
-        &self,
-        program_output: Vec<[u8; 32]>,
-        onchain_data_hash: [u8; 32],
-        onchain_data_size: [u8; 32],
-    ) -> Result<String> {
-        tracing::info!(
-            log_type = "starting",
-            category = "update_state",
-            function_type = "calldata",
-            "Updating state with calldata."
-        );
-        let program_output: Vec<U256> = vec_u8_32_to_vec_u256(program_output.as_slice())?;
-        let onchain_data_hash: U256 = slice_u8_to_u256(&onchain_data_hash)?;
-        let onchain_data_size = U256::from_be_bytes(onchain_data_size);
-        let tx_receipt =
-            self.core_contract_client.update_state(program_output, onchain_data_hash, onchain_data_size).await?;
-        tracing::info!(
-            log_type = "completed",
-            category = "update_state",
-            function_type = "calldata",
-            tx_hash = %tx_receipt.transaction_hash,
-            "State updated with calldata."
-        );
-        Ok(format!("0x{:x}", tx_receipt.transaction_hash))
-    }
-
-    /// Should be used to update state on core contract when DA is in blobs/alt DA
-    async fn update_state_with_blobs(
+    /// Builds the EIP-4844 transaction updating the state on the core contract and publishing the state diff in
+    /// blobs.
+    async fn build_update_state_with_blobs_tx(
         &self,
         program_output: Vec<[u8; 32]>,
         state_diff: Vec<Vec<u8>>,
-        _nonce: u64,
-    ) -> Result<String> {
-        tracing::info!(
-            log_type = "starting",
-            category = "update_state",
-            function_type = "blobs",
-            "Updating state with blobs."
-        );
+    ) -> Result<(TxEip4844, BlobTransactionSidecar)> {
         let (sidecar_blobs, sidecar_commitments, sidecar_proofs) = prepare_sidecar(&state_diff, &KZG_SETTINGS).await?;
         let sidecar = BlobTransactionSidecar::new(sidecar_blobs, sidecar_commitments, sidecar_proofs);
 
@@ -273,7 +240,111 @@ impl SettlementClient for EthereumSettlementClient {
             input: Bytes::from(hex::decode(input_bytes)?),
         };
 
-        let tx_sidecar = TxEip4844WithSidecar { tx: tx.clone(), sidecar: sidecar.clone() };
+        Ok((tx, sidecar))
+    }
+
+    /// Overrides the state stored in the core contract with the state left by settling the block of
+    /// `program_output`, to simulate the settlement of the next block before this one is settled.
+    fn core_contract_state_override(&self, program_output: &[[u8; 32]]) -> Result<StateOverride> {
+        let state = [NEW_STATE_ROOT_OFFSET, NEW_BLOCK_NUMBER_OFFSET, NEW_BLOCK_HASH_OFFSET]
+            .into_iter()
+            .map(|offset| program_output.get(offset).copied())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| color_eyre::eyre::eyre!("Program output is too short to read the new state"))?;
+        let slot = U256::from_be_bytes(keccak256(STARKNET_STATE_STRUCT_TAG).0);
+        let state_diff = state
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (B256::from((slot + U256::from(index)).to_be_bytes::<32>()), B256::from(value)))
+            .collect();
+        let account_override = AccountOverride { state_diff: Some(state_diff), ..Default::default() };
+        Ok(StateOverride::from_iter([(self.core_contract_client.contract_address(), account_override)]))
+    }
+
+    /// Estimates the gas of an update state transaction. When the block settled before this one is not settled yet,
+    /// the transaction is simulated on top of the state it leaves, read from its `previous_program_output`.
+    async fn estimate_update_state_gas(
+        &self,
+        mut txn_request: TransactionRequest,
+        previous_program_output: Option<&[[u8; 32]]>,
+    ) -> Result<u64> {
+        txn_request.from = Some(self.impersonate_account.unwrap_or(self.wallet_address));
+        // let the node estimate the gas instead of using our hardcoded limit
+        txn_request.gas = None;
+        match previous_program_output {
+            None => Ok(self
+                .provider
+                .estimate_gas(&txn_request)
+                .await
+                .wrap_err("State update simulation failed")?
+                .try_into()?),
+            Some(previous_program_output) => {
+                let state_override = self.core_contract_state_override(previous_program_output)?;
+                let estimated_gas: U64 = self
+                    .provider
+                    .raw_request("eth_estimateGas".into(), (&txn_request, BlockNumberOrTag::Latest, state_override))
+                    .await
+                    .wrap_err("State update simulation failed")?;
+                Ok(estimated_gas.to::<u64>())
+            }
+        }
+    }
+}
+
+#[automock]
+#[async_trait]
+impl SettlementClient for EthereumSettlementClient {
+    /// Should register the proof on the base layer and return an external id
+    /// which can be used to track the status.
+    #[allow(unused)]
+    async fn register_proof(&self, proof: [u8; 32]) -> Result<String> {
+        todo!("register_proof is not implemented yet")
+    }
+
+    /// Should be used to update state on core contract when DA is done in calldata
+    async fn update_state_calldata(
+        &self,
+        program_output: Vec<[u8; 32]>,
+        onchain_data_hash: [u8; 32],
+        onchain_data_size: [u8; 32],
+    ) -> Result<String> {
+        tracing::info!(
+            log_type = "starting",
+            category = "update_state",
+            function_type = "calldata",
+            "Updating state with calldata."
+        );
+        let program_output: Vec<U256> = vec_u8_32_to_vec_u256(program_output.as_slice())?;
+        let onchain_data_hash: U256 = slice_u8_to_u256(&onchain_data_hash)?;
+        let onchain_data_size = U256::from_be_bytes(onchain_data_size);
+        let tx_receipt =
+            self.core_contract_client.update_state(program_output, onchain_data_hash, onchain_data_size).await?;
+        tracing::info!(
+            log_type = "completed",
+            category = "update_state",
+            function_type = "calldata",
+            tx_hash = %tx_receipt.transaction_hash,
+            "State updated with calldata."
+        );
+        Ok(format!("0x{:x}", tx_receipt.transaction_hash))
+    }
+
+    /// Should be used to update state on core contract when DA is in blobs/alt DA
+    async fn update_state_with_blobs(
+        &self,
+        program_output: Vec<[u8; 32]>,
+        state_diff: Vec<Vec<u8>>,
+        _nonce: u64,
+    ) -> Result<String> {
+        tracing::info!(
+            log_type = "starting",
+            category = "update_state",
+            function_type = "blobs",
+            "Updating state with blobs."
+        );
+        let (tx, sidecar) = self.build_update_state_with_blobs_tx(program_output, state_diff).await?;
+
+        let tx_sidecar = TxEip4844WithSidecar { tx, sidecar };
 
         let mut variant = TxEip4844Variant::from(tx_sidecar);
         let signature = self.wallet.default_signer().sign_transaction(&mut variant).await?;
@@ -316,6 +387,80 @@ impl SettlementClient for EthereumSettlementClient {
         Ok(pending_transaction.tx_hash().to_string())
     }
 
+    /// Simulates the update state transactions with `eth_estimateGas`, which fails if a transaction would revert. The
+    /// blocks after the first one are simulated with the state of the core contract overridden with the state left by
+    /// the previous block.
+    async fn simulate_update_state_with_blobs(
+        &self,
+        updates: Vec<BlobStateUpdate>,
+    ) -> Result<Vec<UpdateStateSimulation>> {
+        tracing::info!(
+            log_type = "starting",
+            category = "update_state",
+            function_type = "simulate_blobs",
+            num_blocks = %updates.len(),
+            "Simulating state updates with blobs."
+        );
+        let mut simulations = Vec::with_capacity(updates.len());
+        let mut previous_program_output: Option<Vec<[u8; 32]>> = None;
+        for BlobStateUpdate { program_output, state_diff } in updates {
+            let (tx, sidecar) = self.build_update_state_with_blobs_tx(program_output.clone(), state_diff).await?;
+            let calldata_size = tx.input.len();
+
+            let txn_request: TransactionRequest = TxEip4844WithSidecar { tx, sidecar }.into();
+            let estimated_gas = self.estimate_update_state_gas(txn_request, previous_program_output.as_deref()).await?;
+            simulations.push(UpdateStateSimulation { estimated_gas, calldata_size });
+            previous_program_output = Some(program_output);
+        }
+
+        tracing::info!(
+            log_type = "completed",
+            category = "update_state",
+            function_type = "simulate_blobs",
+            estimated_gas = ?simulations.iter().map(|simulation| simulation.estimated_gas).collect::<Vec<_>>(),
+            "State updates with blobs simulated."
+        );
+        Ok(simulations)
+    }
+
+    /// Same as `simulate_update_state_with_blobs`, with the `updateState` transactions sent when the state diff is
+    /// published in calldata.
+    async fn simulate_update_state_calldata(
+        &self,
+        updates: Vec<CalldataStateUpdate>,
+    ) -> Result<Vec<UpdateStateSimulation>> {
+        tracing::info!(
+            log_type = "starting",
+            category = "update_state",
+            function_type = "simulate_calldata",
+            num_blocks = %updates.len(),
+            "Simulating state updates with calldata."
+        );
+        let mut simulations = Vec::with_capacity(updates.len());
+        let mut previous_program_output: Option<Vec<[u8; 32]>> = None;
+        for CalldataStateUpdate { program_output, onchain_data_hash, onchain_data_size } in updates {
+            let txn_request = self.core_contract_client.update_state_request(
+                vec_u8_32_to_vec_u256(program_output.as_slice())?,
+                slice_u8_to_u256(&onchain_data_hash)?,
+                U256::from_be_bytes(onchain_data_size),
+            );
+            let calldata_size = txn_request.input.input().map_or(0, |input| input.len());
+
+            let estimated_gas = self.estimate_update_state_gas(txn_request, previous_program_output.as_deref()).await?;
+            simulations.push(UpdateStateSimulation { estimated_gas, calldata_size });
+            previous_program_output = Some(program_output);
+        }
+
+        tracing::info!(
+            log_type = "completed",
+            category = "update_state",
+            function_type = "simulate_calldata",
+            estimated_gas = ?simulations.iter().map(|simulation| simulation.estimated_gas).collect::<Vec<_>>(),
+            "State updates with calldata simulated."
+        );
+        Ok(simulations)
+    }
+
     /// Should verify the inclusion of a tx in the settlement layer
     async fn verify_tx_inclusion(&self, tx_hash: &str) -> Result<SettlementVerificationStatus> {
         tracing::info!(
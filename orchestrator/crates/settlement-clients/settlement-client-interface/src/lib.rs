@@ -12,6 +12,30 @@ pub enum SettlementVerificationStatus {
     Rejected(String),
 }
 
+/// Outcome of the simulation of an update state transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateStateSimulation {
+    /// Gas the transaction is estimated to use
+    pub estimated_gas: u64,
+    /// Size of the transaction calldata, in bytes
+    pub calldata_size: usize,
+}
+
+/// Data sent to the settlement layer by `update_state_with_blobs` to settle a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobStateUpdate {
+    pub program_output: Vec<[u8; 32]>,
+    pub state_diff: Vec<Vec<u8>>,
+}
+
+/// Data sent to the settlement layer by `update_state_calldata` to settle a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalldataStateUpdate {
+    pub program_output: Vec<[u8; 32]>,
+    pub onchain_data_hash: [u8; 32],
+    pub onchain_data_size: [u8; 32],
+}
+
 /// Trait for every new Settlement Layer to implement
 #[automock]
 #[async_trait]
@@ -36,6 +60,20 @@ pub trait SettlementClient: Send + Sync {
         nonce: u64,
     ) -> Result<String>;
 
+    /// Should build the same transactions as `update_state_with_blobs` for consecutive blocks and
+    /// simulate them in order against the latest state of the settlement layer, each one on top of
+    /// the previous ones, without broadcasting them. Returns the simulation of each block.
+    async fn simulate_update_state_with_blobs(
+        &self,
+        updates: Vec<BlobStateUpdate>,
+    ) -> Result<Vec<UpdateStateSimulation>>;
+
+    /// Same as `simulate_update_state_with_blobs`, for the transactions built by `update_state_calldata`.
+    async fn simulate_update_state_calldata(
+        &self,
+        updates: Vec<CalldataStateUpdate>,
+    ) -> Result<Vec<UpdateStateSimulation>>;
+
     /// Should verify the inclusion of a tx in the settlement layer
     async fn verify_tx_inclusion(&self, tx_hash: &str) -> Result<SettlementVerificationStatus>;
 
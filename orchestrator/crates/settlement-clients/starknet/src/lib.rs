@@ -14,9 +14,14 @@ use crypto_bigint::Encoding;
 use lazy_static::lazy_static;
 use mockall::automock;
 use mockall::predicate::*;
-use orchestrator_settlement_client_interface::{SettlementClient, SettlementVerificationStatus};
-use starknet::accounts::{ConnectedAccount, ExecutionEncoding, SingleOwnerAccount};
-use starknet::core::types::{BlockId, BlockTag, Felt, FunctionCall, TransactionExecutionStatus};
+use orchestrator_settlement_client_interface::{
+    BlobStateUpdate, CalldataStateUpdate, SettlementClient, SettlementVerificationStatus, UpdateStateSimulation,
+};
+use starknet::accounts::{Account, ConnectedAccount, ExecutionEncoding, SingleOwnerAccount};
+use starknet::core::types::{
+    BlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedTransaction, Call, ExecuteInvocation, Felt,
+    FunctionCall, SimulationFlag, TransactionExecutionStatus, TransactionTrace,
+};
 use starknet::core::utils::get_selector_from_name;
 use starknet::providers::jsonrpc::HttpTransport;
 use starknet::providers::{JsonRpcClient, Provider};
@@ -208,6 +213,88 @@ impl SettlementClient for StarknetSettlementClient {
         !unimplemented!("not implemented yet.")
     }
 
+    /// There are no blobs on Starknet, see `update_state_with_blobs`.
+    async fn simulate_update_state_with_blobs(
+        &self,
+        _updates: Vec<BlobStateUpdate>,
+    ) -> Result<Vec<UpdateStateSimulation>> {
+        Err(eyre!("State updates with blobs are not supported when settling on Starknet"))
+    }
+
+    /// Simulates the `update_state` invocations of the core contract in a single
+    /// `starknet_simulateTransactions` call, so that each block is settled on top of the previous
+    /// ones.
+    async fn simulate_update_state_calldata(
+        &self,
+        updates: Vec<CalldataStateUpdate>,
+    ) -> Result<Vec<UpdateStateSimulation>> {
+        tracing::info!(
+            log_type = "starting",
+            category = "update_state",
+            function_type = "simulate_calldata",
+            num_blocks = %updates.len(),
+            "Simulating state updates with calldata."
+        );
+        let mut nonce = self.account.get_nonce().await?;
+        let mut transactions = Vec::with_capacity(updates.len());
+        let mut calldata_sizes = Vec::with_capacity(updates.len());
+        for CalldataStateUpdate { program_output, onchain_data_hash, onchain_data_size } in updates {
+            let program_output = slice_slice_u8_to_vec_field(program_output.as_slice());
+            // update_state(program_output: Span<felt252>, onchain_data_hash: felt252, onchain_data_size: u256)
+            let mut calldata = Vec::with_capacity(program_output.len() + 4);
+            calldata.push(Felt::from(program_output.len()));
+            calldata.extend(program_output);
+            calldata.push(slice_u8_to_field(&onchain_data_hash));
+            // u256 is serialized as (low, high)
+            calldata.push(Felt::from_bytes_be_slice(&onchain_data_size[16..]));
+            calldata.push(Felt::from_bytes_be_slice(&onchain_data_size[..16]));
+            calldata_sizes.push(calldata.len() * 32);
+
+            let call =
+                Call { to: self.core_contract_address, selector: *CONTRACT_WRITE_UPDATE_STATE_SELECTOR, calldata };
+            // The fee of the transactions after the first one cannot be estimated before the previous ones are
+            // executed: the simulation does not charge it.
+            let invoke = self
+                .account
+                .execute_v1(vec![call])
+                .nonce(nonce)
+                .max_fee(Felt::ZERO)
+                .prepared()?
+                .get_invoke_request(true, false)
+                .await?;
+            transactions.push(BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(invoke)));
+            nonce += Felt::ONE;
+        }
+
+        let simulated = self
+            .account
+            .provider()
+            .simulate_transactions(self.account.block_id(), transactions, [SimulationFlag::SkipFeeCharge])
+            .await
+            .wrap_err("State update simulation failed")?;
+
+        let mut simulations = Vec::with_capacity(simulated.len());
+        for (simulated, calldata_size) in simulated.into_iter().zip(calldata_sizes) {
+            if let TransactionTrace::Invoke(trace) = &simulated.transaction_trace {
+                if let ExecuteInvocation::Reverted(reverted) = &trace.execute_invocation {
+                    return Err(eyre!("State update simulation reverted: {}", reverted.revert_reason));
+                }
+            }
+            let estimated_gas = u64_from_felt(simulated.fee_estimation.gas_consumed)
+                .wrap_err("Failed to convert the estimated gas from Felt to u64")?;
+            simulations.push(UpdateStateSimulation { estimated_gas, calldata_size });
+        }
+
+        tracing::info!(
+            log_type = "completed",
+            category = "update_state",
+            function_type = "simulate_calldata",
+            estimated_gas = ?simulations.iter().map(|simulation| simulation.estimated_gas).collect::<Vec<_>>(),
+            "State updates with calldata simulated."
+        );
+        Ok(simulations)
+    }
+
     /// Wait for a pending tx to achieve finality
     async fn wait_for_tx_finality(&self, tx_hash: &str) -> Result<Option<u64>> {
         let mut retries = 0;
@@ -0,0 +1,48 @@
+// State transition jobs used to be identified by the first block they settle. They are now identified by the range
+// of blocks they settle, `<first block>-<last block>`, so that the overlap check of new jobs sees all their blocks.
+const BLOCKS_TO_SETTLE_KEY = "blocks_number_to_settle";
+
+module.exports = {
+  async up(db) {
+    const jobs = db.collection("jobs");
+    const cursor = jobs.find({
+      job_type: "StateTransition",
+      internal_id: { $not: /-/ },
+    });
+
+    for await (const job of cursor) {
+      const blocks = (job.metadata?.[BLOCKS_TO_SETTLE_KEY] ?? "")
+        .split(",")
+        .filter((block) => /^\d+$/.test(block))
+        .map(BigInt);
+      if (blocks.length === 0) {
+        continue;
+      }
+      const first = blocks.reduce((a, b) => (b < a ? b : a));
+      const last = blocks.reduce((a, b) => (b > a ? b : a));
+      // A job settling a single block keeps its block number as internal id.
+      if (first === last) {
+        continue;
+      }
+      await jobs.updateOne(
+        { _id: job._id },
+        { $set: { internal_id: `${first}-${last}` } },
+      );
+    }
+  },
+
+  async down(db) {
+    const jobs = db.collection("jobs");
+    const cursor = jobs.find({
+      job_type: "StateTransition",
+      internal_id: /-/,
+    });
+
+    for await (const job of cursor) {
+      await jobs.updateOne(
+        { _id: job._id },
+        { $set: { internal_id: job.internal_id.split("-")[0] } },
+      );
+    }
+  },
+};